@@ -0,0 +1,255 @@
+// src/adapter/ack.rs
+//! Explicit acknowledgement for critical channels. Broadcasts to a listed
+//! channel carry a `sequence`, counted per channel on this node, and each
+//! recipient has to acknowledge them with a `sockudo:ack` message naming the
+//! channel and the highest sequence it has processed. Messages still
+//! unacknowledged after `redelivery_ms` are sent again with the same
+//! sequence, so clients can drop duplicates, until `ack_timeout_ms` has
+//! passed and they are counted as expired.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use serde::Serialize;
+use tokio::sync::{Mutex, RwLock};
+use tracing::debug;
+
+use crate::error::{Error, Result};
+use crate::metrics::MetricsInterface;
+use crate::namespace::Connections;
+use crate::options::MessageAcksConfig;
+use crate::protocol::encoding::{encode_json, text_frame};
+use crate::protocol::messages::PusherMessage;
+use crate::websocket::{MessageSender, SocketId};
+
+/// Event clients acknowledge messages with
+pub const ACK_EVENT: &str = "sockudo:ack";
+
+/// A broadcast as sent on an acknowledged channel
+#[derive(Serialize)]
+struct Sequenced<'a> {
+    #[serde(flatten)]
+    message: &'a PusherMessage,
+    sequence: u64,
+}
+
+struct Unacked {
+    sequence: u64,
+    payload: Vec<u8>,
+    first_sent: Instant,
+    last_sent: Instant,
+    redeliveries: u32,
+}
+
+/// Messages one socket has yet to acknowledge, in sequence order per channel
+struct SocketUnacked {
+    app_id: String,
+    sender: MessageSender,
+    channels: HashMap<String, VecDeque<Unacked>>,
+}
+
+pub struct MessageAcks {
+    config: MessageAcksConfig,
+    /// Last sequence sent per app and channel
+    sequences: DashMap<(String, String), u64>,
+    unacked: DashMap<SocketId, SocketUnacked>,
+    metrics: RwLock<Option<Arc<Mutex<dyn MetricsInterface + Send + Sync>>>>,
+}
+
+impl MessageAcks {
+    pub fn new(config: MessageAcksConfig) -> Self {
+        Self {
+            config,
+            sequences: DashMap::new(),
+            unacked: DashMap::new(),
+            metrics: RwLock::new(None),
+        }
+    }
+
+    pub async fn set_metrics(&self, metrics: Arc<Mutex<dyn MetricsInterface + Send + Sync>>) {
+        *self.metrics.write().await = Some(metrics);
+    }
+
+    /// Whether broadcasts to `channel` must be acknowledged
+    pub fn tracks(&self, channel: &str) -> bool {
+        self.config
+            .channels
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => channel.starts_with(prefix),
+                None => channel == pattern,
+            })
+    }
+
+    /// Send a broadcast to the channel's local sockets under the channel's
+    /// next sequence, and hold it until each of them acknowledges it
+    pub async fn deliver(
+        &self,
+        app_id: &str,
+        channel: &str,
+        message: &PusherMessage,
+        connections: Connections,
+        except: Option<&SocketId>,
+    ) -> Result<()> {
+        let sequence = {
+            let mut last = self
+                .sequences
+                .entry((app_id.to_string(), channel.to_string()))
+                .or_insert(0);
+            *last += 1;
+            *last
+        };
+        let payload = encode_json(&Sequenced { message, sequence })
+            .map_err(|e| Error::ConnectionError(format!("Failed to serialize message: {}", e)))?;
+
+        for connection in connections {
+            let ws = connection.lock().await;
+            if except == Some(&ws.state.socket_id) {
+                continue;
+            }
+            if ws.message_sender.send(text_frame(&payload)).is_err() {
+                continue;
+            }
+            let now = Instant::now();
+            let overflowed = {
+                let mut socket = self
+                    .unacked
+                    .entry(ws.state.socket_id.clone())
+                    .or_insert_with(|| SocketUnacked {
+                        app_id: app_id.to_string(),
+                        sender: ws.message_sender.clone(),
+                        channels: HashMap::new(),
+                    });
+                let pending = socket.channels.entry(channel.to_string()).or_default();
+                pending.push_back(Unacked {
+                    sequence,
+                    payload: payload.clone(),
+                    first_sent: now,
+                    last_sent: now,
+                    redeliveries: 0,
+                });
+                // The oldest message gives way once the socket falls too far behind
+                pending.len() > self.config.max_unacked.max(1) && pending.pop_front().is_some()
+            };
+            if overflowed {
+                if let Some(metrics) = self.metrics.read().await.as_ref() {
+                    metrics.lock().await.mark_ack_expired(app_id);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Acknowledge every message on `channel` up to and including `sequence`
+    pub fn ack(&self, socket_id: &SocketId, channel: &str, sequence: u64) {
+        let Some(mut socket) = self.unacked.get_mut(socket_id) else {
+            return;
+        };
+        if let Some(pending) = socket.channels.get_mut(channel) {
+            while pending
+                .front()
+                .is_some_and(|unacked| unacked.sequence <= sequence)
+            {
+                pending.pop_front();
+            }
+            if pending.is_empty() {
+                socket.channels.remove(channel);
+            }
+        }
+    }
+
+    /// Stop tracking a channel the socket unsubscribed from
+    pub fn forget_channel(&self, socket_id: &SocketId, channel: &str) {
+        if let Some(mut socket) = self.unacked.get_mut(socket_id) {
+            socket.channels.remove(channel);
+        }
+    }
+
+    /// Stop tracking a disconnected socket
+    pub fn forget_socket(&self, socket_id: &SocketId) {
+        self.unacked.remove(socket_id);
+    }
+
+    /// Resend and expire unacknowledged messages, and report how many are
+    /// outstanding per app
+    pub fn start(self: Arc<Self>) {
+        let redelivery = Duration::from_millis(self.config.redelivery_ms);
+        let timeout = Duration::from_millis(self.config.ack_timeout_ms.max(1));
+        let sweep_interval = if redelivery.is_zero() {
+            timeout / 2
+        } else {
+            redelivery.min(timeout) / 2
+        }
+        .max(Duration::from_millis(100));
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(sweep_interval);
+            let mut reported_apps: HashSet<String> = HashSet::new();
+            loop {
+                interval.tick().await;
+                let mut unacked_per_app: HashMap<String, usize> = HashMap::new();
+                let mut redelivered: Vec<String> = Vec::new();
+                let mut expired: Vec<String> = Vec::new();
+
+                for mut socket in self.unacked.iter_mut() {
+                    let socket = socket.value_mut();
+                    for pending in socket.channels.values_mut() {
+                        pending.retain_mut(|unacked| {
+                            if unacked.first_sent.elapsed() >= timeout {
+                                expired.push(socket.app_id.clone());
+                                return false;
+                            }
+                            if !redelivery.is_zero()
+                                && unacked.last_sent.elapsed() >= redelivery
+                                && unacked.redeliveries < self.config.max_redeliveries
+                                && socket.sender.send(text_frame(&unacked.payload)).is_ok()
+                            {
+                                unacked.last_sent = Instant::now();
+                                unacked.redeliveries += 1;
+                                redelivered.push(socket.app_id.clone());
+                            }
+                            true
+                        });
+                    }
+                    socket.channels.retain(|_, pending| !pending.is_empty());
+                    let count: usize = socket.channels.values().map(VecDeque::len).sum();
+                    *unacked_per_app.entry(socket.app_id.clone()).or_default() += count;
+                }
+                self.unacked.retain(|_, socket| !socket.channels.is_empty());
+
+                if !expired.is_empty() || !redelivered.is_empty() {
+                    debug!(
+                        "{}",
+                        format!(
+                            "Redelivered {} and expired {} unacknowledged messages",
+                            redelivered.len(),
+                            expired.len()
+                        )
+                    );
+                }
+                let Some(metrics) = self.metrics.read().await.clone() else {
+                    continue;
+                };
+                let metrics = metrics.lock().await;
+                for app_id in &redelivered {
+                    metrics.mark_ack_redelivery(app_id);
+                }
+                for app_id in &expired {
+                    metrics.mark_ack_expired(app_id);
+                }
+                // Apps that no longer have anything outstanding go back to 0
+                for app_id in reported_apps.drain() {
+                    unacked_per_app.entry(app_id).or_default();
+                }
+                for (app_id, unacked) in unacked_per_app {
+                    metrics.mark_unacked_messages(&app_id, unacked);
+                    if unacked > 0 {
+                        reported_apps.insert(app_id);
+                    }
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,97 @@
+// src/adapter/activity.rs
+//! Idle detection for a WebSocket connection.
+//!
+//! Clients such as pusher-js restart their activity timer on every message
+//! they receive, so a subscriber that only receives broadcasts never pings.
+//! When a connection has been silent for its activity timeout, the server
+//! pings it instead, and only closes it if nothing arrives within the pong
+//! timeout.
+
+use std::time::Duration;
+
+/// What to do when a wait for the next frame times out
+#[derive(Debug, PartialEq, Eq)]
+pub enum IdleAction {
+    /// Send a `pusher:ping` and wait for the pong timeout
+    Ping,
+    /// Close the connection with the inactivity error (4202)
+    Close,
+}
+
+pub struct ActivityWatch {
+    activity_timeout: Duration,
+    pong_timeout: Duration,
+    /// A server ping went out and nothing arrived since
+    awaiting_pong: bool,
+}
+
+impl ActivityWatch {
+    pub fn new(activity_timeout: Duration, pong_timeout: Duration) -> Self {
+        Self {
+            activity_timeout,
+            pong_timeout,
+            awaiting_pong: false,
+        }
+    }
+
+    /// How long to wait for the next frame
+    pub fn next_wait(&self) -> Duration {
+        if self.awaiting_pong {
+            self.pong_timeout
+        } else {
+            self.activity_timeout
+        }
+    }
+
+    /// Any frame from the client, a pong included, shows it is alive
+    pub fn frame_received(&mut self) {
+        self.awaiting_pong = false;
+    }
+
+    pub fn timed_out(&mut self) -> IdleAction {
+        if self.awaiting_pong {
+            IdleAction::Close
+        } else {
+            self.awaiting_pong = true;
+            IdleAction::Ping
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watch() -> ActivityWatch {
+        ActivityWatch::new(Duration::from_secs(120), Duration::from_secs(30))
+    }
+
+    #[test]
+    fn test_receive_only_client_answering_pings_stays_open() {
+        let mut watch = watch();
+        for _ in 0..5 {
+            // Silent for the whole activity timeout: the server pings
+            assert_eq!(watch.next_wait(), Duration::from_secs(120));
+            assert_eq!(watch.timed_out(), IdleAction::Ping);
+            assert_eq!(watch.next_wait(), Duration::from_secs(30));
+            // The client answers with pusher:pong
+            watch.frame_received();
+        }
+        assert_eq!(watch.next_wait(), Duration::from_secs(120));
+    }
+
+    #[test]
+    fn test_unanswered_ping_closes() {
+        let mut watch = watch();
+        assert_eq!(watch.timed_out(), IdleAction::Ping);
+        assert_eq!(watch.timed_out(), IdleAction::Close);
+    }
+
+    #[test]
+    fn test_client_traffic_restarts_activity_timeout() {
+        let mut watch = watch();
+        watch.frame_received();
+        assert_eq!(watch.next_wait(), Duration::from_secs(120));
+        assert_eq!(watch.timed_out(), IdleAction::Ping);
+    }
+}
@@ -1,9 +1,12 @@
+use crate::adapter::registry::NodeInfo;
 use crate::app::manager::AppManager;
 use crate::channel::PresenceMemberInfo;
-use crate::error::Result;
+use crate::error::{Error, Result};
+use crate::metrics::MetricsInterface;
 use crate::namespace::Namespace;
+use crate::protocol::constants::APP_DISABLED_CHANNEL;
 use crate::protocol::messages::PusherMessage;
-use crate::websocket::{SocketId, WebSocket, WebSocketRef};
+use crate::websocket::{SocketDetails, SocketId, WebSocket, WebSocketRef};
 use async_trait::async_trait;
 use dashmap::{DashMap, DashSet};
 use fastwebsockets::WebSocketWrite;
@@ -15,15 +18,22 @@ use std::sync::Arc;
 use tokio::io::WriteHalf;
 use tokio::sync::Mutex;
 
+/// Connection registry shared by the whole server as `Arc<dyn Adapter>`.
+/// Every method takes `&self`: implementations keep their state in sharded
+/// maps or behind their own fine-grained locks, so concurrent operations on
+/// different apps and channels do not serialize behind a global mutex.
+/// Only single operations are atomic: a join or leave together with its
+/// presence bookkeeping is ordered per channel by
+/// `ChannelManager::lock_channel`, on each node.
 #[async_trait]
 pub trait Adapter: Send + Sync {
-    async fn init(&mut self);
+    async fn init(&self);
     // Namespace management
-    async fn get_namespace(&mut self, app_id: &str) -> Option<Arc<Namespace>>;
+    async fn get_namespace(&self, app_id: &str) -> Option<Arc<Namespace>>;
 
     // WebSocket management
     async fn add_socket(
-        &mut self,
+        &self,
         socket_id: SocketId,
         socket: WebSocketWrite<WriteHalf<TokioIo<Upgraded>>>,
         app_id: &str,
@@ -31,82 +41,132 @@ pub trait Adapter: Send + Sync {
     ) -> Result<()>;
 
     async fn get_connection(
-        &mut self,
+        &self,
         socket_id: &SocketId,
         app_id: &str,
     ) -> Option<Arc<Mutex<WebSocket>>>;
 
-    async fn remove_connection(&mut self, socket_id: &SocketId, app_id: &str) -> Result<()>;
+    async fn remove_connection(&self, socket_id: &SocketId, app_id: &str) -> Result<()>;
 
     // Message handling
     async fn send_message(
-        &mut self,
+        &self,
         app_id: &str,
         socket_id: &SocketId,
         message: PusherMessage,
     ) -> Result<()>;
 
     async fn send(
-        &mut self,
+        &self,
         channel: &str,
         message: PusherMessage,
         except: Option<&SocketId>,
         app_id: &str,
     ) -> Result<()>;
     async fn get_channel_members(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
     ) -> Result<HashMap<String, PresenceMemberInfo>>;
     async fn get_channel_sockets(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
     ) -> Result<DashMap<SocketId, Arc<Mutex<WebSocket>>>>;
-    async fn get_channel(&mut self, app_id: &str, channel: &str) -> Result<DashSet<SocketId>>;
-    async fn remove_channel(&mut self, app_id: &str, channel: &str);
+    async fn get_channel(&self, app_id: &str, channel: &str) -> Result<DashSet<SocketId>>;
+    async fn remove_channel(&self, app_id: &str, channel: &str);
     async fn is_in_channel(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
         socket_id: &SocketId,
     ) -> Result<bool>;
-    async fn get_user_sockets(
-        &mut self,
-        user_id: &str,
-        app_id: &str,
-    ) -> Result<DashSet<WebSocketRef>>;
-    async fn cleanup_connection(&mut self, app_id: &str, ws: WebSocketRef);
-    async fn terminate_connection(&mut self, app_id: &str, user_id: &str) -> Result<()>;
-    async fn add_channel_to_sockets(&mut self, app_id: &str, channel: &str, socket_id: &SocketId);
-    async fn get_channel_socket_count(&mut self, app_id: &str, channel: &str) -> usize;
+    async fn get_user_sockets(&self, user_id: &str, app_id: &str) -> Result<DashSet<WebSocketRef>>;
+    async fn cleanup_connection(&self, app_id: &str, ws: WebSocketRef);
+    async fn terminate_connection(&self, app_id: &str, user_id: &str) -> Result<()>;
+    async fn add_channel_to_sockets(&self, app_id: &str, channel: &str, socket_id: &SocketId);
+    async fn get_channel_socket_count(&self, app_id: &str, channel: &str) -> usize;
+    /// Whether the subscription change this node just made to `channel` took
+    /// it from no subscribers anywhere to some (`occupied`) or back to none,
+    /// so `channel_occupied` and `channel_vacated` go out once per change.
+    /// The default compares the subscriber count, which is exact on a single
+    /// node; adapters with a shared store settle races between nodes there.
+    async fn claim_channel_transition(&self, app_id: &str, channel: &str, occupied: bool) -> bool {
+        let count = self.get_channel_socket_count(app_id, channel).await;
+        if occupied { count == 1 } else { count == 0 }
+    }
     async fn add_to_channel(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
         socket_id: &SocketId,
     ) -> Result<bool>;
     async fn remove_from_channel(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
         socket_id: &SocketId,
     ) -> Result<bool>;
     async fn get_presence_member(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
         socket_id: &SocketId,
     ) -> Option<PresenceMemberInfo>;
-    async fn terminate_user_connections(&mut self, app_id: &str, user_id: &str) -> Result<()>;
-    async fn add_user(&mut self, ws: Arc<Mutex<WebSocket>>) -> Result<()>;
-    async fn remove_user(&mut self, ws: Arc<Mutex<WebSocket>>) -> Result<()>;
-    async fn get_channels_with_socket_count(
-        &mut self,
+    async fn terminate_user_connections(&self, app_id: &str, user_id: &str) -> Result<()>;
+    async fn add_user(&self, ws: Arc<Mutex<WebSocket>>) -> Result<()>;
+    async fn remove_user(&self, ws: Arc<Mutex<WebSocket>>) -> Result<()>;
+    async fn get_channels_with_socket_count(&self, app_id: &str) -> Result<DashMap<String, usize>>;
+
+    async fn get_sockets_count(&self, app_id: &str) -> Result<usize>;
+    /// Random sample of up to `sample` sockets subscribed to a channel, across all nodes
+    async fn get_channel_socket_details(
+        &self,
         app_id: &str,
-    ) -> Result<DashMap<String, usize>>;
+        channel: &str,
+        sample: usize,
+    ) -> Result<Vec<SocketDetails>>;
+    async fn get_namespaces(&self) -> Result<DashMap<String, Arc<Namespace>>>;
+    /// Send every connection of a disabled app a `pusher:error` and close
+    /// it, on every node of the cluster
+    async fn disconnect_app(&self, app_id: &str) -> Result<()> {
+        let error = Error::ApplicationDisabled;
+        let message = PusherMessage::error(error.close_code(), error.to_string(), None);
+        self.send(APP_DISABLED_CHANNEL, message, None, app_id).await
+    }
 
-    async fn get_sockets_count(&mut self, app_id: &str) -> Result<usize>;
-    async fn get_namespaces(&mut self) -> Result<DashMap<String, Arc<Namespace>>>;
-    fn as_any_mut(&mut self) -> &mut dyn Any;
+    // Health management
+    /// Check that the connections backing this adapter (pub/sub listeners, broker
+    /// clients) are usable. Adapters without external dependencies are always healthy.
+    async fn health(&self) -> Result<()> {
+        Ok(())
+    }
+    /// Re-establish the backing connections after a failed health check.
+    async fn reconnect(&self) -> Result<()> {
+        Ok(())
+    }
+    // Cluster membership
+    /// Publish this node's registry entry to the other nodes. Adapters without
+    /// a shared backend keep the default no-op.
+    async fn register_node(&self, _node: &NodeInfo) -> Result<()> {
+        Ok(())
+    }
+    /// Registry entries published by the nodes of the cluster. Entries may be
+    /// expired; callers filter them.
+    async fn registered_nodes(&self) -> Result<Vec<NodeInfo>> {
+        Ok(Vec::new())
+    }
+    /// Hand the metrics sink to the adapter. Adapters that do not report
+    /// metrics of their own keep the default no-op.
+    async fn set_metrics(
+        &self,
+        _metrics: Arc<Mutex<dyn MetricsInterface + Send + Sync>>,
+    ) -> Result<()> {
+        Ok(())
+    }
+    /// Hand the app manager to the adapter, so an app disabled on any node
+    /// is dropped from every node's app cache. Adapters keep it on their
+    /// local registry, which handles the disable broadcast.
+    fn set_app_manager(&self, _app_manager: Arc<dyn AppManager + Send + Sync>) {}
+    fn as_any(&self) -> &dyn Any;
 }
@@ -0,0 +1,87 @@
+// src/adapter/connection_quota.rs
+//! Cluster-wide enforcement of an app's `max_connections`. Asking every node
+//! for its count on each new connection would put a cluster round trip on
+//! every handshake, so the count of the other nodes is cached for
+//! `cache_ttl_ms` and added to the exact local count. The quota is therefore
+//! approximate: nodes admitting connections at the same moment may overshoot
+//! it by what they admit within one cache period.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use tracing::debug;
+
+use crate::adapter::Adapter;
+use crate::error::Result;
+use crate::options::ConnectionQuotaConfig;
+
+struct RemoteCount {
+    sockets: usize,
+    fetched_at: Instant,
+}
+
+pub struct ConnectionQuota {
+    adapter: Arc<dyn Adapter + Send + Sync>,
+    ttl: Duration,
+    /// Sockets of each app on the other nodes, as of the last cluster count
+    remote_counts: DashMap<String, RemoteCount>,
+}
+
+impl ConnectionQuota {
+    pub fn new(adapter: Arc<dyn Adapter + Send + Sync>, config: &ConnectionQuotaConfig) -> Self {
+        Self {
+            adapter,
+            ttl: Duration::from_millis(config.cache_ttl_ms),
+            remote_counts: DashMap::new(),
+        }
+    }
+
+    async fn local_count(&self, app_id: &str) -> usize {
+        self.adapter
+            .get_namespace(app_id)
+            .await
+            .map_or(0, |namespace| namespace.sockets.len())
+    }
+
+    /// Connections of the app across the cluster, with the other nodes'
+    /// share at most `cache_ttl_ms` old
+    pub async fn connection_count(&self, app_id: &str) -> Result<usize> {
+        let cached = self
+            .remote_counts
+            .get(app_id)
+            .filter(|count| count.fetched_at.elapsed() < self.ttl)
+            .map(|count| count.sockets);
+        if let Some(remote) = cached {
+            return Ok(self.local_count(app_id).await + remote);
+        }
+
+        let total = self.adapter.get_sockets_count(app_id).await?;
+        let local = self.local_count(app_id).await;
+        let remote = total.saturating_sub(local);
+        debug!(
+            "{}",
+            format!(
+                "App {} has {} connections here and {} on other nodes",
+                app_id, local, remote
+            )
+        );
+        self.remote_counts.insert(
+            app_id.to_string(),
+            RemoteCount {
+                sockets: remote,
+                fetched_at: Instant::now(),
+            },
+        );
+        Ok(local + remote)
+    }
+
+    /// Whether the app may take another connection under `max_connections`;
+    /// 0 means unlimited
+    pub async fn admits(&self, app_id: &str, max_connections: u32) -> Result<bool> {
+        if max_connections == 0 {
+            return Ok(true);
+        }
+        Ok(self.connection_count(app_id).await? < max_connections as usize)
+    }
+}
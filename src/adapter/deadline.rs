@@ -0,0 +1,89 @@
+// src/adapter/deadline.rs
+//! Delivery deadline SLO. Every broadcast a node delivers to its local
+//! sockets is checked against `deadline_ms`, measured from when the message
+//! was published (on whichever node that was) to when the last local socket
+//! had it queued. Deliveries are counted per app and channel class, and so
+//! are the ones that missed the deadline. Their ratio is the SLO signal.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{Mutex, RwLock};
+use tracing::debug;
+
+use crate::channel::ChannelType;
+use crate::metrics::MetricsInterface;
+use crate::options::DeliveryDeadlineConfig;
+use crate::protocol::constants::{APP_DISABLED_CHANNEL, SERVER_NOTICE_CHANNEL};
+use crate::utils::is_cache_channel;
+
+pub struct DeliveryDeadline {
+    deadline: Duration,
+    metrics: RwLock<Option<Arc<Mutex<dyn MetricsInterface + Send + Sync>>>>,
+}
+
+impl DeliveryDeadline {
+    pub fn new(config: &DeliveryDeadlineConfig) -> Self {
+        Self {
+            deadline: Duration::from_millis(config.deadline_ms),
+            metrics: RwLock::new(None),
+        }
+    }
+
+    pub async fn set_metrics(&self, metrics: Arc<Mutex<dyn MetricsInterface + Send + Sync>>) {
+        *self.metrics.write().await = Some(metrics);
+    }
+
+    /// Time since a message published at `published_at_ms` (Unix milliseconds,
+    /// possibly by another node, so subject to clock skew between nodes)
+    pub fn since(published_at_ms: i64) -> Duration {
+        let elapsed = chrono::Utc::now().timestamp_millis() - published_at_ms;
+        Duration::from_millis(elapsed.max(0) as u64)
+    }
+
+    /// Count a delivery to `channel` that took `elapsed`
+    pub async fn record(&self, app_id: &str, channel: &str, elapsed: Duration) {
+        let Some(metrics) = self.metrics.read().await.clone() else {
+            return;
+        };
+        let class = channel_class(channel);
+        let met = elapsed <= self.deadline;
+        if !met {
+            debug!(
+                "{}",
+                format!(
+                    "Delivery to {} ({}) missed its deadline: {}ms",
+                    channel,
+                    class,
+                    elapsed.as_millis()
+                )
+            );
+        }
+        metrics
+            .lock()
+            .await
+            .mark_delivery_deadline(app_id, class, met);
+    }
+}
+
+/// Class a channel is reported under, keeping the label set small
+fn channel_class(channel: &str) -> &'static str {
+    if channel == SERVER_NOTICE_CHANNEL {
+        return "server_notice";
+    }
+    if channel == APP_DISABLED_CHANNEL {
+        return "app_disabled";
+    }
+    if channel.starts_with("#server-to-user-") {
+        return "user";
+    }
+    if is_cache_channel(channel) {
+        return "cache";
+    }
+    match ChannelType::from_name(channel) {
+        ChannelType::Public => "public",
+        ChannelType::Private => "private",
+        ChannelType::Presence => "presence",
+        ChannelType::PrivateEncrypted => "private_encrypted",
+    }
+}
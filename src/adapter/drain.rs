@@ -0,0 +1,123 @@
+// src/adapter/drain.rs
+//! Graceful node drain for rolling deploys. Instead of dropping every socket
+//! at once on shutdown, the node stops accepting connections and asks its
+//! clients to reconnect a batch at a time, so they land on other nodes
+//! spread over the drain period.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use tokio::sync::{Mutex, Notify};
+use tracing::{info, warn};
+
+use crate::adapter::Adapter;
+use crate::backoff::BackoffAdvisor;
+use crate::options::DrainConfig;
+use crate::protocol::messages::PusherMessage;
+use crate::websocket::WebSocket;
+
+/// Pusher error code 4200: generic "reconnect immediately"
+const RECONNECT_CODE: u16 = 4200;
+const RECONNECT_MESSAGE: &str = "Server is restarting, please reconnect";
+
+pub struct NodeDrain {
+    connection_manager: Arc<dyn Adapter + Send + Sync>,
+    config: DrainConfig,
+    backoff: Arc<BackoffAdvisor>,
+    draining: AtomicBool,
+    finished: Notify,
+}
+
+impl NodeDrain {
+    pub fn new(
+        connection_manager: Arc<dyn Adapter + Send + Sync>,
+        config: DrainConfig,
+        backoff: Arc<BackoffAdvisor>,
+    ) -> Self {
+        Self {
+            connection_manager,
+            config,
+            backoff,
+            draining: AtomicBool::new(false),
+            finished: Notify::new(),
+        }
+    }
+
+    /// Whether new connections should be refused
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    /// Begin draining in the background. Returns false if a drain was
+    /// already in progress.
+    pub fn start(self: &Arc<Self>) -> bool {
+        if self.draining.swap(true, Ordering::SeqCst) {
+            return false;
+        }
+        let drain = self.clone();
+        tokio::spawn(async move {
+            drain.run().await;
+            drain.finished.notify_one();
+        });
+        true
+    }
+
+    /// Resolves once a started drain has disconnected every socket
+    pub async fn finished(&self) {
+        self.finished.notified().await;
+    }
+
+    async fn run(&self) {
+        let mut sockets: Vec<Arc<Mutex<WebSocket>>> = Vec::new();
+        match self.connection_manager.get_namespaces().await {
+            Ok(namespaces) => {
+                for namespace in namespaces.iter() {
+                    sockets.extend(namespace.sockets.iter().map(|entry| entry.value().clone()));
+                }
+            }
+            Err(e) => warn!("{}", format!("Failed to list sockets for drain: {}", e)),
+        }
+
+        let batch_size = self.config.batch_size.max(1);
+        let batches = sockets.len().div_ceil(batch_size).max(1);
+        let pause = Duration::from_millis(self.config.duration_ms) / batches as u32;
+        info!(
+            "{}",
+            format!(
+                "Draining {} connections in {} batches, {}ms apart",
+                sockets.len(),
+                batches,
+                pause.as_millis()
+            )
+        );
+
+        for (index, batch) in sockets.chunks(batch_size).enumerate() {
+            if index > 0 {
+                tokio::time::sleep(pause).await;
+            }
+            for socket in batch {
+                // The connection's read loop does the usual disconnect cleanup
+                // (presence, webhooks) once the client answers the close frame
+                let mut ws = socket.lock().await;
+                let retry_in_ms = self.backoff.advise(&ws.get_socket_id().0);
+                let notice = serde_json::to_value(PusherMessage::error_with_backoff(
+                    RECONNECT_CODE,
+                    RECONNECT_MESSAGE.to_string(),
+                    None,
+                    retry_in_ms,
+                ))
+                .unwrap_or_default();
+                let _ = ws.send_json(notice).await;
+                let _ = ws
+                    .close(
+                        RECONNECT_CODE,
+                        format!("{} (retry in {}ms)", RECONNECT_MESSAGE, retry_in_ms),
+                    )
+                    .await;
+            }
+        }
+
+        info!("{}", "Drain completed");
+    }
+}
@@ -1,12 +1,23 @@
 // src/adapter/factory.rs
 use crate::adapter::Adapter;
+use crate::adapter::ack::MessageAcks;
+use crate::adapter::deadline::DeliveryDeadline;
+use crate::adapter::failover::FailoverAdapter;
+use crate::adapter::gossip_adapter::GossipAdapter;
+use crate::adapter::horizontal_adapter::BroadcastCodec;
+use crate::adapter::hot_channel::HotChannels;
+use crate::adapter::interest::ChannelInterest;
 use crate::adapter::local_adapter::LocalAdapter;
-use crate::adapter::nats_adapter::{NatsAdapter, NatsAdapterConfig};
+use crate::adapter::mqtt_adapter::MqttAdapter;
+use crate::adapter::nats_adapter::NatsAdapter;
+use crate::adapter::qos::DispatchQos;
+use crate::adapter::rabbitmq_adapter::RabbitMqAdapter;
 use crate::adapter::redis_adapter::{RedisAdapter, RedisAdapterConfig as RedisAdapterOptions};
 use crate::adapter::redis_cluster_adapter::{RedisClusterAdapter, RedisClusterAdapterConfig};
 use crate::error::Result;
 
 use crate::options::{AdapterConfig, AdapterDriver, DatabaseConfig}; // Import AdapterDriver, RedisConnection
+use std::sync::Arc;
 use tracing::{info, warn};
 
 pub struct AdapterFactory;
@@ -16,11 +27,37 @@ impl AdapterFactory {
         config: &AdapterConfig,
         db_config: &DatabaseConfig,
         debug_enabled: bool,
-    ) -> Result<Box<dyn Adapter + Send + Sync>> {
+        dispatch_qos: Option<Arc<DispatchQos>>,
+        delivery_deadline: Option<Arc<DeliveryDeadline>>,
+        message_acks: Option<Arc<MessageAcks>>,
+    ) -> Result<Arc<dyn Adapter + Send + Sync>> {
         info!(
             "{}",
             format!("Initializing Adapter with driver: {:?}", config.driver)
         );
+        if config.channel_interest.enabled
+            && !matches!(
+                config.driver,
+                AdapterDriver::Redis | AdapterDriver::Nats | AdapterDriver::Rabbitmq
+            )
+        {
+            warn!(
+                "{}",
+                format!(
+                    "Channel interest tracking is not supported by the {:?} adapter, ignoring",
+                    config.driver
+                )
+            );
+        }
+        if config.hot_channels.enabled && !matches!(config.driver, AdapterDriver::Redis) {
+            warn!(
+                "{}",
+                format!(
+                    "Hot channel ownership is not supported by the {:?} adapter, ignoring",
+                    config.driver
+                )
+            );
+        }
         match config.driver {
             // Match on the enum
             AdapterDriver::Redis => {
@@ -40,9 +77,54 @@ impl AdapterFactory {
                     request_timeout_ms: config.redis.requests_timeout,
                     use_connection_manager: true,
                     cluster_mode: config.redis.cluster_mode,
+                    publish_batching: config.redis.publish_batching.clone(),
+                    broadcast_shards: config.redis.broadcast_shards,
+                    username: config
+                        .redis
+                        .username
+                        .clone()
+                        .or_else(|| db_config.redis.username.clone()),
+                    password: config
+                        .redis
+                        .password
+                        .clone()
+                        .or_else(|| db_config.redis.password.clone()),
+                    tls: if config.redis.tls.enabled {
+                        config.redis.tls.clone()
+                    } else {
+                        db_config.redis.tls.clone()
+                    },
                 };
                 match RedisAdapter::new(adapter_options).await {
-                    Ok(adapter) => Ok(Box::new(adapter)),
+                    Ok(mut adapter) => {
+                        adapter.codec = BroadcastCodec::new(&config.compression);
+                        if config.channel_interest.enabled {
+                            adapter.interest = Some(Arc::new(ChannelInterest::new(
+                                adapter.node_id.clone(),
+                                &config.channel_interest,
+                            )));
+                        }
+                        if config.hot_channels.enabled && !config.hot_channels.channels.is_empty() {
+                            adapter.hot_channels = Some(Arc::new(HotChannels::new(
+                                adapter.node_id.clone(),
+                                adapter.prefix.clone(),
+                                &config.hot_channels,
+                            )));
+                        }
+                        adapter
+                            .horizontal
+                            .lock()
+                            .await
+                            .configure_requests(&config.horizontal);
+                        Ok(Self::wrap_horizontal(
+                            config,
+                            &dispatch_qos,
+                            &delivery_deadline,
+                            &message_acks,
+                            adapter.local_adapter.clone(),
+                            Arc::new(adapter),
+                        ))
+                    }
                     Err(e) => {
                         warn!(
                             "{}",
@@ -51,7 +133,11 @@ impl AdapterFactory {
                                 e
                             )
                         );
-                        Ok(Box::new(LocalAdapter::new()))
+                        Ok(Self::local(
+                            &dispatch_qos,
+                            &delivery_deadline,
+                            &message_acks,
+                        ))
                     }
                 }
             }
@@ -70,7 +156,11 @@ impl AdapterFactory {
 
                 if nodes.is_empty() {
                     warn!("{}", "Redis Cluster Adapter selected, but no nodes configured. Falling back to local adapter.".to_string());
-                    return Ok(Box::new(LocalAdapter::new()));
+                    return Ok(Self::local(
+                        &dispatch_qos,
+                        &delivery_deadline,
+                        &message_acks,
+                    ));
                 }
 
                 let cluster_adapter_config = RedisClusterAdapterConfig {
@@ -79,9 +169,41 @@ impl AdapterFactory {
                     prefix: config.cluster.prefix.clone(),
                     request_timeout_ms: config.cluster.request_timeout_ms,
                     use_connection_manager: config.cluster.use_connection_manager,
+                    broadcast_shards: config.cluster.broadcast_shards,
+                    sharded_pubsub: config.cluster.sharded_pubsub,
+                    username: config
+                        .cluster
+                        .username
+                        .clone()
+                        .or_else(|| db_config.redis.username.clone()),
+                    password: config
+                        .cluster
+                        .password
+                        .clone()
+                        .or_else(|| db_config.redis.password.clone()),
+                    tls: if config.cluster.tls.enabled {
+                        config.cluster.tls.clone()
+                    } else {
+                        db_config.redis.tls.clone()
+                    },
                 };
                 match RedisClusterAdapter::new(cluster_adapter_config).await {
-                    Ok(adapter) => Ok(Box::new(adapter)),
+                    Ok(mut adapter) => {
+                        adapter.codec = BroadcastCodec::new(&config.compression);
+                        adapter
+                            .horizontal
+                            .lock()
+                            .await
+                            .configure_requests(&config.horizontal);
+                        Ok(Self::wrap_horizontal(
+                            config,
+                            &dispatch_qos,
+                            &delivery_deadline,
+                            &message_acks,
+                            adapter.local_adapter.clone(),
+                            Arc::new(adapter),
+                        ))
+                    }
                     Err(e) => {
                         warn!(
                             "{}",
@@ -90,41 +212,219 @@ impl AdapterFactory {
                                 e
                             )
                         );
-                        Ok(Box::new(LocalAdapter::new()))
+                        Ok(Self::local(
+                            &dispatch_qos,
+                            &delivery_deadline,
+                            &message_acks,
+                        ))
                     }
                 }
             }
-            AdapterDriver::Nats => {
-                let nats_cfg = NatsAdapterConfig {
-                    // Directly use the NatsAdapterConfig from options
-                    servers: config.nats.servers.clone(),
-                    prefix: config.nats.prefix.clone(),
-                    request_timeout_ms: config.nats.request_timeout_ms,
-                    username: config.nats.username.clone(),
-                    password: config.nats.password.clone(),
-                    token: config.nats.token.clone(),
-                    connection_timeout_ms: config.nats.connection_timeout_ms,
-                    nodes_number: config.nats.nodes_number,
-                };
-                match NatsAdapter::new(nats_cfg).await {
-                    Ok(adapter) => Ok(Box::new(adapter)),
-                    Err(e) => {
-                        warn!(
-                            "{}",
-                            format!(
-                                "Failed to initialize NATS adapter: {}, falling back to local adapter",
-                                e
-                            )
-                        );
-                        Ok(Box::new(LocalAdapter::new()))
+            AdapterDriver::Nats => match NatsAdapter::new(config.nats.clone()).await {
+                Ok(mut adapter) => {
+                    adapter.codec = BroadcastCodec::new(&config.compression);
+                    if config.channel_interest.enabled {
+                        adapter.interest = Some(Arc::new(ChannelInterest::new(
+                            adapter.node_id.clone(),
+                            &config.channel_interest,
+                        )));
                     }
+                    adapter
+                        .horizontal
+                        .lock()
+                        .await
+                        .configure_requests(&config.horizontal);
+                    Ok(Self::wrap_horizontal(
+                        config,
+                        &dispatch_qos,
+                        &delivery_deadline,
+                        &message_acks,
+                        adapter.local_adapter.clone(),
+                        Arc::new(adapter),
+                    ))
                 }
-            }
+                Err(e) => {
+                    warn!(
+                        "{}",
+                        format!(
+                            "Failed to initialize NATS adapter: {}, falling back to local adapter",
+                            e
+                        )
+                    );
+                    Ok(Self::local(
+                        &dispatch_qos,
+                        &delivery_deadline,
+                        &message_acks,
+                    ))
+                }
+            },
+            AdapterDriver::Rabbitmq => match RabbitMqAdapter::new(config.rabbitmq.clone()).await {
+                Ok(mut adapter) => {
+                    adapter.codec = BroadcastCodec::new(&config.compression);
+                    if config.channel_interest.enabled {
+                        adapter.interest = Some(Arc::new(ChannelInterest::new(
+                            adapter.node_id.clone(),
+                            &config.channel_interest,
+                        )));
+                    }
+                    adapter
+                        .horizontal
+                        .lock()
+                        .await
+                        .configure_requests(&config.horizontal);
+                    Ok(Self::wrap_horizontal(
+                        config,
+                        &dispatch_qos,
+                        &delivery_deadline,
+                        &message_acks,
+                        adapter.local_adapter.clone(),
+                        Arc::new(adapter),
+                    ))
+                }
+                Err(e) => {
+                    warn!(
+                        "{}",
+                        format!(
+                            "Failed to initialize RabbitMQ adapter: {}, falling back to local adapter",
+                            e
+                        )
+                    );
+                    Ok(Self::local(
+                        &dispatch_qos,
+                        &delivery_deadline,
+                        &message_acks,
+                    ))
+                }
+            },
+            AdapterDriver::Gossip => match GossipAdapter::new(config.gossip.clone()).await {
+                Ok(adapter) => {
+                    adapter
+                        .horizontal
+                        .lock()
+                        .await
+                        .configure_requests(&config.horizontal);
+                    Ok(Self::wrap_horizontal(
+                        config,
+                        &dispatch_qos,
+                        &delivery_deadline,
+                        &message_acks,
+                        adapter.local_adapter.clone(),
+                        Arc::new(adapter),
+                    ))
+                }
+                Err(e) => {
+                    warn!(
+                        "{}",
+                        format!(
+                            "Failed to initialize gossip adapter: {}, falling back to local adapter",
+                            e
+                        )
+                    );
+                    Ok(Self::local(
+                        &dispatch_qos,
+                        &delivery_deadline,
+                        &message_acks,
+                    ))
+                }
+            },
+            AdapterDriver::Mqtt => match MqttAdapter::new(config.mqtt.clone()).await {
+                Ok(adapter) => Ok(Self::wrap_horizontal(
+                    config,
+                    &dispatch_qos,
+                    &delivery_deadline,
+                    &message_acks,
+                    adapter.local_adapter.clone(),
+                    Arc::new(adapter),
+                )),
+                Err(e) => {
+                    warn!(
+                        "{}",
+                        format!(
+                            "Failed to initialize MQTT adapter: {}, falling back to local adapter",
+                            e
+                        )
+                    );
+                    Ok(Self::local(
+                        &dispatch_qos,
+                        &delivery_deadline,
+                        &message_acks,
+                    ))
+                }
+            },
             AdapterDriver::Local | _ => {
                 // Handle unknown as Local or make it an error
                 info!("{}", "Using local adapter.".to_string());
-                Ok(Box::new(LocalAdapter::new()))
+                Ok(Self::local(
+                    &dispatch_qos,
+                    &delivery_deadline,
+                    &message_acks,
+                ))
             }
         }
     }
+
+    fn local(
+        dispatch_qos: &Option<Arc<DispatchQos>>,
+        delivery_deadline: &Option<Arc<DeliveryDeadline>>,
+        message_acks: &Option<Arc<MessageAcks>>,
+    ) -> Arc<dyn Adapter + Send + Sync> {
+        let adapter = LocalAdapter::new();
+        if let Some(qos) = dispatch_qos {
+            adapter.set_dispatch_qos(qos.clone());
+        }
+        if let Some(deadline) = delivery_deadline {
+            adapter.set_delivery_deadline(deadline.clone());
+        }
+        if let Some(acks) = message_acks {
+            adapter.set_message_acks(acks.clone());
+        }
+        Arc::new(adapter)
+    }
+
+    /// Install dispatch QoS, the delivery deadline and message acks on a
+    /// horizontal adapter's local registry, and wrap the adapter so it degrades to
+    /// local-only delivery while its backend is unreachable, when failover
+    /// is enabled
+    fn wrap_horizontal(
+        config: &AdapterConfig,
+        dispatch_qos: &Option<Arc<DispatchQos>>,
+        delivery_deadline: &Option<Arc<DeliveryDeadline>>,
+        message_acks: &Option<Arc<MessageAcks>>,
+        local_adapter: Arc<LocalAdapter>,
+        adapter: Arc<dyn Adapter + Send + Sync>,
+    ) -> Arc<dyn Adapter + Send + Sync> {
+        if let Some(qos) = dispatch_qos {
+            local_adapter.set_dispatch_qos(qos.clone());
+        }
+        if let Some(deadline) = delivery_deadline {
+            local_adapter.set_delivery_deadline(deadline.clone());
+        }
+        if let Some(acks) = message_acks {
+            local_adapter.set_message_acks(acks.clone());
+        }
+        if !config.failover.enabled {
+            return adapter;
+        }
+        if config.failover.fallback_driver != AdapterDriver::Local {
+            warn!(
+                "{}",
+                format!(
+                    "Adapter failover only supports the local fallback driver, ignoring {:?}",
+                    config.failover.fallback_driver
+                )
+            );
+        }
+        info!(
+            "{}",
+            format!(
+                "Adapter failover enabled: {:?} falls back to local delivery",
+                config.driver
+            )
+        );
+        Arc::new(FailoverAdapter::new(
+            adapter,
+            local_adapter,
+            &config.failover,
+        ))
+    }
 }
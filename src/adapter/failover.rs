@@ -0,0 +1,375 @@
+// src/adapter/failover.rs
+//! Failover wrapper around a horizontal adapter. While the backend (Redis,
+//! NATS, ...) is unreachable, cross-node operations are answered from this
+//! node's local registry so clients keep receiving local broadcasts, and the
+//! backend is probed until it recovers.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use dashmap::{DashMap, DashSet};
+use fastwebsockets::WebSocketWrite;
+use hyper::upgrade::Upgraded;
+use hyper_util::rt::TokioIo;
+use tokio::io::WriteHalf;
+use tokio::sync::{Mutex, RwLock};
+use tracing::{info, warn};
+
+use crate::adapter::Adapter;
+use crate::adapter::local_adapter::LocalAdapter;
+use crate::adapter::registry::NodeInfo;
+use crate::app::manager::AppManager;
+use crate::channel::PresenceMemberInfo;
+use crate::error::{Error, Result};
+use crate::metrics::MetricsInterface;
+use crate::namespace::Namespace;
+use crate::options::AdapterFailoverConfig;
+use crate::protocol::messages::PusherMessage;
+use crate::websocket::{SocketDetails, SocketId, WebSocket, WebSocketRef};
+
+/// Failover state shared with the probe task
+#[derive(Default)]
+struct FailoverState {
+    active: AtomicBool,
+    probing: AtomicBool,
+    metrics: RwLock<Option<Arc<Mutex<dyn MetricsInterface + Send + Sync>>>>,
+}
+
+impl FailoverState {
+    async fn record(&self, active: bool) {
+        if let Some(metrics) = self.metrics.read().await.as_ref() {
+            metrics.lock().await.mark_adapter_failover(active);
+        }
+    }
+}
+
+pub struct FailoverAdapter {
+    primary: Arc<dyn Adapter + Send + Sync>,
+    /// The primary's own local registry, which holds this node's sockets
+    local: Arc<LocalAdapter>,
+    probe_interval: Duration,
+    state: Arc<FailoverState>,
+}
+
+impl FailoverAdapter {
+    pub fn new(
+        primary: Arc<dyn Adapter + Send + Sync>,
+        local: Arc<LocalAdapter>,
+        config: &AdapterFailoverConfig,
+    ) -> Self {
+        Self {
+            primary,
+            local,
+            probe_interval: Duration::from_millis(config.probe_interval_ms.max(1)),
+            state: Arc::new(FailoverState::default()),
+        }
+    }
+
+    /// Whether cross-node operations are currently served locally
+    pub fn is_failed_over(&self) -> bool {
+        self.state.active.load(Ordering::SeqCst)
+    }
+
+    /// Switch to local-only delivery after the primary failed with `error`
+    /// and start probing it for recovery
+    async fn fail_over(&self, error: &Error) {
+        if self.state.active.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        warn!(
+            "{}",
+            format!(
+                "Horizontal adapter unreachable ({}), degrading to local-only delivery",
+                error
+            )
+        );
+        self.state.record(true).await;
+
+        if self.state.probing.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let primary = self.primary.clone();
+        let state = self.state.clone();
+        let interval = self.probe_interval;
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                if primary.health().await.is_ok() || primary.reconnect().await.is_ok() {
+                    break;
+                }
+            }
+            state.active.store(false, Ordering::SeqCst);
+            state.probing.store(false, Ordering::SeqCst);
+            state.record(false).await;
+            info!(
+                "{}",
+                "Horizontal adapter recovered, resuming cross-node delivery"
+            );
+        });
+    }
+}
+
+/// Run a cross-node operation on the primary, or on the local registry while
+/// failed over. A primary error triggers failover and is answered locally.
+macro_rules! with_failover {
+    ($self:ident, $adapter:ident => $call:expr) => {{
+        if $self.is_failed_over() {
+            let $adapter = &$self.local;
+            $call
+        } else {
+            let result = {
+                let $adapter = &$self.primary;
+                $call
+            };
+            match result {
+                Ok(value) => Ok(value),
+                Err(e) => {
+                    $self.fail_over(&e).await;
+                    let $adapter = &$self.local;
+                    $call
+                }
+            }
+        }
+    }};
+}
+
+#[async_trait]
+impl Adapter for FailoverAdapter {
+    async fn init(&self) {
+        self.primary.init().await
+    }
+
+    async fn get_namespace(&self, app_id: &str) -> Option<Arc<Namespace>> {
+        self.primary.get_namespace(app_id).await
+    }
+
+    async fn add_socket(
+        &self,
+        socket_id: SocketId,
+        socket: WebSocketWrite<WriteHalf<TokioIo<Upgraded>>>,
+        app_id: &str,
+        app_manager: &Arc<dyn AppManager + Send + Sync>,
+    ) -> Result<()> {
+        self.primary
+            .add_socket(socket_id, socket, app_id, app_manager)
+            .await
+    }
+
+    async fn get_connection(
+        &self,
+        socket_id: &SocketId,
+        app_id: &str,
+    ) -> Option<Arc<Mutex<WebSocket>>> {
+        self.primary.get_connection(socket_id, app_id).await
+    }
+
+    async fn remove_connection(&self, socket_id: &SocketId, app_id: &str) -> Result<()> {
+        self.primary.remove_connection(socket_id, app_id).await
+    }
+
+    async fn send_message(
+        &self,
+        app_id: &str,
+        socket_id: &SocketId,
+        message: PusherMessage,
+    ) -> Result<()> {
+        self.primary.send_message(app_id, socket_id, message).await
+    }
+
+    async fn send(
+        &self,
+        channel: &str,
+        message: PusherMessage,
+        except: Option<&SocketId>,
+        app_id: &str,
+    ) -> Result<()> {
+        if self.is_failed_over() {
+            return self.local.send(channel, message, except, app_id).await;
+        }
+        // Horizontal adapters deliver to local sockets before publishing, so
+        // a publish failure needs no second local delivery
+        if let Err(e) = self.primary.send(channel, message, except, app_id).await {
+            self.fail_over(&e).await;
+        }
+        Ok(())
+    }
+
+    async fn get_channel_members(
+        &self,
+        app_id: &str,
+        channel: &str,
+    ) -> Result<HashMap<String, PresenceMemberInfo>> {
+        with_failover!(self, adapter => adapter.get_channel_members(app_id, channel).await)
+    }
+
+    async fn get_channel_sockets(
+        &self,
+        app_id: &str,
+        channel: &str,
+    ) -> Result<DashMap<SocketId, Arc<Mutex<WebSocket>>>> {
+        with_failover!(self, adapter => adapter.get_channel_sockets(app_id, channel).await)
+    }
+
+    async fn get_channel(&self, app_id: &str, channel: &str) -> Result<DashSet<SocketId>> {
+        with_failover!(self, adapter => adapter.get_channel(app_id, channel).await)
+    }
+
+    async fn remove_channel(&self, app_id: &str, channel: &str) {
+        self.primary.remove_channel(app_id, channel).await
+    }
+
+    async fn is_in_channel(
+        &self,
+        app_id: &str,
+        channel: &str,
+        socket_id: &SocketId,
+    ) -> Result<bool> {
+        self.primary.is_in_channel(app_id, channel, socket_id).await
+    }
+
+    async fn get_user_sockets(&self, user_id: &str, app_id: &str) -> Result<DashSet<WebSocketRef>> {
+        with_failover!(self, adapter => adapter.get_user_sockets(user_id, app_id).await)
+    }
+
+    async fn cleanup_connection(&self, app_id: &str, ws: WebSocketRef) {
+        self.primary.cleanup_connection(app_id, ws).await
+    }
+
+    async fn terminate_connection(&self, app_id: &str, user_id: &str) -> Result<()> {
+        with_failover!(self, adapter => adapter.terminate_connection(app_id, user_id).await)
+    }
+
+    async fn add_channel_to_sockets(&self, app_id: &str, channel: &str, socket_id: &SocketId) {
+        self.primary
+            .add_channel_to_sockets(app_id, channel, socket_id)
+            .await
+    }
+
+    async fn get_channel_socket_count(&self, app_id: &str, channel: &str) -> usize {
+        // Errors are already folded into the count by the primary
+        if self.is_failed_over() {
+            self.local.get_channel_socket_count(app_id, channel).await
+        } else {
+            self.primary.get_channel_socket_count(app_id, channel).await
+        }
+    }
+
+    async fn claim_channel_transition(&self, app_id: &str, channel: &str, occupied: bool) -> bool {
+        if self.is_failed_over() {
+            self.local
+                .claim_channel_transition(app_id, channel, occupied)
+                .await
+        } else {
+            self.primary
+                .claim_channel_transition(app_id, channel, occupied)
+                .await
+        }
+    }
+
+    async fn add_to_channel(
+        &self,
+        app_id: &str,
+        channel: &str,
+        socket_id: &SocketId,
+    ) -> Result<bool> {
+        self.primary
+            .add_to_channel(app_id, channel, socket_id)
+            .await
+    }
+
+    async fn remove_from_channel(
+        &self,
+        app_id: &str,
+        channel: &str,
+        socket_id: &SocketId,
+    ) -> Result<bool> {
+        self.primary
+            .remove_from_channel(app_id, channel, socket_id)
+            .await
+    }
+
+    async fn get_presence_member(
+        &self,
+        app_id: &str,
+        channel: &str,
+        socket_id: &SocketId,
+    ) -> Option<PresenceMemberInfo> {
+        self.primary
+            .get_presence_member(app_id, channel, socket_id)
+            .await
+    }
+
+    async fn terminate_user_connections(&self, app_id: &str, user_id: &str) -> Result<()> {
+        with_failover!(self, adapter => adapter.terminate_user_connections(app_id, user_id).await)
+    }
+
+    async fn add_user(&self, ws: Arc<Mutex<WebSocket>>) -> Result<()> {
+        self.primary.add_user(ws).await
+    }
+
+    async fn remove_user(&self, ws: Arc<Mutex<WebSocket>>) -> Result<()> {
+        self.primary.remove_user(ws).await
+    }
+
+    async fn get_channels_with_socket_count(&self, app_id: &str) -> Result<DashMap<String, usize>> {
+        with_failover!(self, adapter => adapter.get_channels_with_socket_count(app_id).await)
+    }
+
+    async fn get_sockets_count(&self, app_id: &str) -> Result<usize> {
+        with_failover!(self, adapter => adapter.get_sockets_count(app_id).await)
+    }
+
+    async fn get_channel_socket_details(
+        &self,
+        app_id: &str,
+        channel: &str,
+        sample: usize,
+    ) -> Result<Vec<SocketDetails>> {
+        with_failover!(self, adapter => adapter.get_channel_socket_details(app_id, channel, sample).await)
+    }
+
+    async fn get_namespaces(&self) -> Result<DashMap<String, Arc<Namespace>>> {
+        self.primary.get_namespaces().await
+    }
+
+    async fn health(&self) -> Result<()> {
+        self.primary.health().await
+    }
+
+    async fn reconnect(&self) -> Result<()> {
+        self.primary.reconnect().await
+    }
+
+    async fn register_node(&self, node: &NodeInfo) -> Result<()> {
+        self.primary.register_node(node).await
+    }
+
+    async fn registered_nodes(&self) -> Result<Vec<NodeInfo>> {
+        self.primary.registered_nodes().await
+    }
+
+    async fn set_metrics(
+        &self,
+        metrics: Arc<Mutex<dyn MetricsInterface + Send + Sync>>,
+    ) -> Result<()> {
+        metrics
+            .lock()
+            .await
+            .mark_adapter_failover(self.is_failed_over());
+        *self.state.metrics.write().await = Some(metrics.clone());
+        self.primary.set_metrics(metrics).await
+    }
+
+    fn set_app_manager(&self, app_manager: Arc<dyn AppManager + Send + Sync>) {
+        self.primary.set_app_manager(app_manager.clone());
+        self.local.set_app_manager(app_manager);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
@@ -0,0 +1,1264 @@
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::adapter::adapter::Adapter;
+use crate::adapter::horizontal_adapter::{
+    BroadcastMessage, HorizontalAdapter, PendingRequest, RequestBody, RequestType, ResponseBody,
+};
+use crate::adapter::listener::ListenerState;
+use crate::adapter::local_adapter::LocalAdapter;
+use crate::adapter::registry::NodeInfo;
+use crate::app::manager::AppManager;
+use crate::channel::PresenceMemberInfo;
+use crate::error::{Error, Result};
+use async_trait::async_trait;
+use dashmap::{DashMap, DashSet};
+use fastwebsockets::WebSocketWrite;
+use hickory_resolver::TokioAsyncResolver;
+use hmac::{Hmac, KeyInit, Mac};
+use hyper::upgrade::Upgraded;
+use hyper_util::rt::TokioIo;
+use sha2::Sha256;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, WriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{Mutex, mpsc};
+use tracing::{debug, error, info, warn};
+
+use crate::metrics::MetricsInterface;
+use crate::namespace::Namespace;
+pub(crate) use crate::options::GossipAdapterConfig;
+use crate::protocol::messages::PusherMessage;
+use crate::token::secure_compare;
+use crate::websocket::{SocketDetails, SocketId, WebSocket, WebSocketRef};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Frame prefixes of the line-based peer protocol. Each frame is a single
+/// line: its signature, a space, a one-letter kind, a space, and the payload.
+///
+/// The listener opens every inbound connection with `C {nonce} {node_id}`.
+/// The dialling peer signs its frames with that nonce, and recognises a
+/// connection to itself by the node ID.
+const FRAME_CHALLENGE: &str = "C";
+const FRAME_HELLO: &str = "H";
+const FRAME_HEARTBEAT: &str = "P";
+const FRAME_BROADCAST: &str = "B";
+const FRAME_REQUEST: &str = "Q";
+const FRAME_RESPONSE: &str = "R";
+const FRAME_NODE: &str = "N";
+
+/// Signature of frames on a connection without a shared secret
+const UNSIGNED: &str = "-";
+
+/// Signs or checks the frames of one peer connection. A signature covers the
+/// listener's nonce and the frame's position on the connection, so a frame
+/// can't be replayed, reordered or moved to another connection.
+struct FrameSigner {
+    secret: Option<String>,
+    nonce: String,
+    sequence: u64,
+}
+
+impl FrameSigner {
+    fn new(secret: Option<String>, nonce: String) -> Self {
+        Self {
+            secret,
+            nonce,
+            sequence: 0,
+        }
+    }
+
+    fn signature(&self, frame: &str) -> String {
+        match &self.secret {
+            Some(secret) => {
+                let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+                    .expect("HMAC can take key of any size");
+                mac.update(self.nonce.as_bytes());
+                mac.update(&self.sequence.to_be_bytes());
+                mac.update(frame.as_bytes());
+                hex::encode(mac.finalize().into_bytes())
+            }
+            None => UNSIGNED.to_string(),
+        }
+    }
+
+    /// The line to send for the next `{kind} {payload}` frame
+    fn sign(&mut self, frame: &str) -> String {
+        self.sequence += 1;
+        format!("{} {}\n", self.signature(frame), frame)
+    }
+
+    /// The kind and payload of the next line received, if its signature
+    /// holds
+    fn verify<'a>(&mut self, line: &'a str) -> Option<(&'a str, &'a str)> {
+        self.sequence += 1;
+        let (signature, frame) = line.split_once(' ')?;
+        if !secure_compare(signature, &self.signature(frame)) {
+            return None;
+        }
+        Some(frame.split_once(' ').unwrap_or((frame, "")))
+    }
+}
+
+/// Read one line of at most `max_bytes`, without its line ending. A longer
+/// line is an error, so a peer can't grow the buffer without bound.
+async fn read_frame<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    max_bytes: usize,
+) -> std::io::Result<Option<String>> {
+    let mut buf = Vec::new();
+    let read = (&mut *reader)
+        .take(max_bytes as u64 + 1)
+        .read_until(b'\n', &mut buf)
+        .await?;
+    if read == 0 {
+        return Ok(None);
+    }
+    if buf.pop() != Some(b'\n') {
+        let kind = if read > max_bytes {
+            std::io::ErrorKind::InvalidData
+        } else {
+            std::io::ErrorKind::UnexpectedEof
+        };
+        return Err(std::io::Error::new(
+            kind,
+            format!("gossip frame over {} bytes or unterminated", max_bytes),
+        ));
+    }
+    if buf.last() == Some(&b'\r') {
+        buf.pop();
+    }
+    String::from_utf8(buf)
+        .map(Some)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// The nonce and node ID of a listener's challenge line
+fn parse_challenge(line: &str) -> Option<(&str, &str)> {
+    let mut parts = line.split(' ');
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(FRAME_CHALLENGE), Some(nonce), Some(node_id), None)
+            if !nonce.is_empty() && !node_id.is_empty() =>
+        {
+            Some((nonce, node_id))
+        }
+        _ => None,
+    }
+}
+
+/// Whether a bind address only accepts connections from this host
+fn is_loopback(bind_address: &str) -> bool {
+    match bind_address.parse::<std::net::SocketAddr>() {
+        Ok(addr) => addr.ip().is_loopback(),
+        Err(_) => bind_address
+            .rsplit_once(':')
+            .is_some_and(|(host, _)| host == "localhost"),
+    }
+}
+
+/// Broker-less adapter that connects Sockudo nodes directly to each other.
+///
+/// Peers come from a static list and/or a DNS SRV record. Every node dials
+/// every peer over TCP and only writes on its outbound connections; inbound
+/// connections are read-only. Heartbeats on those connections drive
+/// membership, which in turn drives the node count used for horizontal requests.
+pub struct GossipAdapter {
+    /// Base horizontal adapter (protected by a Mutex). Only cross-node requests
+    /// take this lock; local operations go through `local_adapter`.
+    pub horizontal: Arc<Mutex<HorizontalAdapter>>,
+
+    /// Local connection registry, shared with the horizontal adapter
+    pub local_adapter: Arc<LocalAdapter>,
+
+    /// Unique ID of this node, copied from the horizontal adapter
+    pub node_id: String,
+
+    /// Configuration
+    pub config: GossipAdapterConfig,
+
+    /// Outbound frame queues, keyed by peer address
+    peers: Arc<DashMap<String, mpsc::UnboundedSender<Arc<str>>>>,
+
+    /// Last heartbeat seen from each remote node, keyed by node ID
+    members: Arc<DashMap<String, Instant>>,
+
+    /// Pending horizontal requests, shared with the horizontal adapter
+    pending_requests: Arc<DashMap<String, PendingRequest>>,
+
+    /// Node registry entries received from peers, keyed by process ID
+    nodes: Arc<DashMap<String, NodeInfo>>,
+
+    /// Peer addresses that turned out to reach this node
+    own_addresses: Arc<DashSet<String>>,
+
+    /// Whether the inbound listener is stopped, binding or accepting peers
+    pub listener: Arc<ListenerState>,
+}
+
+impl GossipAdapter {
+    /// Create a new gossip adapter
+    pub async fn new(config: GossipAdapterConfig) -> Result<Self> {
+        let mut horizontal = HorizontalAdapter::new();
+        info!(
+            "{}",
+            format!(
+                "Gossip adapter config: bind={}, peers={:?}, dns_srv={:?}",
+                config.bind_address, config.peers, config.dns_srv
+            )
+        );
+
+        // Frames from the listener can broadcast into any app and run any
+        // request, so it must not be reachable from other hosts unsigned
+        if config.secret.is_none() && !is_loopback(&config.bind_address) {
+            return Err(Error::ConfigurationError(format!(
+                "Gossip bind address {} is reachable from other hosts; set adapter.gossip.secret to accept peers on it",
+                config.bind_address
+            )));
+        }
+
+        horizontal.requests_timeout = config.request_timeout_ms;
+
+        let local_adapter = horizontal.local_adapter.clone();
+        let node_id = horizontal.node_id.clone();
+        let pending_requests = horizontal.pending_requests.clone();
+
+        Ok(Self {
+            horizontal: Arc::new(Mutex::new(horizontal)),
+            local_adapter,
+            node_id,
+            config,
+            peers: Arc::new(DashMap::new()),
+            members: Arc::new(DashMap::new()),
+            pending_requests,
+            nodes: Arc::new(DashMap::new()),
+            own_addresses: Arc::new(DashSet::new()),
+            listener: Arc::new(ListenerState::default()),
+        })
+    }
+
+    /// Start the listener, peer discovery and request publishing
+    pub async fn start(&self) -> Result<()> {
+        let (request_tx, request_rx) = mpsc::unbounded_channel();
+        {
+            let mut horizontal = self.horizontal.lock().await;
+            horizontal.start_request_cleanup();
+            horizontal.request_publisher = Some(request_tx);
+        }
+
+        self.start_listener().await?;
+        self.start_request_forwarder(request_rx);
+        self.start_discovery();
+
+        Ok(())
+    }
+
+    /// Accept inbound peer connections and process the frames they send
+    async fn start_listener(&self) -> Result<()> {
+        // Another start may still be binding, or the listener is up
+        if !self.listener.try_start() {
+            return Ok(());
+        }
+
+        let listener = match TcpListener::bind(&self.config.bind_address).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                self.listener.stopped();
+                return Err(Error::InternalError(format!(
+                    "Failed to bind gossip listener on {}: {}",
+                    self.config.bind_address, e
+                )));
+            }
+        };
+
+        self.listener.running();
+        info!(
+            "{}",
+            format!("Gossip adapter listening on {}", self.config.bind_address)
+        );
+
+        let listener_state = self.listener.clone();
+        let local_adapter = self.local_adapter.clone();
+        let node_id = self.node_id.clone();
+        let members = self.members.clone();
+        let peers = self.peers.clone();
+        let pending_requests = self.pending_requests.clone();
+        let nodes = self.nodes.clone();
+        let secret = self.config.secret.clone();
+        let max_frame_bytes = self.config.max_frame_bytes;
+        let task = tokio::spawn(async move {
+            loop {
+                let (stream, addr) = match listener.accept().await {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        error!("{}", format!("Gossip listener failed: {}", e));
+                        break;
+                    }
+                };
+                debug!(
+                    "{}",
+                    format!("Accepted gossip peer connection from {}", addr)
+                );
+
+                tokio::spawn(Self::read_peer(
+                    stream,
+                    FrameSigner::new(secret.clone(), hex::encode(rand::random::<[u8; 16]>())),
+                    max_frame_bytes,
+                    local_adapter.clone(),
+                    node_id.clone(),
+                    members.clone(),
+                    peers.clone(),
+                    pending_requests.clone(),
+                    nodes.clone(),
+                ));
+            }
+            listener_state.stopped();
+            warn!("{}", "Gossip listener stopped accepting peers.");
+        });
+        self.listener.track(&task);
+
+        Ok(())
+    }
+
+    /// Read frames from one inbound peer connection until it closes or sends
+    /// a frame that is too long or wrongly signed
+    #[allow(clippy::too_many_arguments)]
+    async fn read_peer(
+        stream: TcpStream,
+        mut signer: FrameSigner,
+        max_frame_bytes: usize,
+        local_adapter: Arc<LocalAdapter>,
+        node_id: String,
+        members: Arc<DashMap<String, Instant>>,
+        peers: Arc<DashMap<String, mpsc::UnboundedSender<Arc<str>>>>,
+        pending_requests: Arc<DashMap<String, PendingRequest>>,
+        nodes: Arc<DashMap<String, NodeInfo>>,
+    ) {
+        let (reader, mut writer) = stream.into_split();
+        let challenge = format!("{} {} {}\n", FRAME_CHALLENGE, signer.nonce, node_id);
+        if let Err(e) = writer.write_all(challenge.as_bytes()).await {
+            debug!("{}", format!("Failed to challenge gossip peer: {}", e));
+            return;
+        }
+
+        let mut reader = BufReader::new(reader);
+        let mut remote_node_id: Option<String> = None;
+
+        loop {
+            let line = match read_frame(&mut reader, max_frame_bytes).await {
+                Ok(Some(line)) => line,
+                Ok(None) => break,
+                Err(e) => {
+                    warn!("{}", format!("Gossip peer read failed: {}", e));
+                    break;
+                }
+            };
+            let Some((kind, payload)) = signer.verify(&line) else {
+                warn!(
+                    "{}",
+                    "Closing gossip connection after a frame with an invalid signature"
+                );
+                break;
+            };
+
+            match kind {
+                FRAME_HELLO | FRAME_HEARTBEAT => {
+                    if payload != node_id {
+                        members.insert(payload.to_string(), Instant::now());
+                        remote_node_id = Some(payload.to_string());
+                    }
+                }
+                FRAME_BROADCAST => match serde_json::from_str::<BroadcastMessage>(payload) {
+                    Ok(broadcast) => {
+                        if broadcast.node_id == node_id {
+                            continue;
+                        }
+                        match serde_json::from_str(&broadcast.message) {
+                            Ok(message) => {
+                                let except_id = broadcast
+                                    .except_socket_id
+                                    .as_ref()
+                                    .map(|id| SocketId(id.clone()));
+                                let _ = local_adapter
+                                    .deliver(
+                                        &broadcast.channel,
+                                        message,
+                                        except_id.as_ref(),
+                                        &broadcast.app_id,
+                                        broadcast.published_at,
+                                    )
+                                    .await;
+                            }
+                            Err(e) => {
+                                warn!(
+                                    "{}",
+                                    format!("Failed to deserialize broadcast inner message: {}", e)
+                                );
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        warn!(
+                            "{}",
+                            format!("Failed to deserialize broadcast frame: {}", e)
+                        );
+                    }
+                },
+                FRAME_REQUEST => match serde_json::from_str::<RequestBody>(payload) {
+                    Ok(request) => {
+                        // Answer from the local registry without the horizontal lock,
+                        // so a request pending on this node cannot block the reply
+                        let response =
+                            HorizontalAdapter::build_response(&local_adapter, &node_id, request)
+                                .await;
+                        if let Ok(response) = response {
+                            match serde_json::to_string(&response) {
+                                Ok(response_json) => {
+                                    Self::fan_out(&peers, FRAME_RESPONSE, &response_json)
+                                }
+                                Err(e) => {
+                                    error!("{}", format!("Failed to serialize response: {}", e));
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        warn!("{}", format!("Failed to deserialize request frame: {}", e));
+                    }
+                },
+                FRAME_RESPONSE => match serde_json::from_str::<ResponseBody>(payload) {
+                    Ok(response) => {
+                        if response.node_id != node_id {
+                            HorizontalAdapter::record_response(&pending_requests, response);
+                        }
+                    }
+                    Err(e) => {
+                        warn!("{}", format!("Failed to deserialize response frame: {}", e));
+                    }
+                },
+                FRAME_NODE => match serde_json::from_str::<NodeInfo>(payload) {
+                    Ok(node) => {
+                        nodes.insert(node.process_id.clone(), node);
+                    }
+                    Err(e) => {
+                        warn!("{}", format!("Failed to deserialize node frame: {}", e));
+                    }
+                },
+                _ => {
+                    warn!("{}", format!("Unknown gossip frame kind: {}", kind));
+                }
+            }
+        }
+
+        if let Some(remote_node_id) = remote_node_id {
+            debug!(
+                "{}",
+                format!("Gossip connection from node {} closed", remote_node_id)
+            );
+        }
+    }
+
+    /// Queue a frame on every outbound peer connection
+    fn fan_out(
+        peers: &DashMap<String, mpsc::UnboundedSender<Arc<str>>>,
+        kind: &str,
+        payload: &str,
+    ) {
+        let frame: Arc<str> = Arc::from(format!("{} {}", kind, payload));
+        for peer in peers.iter() {
+            // A closed queue means the connector was replaced; discovery cleans it up
+            let _ = peer.value().send(frame.clone());
+        }
+    }
+
+    /// Forward requests issued through the horizontal adapter to every peer
+    fn start_request_forwarder(&self, mut request_rx: mpsc::UnboundedReceiver<String>) {
+        let peers = self.peers.clone();
+        tokio::spawn(async move {
+            while let Some(request_json) = request_rx.recv().await {
+                Self::fan_out(&peers, FRAME_REQUEST, &request_json);
+            }
+        });
+    }
+
+    /// Periodically resolve the peer list and dial any peer we are not connected to
+    fn start_discovery(&self) {
+        let config = self.config.clone();
+        let peers = self.peers.clone();
+        let members = self.members.clone();
+        let node_id = self.node_id.clone();
+        let own_addresses = self.own_addresses.clone();
+        tokio::spawn(async move {
+            let interval = Duration::from_millis(config.discovery_interval_ms.max(100));
+            let peer_timeout = Duration::from_millis(config.peer_timeout_ms);
+            loop {
+                let addresses = Self::discover_peers(&config).await;
+                for address in addresses {
+                    if own_addresses.contains(&address) {
+                        peers.remove(&address);
+                        continue;
+                    }
+                    let connected = peers
+                        .get(&address)
+                        .map(|sender| !sender.is_closed())
+                        .unwrap_or(false);
+                    if connected {
+                        continue;
+                    }
+                    let (tx, rx) = mpsc::unbounded_channel();
+                    peers.insert(address.clone(), tx);
+                    tokio::spawn(Self::run_connector(
+                        address,
+                        rx,
+                        node_id.clone(),
+                        config.clone(),
+                        own_addresses.clone(),
+                    ));
+                }
+
+                // Forget members that stopped sending heartbeats
+                members.retain(|member, last_seen| {
+                    let alive = last_seen.elapsed() < peer_timeout;
+                    if !alive {
+                        info!("{}", format!("Gossip member {} timed out", member));
+                    }
+                    alive
+                });
+
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+
+    /// Resolve the configured static peers and DNS SRV record into addresses
+    async fn discover_peers(config: &GossipAdapterConfig) -> HashSet<String> {
+        let mut addresses: HashSet<String> = config.peers.iter().cloned().collect();
+
+        if let Some(srv_name) = &config.dns_srv {
+            match TokioAsyncResolver::tokio_from_system_conf() {
+                Ok(resolver) => match resolver.srv_lookup(srv_name.as_str()).await {
+                    Ok(records) => {
+                        for record in records.iter() {
+                            let target = record.target().to_utf8();
+                            addresses.insert(format!(
+                                "{}:{}",
+                                target.trim_end_matches('.'),
+                                record.port()
+                            ));
+                        }
+                    }
+                    Err(e) => {
+                        warn!(
+                            "{}",
+                            format!("Gossip SRV lookup for {} failed: {}", srv_name, e)
+                        );
+                    }
+                },
+                Err(e) => {
+                    warn!("{}", format!("Failed to create DNS resolver: {}", e));
+                }
+            }
+        }
+
+        // Never dial ourselves
+        if let Some(advertise) = &config.advertise_address {
+            addresses.remove(advertise);
+        }
+        addresses
+    }
+
+    /// Keep one outbound connection to a peer alive, reconnecting with backoff
+    async fn run_connector(
+        address: String,
+        mut rx: mpsc::UnboundedReceiver<Arc<str>>,
+        node_id: String,
+        config: GossipAdapterConfig,
+        own_addresses: Arc<DashSet<String>>,
+    ) {
+        let connect_timeout = Duration::from_millis(config.connect_timeout_ms);
+        let heartbeat_interval = Duration::from_millis(config.heartbeat_interval_ms.max(100));
+        let initial_backoff = Duration::from_millis(500);
+        let max_backoff = Duration::from_millis(config.max_backoff_ms.max(500));
+        let mut backoff = initial_backoff;
+        let resolver = crate::dns::resolver();
+
+        loop {
+            let stream =
+                match tokio::time::timeout(connect_timeout, resolver.connect_tcp(&address)).await {
+                    Ok(Ok(stream)) => stream,
+                    Ok(Err(e)) => {
+                        debug!("{}", format!("Gossip connect to {} failed: {}", address, e));
+                        Self::wait_backoff(&mut rx, &mut backoff, max_backoff).await;
+                        if rx.is_closed() {
+                            return;
+                        }
+                        continue;
+                    }
+                    Err(_) => {
+                        debug!("{}", format!("Gossip connect to {} timed out", address));
+                        Self::wait_backoff(&mut rx, &mut backoff, max_backoff).await;
+                        if rx.is_closed() {
+                            return;
+                        }
+                        continue;
+                    }
+                };
+            let _ = stream.set_nodelay(true);
+
+            let (reader, mut writer) = stream.into_split();
+            let mut reader = BufReader::new(reader);
+            let challenge =
+                match tokio::time::timeout(connect_timeout, read_frame(&mut reader, 256)).await {
+                    Ok(Ok(Some(line))) => line,
+                    _ => {
+                        debug!("{}", format!("Gossip peer {} sent no challenge", address));
+                        Self::wait_backoff(&mut rx, &mut backoff, max_backoff).await;
+                        if rx.is_closed() {
+                            return;
+                        }
+                        continue;
+                    }
+                };
+            let Some((nonce, remote_node_id)) = parse_challenge(&challenge) else {
+                warn!(
+                    "{}",
+                    format!("Gossip peer {} sent an invalid challenge", address)
+                );
+                Self::wait_backoff(&mut rx, &mut backoff, max_backoff).await;
+                if rx.is_closed() {
+                    return;
+                }
+                continue;
+            };
+            if remote_node_id == node_id {
+                info!(
+                    "{}",
+                    format!(
+                        "Gossip peer {} is this node, not dialling it again",
+                        address
+                    )
+                );
+                own_addresses.insert(address);
+                return;
+            }
+            let mut signer = FrameSigner::new(config.secret.clone(), nonce.to_string());
+            info!("{}", format!("Connected to gossip peer {}", address));
+            backoff = initial_backoff;
+
+            let hello = signer.sign(&format!("{} {}", FRAME_HELLO, node_id));
+            if writer.write_all(hello.as_bytes()).await.is_err() {
+                continue;
+            }
+
+            let heartbeat: Arc<str> = Arc::from(format!("{} {}", FRAME_HEARTBEAT, node_id));
+            let mut ticker = tokio::time::interval(heartbeat_interval);
+            loop {
+                let frame = tokio::select! {
+                    frame = rx.recv() => match frame {
+                        Some(frame) => frame,
+                        None => return,
+                    },
+                    _ = ticker.tick() => heartbeat.clone(),
+                };
+                let line = signer.sign(&frame);
+                if let Err(e) = writer.write_all(line.as_bytes()).await {
+                    warn!(
+                        "{}",
+                        format!("Lost gossip connection to {}: {}", address, e)
+                    );
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Sleep for the current backoff, dropping frames queued while disconnected
+    async fn wait_backoff(
+        rx: &mut mpsc::UnboundedReceiver<Arc<str>>,
+        backoff: &mut Duration,
+        max_backoff: Duration,
+    ) {
+        tokio::time::sleep(*backoff).await;
+        *backoff = (*backoff * 2).min(max_backoff);
+        while rx.try_recv().is_ok() {}
+    }
+
+    /// Get the number of nodes in the cluster: ourselves plus every live member
+    pub async fn get_node_count(&self) -> Result<usize> {
+        let peer_timeout = Duration::from_millis(self.config.peer_timeout_ms);
+        let alive = self
+            .members
+            .iter()
+            .filter(|member| member.value().elapsed() < peer_timeout)
+            .count();
+        Ok(alive + 1)
+    }
+
+    /// Node IDs of the peers currently considered alive
+    pub fn members(&self) -> Vec<String> {
+        let peer_timeout = Duration::from_millis(self.config.peer_timeout_ms);
+        self.members
+            .iter()
+            .filter(|member| member.value().elapsed() < peer_timeout)
+            .map(|member| member.key().clone())
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Adapter for GossipAdapter {
+    async fn init(&self) {
+        self.local_adapter.init().await;
+
+        if let Err(e) = self.start().await {
+            error!("{}", format!("Failed to start gossip adapter: {}", e));
+        }
+    }
+
+    async fn get_namespace(&self, app_id: &str) -> Option<Arc<Namespace>> {
+        self.local_adapter.get_namespace(app_id).await
+    }
+
+    async fn add_socket(
+        &self,
+        socket_id: SocketId,
+        socket: WebSocketWrite<WriteHalf<TokioIo<Upgraded>>>,
+        app_id: &str,
+        app_manager: &Arc<dyn AppManager + Send + Sync>,
+    ) -> Result<()> {
+        self.local_adapter
+            .add_socket(socket_id, socket, app_id, app_manager)
+            .await
+    }
+
+    async fn get_connection(
+        &self,
+        socket_id: &SocketId,
+        app_id: &str,
+    ) -> Option<Arc<Mutex<WebSocket>>> {
+        self.local_adapter.get_connection(socket_id, app_id).await
+    }
+
+    async fn remove_connection(&self, socket_id: &SocketId, app_id: &str) -> Result<()> {
+        self.local_adapter
+            .remove_connection(socket_id, app_id)
+            .await
+    }
+
+    async fn send_message(
+        &self,
+        app_id: &str,
+        socket_id: &SocketId,
+        message: PusherMessage,
+    ) -> Result<()> {
+        self.local_adapter
+            .send_message(app_id, socket_id, message)
+            .await
+    }
+
+    async fn send(
+        &self,
+        channel: &str,
+        message: PusherMessage,
+        except: Option<&SocketId>,
+        app_id: &str,
+    ) -> Result<()> {
+        let published_at = chrono::Utc::now().timestamp_millis();
+        // 1. Deliver to local sockets
+        if let Err(e) = self
+            .local_adapter
+            .send(channel, message.clone(), except, app_id)
+            .await
+        {
+            warn!(
+                "{}",
+                format!(
+                    "Local send failed during broadcast for channel {}: {}",
+                    channel, e
+                )
+            );
+        }
+
+        // 2. Wrap the message for the other nodes
+        let broadcast = BroadcastMessage {
+            node_id: self.node_id.clone(),
+            app_id: app_id.to_string(),
+            channel: channel.to_string(),
+            message: serde_json::to_string(&message)?,
+            except_socket_id: except.map(|id| id.0.clone()),
+            published_at: Some(published_at),
+        };
+
+        // 3. Queue it on every peer connection. Frames are text lines, so
+        // adapter compression does not apply here.
+        let broadcast_json = serde_json::to_string(&broadcast)?;
+        Self::fan_out(&self.peers, FRAME_BROADCAST, &broadcast_json);
+        Ok(())
+    }
+
+    async fn get_channel_members(
+        &self,
+        app_id: &str,
+        channel: &str,
+    ) -> Result<HashMap<String, PresenceMemberInfo>> {
+        let node_count = self.get_node_count().await?;
+
+        let mut members = self
+            .local_adapter
+            .get_channel_members(app_id, channel)
+            .await?;
+
+        if node_count > 1 {
+            let response_data = self
+                .horizontal
+                .lock()
+                .await
+                .send_request(
+                    app_id,
+                    RequestType::ChannelMembers,
+                    Some(channel),
+                    None,
+                    None,
+                    node_count,
+                )
+                .await?;
+            members.extend(response_data.members);
+        }
+
+        Ok(members)
+    }
+
+    // Returns only local sockets - inherent limitation
+    async fn get_channel_sockets(
+        &self,
+        app_id: &str,
+        channel: &str,
+    ) -> Result<DashMap<SocketId, Arc<Mutex<WebSocket>>>> {
+        self.local_adapter
+            .get_channel_sockets(app_id, channel)
+            .await
+    }
+
+    async fn get_channel(&self, app_id: &str, channel: &str) -> Result<DashSet<SocketId>> {
+        let node_count = self.get_node_count().await?;
+
+        let result = self.local_adapter.get_channel(app_id, channel).await?;
+
+        if node_count > 1 {
+            let response_data = self
+                .horizontal
+                .lock()
+                .await
+                .send_request(
+                    app_id,
+                    RequestType::ChannelSockets,
+                    Some(channel),
+                    None,
+                    None,
+                    node_count,
+                )
+                .await?;
+
+            for socket_id in response_data.socket_ids {
+                result.insert(SocketId(socket_id));
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn remove_channel(&self, app_id: &str, channel: &str) {
+        self.local_adapter.remove_channel(app_id, channel).await
+    }
+
+    async fn is_in_channel(
+        &self,
+        app_id: &str,
+        channel: &str,
+        socket_id: &SocketId,
+    ) -> Result<bool> {
+        if self
+            .local_adapter
+            .is_in_channel(app_id, channel, socket_id)
+            .await?
+        {
+            return Ok(true);
+        }
+
+        let node_count = self.get_node_count().await?;
+        if node_count > 1 {
+            let response_data = self
+                .horizontal
+                .lock()
+                .await
+                .send_request(
+                    app_id,
+                    RequestType::SocketExistsInChannel,
+                    Some(channel),
+                    Some(&socket_id.0),
+                    None,
+                    node_count,
+                )
+                .await?;
+            return Ok(response_data.exists);
+        }
+
+        Ok(false)
+    }
+
+    // Returns only local sockets - inherent limitation
+    async fn get_user_sockets(&self, user_id: &str, app_id: &str) -> Result<DashSet<WebSocketRef>> {
+        self.local_adapter.get_user_sockets(user_id, app_id).await
+    }
+
+    async fn cleanup_connection(&self, app_id: &str, ws: WebSocketRef) {
+        self.local_adapter.cleanup_connection(app_id, ws).await
+    }
+
+    async fn terminate_connection(&self, app_id: &str, user_id: &str) -> Result<()> {
+        let node_count = self.get_node_count().await?;
+
+        self.local_adapter
+            .terminate_connection(app_id, user_id)
+            .await?;
+
+        if node_count > 1 {
+            self.horizontal
+                .lock()
+                .await
+                .send_request(
+                    app_id,
+                    RequestType::TerminateUserConnections,
+                    None,
+                    None,
+                    Some(user_id),
+                    node_count,
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn add_channel_to_sockets(&self, app_id: &str, channel: &str, socket_id: &SocketId) {
+        self.local_adapter
+            .add_channel_to_sockets(app_id, channel, socket_id)
+            .await
+    }
+
+    async fn get_channel_socket_count(&self, app_id: &str, channel: &str) -> usize {
+        let node_count = self.get_node_count().await.unwrap_or(1);
+
+        let local_count = self
+            .local_adapter
+            .get_channel_socket_count(app_id, channel)
+            .await;
+
+        if node_count > 1 {
+            match self
+                .horizontal
+                .lock()
+                .await
+                .send_request(
+                    app_id,
+                    RequestType::ChannelSocketsCount,
+                    Some(channel),
+                    None,
+                    None,
+                    node_count,
+                )
+                .await
+            {
+                Ok(response_data) => local_count + response_data.sockets_count,
+                Err(e) => {
+                    error!(
+                        "{}",
+                        format!(
+                            "Failed to get remote socket count for channel {}: {}",
+                            channel, e
+                        )
+                    );
+                    local_count
+                }
+            }
+        } else {
+            local_count
+        }
+    }
+
+    async fn add_to_channel(
+        &self,
+        app_id: &str,
+        channel: &str,
+        socket_id: &SocketId,
+    ) -> Result<bool> {
+        self.local_adapter
+            .add_to_channel(app_id, channel, socket_id)
+            .await
+    }
+
+    async fn remove_from_channel(
+        &self,
+        app_id: &str,
+        channel: &str,
+        socket_id: &SocketId,
+    ) -> Result<bool> {
+        self.local_adapter
+            .remove_from_channel(app_id, channel, socket_id)
+            .await
+    }
+
+    async fn get_presence_member(
+        &self,
+        app_id: &str,
+        channel: &str,
+        socket_id: &SocketId,
+    ) -> Option<PresenceMemberInfo> {
+        self.local_adapter
+            .get_presence_member(app_id, channel, socket_id)
+            .await
+    }
+
+    async fn terminate_user_connections(&self, app_id: &str, user_id: &str) -> Result<()> {
+        self.terminate_connection(app_id, user_id).await
+    }
+
+    async fn add_user(&self, ws: Arc<Mutex<WebSocket>>) -> Result<()> {
+        self.local_adapter.add_user(ws).await
+    }
+
+    async fn remove_user(&self, ws: Arc<Mutex<WebSocket>>) -> Result<()> {
+        self.local_adapter.remove_user(ws).await
+    }
+
+    async fn get_channels_with_socket_count(&self, app_id: &str) -> Result<DashMap<String, usize>> {
+        let node_count = self.get_node_count().await?;
+
+        let local_channels = self
+            .local_adapter
+            .get_channels_with_socket_count(app_id)
+            .await?;
+
+        if node_count > 1 {
+            let response_data = self
+                .horizontal
+                .lock()
+                .await
+                .send_request(
+                    app_id,
+                    RequestType::ChannelsWithSocketsCount,
+                    None,
+                    None,
+                    None,
+                    node_count,
+                )
+                .await?;
+            for (channel, count) in response_data.channels_with_sockets_count {
+                local_channels.insert(channel, count);
+            }
+        }
+
+        Ok(local_channels)
+    }
+
+    async fn get_sockets_count(&self, app_id: &str) -> Result<usize> {
+        let node_count = self.get_node_count().await?;
+
+        let local_count = self.local_adapter.get_sockets_count(app_id).await?;
+
+        if node_count > 1 {
+            let response_data = self
+                .horizontal
+                .lock()
+                .await
+                .send_request(
+                    app_id,
+                    RequestType::SocketsCount,
+                    None,
+                    None,
+                    None,
+                    node_count,
+                )
+                .await?;
+            return Ok(local_count + response_data.sockets_count);
+        }
+
+        Ok(local_count)
+    }
+
+    async fn get_channel_socket_details(
+        &self,
+        app_id: &str,
+        channel: &str,
+        sample: usize,
+    ) -> Result<Vec<SocketDetails>> {
+        let node_count = self.get_node_count().await?;
+        self.horizontal
+            .lock()
+            .await
+            .get_channel_socket_details(app_id, channel, sample, node_count)
+            .await
+    }
+
+    async fn get_namespaces(&self) -> Result<DashMap<String, Arc<Namespace>>> {
+        self.local_adapter.get_namespaces().await
+    }
+
+    async fn health(&self) -> Result<()> {
+        if !self.listener.is_running() {
+            return Err(Error::InternalError(
+                "Gossip listener is not running".to_string(),
+            ));
+        }
+
+        // Losing every peer is only a failure when peers are expected
+        let expects_peers = !self.config.peers.is_empty() || self.config.dns_srv.is_some();
+        if expects_peers && self.get_node_count().await? == 1 {
+            return Err(Error::InternalError(
+                "No gossip peers are reachable".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn reconnect(&self) -> Result<()> {
+        // Peer connectors reconnect on their own; only the listener needs a
+        // restart, unless it is accepting peers or still binding
+        self.start_listener().await
+    }
+
+    async fn register_node(&self, node: &NodeInfo) -> Result<()> {
+        let entry = serde_json::to_string(node)?;
+        Self::fan_out(&self.peers, FRAME_NODE, &entry);
+        Ok(())
+    }
+
+    async fn registered_nodes(&self) -> Result<Vec<NodeInfo>> {
+        let now = chrono::Utc::now().timestamp_millis();
+        self.nodes.retain(|_, node| !node.is_expired(now));
+        Ok(self
+            .nodes
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect())
+    }
+
+    async fn set_metrics(
+        &self,
+        metrics: Arc<Mutex<dyn MetricsInterface + Send + Sync>>,
+    ) -> Result<()> {
+        self.horizontal.lock().await.metrics = Some(metrics);
+        Ok(())
+    }
+
+    fn set_app_manager(&self, app_manager: Arc<dyn AppManager + Send + Sync>) {
+        self.local_adapter.set_app_manager(app_manager);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(secret: Option<&str>) -> (FrameSigner, FrameSigner) {
+        let secret = secret.map(str::to_string);
+        (
+            FrameSigner::new(secret.clone(), "nonce".to_string()),
+            FrameSigner::new(secret, "nonce".to_string()),
+        )
+    }
+
+    #[test]
+    fn test_signed_frames_verify_in_order() {
+        let (mut sender, mut receiver) = pair(Some("secret"));
+        let hello = sender.sign("H node-a");
+        let broadcast = sender.sign("B {\"channel\":\"a b\"}");
+
+        assert_eq!(receiver.verify(hello.trim_end()), Some(("H", "node-a")));
+        assert_eq!(
+            receiver.verify(broadcast.trim_end()),
+            Some(("B", "{\"channel\":\"a b\"}"))
+        );
+    }
+
+    #[test]
+    fn test_tampered_replayed_and_foreign_frames_are_rejected() {
+        let (mut sender, mut receiver) = pair(Some("secret"));
+        let frame = sender.sign("Q {}");
+        let (signature, _) = frame.trim_end().split_once(' ').unwrap();
+        assert_eq!(receiver.verify(&format!("{} R {{}}", signature)), None);
+
+        // The same frame again is out of sequence
+        let (mut sender, mut receiver) = pair(Some("secret"));
+        let frame = sender.sign("P node-a");
+        assert!(receiver.verify(frame.trim_end()).is_some());
+        assert_eq!(receiver.verify(frame.trim_end()), None);
+
+        // Signed for another connection's nonce
+        let mut other = FrameSigner::new(Some("secret".to_string()), "other".to_string());
+        let (_, mut receiver) = pair(Some("secret"));
+        assert_eq!(receiver.verify(other.sign("P node-a").trim_end()), None);
+
+        // Signed with another secret, or not at all
+        let (mut sender, _) = pair(Some("wrong"));
+        let (_, mut receiver) = pair(Some("secret"));
+        assert_eq!(receiver.verify(sender.sign("P node-a").trim_end()), None);
+        let (mut sender, _) = pair(None);
+        let (_, mut receiver) = pair(Some("secret"));
+        assert_eq!(receiver.verify(sender.sign("P node-a").trim_end()), None);
+    }
+
+    #[test]
+    fn test_unsigned_frames_without_secret() {
+        let (mut sender, mut receiver) = pair(None);
+        let frame = sender.sign("P node-a");
+        assert!(frame.starts_with("- P node-a"));
+        assert_eq!(receiver.verify(frame.trim_end()), Some(("P", "node-a")));
+        assert_eq!(receiver.verify("P"), None);
+    }
+
+    #[tokio::test]
+    async fn test_read_frame_bounds_length() {
+        let mut input: &[u8] = b"short\r\nexactly10!\n";
+        assert_eq!(
+            read_frame(&mut input, 10).await.unwrap(),
+            Some("short".to_string())
+        );
+        assert_eq!(
+            read_frame(&mut input, 10).await.unwrap(),
+            Some("exactly10!".to_string())
+        );
+        assert_eq!(read_frame(&mut input, 10).await.unwrap(), None);
+
+        let mut input: &[u8] = b"no newline within the limit\n";
+        let err = read_frame(&mut input, 10).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+        let mut input: &[u8] = b"cut off";
+        let err = read_frame(&mut input, 10).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_parse_challenge() {
+        assert_eq!(parse_challenge("C abc node-a"), Some(("abc", "node-a")));
+        assert_eq!(parse_challenge("C abc"), None);
+        assert_eq!(parse_challenge("C abc node-a extra"), None);
+        assert_eq!(parse_challenge("H abc node-a"), None);
+    }
+
+    #[test]
+    fn test_is_loopback() {
+        assert!(is_loopback("127.0.0.1:7946"));
+        assert!(is_loopback("[::1]:7946"));
+        assert!(is_loopback("localhost:7946"));
+        assert!(!is_loopback("0.0.0.0:7946"));
+        assert!(!is_loopback("10.0.0.5:7946"));
+        assert!(!is_loopback("gossip.internal:7946"));
+    }
+}
@@ -1,18 +1,39 @@
 // src/adapter/handler.rs
+use crate::adapter::ack::{ACK_EVENT, MessageAcks};
+use crate::adapter::activity::{ActivityWatch, IdleAction};
 use crate::adapter::adapter::Adapter;
+use crate::adapter::connection_quota::ConnectionQuota;
+use crate::adapter::drain::NodeDrain;
+use crate::adapter::health::AdapterHealthStatus;
+use crate::adapter::registry::NodeRegistry;
 use crate::app::auth::AuthValidator;
 use crate::app::config::App;
 use crate::app::manager::AppManager;
+use crate::app::reload::AppReloader;
+use crate::backoff::BackoffAdvisor;
 use crate::cache::manager::CacheManager;
+use crate::channel::reclaim::ChannelReclaimer;
 use crate::channel::{ChannelManager, ChannelType, PresenceMemberInfo};
+use crate::log_filters::LogFilters;
 use crate::metrics::MetricsInterface;
+use crate::options::{
+    ActivityTimeoutConfig, BackoffConfig, ChannelLimits, ClientEventRateLimit,
+    ConnectionQuotaConfig,
+};
+use crate::probe::WsProbe;
 use crate::protocol::constants::{
     CHANNEL_NAME_MAX_LENGTH as DEFAULT_CHANNEL_NAME_MAX_LENGTH, CLIENT_EVENT_PREFIX,
     EVENT_NAME_MAX_LENGTH as DEFAULT_EVENT_NAME_MAX_LENGTH,
 };
 use crate::protocol::messages::{ErrorData, MessageData, PusherApiMessage, PusherMessage};
-use crate::rate_limiter::{RateLimiter, memory_limiter::MemoryRateLimiter};
-use crate::utils::{is_cache_channel, validate_channel_name};
+use crate::protocol::subprotocols::SubprotocolRegistry;
+use crate::rate_limiter::client_event_limiter::{ClientEventLimiter, ClientEventVerdict};
+use crate::rate_limiter::middleware::IpKeyExtractor;
+use crate::rate_limiter::{ConnectRateLimit, RateLimitResult, RateLimiter};
+use crate::screening::ConnectionScreening;
+use crate::tls::HostAppBindings;
+use crate::usage_stats::UsageStats;
+use crate::utils::{check_channel_features, is_cache_channel, validate_channel_name};
 use crate::watchlist::WatchlistManager;
 use crate::webhook::integration::WebhookIntegration;
 use crate::websocket::{SocketId, UserInfo, WebSocketRef};
@@ -29,6 +50,8 @@ use hyper_util::rt::TokioIo; // Required for UpgradeFut
 use serde_json::{Value, json};
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
 use tokio::io::WriteHalf; // Required for WebSocketWrite
 use tokio::sync::Mutex;
 use tokio::sync::RwLock;
@@ -37,25 +60,57 @@ use tracing::{error, info, warn};
 pub struct ConnectionHandler {
     pub(crate) app_manager: Arc<dyn AppManager + Send + Sync>,
     pub(crate) channel_manager: Arc<RwLock<ChannelManager>>,
-    pub(crate) connection_manager: Arc<Mutex<Box<dyn Adapter + Send + Sync>>>,
+    pub(crate) connection_manager: Arc<dyn Adapter + Send + Sync>,
     pub(crate) cache_manager: Arc<Mutex<dyn CacheManager + Send + Sync>>,
     pub(crate) metrics: Option<Arc<Mutex<dyn MetricsInterface + Send + Sync>>>,
     pub(crate) webhook_integration: Option<Arc<WebhookIntegration>>,
     pub(crate) http_rate_limiter: Option<Arc<dyn RateLimiter + Send + Sync>>,
-    pub(crate) client_event_limiters: Arc<DashMap<SocketId, Arc<dyn RateLimiter + Send + Sync>>>,
+    pub(crate) client_event_limiters: Arc<DashMap<SocketId, ClientEventLimiter>>,
+    pub(crate) client_event_rate_limit: ClientEventRateLimit,
+    /// Limits WebSocket handshakes; unset when rate limiting is off
+    pub(crate) connect_rate_limit: Option<ConnectRateLimit>,
+    /// Resolves a handshake's client address through trusted proxies
+    pub(crate) ip_extractor: IpKeyExtractor,
     pub(crate) watchlist_manager: Arc<WatchlistManager>,
+    pub(crate) adapter_health: Arc<AdapterHealthStatus>,
+    pub(crate) activity_timeout: ActivityTimeoutConfig,
+    pub(crate) node_registry: Option<Arc<NodeRegistry>>,
+    pub(crate) node_drain: Option<Arc<NodeDrain>>,
+    pub(crate) backoff: Arc<BackoffAdvisor>,
+    pub(crate) connection_quota: Arc<ConnectionQuota>,
+    /// Seconds the last event of a cache channel is kept for new subscribers
+    pub(crate) cache_channel_ttl: u64,
+    /// Bearer token required by the `/admin` endpoints; they are disabled without one
+    pub(crate) admin_token: Option<String>,
+    pub(crate) log_filters: Option<Arc<LogFilters>>,
+    pub(crate) usage_stats: Option<Arc<UsageStats>>,
+    pub(crate) subprotocols: Arc<SubprotocolRegistry>,
+    pub(crate) screening: Option<ConnectionScreening>,
+    pub(crate) channel_reclaimer: Option<Arc<ChannelReclaimer>>,
+    /// Loopback WebSocket probe behind `/probe/ws`; unset when the listener serves TLS
+    pub(crate) ws_probe: Option<Arc<WsProbe>>,
+    /// Apps bound to SNI hostnames, which only accept their own app's clients
+    pub(crate) host_apps: Option<Arc<HostAppBindings>>,
+    /// Tracks client acks of broadcasts to acknowledged channels
+    pub(crate) message_acks: Option<Arc<MessageAcks>>,
+    /// Reloads apps from the config file; only set with the memory app manager
+    pub(crate) app_reloader: Option<Arc<AppReloader>>,
 }
 
 impl ConnectionHandler {
     pub fn new(
         app_manager: Arc<dyn AppManager + Send + Sync>,
         channel_manager: Arc<RwLock<ChannelManager>>,
-        connection_manager: Arc<Mutex<Box<dyn Adapter + Send + Sync>>>,
+        connection_manager: Arc<dyn Adapter + Send + Sync>,
         cache_manager: Arc<Mutex<dyn CacheManager + Send + Sync>>,
         metrics: Option<Arc<Mutex<dyn MetricsInterface + Send + Sync>>>,
         webhook_integration: Option<Arc<WebhookIntegration>>,
         http_rate_limiter: Option<Arc<dyn RateLimiter + Send + Sync>>,
     ) -> Self {
+        let connection_quota = Arc::new(ConnectionQuota::new(
+            connection_manager.clone(),
+            &ConnectionQuotaConfig::default(),
+        ));
         Self {
             app_manager,
             channel_manager,
@@ -65,7 +120,100 @@ impl ConnectionHandler {
             webhook_integration,
             http_rate_limiter,
             client_event_limiters: Arc::new(DashMap::new()),
+            client_event_rate_limit: ClientEventRateLimit::default(),
+            connect_rate_limit: None,
+            ip_extractor: IpKeyExtractor::new(0),
             watchlist_manager: Arc::new(WatchlistManager::new()),
+            adapter_health: Arc::new(AdapterHealthStatus::default()),
+            activity_timeout: ActivityTimeoutConfig::default(),
+            node_registry: None,
+            node_drain: None,
+            backoff: Arc::new(BackoffAdvisor::new(BackoffConfig::default())),
+            connection_quota,
+            cache_channel_ttl: ChannelLimits::default().cache_ttl,
+            admin_token: None,
+            log_filters: None,
+            usage_stats: None,
+            subprotocols: Arc::new(SubprotocolRegistry::default()),
+            screening: None,
+            channel_reclaimer: None,
+            ws_probe: None,
+            host_apps: None,
+            message_acks: None,
+            app_reloader: None,
+        }
+    }
+
+    /// Cache key under which a user's ban is stored
+    pub(crate) fn user_ban_key(app_id: &str, user_id: &str) -> String {
+        format!("app:{}:user:{}:banned", app_id, user_id)
+    }
+
+    /// Ban a user: terminate their connections across the cluster and block
+    /// re-authentication for `ttl_seconds`. Bans are kept in the cache, so
+    /// they need a driver whose entries are not dropped to make room.
+    pub async fn ban_user(
+        &self,
+        app: &App,
+        user_id: &str,
+        ttl_seconds: u64,
+        reason: Option<&str>,
+    ) -> Result<()> {
+        let expires_at = chrono::Utc::now().timestamp() + ttl_seconds as i64;
+        let record = json!({
+            "reason": reason,
+            "expires_at": expires_at,
+        });
+        {
+            let mut cache = self.cache_manager.lock().await;
+            if !cache.is_persistent().await {
+                return Err(Error::BansUnavailable(
+                    "bans need the redis, redis-cluster or sqlite cache driver".to_string(),
+                ));
+            }
+            cache
+                .set(
+                    &Self::user_ban_key(&app.id, user_id),
+                    &record.to_string(),
+                    ttl_seconds,
+                )
+                .await?;
+        }
+
+        self.connection_manager
+            .terminate_user_connections(&app.id, user_id)
+            .await?;
+
+        if let Some(webhook_integration) = &self.webhook_integration {
+            if let Err(e) = webhook_integration
+                .send_user_banned(app, user_id, ttl_seconds, reason)
+                .await
+            {
+                warn!("Failed to send user_banned webhook for {}: {}", user_id, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Check whether a user is currently banned for the given app
+    pub async fn is_user_banned(&self, app_id: &str, user_id: &str) -> bool {
+        let stored = {
+            let mut cache = self.cache_manager.lock().await;
+            cache.get(&Self::user_ban_key(app_id, user_id)).await
+        };
+        match stored {
+            Ok(Some(value)) => {
+                // Not every cache driver enforces per-entry TTLs, so honour the stored expiry too
+                let expires_at = serde_json::from_str::<Value>(&value)
+                    .ok()
+                    .and_then(|record| record.get("expires_at").and_then(Value::as_i64));
+                expires_at.map_or(true, |at| at > chrono::Utc::now().timestamp())
+            }
+            Ok(None) => false,
+            Err(e) => {
+                warn!("Failed to check ban status for user {}: {}", user_id, e);
+                false
+            }
         }
     }
 
@@ -76,8 +224,10 @@ impl ConnectionHandler {
         channel_name: &str,
         user_id: &str,
     ) -> Result<bool> {
-        let mut connection_manager = self.connection_manager.lock().await;
-        let user_sockets = connection_manager.get_user_sockets(user_id, app_id).await?;
+        let user_sockets = self
+            .connection_manager
+            .get_user_sockets(user_id, app_id)
+            .await?;
 
         for ws_ref in user_sockets.iter() {
             let socket_state_guard = ws_ref.0.lock().await;
@@ -123,10 +273,12 @@ impl ConnectionHandler {
 
         match cache_result {
             Ok(Some(cache_content)) => {
-                let cache_message: PusherMessage = serde_json::from_str(&cache_content)?;
+                let mut cache_message: PusherMessage = serde_json::from_str(&cache_content)?;
+                // Entries cached by older versions don't carry their channel
+                cache_message
+                    .channel
+                    .get_or_insert_with(|| channel.to_string());
                 self.connection_manager
-                    .lock()
-                    .await
                     .send_message(app_id, socket_id, cache_message)
                     .await?;
             }
@@ -138,16 +290,18 @@ impl ConnectionHandler {
                     data: None,
                 };
                 self.connection_manager
-                    .lock()
-                    .await
                     .send_message(app_id, socket_id, message)
                     .await?;
 
+                // The client already has its answer; a failed webhook must not fail the subscribe
                 if let Some(app_config) = self.app_manager.find_by_id(app_id).await? {
                     if let Some(webhook_integration_instance) = &self.webhook_integration {
-                        webhook_integration_instance
+                        if let Err(e) = webhook_integration_instance
                             .send_cache_missed(&app_config, channel)
-                            .await?;
+                            .await
+                        {
+                            warn!("Failed to send cache_miss webhook for {}: {}", channel, e);
+                        }
                     }
                 }
                 info!("No missed cache for channel: {}", channel);
@@ -160,6 +314,45 @@ impl ConnectionHandler {
         Ok(())
     }
 
+    /// Send `channel_occupied` (or `channel_vacated`) when this node's
+    /// subscription change occupied (or vacated) the channel cluster-wide.
+    /// The adapter settles which node that is, so each change is sent once.
+    async fn send_channel_existence_webhook(
+        &self,
+        app_config: &App,
+        channel: &str,
+        occupied: bool,
+    ) {
+        let Some(webhook_integration_instance) = &self.webhook_integration else {
+            return;
+        };
+        if !webhook_integration_instance
+            .tracks_channel_existence(app_config)
+            .await
+        {
+            return;
+        }
+        if !self
+            .connection_manager
+            .claim_channel_transition(&app_config.id, channel, occupied)
+            .await
+        {
+            return;
+        }
+        if occupied {
+            webhook_integration_instance
+                .send_channel_occupied(app_config, channel)
+                .await
+                .ok();
+        } else {
+            info!("Sending channel_vacated webhook for channel {}", channel);
+            webhook_integration_instance
+                .send_channel_vacated(app_config, channel)
+                .await
+                .ok();
+        }
+    }
+
     /// Centralized function to send Pusher error and WebSocket close frame.
     /// This is typically used for errors encountered *before* the main message loop starts,
     /// or when the WebSocket write half is directly available.
@@ -199,7 +392,13 @@ impl ConnectionHandler {
         }
     }
 
-    pub async fn handle_socket(&self, fut: upgrade::UpgradeFut, app_key: String) -> Result<()> {
+    pub async fn handle_socket(
+        &self,
+        fut: upgrade::UpgradeFut,
+        app_key: String,
+        remote_ip: Option<String>,
+        tags: Vec<String>,
+    ) -> Result<()> {
         let app_config_option = self.app_manager.find_by_key(&app_key).await;
 
         // Perform upgrade and handle early errors by sending Pusher error and closing.
@@ -245,15 +444,13 @@ impl ConnectionHandler {
 
         let max_connections = app_config.max_connections;
         if max_connections > 0 {
-            let current_connections_result = self
-                .connection_manager
-                .lock()
-                .await
-                .get_sockets_count(&app_config.id)
+            let quota_result = self
+                .connection_quota
+                .admits(&app_config.id, max_connections)
                 .await;
 
-            match current_connections_result {
-                Ok(count) if count >= max_connections as usize => {
+            match quota_result {
+                Ok(false) => {
                     ConnectionHandler::send_error_and_close_ws(
                         &mut socket_tx_direct,
                         &Error::OverConnectionQuota,
@@ -283,22 +480,24 @@ impl ConnectionHandler {
         let socket_id = SocketId::new();
         info!("New socket: {} for app: {}", socket_id, app_config.id);
 
+        let mut connection_stats = None;
         {
-            let mut connection_manager_locked = self.connection_manager.lock().await;
             // It's unlikely a duplicate socket_id exists here, but good practice.
-            if let Some(conn) = connection_manager_locked
+            if let Some(conn) = self
+                .connection_manager
                 .get_connection(&socket_id, &app_config.id)
                 .await
             {
                 // This cleanup might send messages; ensure it's safe if the socket isn't fully "active" yet.
                 // Consider if cleanup should only do resource release here.
-                connection_manager_locked
+                self.connection_manager
                     .cleanup_connection(&app_config.id, WebSocketRef(conn))
                     .await;
             }
             // Add the socket (tx part) to the connection manager.
             // The `socket_tx_direct` is consumed here.
-            if let Err(e) = connection_manager_locked
+            if let Err(e) = self
+                .connection_manager
                 .add_socket(
                     socket_id.clone(),
                     socket_tx_direct, // Pass the direct write half
@@ -320,6 +519,17 @@ impl ConnectionHandler {
                 return Err(e); // Propagate the error.
             }
 
+            if let Some(conn) = self
+                .connection_manager
+                .get_connection(&socket_id, &app_config.id)
+                .await
+            {
+                let mut conn = conn.lock().await;
+                conn.state.remote_ip = remote_ip;
+                conn.state.tags = tags;
+                connection_stats = Some(conn.stats.clone());
+            }
+
             if let Some(ref metrics) = self.metrics {
                 let metrics_locked = metrics.lock().await;
                 metrics_locked.mark_new_connection(&app_config.id, &socket_id)
@@ -327,10 +537,10 @@ impl ConnectionHandler {
         }
 
         if app_config.max_client_events_per_second > 0 {
-            let limiter = Arc::new(MemoryRateLimiter::new(
+            let limiter = ClientEventLimiter::new(
                 app_config.max_client_events_per_second,
-                1, // Per second
-            ));
+                &self.client_event_rate_limit,
+            );
             self.client_event_limiters
                 .insert(socket_id.clone(), limiter);
             info!(
@@ -340,8 +550,9 @@ impl ConnectionHandler {
         }
 
         // Send pusher:connection_established
+        let activity_timeout = self.activity_timeout.for_app(&app_config);
         if let Err(e) = self
-            .send_connection_established(&app_config.id, &socket_id)
+            .send_connection_established(&app_config.id, &socket_id, activity_timeout)
             .await
         {
             // Failed to send connection_established. This is a server-side issue or socket closed prematurely.
@@ -356,8 +567,8 @@ impl ConnectionHandler {
                 });
 
             // Then, explicitly close the WebSocket via the manager.
-            let mut connection_manager_locked = self.connection_manager.lock().await;
-            if let Some(conn_arc) = connection_manager_locked
+            if let Some(conn_arc) = self
+                .connection_manager
                 .get_connection(&socket_id, &app_config.id)
                 .await
             {
@@ -370,7 +581,6 @@ impl ConnectionHandler {
                 }
                 // Adapter's remove_connection will be called by handle_disconnect later if needed.
             }
-            drop(connection_manager_locked);
 
             // Perform full disconnect cleanup
             if let Err(disconnect_err) = self.handle_disconnect(&app_config.id, &socket_id).await {
@@ -383,13 +593,43 @@ impl ConnectionHandler {
             return Ok(()); // Error handled by closing the connection.
         }
 
-        // Main message loop using the read half
+        // Main message loop using the read half. A connection silent for
+        // `activity_timeout` seconds is pinged, and closed if nothing arrives
+        // within the pong timeout.
         let mut fragment_collector = FragmentCollectorRead::new(socket_rx_frag);
+        let mut activity = ActivityWatch::new(
+            Duration::from_secs(activity_timeout),
+            Duration::from_secs(self.activity_timeout.pong_timeout_seconds),
+        );
 
-        while let Ok(frame) = fragment_collector
-            .read_frame(&mut move |_| async { Ok::<_, WebSocketError>(()) })
+        loop {
+            let frame = match tokio::time::timeout(
+                activity.next_wait(),
+                fragment_collector.read_frame(&mut move |_| async { Ok::<_, WebSocketError>(()) }),
+            )
             .await
-        {
+            {
+                Ok(Ok(frame)) => frame,
+                Ok(Err(_)) => break,
+                Err(_) => match activity.timed_out() {
+                    IdleAction::Ping => {
+                        if let Err(e) = self
+                            .connection_manager
+                            .send_message(&app_config.id, &socket_id, PusherMessage::ping())
+                            .await
+                        {
+                            warn!("Failed to ping idle socket {}: {}", socket_id, e);
+                        }
+                        continue;
+                    }
+                    IdleAction::Close => {
+                        self.close_inactive_connection(&app_config.id, &socket_id)
+                            .await;
+                        break;
+                    }
+                },
+            };
+            activity.frame_received();
             match frame.opcode {
                 OpCode::Close => {
                     info!("Received Close frame from socket {}", socket_id);
@@ -407,6 +647,9 @@ impl ConnectionHandler {
                     break; // Exit message loop
                 }
                 OpCode::Text | OpCode::Binary => {
+                    if let Some(stats) = &connection_stats {
+                        stats.messages_received.fetch_add(1, Ordering::Relaxed);
+                    }
                     if let Err(e) = self
                         .handle_message(frame, &socket_id, app_config.clone())
                         .await
@@ -423,8 +666,8 @@ impl ConnectionHandler {
                 }
                 OpCode::Ping => {
                     // Respond with Pong or update last ping time
-                    let mut connection_manager_locked = self.connection_manager.lock().await;
-                    if let Some(conn_arc) = connection_manager_locked
+                    if let Some(conn_arc) = self
+                        .connection_manager
                         .get_connection(&socket_id, &app_config.id)
                         .await
                     {
@@ -443,13 +686,6 @@ impl ConnectionHandler {
             }
         }
 
-        // TODO: Add a timeout mechanism here for inactivity if needed,
-        // which would then send Error::InactivityTimeout (4202)
-        // _ = tokio::time::sleep(Duration::from_secs(app_config.activity_timeout_seconds.unwrap_or(120))) => {
-        //     info!("Socket {} inactive, sending ping or closing.", socket_id);
-        //     // Implement ping logic or close with InactivityTimeout
-        // }
-
         // Loop exited, ensure rate limiter is cleaned up if it was added
         self.client_event_limiters.remove(&socket_id);
         info!("Message loop terminated for socket {}", socket_id);
@@ -478,23 +714,43 @@ impl ConnectionHandler {
 
         // Client Event Rate Limiting
         if event_name_str.starts_with(CLIENT_EVENT_PREFIX) {
-            if let Some(limiter_arc) = self.client_event_limiters.get(socket_id) {
-                let limiter = limiter_arc.value(); // Get a reference to the Arc<dyn RateLimiter>
-                let limit_result = limiter.increment(socket_id.as_ref()).await?;
-                if !limit_result.allowed {
+            // The verdict is taken before any await, so the map entry isn't
+            // held across one
+            let verdict = self
+                .client_event_limiters
+                .get_mut(socket_id)
+                .map(|mut limiter| limiter.check());
+            if let Some(verdict) = verdict {
+                let (limit_result, disconnect) = match verdict {
+                    ClientEventVerdict::Allowed => (None, false),
+                    ClientEventVerdict::Rejected(result) => (Some(result), false),
+                    ClientEventVerdict::Disconnect(result) => (Some(result), true),
+                };
+                if let Some(limit_result) = limit_result {
                     warn!(
                         "Client event rate limit exceeded for socket {}: event '{}'",
                         socket_id, event_name_str
                     );
-                    // Send pusher:error JSON message (non-fatal for client event rate limit)
-                    self.send_error(
+                    // The event is dropped with a pusher:error (4301), and the
+                    // connection carries on unless it keeps offending
+                    self.send_rate_limit_error(
                         &app_config.id,
                         socket_id,
-                        &Error::ClientEventRateLimit, // This error (4301) is typically not fatal
+                        &Error::ClientEventRateLimit,
                         channel_name_option.clone(),
+                        &limit_result,
                     )
                     .await?;
-                    return Err(Error::ClientEventRateLimit); // Return error, but loop might continue
+                    if !disconnect {
+                        return Ok(());
+                    }
+                    warn!(
+                        "Closing socket {} of app {} for repeatedly exceeding the client event rate limit",
+                        socket_id, app_config.id
+                    );
+                    self.close_rate_limited_connection(&app_config.id, socket_id)
+                        .await;
+                    return Err(Error::ClientEventRateLimit);
                 }
             } else if app_config.max_client_events_per_second > 0 {
                 // This case indicates a server logic error if a limiter was expected but not found.
@@ -515,6 +771,8 @@ impl ConnectionHandler {
         // Process the message based on event type
         let processing_result = match event_name_str {
             "pusher:ping" => self.handle_ping(&app_config.id, socket_id).await,
+            // Answers a server ping; receiving it already counted as activity
+            "pusher:pong" => Ok(()),
             "pusher:subscribe" => {
                 self.handle_subscribe(socket_id, &app_config, &message)
                     .await
@@ -527,6 +785,7 @@ impl ConnectionHandler {
                 self.handle_signin(socket_id, message.clone(), &app_config) // Clone message if needed by signin
                     .await
             }
+            ACK_EVENT => self.handle_ack(socket_id, &message),
             _ if event_name_str.starts_with(CLIENT_EVENT_PREFIX) => {
                 self.handle_client_event(
                     &app_config,
@@ -566,8 +825,8 @@ impl ConnectionHandler {
                     "Fatal error encountered for socket {}: {}. Closing connection.",
                     socket_id, e
                 );
-                let mut connection_manager_locked = self.connection_manager.lock().await;
-                if let Some(conn_arc) = connection_manager_locked
+                if let Some(conn_arc) = self
+                    .connection_manager
                     .get_connection(socket_id, &app_config.id)
                     .await
                 {
@@ -584,7 +843,6 @@ impl ConnectionHandler {
                         socket_id
                     );
                 }
-                drop(connection_manager_locked);
 
                 // Perform full server-side cleanup for the disconnected socket.
                 if let Err(disconnect_err) = self.handle_disconnect(&app_config.id, socket_id).await
@@ -611,12 +869,36 @@ impl ConnectionHandler {
 
     pub async fn handle_ping(&self, app_id: &str, socket_id: &SocketId) -> Result<()> {
         self.connection_manager
-            .lock()
-            .await
             .send_message(app_id, socket_id, PusherMessage::pong())
             .await
     }
 
+    /// Record a client's `sockudo:ack`, naming a channel (in its data or on
+    /// the message) and the highest sequence it has processed there
+    fn handle_ack(&self, socket_id: &SocketId, message: &PusherMessage) -> Result<()> {
+        let Some(acks) = &self.message_acks else {
+            return Ok(());
+        };
+        let (channel, sequence) = match &message.data {
+            Some(MessageData::Structured { channel, extra, .. }) => (
+                channel.as_deref(),
+                extra.get("sequence").and_then(Value::as_u64),
+            ),
+            Some(MessageData::Json(data_val)) => (
+                data_val.get("channel").and_then(Value::as_str),
+                data_val.get("sequence").and_then(Value::as_u64),
+            ),
+            _ => (None, None),
+        };
+        let channel = channel
+            .or(message.channel.as_deref())
+            .ok_or_else(|| Error::InvalidMessageFormat("Missing channel in ack message".into()))?;
+        let sequence = sequence
+            .ok_or_else(|| Error::InvalidMessageFormat("Missing sequence in ack message".into()))?;
+        acks.ack(socket_id, channel, sequence);
+        Ok(())
+    }
+
     fn extract_signature(&self, message: &PusherMessage) -> Result<String> {
         match &message.data {
             Some(MessageData::String(_)) => {
@@ -647,6 +929,30 @@ impl ConnectionHandler {
         }
     }
 
+    /// Tell the client a subscription was refused for an app limit, with a
+    /// `pusher:subscription_error` of type `LimitReached` as Pusher sends
+    async fn refuse_subscription(
+        &self,
+        app_id: &str,
+        socket_id: &SocketId,
+        channel: &str,
+        error: Error,
+    ) -> Result<()> {
+        warn!(
+            "Refused subscription of socket {} to {}: {}",
+            socket_id, channel, error
+        );
+        let refusal = PusherMessage::subscription_error(
+            channel.to_string(),
+            "LimitReached",
+            error.to_string(),
+            error.close_code(),
+        );
+        self.connection_manager
+            .send_message(app_id, socket_id, refusal)
+            .await
+    }
+
     pub async fn handle_subscribe(
         &self,
         socket_id: &SocketId,
@@ -678,6 +984,32 @@ impl ConnectionHandler {
         }
 
         validate_channel_name(app_config, channel_str).await?;
+        check_channel_features(app_config, channel_str)?;
+
+        if let Some(max_channels) = app_config.max_channels_per_connection {
+            let subscribed_elsewhere = match self
+                .connection_manager
+                .get_connection(socket_id, &app_config.id)
+                .await
+            {
+                Some(conn_arc) => {
+                    let conn_locked = conn_arc.lock().await;
+                    // Subscribing again to a channel doesn't take another slot
+                    if conn_locked.state.is_subscribed(channel_str) {
+                        0
+                    } else {
+                        conn_locked.state.subscribed_channels.len()
+                    }
+                }
+                None => 0,
+            };
+            if subscribed_elsewhere >= max_channels as usize {
+                let error = Error::ChannelsPerConnectionLimit(max_channels);
+                return self
+                    .refuse_subscription(&app_config.id, socket_id, channel_str, error)
+                    .await;
+            }
+        }
 
         let is_authenticated = {
             let channel_manager_locked = self.channel_manager.read().await;
@@ -722,64 +1054,88 @@ impl ConnectionHandler {
             return Err(Error::AuthError("Invalid authentication signature".into()));
         }
 
+        // Joins and leaves of this channel wait for each other, so capacity
+        // checks and member_added see a settled membership
+        let _channel_guard = self
+            .channel_manager
+            .read()
+            .await
+            .lock_channel(&app_config.id, channel_str)
+            .await;
+
+        // Members fetched for the capacity check, reused for the
+        // subscription_succeeded payload so the cluster is only asked once
+        let mut known_members: Option<HashMap<String, PresenceMemberInfo>> = None;
+
         // Presence channel specific validations (member size, channel capacity)
         if channel_str.starts_with("presence-") {
             let user_info_from_data = match &message.data {
-                Some(MessageData::Structured { channel_data, .. }) => {
-                    Some(channel_data.as_ref().unwrap().as_str())
+                Some(MessageData::Structured { channel_data, .. }) => channel_data.as_deref(),
+                Some(MessageData::Json(json_data)) => {
+                    json_data.get("channel_data").and_then(Value::as_str)
                 }
-                Some(MessageData::Json(json_data)) => Some(
-                    json_data
-                        .get("channel_data")
-                        .and_then(Value::as_str)
-                        .unwrap(),
-                ),
                 _ => None,
             };
 
-            if let Some(cd_str) = user_info_from_data {
-                let user_info_payload: Value = serde_json::from_str(cd_str).map_err(|_| {
-                    Error::InvalidMessageFormat("Invalid channel_data JSON for presence".into())
-                })?;
-                let user_info_for_size_calc = user_info_payload
-                    .get("user_info")
-                    .cloned()
-                    .unwrap_or_default();
-                let user_info_size_kb =
-                    utils::data_to_bytes_flexible(vec![user_info_for_size_calc]) / 1024;
-
-                if let Some(max_size) = app_config.max_presence_member_size_in_kb {
-                    if user_info_size_kb > max_size as usize {
-                        return Err(Error::ChannelError(format!(
-                            // This error should map to a 43xx code
-                            "Presence member data size ({}KB) exceeds limit ({}KB)",
-                            user_info_size_kb, max_size
-                        )));
-                    }
-                }
-            } else {
+            let Some(cd_str) = user_info_from_data else {
                 // If channel_data is missing for presence, it's an issue.
                 return Err(Error::InvalidMessageFormat(
                     "Missing 'channel_data' for presence channel subscription.".into(),
                 ));
+            };
+            let user_info_payload: Value = serde_json::from_str(cd_str).map_err(|_| {
+                Error::InvalidMessageFormat("Invalid channel_data JSON for presence".into())
+            })?;
+            let user_info_for_size_calc = user_info_payload
+                .get("user_info")
+                .cloned()
+                .unwrap_or_default();
+            let user_info_bytes = utils::data_to_bytes_flexible(vec![user_info_for_size_calc]);
+
+            if let Some(max_size) = app_config.max_presence_member_size_in_kb {
+                if user_info_bytes > max_size as usize * 1024 {
+                    let error = Error::PresenceMemberTooLarge {
+                        size_kb: user_info_bytes.div_ceil(1024),
+                        max_kb: max_size,
+                    };
+                    return self
+                        .refuse_subscription(&app_config.id, socket_id, channel_str, error)
+                        .await;
+                }
+            }
+
+            let presence_user_id = match user_info_payload.get("user_id") {
+                Some(Value::String(id)) => Some(id.clone()),
+                Some(Value::Number(id)) => Some(id.to_string()),
+                _ => None,
+            };
+            if let Some(presence_user_id) = &presence_user_id {
+                if self.is_user_banned(&app_config.id, presence_user_id).await {
+                    return Err(Error::AuthError("User is banned".into()));
+                }
             }
 
             if let Some(max_members) = app_config.max_presence_members_per_channel {
                 let current_members = self
                     .connection_manager
-                    .lock()
-                    .await
                     .get_channel_members(&app_config.id, channel_str) // Assuming this gets count across nodes if applicable
-                    .await?
-                    .len();
-                if current_members >= max_members as usize {
-                    return Err(Error::OverCapacity); // Pusher code 4100
+                    .await?;
+                // Another connection of a member doesn't add a member
+                let already_member = presence_user_id
+                    .as_ref()
+                    .is_some_and(|user_id| current_members.contains_key(user_id));
+                if !already_member && current_members.len() >= max_members as usize {
+                    let error = Error::PresenceMembersLimit(max_members);
+                    return self
+                        .refuse_subscription(&app_config.id, socket_id, channel_str, error)
+                        .await;
                 }
+                known_members = Some(current_members);
             }
         }
 
         let subscription_result = {
-            let mut channel_manager_locked = self.channel_manager.write().await;
+            let channel_manager_locked = self.channel_manager.read().await;
             channel_manager_locked
                 .subscribe(
                     socket_id.0.as_str(),
@@ -801,21 +1157,17 @@ impl ConnectionHandler {
             ));
         }
 
-        if subscription_result.channel_connections == Some(1) {
-            if let Some(webhook_integration_instance) = &self.webhook_integration {
-                webhook_integration_instance
-                    .send_channel_occupied(app_config, channel_str)
-                    .await
-                    .ok();
-            }
+        if let Some(reclaimer) = &self.channel_reclaimer {
+            reclaimer.mark_occupied(&app_config.id, channel_str);
         }
 
+        self.send_channel_existence_webhook(app_config, channel_str, true)
+            .await;
+
         if !channel_str.starts_with("presence-") {
             if let Some(webhook_integration_instance) = &self.webhook_integration {
                 let current_count = self
                     .connection_manager
-                    .lock()
-                    .await
                     .get_channel_socket_count(&app_config.id, channel_str)
                     .await;
                 info!(
@@ -845,8 +1197,8 @@ impl ConnectionHandler {
         };
 
         {
-            let mut connection_manager_locked = self.connection_manager.lock().await;
-            if let Some(conn_arc) = connection_manager_locked
+            if let Some(conn_arc) = self
+                .connection_manager
                 .get_connection(socket_id, &app_config.id)
                 .await
             {
@@ -885,16 +1237,23 @@ impl ConnectionHandler {
                 }
 
                 let members_map = {
-                    let mut connection_manager_locked = self.connection_manager.lock().await;
-                    let current_members = connection_manager_locked
-                        .get_channel_members(&app_config.id, channel_str)
-                        .await?;
+                    let current_members = match known_members {
+                        Some(mut members) => {
+                            members.insert(user_id_str.clone(), presence_info_val.clone());
+                            members
+                        }
+                        None => {
+                            self.connection_manager
+                                .get_channel_members(&app_config.id, channel_str)
+                                .await?
+                        }
+                    };
                     let member_added_msg = PusherMessage::member_added(
                         channel_str.to_string(),
                         user_id_str.clone(),
                         presence_info_val.user_info.clone(),
                     );
-                    connection_manager_locked
+                    self.connection_manager
                         .send(
                             channel_str,
                             member_added_msg,
@@ -919,16 +1278,12 @@ impl ConnectionHandler {
                     Some(presence_message_val),
                 );
                 self.connection_manager
-                    .lock()
-                    .await
                     .send_message(&app_config.id, socket_id, subscription_succeeded_msg)
                     .await?;
             }
         } else {
             let response_msg = PusherMessage::subscription_succeeded(channel_str.to_string(), None);
             self.connection_manager
-                .lock()
-                .await
                 .send_message(&app_config.id, socket_id, response_msg)
                 .await?;
         }
@@ -963,9 +1318,19 @@ impl ConnectionHandler {
             }
         };
 
+        let _channel_guard = self
+            .channel_manager
+            .read()
+            .await
+            .lock_channel(&app_config.id, channel_name_str)
+            .await;
+
         let user_id_of_socket: Option<String> = {
-            let mut conn_manager = self.connection_manager.lock().await;
-            if let Some(conn) = conn_manager.get_connection(socket_id, &app_config.id).await {
+            if let Some(conn) = self
+                .connection_manager
+                .get_connection(socket_id, &app_config.id)
+                .await
+            {
                 conn.lock().await.state.user_id.clone()
             } else {
                 None
@@ -973,7 +1338,7 @@ impl ConnectionHandler {
         };
 
         let _leave_response = {
-            let mut channel_manager_locked = self.channel_manager.write().await;
+            let channel_manager_locked = self.channel_manager.read().await;
             channel_manager_locked
                 .unsubscribe(
                     socket_id.0.as_str(),
@@ -983,10 +1348,16 @@ impl ConnectionHandler {
                 )
                 .await? // Propagate errors
         };
+        if let Some(acks) = &self.message_acks {
+            acks.forget_channel(socket_id, channel_name_str);
+        }
 
         {
-            let mut conn_manager = self.connection_manager.lock().await;
-            if let Some(conn_arc) = conn_manager.get_connection(socket_id, &app_config.id).await {
+            if let Some(conn_arc) = self
+                .connection_manager
+                .get_connection(socket_id, &app_config.id)
+                .await
+            {
                 let mut conn_state_guard = conn_arc.lock().await;
                 conn_state_guard
                     .state
@@ -1002,8 +1373,6 @@ impl ConnectionHandler {
 
         let current_sub_count = self
             .connection_manager
-            .lock()
-            .await
             .get_channel_socket_count(&app_config.id, channel_name_str)
             .await;
 
@@ -1033,8 +1402,6 @@ impl ConnectionHandler {
                         user_id_that_left.clone(),
                     );
                     self.connection_manager
-                        .lock()
-                        .await
                         .send(
                             channel_name_str,
                             member_removed_msg,
@@ -1056,17 +1423,12 @@ impl ConnectionHandler {
         }
 
         if current_sub_count == 0 {
-            if let Some(webhook_integration_instance) = &self.webhook_integration {
-                info!(
-                    "Sending channel_vacated webhook for channel {}",
-                    channel_name_str
-                );
-                webhook_integration_instance
-                    .send_channel_vacated(app_config, channel_name_str)
-                    .await
-                    .ok();
+            if let Some(reclaimer) = &self.channel_reclaimer {
+                reclaimer.mark_vacated(&app_config.id, channel_name_str);
             }
         }
+        self.send_channel_existence_webhook(app_config, channel_name_str, false)
+            .await;
         Ok(())
     }
 
@@ -1126,6 +1488,10 @@ impl ConnectionHandler {
             .ok_or_else(|| Error::AuthError("Missing 'id' field in user_data".into()))?
             .to_string();
 
+        if self.is_user_banned(&app_config.id, &user_id).await {
+            return Err(Error::AuthError("User is banned".into()));
+        }
+
         // Extract watchlist (optional)
         let watchlist = user_info_val
             .get("watchlist")
@@ -1149,8 +1515,8 @@ impl ConnectionHandler {
         }
 
         // Get connection and update user state
-        let mut connection_manager_locked = self.connection_manager.lock().await;
-        let connection_arc = connection_manager_locked
+        let connection_arc = self
+            .connection_manager
             .get_connection(socket_id, &app_config.id)
             .await
             .ok_or_else(|| Error::ConnectionNotFound)?;
@@ -1179,7 +1545,7 @@ impl ConnectionHandler {
         };
 
         if let Some(socket_tx_val) = temp_socket_tx {
-            connection_manager_locked
+            self.connection_manager
                 .add_socket(
                     socket_id.clone(),
                     socket_tx_val,
@@ -1198,12 +1564,10 @@ impl ConnectionHandler {
         }
 
         // Add user to adapter's user tracking
-        connection_manager_locked
+        self.connection_manager
             .add_user(connection_arc.clone())
             .await?;
 
-        drop(connection_manager_locked); // Release the lock before watchlist operations
-
         // Handle watchlist functionality if enabled
         let mut watchlist_events_for_user = Vec::new();
         let mut watchers_to_notify = Vec::new();
@@ -1251,8 +1615,6 @@ impl ConnectionHandler {
         };
 
         self.connection_manager
-            .lock()
-            .await
             .send_message(&app_config.id, socket_id, success_message_val)
             .await?;
 
@@ -1260,8 +1622,6 @@ impl ConnectionHandler {
         for event in watchlist_events_for_user {
             if let Err(e) = self
                 .connection_manager
-                .lock()
-                .await
                 .send_message(&app_config.id, socket_id, event)
                 .await
             {
@@ -1283,8 +1643,6 @@ impl ConnectionHandler {
             for watcher_socket_id in watchers_to_notify {
                 if let Err(e) = self
                     .connection_manager
-                    .lock()
-                    .await
                     .send_message(&app_config.id, &watcher_socket_id, online_event.clone())
                     .await
                 {
@@ -1374,12 +1732,13 @@ impl ConnectionHandler {
 
         // Verify socket is subscribed to the channel
         let (is_subscribed_globally, user_id_for_webhook) = {
-            let mut conn_manager_locked = self.connection_manager.lock().await;
-            let subscribed = conn_manager_locked
+            let subscribed = self
+                .connection_manager
                 .is_in_channel(&app_config.id, channel_name, socket_id)
                 .await?;
 
-            let user_id = if let Some(conn_arc) = conn_manager_locked
+            let user_id = if let Some(conn_arc) = self
+                .connection_manager
                 .get_connection(socket_id, &app_config.id)
                 .await
             {
@@ -1414,8 +1773,7 @@ impl ConnectionHandler {
 
         // Send the message via the adapter (broadcasts to channel, excluding sender)
         {
-            let mut conn_manager_locked = self.connection_manager.lock().await;
-            conn_manager_locked
+            self.connection_manager
                 .send(
                     channel_name,
                     message_to_send.clone(),
@@ -1424,6 +1782,9 @@ impl ConnectionHandler {
                 )
                 .await?;
         }
+        if let Some(usage_stats) = &self.usage_stats {
+            usage_stats.record_message(&app_config.id, channel_name);
+        }
 
         // Send webhook if configured
         if let Some(webhook_integration_val) = &self.webhook_integration {
@@ -1466,28 +1827,128 @@ impl ConnectionHandler {
             message: error.to_string(),
             code: Some(error.close_code()),
         };
-        let error_message =
-            PusherMessage::error(error_data.code.unwrap_or(4000), error_data.message, channel);
+        let code = error_data.code.unwrap_or(4000);
+        let error_message = if error.should_back_off() {
+            let retry_in_ms = self.backoff.advise(&socket_id.0);
+            PusherMessage::error_with_backoff(code, error_data.message, channel, retry_in_ms)
+        } else {
+            PusherMessage::error(code, error_data.message, channel)
+        };
         self.connection_manager
-            .lock()
-            .await
             .send_message(app_id, socket_id, error_message) // This uses the adapter's send_message
             .await
     }
 
-    pub async fn send_connection_established(
+    /// Like `send_error`, but includes the limit, remaining budget and reset time
+    /// so the client can back off until the window resets.
+    pub async fn send_rate_limit_error(
         &self,
         app_id: &str,
         socket_id: &SocketId,
+        error: &Error,
+        channel: Option<String>,
+        limit_result: &RateLimitResult,
     ) -> Result<()> {
-        let connection_message = PusherMessage::connection_established(socket_id.0.clone());
+        let error_message = PusherMessage::rate_limit_error(
+            error.close_code(),
+            error.to_string(),
+            channel,
+            limit_result.limit,
+            limit_result.remaining,
+            limit_result.reset_after,
+        );
         self.connection_manager
-            .lock()
+            .send_message(app_id, socket_id, error_message)
             .await
+    }
+
+    pub async fn send_connection_established(
+        &self,
+        app_id: &str,
+        socket_id: &SocketId,
+        activity_timeout: u64,
+    ) -> Result<()> {
+        let connection_message =
+            PusherMessage::connection_established(socket_id.0.clone(), activity_timeout);
+        self.connection_manager
             .send_message(app_id, socket_id, connection_message)
             .await
     }
 
+    /// Close a connection that did not answer a server ping, with the Pusher
+    /// inactivity error (4202)
+    async fn close_inactive_connection(&self, app_id: &str, socket_id: &SocketId) {
+        warn!(
+            "Socket {} exceeded its activity timeout, closing",
+            socket_id
+        );
+        let error = Error::InactivityTimeout;
+        if let Err(e) = self.send_error(app_id, socket_id, &error, None).await {
+            warn!(
+                "Failed to send inactivity error to socket {}: {}",
+                socket_id, e
+            );
+        }
+        if let Some(conn_arc) = self
+            .connection_manager
+            .get_connection(socket_id, app_id)
+            .await
+        {
+            let mut conn_locked = conn_arc.lock().await;
+            if let Err(e) = conn_locked
+                .close(error.close_code(), error.to_string())
+                .await
+            {
+                warn!(
+                    "Failed to send WebSocket close frame to inactive socket {}: {}",
+                    socket_id, e
+                );
+            }
+        }
+        if let Some(ref metrics) = self.metrics {
+            let metrics_locked = metrics.lock().await;
+            metrics_locked.mark_disconnection(app_id, socket_id);
+        }
+        if let Err(e) = self.handle_disconnect(app_id, socket_id).await {
+            error!(
+                "Error during disconnect of inactive socket {}: {}",
+                socket_id, e
+            );
+        }
+    }
+
+    /// Close a connection that kept sending client events over its limit.
+    /// It has already been sent the `pusher:error`.
+    async fn close_rate_limited_connection(&self, app_id: &str, socket_id: &SocketId) {
+        let error = Error::ClientEventRateLimit;
+        if let Some(conn_arc) = self
+            .connection_manager
+            .get_connection(socket_id, app_id)
+            .await
+        {
+            let mut conn_locked = conn_arc.lock().await;
+            if let Err(e) = conn_locked
+                .close(error.close_code(), error.to_string())
+                .await
+            {
+                warn!(
+                    "Failed to send WebSocket close frame to rate limited socket {}: {}",
+                    socket_id, e
+                );
+            }
+        }
+        if let Some(ref metrics) = self.metrics {
+            let metrics_locked = metrics.lock().await;
+            metrics_locked.mark_disconnection(app_id, socket_id);
+        }
+        if let Err(e) = self.handle_disconnect(app_id, socket_id).await {
+            error!(
+                "Error during disconnect of rate limited socket {}: {}",
+                socket_id, e
+            );
+        }
+    }
+
     pub async fn handle_disconnect(&self, app_id: &str, socket_id: &SocketId) -> Result<()> {
         info!("Handling disconnect for socket: {}", socket_id);
 
@@ -1498,6 +1959,9 @@ impl ConnectionHandler {
                 socket_id
             );
         }
+        if let Some(acks) = &self.message_acks {
+            acks.forget_socket(socket_id);
+        }
 
         // Get app configuration
         let app_config = match self.app_manager.find_by_id(app_id).await? {
@@ -1505,22 +1969,27 @@ impl ConnectionHandler {
             None => {
                 error!("App not found during disconnect: {}", app_id);
                 // Attempt cleanup even if app is gone
-                let mut conn_manager = self.connection_manager.lock().await;
-                if let Some(conn_to_cleanup) = conn_manager.get_connection(socket_id, app_id).await
+                if let Some(conn_to_cleanup) = self
+                    .connection_manager
+                    .get_connection(socket_id, app_id)
+                    .await
                 {
-                    conn_manager
+                    self.connection_manager
                         .cleanup_connection(app_id, WebSocketRef(conn_to_cleanup))
                         .await;
                 }
-                conn_manager.remove_connection(socket_id, app_id).await.ok();
+                self.connection_manager
+                    .remove_connection(socket_id, app_id)
+                    .await
+                    .ok();
                 return Err(Error::ApplicationNotFound);
             }
         };
 
         // Extract connection state before cleanup
         let (subscribed_channels_set, user_id_of_disconnected_socket, user_watchlist) = {
-            let mut connection_manager_locked = self.connection_manager.lock().await;
-            match connection_manager_locked
+            match self
+                .connection_manager
                 .get_connection(socket_id, app_id)
                 .await
             {
@@ -1565,13 +2034,16 @@ impl ConnectionHandler {
                 socket_id
             );
 
-            let channel_manager_locked = self.channel_manager.write().await;
+            let channel_manager_locked = self.channel_manager.read().await;
 
             for channel_str in &subscribed_channels_set {
                 info!(
                     "Processing channel {} for disconnect of socket {}",
                     channel_str, socket_id
                 );
+                let _channel_guard = channel_manager_locked
+                    .lock_channel(app_id, channel_str)
+                    .await;
 
                 match channel_manager_locked
                     .unsubscribe(
@@ -1585,8 +2057,6 @@ impl ConnectionHandler {
                     Ok(_leave_response) => {
                         let current_sub_count_after_cm_unsubscribe = self
                             .connection_manager
-                            .lock()
-                            .await
                             .get_channel_socket_count(app_id, channel_str)
                             .await;
 
@@ -1626,8 +2096,6 @@ impl ConnectionHandler {
                                         disconnected_user_id.clone(),
                                     );
                                     self.connection_manager
-                                        .lock()
-                                        .await
                                         .send(
                                             channel_str,
                                             member_removed_msg,
@@ -1656,19 +2124,14 @@ impl ConnectionHandler {
                             }
                         }
 
-                        // Send channel_vacated webhook if no subscribers left
                         if current_sub_count_after_cm_unsubscribe == 0 {
-                            if let Some(webhook_integration_instance) = &self.webhook_integration {
-                                info!(
-                                    "Sending channel_vacated webhook for channel {}",
-                                    channel_str
-                                );
-                                webhook_integration_instance
-                                    .send_channel_vacated(&app_config, channel_str)
-                                    .await
-                                    .ok();
+                            if let Some(reclaimer) = &self.channel_reclaimer {
+                                reclaimer.mark_vacated(&app_config.id, channel_str);
                             }
                         }
+                        // Send channel_vacated webhook if no subscribers are left anywhere
+                        self.send_channel_existence_webhook(&app_config, channel_str, false)
+                            .await;
                     }
                     Err(e) => {
                         error!(
@@ -1715,8 +2178,6 @@ impl ConnectionHandler {
                         for watcher_socket_id in &watchers_to_notify {
                             match self
                                 .connection_manager
-                                .lock()
-                                .await
                                 .send_message(app_id, watcher_socket_id, event.clone())
                                 .await
                             {
@@ -1743,20 +2204,19 @@ impl ConnectionHandler {
 
         // Final cleanup from connection manager
         {
-            let mut connection_manager_locked = self.connection_manager.lock().await;
-
             // Cleanup connection resources
-            if let Some(conn_to_cleanup) = connection_manager_locked
+            if let Some(conn_to_cleanup) = self
+                .connection_manager
                 .get_connection(socket_id, app_id)
                 .await
             {
-                connection_manager_locked
+                self.connection_manager
                     .cleanup_connection(app_id, WebSocketRef(conn_to_cleanup))
                     .await;
             }
 
             // Remove connection from primary tracking
-            connection_manager_locked
+            self.connection_manager
                 .remove_connection(socket_id, app_id)
                 .await
                 .ok();
@@ -1791,8 +2251,6 @@ impl ConnectionHandler {
     pub async fn channel(&self, app_id: &str, channel_name: &str) -> Value {
         let socket_count_val = self
             .connection_manager
-            .lock()
-            .await
             .get_channel_socket_count(app_id, channel_name)
             .await;
         json!({
@@ -1802,8 +2260,8 @@ impl ConnectionHandler {
     }
 
     pub async fn channels(&self, app_id: &str) -> Value {
-        let mut connection_manager_locked = self.connection_manager.lock().await;
-        let channels_map_result = connection_manager_locked
+        let channels_map_result = self
+            .connection_manager
             .get_channels_with_socket_count(app_id)
             .await;
         let mut response_val = json!({});
@@ -1869,8 +2327,6 @@ impl ConnectionHandler {
 
         if let Err(e) = self
             .connection_manager
-            .lock()
-            .await
             .send(channel, pusher_message_val, socket_id, app_id)
             .await
         {
@@ -1880,6 +2336,9 @@ impl ConnectionHandler {
                 "Message sent to channel {} successfully (via HTTP API path)",
                 channel
             );
+            if let Some(usage_stats) = &self.usage_stats {
+                usage_stats.record_message(app_id, channel);
+            }
         }
     }
 
@@ -1893,9 +2352,9 @@ impl ConnectionHandler {
             .await?;
 
         // For each watcher, get their active socket IDs
-        let mut connection_manager_locked = self.connection_manager.lock().await;
         for watcher_user_id in watchers {
-            let user_sockets = connection_manager_locked
+            let user_sockets = self
+                .connection_manager
                 .get_user_sockets(&watcher_user_id, app_id)
                 .await?;
 
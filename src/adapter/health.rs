@@ -0,0 +1,170 @@
+use std::sync::Arc;
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::Duration;
+
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+use crate::adapter::Adapter;
+use crate::metrics::MetricsInterface;
+use crate::options::AdapterHealthCheckConfig;
+
+/// Last known health of the configured adapter, shared between the supervisor
+/// and the HTTP health endpoints.
+#[derive(Debug)]
+pub struct AdapterHealthStatus {
+    healthy: AtomicBool,
+    consecutive_failures: AtomicU32,
+    last_error: RwLock<Option<String>>,
+}
+
+impl Default for AdapterHealthStatus {
+    fn default() -> Self {
+        Self {
+            healthy: AtomicBool::new(true),
+            consecutive_failures: AtomicU32::new(0),
+            last_error: RwLock::new(None),
+        }
+    }
+}
+
+impl AdapterHealthStatus {
+    pub fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::SeqCst)
+    }
+
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures.load(Ordering::SeqCst)
+    }
+
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error.read().ok().and_then(|e| e.clone())
+    }
+
+    fn mark_healthy(&self) {
+        self.healthy.store(true, Ordering::SeqCst);
+        self.consecutive_failures.store(0, Ordering::SeqCst);
+        if let Ok(mut last_error) = self.last_error.write() {
+            *last_error = None;
+        }
+    }
+
+    fn mark_unhealthy(&self, reason: String) {
+        self.healthy.store(false, Ordering::SeqCst);
+        self.consecutive_failures.fetch_add(1, Ordering::SeqCst);
+        if let Ok(mut last_error) = self.last_error.write() {
+            *last_error = Some(reason);
+        }
+    }
+}
+
+/// Background task that periodically checks the adapter and reconnects it
+/// with exponential backoff when the check fails.
+pub struct AdapterSupervisor {
+    connection_manager: Arc<dyn Adapter + Send + Sync>,
+    status: Arc<AdapterHealthStatus>,
+    metrics: Option<Arc<Mutex<dyn MetricsInterface + Send + Sync>>>,
+    config: AdapterHealthCheckConfig,
+}
+
+impl AdapterSupervisor {
+    pub fn new(
+        connection_manager: Arc<dyn Adapter + Send + Sync>,
+        status: Arc<AdapterHealthStatus>,
+        metrics: Option<Arc<Mutex<dyn MetricsInterface + Send + Sync>>>,
+        config: AdapterHealthCheckConfig,
+    ) -> Self {
+        Self {
+            connection_manager,
+            status,
+            metrics,
+            config,
+        }
+    }
+
+    /// Spawn the supervisor loop. It runs for the lifetime of the process.
+    pub fn start(self) {
+        if !self.config.enabled {
+            info!("{}", "Adapter health supervisor is disabled");
+            return;
+        }
+
+        info!(
+            "{}",
+            format!(
+                "Starting adapter health supervisor (interval: {}ms, max backoff: {}ms)",
+                self.config.interval_ms, self.config.max_backoff_ms
+            )
+        );
+
+        tokio::spawn(async move {
+            let interval = Duration::from_millis(self.config.interval_ms.max(1));
+            let initial_backoff = Duration::from_millis(self.config.initial_backoff_ms.max(1));
+            let max_backoff = Duration::from_millis(self.config.max_backoff_ms.max(1));
+            let mut delay = interval;
+            let mut backoff = initial_backoff;
+
+            loop {
+                tokio::time::sleep(delay).await;
+
+                let check = self.connection_manager.health().await;
+
+                match check {
+                    Ok(()) => {
+                        if !self.status.is_healthy() {
+                            info!("{}", "Adapter connection recovered");
+                        }
+                        self.status.mark_healthy();
+                        self.record_health(true).await;
+                        delay = interval;
+                        backoff = initial_backoff;
+                    }
+                    Err(e) => {
+                        warn!("{}", format!("Adapter health check failed: {}", e));
+                        self.status.mark_unhealthy(e.to_string());
+                        self.record_health(false).await;
+
+                        let reconnect = self.connection_manager.reconnect().await;
+                        let reconnected = match reconnect {
+                            Ok(()) => {
+                                info!("{}", "Adapter reconnect attempt succeeded");
+                                true
+                            }
+                            Err(e) => {
+                                error!(
+                                    "{}",
+                                    format!(
+                                        "Adapter reconnect attempt {} failed: {}",
+                                        self.status.consecutive_failures(),
+                                        e
+                                    )
+                                );
+                                false
+                            }
+                        };
+                        self.record_reconnect(reconnected).await;
+
+                        // Re-check after the current backoff, doubling it for the next failure
+                        delay = backoff;
+                        backoff = (backoff * 2).min(max_backoff);
+                    }
+                }
+            }
+        });
+    }
+
+    async fn record_health(&self, healthy: bool) {
+        if let Some(metrics) = &self.metrics {
+            let metrics = metrics.lock().await;
+            metrics.mark_adapter_health(healthy);
+        }
+    }
+
+    async fn record_reconnect(&self, success: bool) {
+        if let Some(metrics) = &self.metrics {
+            let metrics = metrics.lock().await;
+            metrics.mark_adapter_reconnect_attempt(success);
+        }
+    }
+}
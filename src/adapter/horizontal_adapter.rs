@@ -8,10 +8,14 @@ use crate::channel::PresenceMemberInfo;
 use crate::error::{Error, Result};
 
 use crate::metrics::MetricsInterface;
-use crate::websocket::SocketId;
+use crate::options::{
+    AdapterCompressionConfig, CompressionAlgorithm, HorizontalRequestConfig, QuorumPolicy,
+};
+use crate::protocol::encoding::encode_json;
+use crate::websocket::{SocketDetails, SocketId};
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, mpsc};
 use tokio::time::sleep;
 use tracing::{info, warn};
 use uuid::Uuid;
@@ -28,10 +32,11 @@ pub enum RequestType {
     ChannelsWithSocketsCount, // Get channels with socket counts
 
     // New request types
-    Sockets,             // Get all sockets
-    Channels,            // Get all channels
-    SocketsCount,        // Get count of all sockets
-    ChannelMembersCount, // Get count of members in a channel
+    Sockets,              // Get all sockets
+    Channels,             // Get all channels
+    SocketsCount,         // Get count of all sockets
+    ChannelMembersCount,  // Get count of members in a channel
+    ChannelSocketDetails, // Get a sample of socket details in a channel
 }
 
 /// Request body for horizontal communication
@@ -44,6 +49,9 @@ pub struct RequestBody {
     pub channel: Option<String>,
     pub socket_id: Option<String>,
     pub user_id: Option<String>,
+    /// Maximum number of items to return, for sampling requests
+    #[serde(default)]
+    pub limit: Option<usize>,
 }
 
 /// Response body for horizontal requests
@@ -59,6 +67,11 @@ pub struct ResponseBody {
     pub exists: bool,
     pub channels: HashSet<String>,
     pub members_count: usize, // New field for ChannelMembersCount
+    #[serde(default)]
+    pub socket_details: Vec<SocketDetails>,
+    /// Set on combined responses when not every expected node answered in time
+    #[serde(default)]
+    pub partial: bool,
 }
 
 /// Message for broadcasting events
@@ -69,6 +82,10 @@ pub struct BroadcastMessage {
     pub channel: String,
     pub message: String,
     pub except_socket_id: Option<String>,
+    /// When the publishing node started delivering, in Unix milliseconds;
+    /// absent from nodes that predate delivery deadlines
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published_at: Option<i64>,
 }
 
 /// Request tracking struct
@@ -79,20 +96,110 @@ pub struct PendingRequest {
     responses: Vec<ResponseBody>,
 }
 
-/// Base horizontal adapter
+/// First byte of a compressed envelope. Plain JSON always starts with `{`, so
+/// receivers can tell both formats apart and mixed-version clusters keep working.
+const COMPRESSED_MARKER: u8 = 0x00;
+const ZSTD_ID: u8 = 1;
+const LZ4_ID: u8 = 2;
+
+/// Encodes broadcast envelopes for the wire, compressing large ones
+#[derive(Debug, Clone)]
+pub struct BroadcastCodec {
+    algorithm: CompressionAlgorithm,
+    threshold_bytes: usize,
+    level: i32,
+}
+
+impl Default for BroadcastCodec {
+    fn default() -> Self {
+        Self::new(&AdapterCompressionConfig::default())
+    }
+}
+
+impl BroadcastCodec {
+    pub fn new(config: &AdapterCompressionConfig) -> Self {
+        Self {
+            algorithm: config.algorithm,
+            threshold_bytes: config.threshold_bytes,
+            level: config.level,
+        }
+    }
+
+    /// Serialize a broadcast, compressing it when it reaches the threshold
+    pub fn encode(&self, broadcast: &BroadcastMessage) -> Result<Vec<u8>> {
+        let json = encode_json(broadcast)?;
+        if json.len() < self.threshold_bytes {
+            return Ok(json);
+        }
+
+        let (id, compressed) = match self.algorithm {
+            CompressionAlgorithm::None => return Ok(json),
+            CompressionAlgorithm::Zstd => (
+                ZSTD_ID,
+                zstd::bulk::compress(&json, self.level).map_err(|e| {
+                    Error::InternalError(format!("Failed to compress broadcast: {}", e))
+                })?,
+            ),
+            CompressionAlgorithm::Lz4 => (LZ4_ID, lz4_flex::compress_prepend_size(&json)),
+        };
+
+        let mut payload = Vec::with_capacity(compressed.len() + 2);
+        payload.push(COMPRESSED_MARKER);
+        payload.push(id);
+        payload.extend_from_slice(&compressed);
+        Ok(payload)
+    }
+
+    /// Parse a broadcast published by any node, compressed or not
+    pub fn decode(payload: &[u8]) -> Result<BroadcastMessage> {
+        let json = match payload {
+            [COMPRESSED_MARKER, ZSTD_ID, body @ ..] => zstd::stream::decode_all(body)
+                .map_err(|e| Error::InternalError(format!("Invalid zstd broadcast: {}", e)))?,
+            [COMPRESSED_MARKER, LZ4_ID, body @ ..] => lz4_flex::decompress_size_prepended(body)
+                .map_err(|e| Error::InternalError(format!("Invalid lz4 broadcast: {}", e)))?,
+            [COMPRESSED_MARKER, id, ..] => {
+                return Err(Error::InternalError(format!(
+                    "Unknown broadcast compression id: {}",
+                    id
+                )));
+            }
+            _ => return Ok(serde_json::from_slice(payload)?),
+        };
+        Ok(serde_json::from_slice(&json)?)
+    }
+}
+
+/// Base horizontal adapter. Every field is shared or cheap to copy, so a
+/// clone can wait on requests without holding the owner's lock.
+#[derive(Clone)]
 pub struct HorizontalAdapter {
     /// Unique node ID
     pub node_id: String,
 
-    /// Local adapter for handling local connections
-    pub local_adapter: LocalAdapter,
+    /// Local adapter for handling local connections. Shared with the owning
+    /// adapter so local operations never wait on this struct's mutex.
+    pub local_adapter: Arc<LocalAdapter>,
 
-    /// Pending requests map - Use DashMap for thread-safe access
-    pub pending_requests: DashMap<String, PendingRequest>,
+    /// Pending requests map - Use DashMap for thread-safe access. Shared so
+    /// transports can record responses without taking the adapter mutex.
+    pub pending_requests: Arc<DashMap<String, PendingRequest>>,
+
+    /// Transport that fans serialized requests out to the other nodes, for
+    /// adapters that publish requests themselves
+    pub request_publisher: Option<mpsc::UnboundedSender<String>>,
 
     /// Timeout for requests in milliseconds
     pub requests_timeout: u64,
 
+    /// Fixed node count used instead of the driver's detection, if configured
+    pub expected_node_count: Option<usize>,
+
+    /// How many nodes must answer before a request resolves
+    pub quorum: QuorumPolicy,
+
+    /// Whether requests that miss their quorum return partial results or fail
+    pub allow_partial_results: bool,
+
     pub metrics: Option<Arc<Mutex<dyn MetricsInterface + Send + Sync>>>,
 }
 
@@ -101,13 +208,38 @@ impl HorizontalAdapter {
     pub fn new() -> Self {
         Self {
             node_id: Uuid::new_v4().to_string(),
-            local_adapter: LocalAdapter::new(),
-            pending_requests: DashMap::new(),
+            local_adapter: Arc::new(LocalAdapter::new()),
+            pending_requests: Arc::new(DashMap::new()),
+            request_publisher: None,
             requests_timeout: 5000, // Default 5 seconds
+            expected_node_count: None,
+            quorum: QuorumPolicy::All,
+            allow_partial_results: true,
             metrics: None,
         }
     }
 
+    /// Apply the request options from the adapter configuration
+    pub fn configure_requests(&mut self, config: &HorizontalRequestConfig) {
+        if let Some(timeout) = config.request_timeout_ms {
+            self.requests_timeout = timeout;
+        }
+        self.expected_node_count = config.expected_node_count.map(|n| n as usize);
+        self.quorum = config.quorum;
+        self.allow_partial_results = config.allow_partial_results;
+    }
+
+    /// Number of remote responses needed to satisfy the quorum policy
+    fn required_responses(&self, node_count: usize) -> usize {
+        let remote_nodes = node_count.saturating_sub(1);
+        match self.quorum {
+            QuorumPolicy::All => remote_nodes,
+            // A majority of all nodes, counting ourselves as one of them
+            QuorumPolicy::Majority => (node_count / 2).min(remote_nodes),
+            QuorumPolicy::Any => remote_nodes.min(1),
+        }
+    }
+
     /// Start the request cleanup task
     pub fn start_request_cleanup(&mut self) {
         // Clone data needed for the task
@@ -143,7 +275,7 @@ impl HorizontalAdapter {
     }
 
     /// Process a received request from another node
-    pub async fn process_request(&mut self, request: RequestBody) -> Result<ResponseBody> {
+    pub async fn process_request(&self, request: RequestBody) -> Result<ResponseBody> {
         info!(
             "{}",
             format!(
@@ -152,15 +284,31 @@ impl HorizontalAdapter {
             )
         );
 
+        // Track received request
+        if let Some(metrics_ref) = &self.metrics {
+            let metrics = metrics_ref.lock().await;
+            metrics.mark_horizontal_adapter_request_received(&request.app_id);
+        }
+
+        Self::build_response(&self.local_adapter, &self.node_id, request).await
+    }
+
+    /// Answer a request from the local registry. Does not need the adapter
+    /// itself, so transports can serve requests while a local request is pending.
+    pub async fn build_response(
+        local_adapter: &LocalAdapter,
+        node_id: &str,
+        request: RequestBody,
+    ) -> Result<ResponseBody> {
         // Skip processing our own requests
-        if request.node_id == self.node_id {
+        if request.node_id == node_id {
             return Err(Error::OwnRequestIgnored);
         }
 
         // Initialize empty response
         let mut response = ResponseBody {
             request_id: request.request_id.clone(),
-            node_id: self.node_id.clone(),
+            node_id: node_id.to_string(),
             app_id: request.app_id.clone(),
             members: HashMap::new(),
             socket_ids: Vec::new(),
@@ -169,6 +317,8 @@ impl HorizontalAdapter {
             exists: false,
             channels: HashSet::new(),
             members_count: 0,
+            socket_details: Vec::new(),
+            partial: false,
         };
 
         // Process based on request type
@@ -176,8 +326,7 @@ impl HorizontalAdapter {
             RequestType::ChannelMembers => {
                 if let Some(channel) = &request.channel {
                     // Get channel members from local adapter
-                    let members = self
-                        .local_adapter
+                    let members = local_adapter
                         .get_channel_members(&request.app_id, channel)
                         .await?;
                     response.members = members;
@@ -186,10 +335,7 @@ impl HorizontalAdapter {
             RequestType::ChannelSockets => {
                 if let Some(channel) = &request.channel {
                     // Get channel sockets from local adapter
-                    let channel_set = self
-                        .local_adapter
-                        .get_channel(&request.app_id, channel)
-                        .await?;
+                    let channel_set = local_adapter.get_channel(&request.app_id, channel).await?;
                     response.socket_ids = channel_set
                         .iter()
                         .map(|socket_id| socket_id.0.clone())
@@ -199,8 +345,7 @@ impl HorizontalAdapter {
             RequestType::ChannelSocketsCount => {
                 if let Some(channel) = &request.channel {
                     // Get channel socket count from local adapter
-                    response.sockets_count = self
-                        .local_adapter
+                    response.sockets_count = local_adapter
                         .get_channel_socket_count(&request.app_id, channel)
                         .await;
                 }
@@ -209,8 +354,7 @@ impl HorizontalAdapter {
                 if let (Some(channel), Some(socket_id)) = (&request.channel, &request.socket_id) {
                     // Check if socket exists in channel
                     let socket_id = SocketId(socket_id.clone());
-                    response.exists = self
-                        .local_adapter
+                    response.exists = local_adapter
                         .is_in_channel(&request.app_id, channel, &socket_id)
                         .await?;
                 }
@@ -218,7 +362,7 @@ impl HorizontalAdapter {
             RequestType::TerminateUserConnections => {
                 if let Some(user_id) = &request.user_id {
                     // Terminate user connections locally
-                    self.local_adapter
+                    local_adapter
                         .terminate_user_connections(&request.app_id, user_id)
                         .await?;
                     response.exists = true;
@@ -226,8 +370,7 @@ impl HorizontalAdapter {
             }
             RequestType::ChannelsWithSocketsCount => {
                 // Get channels with socket count from local adapter
-                let channels = self
-                    .local_adapter
+                let channels = local_adapter
                     .get_channels_with_socket_count(&request.app_id)
                     .await?;
                 response.channels_with_sockets_count = channels
@@ -238,10 +381,7 @@ impl HorizontalAdapter {
             // New request types
             RequestType::Sockets => {
                 // Get all connections for the app
-                let connections = self
-                    .local_adapter
-                    .get_all_connections(&request.app_id)
-                    .await;
+                let connections = local_adapter.get_all_connections(&request.app_id).await;
                 response.socket_ids = connections
                     .iter()
                     .map(|entry| entry.key().0.clone())
@@ -250,30 +390,40 @@ impl HorizontalAdapter {
             }
             RequestType::Channels => {
                 // Get all channels for the app
-                let channels = self
-                    .local_adapter
+                let channels = local_adapter
                     .get_channels_with_socket_count(&request.app_id)
                     .await?;
                 response.channels = channels.iter().map(|entry| entry.key().clone()).collect();
             }
             RequestType::SocketsCount => {
                 // Get count of all sockets
-                let connections = self
-                    .local_adapter
-                    .get_all_connections(&request.app_id)
-                    .await;
+                let connections = local_adapter.get_all_connections(&request.app_id).await;
                 response.sockets_count = connections.len();
             }
             RequestType::ChannelMembersCount => {
                 if let Some(channel) = &request.channel {
                     // Get count of members in a channel
-                    let members = self
-                        .local_adapter
+                    let members = local_adapter
                         .get_channel_members(&request.app_id, channel)
                         .await?;
                     response.members_count = members.len();
                 }
             }
+            RequestType::ChannelSocketDetails => {
+                if let Some(channel) = &request.channel {
+                    // Sample this node's subscribers and tag them with our node ID
+                    let limit = request.limit.unwrap_or(usize::MAX);
+                    response.socket_details = local_adapter
+                        .get_channel_socket_details(&request.app_id, channel, limit)
+                        .await?
+                        .into_iter()
+                        .map(|mut details| {
+                            details.node_id = Some(node_id.to_string());
+                            details
+                        })
+                        .collect();
+                }
+            }
         }
 
         // Return the response
@@ -288,18 +438,24 @@ impl HorizontalAdapter {
             metrics.mark_horizontal_adapter_response_received(&response.app_id);
         }
 
-        // Get the pending request
-        if let Some(mut request) = self.pending_requests.get_mut(&response.request_id) {
-            // Add response to the list
-            request.responses.push(response);
-        }
+        Self::record_response(&self.pending_requests, response);
 
         Ok(())
     }
 
+    /// Attach a response to its pending request, if it is still waiting
+    pub fn record_response(
+        pending_requests: &DashMap<String, PendingRequest>,
+        response: ResponseBody,
+    ) {
+        if let Some(mut request) = pending_requests.get_mut(&response.request_id) {
+            request.responses.push(response);
+        }
+    }
+
     /// Send a request to other nodes and wait for responses
     pub async fn send_request(
-        &mut self,
+        &self,
         app_id: &str,
         request_type: RequestType,
         channel: Option<&str>,
@@ -307,19 +463,84 @@ impl HorizontalAdapter {
         user_id: Option<&str>,
         expected_node_count: usize,
     ) -> Result<ResponseBody> {
-        // Generate a new request ID
-        let request_id = Uuid::new_v4().to_string();
-
-        // Create the request
         let request = RequestBody {
-            request_id: request_id.clone(),
+            request_id: Uuid::new_v4().to_string(),
             node_id: self.node_id.clone(),
             app_id: app_id.to_string(),
             request_type,
             channel: channel.map(String::from),
             socket_id: socket_id.map(String::from),
             user_id: user_id.map(String::from),
+            limit: None,
+        };
+        self.dispatch_request(request, expected_node_count).await
+    }
+
+    /// Send a channel request that asks each node for at most `limit` items
+    pub async fn send_request_with_limit(
+        &self,
+        app_id: &str,
+        request_type: RequestType,
+        channel: &str,
+        limit: usize,
+        expected_node_count: usize,
+    ) -> Result<ResponseBody> {
+        let request = RequestBody {
+            request_id: Uuid::new_v4().to_string(),
+            node_id: self.node_id.clone(),
+            app_id: app_id.to_string(),
+            request_type,
+            channel: Some(channel.to_string()),
+            socket_id: None,
+            user_id: None,
+            limit: Some(limit),
         };
+        self.dispatch_request(request, expected_node_count).await
+    }
+
+    /// Sample a channel's subscribers across the cluster. Each node samples
+    /// its own subscribers, and the union is sampled again.
+    pub async fn get_channel_socket_details(
+        &self,
+        app_id: &str,
+        channel: &str,
+        sample: usize,
+        node_count: usize,
+    ) -> Result<Vec<SocketDetails>> {
+        let mut details: Vec<SocketDetails> = self
+            .local_adapter
+            .get_channel_socket_details(app_id, channel, sample)
+            .await?
+            .into_iter()
+            .map(|mut details| {
+                details.node_id = Some(self.node_id.clone());
+                details
+            })
+            .collect();
+
+        if node_count > 1 {
+            let response_data = self
+                .send_request_with_limit(
+                    app_id,
+                    RequestType::ChannelSocketDetails,
+                    channel,
+                    sample,
+                    node_count,
+                )
+                .await?;
+            details.extend(response_data.socket_details);
+        }
+
+        Ok(SocketDetails::sample(details, sample))
+    }
+
+    async fn dispatch_request(
+        &self,
+        request: RequestBody,
+        expected_node_count: usize,
+    ) -> Result<ResponseBody> {
+        let request_id = request.request_id.clone();
+        let app_id = request.app_id.as_str();
 
         // Add to pending requests
         self.pending_requests.insert(
@@ -338,19 +559,27 @@ impl HorizontalAdapter {
             metrics.mark_horizontal_adapter_request_sent(app_id);
         }
 
-        // This would be implemented by the specific adapter (Redis, etc.)
-        // self.broadcast_request(request_json).await?;
+        // Adapters that publish requests themselves register a publisher
+        if let Some(publisher) = &self.request_publisher {
+            if publisher.send(request_json).is_err() {
+                warn!(
+                    "{}",
+                    format!(
+                        "Request publisher is closed, request {} not sent",
+                        request_id
+                    )
+                );
+            }
+        }
 
         // Wait for responses
         let timeout = self.requests_timeout;
         let start = Instant::now();
 
-        // Maximum nodes to wait for (don't wait for more than expected_node_count)
-        let max_nodes = if expected_node_count > 1 {
-            expected_node_count - 1
-        } else {
-            0
-        };
+        // A configured node count takes precedence over the driver's estimate
+        let node_count = self.expected_node_count.unwrap_or(expected_node_count);
+        let remote_nodes = node_count.saturating_sub(1);
+        let required = self.required_responses(node_count);
 
         // Combine the results
         let mut combined_response = ResponseBody {
@@ -364,6 +593,8 @@ impl HorizontalAdapter {
             exists: false,
             channels: Default::default(),
             members_count: 0,
+            socket_details: Vec::new(),
+            partial: false,
         };
 
         // Wait for responses until timeout or we have enough responses
@@ -371,7 +602,7 @@ impl HorizontalAdapter {
             // Check if we have the request
             if let Some(request) = self.pending_requests.get(&request_id) {
                 // Check if we have enough responses
-                if request.responses.len() >= max_nodes {
+                if request.responses.len() >= required {
                     break;
                 }
             } else {
@@ -384,7 +615,9 @@ impl HorizontalAdapter {
         }
 
         // Get all responses
+        let mut responded = 0;
         if let Some((_, request)) = self.pending_requests.remove(&request_id) {
+            responded = request.responses.len();
             // Combine the results
             for response in request.responses {
                 // Also note it's "responses" not "response"
@@ -402,25 +635,121 @@ impl HorizontalAdapter {
 
                 // Combine channels with sockets count
                 for (channel, sockets) in response.channels_with_sockets_count {
-                    combined_response
+                    *combined_response
                         .channels_with_sockets_count
                         .entry(channel)
-                        .or_insert_with(|| 0);
+                        .or_insert(0) += sockets;
                 }
+
+                combined_response.channels.extend(response.channels);
+                combined_response.members_count += response.members_count;
+                combined_response
+                    .socket_details
+                    .extend(response.socket_details);
             }
         }
+
+        combined_response.partial = responded < remote_nodes;
+        let quorum_reached = responded >= required;
+
         if let Some(metrics_ref) = &self.metrics {
             let duration_ms = start.elapsed().as_millis() as f64;
             let metrics = metrics_ref.lock().await;
 
-            // Track resolution time
-            metrics.track_horizontal_adapter_resolve_time(app_id, duration_ms);
+            // Track resolution time, linked to the request ID every node logs
+            metrics.track_horizontal_adapter_resolve_time_with_exemplar(
+                app_id,
+                duration_ms,
+                &request_id,
+            );
 
             // Track if the request was successfully resolved
-            metrics.track_horizontal_adapter_resolved_promises(app_id, true);
+            metrics.track_horizontal_adapter_resolved_promises(app_id, !combined_response.partial);
+        }
+
+        if !quorum_reached {
+            warn!(
+                "{}",
+                format!(
+                    "Request {} reached {} of {} required responses ({} nodes expected)",
+                    request_id, responded, required, node_count
+                )
+            );
+            if !self.allow_partial_results {
+                return Err(Error::Other(format!(
+                    "Horizontal request quorum not reached: {}/{} responses",
+                    responded, required
+                )));
+            }
+        } else if combined_response.partial {
+            info!(
+                "{}",
+                format!(
+                    "Request {} resolved with {} of {} remote nodes",
+                    request_id, responded, remote_nodes
+                )
+            );
         }
 
         // Return the combined response
         Ok(combined_response)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adapter_with(quorum: QuorumPolicy) -> HorizontalAdapter {
+        let mut adapter = HorizontalAdapter::new();
+        adapter.quorum = quorum;
+        adapter
+    }
+
+    #[test]
+    fn test_all_needs_every_remote_node() {
+        let adapter = adapter_with(QuorumPolicy::All);
+        assert_eq!(adapter.required_responses(0), 0);
+        assert_eq!(adapter.required_responses(1), 0);
+        assert_eq!(adapter.required_responses(2), 1);
+        assert_eq!(adapter.required_responses(5), 4);
+    }
+
+    #[test]
+    fn test_majority_counts_this_node() {
+        let adapter = adapter_with(QuorumPolicy::Majority);
+        assert_eq!(adapter.required_responses(0), 0);
+        assert_eq!(adapter.required_responses(1), 0);
+        // Two nodes: the majority needs the other one
+        assert_eq!(adapter.required_responses(2), 1);
+        // We plus one of two remote nodes make two of three
+        assert_eq!(adapter.required_responses(3), 1);
+        assert_eq!(adapter.required_responses(4), 2);
+        assert_eq!(adapter.required_responses(5), 2);
+    }
+
+    #[test]
+    fn test_any_needs_one_remote_node() {
+        let adapter = adapter_with(QuorumPolicy::Any);
+        assert_eq!(adapter.required_responses(0), 0);
+        assert_eq!(adapter.required_responses(1), 0);
+        assert_eq!(adapter.required_responses(2), 1);
+        assert_eq!(adapter.required_responses(5), 1);
+    }
+
+    #[test]
+    fn test_required_responses_never_exceed_remote_nodes() {
+        // A configured node count larger than the cluster still only asks
+        // for as many answers as there are remote nodes in that count
+        for quorum in [QuorumPolicy::All, QuorumPolicy::Majority, QuorumPolicy::Any] {
+            let adapter = adapter_with(quorum);
+            for node_count in [1, 2, 3, 10, 1000] {
+                assert!(adapter.required_responses(node_count) <= node_count - 1);
+            }
+        }
+        assert_eq!(
+            adapter_with(QuorumPolicy::Majority).required_responses(1000),
+            500
+        );
+    }
+}
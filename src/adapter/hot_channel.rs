@@ -0,0 +1,285 @@
+// src/adapter/hot_channel.rs
+//! Sticky ownership of hot channels for the Redis adapter. Each configured
+//! hot channel has a single owner node, elected through a Redis key held on a
+//! lease. Other nodes forward publishes for the channel to the owner instead
+//! of broadcasting them, and the owner relays each message only to the nodes
+//! that have subscribers to the channel. Nodes without subscribers never see
+//! the channel's traffic.
+
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use redis::aio::ConnectionLike;
+use tracing::{debug, info};
+
+use crate::options::HotChannelsConfig;
+
+const PUBLISH_SUFFIX: &str = "#hot-publish";
+const DELIVER_SUFFIX: &str = "#hot-deliver";
+
+/// KEYS[1]: owner key; ARGV[1]: node ID; ARGV[2]: lease in milliseconds.
+/// Returns the current owner, taking ownership when there is none.
+const CLAIM_SCRIPT: &str = r#"
+local owner = redis.call('GET', KEYS[1])
+if owner then
+    return owner
+end
+redis.call('SET', KEYS[1], ARGV[1], 'PX', ARGV[2])
+return ARGV[1]
+"#;
+
+/// Extends the lease when ARGV[1] still owns KEYS[1]
+const RENEW_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('PEXPIRE', KEYS[1], ARGV[2])
+end
+return 0
+"#;
+
+/// Deletes KEYS[1] when ARGV[1] still owns it
+const RELEASE_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+"#;
+
+pub struct HotChannels {
+    node_id: String,
+    prefix: String,
+    /// Channel names, or prefixes when they end in `*`
+    channels: Vec<String>,
+    lease: Duration,
+    subscriber_refresh: Duration,
+    /// Owners of channels this node publishes to, until the lookup goes stale
+    owners: DashMap<String, (String, Instant)>,
+    /// Owner keys this node holds, with when a publish last went through it
+    owned: DashMap<String, Instant>,
+    /// Nodes with subscribers to each channel this node fans out, keyed
+    /// like `owned`
+    subscribers: DashMap<String, (Vec<String>, Instant)>,
+}
+
+impl HotChannels {
+    pub fn new(node_id: String, prefix: String, config: &HotChannelsConfig) -> Self {
+        info!(
+            "{}",
+            format!(
+                "Hot channel ownership enabled for: {}",
+                config.channels.join(", ")
+            )
+        );
+        Self {
+            node_id,
+            prefix,
+            channels: config.channels.clone(),
+            lease: Duration::from_millis(config.lease_ms.max(1000)),
+            subscriber_refresh: Duration::from_millis(config.subscriber_refresh_ms),
+            owners: DashMap::new(),
+            owned: DashMap::new(),
+            subscribers: DashMap::new(),
+        }
+    }
+
+    pub fn is_hot(&self, channel: &str) -> bool {
+        self.channels
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => channel.starts_with(prefix),
+                None => channel == pattern,
+            })
+    }
+
+    /// How often owned leases are renewed
+    pub fn renew_interval(&self) -> Duration {
+        self.lease / 3
+    }
+
+    /// Topic a node receives publishes it is asked to fan out on
+    pub fn publish_topic(&self, node_id: &str) -> String {
+        format!("{}:{}:{}", self.prefix, PUBLISH_SUFFIX, node_id)
+    }
+
+    /// Topic a node receives messages for its local subscribers on
+    pub fn deliver_topic(&self, node_id: &str) -> String {
+        format!("{}:{}:{}", self.prefix, DELIVER_SUFFIX, node_id)
+    }
+
+    fn owner_key(&self, app_id: &str, channel: &str) -> String {
+        format!("{}:hot-owner:{}:{}", self.prefix, app_id, channel)
+    }
+
+    fn nodes_key(&self, app_id: &str, channel: &str) -> String {
+        format!("{}:hot-nodes:{}:{}", self.prefix, app_id, channel)
+    }
+
+    /// Node that fans out the channel, electing this one when it has no owner
+    pub async fn owner<C: ConnectionLike + Send>(
+        &self,
+        conn: &mut C,
+        app_id: &str,
+        channel: &str,
+    ) -> redis::RedisResult<String> {
+        let key = self.owner_key(app_id, channel);
+        if let Some(mut last_publish) = self.owned.get_mut(&key) {
+            *last_publish = Instant::now();
+            return Ok(self.node_id.clone());
+        }
+        if let Some(entry) = self.owners.get(&key) {
+            let (owner, looked_up) = entry.value();
+            if looked_up.elapsed() < self.renew_interval() {
+                return Ok(owner.clone());
+            }
+        }
+
+        let owner: String = redis::Script::new(CLAIM_SCRIPT)
+            .key(&key)
+            .arg(&self.node_id)
+            .arg(self.lease.as_millis() as u64)
+            .invoke_async(conn)
+            .await?;
+        if owner == self.node_id {
+            info!(
+                "{}",
+                format!("Took ownership of hot channel {} ({})", channel, app_id)
+            );
+            self.owners.remove(&key);
+            self.owned.insert(key, Instant::now());
+        } else {
+            self.owners.insert(key, (owner.clone(), Instant::now()));
+        }
+        Ok(owner)
+    }
+
+    /// Drop a cached owner that no longer listens, so the next publish looks it up again
+    pub fn forget_owner(&self, app_id: &str, channel: &str) {
+        self.owners.remove(&self.owner_key(app_id, channel));
+    }
+
+    /// Record a publish forwarded to this node, keeping its lease alive
+    pub fn touch(&self, app_id: &str, channel: &str) {
+        if let Some(mut last_publish) = self.owned.get_mut(&self.owner_key(app_id, channel)) {
+            *last_publish = Instant::now();
+        }
+    }
+
+    /// Announce that this node has subscribers to the channel
+    pub async fn join<C: ConnectionLike + Send>(
+        &self,
+        conn: &mut C,
+        app_id: &str,
+        channel: &str,
+    ) -> redis::RedisResult<()> {
+        redis::cmd("SADD")
+            .arg(self.nodes_key(app_id, channel))
+            .arg(&self.node_id)
+            .query_async(conn)
+            .await
+    }
+
+    /// Announce that this node's last subscriber to the channel left
+    pub async fn leave<C: ConnectionLike + Send>(
+        &self,
+        conn: &mut C,
+        app_id: &str,
+        channel: &str,
+    ) -> redis::RedisResult<()> {
+        redis::cmd("SREM")
+            .arg(self.nodes_key(app_id, channel))
+            .arg(&self.node_id)
+            .query_async(conn)
+            .await
+    }
+
+    /// Relay an encoded broadcast to every other node with subscribers to
+    /// the channel. Nodes nobody listens for anymore are dropped from its set.
+    pub async fn fan_out<C: ConnectionLike + Send>(
+        &self,
+        conn: &mut C,
+        app_id: &str,
+        channel: &str,
+        payload: &[u8],
+    ) -> redis::RedisResult<()> {
+        let key = self.owner_key(app_id, channel);
+        let nodes_key = self.nodes_key(app_id, channel);
+        let cached = self
+            .subscribers
+            .get(&key)
+            .filter(|entry| entry.1.elapsed() < self.subscriber_refresh)
+            .map(|entry| entry.0.clone());
+        let nodes = match cached {
+            Some(nodes) => nodes,
+            None => {
+                let nodes: Vec<String> = redis::cmd("SMEMBERS")
+                    .arg(&nodes_key)
+                    .query_async(conn)
+                    .await?;
+                let nodes: Vec<String> = nodes
+                    .into_iter()
+                    .filter(|node| *node != self.node_id)
+                    .collect();
+                self.subscribers
+                    .insert(key.clone(), (nodes.clone(), Instant::now()));
+                nodes
+            }
+        };
+        if nodes.is_empty() {
+            return Ok(());
+        }
+
+        let mut pipe = redis::pipe();
+        for node in &nodes {
+            pipe.publish(self.deliver_topic(node), payload);
+        }
+        let receivers: Vec<i64> = pipe.query_async(conn).await?;
+
+        let gone: Vec<&String> = nodes
+            .iter()
+            .zip(receivers)
+            .filter(|(_, receivers)| *receivers == 0)
+            .map(|(node, _)| node)
+            .collect();
+        if !gone.is_empty() {
+            debug!(
+                "{}",
+                format!(
+                    "Dropping departed nodes from hot channel {}: {:?}",
+                    channel, gone
+                )
+            );
+            redis::cmd("SREM")
+                .arg(&nodes_key)
+                .arg(&gone)
+                .query_async::<()>(conn)
+                .await?;
+            self.subscribers.remove(&key);
+        }
+        Ok(())
+    }
+
+    /// Renew the leases of owned channels published to within the last
+    /// lease, and give up the others so an active publisher can take over
+    pub async fn renew<C: ConnectionLike + Send>(&self, conn: &mut C) -> redis::RedisResult<()> {
+        let owned: Vec<(String, Instant)> = self
+            .owned
+            .iter()
+            .map(|entry| (entry.key().clone(), *entry.value()))
+            .collect();
+        for (key, last_publish) in owned {
+            let active = last_publish.elapsed() < self.lease;
+            let script = if active { RENEW_SCRIPT } else { RELEASE_SCRIPT };
+            let renewed: i64 = redis::Script::new(script)
+                .key(&key)
+                .arg(&self.node_id)
+                .arg(self.lease.as_millis() as u64)
+                .invoke_async(conn)
+                .await?;
+            if !active || renewed == 0 {
+                debug!("{}", format!("Gave up ownership of {}", key));
+                self.owned.remove(&key);
+                self.subscribers.remove(&key);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+// src/adapter/interest.rs
+//! Per-node channel interest for distributed adapters.
+//!
+//! Channels are hashed onto a fixed number of shards and broadcasts are
+//! published to one topic per shard. Each node only subscribes to the shards
+//! its local sockets are in and announces that set to the other nodes, which
+//! skip publishing to shards nobody else consumes.
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use dashmap::{DashMap, DashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::adapter::local_adapter::LocalAdapter;
+use crate::options::ChannelInterestConfig;
+
+/// Shard set a node announces to the other nodes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterestAnnouncement {
+    pub node_id: String,
+    pub shards: Vec<u32>,
+}
+
+/// Shard of `shards` a channel hashes onto. Uses FNV-1a rather than std's
+/// randomly seeded hashers so every node computes the same shard.
+pub fn channel_shard(app_id: &str, channel: &str, shards: u32) -> u32 {
+    let mut hash: u32 = 0x811c_9dc5;
+    for byte in app_id
+        .bytes()
+        .chain(std::iter::once(b':'))
+        .chain(channel.bytes())
+    {
+        hash ^= u32::from(byte);
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    hash % shards.max(1)
+}
+
+pub struct ChannelInterest {
+    node_id: String,
+    shards: u32,
+    announce_interval: Duration,
+    started_at: Instant,
+    /// Shards this node is subscribed to
+    local: DashSet<u32>,
+    /// Shards announced by other nodes, with when they last announced
+    remote: DashMap<String, (HashSet<u32>, Instant)>,
+}
+
+impl ChannelInterest {
+    pub fn new(node_id: String, config: &ChannelInterestConfig) -> Self {
+        Self {
+            node_id,
+            shards: config.shards.max(1),
+            announce_interval: Duration::from_millis(config.announce_interval_ms.max(100)),
+            started_at: Instant::now(),
+            local: DashSet::new(),
+            remote: DashMap::new(),
+        }
+    }
+
+    pub fn announce_interval(&self) -> Duration {
+        self.announce_interval
+    }
+
+    /// Shard a channel belongs to
+    pub fn shard_for(&self, app_id: &str, channel: &str) -> u32 {
+        channel_shard(app_id, channel, self.shards)
+    }
+
+    /// Record a local subscription. Returns the shard if this node was not
+    /// consuming it yet and the transport needs to subscribe.
+    pub fn claim(&self, app_id: &str, channel: &str) -> Option<u32> {
+        let shard = self.shard_for(app_id, channel);
+        self.local.insert(shard).then_some(shard)
+    }
+
+    pub fn is_local(&self, shard: u32) -> bool {
+        self.local.contains(&shard)
+    }
+
+    pub fn local_shards(&self) -> Vec<u32> {
+        self.local.iter().map(|shard| *shard).collect()
+    }
+
+    /// Drop shards that no longer have local subscribers and return them so
+    /// the transport can unsubscribe. Disconnect cleanup does not go through
+    /// the adapter, so this is driven by a periodic scan rather than by
+    /// `remove_from_channel`.
+    pub fn release_idle(&self, local_adapter: &LocalAdapter) -> Vec<u32> {
+        let occupied = self.occupied_shards(local_adapter);
+        let idle: Vec<u32> = self
+            .local
+            .iter()
+            .map(|shard| *shard)
+            .filter(|shard| !occupied.contains(shard))
+            .collect();
+        if idle.is_empty() {
+            return idle;
+        }
+        for shard in &idle {
+            self.local.remove(shard);
+        }
+
+        // A socket may have joined one of these shards during the scan; keep
+        // those instead of unsubscribing from under it
+        let occupied = self.occupied_shards(local_adapter);
+        idle.into_iter()
+            .filter(|shard| {
+                if occupied.contains(shard) {
+                    self.local.insert(*shard);
+                    false
+                } else {
+                    true
+                }
+            })
+            .collect()
+    }
+
+    fn occupied_shards(&self, local_adapter: &LocalAdapter) -> HashSet<u32> {
+        let mut occupied = HashSet::new();
+        for namespace in local_adapter.namespaces.iter() {
+            for channel in namespace.channels.iter() {
+                if !channel.value().is_empty() {
+                    occupied.insert(self.shard_for(namespace.key(), channel.key()));
+                }
+            }
+        }
+        occupied
+    }
+
+    pub fn announcement(&self) -> InterestAnnouncement {
+        InterestAnnouncement {
+            node_id: self.node_id.clone(),
+            shards: self.local_shards(),
+        }
+    }
+
+    /// Store another node's announcement and forget nodes that went quiet
+    pub fn record(&self, announcement: InterestAnnouncement) {
+        if announcement.node_id == self.node_id {
+            return;
+        }
+        let expiry = self.announce_interval * 3;
+        self.remote
+            .retain(|_, (_, last_seen)| last_seen.elapsed() < expiry);
+        self.remote.insert(
+            announcement.node_id,
+            (announcement.shards.into_iter().collect(), Instant::now()),
+        );
+    }
+
+    /// Whether another node may need broadcasts for this shard. Until every
+    /// node had the chance to announce itself, assume one does.
+    pub fn remote_interested(&self, shard: u32) -> bool {
+        if self.started_at.elapsed() < self.announce_interval * 2 {
+            return true;
+        }
+        let expiry = self.announce_interval * 3;
+        self.remote.iter().any(|entry| {
+            let (shards, last_seen) = entry.value();
+            last_seen.elapsed() < expiry && shards.contains(&shard)
+        })
+    }
+}
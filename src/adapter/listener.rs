@@ -0,0 +1,111 @@
+// src/adapter/listener.rs
+//! Lifecycle of the pub/sub listener of a horizontal adapter.
+//!
+//! Health checks report a listener that is not running, and the supervisor
+//! then calls `reconnect`, possibly while an earlier start is still
+//! subscribing. Starts are claimed with a compare-and-swap so only one runs
+//! at a time, and the tasks of the previous listener are aborted first so a
+//! stream that outlived the others can't deliver twice.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use tokio::task::{AbortHandle, JoinHandle};
+
+const STOPPED: u8 = 0;
+const STARTING: u8 = 1;
+const RUNNING: u8 = 2;
+
+/// Whether an adapter's listener is stopped, subscribing or running
+#[derive(Debug, Default)]
+pub struct ListenerState {
+    state: AtomicU8,
+    /// Tasks of the current listener
+    tasks: Mutex<Vec<AbortHandle>>,
+}
+
+impl ListenerState {
+    /// Claim the start of a listener, aborting what is left of the previous
+    /// one. Returns false while another listener is starting or running. The
+    /// caller must report the outcome with [`running`](Self::running) or
+    /// [`stopped`](Self::stopped).
+    pub fn try_start(&self) -> bool {
+        if self
+            .state
+            .compare_exchange(STOPPED, STARTING, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return false;
+        }
+        for task in self.tasks.lock().unwrap().drain(..) {
+            task.abort();
+        }
+        true
+    }
+
+    /// Remember a task of the listener being started
+    pub fn track(&self, task: &JoinHandle<()>) {
+        self.tasks.lock().unwrap().push(task.abort_handle());
+    }
+
+    /// The listener subscribed and is receiving messages
+    pub fn running(&self) {
+        self.state.store(RUNNING, Ordering::SeqCst);
+    }
+
+    /// The listener failed to start or one of its streams ended
+    pub fn stopped(&self) {
+        self.state.store(STOPPED, Ordering::SeqCst);
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state.load(Ordering::SeqCst) == RUNNING
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_only_one_start_is_claimed() {
+        let listener = ListenerState::default();
+        assert!(!listener.is_running());
+        assert!(listener.try_start());
+        // Still subscribing: a reconnect must not start a second listener
+        assert!(!listener.try_start());
+        assert!(!listener.is_running());
+
+        listener.running();
+        assert!(listener.is_running());
+        assert!(!listener.try_start());
+
+        listener.stopped();
+        assert!(!listener.is_running());
+        assert!(listener.try_start());
+    }
+
+    #[test]
+    fn test_failed_start_can_be_retried() {
+        let listener = ListenerState::default();
+        assert!(listener.try_start());
+        listener.stopped();
+        assert!(listener.try_start());
+    }
+
+    #[tokio::test]
+    async fn test_restart_aborts_previous_tasks() {
+        let listener = ListenerState::default();
+        assert!(listener.try_start());
+        let task = tokio::spawn(async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+        });
+        listener.track(&task);
+        listener.running();
+
+        listener.stopped();
+        assert!(listener.try_start());
+        assert!(task.await.unwrap_err().is_cancelled());
+    }
+}
@@ -3,23 +3,39 @@ use crate::app::manager::AppManager;
 use crate::channel::PresenceMemberInfo;
 use crate::error::{Error, Result};
 
-use crate::namespace::Namespace;
+use crate::adapter::ack::MessageAcks;
+use crate::adapter::deadline::DeliveryDeadline;
+use crate::adapter::qos::DispatchQos;
+use crate::namespace::{Connections, Namespace};
+use crate::protocol::constants::{APP_DISABLED_CHANNEL, SERVER_NOTICE_CHANNEL};
+use crate::protocol::encoding::{encode_json, text_frame};
 use crate::protocol::messages::PusherMessage;
-use crate::websocket::{SocketId, WebSocket, WebSocketRef};
+use crate::websocket::{SocketDetails, SocketId, WebSocket, WebSocketRef};
 use dashmap::{DashMap, DashSet};
-use fastwebsockets::{Frame, Payload, WebSocketWrite};
+use fastwebsockets::WebSocketWrite;
 use hyper::upgrade::Upgraded;
 use hyper_util::rt::TokioIo;
 use std::any::Any;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
 use tokio::io::WriteHalf;
 use tokio::sync::Mutex;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
+/// Node-local connection registry. Namespaces live in a sharded `DashMap`, so
+/// operations on different apps never contend and callers only need `&self`.
 #[derive(Clone)]
 pub struct LocalAdapter {
     pub namespaces: DashMap<String, Arc<Namespace>>,
+    /// Prioritizes channel broadcasts by QoS class when enabled
+    dispatch_qos: OnceLock<Arc<DispatchQos>>,
+    /// Checks broadcasts against the delivery deadline when enabled
+    delivery_deadline: OnceLock<Arc<DeliveryDeadline>>,
+    /// Sequences broadcasts to acknowledged channels when enabled
+    message_acks: OnceLock<Arc<MessageAcks>>,
+    /// Drops disabled apps from the app cache
+    app_manager: OnceLock<Arc<dyn AppManager + Send + Sync>>,
 }
 
 impl Default for LocalAdapter {
@@ -32,19 +48,38 @@ impl LocalAdapter {
     pub fn new() -> Self {
         Self {
             namespaces: DashMap::new(),
+            dispatch_qos: OnceLock::new(),
+            delivery_deadline: OnceLock::new(),
+            message_acks: OnceLock::new(),
+            app_manager: OnceLock::new(),
         }
     }
 
+    pub fn set_dispatch_qos(&self, qos: Arc<DispatchQos>) {
+        let _ = self.dispatch_qos.set(qos);
+    }
+
+    pub fn set_delivery_deadline(&self, deadline: Arc<DeliveryDeadline>) {
+        let _ = self.delivery_deadline.set(deadline);
+    }
+
+    pub fn set_message_acks(&self, acks: Arc<MessageAcks>) {
+        let _ = self.message_acks.set(acks);
+    }
+
     // Helper function to get or create namespace
-    async fn get_or_create_namespace(&mut self, app_id: &str) -> Arc<Namespace> {
-        if !self.namespaces.contains_key(app_id) {
-            let namespace = Arc::new(Namespace::new(app_id.to_string()));
-            self.namespaces.insert(app_id.to_string(), namespace);
+    async fn get_or_create_namespace(&self, app_id: &str) -> Arc<Namespace> {
+        if let Some(namespace) = self.namespaces.get(app_id) {
+            return namespace.clone();
         }
-        self.namespaces.get(app_id).unwrap().clone()
+        // Only the shard holding this app is locked while the namespace is created
+        self.namespaces
+            .entry(app_id.to_string())
+            .or_insert_with(|| Arc::new(Namespace::new(app_id.to_string())))
+            .clone()
     }
     pub async fn get_all_connections(
-        &mut self,
+        &self,
         app_id: &str,
     ) -> DashMap<SocketId, Arc<Mutex<WebSocket>>> {
         // First, get or create the namespace for this app
@@ -53,20 +88,134 @@ impl LocalAdapter {
         // Return a clone of the sockets DashMap from the namespace
         namespace.sockets.clone()
     }
+
+    /// Deliver a broadcast to the local sockets. `published_at` is when
+    /// another node published it, in Unix milliseconds; broadcasts published
+    /// here are timed from now.
+    pub async fn deliver(
+        &self,
+        channel: &str,
+        message: PusherMessage,
+        except: Option<&SocketId>,
+        app_id: &str,
+        published_at: Option<i64>,
+    ) -> Result<()> {
+        let started = Instant::now();
+        info!("{}", format!("Sending message to channel: {}", channel));
+        info!("{}", format!("Message: {:?}", message));
+        // Encoded once; each recipient gets a copy of the bytes
+        let encoded = encode_json(&message)
+            .map_err(|e| Error::ConnectionError(format!("Failed to serialize message: {}", e)))?;
+
+        if channel.starts_with("#server-to-user-") {
+            let user_id = channel.trim_start_matches("#server-to-user-");
+            let namespace = self.get_namespace(app_id).await.unwrap();
+            let connections: Connections = namespace
+                .get_user_sockets(user_id)
+                .await?
+                .iter()
+                .map(|socket_ref| socket_ref.0.clone())
+                .collect();
+            send_encoded(connections, &encoded, except).await;
+        } else if channel == SERVER_NOTICE_CHANNEL {
+            // Every connection of the app, subscribed or not; a node may have
+            // no namespace for the app when none of its clients use it
+            let Some(namespace) = self.get_namespace(app_id).await else {
+                return Ok(());
+            };
+            let connections: Connections = namespace
+                .sockets
+                .iter()
+                .map(|entry| entry.value().clone())
+                .collect();
+            send_encoded(connections, &encoded, except).await;
+        } else if channel == APP_DISABLED_CHANNEL {
+            // Cached copies would keep accepting the app until they expire
+            if let Some(app_manager) = self.app_manager.get() {
+                app_manager.invalidate(app_id).await;
+            }
+            // Every connection of the app gets the error and is closed
+            let Some(namespace) = self.get_namespace(app_id).await else {
+                return Ok(());
+            };
+            let connections: Connections = namespace
+                .sockets
+                .iter()
+                .map(|entry| entry.value().clone())
+                .collect();
+            send_encoded(connections.clone(), &encoded, except).await;
+            let error = Error::ApplicationDisabled;
+            for connection in connections {
+                let mut ws = connection.lock().await;
+                if let Err(e) = ws.close(error.close_code(), error.to_string()).await {
+                    warn!(
+                        "{}",
+                        format!(
+                            "Failed to close socket {} of disabled app {}: {}",
+                            ws.state.socket_id, app_id, e
+                        )
+                    );
+                }
+            }
+        } else {
+            // Held until every local socket has the message
+            let _permit = match self.dispatch_qos.get() {
+                Some(qos) => match qos.admit(channel).await {
+                    Some(permit) => Some(permit),
+                    None => return Ok(()),
+                },
+                None => None,
+            };
+            let namespace = self.get_namespace(app_id).await.unwrap();
+            let connections = namespace.channel_connections(channel);
+            match self.message_acks.get().filter(|acks| acks.tracks(channel)) {
+                Some(acks) => {
+                    acks.deliver(app_id, channel, &message, connections, except)
+                        .await?
+                }
+                None => send_encoded(connections, &encoded, except).await,
+            }
+        }
+
+        if let Some(deadline) = self.delivery_deadline.get() {
+            let elapsed = match published_at {
+                Some(published_at) => DeliveryDeadline::since(published_at),
+                None => started.elapsed(),
+            };
+            deadline.record(app_id, channel, elapsed).await;
+        }
+        Ok(())
+    }
+}
+
+/// Queue an encoded message on each connection except `except`
+async fn send_encoded(connections: Connections, encoded: &[u8], except: Option<&SocketId>) {
+    for connection in connections {
+        let ws = connection.lock().await;
+        if except == Some(&ws.state.socket_id) {
+            continue;
+        }
+        if let Err(e) = ws.message_sender.send(text_frame(encoded)) {
+            error!(
+                "{}",
+                format!("Failed to send message to {}: {}", ws.state.socket_id, e)
+            );
+        }
+    }
 }
 
 #[async_trait::async_trait]
 impl Adapter for LocalAdapter {
-    async fn init(&mut self) {
+    async fn init(&self) {
         info!("{}", "Initializing local adapter");
     }
 
-    async fn get_namespace(&mut self, app_id: &str) -> Option<Arc<Namespace>> {
+    async fn get_namespace(&self, app_id: &str) -> Option<Arc<Namespace>> {
         Some(self.get_or_create_namespace(app_id).await)
     }
 
     async fn add_socket(
-        &mut self,
+        &self,
         socket_id: SocketId,
         socket: WebSocketWrite<WriteHalf<TokioIo<Upgraded>>>,
         app_id: &str,
@@ -79,7 +228,7 @@ impl Adapter for LocalAdapter {
     }
 
     async fn get_connection(
-        &mut self,
+        &self,
         socket_id: &SocketId,
         app_id: &str,
     ) -> Option<Arc<Mutex<WebSocket>>> {
@@ -87,7 +236,7 @@ impl Adapter for LocalAdapter {
         namespace.get_connection(socket_id)
     }
 
-    async fn remove_connection(&mut self, socket_id: &SocketId, app_id: &str) -> Result<()> {
+    async fn remove_connection(&self, socket_id: &SocketId, app_id: &str) -> Result<()> {
         if let Some(namespace) = self.namespaces.get(app_id) {
             namespace.remove_connection(socket_id);
             Ok(())
@@ -97,7 +246,7 @@ impl Adapter for LocalAdapter {
     }
 
     async fn send_message(
-        &mut self,
+        &self,
         app_id: &str,
         socket_id: &SocketId,
         message: PusherMessage,
@@ -107,10 +256,9 @@ impl Adapter for LocalAdapter {
             .await
             .ok_or_else(|| Error::ConnectionError("Connection not found".to_string()))?;
 
-        let message = serde_json::to_string(&message)
+        let encoded = encode_json(&message)
             .map_err(|e| Error::ConnectionError(format!("Failed to serialize message: {}", e)))?;
-
-        let frame = Frame::text(Payload::from(message.into_bytes()));
+        let frame = text_frame(&encoded);
 
         // Get the sender without locking the entire adapter
         let sender = {
@@ -126,49 +274,17 @@ impl Adapter for LocalAdapter {
     }
 
     async fn send(
-        &mut self,
+        &self,
         channel: &str,
         message: PusherMessage,
         except: Option<&SocketId>,
         app_id: &str,
     ) -> Result<()> {
-        info!("{}", format!("Sending message to channel: {}", channel));
-        info!("{}", format!("Message: {:?}", message));
-        if channel.starts_with("#server-to-user-") {
-            let user_id = channel.trim_start_matches("#server-to-user-");
-            let namespace = self.get_namespace(app_id).await.unwrap();
-            let socket_refs = namespace.get_user_sockets(user_id).await?;
-
-            for socket_ref in socket_refs {
-                // Get socket_id before sending
-                let socket_id = {
-                    let ws = socket_ref.0.lock().await;
-                    ws.state.socket_id.clone()
-                };
-
-                if except != Some(&socket_id) {
-                    self.send_message(app_id, &socket_id, message.clone())
-                        .await?;
-                }
-            }
-        } else {
-            let namespace = self.get_namespace(app_id).await.unwrap();
-            let sockets = namespace.get_channel_sockets(channel);
-
-            // Iterate through our DashMap of sockets
-            for socket_id in sockets.iter().map(|entry| entry.key().clone()) {
-                if except != Some(&socket_id) {
-                    self.send_message(app_id, &socket_id, message.clone())
-                        .await?;
-                }
-            }
-        }
-
-        Ok(())
+        self.deliver(channel, message, except, app_id, None).await
     }
 
     async fn get_channel_members(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
     ) -> Result<HashMap<String, PresenceMemberInfo>> {
@@ -177,7 +293,7 @@ impl Adapter for LocalAdapter {
     }
 
     async fn get_channel_sockets(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
     ) -> Result<DashMap<SocketId, Arc<Mutex<WebSocket>>>> {
@@ -185,18 +301,18 @@ impl Adapter for LocalAdapter {
         Ok(namespace.get_channel_sockets(channel))
     }
 
-    async fn get_channel(&mut self, app_id: &str, channel: &str) -> Result<DashSet<SocketId>> {
+    async fn get_channel(&self, app_id: &str, channel: &str) -> Result<DashSet<SocketId>> {
         let namespace = self.get_or_create_namespace(app_id).await;
         namespace.get_channel(channel)
     }
 
-    async fn remove_channel(&mut self, app_id: &str, channel: &str) {
+    async fn remove_channel(&self, app_id: &str, channel: &str) {
         let namespace = self.get_or_create_namespace(app_id).await;
         namespace.remove_channel(channel);
     }
 
     async fn is_in_channel(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
         socket_id: &SocketId,
@@ -205,21 +321,17 @@ impl Adapter for LocalAdapter {
         Ok(namespace.is_in_channel(channel, socket_id))
     }
 
-    async fn get_user_sockets(
-        &mut self,
-        user_id: &str,
-        app_id: &str,
-    ) -> Result<DashSet<WebSocketRef>> {
+    async fn get_user_sockets(&self, user_id: &str, app_id: &str) -> Result<DashSet<WebSocketRef>> {
         let namespace = self.get_or_create_namespace(app_id).await;
         namespace.get_user_sockets(user_id).await
     }
 
-    async fn cleanup_connection(&mut self, app_id: &str, ws: WebSocketRef) {
+    async fn cleanup_connection(&self, app_id: &str, ws: WebSocketRef) {
         let namespace = self.get_or_create_namespace(app_id).await;
         namespace.cleanup_connection(ws).await;
     }
 
-    async fn terminate_connection(&mut self, app_id: &str, user_id: &str) -> Result<()> {
+    async fn terminate_connection(&self, app_id: &str, user_id: &str) -> Result<()> {
         let namespace = self.get_or_create_namespace(app_id).await;
         if let Err(e) = namespace.terminate_user_connections(user_id).await {
             error!("{}", format!("Failed to terminate adapter: {}", e));
@@ -227,18 +339,18 @@ impl Adapter for LocalAdapter {
         Ok(())
     }
 
-    async fn add_channel_to_sockets(&mut self, app_id: &str, channel: &str, socket_id: &SocketId) {
+    async fn add_channel_to_sockets(&self, app_id: &str, channel: &str, socket_id: &SocketId) {
         let namespace = self.get_or_create_namespace(app_id).await;
         namespace.add_channel_to_socket(channel, socket_id);
     }
 
-    async fn get_channel_socket_count(&mut self, app_id: &str, channel: &str) -> usize {
+    async fn get_channel_socket_count(&self, app_id: &str, channel: &str) -> usize {
         let namespace = self.get_or_create_namespace(app_id).await;
         namespace.get_channel_sockets(channel).len()
     }
 
     async fn add_to_channel(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
         socket_id: &SocketId,
@@ -248,7 +360,7 @@ impl Adapter for LocalAdapter {
     }
 
     async fn remove_from_channel(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
         socket_id: &SocketId,
@@ -258,7 +370,7 @@ impl Adapter for LocalAdapter {
     }
 
     async fn get_presence_member(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
         socket_id: &SocketId,
@@ -267,7 +379,7 @@ impl Adapter for LocalAdapter {
         namespace.get_presence_member(channel, socket_id).await
     }
 
-    async fn terminate_user_connections(&mut self, app_id: &str, user_id: &str) -> Result<()> {
+    async fn terminate_user_connections(&self, app_id: &str, user_id: &str) -> Result<()> {
         let namespace = self.get_or_create_namespace(app_id).await;
         if let Err(e) = namespace.terminate_user_connections(user_id).await {
             error!("{}", format!("Failed to terminate user connections: {}", e));
@@ -275,42 +387,101 @@ impl Adapter for LocalAdapter {
         Ok(())
     }
 
-    async fn add_user(&mut self, ws: Arc<Mutex<WebSocket>>) -> Result<()> {
+    async fn add_user(&self, ws: Arc<Mutex<WebSocket>>) -> Result<()> {
         let app_id = ws.lock().await.state.get_app_key();
         let namespace = self.get_namespace(&app_id).await.unwrap();
         namespace.add_user(ws).await
     }
 
-    async fn remove_user(&mut self, ws: Arc<Mutex<WebSocket>>) -> Result<()> {
+    async fn remove_user(&self, ws: Arc<Mutex<WebSocket>>) -> Result<()> {
         let app_id = ws.lock().await.state.get_app_key();
         let namespace = self.get_namespace(&app_id).await.unwrap();
         namespace.remove_user(ws).await
     }
 
-    async fn get_channels_with_socket_count(
-        &mut self,
-        app_id: &str,
-    ) -> Result<DashMap<String, usize>> {
+    async fn get_channels_with_socket_count(&self, app_id: &str) -> Result<DashMap<String, usize>> {
         let namespace = self.get_or_create_namespace(app_id).await;
         let channels = namespace.get_channels_with_socket_count().await;
         Ok(channels?)
     }
 
-    async fn get_sockets_count(&mut self, app_id: &str) -> Result<usize> {
+    async fn get_sockets_count(&self, app_id: &str) -> Result<usize> {
         let namespace = self.get_or_create_namespace(app_id).await;
         let count = namespace.sockets.len();
         Ok(count)
     }
 
-    async fn get_namespaces(&mut self) -> Result<DashMap<String, Arc<Namespace>>> {
-        let mut namespaces = DashMap::new();
+    async fn get_channel_socket_details(
+        &self,
+        app_id: &str,
+        channel: &str,
+        sample: usize,
+    ) -> Result<Vec<SocketDetails>> {
+        let namespace = self.get_or_create_namespace(app_id).await;
+        let details = namespace.get_channel_socket_details(channel).await;
+        Ok(SocketDetails::sample(details, sample))
+    }
+
+    async fn get_namespaces(&self) -> Result<DashMap<String, Arc<Namespace>>> {
+        let namespaces = DashMap::new();
         for entry in self.namespaces.iter() {
             namespaces.insert(entry.key().clone(), entry.value().clone());
         }
         Ok(namespaces)
     }
 
-    fn as_any_mut(&mut self) -> &mut dyn Any {
+    fn set_app_manager(&self, app_manager: Arc<dyn AppManager + Send + Sync>) {
+        let _ = self.app_manager.set(app_manager);
+    }
+
+    fn as_any(&self) -> &dyn Any {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::cached_app_manager::CachedAppManager;
+    use crate::app::config::App;
+    use crate::app::memory_app_manager::MemoryAppManager;
+    use crate::options::CacheSettings;
+
+    #[tokio::test]
+    async fn test_app_disabled_broadcast_drops_cached_app() {
+        let store = Arc::new(MemoryAppManager::new());
+        let app = App {
+            id: "app-1".to_string(),
+            key: "key-1".to_string(),
+            enabled: true,
+            ..Default::default()
+        };
+        store.create_app(app.clone()).await.unwrap();
+        let cached: Arc<dyn AppManager + Send + Sync> = Arc::new(CachedAppManager::new(
+            store.clone(),
+            &CacheSettings::default(),
+        ));
+        assert!(cached.find_by_key("key-1").await.unwrap().unwrap().enabled);
+
+        // Disabled through another node sharing the store, whose broadcast
+        // is all this node sees of the change
+        store
+            .update_app(App {
+                enabled: false,
+                ..app
+            })
+            .await
+            .unwrap();
+        let adapter = LocalAdapter::new();
+        adapter.set_app_manager(cached.clone());
+        let error = Error::ApplicationDisabled;
+        let message = PusherMessage::error(error.close_code(), error.to_string(), None);
+        adapter
+            .send(APP_DISABLED_CHANNEL, message, None, "app-1")
+            .await
+            .unwrap();
+
+        assert!(!cached.find_by_key("key-1").await.unwrap().unwrap().enabled);
+        assert!(!cached.find_by_id("app-1").await.unwrap().unwrap().enabled);
+    }
+}
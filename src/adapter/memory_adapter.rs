@@ -0,0 +1,623 @@
+// src/adapter/memory_adapter.rs
+//! In-process horizontal adapter. Nodes created on the same `MemoryBus`
+//! exchange broadcasts, requests and registry heartbeats over a tokio
+//! broadcast channel instead of a broker, so cross-node behaviour can be
+//! exercised in tests without Redis or NATS.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::adapter::adapter::Adapter;
+use crate::adapter::horizontal_adapter::{
+    BroadcastMessage, HorizontalAdapter, PendingRequest, RequestBody, RequestType, ResponseBody,
+};
+use crate::adapter::local_adapter::LocalAdapter;
+use crate::adapter::registry::NodeInfo;
+use crate::app::manager::AppManager;
+use crate::channel::PresenceMemberInfo;
+use crate::error::{Error, Result};
+use crate::metrics::MetricsInterface;
+use crate::namespace::Namespace;
+use crate::protocol::messages::PusherMessage;
+use crate::websocket::{SocketDetails, SocketId, WebSocket, WebSocketRef};
+use async_trait::async_trait;
+use dashmap::{DashMap, DashSet};
+use fastwebsockets::WebSocketWrite;
+use hyper::upgrade::Upgraded;
+use hyper_util::rt::TokioIo;
+use tokio::io::WriteHalf;
+use tokio::sync::{Mutex, broadcast, mpsc};
+use tracing::{error, warn};
+
+/// Frames published on the bus, already addressed by their `node_id`
+#[derive(Debug, Clone)]
+enum MemoryFrame {
+    Broadcast(BroadcastMessage),
+    Request(RequestBody),
+    Response(ResponseBody),
+}
+
+/// Shared transport of an in-process cluster. Cloning it yields another
+/// handle to the same bus.
+#[derive(Clone)]
+pub struct MemoryBus {
+    sender: broadcast::Sender<MemoryFrame>,
+    /// Nodes currently listening on the bus
+    members: Arc<DashSet<String>>,
+    /// Registry heartbeats, keyed by process ID
+    nodes: Arc<DashMap<String, NodeInfo>>,
+}
+
+impl Default for MemoryBus {
+    fn default() -> Self {
+        Self::new(1024)
+    }
+}
+
+impl MemoryBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self {
+            sender,
+            members: Arc::new(DashSet::new()),
+            nodes: Arc::new(DashMap::new()),
+        }
+    }
+
+    fn publish(&self, frame: MemoryFrame) {
+        // Sending only fails when nobody listens, which is fine for a lone node
+        let _ = self.sender.send(frame);
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.members.len()
+    }
+}
+
+/// Horizontal adapter backed by a `MemoryBus`
+pub struct MemoryAdapter {
+    /// Base horizontal adapter. Only cross-node requests take this lock.
+    pub horizontal: Arc<Mutex<HorizontalAdapter>>,
+
+    /// Local connection registry, shared with the horizontal adapter
+    pub local_adapter: Arc<LocalAdapter>,
+
+    /// Unique ID of this node, copied from the horizontal adapter
+    pub node_id: String,
+
+    pub bus: MemoryBus,
+
+    /// Shared with the horizontal adapter so responses can be recorded while
+    /// a request holds its lock
+    pending_requests: Arc<DashMap<String, PendingRequest>>,
+}
+
+impl MemoryAdapter {
+    pub fn new(bus: MemoryBus, request_timeout_ms: u64) -> Self {
+        let mut horizontal = HorizontalAdapter::new();
+        horizontal.requests_timeout = request_timeout_ms;
+
+        let local_adapter = horizontal.local_adapter.clone();
+        let node_id = horizontal.node_id.clone();
+        let pending_requests = horizontal.pending_requests.clone();
+
+        Self {
+            horizontal: Arc::new(Mutex::new(horizontal)),
+            local_adapter,
+            node_id,
+            bus,
+            pending_requests,
+        }
+    }
+
+    /// Start consuming the bus and join the cluster
+    pub async fn start(&self) {
+        let (request_tx, request_rx) = mpsc::unbounded_channel();
+        {
+            let mut horizontal = self.horizontal.lock().await;
+            horizontal.start_request_cleanup();
+            horizontal.request_publisher = Some(request_tx);
+        }
+
+        self.start_request_forwarder(request_rx);
+        self.start_listener();
+        self.bus.members.insert(self.node_id.clone());
+    }
+
+    /// Leave the cluster. Other nodes stop expecting responses from this one;
+    /// the local sockets are left untouched.
+    pub fn stop(&self) {
+        self.bus.members.remove(&self.node_id);
+    }
+
+    fn start_request_forwarder(&self, mut request_rx: mpsc::UnboundedReceiver<String>) {
+        let bus = self.bus.clone();
+        tokio::spawn(async move {
+            while let Some(request_json) = request_rx.recv().await {
+                match serde_json::from_str::<RequestBody>(&request_json) {
+                    Ok(request) => bus.publish(MemoryFrame::Request(request)),
+                    Err(e) => error!("{}", format!("Failed to deserialize request: {}", e)),
+                }
+            }
+        });
+    }
+
+    fn start_listener(&self) {
+        let mut receiver = self.bus.sender.subscribe();
+        let bus = self.bus.clone();
+        let local_adapter = self.local_adapter.clone();
+        let node_id = self.node_id.clone();
+        let pending_requests = self.pending_requests.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let frame = match receiver.recv().await {
+                    Ok(frame) => frame,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!(
+                            "{}",
+                            format!("Memory adapter {} skipped {} frames", node_id, skipped)
+                        );
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                // A node that left the cluster no longer takes part in it
+                if !bus.members.contains(&node_id) {
+                    continue;
+                }
+                match frame {
+                    MemoryFrame::Broadcast(broadcast) => {
+                        if broadcast.node_id == node_id {
+                            continue;
+                        }
+                        match serde_json::from_str(&broadcast.message) {
+                            Ok(message) => {
+                                let except_id = broadcast.except_socket_id.map(SocketId);
+                                let _ = local_adapter
+                                    .deliver(
+                                        &broadcast.channel,
+                                        message,
+                                        except_id.as_ref(),
+                                        &broadcast.app_id,
+                                        broadcast.published_at,
+                                    )
+                                    .await;
+                            }
+                            Err(e) => warn!(
+                                "{}",
+                                format!("Failed to deserialize broadcast message: {}", e)
+                            ),
+                        }
+                    }
+                    MemoryFrame::Request(request) => {
+                        if request.node_id == node_id {
+                            continue;
+                        }
+                        if let Ok(response) =
+                            HorizontalAdapter::build_response(&local_adapter, &node_id, request)
+                                .await
+                        {
+                            bus.publish(MemoryFrame::Response(response));
+                        }
+                    }
+                    MemoryFrame::Response(response) => {
+                        if response.node_id != node_id {
+                            HorizontalAdapter::record_response(&pending_requests, response);
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    pub async fn get_node_count(&self) -> Result<usize> {
+        Ok(self.bus.node_count().max(1))
+    }
+
+    /// Send a request to the other nodes if there are any
+    async fn request(
+        &self,
+        app_id: &str,
+        request_type: RequestType,
+        channel: Option<&str>,
+        socket_id: Option<&str>,
+        user_id: Option<&str>,
+    ) -> Result<Option<ResponseBody>> {
+        let node_count = self.get_node_count().await?;
+        if node_count <= 1 {
+            return Ok(None);
+        }
+        self.horizontal
+            .lock()
+            .await
+            .send_request(
+                app_id,
+                request_type,
+                channel,
+                socket_id,
+                user_id,
+                node_count,
+            )
+            .await
+            .map(Some)
+    }
+}
+
+#[async_trait]
+impl Adapter for MemoryAdapter {
+    async fn init(&self) {
+        self.local_adapter.init().await;
+        self.start().await;
+    }
+
+    async fn get_namespace(&self, app_id: &str) -> Option<Arc<Namespace>> {
+        self.local_adapter.get_namespace(app_id).await
+    }
+
+    async fn add_socket(
+        &self,
+        socket_id: SocketId,
+        socket: WebSocketWrite<WriteHalf<TokioIo<Upgraded>>>,
+        app_id: &str,
+        app_manager: &Arc<dyn AppManager + Send + Sync>,
+    ) -> Result<()> {
+        self.local_adapter
+            .add_socket(socket_id, socket, app_id, app_manager)
+            .await
+    }
+
+    async fn get_connection(
+        &self,
+        socket_id: &SocketId,
+        app_id: &str,
+    ) -> Option<Arc<Mutex<WebSocket>>> {
+        self.local_adapter.get_connection(socket_id, app_id).await
+    }
+
+    async fn remove_connection(&self, socket_id: &SocketId, app_id: &str) -> Result<()> {
+        self.local_adapter
+            .remove_connection(socket_id, app_id)
+            .await
+    }
+
+    async fn send_message(
+        &self,
+        app_id: &str,
+        socket_id: &SocketId,
+        message: PusherMessage,
+    ) -> Result<()> {
+        self.local_adapter
+            .send_message(app_id, socket_id, message)
+            .await
+    }
+
+    async fn send(
+        &self,
+        channel: &str,
+        message: PusherMessage,
+        except: Option<&SocketId>,
+        app_id: &str,
+    ) -> Result<()> {
+        let published_at = chrono::Utc::now().timestamp_millis();
+        if let Err(e) = self
+            .local_adapter
+            .send(channel, message.clone(), except, app_id)
+            .await
+        {
+            warn!(
+                "{}",
+                format!(
+                    "Local send failed during broadcast for channel {}: {}",
+                    channel, e
+                )
+            );
+        }
+
+        self.bus.publish(MemoryFrame::Broadcast(BroadcastMessage {
+            node_id: self.node_id.clone(),
+            app_id: app_id.to_string(),
+            channel: channel.to_string(),
+            message: serde_json::to_string(&message)?,
+            except_socket_id: except.map(|id| id.0.clone()),
+            published_at: Some(published_at),
+        }));
+        Ok(())
+    }
+
+    async fn get_channel_members(
+        &self,
+        app_id: &str,
+        channel: &str,
+    ) -> Result<HashMap<String, PresenceMemberInfo>> {
+        let mut members = self
+            .local_adapter
+            .get_channel_members(app_id, channel)
+            .await?;
+        if let Some(response) = self
+            .request(
+                app_id,
+                RequestType::ChannelMembers,
+                Some(channel),
+                None,
+                None,
+            )
+            .await?
+        {
+            members.extend(response.members);
+        }
+        Ok(members)
+    }
+
+    async fn get_channel_sockets(
+        &self,
+        app_id: &str,
+        channel: &str,
+    ) -> Result<DashMap<SocketId, Arc<Mutex<WebSocket>>>> {
+        self.local_adapter
+            .get_channel_sockets(app_id, channel)
+            .await
+    }
+
+    async fn get_channel(&self, app_id: &str, channel: &str) -> Result<DashSet<SocketId>> {
+        let result = self.local_adapter.get_channel(app_id, channel).await?;
+        if let Some(response) = self
+            .request(
+                app_id,
+                RequestType::ChannelSockets,
+                Some(channel),
+                None,
+                None,
+            )
+            .await?
+        {
+            for socket_id in response.socket_ids {
+                result.insert(SocketId(socket_id));
+            }
+        }
+        Ok(result)
+    }
+
+    async fn remove_channel(&self, app_id: &str, channel: &str) {
+        self.local_adapter.remove_channel(app_id, channel).await
+    }
+
+    async fn is_in_channel(
+        &self,
+        app_id: &str,
+        channel: &str,
+        socket_id: &SocketId,
+    ) -> Result<bool> {
+        if self
+            .local_adapter
+            .is_in_channel(app_id, channel, socket_id)
+            .await?
+        {
+            return Ok(true);
+        }
+        Ok(self
+            .request(
+                app_id,
+                RequestType::SocketExistsInChannel,
+                Some(channel),
+                Some(&socket_id.0),
+                None,
+            )
+            .await?
+            .is_some_and(|response| response.exists))
+    }
+
+    async fn get_user_sockets(&self, user_id: &str, app_id: &str) -> Result<DashSet<WebSocketRef>> {
+        self.local_adapter.get_user_sockets(user_id, app_id).await
+    }
+
+    async fn cleanup_connection(&self, app_id: &str, ws: WebSocketRef) {
+        self.local_adapter.cleanup_connection(app_id, ws).await
+    }
+
+    async fn terminate_connection(&self, app_id: &str, user_id: &str) -> Result<()> {
+        self.local_adapter
+            .terminate_connection(app_id, user_id)
+            .await?;
+        self.request(
+            app_id,
+            RequestType::TerminateUserConnections,
+            None,
+            None,
+            Some(user_id),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn add_channel_to_sockets(&self, app_id: &str, channel: &str, socket_id: &SocketId) {
+        self.local_adapter
+            .add_channel_to_sockets(app_id, channel, socket_id)
+            .await
+    }
+
+    async fn get_channel_socket_count(&self, app_id: &str, channel: &str) -> usize {
+        let local_count = self
+            .local_adapter
+            .get_channel_socket_count(app_id, channel)
+            .await;
+        match self
+            .request(
+                app_id,
+                RequestType::ChannelSocketsCount,
+                Some(channel),
+                None,
+                None,
+            )
+            .await
+        {
+            Ok(Some(response)) => local_count + response.sockets_count,
+            Ok(None) => local_count,
+            Err(e) => {
+                error!(
+                    "{}",
+                    format!(
+                        "Failed to get remote socket count for channel {}: {}",
+                        channel, e
+                    )
+                );
+                local_count
+            }
+        }
+    }
+
+    async fn add_to_channel(
+        &self,
+        app_id: &str,
+        channel: &str,
+        socket_id: &SocketId,
+    ) -> Result<bool> {
+        self.local_adapter
+            .add_to_channel(app_id, channel, socket_id)
+            .await
+    }
+
+    async fn remove_from_channel(
+        &self,
+        app_id: &str,
+        channel: &str,
+        socket_id: &SocketId,
+    ) -> Result<bool> {
+        self.local_adapter
+            .remove_from_channel(app_id, channel, socket_id)
+            .await
+    }
+
+    async fn get_presence_member(
+        &self,
+        app_id: &str,
+        channel: &str,
+        socket_id: &SocketId,
+    ) -> Option<PresenceMemberInfo> {
+        self.local_adapter
+            .get_presence_member(app_id, channel, socket_id)
+            .await
+    }
+
+    async fn terminate_user_connections(&self, app_id: &str, user_id: &str) -> Result<()> {
+        self.terminate_connection(app_id, user_id).await
+    }
+
+    async fn add_user(&self, ws: Arc<Mutex<WebSocket>>) -> Result<()> {
+        self.local_adapter.add_user(ws).await
+    }
+
+    async fn remove_user(&self, ws: Arc<Mutex<WebSocket>>) -> Result<()> {
+        self.local_adapter.remove_user(ws).await
+    }
+
+    async fn get_channels_with_socket_count(&self, app_id: &str) -> Result<DashMap<String, usize>> {
+        let channels = self
+            .local_adapter
+            .get_channels_with_socket_count(app_id)
+            .await?;
+        if let Some(response) = self
+            .request(
+                app_id,
+                RequestType::ChannelsWithSocketsCount,
+                None,
+                None,
+                None,
+            )
+            .await?
+        {
+            for (channel, count) in response.channels_with_sockets_count {
+                *channels.entry(channel).or_insert(0) += count;
+            }
+        }
+        Ok(channels)
+    }
+
+    async fn get_sockets_count(&self, app_id: &str) -> Result<usize> {
+        let local_count = self.local_adapter.get_sockets_count(app_id).await?;
+        let remote_count = self
+            .request(app_id, RequestType::SocketsCount, None, None, None)
+            .await?
+            .map_or(0, |response| response.sockets_count);
+        Ok(local_count + remote_count)
+    }
+
+    async fn get_channel_socket_details(
+        &self,
+        app_id: &str,
+        channel: &str,
+        sample: usize,
+    ) -> Result<Vec<SocketDetails>> {
+        let node_count = self.get_node_count().await?;
+        let mut details: Vec<SocketDetails> = self
+            .local_adapter
+            .get_channel_socket_details(app_id, channel, sample)
+            .await?
+            .into_iter()
+            .map(|mut details| {
+                details.node_id = Some(self.node_id.clone());
+                details
+            })
+            .collect();
+        if node_count > 1 {
+            let response = self
+                .horizontal
+                .lock()
+                .await
+                .send_request_with_limit(
+                    app_id,
+                    RequestType::ChannelSocketDetails,
+                    channel,
+                    sample,
+                    node_count,
+                )
+                .await?;
+            details.extend(response.socket_details);
+        }
+        Ok(SocketDetails::sample(details, sample))
+    }
+
+    async fn get_namespaces(&self) -> Result<DashMap<String, Arc<Namespace>>> {
+        self.local_adapter.get_namespaces().await
+    }
+
+    async fn health(&self) -> Result<()> {
+        if !self.bus.members.contains(&self.node_id) {
+            return Err(Error::InternalError(
+                "Memory adapter is not attached to its bus".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn register_node(&self, node: &NodeInfo) -> Result<()> {
+        self.bus.nodes.insert(node.process_id.clone(), node.clone());
+        Ok(())
+    }
+
+    async fn registered_nodes(&self) -> Result<Vec<NodeInfo>> {
+        Ok(self
+            .bus
+            .nodes
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect())
+    }
+
+    async fn set_metrics(
+        &self,
+        metrics: Arc<Mutex<dyn MetricsInterface + Send + Sync>>,
+    ) -> Result<()> {
+        self.horizontal.lock().await.metrics = Some(metrics);
+        Ok(())
+    }
+
+    fn set_app_manager(&self, app_manager: Arc<dyn AppManager + Send + Sync>) {
+        self.local_adapter.set_app_manager(app_manager);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
@@ -1,10 +1,28 @@
+pub mod ack;
+pub mod activity;
 pub mod adapter;
+pub mod connection_quota;
+pub mod deadline;
+pub mod drain;
 pub mod factory;
+pub mod failover;
+pub mod gossip_adapter;
 pub mod handler;
+pub mod health;
 pub mod horizontal_adapter;
+pub mod hot_channel;
+pub mod interest;
+pub mod listener;
 pub mod local_adapter;
+#[cfg(test)]
+pub mod memory_adapter;
+pub mod mqtt_adapter;
 pub mod nats_adapter;
+pub mod occupancy;
+pub mod qos;
+pub mod rabbitmq_adapter;
 pub mod redis_adapter;
 pub mod redis_cluster_adapter;
+pub mod registry;
 
 pub use self::{adapter::Adapter, handler::ConnectionHandler};
@@ -0,0 +1,494 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::adapter::adapter::Adapter;
+use crate::adapter::local_adapter::LocalAdapter;
+use crate::app::manager::AppManager;
+use crate::channel::PresenceMemberInfo;
+use crate::error::{Error, Result};
+use async_trait::async_trait;
+use dashmap::{DashMap, DashSet};
+use fastwebsockets::WebSocketWrite;
+use hyper::upgrade::Upgraded;
+use hyper_util::rt::TokioIo;
+use rumqttc::{AsyncClient, Event, EventLoop, MqttOptions, Packet, QoS};
+use serde::Deserialize;
+use tokio::io::WriteHalf;
+use tokio::sync::Mutex;
+use tracing::{debug, error, info, warn};
+
+use crate::namespace::Namespace;
+pub(crate) use crate::options::MqttAdapterConfig;
+use crate::protocol::messages::{MessageData, PusherMessage};
+use crate::websocket::{SocketDetails, SocketId, WebSocket, WebSocketRef};
+
+/// Topic suffix devices publish client events to
+const CLIENT_EVENT_SUFFIX: &str = "client";
+
+/// Client event published by an MQTT device
+#[derive(Debug, Deserialize)]
+struct InboundClientEvent {
+    event: String,
+    #[serde(default)]
+    data: Option<MessageData>,
+}
+
+/// Adapter that mirrors channel broadcasts to an MQTT broker so devices
+/// speaking MQTT receive the same events as WebSocket clients.
+///
+/// Every broadcast is delivered to local sockets and published, as the plain
+/// Pusher message JSON, to `{prefix}/{app_id}/{channel}`. Devices may publish
+/// `{"event": "client-...", "data": ...}` to `{prefix}/{app_id}/{channel}/client`
+/// to send client events on private and presence channels; subscribing to
+/// `{prefix}/{app_id}/{channel}/#` receives both.
+///
+/// The broker is only used as a bridge, not to synchronize Sockudo nodes, so
+/// connection state and channel queries are answered from this node alone.
+pub struct MqttAdapter {
+    /// Local connection registry
+    pub local_adapter: Arc<LocalAdapter>,
+
+    /// Unique ID of this node, used for the default MQTT client ID
+    pub node_id: String,
+
+    /// Configuration
+    pub config: MqttAdapterConfig,
+
+    client: AsyncClient,
+
+    /// Event loop driving the broker connection, taken when the adapter starts
+    event_loop: Mutex<Option<EventLoop>>,
+
+    /// Whether the broker acknowledged the current connection
+    pub connected: Arc<AtomicBool>,
+}
+
+impl MqttAdapter {
+    /// Create a new MQTT bridge adapter. The broker connection is opened by `init`.
+    pub async fn new(config: MqttAdapterConfig) -> Result<Self> {
+        if config.qos > 2 {
+            return Err(Error::ConfigurationError(format!(
+                "Invalid MQTT QoS level {}: expected 0, 1 or 2",
+                config.qos
+            )));
+        }
+
+        let node_id = uuid::Uuid::new_v4().to_string();
+        let client_id = config
+            .client_id
+            .clone()
+            .unwrap_or_else(|| format!("sockudo-{}", node_id));
+        info!(
+            "{}",
+            format!(
+                "MQTT adapter config: broker={}:{}, client_id={}, prefix={}, qos={}",
+                config.host, config.port, client_id, config.topic_prefix, config.qos
+            )
+        );
+
+        let mut options = MqttOptions::new(client_id, config.host.clone(), config.port);
+        options.set_keep_alive(Duration::from_secs(config.keep_alive_secs.max(5)));
+        if let Some(username) = &config.username {
+            options.set_credentials(
+                username.clone(),
+                config.password.clone().unwrap_or_default(),
+            );
+        }
+
+        let (client, event_loop) =
+            AsyncClient::new(options, config.request_channel_capacity.max(1));
+
+        Ok(Self {
+            local_adapter: Arc::new(LocalAdapter::new()),
+            node_id,
+            config,
+            client,
+            event_loop: Mutex::new(Some(event_loop)),
+            connected: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    fn qos(&self) -> QoS {
+        match self.config.qos {
+            0 => QoS::AtMostOnce,
+            2 => QoS::ExactlyOnce,
+            _ => QoS::AtLeastOnce,
+        }
+    }
+
+    /// Topic broadcasts for a channel are published to
+    pub fn channel_topic(&self, app_id: &str, channel: &str) -> String {
+        format!("{}/{}/{}", self.config.topic_prefix, app_id, channel)
+    }
+
+    /// Poll the broker connection. `rumqttc` reconnects on the next poll after
+    /// an error, so the loop only has to keep polling and resubscribe once the
+    /// broker acknowledges the new connection.
+    async fn start_event_loop(&self) -> Result<()> {
+        let Some(mut event_loop) = self.event_loop.lock().await.take() else {
+            return Ok(());
+        };
+
+        let client = self.client.clone();
+        let connected = self.connected.clone();
+        let local_adapter = self.local_adapter.clone();
+        let prefix = self.config.topic_prefix.clone();
+        let client_events = self.config.client_events;
+        let qos = self.qos();
+        let filter = format!("{}/+/+/{}", prefix, CLIENT_EVENT_SUFFIX);
+
+        tokio::spawn(async move {
+            loop {
+                match event_loop.poll().await {
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                        connected.store(true, Ordering::SeqCst);
+                        info!("{}", "Connected to MQTT broker");
+                        if !client_events {
+                            continue;
+                        }
+                        if let Err(e) = client.try_subscribe(filter.clone(), qos) {
+                            error!("{}", format!("Failed to subscribe to {}: {}", filter, e));
+                        }
+                    }
+                    Ok(Event::Incoming(Packet::Publish(publish))) => {
+                        Self::handle_client_event(
+                            &local_adapter,
+                            &prefix,
+                            &publish.topic,
+                            &publish.payload,
+                        )
+                        .await;
+                    }
+                    Ok(Event::Incoming(Packet::Disconnect)) => {
+                        connected.store(false, Ordering::SeqCst);
+                        warn!("{}", "MQTT broker closed the connection");
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        if connected.swap(false, Ordering::SeqCst) {
+                            warn!("{}", format!("MQTT connection lost: {}", e));
+                        } else {
+                            debug!("{}", format!("MQTT connection attempt failed: {}", e));
+                        }
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Deliver a client event published by a device to the local sockets
+    async fn handle_client_event(
+        local_adapter: &LocalAdapter,
+        prefix: &str,
+        topic: &str,
+        payload: &[u8],
+    ) {
+        let Some((app_id, channel)) = topic
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.strip_prefix('/'))
+            .and_then(|rest| rest.strip_suffix(CLIENT_EVENT_SUFFIX))
+            .and_then(|rest| rest.strip_suffix('/'))
+            .and_then(|rest| rest.split_once('/'))
+        else {
+            debug!("{}", format!("Ignoring MQTT message on topic {}", topic));
+            return;
+        };
+
+        let inbound = match serde_json::from_slice::<InboundClientEvent>(payload) {
+            Ok(inbound) => inbound,
+            Err(e) => {
+                warn!(
+                    "{}",
+                    format!("Invalid client event on MQTT topic {}: {}", topic, e)
+                );
+                return;
+            }
+        };
+
+        // Same rules as client events sent over WebSocket
+        if !inbound.event.starts_with("client-") {
+            warn!(
+                "{}",
+                format!(
+                    "Dropping MQTT client event {}: name must start with client-",
+                    inbound.event
+                )
+            );
+            return;
+        }
+        if !channel.starts_with("private-") && !channel.starts_with("presence-") {
+            warn!(
+                "{}",
+                format!(
+                    "Dropping MQTT client event on {}: only private and presence channels accept client events",
+                    channel
+                )
+            );
+            return;
+        }
+
+        let message = PusherMessage {
+            channel: Some(channel.to_string()),
+            name: None,
+            event: Some(inbound.event),
+            data: inbound.data,
+        };
+        if let Err(e) = local_adapter.send(channel, message, None, app_id).await {
+            warn!(
+                "{}",
+                format!("Failed to deliver MQTT client event on {}: {}", channel, e)
+            );
+        }
+    }
+}
+
+#[async_trait]
+impl Adapter for MqttAdapter {
+    async fn init(&self) {
+        self.local_adapter.init().await;
+
+        if let Err(e) = self.start_event_loop().await {
+            error!("{}", format!("Failed to start MQTT adapter: {}", e));
+        }
+    }
+
+    async fn get_namespace(&self, app_id: &str) -> Option<Arc<Namespace>> {
+        self.local_adapter.get_namespace(app_id).await
+    }
+
+    async fn add_socket(
+        &self,
+        socket_id: SocketId,
+        socket: WebSocketWrite<WriteHalf<TokioIo<Upgraded>>>,
+        app_id: &str,
+        app_manager: &Arc<dyn AppManager + Send + Sync>,
+    ) -> Result<()> {
+        self.local_adapter
+            .add_socket(socket_id, socket, app_id, app_manager)
+            .await
+    }
+
+    async fn get_connection(
+        &self,
+        socket_id: &SocketId,
+        app_id: &str,
+    ) -> Option<Arc<Mutex<WebSocket>>> {
+        self.local_adapter.get_connection(socket_id, app_id).await
+    }
+
+    async fn remove_connection(&self, socket_id: &SocketId, app_id: &str) -> Result<()> {
+        self.local_adapter
+            .remove_connection(socket_id, app_id)
+            .await
+    }
+
+    async fn send_message(
+        &self,
+        app_id: &str,
+        socket_id: &SocketId,
+        message: PusherMessage,
+    ) -> Result<()> {
+        self.local_adapter
+            .send_message(app_id, socket_id, message)
+            .await
+    }
+
+    async fn send(
+        &self,
+        channel: &str,
+        message: PusherMessage,
+        except: Option<&SocketId>,
+        app_id: &str,
+    ) -> Result<()> {
+        // 1. Deliver to local sockets
+        if let Err(e) = self
+            .local_adapter
+            .send(channel, message.clone(), except, app_id)
+            .await
+        {
+            warn!(
+                "{}",
+                format!(
+                    "Local send failed during broadcast for channel {}: {}",
+                    channel, e
+                )
+            );
+        }
+
+        // 2. Mirror it to the broker. Queue without waiting so a slow or
+        // unreachable broker never stalls WebSocket delivery.
+        let payload = serde_json::to_vec(&message)?;
+        let topic = self.channel_topic(app_id, channel);
+        if let Err(e) = self
+            .client
+            .try_publish(topic, self.qos(), self.config.retain, payload)
+        {
+            warn!(
+                "{}",
+                format!(
+                    "Failed to queue MQTT publish for channel {}: {}",
+                    channel, e
+                )
+            );
+        }
+        Ok(())
+    }
+
+    async fn get_channel_members(
+        &self,
+        app_id: &str,
+        channel: &str,
+    ) -> Result<HashMap<String, PresenceMemberInfo>> {
+        self.local_adapter
+            .get_channel_members(app_id, channel)
+            .await
+    }
+
+    async fn get_channel_sockets(
+        &self,
+        app_id: &str,
+        channel: &str,
+    ) -> Result<DashMap<SocketId, Arc<Mutex<WebSocket>>>> {
+        self.local_adapter
+            .get_channel_sockets(app_id, channel)
+            .await
+    }
+
+    async fn get_channel(&self, app_id: &str, channel: &str) -> Result<DashSet<SocketId>> {
+        self.local_adapter.get_channel(app_id, channel).await
+    }
+
+    async fn remove_channel(&self, app_id: &str, channel: &str) {
+        self.local_adapter.remove_channel(app_id, channel).await
+    }
+
+    async fn is_in_channel(
+        &self,
+        app_id: &str,
+        channel: &str,
+        socket_id: &SocketId,
+    ) -> Result<bool> {
+        self.local_adapter
+            .is_in_channel(app_id, channel, socket_id)
+            .await
+    }
+
+    async fn get_user_sockets(&self, user_id: &str, app_id: &str) -> Result<DashSet<WebSocketRef>> {
+        self.local_adapter.get_user_sockets(user_id, app_id).await
+    }
+
+    async fn cleanup_connection(&self, app_id: &str, ws: WebSocketRef) {
+        self.local_adapter.cleanup_connection(app_id, ws).await
+    }
+
+    async fn terminate_connection(&self, app_id: &str, user_id: &str) -> Result<()> {
+        self.local_adapter
+            .terminate_connection(app_id, user_id)
+            .await
+    }
+
+    async fn add_channel_to_sockets(&self, app_id: &str, channel: &str, socket_id: &SocketId) {
+        self.local_adapter
+            .add_channel_to_sockets(app_id, channel, socket_id)
+            .await
+    }
+
+    async fn get_channel_socket_count(&self, app_id: &str, channel: &str) -> usize {
+        self.local_adapter
+            .get_channel_socket_count(app_id, channel)
+            .await
+    }
+
+    async fn add_to_channel(
+        &self,
+        app_id: &str,
+        channel: &str,
+        socket_id: &SocketId,
+    ) -> Result<bool> {
+        self.local_adapter
+            .add_to_channel(app_id, channel, socket_id)
+            .await
+    }
+
+    async fn remove_from_channel(
+        &self,
+        app_id: &str,
+        channel: &str,
+        socket_id: &SocketId,
+    ) -> Result<bool> {
+        self.local_adapter
+            .remove_from_channel(app_id, channel, socket_id)
+            .await
+    }
+
+    async fn get_presence_member(
+        &self,
+        app_id: &str,
+        channel: &str,
+        socket_id: &SocketId,
+    ) -> Option<PresenceMemberInfo> {
+        self.local_adapter
+            .get_presence_member(app_id, channel, socket_id)
+            .await
+    }
+
+    async fn terminate_user_connections(&self, app_id: &str, user_id: &str) -> Result<()> {
+        self.terminate_connection(app_id, user_id).await
+    }
+
+    async fn add_user(&self, ws: Arc<Mutex<WebSocket>>) -> Result<()> {
+        self.local_adapter.add_user(ws).await
+    }
+
+    async fn remove_user(&self, ws: Arc<Mutex<WebSocket>>) -> Result<()> {
+        self.local_adapter.remove_user(ws).await
+    }
+
+    async fn get_channels_with_socket_count(&self, app_id: &str) -> Result<DashMap<String, usize>> {
+        self.local_adapter
+            .get_channels_with_socket_count(app_id)
+            .await
+    }
+
+    async fn get_sockets_count(&self, app_id: &str) -> Result<usize> {
+        self.local_adapter.get_sockets_count(app_id).await
+    }
+
+    async fn get_channel_socket_details(
+        &self,
+        app_id: &str,
+        channel: &str,
+        sample: usize,
+    ) -> Result<Vec<SocketDetails>> {
+        self.local_adapter
+            .get_channel_socket_details(app_id, channel, sample)
+            .await
+    }
+
+    async fn get_namespaces(&self) -> Result<DashMap<String, Arc<Namespace>>> {
+        self.local_adapter.get_namespaces().await
+    }
+
+    async fn health(&self) -> Result<()> {
+        if !self.connected.load(Ordering::SeqCst) {
+            return Err(Error::InternalError(
+                "MQTT broker is not connected".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn set_app_manager(&self, app_manager: Arc<dyn AppManager + Send + Sync>) {
+        self.local_adapter.set_app_manager(app_manager);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
@@ -5,11 +5,16 @@ use std::time::Duration;
 
 use crate::adapter::adapter::Adapter;
 use crate::adapter::horizontal_adapter::{
-    BroadcastMessage, HorizontalAdapter, RequestBody, RequestType, ResponseBody,
+    BroadcastCodec, BroadcastMessage, HorizontalAdapter, RequestBody, RequestType, ResponseBody,
 };
+use crate::adapter::interest::{ChannelInterest, InterestAnnouncement};
+use crate::adapter::listener::ListenerState;
+use crate::adapter::local_adapter::LocalAdapter;
+use crate::adapter::registry::NodeInfo;
 use crate::app::manager::AppManager;
 use crate::channel::PresenceMemberInfo;
 use crate::error::{Error, Result};
+use async_nats::connection::State as NatsConnectionState;
 use async_nats::{Client as NatsClient, ConnectOptions as NatsOptions, Subject};
 use async_trait::async_trait;
 use dashmap::{DashMap, DashSet};
@@ -19,27 +24,38 @@ use hyper::upgrade::Upgraded;
 use hyper_util::rt::TokioIo;
 use tokio::io::WriteHalf;
 use tokio::sync::Mutex;
+use tokio::task::AbortHandle;
 use tracing::{error, info, warn};
 
 use crate::metrics::MetricsInterface;
 use crate::namespace::Namespace;
 pub(crate) use crate::options::NatsAdapterConfig;
 use crate::protocol::messages::PusherMessage;
-use crate::websocket::{SocketId, WebSocket, WebSocketRef};
+use crate::utils::is_app_wide_channel;
+use crate::websocket::{SocketDetails, SocketId, WebSocket, WebSocketRef};
 
 /// NATS channels/subjects
 pub const DEFAULT_PREFIX: &str = "sockudo";
 const BROADCAST_SUFFIX: &str = ".broadcast";
 const REQUESTS_SUFFIX: &str = ".requests";
 const RESPONSES_SUFFIX: &str = ".responses";
+const INTEREST_SUFFIX: &str = ".interest";
+const NODES_SUFFIX: &str = ".nodes";
 
 /// NATS adapter configuration
 
 /// NATS adapter for horizontal scaling
 pub struct NatsAdapter {
-    /// Base horizontal adapter (protected by a Mutex)
+    /// Base horizontal adapter (protected by a Mutex). Only cross-node requests
+    /// take this lock; local operations go through `local_adapter`.
     pub horizontal: Arc<Mutex<HorizontalAdapter>>,
 
+    /// Local connection registry, shared with the horizontal adapter
+    pub local_adapter: Arc<LocalAdapter>,
+
+    /// Unique ID of this node, copied from the horizontal adapter
+    pub node_id: String,
+
     /// NATS client
     pub client: NatsClient,
 
@@ -51,6 +67,23 @@ pub struct NatsAdapter {
 
     /// Configuration
     pub config: NatsAdapterConfig,
+
+    /// Whether the subject listener tasks are stopped, subscribing or all running
+    pub listener: Arc<ListenerState>,
+
+    /// Encodes (and optionally compresses) outgoing broadcasts
+    pub codec: BroadcastCodec,
+
+    /// Per-shard broadcast subjects, when channel interest tracking is enabled
+    pub interest: Option<Arc<ChannelInterest>>,
+    pub interest_subject: String,
+
+    /// Listener tasks of the shard subjects this node consumes
+    shard_subscriptions: Arc<DashMap<u32, AbortHandle>>,
+
+    /// Node registry heartbeats, including our own, keyed by process ID
+    pub nodes_subject: String,
+    nodes: Arc<DashMap<String, NodeInfo>>,
 }
 
 impl NatsAdapter {
@@ -58,30 +91,18 @@ impl NatsAdapter {
     pub async fn new(config: NatsAdapterConfig) -> Result<Self> {
         // Create the base horizontal adapter
         let mut horizontal = HorizontalAdapter::new();
-        info!("{}", format!("NATS adapter config: {:?}", config)); // Borrows config temporarily
+        info!(
+            "{}",
+            format!(
+                "NATS adapter config: servers={:?}, prefix={}, tls={}",
+                config.servers, config.prefix, config.tls.enabled
+            )
+        );
 
         // Set timeout
         horizontal.requests_timeout = config.request_timeout_ms; // Accesses field (likely Copy)
 
-        // --- Build NATS Options ---
-        let mut nats_options = NatsOptions::new();
-
-        // Set credentials conditionally: Prefer user/password if both are provided
-        // Use as_deref() assuming the methods take &str.
-        // If they require owned String, use .cloned() instead.
-        if let (Some(username), Some(password)) =
-            (config.username.as_deref(), config.password.as_deref())
-        {
-            nats_options =
-                nats_options.user_and_password(username.to_string(), password.parse().unwrap());
-        } else if let Some(token) = config.token.as_deref() {
-            nats_options = nats_options.token(token.parse().unwrap());
-        };
-        // Note: If both user/pass and token are None, no credentials are set.
-
-        // Set connection timeout
-        nats_options =
-            nats_options.connection_timeout(Duration::from_millis(config.connection_timeout_ms));
+        let nats_options = Self::connect_options(&config).await?;
 
         // --- Connect to NATS ---
         // Assuming connect takes a reference to the servers string/list (e.g., &str or &[String])
@@ -95,10 +116,17 @@ impl NatsAdapter {
         let broadcast_subject = format!("{}{}", config.prefix, BROADCAST_SUFFIX);
         let request_subject = format!("{}{}", config.prefix, REQUESTS_SUFFIX);
         let response_subject = format!("{}{}", config.prefix, RESPONSES_SUFFIX);
+        let interest_subject = format!("{}{}", config.prefix, INTEREST_SUFFIX);
+        let nodes_subject = format!("{}{}", config.prefix, NODES_SUFFIX);
+
+        let local_adapter = horizontal.local_adapter.clone();
+        let node_id = horizontal.node_id.clone();
 
         // Create the adapter instance
         let adapter = Self {
             horizontal: Arc::new(Mutex::new(horizontal)),
+            local_adapter,
+            node_id,
             client,
             // Clone prefix again for storing in the struct
             prefix: config.prefix.clone(),
@@ -107,11 +135,73 @@ impl NatsAdapter {
             response_subject,
             // Clone the entire config *once* here to store it
             config: config.clone(),
+            listener: Arc::new(ListenerState::default()),
+            codec: BroadcastCodec::default(),
+            interest: None,
+            interest_subject,
+            shard_subscriptions: Arc::new(DashMap::new()),
+            nodes_subject,
+            nodes: Arc::new(DashMap::new()),
         };
 
         Ok(adapter)
     }
 
+    /// Build client options from the configured credentials, TLS and buffer
+    /// settings. Only one authentication method is applied, in order of
+    /// precedence: credentials file, NKey seed, user/password, token.
+    async fn connect_options(config: &NatsAdapterConfig) -> Result<NatsOptions> {
+        let mut nats_options = if let Some(path) = config.credentials_file.as_deref() {
+            NatsOptions::with_credentials_file(path)
+                .await
+                .map_err(|e| {
+                    Error::ConfigurationError(format!(
+                        "Failed to load NATS credentials file {}: {}",
+                        path, e
+                    ))
+                })?
+        } else if let Some(seed) = config.nkey_seed.as_deref() {
+            NatsOptions::with_nkey(seed.to_string())
+        } else if let (Some(username), Some(password)) =
+            (config.username.as_deref(), config.password.as_deref())
+        {
+            NatsOptions::with_user_and_password(username.to_string(), password.to_string())
+        } else if let Some(token) = config.token.as_deref() {
+            NatsOptions::with_token(token.to_string())
+        } else {
+            NatsOptions::new()
+        };
+
+        if config.tls.enabled {
+            nats_options = nats_options.require_tls(true);
+        }
+        if config.tls.tls_first {
+            nats_options = nats_options.tls_first();
+        }
+        if let Some(ca_cert_path) = config.tls.ca_cert_path.as_deref() {
+            nats_options = nats_options.add_root_certificates(ca_cert_path.into());
+        }
+        match (&config.tls.client_cert_path, &config.tls.client_key_path) {
+            (Some(cert_path), Some(key_path)) => {
+                nats_options =
+                    nats_options.add_client_certificate(cert_path.into(), key_path.into());
+            }
+            (None, None) => {}
+            _ => {
+                return Err(Error::ConfigurationError(
+                    "NATS TLS client_cert_path and client_key_path must be set together"
+                        .to_string(),
+                ));
+            }
+        }
+
+        Ok(nats_options
+            .connection_timeout(Duration::from_millis(config.connection_timeout_ms))
+            .client_capacity(config.reconnect_buffer_size.max(1))
+            .subscription_capacity(config.subscription_capacity.max(1))
+            .max_reconnects(config.max_reconnects))
+    }
+
     /// Create a new NATS adapter with simple configuration
     pub async fn with_servers(servers: Vec<String>) -> Result<Self> {
         let config = NatsAdapterConfig {
@@ -132,24 +222,206 @@ impl NatsAdapter {
 
         // Start NATS listeners
         self.start_subject_listeners().await?;
+        self.start_interest_task();
 
         Ok(())
     }
 
+    /// Subject carrying broadcasts for one channel shard
+    fn shard_subject(broadcast_subject: &str, shard: u32) -> String {
+        format!("{}.{}", broadcast_subject, shard)
+    }
+
+    /// Deliver a broadcast from another node to the local sockets
+    async fn deliver_broadcast(local_adapter: &LocalAdapter, node_id: &str, payload: &[u8]) {
+        match BroadcastCodec::decode(payload) {
+            Ok(broadcast) => {
+                // Skip our own messages
+                if broadcast.node_id == node_id {
+                    return;
+                }
+                // Process the broadcast
+                match serde_json::from_str(&broadcast.message) {
+                    Ok(message) => {
+                        let except_id = broadcast
+                            .except_socket_id
+                            .as_ref()
+                            .map(|id| SocketId(id.clone()));
+                        // Deliver to local sockets without taking the horizontal lock
+                        let _ = local_adapter
+                            .deliver(
+                                &broadcast.channel,
+                                message,
+                                except_id.as_ref(),
+                                &broadcast.app_id,
+                                broadcast.published_at,
+                            )
+                            .await;
+                    }
+                    Err(e) => {
+                        warn!(
+                            "{}",
+                            format!(
+                                "Failed to deserialize broadcast inner message: {}, Payload: {}",
+                                e, broadcast.message
+                            )
+                        );
+                    }
+                }
+            }
+            Err(e) => {
+                warn!(
+                    "{}",
+                    format!(
+                        "Failed to deserialize broadcast message: {}, Payload: {:?}",
+                        e, payload
+                    )
+                );
+            }
+        }
+    }
+
+    /// Subscribe to a shard subject, replacing any previous listener for it
+    async fn subscribe_shard(
+        client: &NatsClient,
+        broadcast_subject: &str,
+        shard: u32,
+        local_adapter: Arc<LocalAdapter>,
+        node_id: String,
+        subscriptions: &DashMap<u32, AbortHandle>,
+    ) -> Result<()> {
+        let subject = Self::shard_subject(broadcast_subject, shard);
+        let mut subscription = client
+            .subscribe(Subject::from(subject.clone()))
+            .await
+            .map_err(|e| {
+                Error::InternalError(format!("Failed to subscribe to {}: {}", subject, e))
+            })?;
+        let task = tokio::spawn(async move {
+            while let Some(msg) = subscription.next().await {
+                Self::deliver_broadcast(&local_adapter, &node_id, &msg.payload).await;
+            }
+        });
+        if let Some(previous) = subscriptions.insert(shard, task.abort_handle()) {
+            previous.abort();
+        }
+        Ok(())
+    }
+
+    /// Periodically drop shards without local subscribers and re-announce
+    /// this node's interest so other nodes keep publishing to it
+    fn start_interest_task(&self) {
+        let Some(interest) = self.interest.clone() else {
+            return;
+        };
+        let client = self.client.clone();
+        let local_adapter = self.local_adapter.clone();
+        let node_id = self.node_id.clone();
+        let broadcast_subject = self.broadcast_subject.clone();
+        let interest_subject = self.interest_subject.clone();
+        let subscriptions = self.shard_subscriptions.clone();
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interest.announce_interval());
+            loop {
+                ticker.tick().await;
+                for shard in interest.release_idle(&local_adapter) {
+                    // Dropping the subscriber unsubscribes from the subject
+                    if let Some((_, task)) = subscriptions.remove(&shard) {
+                        task.abort();
+                    }
+                    // Claimed again while we were releasing it
+                    if interest.is_local(shard) {
+                        if let Err(e) = Self::subscribe_shard(
+                            &client,
+                            &broadcast_subject,
+                            shard,
+                            local_adapter.clone(),
+                            node_id.clone(),
+                            &subscriptions,
+                        )
+                        .await
+                        {
+                            warn!("{}", e);
+                        }
+                    }
+                }
+                Self::announce_interest(&client, &interest_subject, &interest).await;
+            }
+        });
+    }
+
+    async fn announce_interest(
+        client: &NatsClient,
+        interest_subject: &str,
+        interest: &ChannelInterest,
+    ) {
+        let announcement = match serde_json::to_vec(&interest.announcement()) {
+            Ok(announcement) => announcement,
+            Err(e) => {
+                error!("{}", format!("Failed to serialize interest: {}", e));
+                return;
+            }
+        };
+        if let Err(e) = client
+            .publish(
+                Subject::from(interest_subject.to_string()),
+                announcement.into(),
+            )
+            .await
+        {
+            warn!("{}", format!("Failed to announce channel interest: {}", e));
+        }
+    }
+
+    /// Start consuming the shard of a channel that just gained a local subscriber
+    async fn claim_shard(&self, app_id: &str, channel: &str) {
+        let Some(interest) = &self.interest else {
+            return;
+        };
+        let Some(shard) = interest.claim(app_id, channel) else {
+            return;
+        };
+        if let Err(e) = Self::subscribe_shard(
+            &self.client,
+            &self.broadcast_subject,
+            shard,
+            self.local_adapter.clone(),
+            self.node_id.clone(),
+            &self.shard_subscriptions,
+        )
+        .await
+        {
+            warn!("{}", e);
+        }
+        Self::announce_interest(&self.client, &self.interest_subject, interest).await;
+    }
+
     /// Start subject listeners for NATS
     async fn start_subject_listeners(&self) -> Result<()> {
+        // Another start may still be subscribing, or the listeners are up
+        if !self.listener.try_start() {
+            return Ok(());
+        }
+
+        let result = self.subscribe_subjects().await;
+        if result.is_err() {
+            self.listener.stopped();
+        }
+        result
+    }
+
+    /// Subscribe to the adapter subjects and spawn a listener task for each
+    async fn subscribe_subjects(&self) -> Result<()> {
         // Clone needed values for the async task
         let horizontal_arc = self.horizontal.clone();
+        let local_adapter = self.local_adapter.clone();
         let nats_client = self.client.clone();
         let broadcast_subject = self.broadcast_subject.clone();
         let request_subject = self.request_subject.clone();
         let response_subject = self.response_subject.clone();
 
-        // Get node_id without holding the lock for the whole setup
-        let node_id = {
-            let horizontal_lock = horizontal_arc.lock().await;
-            horizontal_lock.node_id.clone()
-        }; // Lock released
+        let node_id = self.node_id.clone();
 
         // Subscribe to broadcast channel
         let mut broadcast_subscription = nats_client
@@ -175,6 +447,7 @@ impl NatsAdapter {
                 Error::InternalError(format!("Failed to subscribe to response subject: {}", e))
             })?;
 
+        self.listener.running();
         info!(
             "{}",
             format!(
@@ -184,67 +457,82 @@ impl NatsAdapter {
         );
 
         // Spawn a task to handle broadcast messages
-        let broadcast_horizontal = horizontal_arc.clone();
+        let broadcast_local_adapter = local_adapter.clone();
+        let broadcast_listener = self.listener.clone();
         let broadcast_node_id = node_id.clone();
-        tokio::spawn(async move {
+        let task = tokio::spawn(async move {
             while let Some(msg) = broadcast_subscription.next().await {
-                match serde_json::from_slice::<BroadcastMessage>(&msg.payload) {
-                    Ok(broadcast) => {
-                        // Skip our own messages
-                        if broadcast.node_id == broadcast_node_id {
-                            continue;
-                        }
-                        // Process the broadcast
-                        match serde_json::from_str(&broadcast.message) {
-                            Ok(message) => {
-                                let except_id = broadcast
-                                    .except_socket_id
-                                    .as_ref()
-                                    .map(|id| SocketId(id.clone()));
-                                // Lock only when interacting with local adapter
-                                let mut horizontal_lock = broadcast_horizontal.lock().await;
-                                horizontal_lock
-                                    .local_adapter
-                                    .send(
-                                        &broadcast.channel,
-                                        message,
-                                        except_id.as_ref(),
-                                        &broadcast.app_id,
-                                    )
-                                    .await;
-                                // Lock released automatically when horizontal_lock goes out of scope
-                            }
-                            Err(e) => {
-                                warn!(
-                                    "{}",
-                                    format!(
-                                        "Failed to deserialize broadcast inner message: {}, Payload: {}",
-                                        e, broadcast.message
-                                    )
-                                );
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        warn!(
+                Self::deliver_broadcast(&broadcast_local_adapter, &broadcast_node_id, &msg.payload)
+                    .await;
+            }
+            broadcast_listener.stopped();
+            warn!("{}", "NATS broadcast listener stream ended.");
+        });
+        self.listener.track(&task);
+
+        // With channel interest, follow other nodes' announcements and
+        // (re)subscribe to the shards of local channels
+        if let Some(interest) = &self.interest {
+            let mut interest_subscription = nats_client
+                .subscribe(Subject::from(self.interest_subject.clone()))
+                .await
+                .map_err(|e| {
+                    Error::InternalError(format!("Failed to subscribe to interest subject: {}", e))
+                })?;
+            let announcement_interest = interest.clone();
+            let task = tokio::spawn(async move {
+                while let Some(msg) = interest_subscription.next().await {
+                    match serde_json::from_slice::<InterestAnnouncement>(&msg.payload) {
+                        Ok(announcement) => announcement_interest.record(announcement),
+                        Err(e) => warn!(
                             "{}",
-                            format!(
-                                "Failed to deserialize broadcast message: {}, Payload: {:?}",
-                                e, msg.payload
-                            )
-                        );
+                            format!("Failed to deserialize interest announcement: {}", e)
+                        ),
+                    }
+                }
+            });
+            self.listener.track(&task);
+
+            for shard in interest.local_shards() {
+                Self::subscribe_shard(
+                    &nats_client,
+                    &broadcast_subject,
+                    shard,
+                    local_adapter.clone(),
+                    node_id.clone(),
+                    &self.shard_subscriptions,
+                )
+                .await?;
+            }
+        }
+
+        // Collect node registry heartbeats
+        let mut nodes_subscription = nats_client
+            .subscribe(Subject::from(self.nodes_subject.clone()))
+            .await
+            .map_err(|e| {
+                Error::InternalError(format!("Failed to subscribe to nodes subject: {}", e))
+            })?;
+        let nodes = self.nodes.clone();
+        let task = tokio::spawn(async move {
+            while let Some(msg) = nodes_subscription.next().await {
+                match serde_json::from_slice::<NodeInfo>(&msg.payload) {
+                    Ok(node) => {
+                        nodes.insert(node.process_id.clone(), node);
                     }
+                    Err(e) => warn!("{}", format!("Failed to deserialize node heartbeat: {}", e)),
                 }
             }
-            info!("{}", "NATS broadcast listener stream ended.");
         });
+        self.listener.track(&task);
 
         // Spawn a task to handle request messages
         let request_horizontal = horizontal_arc.clone();
+        let request_listener = self.listener.clone();
         let request_node_id = node_id.clone();
         let request_client = nats_client.clone();
         let request_response_subject = response_subject.clone();
-        tokio::spawn(async move {
+        let task = tokio::spawn(async move {
             while let Some(msg) = request_subscription.next().await {
                 match serde_json::from_slice::<RequestBody>(&msg.payload) {
                     Ok(request) => {
@@ -256,7 +544,7 @@ impl NatsAdapter {
                         // Lock only when processing
                         let response = {
                             // Scope for the lock
-                            let mut horizontal_lock = request_horizontal.lock().await;
+                            let horizontal_lock = request_horizontal.lock().await;
                             horizontal_lock.process_request(request).await
                         }; // Lock released
                         if let Ok(response) = response {
@@ -290,13 +578,16 @@ impl NatsAdapter {
                     }
                 }
             }
-            info!("{}", "NATS request listener stream ended.");
+            request_listener.stopped();
+            warn!("{}", "NATS request listener stream ended.");
         });
+        self.listener.track(&task);
 
         // Spawn a task to handle response messages
         let response_horizontal = horizontal_arc.clone();
+        let response_listener = self.listener.clone();
         let response_node_id = node_id.clone();
-        tokio::spawn(async move {
+        let task = tokio::spawn(async move {
             while let Some(msg) = response_subscription.next().await {
                 match serde_json::from_slice::<ResponseBody>(&msg.payload) {
                     Ok(response) => {
@@ -321,8 +612,10 @@ impl NatsAdapter {
                     }
                 }
             }
-            info!("{}", "NATS response listener stream ended.");
+            response_listener.stopped();
+            warn!("{}", "NATS response listener stream ended.");
         });
+        self.listener.track(&task);
 
         Ok(())
     }
@@ -334,32 +627,16 @@ impl NatsAdapter {
             return Ok(nodes as usize);
         }
 
-        // Otherwise estimate based on NATS server info
-        // NATS doesn't provide an easy way to count subscriptions like Redis PUBSUB NUMSUB
-        // So we'll either need to:
-        // 1. Maintain our own heartbeat system
-        // 2. Just return a fixed/configured value
-        // For now, we'll assume at least 1 node (ourselves)
-        Ok(1)
-    }
-
-    /// Set the metrics instance
-    pub async fn set_metrics(
-        &mut self,
-        metrics: Arc<Mutex<dyn MetricsInterface + Send + Sync>>,
-    ) -> Result<()> {
-        // Get lock on the horizontal adapter
-        let mut horizontal = self.horizontal.lock().await;
-
-        // Set the metrics in horizontal adapter
-        horizontal.metrics = Some(metrics);
-
-        Ok(())
+        // NATS can't count subscribers like Redis PUBSUB NUMSUB, so count the
+        // nodes whose registry heartbeats are still fresh (ours included)
+        let now = chrono::Utc::now().timestamp_millis();
+        self.nodes.retain(|_, node| !node.is_expired(now));
+        Ok(self.nodes.len().max(1))
     }
 
     /// Initialize with metrics
     pub async fn init_with_metrics(
-        &mut self,
+        &self,
         metrics: Option<Arc<Mutex<dyn MetricsInterface + Send + Sync>>>,
     ) -> Result<()> {
         // First initialize the adapter
@@ -376,12 +653,8 @@ impl NatsAdapter {
 
 #[async_trait]
 impl Adapter for NatsAdapter {
-    async fn init(&mut self) {
-        // Lock scope minimized
-        {
-            let mut horizontal = self.horizontal.lock().await;
-            horizontal.local_adapter.init().await;
-        } // Lock released
+    async fn init(&self) {
+        self.local_adapter.init().await;
 
         // Start NATS listeners (already optimized)
         if let Err(e) = self.start_listeners().await {
@@ -390,122 +663,121 @@ impl Adapter for NatsAdapter {
         }
     }
 
-    async fn get_namespace(&mut self, app_id: &str) -> Option<Arc<Namespace>> {
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal.local_adapter.get_namespace(app_id).await
+    async fn get_namespace(&self, app_id: &str) -> Option<Arc<Namespace>> {
+        self.local_adapter.get_namespace(app_id).await
     }
 
     async fn add_socket(
-        &mut self,
+        &self,
         socket_id: SocketId,
         socket: WebSocketWrite<WriteHalf<TokioIo<Upgraded>>>,
         app_id: &str,
         app_manager: &Arc<dyn AppManager + Send + Sync>,
     ) -> Result<()> {
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
+        self.local_adapter
             .add_socket(socket_id, socket, app_id, app_manager)
             .await
     }
 
     async fn get_connection(
-        &mut self,
+        &self,
         socket_id: &SocketId,
         app_id: &str,
     ) -> Option<Arc<Mutex<WebSocket>>> {
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
-            .get_connection(socket_id, app_id)
-            .await
+        self.local_adapter.get_connection(socket_id, app_id).await
     }
 
-    async fn remove_connection(&mut self, socket_id: &SocketId, app_id: &str) -> Result<()> {
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
+    async fn remove_connection(&self, socket_id: &SocketId, app_id: &str) -> Result<()> {
+        self.local_adapter
             .remove_connection(socket_id, app_id)
             .await
     }
 
     async fn send_message(
-        &mut self,
+        &self,
         app_id: &str,
         socket_id: &SocketId,
         message: PusherMessage,
     ) -> Result<()> {
         // This likely sends directly to a specific socket, lock scope depends on local_adapter.
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
+        self.local_adapter
             .send_message(app_id, socket_id, message)
             .await
     }
 
     /// Send to a channel (Optimized Lock Scope)
     async fn send(
-        &mut self,
+        &self,
         channel: &str,
         message: PusherMessage,
         except: Option<&SocketId>,
         app_id: &str,
     ) -> Result<()> {
-        // --- Optimization: Minimize lock duration ---
-        let (node_id, broadcast_data) = {
-            // 1. Lock and perform local send
-            let mut horizontal_lock = self.horizontal.lock().await;
-            let local_send_result = horizontal_lock
-                .local_adapter
-                .send(channel, message.clone(), except, app_id)
-                .await;
-
-            // Log local send errors if necessary, but continue to broadcast
-            if let Err(e) = local_send_result {
-                warn!(
-                    "{}",
-                    format!(
-                        "Local send failed during broadcast for channel {}: {}",
-                        channel, e
-                    )
-                );
+        let published_at = chrono::Utc::now().timestamp_millis();
+        // 1. Deliver to local sockets; the local registry is sharded, so this
+        // never waits on broadcasts to other apps or channels
+        let local_send_result = self
+            .local_adapter
+            .send(channel, message.clone(), except, app_id)
+            .await;
+
+        // Log local send errors if necessary, but continue to broadcast
+        if let Err(e) = local_send_result {
+            warn!(
+                "{}",
+                format!(
+                    "Local send failed during broadcast for channel {}: {}",
+                    channel, e
+                )
+            );
+        }
+
+        // 2. Pick the subject; with channel interest, skip the publish
+        // entirely when no other node consumes this channel's shard. Server
+        // notices and app disconnects go to the base subject, which every
+        // node consumes.
+        let broadcast_subject = match &self.interest {
+            Some(interest) if !is_app_wide_channel(channel) => {
+                let shard = interest.shard_for(app_id, channel);
+                if !interest.remote_interested(shard) {
+                    return Ok(());
+                }
+                Self::shard_subject(&self.broadcast_subject, shard)
             }
+            _ => self.broadcast_subject.clone(),
+        };
 
-            // 2. Prepare data needed for broadcast *outside* the lock
+        // 3. Prepare data needed for broadcast
+        let (node_id, broadcast_data) = (
+            self.node_id.clone(),
             (
-                horizontal_lock.node_id.clone(),
-                (
-                    app_id.to_string(),
-                    channel.to_string(),
-                    except.map(|id| id.0.clone()),
-                ),
-            )
-            // 3. Lock released here
-        };
+                app_id.to_string(),
+                channel.to_string(),
+                except.map(|id| id.0.clone()),
+            ),
+        );
 
-        // 4. Serialize the original message (outside the lock)
+        // 4. Serialize the original message
         let message_json = serde_json::to_string(&message)?;
 
-        // 5. Create broadcast message (outside the lock)
+        // 5. Create broadcast message
         let broadcast = BroadcastMessage {
             node_id, // Cloned node_id
             app_id: broadcast_data.0,
             channel: broadcast_data.1,
             message: message_json, // Serialized message
             except_socket_id: broadcast_data.2,
+            published_at: Some(published_at),
         };
 
         info!("{}", format!("Broadcasting message: {:?}", broadcast));
 
-        // 6. Serialize broadcast message (outside the lock)
-        let broadcast_data = serde_json::to_vec(&broadcast)?;
+        // 6. Serialize (and compress, if configured) the broadcast message
+        let broadcast_data = self.codec.encode(&broadcast)?;
 
-        // 7. Publish to NATS (outside the lock)
+        // 7. Publish to NATS
         self.client
-            .publish(
-                Subject::from(self.broadcast_subject.clone()),
-                broadcast_data.into(),
-            )
+            .publish(Subject::from(broadcast_subject), broadcast_data.into())
             .await
             .map_err(|e| Error::InternalError(format!("Failed to publish broadcast: {}", e)))?;
 
@@ -513,15 +785,14 @@ impl Adapter for NatsAdapter {
     }
 
     async fn get_channel_members(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
     ) -> Result<HashMap<String, PresenceMemberInfo>> {
         let node_count = self.get_node_count().await?; // Fetch node count first
-        let mut horizontal = self.horizontal.lock().await; // Lock for local + remote request
 
         // Get local members first
-        let mut members = horizontal
+        let mut members = self
             .local_adapter
             .get_channel_members(app_id, channel)
             .await?;
@@ -529,7 +800,10 @@ impl Adapter for NatsAdapter {
         // Get distributed members if needed
         if node_count > 1 {
             // send_request handles its own locking/timing
-            let response_data = horizontal
+            let response_data = self
+                .horizontal
+                .lock()
+                .await
                 .send_request(
                     app_id,
                     RequestType::ChannelMembers,
@@ -547,34 +821,27 @@ impl Adapter for NatsAdapter {
 
     // Returns only local sockets - inherent limitation
     async fn get_channel_sockets(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
     ) -> Result<DashMap<SocketId, Arc<Mutex<WebSocket>>>> {
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
+        self.local_adapter
             .get_channel_sockets(app_id, channel)
             .await
     }
 
-    async fn get_channel(&mut self, app_id: &str, channel: &str) -> Result<DashSet<SocketId>> {
+    async fn get_channel(&self, app_id: &str, channel: &str) -> Result<DashSet<SocketId>> {
         let node_count = self.get_node_count().await?;
 
         // Get local channel data with minimal lock duration
-        let result = {
-            let mut horizontal = self.horizontal.lock().await;
-            horizontal
-                .local_adapter
-                .get_channel(app_id, channel)
-                .await?
-        };
+        let result = { self.local_adapter.get_channel(app_id, channel).await? };
 
         // Get distributed channels with a separate lock acquisition
         if node_count > 1 {
             let response_data = {
-                let mut horizontal = self.horizontal.lock().await;
-                horizontal
+                self.horizontal
+                    .lock()
+                    .await
                     .send_request(
                         app_id,
                         RequestType::ChannelSockets,
@@ -595,23 +862,18 @@ impl Adapter for NatsAdapter {
         Ok(result)
     }
 
-    async fn remove_channel(&mut self, app_id: &str, channel: &str) {
+    async fn remove_channel(&self, app_id: &str, channel: &str) {
         // This seems purely local, lock scope depends on local_adapter
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
-            .remove_channel(app_id, channel)
-            .await
+        self.local_adapter.remove_channel(app_id, channel).await
     }
 
     async fn is_in_channel(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
         socket_id: &SocketId,
     ) -> Result<bool> {
         let node_count = self.get_node_count().await?; // Get count first
-        let mut horizontal = self.horizontal.lock().await; // Lock for local + potential remote
 
         warn!(
             "{}",
@@ -620,7 +882,7 @@ impl Adapter for NatsAdapter {
                 socket_id, channel
             )
         );
-        let local_result = horizontal
+        let local_result = self
             .local_adapter
             .is_in_channel(app_id, channel, socket_id)
             .await?;
@@ -643,7 +905,10 @@ impl Adapter for NatsAdapter {
                 )
             );
             // send_request handles its own locking/timing
-            let response_data = horizontal
+            let response_data = self
+                .horizontal
+                .lock()
+                .await
                 .send_request(
                     app_id,
                     RequestType::SocketExistsInChannel,
@@ -674,33 +939,19 @@ impl Adapter for NatsAdapter {
     }
 
     // Returns only local sockets - inherent limitation
-    async fn get_user_sockets(
-        &mut self,
-        user_id: &str,
-        app_id: &str,
-    ) -> Result<DashSet<WebSocketRef>> {
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
-            .get_user_sockets(user_id, app_id)
-            .await
+    async fn get_user_sockets(&self, user_id: &str, app_id: &str) -> Result<DashSet<WebSocketRef>> {
+        self.local_adapter.get_user_sockets(user_id, app_id).await
     }
 
-    async fn cleanup_connection(&mut self, app_id: &str, ws: WebSocketRef) {
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
-            .cleanup_connection(app_id, ws)
-            .await
+    async fn cleanup_connection(&self, app_id: &str, ws: WebSocketRef) {
+        self.local_adapter.cleanup_connection(app_id, ws).await
     }
 
-    async fn terminate_connection(&mut self, app_id: &str, user_id: &str) -> Result<()> {
+    async fn terminate_connection(&self, app_id: &str, user_id: &str) -> Result<()> {
         let node_count = self.get_node_count().await?; // Get count first
-        let mut horizontal = self.horizontal.lock().await; // Lock for local + potential remote
 
         // First terminate locally
-        horizontal
-            .local_adapter
+        self.local_adapter
             .terminate_connection(app_id, user_id)
             .await?; // Propagate local errors
 
@@ -708,7 +959,9 @@ impl Adapter for NatsAdapter {
         if node_count > 1 {
             // send_request handles its own locking/timing
             // We ignore the result here as it's a "fire and forget" termination broadcast
-            horizontal
+            self.horizontal
+                .lock()
+                .await
                 .send_request(
                     app_id,
                     RequestType::TerminateUserConnections,
@@ -723,21 +976,18 @@ impl Adapter for NatsAdapter {
         Ok(())
     }
 
-    async fn add_channel_to_sockets(&mut self, app_id: &str, channel: &str, socket_id: &SocketId) {
+    async fn add_channel_to_sockets(&self, app_id: &str, channel: &str, socket_id: &SocketId) {
         // Seems purely local
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
+        self.local_adapter
             .add_channel_to_sockets(app_id, channel, socket_id)
             .await
     }
 
-    async fn get_channel_socket_count(&mut self, app_id: &str, channel: &str) -> usize {
+    async fn get_channel_socket_count(&self, app_id: &str, channel: &str) -> usize {
         let node_count = self.get_node_count().await.unwrap_or(1); // Get count first
-        let mut horizontal = self.horizontal.lock().await; // Lock for local + potential remote
 
         // Get local count
-        let local_count = horizontal
+        let local_count = self
             .local_adapter
             .get_channel_socket_count(app_id, channel)
             .await;
@@ -745,7 +995,10 @@ impl Adapter for NatsAdapter {
         // Get distributed count if needed
         if node_count > 1 {
             // send_request handles its own locking/timing
-            match horizontal
+            match self
+                .horizontal
+                .lock()
+                .await
                 .send_request(
                     app_id,
                     RequestType::ChannelSocketsCount,
@@ -774,77 +1027,67 @@ impl Adapter for NatsAdapter {
     }
 
     async fn add_to_channel(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
         socket_id: &SocketId,
     ) -> Result<bool> {
-        // Seems purely local
-        let mut horizontal = self.horizontal.lock().await;
         warn!(
             "{}",
             format!("Adding socket {} to channel {}", socket_id, channel)
         );
-        horizontal
+        let added = self
             .local_adapter
             .add_to_channel(app_id, channel, socket_id)
-            .await
+            .await?;
+        self.claim_shard(app_id, channel).await;
+        Ok(added)
     }
 
     async fn remove_from_channel(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
         socket_id: &SocketId,
     ) -> Result<bool> {
         // Seems purely local
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
+        self.local_adapter
             .remove_from_channel(app_id, channel, socket_id)
             .await
     }
 
     async fn get_presence_member(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
         socket_id: &SocketId,
     ) -> Option<PresenceMemberInfo> {
         // Seems purely local
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
+        self.local_adapter
             .get_presence_member(app_id, channel, socket_id)
             .await
     }
 
     // Public method using the optimized internal call
-    async fn terminate_user_connections(&mut self, app_id: &str, user_id: &str) -> Result<()> {
+    async fn terminate_user_connections(&self, app_id: &str, user_id: &str) -> Result<()> {
         self.terminate_connection(app_id, user_id).await
     }
 
-    async fn add_user(&mut self, ws: Arc<Mutex<WebSocket>>) -> Result<()> {
+    async fn add_user(&self, ws: Arc<Mutex<WebSocket>>) -> Result<()> {
         // Seems purely local
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal.local_adapter.add_user(ws).await
+        self.local_adapter.add_user(ws).await
     }
 
-    async fn remove_user(&mut self, ws: Arc<Mutex<WebSocket>>) -> Result<()> {
+    async fn remove_user(&self, ws: Arc<Mutex<WebSocket>>) -> Result<()> {
         // Seems purely local
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal.local_adapter.remove_user(ws).await
+        self.local_adapter.remove_user(ws).await
     }
 
-    async fn get_channels_with_socket_count(
-        &mut self,
-        app_id: &str,
-    ) -> Result<DashMap<String, usize>> {
+    async fn get_channels_with_socket_count(&self, app_id: &str) -> Result<DashMap<String, usize>> {
         let node_count = self.get_node_count().await?; // Get count first
-        let mut horizontal = self.horizontal.lock().await; // Lock for local + potential remote
 
         // Get local channels
-        let local_channels = horizontal
+        let local_channels = self
             .local_adapter
             .get_channels_with_socket_count(app_id)
             .await?;
@@ -852,7 +1095,10 @@ impl Adapter for NatsAdapter {
         // Get distributed channels if needed
         if node_count > 1 {
             // send_request handles its own locking/timing
-            let response_data = horizontal
+            let response_data = self
+                .horizontal
+                .lock()
+                .await
                 .send_request(
                     app_id,
                     RequestType::ChannelsWithSocketsCount,
@@ -870,17 +1116,19 @@ impl Adapter for NatsAdapter {
         Ok(local_channels)
     }
 
-    async fn get_sockets_count(&mut self, app_id: &str) -> Result<usize> {
+    async fn get_sockets_count(&self, app_id: &str) -> Result<usize> {
         let node_count = self.get_node_count().await?; // Get count first
-        let mut horizontal = self.horizontal.lock().await; // Lock for local + potential remote
 
         // Get local count
-        let local_count = horizontal.local_adapter.get_sockets_count(app_id).await;
+        let local_count = self.local_adapter.get_sockets_count(app_id).await;
 
         // Get distributed count if needed
         if node_count > 1 {
             // send_request handles its own locking/timing
-            let response_data = horizontal
+            let response_data = self
+                .horizontal
+                .lock()
+                .await
                 .send_request(
                     app_id,
                     RequestType::SocketsCount,
@@ -896,12 +1144,87 @@ impl Adapter for NatsAdapter {
         Ok(local_count?)
     }
 
-    async fn get_namespaces(&mut self) -> Result<DashMap<String, Arc<Namespace>>> {
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal.local_adapter.get_namespaces().await
+    async fn get_channel_socket_details(
+        &self,
+        app_id: &str,
+        channel: &str,
+        sample: usize,
+    ) -> Result<Vec<SocketDetails>> {
+        let node_count = self.get_node_count().await?;
+        self.horizontal
+            .lock()
+            .await
+            .get_channel_socket_details(app_id, channel, sample, node_count)
+            .await
+    }
+
+    async fn get_namespaces(&self) -> Result<DashMap<String, Arc<Namespace>>> {
+        self.local_adapter.get_namespaces().await
+    }
+
+    async fn health(&self) -> Result<()> {
+        match self.client.connection_state() {
+            NatsConnectionState::Connected => {}
+            state => {
+                return Err(Error::InternalError(format!(
+                    "NATS connection is not established: {:?}",
+                    state
+                )));
+            }
+        }
+
+        if !self.listener.is_running() {
+            return Err(Error::InternalError(
+                "NATS subject listeners are not running".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn reconnect(&self) -> Result<()> {
+        // async-nats reconnects the client on its own; we only need to make sure
+        // the subscriptions are back once the connection is up again.
+        if self.client.connection_state() != NatsConnectionState::Connected {
+            return Err(Error::InternalError(
+                "NATS client is still reconnecting".to_string(),
+            ));
+        }
+
+        // Restarts the subject listeners unless they are running or subscribing
+        self.start_subject_listeners().await
+    }
+
+    async fn register_node(&self, node: &NodeInfo) -> Result<()> {
+        let entry = serde_json::to_vec(node)?;
+        self.client
+            .publish(Subject::from(self.nodes_subject.clone()), entry.into())
+            .await
+            .map_err(|e| Error::InternalError(format!("Failed to publish node heartbeat: {}", e)))
+    }
+
+    async fn registered_nodes(&self) -> Result<Vec<NodeInfo>> {
+        let now = chrono::Utc::now().timestamp_millis();
+        self.nodes.retain(|_, node| !node.is_expired(now));
+        Ok(self
+            .nodes
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect())
+    }
+
+    async fn set_metrics(
+        &self,
+        metrics: Arc<Mutex<dyn MetricsInterface + Send + Sync>>,
+    ) -> Result<()> {
+        self.horizontal.lock().await.metrics = Some(metrics);
+        Ok(())
+    }
+
+    fn set_app_manager(&self, app_manager: Arc<dyn AppManager + Send + Sync>) {
+        self.local_adapter.set_app_manager(app_manager);
     }
 
-    fn as_any_mut(&mut self) -> &mut dyn Any {
+    fn as_any(&self) -> &dyn Any {
         self
     }
 }
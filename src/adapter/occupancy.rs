@@ -0,0 +1,50 @@
+// src/adapter/occupancy.rs
+//! Cluster-wide channel occupancy for the Redis-backed adapters. Each channel
+//! has a Redis set of the nodes with local subscribers to it. A node joins
+//! the set when its first local subscriber arrives and leaves it when its
+//! last one goes. The set changes and the membership check run in one
+//! script, so exactly one node sees the set go from empty to one member
+//! (channel occupied) or from one member to empty (channel vacated).
+
+use redis::aio::ConnectionLike;
+
+/// KEYS[1]: the channel's set; ARGV[1]: node ID; ARGV[2]: "1" to join, "0" to leave.
+/// Returns 1 when the change occupied or vacated the channel.
+const CLAIM_SCRIPT: &str = r#"
+local joining = ARGV[2] == '1'
+local changed
+if joining then
+    changed = redis.call('SADD', KEYS[1], ARGV[1])
+else
+    changed = redis.call('SREM', KEYS[1], ARGV[1])
+end
+if changed == 0 then
+    return 0
+end
+local members = redis.call('SCARD', KEYS[1])
+if (joining and members == 1) or (not joining and members == 0) then
+    return 1
+end
+return 0
+"#;
+
+pub fn occupancy_key(prefix: &str, app_id: &str, channel: &str) -> String {
+    format!("{}:occupancy:{}:{}", prefix, app_id, channel)
+}
+
+/// Add (`occupied`) or remove `node_id` from the channel's set, returning
+/// whether this node made the cluster-wide transition
+pub async fn claim_transition<C: ConnectionLike + Send>(
+    conn: &mut C,
+    key: &str,
+    node_id: &str,
+    occupied: bool,
+) -> redis::RedisResult<bool> {
+    let claimed: i64 = redis::Script::new(CLAIM_SCRIPT)
+        .key(key)
+        .arg(node_id)
+        .arg(if occupied { "1" } else { "0" })
+        .invoke_async(conn)
+        .await?;
+    Ok(claimed == 1)
+}
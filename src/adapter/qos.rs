@@ -0,0 +1,102 @@
+// src/adapter/qos.rs
+//! Latency-aware dispatch. Channels are sorted into realtime and bulk
+//! classes by prefix; bulk broadcasts only get a bounded share of the
+//! dispatch slots and are shed when none frees up quickly, so a saturated
+//! node keeps serving trading and presence channels first.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{Mutex, RwLock, Semaphore, SemaphorePermit};
+use tracing::debug;
+
+use crate::metrics::MetricsInterface;
+use crate::options::{DispatchQosConfig, QosClass};
+
+/// Dispatch slots held while a broadcast is delivered to local sockets
+pub struct DispatchPermit<'a> {
+    _slot: SemaphorePermit<'a>,
+    _bulk_slot: Option<SemaphorePermit<'a>>,
+}
+
+pub struct DispatchQos {
+    config: DispatchQosConfig,
+    slots: Semaphore,
+    bulk_slots: Semaphore,
+    metrics: RwLock<Option<Arc<Mutex<dyn MetricsInterface + Send + Sync>>>>,
+}
+
+impl DispatchQos {
+    pub fn new(config: DispatchQosConfig) -> Self {
+        let max_in_flight = config.max_in_flight.max(1);
+        Self {
+            slots: Semaphore::new(max_in_flight),
+            bulk_slots: Semaphore::new(config.bulk_max_in_flight.clamp(1, max_in_flight)),
+            config,
+            metrics: RwLock::new(None),
+        }
+    }
+
+    pub async fn set_metrics(&self, metrics: Arc<Mutex<dyn MetricsInterface + Send + Sync>>) {
+        *self.metrics.write().await = Some(metrics);
+    }
+
+    /// Class of `channel`; the longest matching prefix wins
+    pub fn classify(&self, channel: &str) -> QosClass {
+        let longest = |prefixes: &[String]| {
+            prefixes
+                .iter()
+                .filter(|prefix| channel.starts_with(prefix.as_str()))
+                .map(|prefix| prefix.len())
+                .max()
+        };
+        match (
+            longest(&self.config.realtime_prefixes),
+            longest(&self.config.bulk_prefixes),
+        ) {
+            (Some(realtime), Some(bulk)) if bulk > realtime => QosClass::Bulk,
+            (Some(_), _) => QosClass::Realtime,
+            (None, Some(_)) => QosClass::Bulk,
+            (None, None) => self.config.default_class,
+        }
+    }
+
+    /// Wait for a dispatch slot for a broadcast to `channel`. Realtime
+    /// broadcasts always get one; bulk broadcasts get None (and are shed)
+    /// when no slot frees up within `bulk_wait_ms`.
+    pub async fn admit(&self, channel: &str) -> Option<DispatchPermit<'_>> {
+        let class = self.classify(channel);
+        if class == QosClass::Realtime {
+            let slot = self.slots.acquire().await.ok()?;
+            return Some(DispatchPermit {
+                _slot: slot,
+                _bulk_slot: None,
+            });
+        }
+
+        let wait = Duration::from_millis(self.config.bulk_wait_ms);
+        let acquired = tokio::time::timeout(wait, async {
+            let bulk_slot = self.bulk_slots.acquire().await.ok()?;
+            let slot = self.slots.acquire().await.ok()?;
+            Some(DispatchPermit {
+                _slot: slot,
+                _bulk_slot: Some(bulk_slot),
+            })
+        })
+        .await;
+
+        match acquired {
+            Ok(Some(permit)) => Some(permit),
+            _ => {
+                debug!(
+                    "{}",
+                    format!("Dispatch saturated, shedding broadcast to {}", channel)
+                );
+                if let Some(metrics) = self.metrics.read().await.as_ref() {
+                    metrics.lock().await.mark_dispatch_shed(class.as_str());
+                }
+                None
+            }
+        }
+    }
+}
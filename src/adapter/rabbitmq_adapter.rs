@@ -0,0 +1,1286 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::adapter::adapter::Adapter;
+use crate::adapter::horizontal_adapter::{
+    BroadcastCodec, BroadcastMessage, HorizontalAdapter, PendingRequest, RequestBody, RequestType,
+    ResponseBody,
+};
+use crate::adapter::interest::ChannelInterest;
+use crate::adapter::listener::ListenerState;
+use crate::adapter::local_adapter::LocalAdapter;
+use crate::adapter::registry::NodeInfo;
+use crate::app::manager::AppManager;
+use crate::channel::PresenceMemberInfo;
+use crate::error::{Error, Result};
+use async_trait::async_trait;
+use dashmap::{DashMap, DashSet};
+use fastwebsockets::WebSocketWrite;
+use futures::StreamExt;
+use hyper::upgrade::Upgraded;
+use hyper_util::rt::TokioIo;
+use lapin::options::{
+    BasicConsumeOptions, BasicPublishOptions, ConfirmSelectOptions, ExchangeDeclareOptions,
+    QueueBindOptions, QueueDeclareOptions,
+};
+use lapin::types::FieldTable;
+use lapin::{BasicProperties, Channel, Connection, ConnectionProperties, Consumer, ExchangeKind};
+use tokio::io::WriteHalf;
+use tokio::sync::{Mutex, RwLock, mpsc};
+use tracing::{error, info, warn};
+
+use crate::metrics::MetricsInterface;
+use crate::namespace::Namespace;
+pub(crate) use crate::options::RabbitMqAdapterConfig;
+use crate::protocol::messages::PusherMessage;
+use crate::utils::is_app_wide_channel;
+use crate::websocket::{SocketDetails, SocketId, WebSocket, WebSocketRef};
+
+/// RabbitMQ exchanges
+pub const DEFAULT_PREFIX: &str = "sockudo";
+const BROADCAST_SUFFIX: &str = ".broadcast";
+const REQUESTS_SUFFIX: &str = ".requests";
+const RESPONSES_SUFFIX: &str = ".responses";
+const NODES_SUFFIX: &str = ".nodes";
+const SHARDS_SUFFIX: &str = ".broadcast.shards";
+
+/// AMQP connection plus the channel used for publishing
+struct RabbitMqLink {
+    connection: Connection,
+    publish_channel: Channel,
+}
+
+/// The queue this node consumes broadcasts from, and the channel it was
+/// declared on, for binding it to channel shards
+struct BroadcastQueue {
+    channel: Channel,
+    name: String,
+}
+
+/// RabbitMQ adapter for horizontal scaling.
+///
+/// Every scope (broadcasts, requests, responses, node heartbeats) gets its own
+/// fanout exchange. Each node binds an exclusive, auto-deleted queue to each,
+/// so a message published by one node reaches every other node exactly once.
+///
+/// With channel interest enabled, channel broadcasts go to a direct exchange
+/// instead, routed by the shard the channel hashes onto. Nodes bind their
+/// broadcast queue to the shards their sockets are in, so the broker only
+/// delivers a broadcast to nodes with subscribers in its shard.
+pub struct RabbitMqAdapter {
+    /// Base horizontal adapter (protected by a Mutex). Cross-node requests
+    /// wait on a copy of it, and local operations go through `local_adapter`.
+    pub horizontal: Arc<Mutex<HorizontalAdapter>>,
+
+    /// Local connection registry, shared with the horizontal adapter
+    pub local_adapter: Arc<LocalAdapter>,
+
+    /// Unique ID of this node, copied from the horizontal adapter
+    pub node_id: String,
+
+    /// AMQP connection, replaced on reconnect
+    link: Arc<RwLock<RabbitMqLink>>,
+
+    /// Exchange names
+    pub prefix: String,
+    pub broadcast_exchange: String,
+    pub request_exchange: String,
+    pub response_exchange: String,
+    pub nodes_exchange: String,
+    pub shard_exchange: String,
+
+    /// Channel shards this node consumes, when channel interest tracking is enabled
+    pub interest: Option<Arc<ChannelInterest>>,
+
+    /// Broadcast queue of the running consumers, replaced on reconnect
+    broadcast_queue: Arc<RwLock<Option<BroadcastQueue>>>,
+
+    /// Registry heartbeats of the nodes, ours included, keyed by process ID
+    nodes: Arc<DashMap<String, NodeInfo>>,
+
+    /// Pending requests, shared with the horizontal adapter
+    pending_requests: Arc<DashMap<String, PendingRequest>>,
+
+    /// Configuration
+    pub config: RabbitMqAdapterConfig,
+
+    /// Whether the consumer tasks are stopped, subscribing or all running
+    pub listener: Arc<ListenerState>,
+
+    /// Encodes (and optionally compresses) outgoing broadcasts
+    pub codec: BroadcastCodec,
+}
+
+impl RabbitMqAdapter {
+    /// Create a new RabbitMQ adapter
+    pub async fn new(config: RabbitMqAdapterConfig) -> Result<Self> {
+        let mut horizontal = HorizontalAdapter::new();
+        info!(
+            "{}",
+            format!(
+                "RabbitMQ adapter config: prefix={}, publisher_confirms={}",
+                config.prefix, config.publisher_confirms
+            )
+        );
+
+        horizontal.requests_timeout = config.request_timeout_ms;
+
+        let broadcast_exchange = format!("{}{}", config.prefix, BROADCAST_SUFFIX);
+        let request_exchange = format!("{}{}", config.prefix, REQUESTS_SUFFIX);
+        let response_exchange = format!("{}{}", config.prefix, RESPONSES_SUFFIX);
+        let nodes_exchange = format!("{}{}", config.prefix, NODES_SUFFIX);
+        let shard_exchange = format!("{}{}", config.prefix, SHARDS_SUFFIX);
+
+        let link = Self::connect(
+            &config,
+            &[
+                &broadcast_exchange,
+                &request_exchange,
+                &response_exchange,
+                &nodes_exchange,
+            ],
+            &shard_exchange,
+        )
+        .await?;
+
+        let local_adapter = horizontal.local_adapter.clone();
+        let node_id = horizontal.node_id.clone();
+        let pending_requests = horizontal.pending_requests.clone();
+
+        Ok(Self {
+            horizontal: Arc::new(Mutex::new(horizontal)),
+            local_adapter,
+            node_id,
+            link: Arc::new(RwLock::new(link)),
+            prefix: config.prefix.clone(),
+            broadcast_exchange,
+            request_exchange,
+            response_exchange,
+            nodes_exchange,
+            shard_exchange,
+            interest: None,
+            broadcast_queue: Arc::new(RwLock::new(None)),
+            nodes: Arc::new(DashMap::new()),
+            pending_requests,
+            config,
+            listener: Arc::new(ListenerState::default()),
+            codec: BroadcastCodec::default(),
+        })
+    }
+
+    /// Open a connection, declare the exchanges and prepare the publish channel
+    async fn connect(
+        config: &RabbitMqAdapterConfig,
+        exchanges: &[&str],
+        shard_exchange: &str,
+    ) -> Result<RabbitMqLink> {
+        let connection = tokio::time::timeout(
+            Duration::from_millis(config.connection_timeout_ms),
+            Connection::connect(&config.url, ConnectionProperties::default()),
+        )
+        .await
+        .map_err(|_| Error::InternalError("Timed out connecting to RabbitMQ".to_string()))?
+        .map_err(|e| Error::InternalError(format!("Failed to connect to RabbitMQ: {}", e)))?;
+
+        let publish_channel = connection
+            .create_channel()
+            .await
+            .map_err(|e| Error::InternalError(format!("Failed to open RabbitMQ channel: {}", e)))?;
+
+        let kinds = exchanges
+            .iter()
+            .map(|exchange| (*exchange, ExchangeKind::Fanout))
+            .chain(std::iter::once((shard_exchange, ExchangeKind::Direct)));
+        for (exchange, kind) in kinds {
+            publish_channel
+                .exchange_declare(
+                    exchange,
+                    kind,
+                    ExchangeDeclareOptions::default(),
+                    FieldTable::default(),
+                )
+                .await
+                .map_err(|e| {
+                    Error::InternalError(format!(
+                        "Failed to declare RabbitMQ exchange {}: {}",
+                        exchange, e
+                    ))
+                })?;
+        }
+
+        if config.publisher_confirms {
+            publish_channel
+                .confirm_select(ConfirmSelectOptions::default())
+                .await
+                .map_err(|e| {
+                    Error::InternalError(format!("Failed to enable publisher confirms: {}", e))
+                })?;
+        }
+
+        Ok(RabbitMqLink {
+            connection,
+            publish_channel,
+        })
+    }
+
+    /// Publish a payload to an exchange, waiting for the broker ack when confirms are on
+    async fn publish(&self, exchange: &str, routing_key: &str, payload: &[u8]) -> Result<()> {
+        let channel = self.link.read().await.publish_channel.clone();
+        Self::publish_on(
+            &channel,
+            exchange,
+            routing_key,
+            payload,
+            self.config.publisher_confirms,
+        )
+        .await
+    }
+
+    async fn publish_on(
+        channel: &Channel,
+        exchange: &str,
+        routing_key: &str,
+        payload: &[u8],
+        publisher_confirms: bool,
+    ) -> Result<()> {
+        let confirm = channel
+            .basic_publish(
+                exchange,
+                routing_key,
+                BasicPublishOptions::default(),
+                payload,
+                BasicProperties::default(),
+            )
+            .await
+            .map_err(|e| Error::InternalError(format!("Failed to publish to RabbitMQ: {}", e)))?;
+
+        if publisher_confirms {
+            let confirmation = confirm.await.map_err(|e| {
+                Error::InternalError(format!("Failed to receive RabbitMQ confirm: {}", e))
+            })?;
+            if confirmation.is_nack() {
+                return Err(Error::InternalError(format!(
+                    "RabbitMQ rejected message published to {}",
+                    exchange
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Routing key of a channel shard on the shard exchange
+    fn shard_routing_key(shard: u32) -> String {
+        format!("shard.{}", shard)
+    }
+
+    /// Declare this node's queue on an exchange and start consuming from it
+    async fn consume(
+        channel: &Channel,
+        exchange: &str,
+        consumer_tag: &str,
+    ) -> Result<(String, Consumer)> {
+        let queue = channel
+            .queue_declare(
+                "",
+                QueueDeclareOptions {
+                    exclusive: true,
+                    auto_delete: true,
+                    ..QueueDeclareOptions::default()
+                },
+                FieldTable::default(),
+            )
+            .await
+            .map_err(|e| {
+                Error::InternalError(format!("Failed to declare RabbitMQ queue: {}", e))
+            })?;
+
+        channel
+            .queue_bind(
+                queue.name().as_str(),
+                exchange,
+                "",
+                QueueBindOptions::default(),
+                FieldTable::default(),
+            )
+            .await
+            .map_err(|e| {
+                Error::InternalError(format!("Failed to bind queue to {}: {}", exchange, e))
+            })?;
+
+        let consumer = channel
+            .basic_consume(
+                queue.name().as_str(),
+                consumer_tag,
+                BasicConsumeOptions {
+                    no_ack: true,
+                    ..BasicConsumeOptions::default()
+                },
+                FieldTable::default(),
+            )
+            .await
+            .map_err(|e| {
+                Error::InternalError(format!("Failed to consume from {}: {}", exchange, e))
+            })?;
+        Ok((queue.name().as_str().to_string(), consumer))
+    }
+
+    /// Bind or unbind the broadcast queue to a channel shard
+    async fn set_shard_binding(
+        broadcast_queue: &RwLock<Option<BroadcastQueue>>,
+        shard_exchange: &str,
+        shard: u32,
+        bound: bool,
+    ) -> Result<()> {
+        let queue = broadcast_queue.read().await;
+        // Consumers bind every local shard when they (re)start
+        let Some(queue) = queue.as_ref() else {
+            return Ok(());
+        };
+        let routing_key = Self::shard_routing_key(shard);
+        let result = if bound {
+            queue
+                .channel
+                .queue_bind(
+                    &queue.name,
+                    shard_exchange,
+                    &routing_key,
+                    QueueBindOptions::default(),
+                    FieldTable::default(),
+                )
+                .await
+        } else {
+            queue
+                .channel
+                .queue_unbind(
+                    &queue.name,
+                    shard_exchange,
+                    &routing_key,
+                    FieldTable::default(),
+                )
+                .await
+        };
+        result.map_err(|e| {
+            Error::InternalError(format!(
+                "Failed to {} shard {}: {}",
+                if bound { "bind" } else { "unbind" },
+                shard,
+                e
+            ))
+        })
+    }
+
+    /// Start consuming the shard of a channel that just gained a local subscriber
+    async fn claim_shard(&self, app_id: &str, channel: &str) {
+        let Some(interest) = &self.interest else {
+            return;
+        };
+        let Some(shard) = interest.claim(app_id, channel) else {
+            return;
+        };
+        if let Err(e) =
+            Self::set_shard_binding(&self.broadcast_queue, &self.shard_exchange, shard, true).await
+        {
+            warn!("{}", e);
+        }
+    }
+
+    /// Periodically unbind shards without local subscribers
+    fn start_interest_task(&self) {
+        let Some(interest) = self.interest.clone() else {
+            return;
+        };
+        let local_adapter = self.local_adapter.clone();
+        let broadcast_queue = self.broadcast_queue.clone();
+        let shard_exchange = self.shard_exchange.clone();
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interest.announce_interval());
+            loop {
+                ticker.tick().await;
+                for shard in interest.release_idle(&local_adapter) {
+                    if let Err(e) =
+                        Self::set_shard_binding(&broadcast_queue, &shard_exchange, shard, false)
+                            .await
+                    {
+                        warn!("{}", e);
+                    }
+                    // Claimed again while we were releasing it
+                    if interest.is_local(shard) {
+                        if let Err(e) =
+                            Self::set_shard_binding(&broadcast_queue, &shard_exchange, shard, true)
+                                .await
+                        {
+                            warn!("{}", e);
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    /// Publish requests issued through the horizontal adapter
+    fn start_request_forwarder(&self, mut request_rx: mpsc::UnboundedReceiver<String>) {
+        let link = self.link.clone();
+        let request_exchange = self.request_exchange.clone();
+        let publisher_confirms = self.config.publisher_confirms;
+        tokio::spawn(async move {
+            while let Some(request_json) = request_rx.recv().await {
+                // Read the link per request, so requests follow a reconnect
+                let channel = link.read().await.publish_channel.clone();
+                if let Err(e) = Self::publish_on(
+                    &channel,
+                    &request_exchange,
+                    "",
+                    request_json.as_bytes(),
+                    publisher_confirms,
+                )
+                .await
+                {
+                    error!("{}", format!("Failed to publish request: {}", e));
+                }
+            }
+        });
+    }
+
+    /// A copy of the horizontal adapter to send a request through. Waiting
+    /// for responses on the copy leaves the shared one free for other calls.
+    async fn requester(&self) -> HorizontalAdapter {
+        self.horizontal.lock().await.clone()
+    }
+
+    /// Start listening for RabbitMQ messages
+    pub async fn start_listeners(&self) -> Result<()> {
+        let (request_tx, request_rx) = mpsc::unbounded_channel();
+        {
+            let mut horizontal = self.horizontal.lock().await;
+            horizontal.start_request_cleanup();
+            horizontal.request_publisher = Some(request_tx);
+        }
+
+        self.start_request_forwarder(request_rx);
+        self.start_consumers().await?;
+        self.start_interest_task();
+
+        Ok(())
+    }
+
+    /// Start the broadcast, request, response and node heartbeat consumers
+    async fn start_consumers(&self) -> Result<()> {
+        // Another start may still be consuming, or the consumers are up
+        if !self.listener.try_start() {
+            return Ok(());
+        }
+
+        let result = self.open_consumers().await;
+        if result.is_err() {
+            self.listener.stopped();
+        }
+        result
+    }
+
+    /// Declare the node queues and spawn a task per consumer
+    async fn open_consumers(&self) -> Result<()> {
+        let local_adapter = self.local_adapter.clone();
+        let node_id = self.node_id.clone();
+
+        let (consume_channel, publish_channel) = {
+            let link = self.link.read().await;
+            let consume_channel = link.connection.create_channel().await.map_err(|e| {
+                Error::InternalError(format!("Failed to open RabbitMQ channel: {}", e))
+            })?;
+            (consume_channel, link.publish_channel.clone())
+        };
+
+        let (broadcast_queue, mut broadcast_consumer) = Self::consume(
+            &consume_channel,
+            &self.broadcast_exchange,
+            &format!("{}-broadcast", node_id),
+        )
+        .await?;
+        *self.broadcast_queue.write().await = Some(BroadcastQueue {
+            channel: consume_channel.clone(),
+            name: broadcast_queue,
+        });
+        if let Some(interest) = &self.interest {
+            for shard in interest.local_shards() {
+                Self::set_shard_binding(&self.broadcast_queue, &self.shard_exchange, shard, true)
+                    .await?;
+            }
+        }
+        let (_, mut request_consumer) = Self::consume(
+            &consume_channel,
+            &self.request_exchange,
+            &format!("{}-requests", node_id),
+        )
+        .await?;
+        let (_, mut response_consumer) = Self::consume(
+            &consume_channel,
+            &self.response_exchange,
+            &format!("{}-responses", node_id),
+        )
+        .await?;
+        let (_, mut nodes_consumer) = Self::consume(
+            &consume_channel,
+            &self.nodes_exchange,
+            &format!("{}-nodes", node_id),
+        )
+        .await?;
+
+        self.listener.running();
+        info!(
+            "{}",
+            format!(
+                "RabbitMQ adapter listening on exchanges: {}, {}, {}",
+                self.broadcast_exchange, self.request_exchange, self.response_exchange
+            )
+        );
+
+        // Spawn a task to handle broadcast messages
+        let broadcast_local_adapter = local_adapter.clone();
+        let broadcast_listener = self.listener.clone();
+        let broadcast_node_id = node_id.clone();
+        let task = tokio::spawn(async move {
+            while let Some(delivery) = broadcast_consumer.next().await {
+                let delivery = match delivery {
+                    Ok(delivery) => delivery,
+                    Err(e) => {
+                        error!("{}", format!("RabbitMQ broadcast consumer error: {}", e));
+                        break;
+                    }
+                };
+                match BroadcastCodec::decode(&delivery.data) {
+                    Ok(broadcast) => {
+                        // Skip our own messages
+                        if broadcast.node_id == broadcast_node_id {
+                            continue;
+                        }
+                        match serde_json::from_str(&broadcast.message) {
+                            Ok(message) => {
+                                let except_id = broadcast
+                                    .except_socket_id
+                                    .as_ref()
+                                    .map(|id| SocketId(id.clone()));
+                                let _ = broadcast_local_adapter
+                                    .deliver(
+                                        &broadcast.channel,
+                                        message,
+                                        except_id.as_ref(),
+                                        &broadcast.app_id,
+                                        broadcast.published_at,
+                                    )
+                                    .await;
+                            }
+                            Err(e) => {
+                                warn!(
+                                    "{}",
+                                    format!(
+                                        "Failed to deserialize broadcast inner message: {}, Payload: {}",
+                                        e, broadcast.message
+                                    )
+                                );
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        warn!(
+                            "{}",
+                            format!("Failed to deserialize broadcast message: {}", e)
+                        );
+                    }
+                }
+            }
+            broadcast_listener.stopped();
+            warn!("{}", "RabbitMQ broadcast consumer ended.");
+        });
+        self.listener.track(&task);
+
+        // Collect node registry heartbeats
+        let nodes = self.nodes.clone();
+        let task = tokio::spawn(async move {
+            while let Some(delivery) = nodes_consumer.next().await {
+                let Ok(delivery) = delivery else {
+                    break;
+                };
+                match serde_json::from_slice::<NodeInfo>(&delivery.data) {
+                    Ok(node) => {
+                        nodes.insert(node.process_id.clone(), node);
+                    }
+                    Err(e) => warn!("{}", format!("Failed to deserialize node heartbeat: {}", e)),
+                }
+            }
+        });
+        self.listener.track(&task);
+
+        // Spawn a task to handle request messages
+        let request_local_adapter = local_adapter.clone();
+        let request_listener = self.listener.clone();
+        let request_node_id = node_id.clone();
+        let response_exchange = self.response_exchange.clone();
+        let publisher_confirms = self.config.publisher_confirms;
+        let task = tokio::spawn(async move {
+            while let Some(delivery) = request_consumer.next().await {
+                let delivery = match delivery {
+                    Ok(delivery) => delivery,
+                    Err(e) => {
+                        error!("{}", format!("RabbitMQ request consumer error: {}", e));
+                        break;
+                    }
+                };
+                match serde_json::from_slice::<RequestBody>(&delivery.data) {
+                    Ok(request) => {
+                        // Skip our own requests
+                        if request.node_id == request_node_id {
+                            continue;
+                        }
+                        // Answer from the local registry without the horizontal lock,
+                        // so a request pending on this node cannot block the reply
+                        let response = HorizontalAdapter::build_response(
+                            &request_local_adapter,
+                            &request_node_id,
+                            request,
+                        )
+                        .await;
+                        if let Ok(response) = response {
+                            match serde_json::to_vec(&response) {
+                                Ok(response_data) => {
+                                    if let Err(e) = Self::publish_on(
+                                        &publish_channel,
+                                        &response_exchange,
+                                        "",
+                                        &response_data,
+                                        publisher_confirms,
+                                    )
+                                    .await
+                                    {
+                                        error!("{}", format!("Failed to publish response: {}", e));
+                                    }
+                                }
+                                Err(e) => {
+                                    error!("{}", format!("Failed to serialize response: {}", e));
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        warn!(
+                            "{}",
+                            format!("Failed to deserialize request message: {}", e)
+                        );
+                    }
+                }
+            }
+            request_listener.stopped();
+            warn!("{}", "RabbitMQ request consumer ended.");
+        });
+        self.listener.track(&task);
+
+        // Spawn a task to handle response messages
+        let pending_requests = self.pending_requests.clone();
+        let response_listener = self.listener.clone();
+        let response_node_id = node_id.clone();
+        let task = tokio::spawn(async move {
+            while let Some(delivery) = response_consumer.next().await {
+                let delivery = match delivery {
+                    Ok(delivery) => delivery,
+                    Err(e) => {
+                        error!("{}", format!("RabbitMQ response consumer error: {}", e));
+                        break;
+                    }
+                };
+                match serde_json::from_slice::<ResponseBody>(&delivery.data) {
+                    Ok(response) => {
+                        // Skip our own responses
+                        if response.node_id == response_node_id {
+                            continue;
+                        }
+                        HorizontalAdapter::record_response(&pending_requests, response);
+                    }
+                    Err(e) => {
+                        warn!(
+                            "{}",
+                            format!("Failed to deserialize response message: {}", e)
+                        );
+                    }
+                }
+            }
+            response_listener.stopped();
+            warn!("{}", "RabbitMQ response consumer ended.");
+        });
+        self.listener.track(&task);
+
+        Ok(())
+    }
+
+    /// Get the number of nodes in the cluster
+    pub async fn get_node_count(&self) -> Result<usize> {
+        if let Some(nodes) = self.config.nodes_number {
+            return Ok(nodes as usize);
+        }
+
+        // Exclusive queues are anonymous and can't be counted, so count the
+        // nodes whose registry heartbeats are still fresh (ours included)
+        let now = chrono::Utc::now().timestamp_millis();
+        self.nodes.retain(|_, node| !node.is_expired(now));
+        Ok(self.nodes.len().max(1))
+    }
+}
+
+#[async_trait]
+impl Adapter for RabbitMqAdapter {
+    async fn init(&self) {
+        self.local_adapter.init().await;
+
+        if let Err(e) = self.start_listeners().await {
+            error!("{}", format!("Failed to start RabbitMQ consumers: {}", e));
+        }
+    }
+
+    async fn get_namespace(&self, app_id: &str) -> Option<Arc<Namespace>> {
+        self.local_adapter.get_namespace(app_id).await
+    }
+
+    async fn add_socket(
+        &self,
+        socket_id: SocketId,
+        socket: WebSocketWrite<WriteHalf<TokioIo<Upgraded>>>,
+        app_id: &str,
+        app_manager: &Arc<dyn AppManager + Send + Sync>,
+    ) -> Result<()> {
+        self.local_adapter
+            .add_socket(socket_id, socket, app_id, app_manager)
+            .await
+    }
+
+    async fn get_connection(
+        &self,
+        socket_id: &SocketId,
+        app_id: &str,
+    ) -> Option<Arc<Mutex<WebSocket>>> {
+        self.local_adapter.get_connection(socket_id, app_id).await
+    }
+
+    async fn remove_connection(&self, socket_id: &SocketId, app_id: &str) -> Result<()> {
+        self.local_adapter
+            .remove_connection(socket_id, app_id)
+            .await
+    }
+
+    async fn send_message(
+        &self,
+        app_id: &str,
+        socket_id: &SocketId,
+        message: PusherMessage,
+    ) -> Result<()> {
+        self.local_adapter
+            .send_message(app_id, socket_id, message)
+            .await
+    }
+
+    async fn send(
+        &self,
+        channel: &str,
+        message: PusherMessage,
+        except: Option<&SocketId>,
+        app_id: &str,
+    ) -> Result<()> {
+        let published_at = chrono::Utc::now().timestamp_millis();
+        // 1. Deliver to local sockets
+        if let Err(e) = self
+            .local_adapter
+            .send(channel, message.clone(), except, app_id)
+            .await
+        {
+            warn!(
+                "{}",
+                format!(
+                    "Local send failed during broadcast for channel {}: {}",
+                    channel, e
+                )
+            );
+        }
+
+        // 2. Wrap the message for the other nodes
+        let broadcast = BroadcastMessage {
+            node_id: self.node_id.clone(),
+            app_id: app_id.to_string(),
+            channel: channel.to_string(),
+            message: serde_json::to_string(&message)?,
+            except_socket_id: except.map(|id| id.0.clone()),
+            published_at: Some(published_at),
+        };
+
+        // 3. Publish to the broadcast exchange, or with channel interest to
+        // the channel's shard. Server notices and app disconnects go to the
+        // broadcast exchange, which every node consumes.
+        let broadcast_data = self.codec.encode(&broadcast)?;
+        match &self.interest {
+            Some(interest) if !is_app_wide_channel(channel) => {
+                let shard = interest.shard_for(app_id, channel);
+                self.publish(
+                    &self.shard_exchange,
+                    &Self::shard_routing_key(shard),
+                    &broadcast_data,
+                )
+                .await
+            }
+            _ => {
+                self.publish(&self.broadcast_exchange, "", &broadcast_data)
+                    .await
+            }
+        }
+    }
+
+    async fn get_channel_members(
+        &self,
+        app_id: &str,
+        channel: &str,
+    ) -> Result<HashMap<String, PresenceMemberInfo>> {
+        let node_count = self.get_node_count().await?;
+
+        let mut members = self
+            .local_adapter
+            .get_channel_members(app_id, channel)
+            .await?;
+
+        if node_count > 1 {
+            let response_data = self
+                .requester()
+                .await
+                .send_request(
+                    app_id,
+                    RequestType::ChannelMembers,
+                    Some(channel),
+                    None,
+                    None,
+                    node_count,
+                )
+                .await?;
+            members.extend(response_data.members);
+        }
+
+        Ok(members)
+    }
+
+    // Returns only local sockets - inherent limitation
+    async fn get_channel_sockets(
+        &self,
+        app_id: &str,
+        channel: &str,
+    ) -> Result<DashMap<SocketId, Arc<Mutex<WebSocket>>>> {
+        self.local_adapter
+            .get_channel_sockets(app_id, channel)
+            .await
+    }
+
+    async fn get_channel(&self, app_id: &str, channel: &str) -> Result<DashSet<SocketId>> {
+        let node_count = self.get_node_count().await?;
+
+        let result = self.local_adapter.get_channel(app_id, channel).await?;
+
+        if node_count > 1 {
+            let response_data = self
+                .requester()
+                .await
+                .send_request(
+                    app_id,
+                    RequestType::ChannelSockets,
+                    Some(channel),
+                    None,
+                    None,
+                    node_count,
+                )
+                .await?;
+
+            for socket_id in response_data.socket_ids {
+                result.insert(SocketId(socket_id));
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn remove_channel(&self, app_id: &str, channel: &str) {
+        self.local_adapter.remove_channel(app_id, channel).await
+    }
+
+    async fn is_in_channel(
+        &self,
+        app_id: &str,
+        channel: &str,
+        socket_id: &SocketId,
+    ) -> Result<bool> {
+        if self
+            .local_adapter
+            .is_in_channel(app_id, channel, socket_id)
+            .await?
+        {
+            return Ok(true);
+        }
+
+        let node_count = self.get_node_count().await?;
+        if node_count > 1 {
+            let response_data = self
+                .requester()
+                .await
+                .send_request(
+                    app_id,
+                    RequestType::SocketExistsInChannel,
+                    Some(channel),
+                    Some(&socket_id.0),
+                    None,
+                    node_count,
+                )
+                .await?;
+            return Ok(response_data.exists);
+        }
+
+        Ok(false)
+    }
+
+    // Returns only local sockets - inherent limitation
+    async fn get_user_sockets(&self, user_id: &str, app_id: &str) -> Result<DashSet<WebSocketRef>> {
+        self.local_adapter.get_user_sockets(user_id, app_id).await
+    }
+
+    async fn cleanup_connection(&self, app_id: &str, ws: WebSocketRef) {
+        self.local_adapter.cleanup_connection(app_id, ws).await
+    }
+
+    async fn terminate_connection(&self, app_id: &str, user_id: &str) -> Result<()> {
+        let node_count = self.get_node_count().await?;
+
+        self.local_adapter
+            .terminate_connection(app_id, user_id)
+            .await?;
+
+        if node_count > 1 {
+            self.requester()
+                .await
+                .send_request(
+                    app_id,
+                    RequestType::TerminateUserConnections,
+                    None,
+                    None,
+                    Some(user_id),
+                    node_count,
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn add_channel_to_sockets(&self, app_id: &str, channel: &str, socket_id: &SocketId) {
+        self.local_adapter
+            .add_channel_to_sockets(app_id, channel, socket_id)
+            .await
+    }
+
+    async fn get_channel_socket_count(&self, app_id: &str, channel: &str) -> usize {
+        let node_count = self.get_node_count().await.unwrap_or(1);
+
+        let local_count = self
+            .local_adapter
+            .get_channel_socket_count(app_id, channel)
+            .await;
+
+        if node_count > 1 {
+            match self
+                .requester()
+                .await
+                .send_request(
+                    app_id,
+                    RequestType::ChannelSocketsCount,
+                    Some(channel),
+                    None,
+                    None,
+                    node_count,
+                )
+                .await
+            {
+                Ok(response_data) => local_count + response_data.sockets_count,
+                Err(e) => {
+                    error!(
+                        "{}",
+                        format!(
+                            "Failed to get remote socket count for channel {}: {}",
+                            channel, e
+                        )
+                    );
+                    local_count
+                }
+            }
+        } else {
+            local_count
+        }
+    }
+
+    async fn add_to_channel(
+        &self,
+        app_id: &str,
+        channel: &str,
+        socket_id: &SocketId,
+    ) -> Result<bool> {
+        let added = self
+            .local_adapter
+            .add_to_channel(app_id, channel, socket_id)
+            .await?;
+        self.claim_shard(app_id, channel).await;
+        Ok(added)
+    }
+
+    async fn remove_from_channel(
+        &self,
+        app_id: &str,
+        channel: &str,
+        socket_id: &SocketId,
+    ) -> Result<bool> {
+        self.local_adapter
+            .remove_from_channel(app_id, channel, socket_id)
+            .await
+    }
+
+    async fn get_presence_member(
+        &self,
+        app_id: &str,
+        channel: &str,
+        socket_id: &SocketId,
+    ) -> Option<PresenceMemberInfo> {
+        self.local_adapter
+            .get_presence_member(app_id, channel, socket_id)
+            .await
+    }
+
+    async fn terminate_user_connections(&self, app_id: &str, user_id: &str) -> Result<()> {
+        self.terminate_connection(app_id, user_id).await
+    }
+
+    async fn add_user(&self, ws: Arc<Mutex<WebSocket>>) -> Result<()> {
+        self.local_adapter.add_user(ws).await
+    }
+
+    async fn remove_user(&self, ws: Arc<Mutex<WebSocket>>) -> Result<()> {
+        self.local_adapter.remove_user(ws).await
+    }
+
+    async fn get_channels_with_socket_count(&self, app_id: &str) -> Result<DashMap<String, usize>> {
+        let node_count = self.get_node_count().await?;
+
+        let local_channels = self
+            .local_adapter
+            .get_channels_with_socket_count(app_id)
+            .await?;
+
+        if node_count > 1 {
+            let response_data = self
+                .requester()
+                .await
+                .send_request(
+                    app_id,
+                    RequestType::ChannelsWithSocketsCount,
+                    None,
+                    None,
+                    None,
+                    node_count,
+                )
+                .await?;
+            for (channel, count) in response_data.channels_with_sockets_count {
+                *local_channels.entry(channel).or_insert(0) += count;
+            }
+        }
+
+        Ok(local_channels)
+    }
+
+    async fn get_sockets_count(&self, app_id: &str) -> Result<usize> {
+        let node_count = self.get_node_count().await?;
+
+        let local_count = self.local_adapter.get_sockets_count(app_id).await?;
+
+        if node_count > 1 {
+            let response_data = self
+                .requester()
+                .await
+                .send_request(
+                    app_id,
+                    RequestType::SocketsCount,
+                    None,
+                    None,
+                    None,
+                    node_count,
+                )
+                .await?;
+            return Ok(local_count + response_data.sockets_count);
+        }
+
+        Ok(local_count)
+    }
+
+    async fn get_channel_socket_details(
+        &self,
+        app_id: &str,
+        channel: &str,
+        sample: usize,
+    ) -> Result<Vec<SocketDetails>> {
+        let node_count = self.get_node_count().await?;
+        self.requester()
+            .await
+            .get_channel_socket_details(app_id, channel, sample, node_count)
+            .await
+    }
+
+    async fn get_namespaces(&self) -> Result<DashMap<String, Arc<Namespace>>> {
+        self.local_adapter.get_namespaces().await
+    }
+
+    async fn health(&self) -> Result<()> {
+        let status = self.link.read().await.connection.status().clone();
+        if !status.connected() {
+            return Err(Error::InternalError(format!(
+                "RabbitMQ connection is not established: {:?}",
+                status.state()
+            )));
+        }
+
+        if !self.listener.is_running() {
+            return Err(Error::InternalError(
+                "RabbitMQ consumers are not running".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn reconnect(&self) -> Result<()> {
+        // lapin does not reconnect on its own, so open a fresh connection when
+        // the old one is gone and restart the consumers on top of it
+        let connected = self.link.read().await.connection.status().connected();
+        if !connected {
+            info!("{}", "Reconnecting RabbitMQ adapter");
+            let link = Self::connect(
+                &self.config,
+                &[
+                    &self.broadcast_exchange,
+                    &self.request_exchange,
+                    &self.response_exchange,
+                    &self.nodes_exchange,
+                ],
+                &self.shard_exchange,
+            )
+            .await?;
+            *self.link.write().await = link;
+            self.listener.stopped();
+        }
+
+        // Restarts the consumers unless they are running or starting
+        self.start_consumers().await
+    }
+
+    async fn register_node(&self, node: &NodeInfo) -> Result<()> {
+        let entry = serde_json::to_vec(node)?;
+        self.publish(&self.nodes_exchange, "", &entry).await
+    }
+
+    async fn registered_nodes(&self) -> Result<Vec<NodeInfo>> {
+        let now = chrono::Utc::now().timestamp_millis();
+        self.nodes.retain(|_, node| !node.is_expired(now));
+        Ok(self
+            .nodes
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect())
+    }
+
+    async fn set_metrics(
+        &self,
+        metrics: Arc<Mutex<dyn MetricsInterface + Send + Sync>>,
+    ) -> Result<()> {
+        self.horizontal.lock().await.metrics = Some(metrics);
+        Ok(())
+    }
+
+    fn set_app_manager(&self, app_manager: Arc<dyn AppManager + Send + Sync>) {
+        self.local_adapter.set_app_manager(app_manager);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::{AdapterCompressionConfig, CompressionAlgorithm};
+
+    fn broadcast(message: &str) -> BroadcastMessage {
+        BroadcastMessage {
+            node_id: "node-a".to_string(),
+            app_id: "app-1".to_string(),
+            channel: "presence-room".to_string(),
+            message: message.to_string(),
+            except_socket_id: Some("1.2".to_string()),
+            published_at: Some(1_700_000_000_000),
+        }
+    }
+
+    #[test]
+    fn test_broadcast_payloads_round_trip() {
+        let large = "x".repeat(4096);
+        for algorithm in [
+            CompressionAlgorithm::None,
+            CompressionAlgorithm::Zstd,
+            CompressionAlgorithm::Lz4,
+        ] {
+            let codec = BroadcastCodec::new(&AdapterCompressionConfig {
+                algorithm,
+                ..AdapterCompressionConfig::default()
+            });
+            for message in ["{}", large.as_str()] {
+                let payload = codec.encode(&broadcast(message)).unwrap();
+                let compressed = payload.first() == Some(&0x00);
+                assert_eq!(
+                    compressed,
+                    algorithm != CompressionAlgorithm::None && message.len() > 1024
+                );
+                let decoded = BroadcastCodec::decode(&payload).unwrap();
+                assert_eq!(decoded.message, message);
+                assert_eq!(decoded.channel, "presence-room");
+                assert_eq!(decoded.except_socket_id.as_deref(), Some("1.2"));
+                assert_eq!(decoded.published_at, Some(1_700_000_000_000));
+            }
+        }
+    }
+
+    #[test]
+    fn test_unknown_compression_is_rejected() {
+        assert!(BroadcastCodec::decode(&[0x00, 9, 1, 2]).is_err());
+        assert!(BroadcastCodec::decode(b"not json").is_err());
+    }
+
+    #[test]
+    fn test_requests_round_trip() {
+        let request = RequestBody {
+            request_id: "req-1".to_string(),
+            node_id: "node-a".to_string(),
+            app_id: "app-1".to_string(),
+            request_type: RequestType::ChannelSocketsCount,
+            channel: Some("chat".to_string()),
+            socket_id: None,
+            user_id: None,
+            limit: Some(10),
+        };
+        let decoded: RequestBody =
+            serde_json::from_slice(&serde_json::to_vec(&request).unwrap()).unwrap();
+        assert_eq!(decoded.request_id, "req-1");
+        assert_eq!(decoded.channel.as_deref(), Some("chat"));
+        assert_eq!(decoded.limit, Some(10));
+    }
+
+    #[test]
+    fn test_shard_routing_key() {
+        assert_eq!(RabbitMqAdapter::shard_routing_key(0), "shard.0");
+        assert_eq!(RabbitMqAdapter::shard_routing_key(63), "shard.63");
+    }
+}
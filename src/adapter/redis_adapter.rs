@@ -1,6 +1,7 @@
 use std::any::Any;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use dashmap::{DashMap, DashSet};
@@ -10,27 +11,38 @@ use hyper::upgrade::Upgraded;
 use hyper_util::rt::TokioIo;
 use redis::AsyncCommands;
 use tokio::io::WriteHalf;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, RwLock, mpsc, oneshot};
 use tracing::{error, info, warn};
 
 use crate::adapter::adapter::Adapter;
 use crate::adapter::horizontal_adapter::{
-    BroadcastMessage, HorizontalAdapter, RequestBody, RequestType, ResponseBody,
+    BroadcastCodec, BroadcastMessage, HorizontalAdapter, RequestBody, RequestType, ResponseBody,
 };
+use crate::adapter::hot_channel::HotChannels;
+use crate::adapter::interest::{ChannelInterest, InterestAnnouncement, channel_shard};
+use crate::adapter::listener::ListenerState;
+use crate::adapter::local_adapter::LocalAdapter;
+use crate::adapter::occupancy::{claim_transition, occupancy_key};
+use crate::adapter::registry::NodeInfo;
 use crate::app::manager::AppManager;
 use crate::channel::PresenceMemberInfo;
 use crate::error::{Error, Result};
 
 use crate::metrics::MetricsInterface;
 use crate::namespace::Namespace;
+use crate::options::{RedisPublishBatchingConfig, RedisTlsConfig};
 use crate::protocol::messages::PusherMessage;
-use crate::websocket::{SocketId, WebSocket, WebSocketRef};
+use crate::redis_client::RedisDegradedMode;
+use crate::utils::is_app_wide_channel;
+use crate::websocket::{SocketDetails, SocketId, WebSocket, WebSocketRef};
 
 /// Redis channels
 pub const DEFAULT_PREFIX: &str = "sockudo";
 const BROADCAST_SUFFIX: &str = "#broadcast";
 const REQUESTS_SUFFIX: &str = "#requests";
 const RESPONSES_SUFFIX: &str = "#responses";
+const INTEREST_SUFFIX: &str = "#interest";
+const NODES_SUFFIX: &str = "#nodes";
 
 /// Redis adapter configuration
 #[derive(Debug, Clone)]
@@ -45,6 +57,14 @@ pub struct RedisAdapterConfig {
     pub use_connection_manager: bool,
     /// Cluster mode (for Redis Cluster)
     pub cluster_mode: bool,
+    /// Coalesce broadcast publishes into pipelined commands
+    pub publish_batching: RedisPublishBatchingConfig,
+    /// Number of pub/sub channels broadcasts are spread over
+    pub broadcast_shards: u32,
+    /// ACL username and password, overriding any in the URL
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub tls: RedisTlsConfig,
 }
 
 impl Default for RedisAdapterConfig {
@@ -55,31 +75,170 @@ impl Default for RedisAdapterConfig {
             request_timeout_ms: 5000,
             use_connection_manager: true,
             cluster_mode: false,
+            publish_batching: RedisPublishBatchingConfig::default(),
+            broadcast_shards: 1,
+            username: None,
+            password: None,
+            tls: RedisTlsConfig::default(),
         }
     }
 }
 
+/// A queued publish and where to report whether it reached Redis
+type BatchedPublish = (
+    String,
+    Vec<u8>,
+    oneshot::Sender<std::result::Result<(), String>>,
+);
+
+/// Micro-batching layer for broadcast publishes. Messages queued within one
+/// flush interval are sent to Redis as a single pipeline instead of one
+/// PUBLISH round trip each.
+pub struct PublishBatcher {
+    sender: mpsc::UnboundedSender<BatchedPublish>,
+}
+
+impl PublishBatcher {
+    /// Spawn the flush task publishing through the adapter's shared connection
+    pub fn start(
+        connection: Arc<RwLock<redis::aio::MultiplexedConnection>>,
+        config: &RedisPublishBatchingConfig,
+        degraded: Arc<RedisDegradedMode>,
+    ) -> Self {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<BatchedPublish>();
+        let flush_interval = Duration::from_millis(config.flush_interval_ms.max(1));
+        let max_batch_size = config.max_batch_size.max(1);
+
+        tokio::spawn(async move {
+            while let Some(first) = receiver.recv().await {
+                let mut batch = vec![first];
+                let deadline = tokio::time::Instant::now() + flush_interval;
+
+                // Collect everything else queued before the flush deadline
+                while batch.len() < max_batch_size {
+                    match tokio::time::timeout_at(deadline, receiver.recv()).await {
+                        Ok(Some(item)) => batch.push(item),
+                        Ok(None) | Err(_) => break,
+                    }
+                }
+
+                let mut result = Self::flush(&connection, &batch).await;
+                let refused = matches!(&result, Err(e) if degraded.record_error(e));
+                if !refused && result.is_err() {
+                    // A connection error is usually gone by the next attempt
+                    warn!(
+                        "{}",
+                        format!("Retrying failed publish batch of {} messages", batch.len())
+                    );
+                    result = Self::flush(&connection, &batch).await;
+                }
+
+                let outcome = match result {
+                    Ok(()) => {
+                        degraded.record_success();
+                        Ok(())
+                    }
+                    // Broadcasts stay local while Redis refuses writes
+                    Err(e) if refused || degraded.record_error(&e) => Ok(()),
+                    Err(e) => {
+                        let message =
+                            format!("Failed to publish batch of {} messages: {}", batch.len(), e);
+                        error!("{}", message);
+                        Err(message)
+                    }
+                };
+                for (_, _, done) in batch {
+                    let _ = done.send(outcome.clone());
+                }
+            }
+        });
+
+        Self { sender }
+    }
+
+    async fn flush(
+        connection: &RwLock<redis::aio::MultiplexedConnection>,
+        batch: &[BatchedPublish],
+    ) -> redis::RedisResult<()> {
+        let mut pipe = redis::pipe();
+        for (channel, payload, _) in batch {
+            pipe.publish(channel, payload).ignore();
+        }
+
+        let mut conn = connection.read().await.clone();
+        pipe.query_async::<()>(&mut conn).await
+    }
+
+    /// Queue a message for the next flush. The receiver resolves once the
+    /// batch holding it was published, or with the error that made it fail.
+    /// Returns the message back if the flush task is gone so the caller can
+    /// publish it directly.
+    pub fn enqueue(
+        &self,
+        channel: String,
+        payload: Vec<u8>,
+    ) -> std::result::Result<oneshot::Receiver<std::result::Result<(), String>>, Vec<u8>> {
+        let (done, published) = oneshot::channel();
+        self.sender
+            .send((channel, payload, done))
+            .map(|()| published)
+            .map_err(|mpsc::error::SendError((_, payload, _))| payload)
+    }
+}
+
 /// Redis adapter for horizontal scaling (Optimized Version)
 pub struct RedisAdapter {
-    /// Base horizontal adapter (protected by a Mutex)
-    /// Optimization Note: While the Mutex remains, methods now try to minimize lock duration.
-    /// Further optimization would require refactoring HorizontalAdapter internals.
+    /// Base horizontal adapter (protected by a Mutex). Only cross-node requests
+    /// take this lock; local operations go through `local_adapter`.
     pub horizontal: Arc<Mutex<HorizontalAdapter>>,
 
+    /// Local connection registry, shared with the horizontal adapter
+    pub local_adapter: Arc<LocalAdapter>,
+
+    /// Unique ID of this node, copied from the horizontal adapter
+    pub node_id: String,
+
     /// Redis client
     pub client: redis::Client,
 
     /// Redis connection for publishing (Multiplexed for efficiency)
-    pub connection: redis::aio::MultiplexedConnection,
+    pub connection: Arc<RwLock<redis::aio::MultiplexedConnection>>,
 
     /// Channel names
     pub prefix: String,
     pub broadcast_channel: String,
     pub request_channel: String,
     pub response_channel: String,
+    /// Hash holding the node registry entries, keyed by process ID
+    pub nodes_key: String,
 
     /// Configuration
     pub config: RedisAdapterConfig,
+
+    /// Whether the Pub/Sub listener task is stopped, subscribing or running
+    pub listener: Arc<ListenerState>,
+
+    /// Batches broadcast publishes when `publish_batching` is enabled
+    pub publish_batcher: Option<PublishBatcher>,
+
+    /// Encodes (and optionally compresses) outgoing broadcasts
+    pub codec: BroadcastCodec,
+
+    /// Per-shard broadcast topics, when channel interest tracking is enabled
+    pub interest: Option<Arc<ChannelInterest>>,
+    pub interest_channel: String,
+
+    /// Sticky owners for hot channels, when configured
+    pub hot_channels: Option<Arc<HotChannels>>,
+
+    /// Subscription half of the Pub/Sub connection, used to follow shard topics
+    pubsub_sink: Arc<Mutex<Option<redis::aio::PubSubSink>>>,
+
+    /// Set while Redis refuses writes (OOM/READONLY); broadcasts stay local
+    pub degraded: Arc<RedisDegradedMode>,
+
+    /// Metrics driver, shared with the listener task for broadcast counts
+    pub metrics: Arc<RwLock<Option<Arc<Mutex<dyn MetricsInterface + Send + Sync>>>>>,
 }
 
 impl RedisAdapter {
@@ -93,8 +252,12 @@ impl RedisAdapter {
         horizontal.requests_timeout = config.request_timeout_ms;
 
         // Create Redis client
-        let client = redis::Client::open(&*config.url)
-            .map_err(|e| Error::RedisError(format!("Failed to create Redis client: {}", e)))?;
+        let client = crate::redis_client::build_client(
+            &config.url,
+            config.username.as_deref(),
+            config.password.as_deref(),
+            &config.tls,
+        )?;
 
         // Get connection based on configuration
         let connection = if config.use_connection_manager {
@@ -115,38 +278,66 @@ impl RedisAdapter {
         let broadcast_channel = format!("{}:{}", config.prefix, BROADCAST_SUFFIX);
         let request_channel = format!("{}:{}", config.prefix, REQUESTS_SUFFIX);
         let response_channel = format!("{}:{}", config.prefix, RESPONSES_SUFFIX);
+        let nodes_key = format!("{}:{}", config.prefix, NODES_SUFFIX);
+        let interest_channel = format!("{}:{}", config.prefix, INTEREST_SUFFIX);
+
+        let local_adapter = horizontal.local_adapter.clone();
+
+        let node_id = horizontal.node_id.clone();
+
+        let connection = Arc::new(RwLock::new(connection));
+        let degraded = Arc::new(RedisDegradedMode::new(
+            "the Redis adapter",
+            "local-only broadcasts",
+        ));
+        let publish_batcher = if config.publish_batching.enabled {
+            info!(
+                "{}",
+                format!(
+                    "Redis publish batching enabled (flush interval: {}ms, max batch: {})",
+                    config.publish_batching.flush_interval_ms,
+                    config.publish_batching.max_batch_size
+                )
+            );
+            Some(PublishBatcher::start(
+                connection.clone(),
+                &config.publish_batching,
+                degraded.clone(),
+            ))
+        } else {
+            None
+        };
 
         // Create the adapter
         let adapter = Self {
             horizontal: Arc::new(Mutex::new(horizontal)),
+            local_adapter,
+            node_id,
             client,
             connection,
             prefix: config.prefix.clone(),
             broadcast_channel,
             request_channel,
             response_channel,
+            nodes_key,
             config,
+            listener: Arc::new(ListenerState::default()),
+            publish_batcher,
+            codec: BroadcastCodec::default(),
+            interest: None,
+            interest_channel,
+            hot_channels: None,
+            pubsub_sink: Arc::new(Mutex::new(None)),
+            degraded,
+            metrics: Arc::new(RwLock::new(None)),
         };
 
         Ok(adapter)
     }
 
-    pub async fn set_metrics(
-        &mut self,
-        metrics: Arc<Mutex<dyn MetricsInterface + Send + Sync>>,
-    ) -> Result<()> {
-        // Get lock on the horizontal adapter
-        let mut horizontal = self.horizontal.lock().await;
-
-        // Set the metrics in horizontal adapter
-        horizontal.metrics = Some(metrics);
-
-        Ok(())
-    }
-
     // Method to initialize metrics during adapter startup
     pub async fn init_with_metrics(
-        &mut self,
+        &self,
         metrics: Option<Arc<Mutex<dyn MetricsInterface + Send + Sync>>>,
     ) -> Result<()> {
         // First initialize the adapter
@@ -160,6 +351,19 @@ impl RedisAdapter {
         Ok(())
     }
 
+    /// Record how long a broadcast publish took, when metrics are enabled
+    async fn track_publish_latency(&self, app_id: &str, started: std::time::Instant) {
+        if let Some(metrics) = self.metrics.read().await.as_ref() {
+            metrics
+                .lock()
+                .await
+                .track_horizontal_adapter_publish_latency(
+                    app_id,
+                    started.elapsed().as_secs_f64() * 1000.0,
+                );
+        }
+    }
+
     /// Create a new Redis adapter with simple configuration
     pub async fn with_url(redis_url: &str) -> Result<Self> {
         let config = RedisAdapterConfig {
@@ -180,51 +384,250 @@ impl RedisAdapter {
 
         // Start PubSub listeners
         self.start_listeners_pubsub().await?;
+        self.start_interest_task();
+        self.start_hot_channel_task();
 
         Ok(())
     }
 
+    /// Topic carrying broadcasts for one channel shard
+    fn shard_channel(broadcast_channel: &str, shard: u32) -> String {
+        format!("{}:{}", broadcast_channel, shard)
+    }
+
+    /// Periodically drop shards without local subscribers and re-announce
+    /// this node's interest so other nodes keep publishing to it
+    fn start_interest_task(&self) {
+        let Some(interest) = self.interest.clone() else {
+            return;
+        };
+        let local_adapter = self.local_adapter.clone();
+        let connection = self.connection.clone();
+        let pubsub_sink = self.pubsub_sink.clone();
+        let broadcast_channel = self.broadcast_channel.clone();
+        let interest_channel = self.interest_channel.clone();
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interest.announce_interval());
+            loop {
+                ticker.tick().await;
+                let released = interest.release_idle(&local_adapter);
+                let sink = pubsub_sink.lock().await.clone();
+                if let Some(mut sink) = sink {
+                    for shard in released {
+                        let topic = Self::shard_channel(&broadcast_channel, shard);
+                        if let Err(e) = sink.unsubscribe(&topic).await {
+                            warn!("{}", format!("Failed to unsubscribe from {}: {}", topic, e));
+                        }
+                        // Claimed again while we were unsubscribing
+                        if interest.is_local(shard) {
+                            if let Err(e) = sink.subscribe(&topic).await {
+                                warn!("{}", format!("Failed to subscribe to {}: {}", topic, e));
+                            }
+                        }
+                    }
+                }
+                Self::announce_interest(&connection, &interest_channel, &interest).await;
+            }
+        });
+    }
+
+    /// Periodically renew the leases of hot channels this node owns
+    fn start_hot_channel_task(&self) {
+        let Some(hot_channels) = self.hot_channels.clone() else {
+            return;
+        };
+        let connection = self.connection.clone();
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(hot_channels.renew_interval());
+            loop {
+                ticker.tick().await;
+                let mut conn = connection.read().await.clone();
+                if let Err(e) = hot_channels.renew(&mut conn).await {
+                    warn!("{}", format!("Failed to renew hot channel leases: {}", e));
+                }
+            }
+        });
+    }
+
+    /// Publish to a hot channel through its owner. Returns false when the
+    /// owner is unreachable and the message should be broadcast as usual.
+    async fn send_hot(
+        &self,
+        hot_channels: &HotChannels,
+        broadcast: BroadcastMessage,
+        message: PusherMessage,
+        except: Option<&SocketId>,
+    ) -> Result<bool> {
+        let mut conn = self.connection.read().await.clone();
+        let owner = hot_channels
+            .owner(&mut conn, &broadcast.app_id, &broadcast.channel)
+            .await
+            .map_err(|e| {
+                Error::RedisError(format!("Failed to look up hot channel owner: {}", e))
+            })?;
+        let payload = self.codec.encode(&broadcast)?;
+
+        if owner != self.node_id {
+            let receivers: i64 = conn
+                .publish(hot_channels.publish_topic(&owner), payload)
+                .await
+                .map_err(|e| {
+                    Error::RedisError(format!("Failed to forward to hot channel owner: {}", e))
+                })?;
+            if receivers == 0 {
+                hot_channels.forget_owner(&broadcast.app_id, &broadcast.channel);
+                return Ok(false);
+            }
+            return Ok(true);
+        }
+
+        if let Err(e) = self
+            .local_adapter
+            .deliver(
+                &broadcast.channel,
+                message,
+                except,
+                &broadcast.app_id,
+                broadcast.published_at,
+            )
+            .await
+        {
+            warn!(
+                "{}",
+                format!(
+                    "Local send failed for hot channel {}: {}",
+                    broadcast.channel, e
+                )
+            );
+        }
+        hot_channels
+            .fan_out(&mut conn, &broadcast.app_id, &broadcast.channel, &payload)
+            .await
+            .map_err(|e| Error::RedisError(format!("Failed to fan out hot channel: {}", e)))?;
+        Ok(true)
+    }
+
+    async fn announce_interest(
+        connection: &RwLock<redis::aio::MultiplexedConnection>,
+        interest_channel: &str,
+        interest: &ChannelInterest,
+    ) {
+        let announcement = match serde_json::to_vec(&interest.announcement()) {
+            Ok(announcement) => announcement,
+            Err(e) => {
+                error!("{}", format!("Failed to serialize interest: {}", e));
+                return;
+            }
+        };
+        let mut conn = connection.read().await.clone();
+        if let Err(e) = conn
+            .publish::<_, _, ()>(interest_channel, announcement)
+            .await
+        {
+            warn!("{}", format!("Failed to announce channel interest: {}", e));
+        }
+    }
+
+    /// Start consuming the shard of a channel that just gained a local subscriber
+    async fn claim_shard(&self, app_id: &str, channel: &str) {
+        let Some(interest) = &self.interest else {
+            return;
+        };
+        let Some(shard) = interest.claim(app_id, channel) else {
+            return;
+        };
+        // Without a sink the listener is down; it subscribes to every local
+        // shard when it comes back
+        let sink = self.pubsub_sink.lock().await.clone();
+        if let Some(mut sink) = sink {
+            let topic = Self::shard_channel(&self.broadcast_channel, shard);
+            if let Err(e) = sink.subscribe(&topic).await {
+                warn!("{}", format!("Failed to subscribe to {}: {}", topic, e));
+            }
+        }
+        Self::announce_interest(&self.connection, &self.interest_channel, interest).await;
+    }
+
     /// Start traditional PubSub listeners (Optimized with task spawning)
     async fn start_listeners_pubsub(&self) -> Result<()> {
+        // Another start may still be subscribing, or the listener is up
+        if !self.listener.try_start() {
+            return Ok(());
+        }
+
         // Create a subscription connection (separate from the multiplexed one)
         let sub_client = self.client.clone();
 
         // Clone needed values for the async task
         // Clone Arc for cheap sharing across tasks
         let horizontal_arc = self.horizontal.clone();
-        let pub_connection = self.connection.clone();
+        let local_adapter = self.local_adapter.clone();
+        let pub_connection = self.connection.read().await.clone();
         let broadcast_channel = self.broadcast_channel.clone();
         let request_channel = self.request_channel.clone();
         let response_channel = self.response_channel.clone();
-
-        // Get node_id without holding the lock for the whole setup
-        let node_id = {
-            let horizontal_lock = horizontal_arc.lock().await;
-            horizontal_lock.node_id.clone()
-        };
+        let listener = self.listener.clone();
+        let metrics = self.metrics.clone();
+        let interest = self.interest.clone();
+        let interest_channel = self.interest_channel.clone();
+        let broadcast_shards = self.config.broadcast_shards;
+        let shard_prefix = format!("{}:", self.broadcast_channel);
+        let pubsub_sink = self.pubsub_sink.clone();
+        let hot_channels = self.hot_channels.clone();
+        let hot_publish_topic = hot_channels
+            .as_ref()
+            .map(|hot_channels| hot_channels.publish_topic(&self.node_id));
+        let hot_deliver_topic = hot_channels
+            .as_ref()
+            .map(|hot_channels| hot_channels.deliver_topic(&self.node_id));
+
+        let node_id = self.node_id.clone();
 
         // Spawn the main listener task
-        tokio::spawn(async move {
+        let task = tokio::spawn(async move {
             // Create a pubsub connection
             let mut pubsub = match sub_client.get_async_pubsub().await {
                 Ok(pubsub) => pubsub,
                 Err(e) => {
                     error!("{}", format!("Failed to get pubsub connection: {}", e));
-                    // Consider adding retry logic or more robust error handling here
+                    // The health supervisor will retry through `reconnect`
+                    listener.stopped();
                     return;
                 }
             };
 
-            // Subscribe to all channels
-            // Using psubscribe for potential pattern matching flexibility if needed later,
-            // but currently checking exact channel names.
-            if let Err(e) = pubsub
-                .subscribe(&[&broadcast_channel, &request_channel, &response_channel])
-                .await
-            {
+            // Subscribe to all channels, plus the shards of local channels
+            // when channel interest is enabled, or every broadcast shard
+            // when the topic is statically sharded
+            let mut channels = vec![
+                broadcast_channel.clone(),
+                request_channel.clone(),
+                response_channel.clone(),
+            ];
+            if let Some(interest) = &interest {
+                channels.push(interest_channel.clone());
+                channels.extend(
+                    interest
+                        .local_shards()
+                        .into_iter()
+                        .map(|shard| Self::shard_channel(&broadcast_channel, shard)),
+                );
+            } else if broadcast_shards > 1 {
+                channels.extend(
+                    (0..broadcast_shards)
+                        .map(|shard| Self::shard_channel(&broadcast_channel, shard)),
+                );
+            }
+            channels.extend(hot_publish_topic.iter().cloned());
+            channels.extend(hot_deliver_topic.iter().cloned());
+            if let Err(e) = pubsub.subscribe(&channels).await {
                 error!("{}", format!("Failed to subscribe to channels: {}", e));
+                listener.stopped();
                 return;
             }
+            listener.running();
 
             info!(
                 "{}",
@@ -234,32 +637,119 @@ impl RedisAdapter {
                 )
             );
 
-            // Listen for messages
-            let mut message_stream = pubsub.on_message();
+            // Listen for messages; the sink half stays available for shard
+            // subscriptions made while the listener runs
+            let (sink, mut message_stream) = pubsub.split();
+            *pubsub_sink.lock().await = Some(sink);
 
             while let Some(msg) = message_stream.next().await {
                 let channel: String = msg.get_channel_name().to_string();
-                let payload_result: redis::RedisResult<String> = msg.get_payload();
+                let payload_result: redis::RedisResult<Vec<u8>> = msg.get_payload();
+
+                // Interest announcements are tiny; record them inline
+                if channel == interest_channel {
+                    if let (Some(interest), Ok(payload)) = (&interest, &payload_result) {
+                        match serde_json::from_slice::<InterestAnnouncement>(payload) {
+                            Ok(announcement) => interest.record(announcement),
+                            Err(e) => warn!(
+                                "{}",
+                                format!("Failed to deserialize interest announcement: {}", e)
+                            ),
+                        }
+                    }
+                    continue;
+                }
 
                 if let Ok(payload) = payload_result {
                     // --- Optimization: Process each message type in its own task ---
                     let horizontal_clone = horizontal_arc.clone();
+                    let local_adapter_clone = local_adapter.clone();
+                    let metrics_clone = metrics.clone();
                     let node_id_clone = node_id.clone();
                     let pub_connection_clone = pub_connection.clone();
                     let broadcast_channel_clone = broadcast_channel.clone();
                     let request_channel_clone = request_channel.clone();
                     let response_channel_clone = response_channel.clone();
+                    let is_shard_channel = channel.starts_with(&shard_prefix);
+                    let is_hot_publish = hot_publish_topic.as_ref() == Some(&channel);
+                    let is_hot_delivery = hot_deliver_topic.as_ref() == Some(&channel);
+                    let hot_channels_clone = hot_channels.clone();
 
                     tokio::spawn(async move {
                         // Process based on channel name
-                        if channel == broadcast_channel_clone {
+                        if is_hot_publish {
+                            // Another node forwarded a publish to this owner
+                            let Some(hot_channels) = hot_channels_clone else {
+                                return;
+                            };
+                            let broadcast = match BroadcastCodec::decode(&payload) {
+                                Ok(broadcast) => broadcast,
+                                Err(e) => {
+                                    warn!(
+                                        "{}",
+                                        format!("Failed to deserialize hot channel publish: {}", e)
+                                    );
+                                    return;
+                                }
+                            };
+                            hot_channels.touch(&broadcast.app_id, &broadcast.channel);
+                            match serde_json::from_str(&broadcast.message) {
+                                Ok(message) => {
+                                    let except_id = broadcast
+                                        .except_socket_id
+                                        .as_ref()
+                                        .map(|id| SocketId(id.clone()));
+                                    if let Err(e) = local_adapter_clone
+                                        .deliver(
+                                            &broadcast.channel,
+                                            message,
+                                            except_id.as_ref(),
+                                            &broadcast.app_id,
+                                            broadcast.published_at,
+                                        )
+                                        .await
+                                    {
+                                        error!("Error: {}", e)
+                                    }
+                                }
+                                Err(e) => warn!(
+                                    "{}",
+                                    format!("Failed to deserialize hot channel message: {}", e)
+                                ),
+                            }
+                            let mut conn = pub_connection_clone.clone();
+                            if let Err(e) = hot_channels
+                                .fan_out(&mut conn, &broadcast.app_id, &broadcast.channel, &payload)
+                                .await
+                            {
+                                warn!(
+                                    "{}",
+                                    format!(
+                                        "Failed to fan out hot channel {}: {}",
+                                        broadcast.channel, e
+                                    )
+                                );
+                            }
+                        } else if channel == broadcast_channel_clone
+                            || is_shard_channel
+                            || is_hot_delivery
+                        {
                             // Handle broadcast message
-                            match serde_json::from_str::<BroadcastMessage>(&payload) {
+                            match BroadcastCodec::decode(&payload) {
                                 Ok(broadcast) => {
-                                    // Skip our own messages
-                                    if broadcast.node_id == node_id_clone {
+                                    // Skip our own messages; hot channel owners
+                                    // relay them back to the publishing node too
+                                    if broadcast.node_id == node_id_clone && !is_hot_delivery {
                                         return;
                                     }
+                                    if let Some(metrics) = metrics_clone.read().await.as_ref() {
+                                        metrics
+                                            .lock()
+                                            .await
+                                            .mark_horizontal_adapter_broadcast_received(
+                                                &broadcast.app_id,
+                                            );
+                                    }
                                     // Process the broadcast
                                     match serde_json::from_str(&broadcast.message) {
                                         Ok(message) => {
@@ -267,15 +757,14 @@ impl RedisAdapter {
                                                 .except_socket_id
                                                 .as_ref()
                                                 .map(|id| SocketId(id.clone()));
-                                            // Lock only when interacting with local adapter
-                                            let mut horizontal_lock = horizontal_clone.lock().await;
-                                            match horizontal_lock
-                                                .local_adapter
-                                                .send(
+                                            // Deliver to local sockets without taking the horizontal lock
+                                            match local_adapter_clone
+                                                .deliver(
                                                     &broadcast.channel,
                                                     message,
                                                     except_id.as_ref(),
                                                     &broadcast.app_id,
+                                                    broadcast.published_at,
                                                 )
                                                 .await
                                             {
@@ -284,7 +773,6 @@ impl RedisAdapter {
                                                     error!("Error: {}", e)
                                                 }
                                             }
-                                            // Lock released automatically when horizontal_lock goes out of scope
                                         }
                                         Err(e) => {
                                             warn!(
@@ -302,14 +790,15 @@ impl RedisAdapter {
                                         "{}",
                                         format!(
                                             "Failed to deserialize broadcast message: {}, Payload: {}",
-                                            e, payload
+                                            e,
+                                            String::from_utf8_lossy(&payload)
                                         )
                                     );
                                 }
                             }
                         } else if channel == request_channel_clone {
                             // Handle request message
-                            match serde_json::from_str::<RequestBody>(&payload) {
+                            match serde_json::from_slice::<RequestBody>(&payload) {
                                 Ok(request) => {
                                     // Skip our own requests
                                     if request.node_id == node_id_clone {
@@ -319,7 +808,7 @@ impl RedisAdapter {
                                     // Lock only when processing
                                     let response = {
                                         // Scope for the lock
-                                        let mut horizontal_lock = horizontal_clone.lock().await;
+                                        let horizontal_lock = horizontal_clone.lock().await;
                                         horizontal_lock.process_request(request).await
                                     }; // Lock released
                                     if let Ok(response) = response {
@@ -357,14 +846,15 @@ impl RedisAdapter {
                                         "{}",
                                         format!(
                                             "Failed to deserialize request message: {}, Payload: {}",
-                                            e, payload
+                                            e,
+                                            String::from_utf8_lossy(&payload)
                                         )
                                     );
                                 }
                             }
                         } else if channel == response_channel_clone {
                             // Handle response message
-                            match serde_json::from_str::<ResponseBody>(&payload) {
+                            match serde_json::from_slice::<ResponseBody>(&payload) {
                                 Ok(response) => {
                                     // Skip our own responses
                                     if response.node_id == node_id_clone {
@@ -381,7 +871,8 @@ impl RedisAdapter {
                                         "{}",
                                         format!(
                                             "Failed to deserialize response message: {}, Payload: {}",
-                                            e, payload
+                                            e,
+                                            String::from_utf8_lossy(&payload)
                                         )
                                     );
                                 }
@@ -395,8 +886,11 @@ impl RedisAdapter {
                     );
                 }
             }
-            info!("{}", "Redis Pub/Sub listener stream ended.");
+            listener.stopped();
+            *pubsub_sink.lock().await = None;
+            warn!("{}", "Redis Pub/Sub listener stream ended.");
         });
+        self.listener.track(&task);
 
         Ok(())
     }
@@ -414,7 +908,7 @@ impl RedisAdapter {
             Ok(5) // Placeholder
         } else {
             // Use a cloned connection for the command
-            let mut conn = self.connection.clone();
+            let mut conn = self.connection.read().await.clone();
 
             // Use the PUBSUB NUMSUB command directly
             let result: redis::RedisResult<Vec<redis::Value>> = redis::cmd("PUBSUB")
@@ -469,12 +963,8 @@ impl RedisAdapter {
 
 #[async_trait]
 impl Adapter for RedisAdapter {
-    async fn init(&mut self) {
-        // Lock scope minimized
-        {
-            let mut horizontal = self.horizontal.lock().await;
-            horizontal.local_adapter.init().await;
-        } // Lock released
+    async fn init(&self) {
+        self.local_adapter.init().await;
 
         // Start Redis listeners (already optimized)
         if let Err(e) = self.start_listeners().await {
@@ -482,121 +972,191 @@ impl Adapter for RedisAdapter {
             // Consider returning the error or handling it more gracefully
         }
     }
-    async fn get_namespace(&mut self, app_id: &str) -> Option<Arc<Namespace>> {
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal.local_adapter.get_namespace(app_id).await
+    async fn get_namespace(&self, app_id: &str) -> Option<Arc<Namespace>> {
+        self.local_adapter.get_namespace(app_id).await
     }
 
     async fn add_socket(
-        &mut self,
+        &self,
         socket_id: SocketId,
         socket: WebSocketWrite<WriteHalf<TokioIo<Upgraded>>>,
         app_id: &str,
         app_manager: &Arc<dyn AppManager + Send + Sync>,
     ) -> Result<()> {
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
+        self.local_adapter
             .add_socket(socket_id, socket, app_id, app_manager)
             .await
     }
 
     async fn get_connection(
-        &mut self,
+        &self,
         socket_id: &SocketId,
         app_id: &str,
     ) -> Option<Arc<Mutex<WebSocket>>> {
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
-            .get_connection(socket_id, app_id)
-            .await
+        self.local_adapter.get_connection(socket_id, app_id).await
     }
 
-    async fn remove_connection(&mut self, socket_id: &SocketId, app_id: &str) -> Result<()> {
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
+    async fn remove_connection(&self, socket_id: &SocketId, app_id: &str) -> Result<()> {
+        self.local_adapter
             .remove_connection(socket_id, app_id)
             .await
     }
 
     async fn send_message(
-        &mut self,
+        &self,
         app_id: &str,
         socket_id: &SocketId,
         message: PusherMessage,
     ) -> Result<()> {
         // This likely sends directly to a specific socket, lock scope depends on local_adapter.
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
+        self.local_adapter
             .send_message(app_id, socket_id, message)
             .await
     }
 
     /// Send to a channel (Optimized Lock Scope)
     async fn send(
-        &mut self,
+        &self,
         channel: &str,
         message: PusherMessage,
         except: Option<&SocketId>,
         app_id: &str,
     ) -> Result<()> {
-        // --- Optimization: Minimize lock duration ---
-        let (node_id, broadcast_data) = {
-            // 1. Lock and perform local send
-            let mut horizontal_lock = self.horizontal.lock().await;
-            let local_send_result = horizontal_lock
-                .local_adapter
-                .send(channel, message.clone(), except, app_id)
-                .await;
+        let published_at = chrono::Utc::now().timestamp_millis();
+
+        // Hot channels are fanned out by their owner, including to this node
+        if let Some(hot_channels) = &self.hot_channels {
+            if hot_channels.is_hot(channel) && !self.degraded.is_active() {
+                let broadcast = BroadcastMessage {
+                    node_id: self.node_id.clone(),
+                    app_id: app_id.to_string(),
+                    channel: channel.to_string(),
+                    message: serde_json::to_string(&message)?,
+                    except_socket_id: except.map(|id| id.0.clone()),
+                    published_at: Some(published_at),
+                };
+                match self
+                    .send_hot(hot_channels, broadcast, message.clone(), except)
+                    .await
+                {
+                    Ok(true) => return Ok(()),
+                    Ok(false) => {}
+                    Err(e) => warn!("{}", format!("{}, broadcasting to every node instead", e)),
+                }
+            }
+        }
 
-            // Log local send errors if necessary, but continue to broadcast
-            if let Err(e) = local_send_result {
-                warn!(
-                    "{}",
-                    format!(
-                        "Local send failed during broadcast for channel {}: {}",
-                        channel, e
-                    )
-                );
+        // 1. Deliver to local sockets; the local registry is sharded, so this
+        // never waits on broadcasts to other apps or channels
+        let local_send_result = self
+            .local_adapter
+            .send(channel, message.clone(), except, app_id)
+            .await;
+
+        // Log local send errors if necessary, but continue to broadcast
+        if let Err(e) = local_send_result {
+            warn!(
+                "{}",
+                format!(
+                    "Local send failed during broadcast for channel {}: {}",
+                    channel, e
+                )
+            );
+        }
+
+        // 2. Pick the topic; with channel interest, skip the publish entirely
+        // when no other node consumes this channel's shard. Server notices
+        // and app disconnects go to the base topic, which every node
+        // consumes.
+        let broadcast_channel = match &self.interest {
+            Some(interest) if !is_app_wide_channel(channel) => {
+                let shard = interest.shard_for(app_id, channel);
+                if !interest.remote_interested(shard) {
+                    return Ok(());
+                }
+                Self::shard_channel(&self.broadcast_channel, shard)
             }
+            None if self.config.broadcast_shards > 1 => Self::shard_channel(
+                &self.broadcast_channel,
+                channel_shard(app_id, channel, self.config.broadcast_shards),
+            ),
+            _ => self.broadcast_channel.clone(),
+        };
+
+        // While Redis refuses writes, other nodes are unreachable anyway;
+        // keep delivering locally instead of failing every broadcast
+        if self.degraded.is_active() {
+            return Ok(());
+        }
 
-            // 2. Prepare data needed for broadcast *outside* the lock
+        // 3. Prepare data needed for broadcast
+        let (node_id, broadcast_data) = (
+            self.node_id.clone(),
             (
-                horizontal_lock.node_id.clone(),
-                (
-                    app_id.to_string(),
-                    channel.to_string(),
-                    except.map(|id| id.0.clone()),
-                ),
-            )
-            // 3. Lock released here
-        };
+                app_id.to_string(),
+                channel.to_string(),
+                except.map(|id| id.0.clone()),
+            ),
+        );
 
-        // 4. Serialize the original message (outside the lock)
+        // 4. Serialize the original message
         let message_json = serde_json::to_string(&message)?;
 
-        // 5. Create broadcast message (outside the lock)
+        // 5. Create broadcast message
         let broadcast = BroadcastMessage {
             node_id, // Cloned node_id
             app_id: broadcast_data.0,
             channel: broadcast_data.1,
             message: message_json, // Serialized message
             except_socket_id: broadcast_data.2,
+            published_at: Some(published_at),
         };
 
         info!("{}", format!("Broadcasting message: {:?}", broadcast));
 
-        // 6. Serialize broadcast message (outside the lock)
-        let broadcast_json = serde_json::to_string(&broadcast)?;
-
-        // 7. Publish to Redis (outside the lock)
-        let mut conn = self.connection.clone();
-        conn.publish::<_, _, ()>(&self.broadcast_channel, broadcast_json)
+        // 6. Serialize (and compress, if configured) the broadcast message
+        let broadcast_payload = self.codec.encode(&broadcast)?;
+
+        // 7. Publish to Redis, through the batcher when enabled
+        let publish_start = std::time::Instant::now();
+        let broadcast_payload = match &self.publish_batcher {
+            Some(batcher) => match batcher.enqueue(broadcast_channel.clone(), broadcast_payload) {
+                Ok(published) => {
+                    return match published.await {
+                        Ok(Ok(())) => {
+                            self.track_publish_latency(app_id, publish_start).await;
+                            Ok(())
+                        }
+                        Ok(Err(e)) => Err(Error::RedisError(e)),
+                        Err(_) => Err(Error::RedisError(
+                            "Publish batcher stopped before flushing the broadcast".to_string(),
+                        )),
+                    };
+                }
+                Err(payload) => {
+                    warn!("{}", "Publish batcher stopped, publishing directly");
+                    payload
+                }
+            },
+            None => broadcast_payload,
+        };
+        let mut conn = self.connection.read().await.clone();
+        match conn
+            .publish::<_, _, ()>(&broadcast_channel, broadcast_payload)
             .await
-            .map_err(|e| Error::RedisError(format!("Failed to publish broadcast: {}", e)))?;
+        {
+            Ok(()) => {
+                self.degraded.record_success();
+                self.track_publish_latency(app_id, publish_start).await;
+            }
+            Err(e) if self.degraded.record_error(&e) => {}
+            Err(e) => {
+                return Err(Error::RedisError(format!(
+                    "Failed to publish broadcast: {}",
+                    e
+                )));
+            }
+        }
 
         Ok(())
     }
@@ -604,7 +1164,7 @@ impl Adapter for RedisAdapter {
     // Methods involving requests to other nodes: Lock scope depends on send_request.
     // `send_request` itself handles locking internally.
     async fn get_channel_members(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
     ) -> Result<HashMap<String, PresenceMemberInfo>> {
@@ -612,9 +1172,7 @@ impl Adapter for RedisAdapter {
 
         // Get local members with minimal lock duration
         let mut members = {
-            let mut horizontal = self.horizontal.lock().await;
-            horizontal
-                .local_adapter
+            self.local_adapter
                 .get_channel_members(app_id, channel)
                 .await?
         };
@@ -622,8 +1180,9 @@ impl Adapter for RedisAdapter {
         // Get distributed members if needed, with a separate lock acquisition
         if node_count > 1 {
             let response_data = {
-                let mut horizontal = self.horizontal.lock().await;
-                horizontal
+                self.horizontal
+                    .lock()
+                    .await
                     .send_request(
                         app_id,
                         RequestType::ChannelMembers,
@@ -642,31 +1201,28 @@ impl Adapter for RedisAdapter {
 
     // Returns only local sockets - inherent limitation
     async fn get_channel_sockets(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
     ) -> Result<DashMap<SocketId, Arc<Mutex<WebSocket>>>> {
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
+        self.local_adapter
             .get_channel_sockets(app_id, channel)
             .await
     }
 
-    async fn get_channel(&mut self, app_id: &str, channel: &str) -> Result<DashSet<SocketId>> {
+    async fn get_channel(&self, app_id: &str, channel: &str) -> Result<DashSet<SocketId>> {
         let node_count = self.get_node_count().await?;
-        let mut horizontal = self.horizontal.lock().await; // Lock for local + remote request
 
         // Start with local channel data
-        let result = horizontal
-            .local_adapter
-            .get_channel(app_id, channel)
-            .await?;
+        let result = self.local_adapter.get_channel(app_id, channel).await?;
 
         // Get distributed channels if needed
         if node_count > 1 {
             // send_request handles its own locking/timing
-            let response_data = horizontal
+            let response_data = self
+                .horizontal
+                .lock()
+                .await
                 .send_request(
                     app_id,
                     RequestType::ChannelSockets,
@@ -686,23 +1242,18 @@ impl Adapter for RedisAdapter {
         Ok(result)
     }
 
-    async fn remove_channel(&mut self, app_id: &str, channel: &str) {
+    async fn remove_channel(&self, app_id: &str, channel: &str) {
         // This seems purely local, lock scope depends on local_adapter
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
-            .remove_channel(app_id, channel)
-            .await
+        self.local_adapter.remove_channel(app_id, channel).await
     }
 
     async fn is_in_channel(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
         socket_id: &SocketId,
     ) -> Result<bool> {
         let node_count = self.get_node_count().await?; // Get count first
-        let mut horizontal = self.horizontal.lock().await; // Lock for local + potential remote
 
         warn!(
             "{}",
@@ -711,7 +1262,7 @@ impl Adapter for RedisAdapter {
                 socket_id, channel
             )
         );
-        let local_result = horizontal
+        let local_result = self
             .local_adapter
             .is_in_channel(app_id, channel, socket_id)
             .await?;
@@ -734,7 +1285,10 @@ impl Adapter for RedisAdapter {
                 )
             );
             // send_request handles its own locking/timing
-            let response_data = horizontal
+            let response_data = self
+                .horizontal
+                .lock()
+                .await
                 .send_request(
                     app_id,
                     RequestType::SocketExistsInChannel,
@@ -765,33 +1319,19 @@ impl Adapter for RedisAdapter {
     }
 
     // Returns only local sockets - inherent limitation
-    async fn get_user_sockets(
-        &mut self,
-        user_id: &str,
-        app_id: &str,
-    ) -> Result<DashSet<WebSocketRef>> {
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
-            .get_user_sockets(user_id, app_id)
-            .await
+    async fn get_user_sockets(&self, user_id: &str, app_id: &str) -> Result<DashSet<WebSocketRef>> {
+        self.local_adapter.get_user_sockets(user_id, app_id).await
     }
 
-    async fn cleanup_connection(&mut self, app_id: &str, ws: WebSocketRef) {
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
-            .cleanup_connection(app_id, ws)
-            .await
+    async fn cleanup_connection(&self, app_id: &str, ws: WebSocketRef) {
+        self.local_adapter.cleanup_connection(app_id, ws).await
     }
 
-    async fn terminate_connection(&mut self, app_id: &str, user_id: &str) -> Result<()> {
+    async fn terminate_connection(&self, app_id: &str, user_id: &str) -> Result<()> {
         let node_count = self.get_node_count().await?; // Get count first
-        let mut horizontal = self.horizontal.lock().await; // Lock for local + potential remote
 
         // First terminate locally
-        horizontal
-            .local_adapter
+        self.local_adapter
             .terminate_connection(app_id, user_id)
             .await?; // Propagate local errors
 
@@ -799,7 +1339,9 @@ impl Adapter for RedisAdapter {
         if node_count > 1 {
             // send_request handles its own locking/timing
             // We ignore the result here as it's a "fire and forget" termination broadcast
-            horizontal
+            self.horizontal
+                .lock()
+                .await
                 .send_request(
                     app_id,
                     RequestType::TerminateUserConnections,
@@ -814,21 +1356,18 @@ impl Adapter for RedisAdapter {
         Ok(())
     }
 
-    async fn add_channel_to_sockets(&mut self, app_id: &str, channel: &str, socket_id: &SocketId) {
+    async fn add_channel_to_sockets(&self, app_id: &str, channel: &str, socket_id: &SocketId) {
         // Seems purely local
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
+        self.local_adapter
             .add_channel_to_sockets(app_id, channel, socket_id)
             .await
     }
 
-    async fn get_channel_socket_count(&mut self, app_id: &str, channel: &str) -> usize {
+    async fn get_channel_socket_count(&self, app_id: &str, channel: &str) -> usize {
         let node_count = self.get_node_count().await.unwrap_or(1); // Get count first
-        let mut horizontal = self.horizontal.lock().await; // Lock for local + potential remote
 
         // Get local count
-        let local_count = horizontal
+        let local_count = self
             .local_adapter
             .get_channel_socket_count(app_id, channel)
             .await;
@@ -836,7 +1375,10 @@ impl Adapter for RedisAdapter {
         // Get distributed count if needed
         if node_count > 1 {
             // send_request handles its own locking/timing
-            match horizontal
+            match self
+                .horizontal
+                .lock()
+                .await
                 .send_request(
                     app_id,
                     RequestType::ChannelSocketsCount,
@@ -864,81 +1406,140 @@ impl Adapter for RedisAdapter {
         }
     }
 
+    async fn claim_channel_transition(&self, app_id: &str, channel: &str, occupied: bool) -> bool {
+        // Only this node's first subscriber arriving or last one leaving
+        // changes its membership of the channel's occupancy set
+        let local_count = self
+            .local_adapter
+            .get_channel_socket_count(app_id, channel)
+            .await;
+        if local_count != usize::from(occupied) {
+            return false;
+        }
+
+        let key = occupancy_key(&self.prefix, app_id, channel);
+        let mut conn = self.connection.read().await.clone();
+        match claim_transition(&mut conn, &key, &self.node_id, occupied).await {
+            Ok(claimed) if claimed || occupied => claimed,
+            // Nodes that died with subscribers never leave the set. Once no
+            // live node reports a subscriber, whoever deletes it announces.
+            Ok(_) => {
+                if self.get_channel_socket_count(app_id, channel).await > 0 {
+                    return false;
+                }
+                conn.del::<_, i64>(&key)
+                    .await
+                    .is_ok_and(|removed| removed > 0)
+            }
+            Err(e) => {
+                warn!(
+                    "{}",
+                    format!("Failed to update occupancy of channel {}: {}", channel, e)
+                );
+                let count = self.get_channel_socket_count(app_id, channel).await;
+                if occupied { count == 1 } else { count == 0 }
+            }
+        }
+    }
+
     async fn add_to_channel(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
         socket_id: &SocketId,
     ) -> Result<bool> {
-        // Seems purely local
-        let mut horizontal = self.horizontal.lock().await;
         warn!(
             "{}",
             format!("Adding socket {} to channel {}", socket_id, channel)
         );
-        horizontal
+        let added = self
             .local_adapter
             .add_to_channel(app_id, channel, socket_id)
-            .await
+            .await?;
+        self.claim_shard(app_id, channel).await;
+        if let Some(hot_channels) = &self.hot_channels {
+            if added && hot_channels.is_hot(channel) {
+                let mut conn = self.connection.read().await.clone();
+                if let Err(e) = hot_channels.join(&mut conn, app_id, channel).await {
+                    warn!(
+                        "{}",
+                        format!("Failed to join hot channel {}: {}", channel, e)
+                    );
+                }
+            }
+        }
+        Ok(added)
     }
 
     async fn remove_from_channel(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
         socket_id: &SocketId,
     ) -> Result<bool> {
-        // Seems purely local
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
+        let removed = self
             .local_adapter
             .remove_from_channel(app_id, channel, socket_id)
-            .await
+            .await?;
+        if let Some(hot_channels) = &self.hot_channels {
+            if removed
+                && hot_channels.is_hot(channel)
+                && self
+                    .local_adapter
+                    .get_channel_socket_count(app_id, channel)
+                    .await
+                    == 0
+            {
+                let mut conn = self.connection.read().await.clone();
+                if let Err(e) = hot_channels.leave(&mut conn, app_id, channel).await {
+                    warn!(
+                        "{}",
+                        format!("Failed to leave hot channel {}: {}", channel, e)
+                    );
+                }
+            }
+        }
+        Ok(removed)
     }
 
     async fn get_presence_member(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
         socket_id: &SocketId,
     ) -> Option<PresenceMemberInfo> {
         // Seems purely local
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
+        self.local_adapter
             .get_presence_member(app_id, channel, socket_id)
             .await
     }
 
     // Public method using the optimized internal call
-    async fn terminate_user_connections(&mut self, app_id: &str, user_id: &str) -> Result<()> {
+    async fn terminate_user_connections(&self, app_id: &str, user_id: &str) -> Result<()> {
         self.terminate_connection(app_id, user_id).await
     }
 
-    async fn add_user(&mut self, ws: Arc<Mutex<WebSocket>>) -> Result<()> {
+    async fn add_user(&self, ws: Arc<Mutex<WebSocket>>) -> Result<()> {
         // Seems purely local
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal.local_adapter.add_user(ws).await
+        self.local_adapter.add_user(ws).await
     }
 
-    async fn remove_user(&mut self, ws: Arc<Mutex<WebSocket>>) -> Result<()> {
+    async fn remove_user(&self, ws: Arc<Mutex<WebSocket>>) -> Result<()> {
         // Seems purely local
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal.local_adapter.remove_user(ws).await
+        self.local_adapter.remove_user(ws).await
     }
 
-    async fn get_channels_with_socket_count(
-        &mut self,
-        app_id: &str,
-    ) -> Result<DashMap<String, usize>> {
+    async fn get_channels_with_socket_count(&self, app_id: &str) -> Result<DashMap<String, usize>> {
         let node_count = self.get_node_count().await?; // Get count first
-        let mut horizontal = self.horizontal.lock().await; // Lock for local + potential remote
 
         // Then broadcast to other nodes if needed
         if node_count > 1 {
             // send_request handles its own locking/timing
             // We ignore the result here as it's a "fire and forget" termination broadcast
-            match horizontal
+            match self
+                .horizontal
+                .lock()
+                .await
                 .send_request(
                     app_id,
                     RequestType::ChannelsWithSocketsCount,
@@ -951,7 +1552,7 @@ impl Adapter for RedisAdapter {
             {
                 Ok(response_data) => {
                     // Merge the local and remote data
-                    let channels = horizontal
+                    let channels = self
                         .local_adapter
                         .get_channels_with_socket_count(app_id)
                         .await?;
@@ -969,23 +1570,25 @@ impl Adapter for RedisAdapter {
             }
         }
 
-        Ok(horizontal
+        Ok(self
             .local_adapter
             .get_channels_with_socket_count(app_id)
             .await?)
     }
 
-    async fn get_sockets_count(&mut self, app_id: &str) -> Result<usize> {
+    async fn get_sockets_count(&self, app_id: &str) -> Result<usize> {
         let node_count = self.get_node_count().await?; // Get count first
-        let mut horizontal = self.horizontal.lock().await; // Lock for local + potential remote
 
         // Get local count
-        let local_count = horizontal.local_adapter.get_sockets_count(app_id).await?;
+        let local_count = self.local_adapter.get_sockets_count(app_id).await?;
 
         // Get distributed count if needed
         if node_count > 1 {
             // send_request handles its own locking/timing
-            match horizontal
+            match self
+                .horizontal
+                .lock()
+                .await
                 .send_request(
                     app_id,
                     RequestType::SocketsCount,
@@ -1007,12 +1610,117 @@ impl Adapter for RedisAdapter {
         }
     }
 
-    async fn get_namespaces(&mut self) -> Result<DashMap<String, Arc<Namespace>>> {
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal.local_adapter.get_namespaces().await
+    async fn get_channel_socket_details(
+        &self,
+        app_id: &str,
+        channel: &str,
+        sample: usize,
+    ) -> Result<Vec<SocketDetails>> {
+        let node_count = self.get_node_count().await?;
+        self.horizontal
+            .lock()
+            .await
+            .get_channel_socket_details(app_id, channel, sample, node_count)
+            .await
+    }
+
+    async fn get_namespaces(&self) -> Result<DashMap<String, Arc<Namespace>>> {
+        self.local_adapter.get_namespaces().await
+    }
+
+    async fn health(&self) -> Result<()> {
+        let mut conn = self.connection.read().await.clone();
+        let result: redis::RedisResult<String> = redis::cmd("PING").query_async(&mut conn).await;
+        match result {
+            Ok(response) if response == "PONG" => {}
+            Ok(response) => {
+                return Err(Error::RedisError(format!(
+                    "Unexpected PING response: {}",
+                    response
+                )));
+            }
+            Err(e) => {
+                return Err(Error::RedisError(format!("Redis PING failed: {}", e)));
+            }
+        }
+
+        if !self.listener.is_running() {
+            return Err(Error::RedisError(
+                "Redis Pub/Sub listener is not running".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn reconnect(&self) -> Result<()> {
+        info!("{}", "Reconnecting Redis adapter");
+        *self.connection.write().await = self
+            .client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| Error::RedisError(format!("Failed to reconnect to Redis: {}", e)))?;
+
+        // The listener task exits when its stream ends, so start a fresh one
+        // unless one is already running or subscribing
+        self.start_listeners_pubsub().await?;
+        Ok(())
+    }
+
+    async fn register_node(&self, node: &NodeInfo) -> Result<()> {
+        let mut conn = self.connection.read().await.clone();
+        let entry = serde_json::to_string(node)?;
+        match conn
+            .hset::<_, _, _, ()>(&self.nodes_key, &node.process_id, entry)
+            .await
+        {
+            Ok(()) => Ok(()),
+            // The heartbeat retries on its own schedule; one alert is enough
+            Err(e) if self.degraded.record_error(&e) => Ok(()),
+            Err(e) => Err(Error::RedisError(format!("Failed to register node: {}", e))),
+        }
+    }
+
+    async fn registered_nodes(&self) -> Result<Vec<NodeInfo>> {
+        let mut conn = self.connection.read().await.clone();
+        let entries: HashMap<String, String> = conn
+            .hgetall(&self.nodes_key)
+            .await
+            .map_err(|e| Error::RedisError(format!("Failed to read node registry: {}", e)))?;
+
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut nodes = Vec::with_capacity(entries.len());
+        let mut expired = Vec::new();
+        for (process_id, entry) in entries {
+            match serde_json::from_str::<NodeInfo>(&entry) {
+                Ok(node) if !node.is_expired(now) => nodes.push(node),
+                _ => expired.push(process_id),
+            }
+        }
+
+        // Drop entries of nodes that went away without deregistering
+        if !expired.is_empty() {
+            let result: redis::RedisResult<()> = conn.hdel(&self.nodes_key, expired).await;
+            if let Err(e) = result {
+                warn!("{}", format!("Failed to prune node registry: {}", e));
+            }
+        }
+        Ok(nodes)
+    }
+
+    async fn set_metrics(
+        &self,
+        metrics: Arc<Mutex<dyn MetricsInterface + Send + Sync>>,
+    ) -> Result<()> {
+        self.horizontal.lock().await.metrics = Some(metrics.clone());
+        *self.metrics.write().await = Some(metrics);
+        Ok(())
+    }
+
+    fn set_app_manager(&self, app_manager: Arc<dyn AppManager + Send + Sync>) {
+        self.local_adapter.set_app_manager(app_manager);
     }
 
-    fn as_any_mut(&mut self) -> &mut dyn Any {
+    fn as_any(&self) -> &dyn Any {
         self
     }
 }
@@ -9,53 +9,79 @@ use futures::StreamExt;
 use hyper::upgrade::Upgraded;
 use hyper_util::rt::TokioIo;
 use redis::AsyncCommands;
-use redis::cluster::{ClusterClient, ClusterClientBuilder};
 use tokio::io::WriteHalf;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, RwLock};
 use tracing::{error, info, warn};
 
 use crate::adapter::adapter::Adapter;
 use crate::adapter::horizontal_adapter::{
-    BroadcastMessage, HorizontalAdapter, RequestBody, RequestType, ResponseBody,
+    BroadcastCodec, BroadcastMessage, HorizontalAdapter, RequestBody, RequestType, ResponseBody,
 };
+use crate::adapter::interest::channel_shard;
+use crate::adapter::listener::ListenerState;
+use crate::adapter::local_adapter::LocalAdapter;
+use crate::adapter::occupancy::{claim_transition, occupancy_key};
+use crate::adapter::registry::NodeInfo;
 use crate::app::manager::AppManager;
 use crate::channel::PresenceMemberInfo;
 use crate::error::{Error, Result};
+use crate::metrics::MetricsInterface;
 
 use crate::namespace::Namespace;
 pub(crate) use crate::options::RedisClusterAdapterConfig;
 use crate::protocol::messages::PusherMessage;
-use crate::websocket::{SocketId, WebSocket, WebSocketRef};
+use crate::redis_client::RedisDegradedMode;
+use crate::websocket::{SocketDetails, SocketId, WebSocket, WebSocketRef};
 
 /// Redis channels
 pub const DEFAULT_PREFIX: &str = "sockudo";
 const BROADCAST_SUFFIX: &str = "#broadcast";
 const REQUESTS_SUFFIX: &str = "#requests";
 const RESPONSES_SUFFIX: &str = "#responses";
+const NODES_SUFFIX: &str = "#nodes";
 
 /// Redis adapter configuration
 
 /// Redis adapter for horizontal scaling (Optimized Version)
 pub struct RedisClusterAdapter {
-    /// Base horizontal adapter (protected by a Mutex)
-    /// Optimization Note: While the Mutex remains, methods now try to minimize lock duration.
-    /// Further optimization would require refactoring HorizontalAdapter internals.
+    /// Base horizontal adapter (protected by a Mutex). Only cross-node requests
+    /// take this lock; local operations go through `local_adapter`.
     pub horizontal: Arc<Mutex<HorizontalAdapter>>,
 
+    /// Local connection registry, shared with the horizontal adapter
+    pub local_adapter: Arc<LocalAdapter>,
+
+    /// Unique ID of this node, copied from the horizontal adapter
+    pub node_id: String,
+
     /// Redis client
     pub client: redis::cluster::ClusterClient,
 
     /// Redis connection for publishing (Multiplexed for efficiency)
-    pub connection: redis::cluster_async::ClusterConnection,
+    pub connection: Arc<RwLock<redis::cluster_async::ClusterConnection>>,
 
     /// Channel names
     pub prefix: String,
     pub broadcast_channel: String,
     pub request_channel: String,
     pub response_channel: String,
+    /// Hash holding the node registry entries, keyed by process ID
+    pub nodes_key: String,
 
     /// Configuration
     pub config: RedisClusterAdapterConfig,
+
+    /// Whether the Pub/Sub listener task is stopped, subscribing or running
+    pub listener: Arc<ListenerState>,
+
+    /// Encodes (and optionally compresses) outgoing broadcasts
+    pub codec: BroadcastCodec,
+
+    /// Set while Redis refuses writes (OOM/READONLY); broadcasts stay local
+    pub degraded: Arc<RedisDegradedMode>,
+
+    /// Metrics driver, shared with the listener task for broadcast counts
+    pub metrics: Arc<RwLock<Option<Arc<Mutex<dyn MetricsInterface + Send + Sync>>>>>,
 }
 
 impl RedisClusterAdapter {
@@ -69,8 +95,14 @@ impl RedisClusterAdapter {
         horizontal.requests_timeout = config.request_timeout_ms;
 
         // Create Redis client
-        let client = ClusterClient::new(config.nodes.clone())
-            .map_err(|e| Error::RedisError(format!("Failed to create Redis client: {}", e)))?;
+        let client = crate::redis_client::cluster_client_builder(
+            &config.nodes,
+            config.username.as_deref(),
+            config.password.as_deref(),
+            &config.tls,
+        )?
+        .build()
+        .map_err(|e| Error::RedisError(format!("Failed to create Redis client: {}", e)))?;
 
         // Get connection based on configuration
         let connection = if config.use_connection_manager {
@@ -88,22 +120,82 @@ impl RedisClusterAdapter {
         let broadcast_channel = format!("{}:{}", config.prefix, BROADCAST_SUFFIX);
         let request_channel = format!("{}:{}", config.prefix, REQUESTS_SUFFIX);
         let response_channel = format!("{}:{}", config.prefix, RESPONSES_SUFFIX);
+        let nodes_key = format!("{}:{}", config.prefix, NODES_SUFFIX);
+
+        let local_adapter = horizontal.local_adapter.clone();
+
+        let node_id = horizontal.node_id.clone();
 
         // Create the adapter
         let adapter = Self {
             horizontal: Arc::new(Mutex::new(horizontal)),
+            local_adapter,
+            node_id,
             client,
-            connection,
+            connection: Arc::new(RwLock::new(connection)),
             prefix: config.prefix.clone(),
             broadcast_channel,
             request_channel,
             response_channel,
+            nodes_key,
             config,
+            listener: Arc::new(ListenerState::default()),
+            codec: BroadcastCodec::default(),
+            degraded: Arc::new(RedisDegradedMode::new(
+                "the Redis cluster adapter",
+                "local-only broadcasts",
+            )),
+            metrics: Arc::new(RwLock::new(None)),
         };
 
         Ok(adapter)
     }
 
+    // Method to initialize metrics during adapter startup
+    pub async fn init_with_metrics(
+        &self,
+        metrics: Option<Arc<Mutex<dyn MetricsInterface + Send + Sync>>>,
+    ) -> Result<()> {
+        // First initialize the adapter
+        self.init().await;
+
+        // If metrics are provided, set them in the horizontal adapter
+        if let Some(metrics_instance) = metrics {
+            self.set_metrics(metrics_instance).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Topic carrying broadcasts for one channel shard
+    fn shard_channel(broadcast_channel: &str, shard: u32) -> String {
+        format!("{}:{}", broadcast_channel, shard)
+    }
+
+    /// Topics broadcasts are published to: one per shard when sharded
+    fn broadcast_topics(&self) -> Vec<String> {
+        if self.config.broadcast_shards > 1 {
+            (0..self.config.broadcast_shards)
+                .map(|shard| Self::shard_channel(&self.broadcast_channel, shard))
+                .collect()
+        } else {
+            vec![self.broadcast_channel.clone()]
+        }
+    }
+
+    /// Record how long a broadcast publish took, when metrics are enabled
+    async fn track_publish_latency(&self, app_id: &str, started: std::time::Instant) {
+        if let Some(metrics) = self.metrics.read().await.as_ref() {
+            metrics
+                .lock()
+                .await
+                .track_horizontal_adapter_publish_latency(
+                    app_id,
+                    started.elapsed().as_secs_f64() * 1000.0,
+                );
+        }
+    }
+
     /// Create a new Redis adapter with simple configuration
     pub async fn with_nodes(nodes: Vec<String>) -> Result<Self> {
         let config = RedisClusterAdapterConfig {
@@ -131,49 +223,87 @@ impl RedisClusterAdapter {
     /// Start traditional PubSub listeners (Optimized with task spawning)
     /// Start traditional PubSub listeners (Optimized with task spawning)
     async fn start_listeners_pubsub(&self) -> Result<()> {
+        // Another start may still be subscribing, or the listener is up
+        if !self.listener.try_start() {
+            return Ok(());
+        }
+
         // Clone needed values for the async task
         let horizontal_arc = self.horizontal.clone();
-        let pub_connection = self.connection.clone();
+        let local_adapter = self.local_adapter.clone();
+        let pub_connection = self.connection.read().await.clone();
         let broadcast_channel = self.broadcast_channel.clone();
+        let shard_prefix = format!("{}:", self.broadcast_channel);
         let request_channel = self.request_channel.clone();
         let response_channel = self.response_channel.clone();
         let nodes = self.config.nodes.clone();
+        let sharded_pubsub = self.config.sharded_pubsub;
+        let broadcast_topics = self.broadcast_topics();
+        let listener = self.listener.clone();
+        let metrics = self.metrics.clone();
 
-        // Get node_id without holding the lock for the whole setup
-        let node_id = {
-            let horizontal_lock = horizontal_arc.lock().await;
-            horizontal_lock.node_id.clone()
-        }; // Lock released
+        let node_id = self.node_id.clone();
 
         // Create a separate channel for receiving PubSub messages
         let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
 
         // Create a new client with RESP3 protocol for PubSub
-        let sub_client = ClusterClientBuilder::new(nodes)
-            .use_protocol(redis::ProtocolVersion::RESP3)
-            .push_sender(tx)
-            .build()
-            .unwrap();
+        let sub_client = crate::redis_client::cluster_client_builder(
+            &nodes,
+            self.config.username.as_deref(),
+            self.config.password.as_deref(),
+            &self.config.tls,
+        )
+        .and_then(|builder| {
+            builder
+                .use_protocol(redis::ProtocolVersion::RESP3)
+                .push_sender(tx)
+                .build()
+                .map_err(|e| {
+                    Error::RedisError(format!("Failed to create Redis pubsub client: {}", e))
+                })
+        });
+        let sub_client = match sub_client {
+            Ok(sub_client) => sub_client,
+            Err(e) => {
+                self.listener.stopped();
+                return Err(e);
+            }
+        };
 
         // Spawn the main listener task
-        tokio::spawn(async move {
+        let task = tokio::spawn(async move {
             // Create a connection for PubSub
             let mut pubsub = match sub_client.get_async_connection().await {
                 Ok(conn) => conn,
                 Err(e) => {
                     error!("{}", format!("Failed to get pubsub connection: {}", e));
+                    listener.stopped();
                     return;
                 }
             };
 
-            // Subscribe to all channels
-            if let Err(e) = pubsub
-                .subscribe(&[&broadcast_channel, &request_channel, &response_channel])
-                .await
-            {
+            // Subscribe to the request/response channels and the broadcast
+            // topics; sharded topics are only served by their slot's node
+            let subscribed = if sharded_pubsub {
+                match pubsub
+                    .subscribe(&[&request_channel, &response_channel])
+                    .await
+                {
+                    Ok(()) => pubsub.ssubscribe(&broadcast_topics).await,
+                    Err(e) => Err(e),
+                }
+            } else {
+                let mut channels = vec![request_channel.clone(), response_channel.clone()];
+                channels.extend(broadcast_topics.iter().cloned());
+                pubsub.subscribe(&channels).await
+            };
+            if let Err(e) = subscribed {
                 error!("{}", format!("Failed to subscribe to channels: {}", e));
+                listener.stopped();
                 return;
             }
+            listener.running();
 
             info!(
                 "{}",
@@ -186,7 +316,10 @@ impl RedisClusterAdapter {
             // Process messages from the channel - PushInfo is the message type for RESP3
             while let Some(push_info) = rx.recv().await {
                 // Extract channel and payload from PushInfo
-                if push_info.kind != redis::PushKind::Message {
+                if !matches!(
+                    push_info.kind,
+                    redis::PushKind::Message | redis::PushKind::SMessage
+                ) {
                     continue; // Skip non-message push notifications
                 }
 
@@ -221,19 +354,11 @@ impl RedisClusterAdapter {
                     }
                 };
 
-                let payload = match &push_info.data[1] {
-                    redis::Value::BulkString(bytes) => match String::from_utf8(bytes.clone()) {
-                        Ok(s) => s,
-                        Err(_) => {
-                            error!(
-                                "{}",
-                                "Failed to parse payload from bulk string bytes".to_string(),
-                            );
-                            continue;
-                        }
-                    },
-                    redis::Value::SimpleString(s) => s.clone(),
-                    redis::Value::VerbatimString { format: _, text } => text.clone(),
+                // Kept as raw bytes: broadcasts may be compressed
+                let payload: Vec<u8> = match &push_info.data[1] {
+                    redis::Value::BulkString(bytes) => bytes.clone(),
+                    redis::Value::SimpleString(s) => s.clone().into_bytes(),
+                    redis::Value::VerbatimString { format: _, text } => text.clone().into_bytes(),
                     _ => {
                         error!(
                             "{}",
@@ -245,22 +370,33 @@ impl RedisClusterAdapter {
 
                 // Process the message in a separate task
                 let horizontal_clone = horizontal_arc.clone();
+                let local_adapter_clone = local_adapter.clone();
+                let metrics_clone = metrics.clone();
                 let node_id_clone = node_id.clone();
                 let pub_connection_clone = pub_connection.clone();
                 let broadcast_channel_clone = broadcast_channel.clone();
+                let is_shard_channel = channel.starts_with(&shard_prefix);
                 let request_channel_clone = request_channel.clone();
                 let response_channel_clone = response_channel.clone();
 
                 tokio::spawn(async move {
                     // Process based on channel name
-                    if channel == broadcast_channel_clone {
+                    if channel == broadcast_channel_clone || is_shard_channel {
                         // Handle broadcast message
-                        match serde_json::from_str::<BroadcastMessage>(&payload) {
+                        match BroadcastCodec::decode(&payload) {
                             Ok(broadcast) => {
                                 // Skip our own messages
                                 if broadcast.node_id == node_id_clone {
                                     return;
                                 }
+                                if let Some(metrics) = metrics_clone.read().await.as_ref() {
+                                    metrics
+                                        .lock()
+                                        .await
+                                        .mark_horizontal_adapter_broadcast_received(
+                                            &broadcast.app_id,
+                                        );
+                                }
                                 // Process the broadcast
                                 match serde_json::from_str(&broadcast.message) {
                                     Ok(message) => {
@@ -268,18 +404,16 @@ impl RedisClusterAdapter {
                                             .except_socket_id
                                             .as_ref()
                                             .map(|id| SocketId(id.clone()));
-                                        // Lock only when interacting with local adapter
-                                        let mut horizontal_lock = horizontal_clone.lock().await;
-                                        horizontal_lock
-                                            .local_adapter
-                                            .send(
+                                        // Deliver to local sockets without taking the horizontal lock
+                                        local_adapter_clone
+                                            .deliver(
                                                 &broadcast.channel,
                                                 message,
                                                 except_id.as_ref(),
                                                 &broadcast.app_id,
+                                                broadcast.published_at,
                                             )
                                             .await;
-                                        // Lock released automatically when horizontal_lock goes out of scope
                                     }
                                     Err(e) => {
                                         warn!(
@@ -297,14 +431,15 @@ impl RedisClusterAdapter {
                                     "{}",
                                     format!(
                                         "Failed to deserialize broadcast message: {}, Payload: {}",
-                                        e, payload
+                                        e,
+                                        String::from_utf8_lossy(&payload)
                                     )
                                 );
                             }
                         }
                     } else if channel == request_channel_clone {
                         // Handle request message
-                        match serde_json::from_str::<RequestBody>(&payload) {
+                        match serde_json::from_slice::<RequestBody>(&payload) {
                             Ok(request) => {
                                 // Skip our own requests
                                 if request.node_id == node_id_clone {
@@ -314,7 +449,7 @@ impl RedisClusterAdapter {
                                 // Lock only when processing
                                 let response = {
                                     // Scope for the lock
-                                    let mut horizontal_lock = horizontal_clone.lock().await;
+                                    let horizontal_lock = horizontal_clone.lock().await;
                                     horizontal_lock.process_request(request).await
                                 }; // Lock released
                                 if let Ok(response) = response {
@@ -349,14 +484,15 @@ impl RedisClusterAdapter {
                                     "{}",
                                     format!(
                                         "Failed to deserialize request message: {}, Payload: {}",
-                                        e, payload
+                                        e,
+                                        String::from_utf8_lossy(&payload)
                                     )
                                 );
                             }
                         }
                     } else if channel == response_channel_clone {
                         // Handle response message
-                        match serde_json::from_str::<ResponseBody>(&payload) {
+                        match serde_json::from_slice::<ResponseBody>(&payload) {
                             Ok(response) => {
                                 // Skip our own responses
                                 if response.node_id == node_id_clone {
@@ -373,7 +509,8 @@ impl RedisClusterAdapter {
                                     "{}",
                                     format!(
                                         "Failed to deserialize response message: {}, Payload: {}",
-                                        e, payload
+                                        e,
+                                        String::from_utf8_lossy(&payload)
                                     )
                                 );
                             }
@@ -381,8 +518,10 @@ impl RedisClusterAdapter {
                     }
                 }); // End of spawned task for message processing
             }
-            info!("{}", "Redis Pub/Sub listener stream ended.");
+            listener.stopped();
+            warn!("{}", "Redis Pub/Sub listener stream ended.");
         });
+        self.listener.track(&task);
 
         Ok(())
     }
@@ -390,7 +529,7 @@ impl RedisClusterAdapter {
     /// Get the number of nodes in the cluster (Optimized parsing)
     pub async fn get_node_count(&self) -> Result<usize> {
         // Use a cloned connection for the command
-        let mut conn = self.connection.clone();
+        let mut conn = self.connection.read().await.clone();
 
         // Use the PUBSUB NUMSUB command directly
         let result: redis::RedisResult<Vec<redis::Value>> = redis::cmd("PUBSUB")
@@ -444,12 +583,8 @@ impl RedisClusterAdapter {
 
 #[async_trait]
 impl Adapter for RedisClusterAdapter {
-    async fn init(&mut self) {
-        // Lock scope minimized
-        {
-            let mut horizontal = self.horizontal.lock().await;
-            horizontal.local_adapter.init().await;
-        } // Lock released
+    async fn init(&self) {
+        self.local_adapter.init().await;
 
         // Start Redis listeners (already optimized)
         if let Err(e) = self.start_listeners().await {
@@ -457,121 +592,143 @@ impl Adapter for RedisClusterAdapter {
             // Consider returning the error or handling it more gracefully
         }
     }
-    async fn get_namespace(&mut self, app_id: &str) -> Option<Arc<Namespace>> {
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal.local_adapter.get_namespace(app_id).await
+    async fn get_namespace(&self, app_id: &str) -> Option<Arc<Namespace>> {
+        self.local_adapter.get_namespace(app_id).await
     }
 
     async fn add_socket(
-        &mut self,
+        &self,
         socket_id: SocketId,
         socket: WebSocketWrite<WriteHalf<TokioIo<Upgraded>>>,
         app_id: &str,
         app_manager: &Arc<dyn AppManager + Send + Sync>,
     ) -> Result<()> {
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
+        self.local_adapter
             .add_socket(socket_id, socket, app_id, app_manager)
             .await
     }
 
     async fn get_connection(
-        &mut self,
+        &self,
         socket_id: &SocketId,
         app_id: &str,
     ) -> Option<Arc<Mutex<WebSocket>>> {
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
-            .get_connection(socket_id, app_id)
-            .await
+        self.local_adapter.get_connection(socket_id, app_id).await
     }
 
-    async fn remove_connection(&mut self, socket_id: &SocketId, app_id: &str) -> Result<()> {
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
+    async fn remove_connection(&self, socket_id: &SocketId, app_id: &str) -> Result<()> {
+        self.local_adapter
             .remove_connection(socket_id, app_id)
             .await
     }
 
     async fn send_message(
-        &mut self,
+        &self,
         app_id: &str,
         socket_id: &SocketId,
         message: PusherMessage,
     ) -> Result<()> {
         // This likely sends directly to a specific socket, lock scope depends on local_adapter.
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
+        self.local_adapter
             .send_message(app_id, socket_id, message)
             .await
     }
 
     /// Send to a channel (Optimized Lock Scope)
     async fn send(
-        &mut self,
+        &self,
         channel: &str,
         message: PusherMessage,
         except: Option<&SocketId>,
         app_id: &str,
     ) -> Result<()> {
-        // --- Optimization: Minimize lock duration ---
-        let (node_id, broadcast_data) = {
-            // 1. Lock and perform local send
-            let mut horizontal_lock = self.horizontal.lock().await;
-            let local_send_result = horizontal_lock
-                .local_adapter
-                .send(channel, message.clone(), except, app_id)
-                .await;
-
-            // Log local send errors if necessary, but continue to broadcast
-            if let Err(e) = local_send_result {
-                warn!(
-                    "{}",
-                    format!(
-                        "Local send failed during broadcast for channel {}: {}",
-                        channel, e
-                    )
-                );
-            }
+        let published_at = chrono::Utc::now().timestamp_millis();
+        // 1. Deliver to local sockets; the local registry is sharded, so this
+        // never waits on broadcasts to other apps or channels
+        let local_send_result = self
+            .local_adapter
+            .send(channel, message.clone(), except, app_id)
+            .await;
+
+        // Log local send errors if necessary, but continue to broadcast
+        if let Err(e) = local_send_result {
+            warn!(
+                "{}",
+                format!(
+                    "Local send failed during broadcast for channel {}: {}",
+                    channel, e
+                )
+            );
+        }
+
+        // While Redis refuses writes, other nodes are unreachable anyway;
+        // keep delivering locally instead of failing every broadcast
+        if self.degraded.is_active() {
+            return Ok(());
+        }
 
-            // 2. Prepare data needed for broadcast *outside* the lock
+        // 2. Prepare data needed for broadcast
+        let (node_id, broadcast_data) = (
+            self.node_id.clone(),
             (
-                horizontal_lock.node_id.clone(),
-                (
-                    app_id.to_string(),
-                    channel.to_string(),
-                    except.map(|id| id.0.clone()),
-                ),
-            )
-            // 3. Lock released here
-        };
+                app_id.to_string(),
+                channel.to_string(),
+                except.map(|id| id.0.clone()),
+            ),
+        );
 
-        // 4. Serialize the original message (outside the lock)
+        // 3. Serialize the original message
         let message_json = serde_json::to_string(&message)?;
 
-        // 5. Create broadcast message (outside the lock)
+        // 4. Create broadcast message
         let broadcast = BroadcastMessage {
             node_id, // Cloned node_id
             app_id: broadcast_data.0,
             channel: broadcast_data.1,
             message: message_json, // Serialized message
             except_socket_id: broadcast_data.2,
+            published_at: Some(published_at),
         };
 
         info!("{}", format!("Broadcasting message: {:?}", broadcast));
 
-        // 6. Serialize broadcast message (outside the lock)
-        let broadcast_json = serde_json::to_string(&broadcast)?;
+        // 5. Serialize (and compress, if configured) the broadcast message
+        let broadcast_payload = self.codec.encode(&broadcast)?;
 
-        // 7. Publish to Redis (outside the lock)
-        let mut conn = self.connection.clone();
-        conn.publish::<_, _, ()>(&self.broadcast_channel, broadcast_json)
+        // 6. Publish to Redis, on the channel's shard topic when sharded
+        let topic = if self.config.broadcast_shards > 1 {
+            Self::shard_channel(
+                &self.broadcast_channel,
+                channel_shard(app_id, channel, self.config.broadcast_shards),
+            )
+        } else {
+            self.broadcast_channel.clone()
+        };
+        let command = if self.config.sharded_pubsub {
+            "SPUBLISH"
+        } else {
+            "PUBLISH"
+        };
+        let mut conn = self.connection.read().await.clone();
+        let publish_start = std::time::Instant::now();
+        match redis::cmd(command)
+            .arg(&topic)
+            .arg(broadcast_payload)
+            .query_async::<()>(&mut conn)
             .await
-            .map_err(|e| Error::RedisError(format!("Failed to publish broadcast: {}", e)))?;
+        {
+            Ok(()) => {
+                self.degraded.record_success();
+                self.track_publish_latency(app_id, publish_start).await;
+            }
+            Err(e) if self.degraded.record_error(&e) => {}
+            Err(e) => {
+                return Err(Error::RedisError(format!(
+                    "Failed to publish broadcast: {}",
+                    e
+                )));
+            }
+        }
 
         Ok(())
     }
@@ -579,15 +736,14 @@ impl Adapter for RedisClusterAdapter {
     // Methods involving requests to other nodes: Lock scope depends on send_request.
     // `send_request` itself handles locking internally.
     async fn get_channel_members(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
     ) -> Result<HashMap<String, PresenceMemberInfo>> {
         let node_count = self.get_node_count().await?; // Fetch node count first
-        let mut horizontal = self.horizontal.lock().await; // Lock for local + remote request
 
         // Get local members first
-        let mut members = horizontal
+        let mut members = self
             .local_adapter
             .get_channel_members(app_id, channel)
             .await?;
@@ -595,7 +751,10 @@ impl Adapter for RedisClusterAdapter {
         // Get distributed members if needed
         if node_count > 1 {
             // send_request handles its own locking/timing
-            let response_data = horizontal
+            let response_data = self
+                .horizontal
+                .lock()
+                .await
                 .send_request(
                     app_id,
                     RequestType::ChannelMembers,
@@ -613,34 +772,27 @@ impl Adapter for RedisClusterAdapter {
 
     // Returns only local sockets - inherent limitation
     async fn get_channel_sockets(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
     ) -> Result<DashMap<SocketId, Arc<Mutex<WebSocket>>>> {
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
+        self.local_adapter
             .get_channel_sockets(app_id, channel)
             .await
     }
 
-    async fn get_channel(&mut self, app_id: &str, channel: &str) -> Result<DashSet<SocketId>> {
+    async fn get_channel(&self, app_id: &str, channel: &str) -> Result<DashSet<SocketId>> {
         let node_count = self.get_node_count().await?;
 
         // Get local channel data with minimal lock duration
-        let result = {
-            let mut horizontal = self.horizontal.lock().await;
-            horizontal
-                .local_adapter
-                .get_channel(app_id, channel)
-                .await?
-        };
+        let result = { self.local_adapter.get_channel(app_id, channel).await? };
 
         // Get distributed channels with a separate lock acquisition
         if node_count > 1 {
             let response_data = {
-                let mut horizontal = self.horizontal.lock().await;
-                horizontal
+                self.horizontal
+                    .lock()
+                    .await
                     .send_request(
                         app_id,
                         RequestType::ChannelSockets,
@@ -661,23 +813,18 @@ impl Adapter for RedisClusterAdapter {
         Ok(result)
     }
 
-    async fn remove_channel(&mut self, app_id: &str, channel: &str) {
+    async fn remove_channel(&self, app_id: &str, channel: &str) {
         // This seems purely local, lock scope depends on local_adapter
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
-            .remove_channel(app_id, channel)
-            .await
+        self.local_adapter.remove_channel(app_id, channel).await
     }
 
     async fn is_in_channel(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
         socket_id: &SocketId,
     ) -> Result<bool> {
         let node_count = self.get_node_count().await?; // Get count first
-        let mut horizontal = self.horizontal.lock().await; // Lock for local + potential remote
 
         warn!(
             "{}",
@@ -686,7 +833,7 @@ impl Adapter for RedisClusterAdapter {
                 socket_id, channel
             )
         );
-        let local_result = horizontal
+        let local_result = self
             .local_adapter
             .is_in_channel(app_id, channel, socket_id)
             .await?;
@@ -709,7 +856,10 @@ impl Adapter for RedisClusterAdapter {
                 )
             );
             // send_request handles its own locking/timing
-            let response_data = horizontal
+            let response_data = self
+                .horizontal
+                .lock()
+                .await
                 .send_request(
                     app_id,
                     RequestType::SocketExistsInChannel,
@@ -740,41 +890,26 @@ impl Adapter for RedisClusterAdapter {
     }
 
     // Returns only local sockets - inherent limitation
-    async fn get_user_sockets(
-        &mut self,
-        user_id: &str,
-        app_id: &str,
-    ) -> Result<DashSet<WebSocketRef>> {
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
-            .get_user_sockets(user_id, app_id)
-            .await
+    async fn get_user_sockets(&self, user_id: &str, app_id: &str) -> Result<DashSet<WebSocketRef>> {
+        self.local_adapter.get_user_sockets(user_id, app_id).await
     }
 
-    async fn cleanup_connection(&mut self, app_id: &str, ws: WebSocketRef) {
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
-            .cleanup_connection(app_id, ws)
-            .await
+    async fn cleanup_connection(&self, app_id: &str, ws: WebSocketRef) {
+        self.local_adapter.cleanup_connection(app_id, ws).await
     }
 
-    async fn add_channel_to_sockets(&mut self, app_id: &str, channel: &str, socket_id: &SocketId) {
+    async fn add_channel_to_sockets(&self, app_id: &str, channel: &str, socket_id: &SocketId) {
         // Seems purely local
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
+        self.local_adapter
             .add_channel_to_sockets(app_id, channel, socket_id)
             .await
     }
 
-    async fn get_channel_socket_count(&mut self, app_id: &str, channel: &str) -> usize {
+    async fn get_channel_socket_count(&self, app_id: &str, channel: &str) -> usize {
         let node_count = self.get_node_count().await.unwrap_or(1); // Get count first
-        let mut horizontal = self.horizontal.lock().await; // Lock for local + potential remote
 
         // Get local count
-        let local_count = horizontal
+        let local_count = self
             .local_adapter
             .get_channel_socket_count(app_id, channel)
             .await;
@@ -782,7 +917,10 @@ impl Adapter for RedisClusterAdapter {
         // Get distributed count if needed
         if node_count > 1 {
             // send_request handles its own locking/timing
-            match horizontal
+            match self
+                .horizontal
+                .lock()
+                .await
                 .send_request(
                     app_id,
                     RequestType::ChannelSocketsCount,
@@ -810,76 +948,102 @@ impl Adapter for RedisClusterAdapter {
         }
     }
 
+    async fn claim_channel_transition(&self, app_id: &str, channel: &str, occupied: bool) -> bool {
+        // Only this node's first subscriber arriving or last one leaving
+        // changes its membership of the channel's occupancy set
+        let local_count = self
+            .local_adapter
+            .get_channel_socket_count(app_id, channel)
+            .await;
+        if local_count != usize::from(occupied) {
+            return false;
+        }
+
+        let key = occupancy_key(&self.prefix, app_id, channel);
+        let mut conn = self.connection.read().await.clone();
+        match claim_transition(&mut conn, &key, &self.node_id, occupied).await {
+            Ok(claimed) if claimed || occupied => claimed,
+            // Nodes that died with subscribers never leave the set. Once no
+            // live node reports a subscriber, whoever deletes it announces.
+            Ok(_) => {
+                if self.get_channel_socket_count(app_id, channel).await > 0 {
+                    return false;
+                }
+                conn.del::<_, i64>(&key)
+                    .await
+                    .is_ok_and(|removed| removed > 0)
+            }
+            Err(e) => {
+                warn!(
+                    "{}",
+                    format!("Failed to update occupancy of channel {}: {}", channel, e)
+                );
+                let count = self.get_channel_socket_count(app_id, channel).await;
+                if occupied { count == 1 } else { count == 0 }
+            }
+        }
+    }
+
     async fn add_to_channel(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
         socket_id: &SocketId,
     ) -> Result<bool> {
         // Seems purely local
-        let mut horizontal = self.horizontal.lock().await;
         warn!(
             "{}",
             format!("Adding socket {} to channel {}", socket_id, channel)
         );
-        horizontal
-            .local_adapter
+        self.local_adapter
             .add_to_channel(app_id, channel, socket_id)
             .await
     }
 
     async fn remove_from_channel(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
         socket_id: &SocketId,
     ) -> Result<bool> {
         // Seems purely local
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
+        self.local_adapter
             .remove_from_channel(app_id, channel, socket_id)
             .await
     }
 
     async fn get_presence_member(
-        &mut self,
+        &self,
         app_id: &str,
         channel: &str,
         socket_id: &SocketId,
     ) -> Option<PresenceMemberInfo> {
         // Seems purely local
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal
-            .local_adapter
+        self.local_adapter
             .get_presence_member(app_id, channel, socket_id)
             .await
     }
 
     // Public method using the optimized internal call
-    async fn terminate_user_connections(&mut self, app_id: &str, user_id: &str) -> Result<()> {
+    async fn terminate_user_connections(&self, app_id: &str, user_id: &str) -> Result<()> {
         self.terminate_connection(app_id, user_id).await
     }
 
-    async fn add_user(&mut self, ws: Arc<Mutex<WebSocket>>) -> Result<()> {
+    async fn add_user(&self, ws: Arc<Mutex<WebSocket>>) -> Result<()> {
         // Seems purely local
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal.local_adapter.add_user(ws).await
+        self.local_adapter.add_user(ws).await
     }
 
-    async fn remove_user(&mut self, ws: Arc<Mutex<WebSocket>>) -> Result<()> {
+    async fn remove_user(&self, ws: Arc<Mutex<WebSocket>>) -> Result<()> {
         // Seems purely local
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal.local_adapter.remove_user(ws).await
+        self.local_adapter.remove_user(ws).await
     }
 
-    async fn terminate_connection(&mut self, app_id: &str, user_id: &str) -> Result<()> {
+    async fn terminate_connection(&self, app_id: &str, user_id: &str) -> Result<()> {
         let node_count = self.get_node_count().await?; // Get count first
-        let mut horizontal = self.horizontal.lock().await; // Lock for local + potential remote
 
         // First terminate locally
-        horizontal
-            .local_adapter
+        self.local_adapter
             .terminate_connection(app_id, user_id)
             .await?; // Propagate local errors
 
@@ -887,7 +1051,9 @@ impl Adapter for RedisClusterAdapter {
         if node_count > 1 {
             // send_request handles its own locking/timing
             // We ignore the result here as it's a "fire and forget" termination broadcast
-            horizontal
+            self.horizontal
+                .lock()
+                .await
                 .send_request(
                     app_id,
                     RequestType::TerminateUserConnections,
@@ -902,15 +1068,11 @@ impl Adapter for RedisClusterAdapter {
         Ok(())
     }
 
-    async fn get_channels_with_socket_count(
-        &mut self,
-        app_id: &str,
-    ) -> Result<DashMap<String, usize>> {
+    async fn get_channels_with_socket_count(&self, app_id: &str) -> Result<DashMap<String, usize>> {
         let node_count = self.get_node_count().await?; // Get count first
-        let mut horizontal = self.horizontal.lock().await; // Lock for local + potential remote
 
         // Get local channels
-        let local_channels = horizontal
+        let local_channels = self
             .local_adapter
             .get_channels_with_socket_count(app_id)
             .await?;
@@ -918,7 +1080,10 @@ impl Adapter for RedisClusterAdapter {
         // Get distributed channels if needed
         if node_count > 1 {
             // send_request handles its own locking/timing
-            let response_data = horizontal
+            let response_data = self
+                .horizontal
+                .lock()
+                .await
                 .send_request(
                     app_id,
                     RequestType::ChannelsWithSocketsCount,
@@ -936,21 +1101,107 @@ impl Adapter for RedisClusterAdapter {
         Ok(local_channels)
     }
 
-    fn as_any_mut(&mut self) -> &mut dyn Any {
+    async fn health(&self) -> Result<()> {
+        let mut conn = self.connection.read().await.clone();
+        let result: redis::RedisResult<String> = redis::cmd("PING").query_async(&mut conn).await;
+        if let Err(e) = result {
+            return Err(Error::RedisError(format!(
+                "Redis Cluster PING failed: {}",
+                e
+            )));
+        }
+
+        if !self.listener.is_running() {
+            return Err(Error::RedisError(
+                "Redis Cluster Pub/Sub listener is not running".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn reconnect(&self) -> Result<()> {
+        info!("{}", "Reconnecting Redis Cluster adapter");
+        *self.connection.write().await = self.client.get_async_connection().await.map_err(|e| {
+            Error::RedisError(format!("Failed to reconnect to Redis Cluster: {}", e))
+        })?;
+
+        // The listener task exits when its stream ends, so start a fresh one
+        // unless one is already running or subscribing
+        self.start_listeners_pubsub().await?;
+        Ok(())
+    }
+
+    async fn register_node(&self, node: &NodeInfo) -> Result<()> {
+        let mut conn = self.connection.read().await.clone();
+        let entry = serde_json::to_string(node)?;
+        match conn
+            .hset::<_, _, _, ()>(&self.nodes_key, &node.process_id, entry)
+            .await
+        {
+            Ok(()) => Ok(()),
+            // The heartbeat retries on its own schedule; one alert is enough
+            Err(e) if self.degraded.record_error(&e) => Ok(()),
+            Err(e) => Err(Error::RedisError(format!("Failed to register node: {}", e))),
+        }
+    }
+
+    async fn registered_nodes(&self) -> Result<Vec<NodeInfo>> {
+        let mut conn = self.connection.read().await.clone();
+        let entries: HashMap<String, String> = conn
+            .hgetall(&self.nodes_key)
+            .await
+            .map_err(|e| Error::RedisError(format!("Failed to read node registry: {}", e)))?;
+
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut nodes = Vec::with_capacity(entries.len());
+        let mut expired = Vec::new();
+        for (process_id, entry) in entries {
+            match serde_json::from_str::<NodeInfo>(&entry) {
+                Ok(node) if !node.is_expired(now) => nodes.push(node),
+                _ => expired.push(process_id),
+            }
+        }
+
+        // Drop entries of nodes that went away without deregistering
+        if !expired.is_empty() {
+            let result: redis::RedisResult<()> = conn.hdel(&self.nodes_key, expired).await;
+            if let Err(e) = result {
+                warn!("{}", format!("Failed to prune node registry: {}", e));
+            }
+        }
+        Ok(nodes)
+    }
+
+    async fn set_metrics(
+        &self,
+        metrics: Arc<Mutex<dyn MetricsInterface + Send + Sync>>,
+    ) -> Result<()> {
+        self.horizontal.lock().await.metrics = Some(metrics.clone());
+        *self.metrics.write().await = Some(metrics);
+        Ok(())
+    }
+
+    fn set_app_manager(&self, app_manager: Arc<dyn AppManager + Send + Sync>) {
+        self.local_adapter.set_app_manager(app_manager);
+    }
+
+    fn as_any(&self) -> &dyn Any {
         self
     }
 
-    async fn get_sockets_count(&mut self, app_id: &str) -> Result<usize> {
+    async fn get_sockets_count(&self, app_id: &str) -> Result<usize> {
         let node_count = self.get_node_count().await?; // Get count first
-        let mut horizontal = self.horizontal.lock().await; // Lock for local + potential remote
 
         // Get local count
-        let local_count = horizontal.local_adapter.get_sockets_count(app_id).await?;
+        let local_count = self.local_adapter.get_sockets_count(app_id).await?;
 
         // Get distributed count if needed
         if node_count > 1 {
             // send_request handles its own locking/timing
-            let response_data = horizontal
+            let response_data = self
+                .horizontal
+                .lock()
+                .await
                 .send_request(
                     app_id,
                     RequestType::SocketsCount,
@@ -966,8 +1217,21 @@ impl Adapter for RedisClusterAdapter {
         Ok(local_count)
     }
 
-    async fn get_namespaces(&mut self) -> Result<DashMap<String, Arc<Namespace>>> {
-        let mut horizontal = self.horizontal.lock().await;
-        horizontal.local_adapter.get_namespaces().await
+    async fn get_channel_socket_details(
+        &self,
+        app_id: &str,
+        channel: &str,
+        sample: usize,
+    ) -> Result<Vec<SocketDetails>> {
+        let node_count = self.get_node_count().await?;
+        self.horizontal
+            .lock()
+            .await
+            .get_channel_socket_details(app_id, channel, sample, node_count)
+            .await
+    }
+
+    async fn get_namespaces(&self) -> Result<DashMap<String, Arc<Namespace>>> {
+        self.local_adapter.get_namespaces().await
     }
 }
@@ -0,0 +1,131 @@
+// src/adapter/registry.rs
+//! Cluster membership: every node periodically publishes a registry entry
+//! through the adapter backend, and the live entries are what the
+//! `/cluster/nodes` endpoint reports.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::adapter::Adapter;
+use crate::error::Result;
+use crate::options::NodeRegistryConfig;
+
+/// Registry entry a node publishes about itself
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeInfo {
+    pub process_id: String,
+    pub host: String,
+    pub version: String,
+    /// Unix timestamps in milliseconds
+    pub started_at: i64,
+    pub last_heartbeat: i64,
+    /// The entry is considered dead after this time unless refreshed
+    pub expires_at: i64,
+    /// WebSocket connections open on the node
+    pub connections: usize,
+}
+
+impl NodeInfo {
+    pub fn is_expired(&self, now_ms: i64) -> bool {
+        self.expires_at <= now_ms
+    }
+}
+
+/// Publishes this node's entry on every heartbeat and assembles the list of
+/// live nodes from the entries the adapter collected.
+pub struct NodeRegistry {
+    connection_manager: Arc<dyn Adapter + Send + Sync>,
+    process_id: String,
+    host: String,
+    started_at: i64,
+    config: NodeRegistryConfig,
+}
+
+impl NodeRegistry {
+    pub fn new(
+        connection_manager: Arc<dyn Adapter + Send + Sync>,
+        process_id: String,
+        config: NodeRegistryConfig,
+    ) -> Self {
+        let host = sysinfo::System::host_name().unwrap_or_else(|| "unknown".to_string());
+        Self {
+            connection_manager,
+            process_id,
+            host,
+            started_at: chrono::Utc::now().timestamp_millis(),
+            config,
+        }
+    }
+
+    /// Spawn the heartbeat loop. It runs for the lifetime of the process.
+    pub fn start(self: Arc<Self>) {
+        info!(
+            "{}",
+            format!(
+                "Starting node registry heartbeat for {} (interval: {}ms, timeout: {}ms)",
+                self.process_id, self.config.heartbeat_interval_ms, self.config.node_timeout_ms
+            )
+        );
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(Duration::from_millis(
+                self.config.heartbeat_interval_ms.max(100),
+            ));
+            loop {
+                ticker.tick().await;
+                match self.local_node().await {
+                    Ok(node) => {
+                        if let Err(e) = self.connection_manager.register_node(&node).await {
+                            warn!("{}", format!("Failed to publish node heartbeat: {}", e));
+                        }
+                    }
+                    Err(e) => {
+                        warn!("{}", format!("Failed to describe local node: {}", e));
+                    }
+                }
+            }
+        });
+    }
+
+    /// Fresh entry for this node
+    pub async fn local_node(&self) -> Result<NodeInfo> {
+        let connections = self
+            .connection_manager
+            .get_namespaces()
+            .await?
+            .iter()
+            .map(|namespace| namespace.sockets.len())
+            .sum();
+        let now = chrono::Utc::now().timestamp_millis();
+        Ok(NodeInfo {
+            process_id: self.process_id.clone(),
+            host: self.host.clone(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            started_at: self.started_at,
+            last_heartbeat: now,
+            expires_at: now + self.config.node_timeout_ms as i64,
+            connections,
+        })
+    }
+
+    /// This node followed by every other node whose entry has not expired,
+    /// oldest first
+    pub async fn live_nodes(&self) -> Result<Vec<NodeInfo>> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut others: Vec<NodeInfo> = self
+            .connection_manager
+            .registered_nodes()
+            .await?
+            .into_iter()
+            .filter(|node| node.process_id != self.process_id && !node.is_expired(now))
+            .collect();
+        others.sort_by_key(|node| node.started_at);
+
+        let mut nodes = vec![self.local_node().await?];
+        nodes.extend(others);
+        Ok(nodes)
+    }
+}
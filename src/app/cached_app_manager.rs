@@ -0,0 +1,104 @@
+// src/app/cached_app_manager.rs
+use super::config::App;
+use crate::app::manager::AppManager;
+use crate::error::Result;
+use crate::options::CacheSettings;
+use async_trait::async_trait;
+use moka::future::Cache;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Read-through cache in front of another AppManager. Lookups by ID and by
+/// key are served from memory for `ttl` seconds; creating, updating or
+/// deleting an app through this manager refreshes or drops its entries right
+/// away. Changes made on other nodes show up once the entries expire, or
+/// right away for apps disabled on another node.
+pub struct CachedAppManager {
+    inner: Arc<dyn AppManager + Send + Sync>,
+    by_id: Cache<String, App>,
+    by_key: Cache<String, App>,
+}
+
+impl CachedAppManager {
+    pub fn new(inner: Arc<dyn AppManager + Send + Sync>, settings: &CacheSettings) -> Self {
+        let cache = || {
+            Cache::builder()
+                .time_to_live(Duration::from_secs(settings.ttl.max(1)))
+                .max_capacity(settings.max_capacity)
+                .build()
+        };
+        Self {
+            inner,
+            by_id: cache(),
+            by_key: cache(),
+        }
+    }
+
+    async fn remember(&self, app: &App) {
+        self.by_id.insert(app.id.clone(), app.clone()).await;
+        self.by_key.insert(app.key.clone(), app.clone()).await;
+    }
+}
+
+#[async_trait]
+impl AppManager for CachedAppManager {
+    async fn init(&self) -> Result<()> {
+        self.inner.init().await
+    }
+
+    async fn create_app(&self, config: App) -> Result<()> {
+        self.inner.create_app(config.clone()).await?;
+        self.remember(&config).await;
+        Ok(())
+    }
+
+    async fn update_app(&self, config: App) -> Result<()> {
+        // Drop the entries first, so an old key doesn't outlive the update
+        self.invalidate(&config.id).await;
+        self.inner.update_app(config.clone()).await?;
+        self.remember(&config).await;
+        Ok(())
+    }
+
+    async fn delete_app(&self, app_id: &str) -> Result<()> {
+        self.invalidate(app_id).await;
+        self.inner.delete_app(app_id).await
+    }
+
+    async fn get_apps(&self) -> Result<Vec<App>> {
+        let apps = self.inner.get_apps().await?;
+        for app in &apps {
+            self.remember(app).await;
+        }
+        Ok(apps)
+    }
+
+    async fn find_by_key(&self, key: &str) -> Result<Option<App>> {
+        if let Some(app) = self.by_key.get(key).await {
+            return Ok(Some(app));
+        }
+        let app = self.inner.find_by_key(key).await?;
+        if let Some(app) = &app {
+            self.remember(app).await;
+        }
+        Ok(app)
+    }
+
+    async fn find_by_id(&self, app_id: &str) -> Result<Option<App>> {
+        if let Some(app) = self.by_id.get(app_id).await {
+            return Ok(Some(app));
+        }
+        let app = self.inner.find_by_id(app_id).await?;
+        if let Some(app) = &app {
+            self.remember(app).await;
+        }
+        Ok(app)
+    }
+
+    /// Drop the cached entries of an app, under its ID and its last known key
+    async fn invalidate(&self, app_id: &str) {
+        if let Some(app) = self.by_id.remove(app_id).await {
+            self.by_key.remove(&app.key).await;
+        }
+    }
+}
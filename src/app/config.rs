@@ -6,6 +6,11 @@ pub struct App {
     pub id: String,
     pub key: String,
     pub secret: String,
+    /// Second secret accepted for signatures while the secret is rotated.
+    /// Webhooks and other server-side signatures keep using `secret` until
+    /// the secondary is promoted.
+    #[serde(default)]
+    pub secondary_secret: Option<String>,
     #[serde(deserialize_with = "deserialize_number_from_string")]
     pub max_connections: u32,
     pub enable_client_messages: bool,
@@ -16,26 +21,92 @@ pub struct App {
     pub max_client_events_per_second: u32,
     #[serde(default, deserialize_with = "deserialize_optional_number_from_string")]
     pub max_read_requests_per_second: Option<u32>,
+    /// HTTP API requests per second, replacing the global
+    /// `rate_limiter.api_rate_limit` for this app
     #[serde(default, deserialize_with = "deserialize_optional_number_from_string")]
-    pub max_presence_members_per_channel: Option<u32>,
+    pub max_api_requests_per_second: Option<u32>,
+    /// New WebSocket connections per second, replacing the global
+    /// `rate_limiter.websocket_rate_limit` for this app
     #[serde(default, deserialize_with = "deserialize_optional_number_from_string")]
+    pub max_connections_per_second: Option<u32>,
+    #[serde(default, deserialize_with = "deserialize_optional_number_from_string")]
+    pub max_presence_members_per_channel: Option<u32>,
+    #[serde(
+        default,
+        alias = "max_presence_member_size_kb",
+        deserialize_with = "deserialize_optional_number_from_string"
+    )]
     pub max_presence_member_size_in_kb: Option<u32>,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "deserialize_optional_number_from_string")]
     pub max_channel_name_length: Option<u32>,
     #[serde(default, deserialize_with = "deserialize_optional_number_from_string")]
     pub max_event_channels_at_once: Option<u32>,
-    #[serde(default)]
-    pub max_event_name_length: Option<u32>,
     #[serde(default, deserialize_with = "deserialize_optional_number_from_string")]
+    pub max_event_name_length: Option<u32>,
+    #[serde(
+        default,
+        alias = "max_event_payload_kb",
+        deserialize_with = "deserialize_optional_number_from_string"
+    )]
     pub max_event_payload_in_kb: Option<u32>,
     #[serde(default, deserialize_with = "deserialize_optional_number_from_string")]
     pub max_event_batch_size: Option<u32>,
+    /// Channels a single connection may be subscribed to at once
+    #[serde(default, deserialize_with = "deserialize_optional_number_from_string")]
+    pub max_channels_per_connection: Option<u32>,
     #[serde(default)]
     pub enable_user_authentication: Option<bool>,
+    /// Whether clients may use `cache-` channels; on when unset
+    #[serde(default)]
+    pub enable_cache_channels: Option<bool>,
+    /// Whether clients may use `private-encrypted-` channels; on when unset
+    #[serde(default)]
+    pub enable_encrypted_channels: Option<bool>,
     #[serde(default)]
     pub webhooks: Option<Vec<Webhook>>,
     #[serde(default)]
     pub enable_watchlist_events: Option<bool>,
+    /// Seconds of inactivity before clients should ping, advertised in
+    /// `pusher:connection_established`. Falls back to the server default.
+    #[serde(default, deserialize_with = "deserialize_optional_number_from_string")]
+    pub activity_timeout: Option<u32>,
+}
+
+impl App {
+    /// Secrets a client signature may be made with, primary first
+    pub fn verification_secrets(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.secret.as_str()).chain(
+            self.secondary_secret
+                .as_deref()
+                .filter(|secret| !secret.is_empty()),
+        )
+    }
+
+    pub fn cache_channels_enabled(&self) -> bool {
+        self.enable_cache_channels.unwrap_or(true)
+    }
+
+    pub fn encrypted_channels_enabled(&self) -> bool {
+        self.enable_encrypted_channels.unwrap_or(true)
+    }
+}
+
+/// A number given as is or as a numeric string, as environment-sourced
+/// configs provide them
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberOrString {
+    Number(u32),
+    String(String),
+}
+
+impl NumberOrString {
+    fn parse<E: serde::de::Error>(self) -> Result<u32, E> {
+        match self {
+            NumberOrString::Number(n) => Ok(n),
+            NumberOrString::String(s) => s.parse::<u32>().map_err(E::custom),
+        }
+    }
 }
 
 // Helper functions to deserialize numbers from strings
@@ -43,21 +114,16 @@ fn deserialize_number_from_string<'de, D>(deserializer: D) -> Result<u32, D::Err
 where
     D: serde::Deserializer<'de>,
 {
-    use serde::de::Error;
-    let value = String::deserialize(deserializer)?;
-    value.parse::<u32>().map_err(D::Error::custom)
+    NumberOrString::deserialize(deserializer)?.parse()
 }
 
 fn deserialize_optional_number_from_string<'de, D>(deserializer: D) -> Result<Option<u32>, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
-    use serde::de::Error;
-    let value = Option::<String>::deserialize(deserializer)?;
-    match value {
-        Some(s) => Ok(Some(s.parse::<u32>().map_err(D::Error::custom)?)),
-        None => Ok(None),
-    }
+    Option::<NumberOrString>::deserialize(deserializer)?
+        .map(NumberOrString::parse)
+        .transpose()
 }
 
 // Implementation with default values
@@ -2,6 +2,7 @@
 use super::config::App;
 use crate::app::manager::AppManager;
 use crate::error::{Error, Result};
+use crate::webhook::types::validate_app_webhooks;
 use async_trait::async_trait;
 use std::collections::HashMap;
 
@@ -100,6 +101,14 @@ impl DynamoDbAppManager {
                 }
             };
 
+            let get_opt_bool = |key: &str| -> Option<bool> {
+                if let Some(aws_sdk_dynamodb::types::AttributeValue::Bool(b)) = map.get(key) {
+                    Some(*b)
+                } else {
+                    None
+                }
+            };
+
             let get_u32 = |key: &str, default: Option<u32>| -> Option<u32> {
                 if let Some(aws_sdk_dynamodb::types::AttributeValue::N(n)) = map.get(key) {
                     n.parse::<u32>().ok()
@@ -112,6 +121,7 @@ impl DynamoDbAppManager {
                 id: get_string("id")?,
                 key: get_string("key")?,
                 secret: get_string("secret")?,
+                secondary_secret: get_string("secondary_secret").ok(),
                 max_connections: get_u32("max_connections", Some(0)).unwrap_or(0),
                 enable_client_messages: get_bool("enable_client_messages", false),
                 enabled: get_bool("enabled", true),
@@ -119,6 +129,8 @@ impl DynamoDbAppManager {
                 max_client_events_per_second: get_u32("max_client_events_per_second", Some(0))
                     .unwrap_or(0),
                 max_read_requests_per_second: get_u32("max_read_requests_per_second", None),
+                max_api_requests_per_second: get_u32("max_api_requests_per_second", None),
+                max_connections_per_second: get_u32("max_connections_per_second", None),
                 max_presence_members_per_channel: get_u32("max_presence_members_per_channel", None),
                 max_presence_member_size_in_kb: get_u32("max_presence_member_size_in_kb", None),
                 max_channel_name_length: get_u32("max_channel_name_length", None),
@@ -126,16 +138,13 @@ impl DynamoDbAppManager {
                 max_event_name_length: get_u32("max_event_name_length", None),
                 max_event_payload_in_kb: get_u32("max_event_payload_in_kb", None),
                 max_event_batch_size: get_u32("max_event_batch_size", None),
-                enable_user_authentication: if let Some(
-                    aws_sdk_dynamodb::types::AttributeValue::Bool(b),
-                ) = map.get("enable_user_authentication")
-                {
-                    Some(*b)
-                } else {
-                    None
-                },
+                enable_user_authentication: get_opt_bool("enable_user_authentication"),
+                enable_cache_channels: get_opt_bool("enable_cache_channels"),
+                enable_encrypted_channels: get_opt_bool("enable_encrypted_channels"),
                 webhooks: None,
                 enable_watchlist_events: None,
+                activity_timeout: get_u32("activity_timeout", None),
+                max_channels_per_connection: get_u32("max_channels_per_connection", None),
             })
         } else {
             Err(Error::InternalError(
@@ -161,6 +170,12 @@ impl DynamoDbAppManager {
             "secret".to_string(),
             aws_sdk_dynamodb::types::AttributeValue::S(app.secret.clone()),
         );
+        if let Some(val) = &app.secondary_secret {
+            item.insert(
+                "secondary_secret".to_string(),
+                aws_sdk_dynamodb::types::AttributeValue::S(val.clone()),
+            );
+        }
         item.insert(
             "max_connections".to_string(),
             aws_sdk_dynamodb::types::AttributeValue::N(app.max_connections.to_string()),
@@ -195,6 +210,20 @@ impl DynamoDbAppManager {
             );
         }
 
+        if let Some(val) = app.max_api_requests_per_second {
+            item.insert(
+                "max_api_requests_per_second".to_string(),
+                aws_sdk_dynamodb::types::AttributeValue::N(val.to_string()),
+            );
+        }
+
+        if let Some(val) = app.max_connections_per_second {
+            item.insert(
+                "max_connections_per_second".to_string(),
+                aws_sdk_dynamodb::types::AttributeValue::N(val.to_string()),
+            );
+        }
+
         if let Some(val) = app.max_presence_members_per_channel {
             item.insert(
                 "max_presence_members_per_channel".to_string(),
@@ -244,6 +273,13 @@ impl DynamoDbAppManager {
             );
         }
 
+        if let Some(val) = app.max_channels_per_connection {
+            item.insert(
+                "max_channels_per_connection".to_string(),
+                aws_sdk_dynamodb::types::AttributeValue::N(val.to_string()),
+            );
+        }
+
         if let Some(val) = app.enable_user_authentication {
             item.insert(
                 "enable_user_authentication".to_string(),
@@ -251,6 +287,20 @@ impl DynamoDbAppManager {
             );
         }
 
+        if let Some(val) = app.enable_cache_channels {
+            item.insert(
+                "enable_cache_channels".to_string(),
+                aws_sdk_dynamodb::types::AttributeValue::Bool(val),
+            );
+        }
+
+        if let Some(val) = app.enable_encrypted_channels {
+            item.insert(
+                "enable_encrypted_channels".to_string(),
+                aws_sdk_dynamodb::types::AttributeValue::Bool(val),
+            );
+        }
+
         item
     }
 
@@ -396,6 +446,8 @@ impl AppManager for DynamoDbAppManager {
     }
 
     async fn create_app(&self, config: App) -> Result<()> {
+        validate_app_webhooks(&config)?;
+
         // Convert App to DynamoDB item
         let item = self.app_to_item(&config);
 
@@ -416,6 +468,8 @@ impl AppManager for DynamoDbAppManager {
     }
 
     async fn update_app(&self, config: App) -> Result<()> {
+        validate_app_webhooks(&config)?;
+
         // Convert App to DynamoDB item
         let item = self.app_to_item(&config);
 
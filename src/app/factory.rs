@@ -1,8 +1,11 @@
 // src/app/factory.rs
+use crate::app::cached_app_manager::CachedAppManager;
 use crate::app::dynamodb_app_manager::{DynamoDbAppManager, DynamoDbConfig};
+use crate::app::http_app_manager::HttpAppManager;
 use crate::app::manager::AppManager;
 use crate::app::memory_app_manager::MemoryAppManager;
 use crate::app::mysql_app_manager::MySQLAppManager;
+use crate::app::sqlite_app_manager::SqliteAppManager;
 use crate::error::Result;
 
 use crate::options::{AppManagerConfig, AppManagerDriver, DatabaseConfig}; // Import AppManagerDriver
@@ -12,9 +15,26 @@ use tracing::{info, warn};
 pub struct AppManagerFactory;
 
 impl AppManagerFactory {
+    /// The configured app manager, behind a read-through cache when
+    /// `cache.enabled` is set and the apps live outside this process
     pub async fn create(
         config: &AppManagerConfig,
         db_config: &DatabaseConfig,
+    ) -> Result<Arc<dyn AppManager + Send + Sync>> {
+        let manager = Self::create_driver(config, db_config).await?;
+        if !config.cache.enabled || config.driver == AppManagerDriver::Memory {
+            return Ok(manager);
+        }
+        info!(
+            "{}",
+            format!("Caching apps for {} seconds", config.cache.ttl)
+        );
+        Ok(Arc::new(CachedAppManager::new(manager, &config.cache)))
+    }
+
+    async fn create_driver(
+        config: &AppManagerConfig,
+        db_config: &DatabaseConfig,
     ) -> Result<Arc<dyn AppManager + Send + Sync>> {
         info!(
             "{}",
@@ -64,6 +84,35 @@ impl AppManagerFactory {
                     }
                 }
             }
+            AppManagerDriver::Sqlite => {
+                let sqlite_settings = db_config.sqlite.clone();
+                match SqliteAppManager::new(sqlite_settings).await {
+                    Ok(manager) => Ok(Arc::new(manager)),
+                    Err(e) => {
+                        warn!(
+                            "{}",
+                            format!(
+                                "Failed to initialize SQLite app manager: {}, falling back to memory manager",
+                                e
+                            )
+                        );
+                        Ok(Arc::new(MemoryAppManager::new()))
+                    }
+                }
+            }
+            AppManagerDriver::Http => match HttpAppManager::new(&config.http) {
+                Ok(manager) => Ok(Arc::new(manager)),
+                Err(e) => {
+                    warn!(
+                        "{}",
+                        format!(
+                            "Failed to initialize HTTP app manager: {}, falling back to memory manager",
+                            e
+                        )
+                    );
+                    Ok(Arc::new(MemoryAppManager::new()))
+                }
+            },
             AppManagerDriver::Memory | _ => {
                 // Handle unknown as Memory or make it an error
                 info!("{}", "Using memory app manager.".to_string());
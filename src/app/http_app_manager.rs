@@ -0,0 +1,130 @@
+// src/app/http_app_manager.rs
+use super::config::App;
+use crate::app::manager::AppManager;
+use crate::error::{Error, Result};
+use crate::options::HttpAppManagerConfig;
+use async_trait::async_trait;
+use reqwest::{StatusCode, Url, header};
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+use tracing::{debug, info};
+
+/// Reads apps from an external HTTP service that owns the app records:
+///
+/// - `GET {url}` returns every app as a JSON array
+/// - `GET {url}/{app_id}` returns one app, or 404
+/// - `GET {url}/by-key/{key}` returns the app with that key, or 404
+///
+/// Apps are read-only here; they are created and changed in the service.
+pub struct HttpAppManager {
+    client: reqwest::Client,
+    url: Url,
+}
+
+impl HttpAppManager {
+    pub fn new(config: &HttpAppManagerConfig) -> Result<Self> {
+        let url = config
+            .url
+            .as_deref()
+            .ok_or_else(|| Error::Config("app_manager.http.url is required".to_string()))?;
+        let url = Url::parse(url)
+            .map_err(|e| Error::Config(format!("Invalid app_manager.http.url: {}", e)))?;
+        if url.cannot_be_a_base() {
+            return Err(Error::Config(format!(
+                "app_manager.http.url must be an http(s) URL: {}",
+                url
+            )));
+        }
+
+        let mut headers = header::HeaderMap::new();
+        headers.insert(
+            header::ACCEPT,
+            header::HeaderValue::from_static("application/json"),
+        );
+        if let Some(auth) = &config.auth_header {
+            let mut value = header::HeaderValue::from_str(auth).map_err(|e| {
+                Error::Config(format!("Invalid app_manager.http.auth_header: {}", e))
+            })?;
+            value.set_sensitive(true);
+            headers.insert(header::AUTHORIZATION, value);
+        }
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms.max(1)))
+            .default_headers(headers)
+            .build()
+            .map_err(|e| Error::Config(format!("Failed to build app manager client: {}", e)))?;
+
+        info!("{}", format!("Reading apps from {}", url));
+        Ok(Self { client, url })
+    }
+
+    /// `url` with `segments` appended as percent-encoded path segments
+    fn endpoint(&self, segments: &[&str]) -> Url {
+        let mut url = self.url.clone();
+        if let Ok(mut path) = url.path_segments_mut() {
+            path.pop_if_empty().extend(segments);
+        }
+        url
+    }
+
+    /// GET a JSON document, with None for a 404
+    async fn fetch<T: DeserializeOwned>(&self, url: Url) -> Result<Option<T>> {
+        debug!("{}", format!("Fetching apps from {}", url));
+        let response = self
+            .client
+            .get(url.clone())
+            .send()
+            .await
+            .map_err(|e| Error::InternalError(format!("App service request failed: {}", e)))?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(Error::InternalError(format!(
+                "App service returned {} for {}",
+                response.status(),
+                url
+            )));
+        }
+        response
+            .json()
+            .await
+            .map(Some)
+            .map_err(|e| Error::InternalError(format!("Invalid app service response: {}", e)))
+    }
+
+    fn read_only() -> Error {
+        Error::Other("Apps are managed by the app service and are read-only here".to_string())
+    }
+}
+
+#[async_trait]
+impl AppManager for HttpAppManager {
+    async fn init(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn create_app(&self, _config: App) -> Result<()> {
+        Err(Self::read_only())
+    }
+
+    async fn update_app(&self, _config: App) -> Result<()> {
+        Err(Self::read_only())
+    }
+
+    async fn delete_app(&self, _app_id: &str) -> Result<()> {
+        Err(Self::read_only())
+    }
+
+    async fn get_apps(&self) -> Result<Vec<App>> {
+        Ok(self.fetch(self.url.clone()).await?.unwrap_or_default())
+    }
+
+    async fn find_by_key(&self, key: &str) -> Result<Option<App>> {
+        self.fetch(self.endpoint(&["by-key", key])).await
+    }
+
+    async fn find_by_id(&self, app_id: &str) -> Result<Option<App>> {
+        self.fetch(self.endpoint(&[app_id])).await
+    }
+}
@@ -26,4 +26,8 @@ pub trait AppManager: Send + Sync + 'static {
 
     /// Get an app by its ID
     async fn find_by_id(&self, app_id: &str) -> Result<Option<App>>;
+
+    /// Drop the copy of an app kept in memory, so the next lookup reads it
+    /// from the store. Managers without a cache keep the default no-op.
+    async fn invalidate(&self, _app_id: &str) {}
 }
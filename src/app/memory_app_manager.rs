@@ -2,6 +2,7 @@
 use super::config::App;
 use crate::app::manager::AppManager;
 use crate::error::Result;
+use crate::webhook::types::validate_app_webhooks;
 use async_trait::async_trait;
 use dashmap::DashMap;
 use hmac::{Hmac, KeyInit};
@@ -45,11 +46,13 @@ impl AppManager for MemoryAppManager {
     }
 
     async fn create_app(&self, config: App) -> Result<()> {
+        validate_app_webhooks(&config)?;
         self.apps.insert(config.id.clone(), config);
         Ok(())
     }
 
     async fn update_app(&self, config: App) -> Result<()> {
+        validate_app_webhooks(&config)?;
         self.apps.insert(config.id.clone(), config);
         Ok(())
     }
@@ -1,7 +1,11 @@
 pub mod auth;
+pub mod cached_app_manager;
 pub mod config;
 pub mod dynamodb_app_manager;
 pub mod factory;
+pub mod http_app_manager;
 pub mod manager;
 pub mod memory_app_manager;
 pub mod mysql_app_manager;
+pub mod reload;
+pub mod sqlite_app_manager;
@@ -4,22 +4,53 @@ use crate::error::{Error, Result};
 
 use crate::options::DatabaseConnection;
 use crate::token::Token;
+use crate::webhook::types::{Webhook, validate_app_webhooks};
 use crate::websocket::SocketId;
 use async_trait::async_trait;
-use futures_util::{StreamExt, stream};
 use moka::future::Cache;
 use sqlx::{MySqlPool, mysql::MySqlPoolOptions};
-use std::sync::Arc;
+use std::collections::HashSet;
 use std::time::Duration;
 use tracing::{error, info, warn};
 
-/// Configuration for MySQL App Manager
+/// App fields stored in the table, with the column definition used when the
+/// table or a missing nullable column is created. Columns are named after the
+/// field unless `columns` maps the field to another name.
+const APP_FIELDS: &[(&str, &str)] = &[
+    ("id", "VARCHAR(255) PRIMARY KEY"),
+    ("key", "VARCHAR(255) UNIQUE NOT NULL"),
+    ("secret", "VARCHAR(255) NOT NULL"),
+    ("max_connections", "INT UNSIGNED NOT NULL"),
+    ("enable_client_messages", "BOOLEAN NOT NULL DEFAULT FALSE"),
+    ("enabled", "BOOLEAN NOT NULL DEFAULT TRUE"),
+    ("max_backend_events_per_second", "INT UNSIGNED NULL"),
+    ("max_client_events_per_second", "INT UNSIGNED NOT NULL"),
+    ("max_read_requests_per_second", "INT UNSIGNED NULL"),
+    ("max_presence_members_per_channel", "INT UNSIGNED NULL"),
+    ("max_presence_member_size_in_kb", "INT UNSIGNED NULL"),
+    ("max_channel_name_length", "INT UNSIGNED NULL"),
+    ("max_event_channels_at_once", "INT UNSIGNED NULL"),
+    ("max_event_name_length", "INT UNSIGNED NULL"),
+    ("max_event_payload_in_kb", "INT UNSIGNED NULL"),
+    ("max_event_batch_size", "INT UNSIGNED NULL"),
+    ("enable_user_authentication", "BOOLEAN NULL"),
+    ("webhooks", "TEXT NULL"),
+    ("enable_watchlist_events", "BOOLEAN NULL"),
+    ("activity_timeout", "INT UNSIGNED NULL"),
+    ("max_channels_per_connection", "INT UNSIGNED NULL"),
+    ("secondary_secret", "VARCHAR(255) NULL"),
+    ("enable_cache_channels", "BOOLEAN NULL"),
+    ("enable_encrypted_channels", "BOOLEAN NULL"),
+    ("max_api_requests_per_second", "INT UNSIGNED NULL"),
+    ("max_connections_per_second", "INT UNSIGNED NULL"),
+];
 
 /// MySQL-based implementation of the AppManager
 pub struct MySQLAppManager {
     config: DatabaseConnection,
     pool: MySqlPool,
     app_cache: Cache<String, App>, // App ID -> App
+    key_cache: Cache<String, App>, // App key -> App
 }
 
 impl MySQLAppManager {
@@ -40,26 +71,29 @@ impl MySQLAppManager {
             config.username, password, config.host, config.port, config.database
         );
 
-        // Create connection pool with improved options
         let pool = MySqlPoolOptions::new()
-            .max_connections(config.connection_pool_size)
-            .acquire_timeout(Duration::from_secs(5))
-            .idle_timeout(Duration::from_secs(180))
+            .max_connections(config.connection_pool_size.max(1))
+            .min_connections(config.pool_min_connections)
+            .acquire_timeout(Duration::from_millis(config.acquire_timeout_ms))
+            .idle_timeout(Duration::from_secs(config.idle_timeout_secs))
             .connect(&connection_string)
             .await
             .map_err(|e| Error::InternalError(format!("Failed to connect to MySQL: {}", e)))?;
 
-        // Initialize cache
         let app_cache = Cache::builder()
             .time_to_live(Duration::from_secs(config.cache_ttl))
             .max_capacity(config.cache_max_capacity)
-            // Add other options like time_to_idle if needed
+            .build();
+        let key_cache = Cache::builder()
+            .time_to_live(Duration::from_secs(config.cache_ttl))
+            .max_capacity(config.cache_max_capacity)
             .build();
 
         let manager = Self {
             config,
             pool,
             app_cache,
+            key_cache,
         };
 
         manager.ensure_table_exists().await?;
@@ -67,45 +101,90 @@ impl MySQLAppManager {
         Ok(manager)
     }
 
-    /// Create the applications table if it doesn't exist
+    /// Column holding an App field
+    fn column<'a>(&'a self, field: &'a str) -> &'a str {
+        self.config
+            .columns
+            .get(field)
+            .map(String::as_str)
+            .unwrap_or(field)
+    }
+
+    /// Columns of every App field, aliased to the field names `AppRow` reads
+    fn select_columns(&self) -> String {
+        APP_FIELDS
+            .iter()
+            .map(|(field, _)| format!("`{}` AS `{}`", self.column(field), field))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Create the applications table if it doesn't exist, and add nullable
+    /// columns an older table is missing
     async fn ensure_table_exists(&self) -> Result<()> {
-        // Use a constant query (avoid format!) for security
-        const CREATE_TABLE_QUERY: &str = r#"
-            CREATE TABLE IF NOT EXISTS `applications` (
-                id VARCHAR(255) PRIMARY KEY,
-                `key` VARCHAR(255) UNIQUE NOT NULL,
-                secret VARCHAR(255) NOT NULL,
-                max_connections INT UNSIGNED NOT NULL,
-                enable_client_messages BOOLEAN NOT NULL DEFAULT FALSE,
-                enabled BOOLEAN NOT NULL DEFAULT TRUE,
-                max_backend_events_per_second INT UNSIGNED NULL,
-                max_client_events_per_second INT UNSIGNED NOT NULL,
-                max_read_requests_per_second INT UNSIGNED NULL,
-                max_presence_members_per_channel INT UNSIGNED NULL,
-                max_presence_member_size_in_kb INT UNSIGNED NULL,
-                max_channel_name_length INT UNSIGNED NULL,
-                max_event_channels_at_once INT UNSIGNED NULL,
-                max_event_name_length INT UNSIGNED NULL,
-                max_event_payload_in_kb INT UNSIGNED NULL,
-                max_event_batch_size INT UNSIGNED NULL,
-                enable_user_authentication BOOLEAN NULL,
+        let definitions = APP_FIELDS
+            .iter()
+            .map(|(field, definition)| format!("`{}` {}", self.column(field), definition))
+            .collect::<Vec<_>>()
+            .join(",\n                ");
+        let query = format!(
+            r#"CREATE TABLE IF NOT EXISTS `{}` (
+                {},
                 created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
                 updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP
-            ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci
-        "#;
-
-        // Replace table name with the configured one if different from default
-        let query = if self.config.table_name != "applications" {
-            CREATE_TABLE_QUERY.replace("applications", &self.config.table_name)
-        } else {
-            CREATE_TABLE_QUERY.to_string()
-        };
+            ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci"#,
+            self.config.table_name, definitions
+        );
 
         sqlx::query(&query)
             .execute(&self.pool)
             .await
             .map_err(|e| Error::InternalError(format!("Failed to create MySQL table: {}", e)))?;
 
+        let existing: HashSet<String> = sqlx::query_scalar::<_, String>(
+            r#"SELECT COLUMN_NAME FROM information_schema.COLUMNS
+                WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ?"#,
+        )
+        .bind(&self.config.table_name)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| Error::InternalError(format!("Failed to inspect MySQL table: {}", e)))?
+        .into_iter()
+        .map(|column| column.to_lowercase())
+        .collect();
+
+        for (field, definition) in APP_FIELDS {
+            let column = self.column(field);
+            if existing.contains(&column.to_lowercase()) {
+                continue;
+            }
+            if definition.contains("NOT NULL") || definition.contains("KEY") {
+                return Err(Error::ConfigurationError(format!(
+                    "MySQL table '{}' has no column '{}' for the app's {}; map it in `columns`",
+                    self.config.table_name, column, field
+                )));
+            }
+            warn!(
+                "{}",
+                format!(
+                    "Adding missing column '{}' to MySQL table '{}'",
+                    column, self.config.table_name
+                )
+            );
+            sqlx::query(&format!(
+                "ALTER TABLE `{}` ADD COLUMN `{}` {}",
+                self.config.table_name, column, definition
+            ))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                Error::InternalError(format!(
+                    "Failed to add column {} to MySQL table: {}",
+                    column, e
+                ))
+            })?;
+        }
+
         info!(
             "{}",
             format!("Ensured table '{}' exists", self.config.table_name)
@@ -113,138 +192,96 @@ impl MySQLAppManager {
         Ok(())
     }
 
-    /// Get an app by ID from cache or database
-    pub async fn find_by_id(&self, app_id: &str) -> Result<Option<App>> {
-        // Try to get from cache first
-        if let Some(app) = self.app_cache.get(app_id).await {
-            return Ok(Some(app));
+    async fn cache_app(&self, app: &App) {
+        self.app_cache.insert(app.id.clone(), app.clone()).await;
+        self.key_cache.insert(app.key.clone(), app.clone()).await;
+    }
+
+    async fn invalidate_app(&self, app_id: &str) {
+        if let Some(app) = self.app_cache.remove(app_id).await {
+            self.key_cache.remove(&app.key).await;
         }
+    }
 
-        // Not in cache or expired, fetch from database
+    async fn fetch_one(&self, field: &str, value: &str) -> Result<Option<App>> {
         info!(
             "{}",
-            format!("Cache miss for app {}, fetching from database", app_id)
+            format!(
+                "Cache miss for app {} {}, fetching from database",
+                field, value
+            )
         );
-
-        // Use a query_as that matches your App struct
-        // Create the query with the correct table name
         let query = format!(
-            r#"SELECT
-                id, `key`, secret, max_connections,
-                enable_client_messages, enabled,
-                max_backend_events_per_second,
-                max_client_events_per_second,
-                max_read_requests_per_second,
-                max_presence_members_per_channel,
-                max_presence_member_size_in_kb,
-                max_channel_name_length,
-                max_event_channels_at_once,
-                max_event_name_length,
-                max_event_payload_in_kb,
-                max_event_batch_size,
-                enable_user_authentication
-            FROM `{}` WHERE id = ?"#,
-            self.config.table_name
+            "SELECT {} FROM `{}` WHERE `{}` = ?",
+            self.select_columns(),
+            self.config.table_name,
+            self.column(field)
         );
 
-        let app_result = sqlx::query_as::<_, AppRow>(&query)
-            .bind(app_id)
+        let row = sqlx::query_as::<_, AppRow>(&query)
+            .bind(value)
             .fetch_optional(&self.pool)
             .await
             .map_err(|e| {
                 error!(
                     "{}",
-                    format!("Database error fetching app {}: {}", app_id, e)
+                    format!("Database error fetching app by {} {}: {}", field, value, e)
                 );
                 Error::InternalError(format!("Failed to fetch app from MySQL: {}", e))
             })?;
 
-        if let Some(app_row) = app_result {
-            // Convert to App
-            let app = app_row.into_app();
+        match row {
+            Some(row) => {
+                let app = row.into_app();
+                self.cache_app(&app).await;
+                Ok(Some(app))
+            }
+            None => Ok(None),
+        }
+    }
 
-            // Update cache
-            self.app_cache.insert(app_id.to_string(), app.clone()).await;
+    fn serialize_webhooks(app: &App) -> Result<Option<String>> {
+        app.webhooks
+            .as_ref()
+            .map(|webhooks| {
+                serde_json::to_string(webhooks).map_err(|e| {
+                    Error::InternalError(format!("Failed to serialize app webhooks: {}", e))
+                })
+            })
+            .transpose()
+    }
 
-            Ok(Some(app))
-        } else {
-            Ok(None)
+    /// Get an app by ID from cache or database
+    pub async fn find_by_id(&self, app_id: &str) -> Result<Option<App>> {
+        if let Some(app) = self.app_cache.get(app_id).await {
+            return Ok(Some(app));
         }
+        self.fetch_one("id", app_id).await
     }
 
     /// Get an app by key from cache or database
     pub async fn find_by_key(&self, key: &str) -> Result<Option<App>> {
-        // Check cache first
-        if let Some(app) = self.app_cache.get(key).await {
+        if let Some(app) = self.key_cache.get(key).await {
             return Ok(Some(app));
         }
-
-        // Not found in cache, query database
-        info!(
-            "{}",
-            format!("Cache miss for app key {}, fetching from database", key)
-        );
-
-        let query = format!(
-            r#"SELECT
-                id, `key`, secret, max_connections,
-                enable_client_messages, enabled,
-                max_backend_events_per_second,
-                max_client_events_per_second,
-                max_read_requests_per_second,
-                max_presence_members_per_channel,
-                max_presence_member_size_in_kb,
-                max_channel_name_length,
-                max_event_channels_at_once,
-                max_event_name_length,
-                max_event_payload_in_kb,
-                max_event_batch_size,
-                enable_user_authentication
-            FROM `{}` WHERE `key` = ?"#,
-            self.config.table_name
-        );
-
-        let app_result = sqlx::query_as::<_, AppRow>(&query)
-            .bind(key)
-            .fetch_optional(&self.pool)
-            .await
-            .map_err(|e| {
-                error!(
-                    "{}",
-                    format!("Database error fetching app by key {}: {}", key, e)
-                );
-                Error::InternalError(format!("Failed to fetch app from MySQL: {}", e))
-            })?;
-
-        if let Some(app_row) = app_result {
-            let app = app_row.into_app();
-
-            // Update cache with this app
-            let app_id = app.id.clone();
-            self.app_cache.insert(app_id, app.clone()).await;
-
-            Ok(Some(app.clone().into()))
-        } else {
-            Ok(None)
-        }
+        self.fetch_one("key", key).await
     }
 
     /// Register a new app in the database
     pub async fn create_app(&self, app: App) -> Result<()> {
         info!("{}", format!("Registering new app: {}", app.id));
 
-        // Prepare the query with proper table name
+        let columns = APP_FIELDS
+            .iter()
+            .map(|(field, _)| format!("`{}`", self.column(field)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let placeholders = vec!["?"; APP_FIELDS.len()].join(", ");
         let query = format!(
-            r#"INSERT INTO `{}` (
-                id, `key`, secret, max_connections, enable_client_messages, enabled,
-                max_backend_events_per_second, max_client_events_per_second,
-                max_read_requests_per_second, max_presence_members_per_channel,
-                max_presence_member_size_in_kb, max_channel_name_length,
-                max_event_channels_at_once, max_event_name_length,
-                max_event_payload_in_kb, max_event_batch_size, enable_user_authentication
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
-            self.config.table_name
+            "INSERT INTO `{}` ({}) VALUES ({})",
+            self.config.table_name, columns, placeholders
         );
+        let webhooks = Self::serialize_webhooks(&app)?;
 
         sqlx::query(&query)
             .bind(&app.id)
@@ -264,6 +301,15 @@ impl MySQLAppManager {
             .bind(app.max_event_payload_in_kb)
             .bind(app.max_event_batch_size)
             .bind(app.enable_user_authentication)
+            .bind(webhooks)
+            .bind(app.enable_watchlist_events)
+            .bind(app.activity_timeout)
+            .bind(app.max_channels_per_connection)
+            .bind(&app.secondary_secret)
+            .bind(app.enable_cache_channels)
+            .bind(app.enable_encrypted_channels)
+            .bind(app.max_api_requests_per_second)
+            .bind(app.max_connections_per_second)
             .execute(&self.pool)
             .await
             .map_err(|e| {
@@ -274,9 +320,7 @@ impl MySQLAppManager {
                 Error::InternalError(format!("Failed to insert app into MySQL: {}", e))
             })?;
 
-        // Update cach
-        self.app_cache.insert(app.id.clone(), app).await;
-
+        self.cache_app(&app).await;
         Ok(())
     }
 
@@ -284,18 +328,19 @@ impl MySQLAppManager {
     pub async fn update_app(&self, app: App) -> Result<()> {
         info!("{}", format!("Updating app: {}", app.id));
 
-        // Prepare the query with proper table name
+        let assignments = APP_FIELDS
+            .iter()
+            .skip(1) // id
+            .map(|(field, _)| format!("`{}` = ?", self.column(field)))
+            .collect::<Vec<_>>()
+            .join(", ");
         let query = format!(
-            r#"UPDATE `{}` SET
-                `key` = ?, secret = ?, max_connections = ?, enable_client_messages = ?, enabled = ?,
-                max_backend_events_per_second = ?, max_client_events_per_second = ?,
-                max_read_requests_per_second = ?, max_presence_members_per_channel = ?,
-                max_presence_member_size_in_kb = ?, max_channel_name_length = ?,
-                max_event_channels_at_once = ?, max_event_name_length = ?,
-                max_event_payload_in_kb = ?, max_event_batch_size = ?, enable_user_authentication = ?
-                WHERE id = ?"#,
-            self.config.table_name
+            "UPDATE `{}` SET {} WHERE `{}` = ?",
+            self.config.table_name,
+            assignments,
+            self.column("id")
         );
+        let webhooks = Self::serialize_webhooks(&app)?;
 
         let result = sqlx::query(&query)
             .bind(&app.key)
@@ -314,6 +359,15 @@ impl MySQLAppManager {
             .bind(app.max_event_payload_in_kb)
             .bind(app.max_event_batch_size)
             .bind(app.enable_user_authentication)
+            .bind(webhooks)
+            .bind(app.enable_watchlist_events)
+            .bind(app.activity_timeout)
+            .bind(app.max_channels_per_connection)
+            .bind(&app.secondary_secret)
+            .bind(app.enable_cache_channels)
+            .bind(app.enable_encrypted_channels)
+            .bind(app.max_api_requests_per_second)
+            .bind(app.max_connections_per_second)
             .bind(&app.id)
             .execute(&self.pool)
             .await
@@ -329,9 +383,9 @@ impl MySQLAppManager {
             return Err(Error::InvalidAppKey);
         }
 
-        // Update cache
-        self.app_cache.insert(app.id.clone(), app).await;
-
+        // The key may have changed, so drop the old key mapping before re-caching
+        self.invalidate_app(&app.id).await;
+        self.cache_app(&app).await;
         Ok(())
     }
 
@@ -339,8 +393,11 @@ impl MySQLAppManager {
     pub async fn delete_app(&self, app_id: &str) -> Result<()> {
         info!("{}", format!("Removing app: {}", app_id));
 
-        // Prepare the query with proper table name
-        let query = format!(r#"DELETE FROM `{}` WHERE id = ?"#, self.config.table_name);
+        let query = format!(
+            "DELETE FROM `{}` WHERE `{}` = ?",
+            self.config.table_name,
+            self.column("id")
+        );
 
         let result = sqlx::query(&query)
             .bind(app_id)
@@ -358,9 +415,7 @@ impl MySQLAppManager {
             return Err(Error::InvalidAppKey);
         }
 
-        // Remove from cache
-        self.app_cache.remove(app_id).await;
-
+        self.invalidate_app(app_id).await;
         Ok(())
     }
 
@@ -369,68 +424,30 @@ impl MySQLAppManager {
         info!("{}", "Fetching all apps from database");
 
         let query = format!(
-            r#"SELECT
-            id, `key`, secret, max_connections,
-            enable_client_messages, enabled,
-            max_backend_events_per_second,
-            max_client_events_per_second,
-            max_read_requests_per_second,
-            max_presence_members_per_channel,
-            max_presence_member_size_in_kb,
-            max_channel_name_length,
-            max_event_channels_at_once,
-            max_event_name_length,
-            max_event_payload_in_kb,
-            max_event_batch_size,
-            enable_user_authentication,
-            enable_watchlist_events
-        FROM `{}`"#,
-            self.config.table_name // Ensure config.table_name is safely handled
+            "SELECT {} FROM `{}`",
+            self.select_columns(),
+            self.config.table_name
         );
 
-        // Fetch all rows from the database
-        let app_rows = sqlx::query_as::<_, AppRow>(&query) // Ensure AppRow derives FromRow
+        let rows = sqlx::query_as::<_, AppRow>(&query)
             .fetch_all(&self.pool)
             .await
             .map_err(|e| {
                 error!("{}", format!("Database error fetching all apps: {}", e));
-                // Consider a more specific error type if possible
                 Error::InternalError(format!("Failed to fetch apps from MySQL: {}", e))
             })?;
 
-        warn!(
-            "{}",
-            format!("Fetched {} app rows from database.", app_rows.len())
-        );
-
-        // Process rows concurrently using streams:
-        // 1. Convert iterator to stream
-        // 2. Map each row to an async block that converts, caches, and returns the App
-        // 3. Buffer the async operations for concurrency
-        // 4. Collect the results (Apps) into a Vec
-        let apps = stream::iter(app_rows)
-            .map(|row| async {
-                let app = row.into_app(); // Convert row to App struct
-                let app_arc = Arc::new(app.clone()); // Create Arc for caching
-
-                // Insert the Arc<App> into the cache
-                // Note: insert takes key by value, so clone app_arc.id
-                self.app_cache.insert(app_arc.id.clone(), app.clone()).await;
-
-                // Return the owned App for the final Vec<App>
-                app
-            })
-            // Execute up to N futures concurrently (e.g., based on pool size)
-            // Adjust buffer size as needed. Using connection_pool_size might be reasonable.
-            .buffer_unordered(self.config.connection_pool_size as usize)
-            .collect::<Vec<App>>() // Collect the resulting Apps
-            .await; // Await the stream processing
+        let mut apps = Vec::with_capacity(rows.len());
+        for row in rows {
+            let app = row.into_app();
+            self.cache_app(&app).await;
+            apps.push(app);
+        }
 
         info!(
             "{}",
             format!("Finished processing and caching {} apps.", apps.len())
         );
-
         Ok(apps)
     }
 
@@ -439,7 +456,7 @@ impl MySQLAppManager {
         Ok(self.find_by_id(app_id).await?.is_some())
     }
 
-    /// Validate a signature against an app's secret
+    /// Validate a signature against an app's secrets
     pub async fn validate_signature(
         &self,
         app_id: &str,
@@ -451,10 +468,10 @@ impl MySQLAppManager {
             .await?
             .ok_or_else(|| Error::InvalidAppKey)?;
 
-        let token = Token::new(app.key.clone(), app.secret.clone());
-        let expected = token.sign(body);
-
-        Ok(signature == expected)
+        Ok(app.verification_secrets().any(|secret| {
+            let token = Token::new(app.key.clone(), secret.to_string());
+            signature == token.sign(body)
+        }))
     }
 
     /// Validate if a channel name is valid for an app
@@ -513,11 +530,11 @@ impl MySQLAppManager {
         // Create string to sign: socket_id
         let string_to_sign = format!("{}::user::{}", socket_id, signature);
 
-        // Generate token
-        let token = Token::new(app.key.clone(), app.secret.clone());
-
-        // Verify
-        Ok(token.verify(&string_to_sign, &signature))
+        // Verify against each secret accepted during a rotation
+        Ok(app.verification_secrets().any(|secret| {
+            let token = Token::new(app.key.clone(), secret.to_string());
+            token.verify(&string_to_sign, &signature)
+        }))
     }
 }
 
@@ -541,15 +558,36 @@ struct AppRow {
     max_event_payload_in_kb: Option<u32>,
     max_event_batch_size: Option<u32>,
     enable_user_authentication: Option<bool>,
+    webhooks: Option<String>,
+    enable_watchlist_events: Option<bool>,
+    activity_timeout: Option<u32>,
+    max_channels_per_connection: Option<u32>,
+    secondary_secret: Option<String>,
+    enable_cache_channels: Option<bool>,
+    enable_encrypted_channels: Option<bool>,
+    max_api_requests_per_second: Option<u32>,
+    max_connections_per_second: Option<u32>,
 }
 
 impl AppRow {
     /// Convert database row to App struct
     fn into_app(self) -> App {
+        let webhooks = self.webhooks.and_then(|raw| {
+            serde_json::from_str::<Vec<Webhook>>(&raw)
+                .map_err(|e| {
+                    error!(
+                        "{}",
+                        format!("Invalid webhooks JSON stored for app {}: {}", self.id, e)
+                    );
+                })
+                .ok()
+        });
+
         App {
             id: self.id,
             key: self.key,
             secret: self.secret,
+            secondary_secret: self.secondary_secret,
             max_connections: self.max_connections,
             enable_client_messages: self.enable_client_messages,
             enabled: self.enabled,
@@ -564,50 +602,45 @@ impl AppRow {
             max_event_payload_in_kb: self.max_event_payload_in_kb,
             max_event_batch_size: self.max_event_batch_size,
             enable_user_authentication: self.enable_user_authentication,
-            webhooks: None, // Assuming webhooks are not part of the App struct
-            enable_watchlist_events: None, // Assuming this is not part of the App struct
+            enable_cache_channels: self.enable_cache_channels,
+            enable_encrypted_channels: self.enable_encrypted_channels,
+            max_api_requests_per_second: self.max_api_requests_per_second,
+            max_connections_per_second: self.max_connections_per_second,
+            webhooks,
+            enable_watchlist_events: self.enable_watchlist_events,
+            activity_timeout: self.activity_timeout,
+            max_channels_per_connection: self.max_channels_per_connection,
         }
     }
 }
 
-// Implement your App trait for MySQLAppManager
-// This implementation will depend on how your current code is structured
 #[async_trait]
 impl AppManager for MySQLAppManager {
-    // The basic implementation delegates to our improved methods above
-    // You may need to adjust this based on your specific AppManager trait
-
     async fn init(&self) -> Result<()> {
         // Initialization is done in the constructor
         Ok(())
     }
 
     async fn create_app(&self, config: App) -> Result<()> {
-        // Spawn a blocking task to avoid blocking the current thread
-        let config_clone = config.clone();
-        self.create_app(config_clone).await
+        validate_app_webhooks(&config)?;
+        self.create_app(config).await
     }
 
     async fn update_app(&self, config: App) -> Result<()> {
-        // This calls our implementation method
+        validate_app_webhooks(&config)?;
         self.update_app(config).await
     }
 
     async fn delete_app(&self, app_id: &str) -> Result<()> {
-        // This calls our implementation method
         self.delete_app(app_id).await
     }
 
     async fn get_apps(&self) -> Result<Vec<App>> {
-        // This calls our implementation method
         self.get_apps().await
     }
 
     async fn find_by_id(&self, app_id: &str) -> Result<Option<App>> {
-        // For the sync interface, poll the async function in a blocking manner
-        self.find_by_id(app_id)
-            .await
-            .map(|app| app.map(|a| a.clone()))
+        self.find_by_id(app_id).await
     }
 
     async fn find_by_key(&self, key: &str) -> Result<Option<App>> {
@@ -622,6 +655,7 @@ impl Clone for MySQLAppManager {
             config: self.config.clone(),
             pool: self.pool.clone(),
             app_cache: self.app_cache.clone(),
+            key_cache: self.key_cache.clone(),
         }
     }
 }
@@ -638,6 +672,7 @@ mod tests {
             id: id.to_string(),
             key: format!("{}_key", id),
             secret: format!("{}_secret", id),
+            secondary_secret: None,
             max_connections: 100,
             enable_client_messages: true,
             enabled: true,
@@ -652,8 +687,14 @@ mod tests {
             max_event_payload_in_kb: Some(100),
             max_event_batch_size: Some(10),
             enable_user_authentication: Some(true),
+            enable_cache_channels: None,
+            enable_encrypted_channels: None,
+            max_api_requests_per_second: None,
+            max_connections_per_second: None,
             webhooks: None,
             enable_watchlist_events: None,
+            activity_timeout: None,
+            max_channels_per_connection: None,
         }
     }
 
@@ -0,0 +1,161 @@
+// src/app/reload.rs
+//! Reloads the apps of the config file into the running server, on SIGHUP or
+//! `POST /admin/reload`. Apps are diffed against the previous load by ID:
+//! new ones are created, changed ones updated and removed ones deleted.
+//! Unchanged apps aren't touched, so their connections carry on as they were.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::Serialize;
+use tokio::sync::Mutex;
+use tracing::{error, info};
+
+use super::config::App;
+use crate::adapter::Adapter;
+use crate::app::manager::AppManager;
+use crate::config_migration;
+use crate::error::{Error, Result};
+
+#[derive(Debug, Default, Serialize)]
+pub struct ReloadSummary {
+    pub created: Vec<String>,
+    pub updated: Vec<String>,
+    pub deleted: Vec<String>,
+    pub unchanged: usize,
+    /// Apps whose change failed to apply; they are retried on the next reload
+    pub failed: Vec<String>,
+}
+
+pub struct AppReloader {
+    app_manager: Arc<dyn AppManager + Send + Sync>,
+    /// Closes the connections of apps the reload disables
+    connection_manager: Arc<dyn Adapter + Send + Sync>,
+    config_path: String,
+    /// Apps of the last load, by ID. Holding the lock for a whole reload
+    /// keeps concurrent reloads from interleaving.
+    loaded: Mutex<HashMap<String, App>>,
+}
+
+impl AppReloader {
+    pub fn new(
+        app_manager: Arc<dyn AppManager + Send + Sync>,
+        connection_manager: Arc<dyn Adapter + Send + Sync>,
+        config_path: String,
+        apps: &[App],
+    ) -> Self {
+        Self {
+            app_manager,
+            connection_manager,
+            config_path,
+            loaded: Mutex::new(
+                apps.iter()
+                    .map(|app| (app.id.clone(), app.clone()))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn read_apps(&self) -> Result<Vec<App>> {
+        let contents = std::fs::read_to_string(&self.config_path).map_err(|e| {
+            Error::ConfigFileError(format!("Failed to read {}: {}", self.config_path, e))
+        })?;
+        let (config, _) = config_migration::parse(&contents).map_err(|e| {
+            Error::ConfigFileError(format!("Failed to parse {}: {}", self.config_path, e))
+        })?;
+        Ok(config.app_manager.array.apps)
+    }
+
+    /// Close the connections of an app the reload disabled
+    async fn disconnect(&self, app_id: &str) {
+        if let Err(e) = self.connection_manager.disconnect_app(app_id).await {
+            error!(
+                "{}",
+                format!("Failed to disconnect disabled app {}: {}", app_id, e)
+            );
+        }
+    }
+
+    pub async fn reload(&self) -> Result<ReloadSummary> {
+        let apps = self.read_apps()?;
+        let mut loaded = self.loaded.lock().await;
+        let mut summary = ReloadSummary::default();
+        let mut next = HashMap::with_capacity(apps.len());
+
+        for app in apps {
+            let id = app.id.clone();
+            let result = match loaded.get(&id) {
+                Some(previous) if same_app(previous, &app) => {
+                    summary.unchanged += 1;
+                    Ok(())
+                }
+                Some(previous) => {
+                    let disabled = previous.enabled && !app.enabled;
+                    let updated = self.app_manager.update_app(app.clone()).await;
+                    if updated.is_ok() {
+                        summary.updated.push(id.clone());
+                        if disabled {
+                            self.disconnect(&id).await;
+                        }
+                    }
+                    updated
+                }
+                None => self
+                    .app_manager
+                    .create_app(app.clone())
+                    .await
+                    .map(|_| summary.created.push(id.clone())),
+            };
+            match result {
+                Ok(()) => {
+                    next.insert(id, app);
+                }
+                Err(e) => {
+                    error!("{}", format!("Failed to reload app {}: {}", id, e));
+                    if let Some(previous) = loaded.remove(&id) {
+                        next.insert(id.clone(), previous);
+                    }
+                    summary.failed.push(id);
+                }
+            }
+        }
+
+        for id in loaded.keys().filter(|id| !next.contains_key(*id)) {
+            match self.app_manager.delete_app(id).await {
+                Ok(()) => summary.deleted.push(id.clone()),
+                Err(e) => {
+                    error!("{}", format!("Failed to delete app {}: {}", id, e));
+                    summary.failed.push(id.clone());
+                }
+            }
+        }
+        for id in &summary.failed {
+            if let Some(previous) = loaded.get(id) {
+                next.entry(id.clone()).or_insert_with(|| previous.clone());
+            }
+        }
+        *loaded = next;
+
+        info!(
+            "{}",
+            format!(
+                "Reloaded apps from {}: {} created, {} updated, {} deleted, {} unchanged, {} failed",
+                self.config_path,
+                summary.created.len(),
+                summary.updated.len(),
+                summary.deleted.len(),
+                summary.unchanged,
+                summary.failed.len()
+            )
+        );
+        Ok(summary)
+    }
+}
+
+/// App has no PartialEq, so apps are compared through their JSON form
+fn same_app(a: &App, b: &App) -> bool {
+    match (serde_json::to_value(a), serde_json::to_value(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
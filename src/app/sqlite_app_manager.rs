@@ -0,0 +1,589 @@
+use super::config::App;
+use crate::app::manager::AppManager;
+use crate::error::{Error, Result};
+
+use crate::options::SqliteSettings;
+use crate::webhook::types::{Webhook, validate_app_webhooks};
+use async_trait::async_trait;
+use moka::future::Cache;
+use sqlx::SqlitePool;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+const APP_COLUMNS: &str = r#"
+    id, key, secret, max_connections,
+    enable_client_messages, enabled,
+    max_backend_events_per_second,
+    max_client_events_per_second,
+    max_read_requests_per_second,
+    max_presence_members_per_channel,
+    max_presence_member_size_in_kb,
+    max_channel_name_length,
+    max_event_channels_at_once,
+    max_event_name_length,
+    max_event_payload_in_kb,
+    max_event_batch_size,
+    enable_user_authentication,
+    webhooks,
+    enable_watchlist_events,
+    activity_timeout,
+    max_channels_per_connection,
+    secondary_secret,
+    enable_cache_channels,
+    enable_encrypted_channels,
+    max_api_requests_per_second,
+    max_connections_per_second
+"#;
+
+/// Nullable columns added after the first release, with their definitions.
+/// Databases created by an older version get them on startup.
+const ADDED_COLUMNS: [(&str, &str); 7] = [
+    ("activity_timeout", "INTEGER NULL"),
+    ("max_channels_per_connection", "INTEGER NULL"),
+    ("secondary_secret", "TEXT NULL"),
+    ("enable_cache_channels", "BOOLEAN NULL"),
+    ("enable_encrypted_channels", "BOOLEAN NULL"),
+    ("max_api_requests_per_second", "INTEGER NULL"),
+    ("max_connections_per_second", "INTEGER NULL"),
+];
+
+/// SQLite-based implementation of the AppManager, for single-node deployments
+/// that need apps to survive a restart without running an external database.
+pub struct SqliteAppManager {
+    config: SqliteSettings,
+    pool: SqlitePool,
+    app_cache: Cache<String, App>, // App ID -> App
+    key_cache: Cache<String, App>, // App key -> App
+}
+
+impl SqliteAppManager {
+    /// Create a new SQLite-based AppManager, creating the database file if needed
+    pub async fn new(config: SqliteSettings) -> Result<Self> {
+        info!(
+            "{}",
+            format!(
+                "Initializing SQLite AppManager with database {}",
+                config.path
+            )
+        );
+
+        let options = SqliteConnectOptions::from_str(&format!("sqlite://{}", config.path))
+            .map_err(|e| Error::ConfigurationError(format!("Invalid SQLite path: {}", e)))?
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .busy_timeout(Duration::from_secs(5));
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(config.connection_pool_size.max(1))
+            .acquire_timeout(Duration::from_secs(5))
+            .connect_with(options)
+            .await
+            .map_err(|e| Error::InternalError(format!("Failed to open SQLite database: {}", e)))?;
+
+        let app_cache = Cache::builder()
+            .time_to_live(Duration::from_secs(config.cache_ttl))
+            .max_capacity(config.cache_max_capacity)
+            .build();
+        let key_cache = Cache::builder()
+            .time_to_live(Duration::from_secs(config.cache_ttl))
+            .max_capacity(config.cache_max_capacity)
+            .build();
+
+        let manager = Self {
+            config,
+            pool,
+            app_cache,
+            key_cache,
+        };
+
+        manager.ensure_table_exists().await?;
+
+        Ok(manager)
+    }
+
+    /// Create the applications table if it doesn't exist, and add the columns
+    /// an older version's table lacks
+    async fn ensure_table_exists(&self) -> Result<()> {
+        let query = format!(
+            r#"CREATE TABLE IF NOT EXISTS "{}" (
+                id TEXT PRIMARY KEY,
+                key TEXT UNIQUE NOT NULL,
+                secret TEXT NOT NULL,
+                max_connections INTEGER NOT NULL,
+                enable_client_messages BOOLEAN NOT NULL DEFAULT 0,
+                enabled BOOLEAN NOT NULL DEFAULT 1,
+                max_backend_events_per_second INTEGER NULL,
+                max_client_events_per_second INTEGER NOT NULL,
+                max_read_requests_per_second INTEGER NULL,
+                max_presence_members_per_channel INTEGER NULL,
+                max_presence_member_size_in_kb INTEGER NULL,
+                max_channel_name_length INTEGER NULL,
+                max_event_channels_at_once INTEGER NULL,
+                max_event_name_length INTEGER NULL,
+                max_event_payload_in_kb INTEGER NULL,
+                max_event_batch_size INTEGER NULL,
+                enable_user_authentication BOOLEAN NULL,
+                webhooks TEXT NULL,
+                enable_watchlist_events BOOLEAN NULL,
+                activity_timeout INTEGER NULL,
+                max_channels_per_connection INTEGER NULL,
+                secondary_secret TEXT NULL,
+                enable_cache_channels BOOLEAN NULL,
+                enable_encrypted_channels BOOLEAN NULL,
+                max_api_requests_per_second INTEGER NULL,
+                max_connections_per_second INTEGER NULL,
+                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            )"#,
+            self.config.table_name
+        );
+
+        sqlx::query(&query)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::InternalError(format!("Failed to create SQLite table: {}", e)))?;
+
+        let existing: HashSet<String> =
+            sqlx::query_scalar::<_, String>("SELECT name FROM pragma_table_info(?)")
+                .bind(&self.config.table_name)
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| {
+                    Error::InternalError(format!("Failed to inspect SQLite table: {}", e))
+                })?
+                .into_iter()
+                .collect();
+
+        for (column, definition) in ADDED_COLUMNS {
+            if existing.contains(column) {
+                continue;
+            }
+            warn!(
+                "{}",
+                format!(
+                    "Adding missing column '{}' to SQLite table '{}'",
+                    column, self.config.table_name
+                )
+            );
+            sqlx::query(&format!(
+                r#"ALTER TABLE "{}" ADD COLUMN {} {}"#,
+                self.config.table_name, column, definition
+            ))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                Error::InternalError(format!(
+                    "Failed to add column {} to SQLite table: {}",
+                    column, e
+                ))
+            })?;
+        }
+
+        info!(
+            "{}",
+            format!("Ensured table '{}' exists", self.config.table_name)
+        );
+        Ok(())
+    }
+
+    async fn cache_app(&self, app: &App) {
+        self.app_cache.insert(app.id.clone(), app.clone()).await;
+        self.key_cache.insert(app.key.clone(), app.clone()).await;
+    }
+
+    /// Drop an app from both caches. The key cache can still hold the app
+    /// after its ID entry was evicted, so the key stored in the table is
+    /// removed as well as the one cached by ID.
+    async fn invalidate_app(&self, app_id: &str, stored_key: Option<&str>) {
+        if let Some(app) = self.app_cache.remove(app_id).await {
+            self.key_cache.remove(&app.key).await;
+        }
+        if let Some(key) = stored_key {
+            self.key_cache.remove(key).await;
+        }
+    }
+
+    /// The key an app currently has in the table
+    async fn stored_key(&self, app_id: &str) -> Result<Option<String>> {
+        let query = format!(
+            r#"SELECT key FROM "{}" WHERE id = ?"#,
+            self.config.table_name
+        );
+        sqlx::query_scalar(&query)
+            .bind(app_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| {
+                error!(
+                    "{}",
+                    format!("Database error fetching key of app {}: {}", app_id, e)
+                );
+                Error::InternalError(format!("Failed to fetch app from SQLite: {}", e))
+            })
+    }
+
+    async fn fetch_one(&self, column: &str, value: &str) -> Result<Option<App>> {
+        let query = format!(
+            r#"SELECT {} FROM "{}" WHERE {} = ?"#,
+            APP_COLUMNS, self.config.table_name, column
+        );
+
+        let row = sqlx::query_as::<_, AppRow>(&query)
+            .bind(value)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| {
+                error!(
+                    "{}",
+                    format!("Database error fetching app by {} {}: {}", column, value, e)
+                );
+                Error::InternalError(format!("Failed to fetch app from SQLite: {}", e))
+            })?;
+
+        match row {
+            Some(row) => {
+                let app = row.into_app();
+                self.cache_app(&app).await;
+                Ok(Some(app))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn serialize_webhooks(app: &App) -> Result<Option<String>> {
+        app.webhooks
+            .as_ref()
+            .map(|webhooks| {
+                serde_json::to_string(webhooks).map_err(|e| {
+                    Error::InternalError(format!("Failed to serialize app webhooks: {}", e))
+                })
+            })
+            .transpose()
+    }
+}
+
+/// Row struct for SQLx query results
+#[derive(sqlx::FromRow)]
+struct AppRow {
+    id: String,
+    key: String,
+    secret: String,
+    max_connections: u32,
+    enable_client_messages: bool,
+    enabled: bool,
+    max_backend_events_per_second: Option<u32>,
+    max_client_events_per_second: u32,
+    max_read_requests_per_second: Option<u32>,
+    max_presence_members_per_channel: Option<u32>,
+    max_presence_member_size_in_kb: Option<u32>,
+    max_channel_name_length: Option<u32>,
+    max_event_channels_at_once: Option<u32>,
+    max_event_name_length: Option<u32>,
+    max_event_payload_in_kb: Option<u32>,
+    max_event_batch_size: Option<u32>,
+    enable_user_authentication: Option<bool>,
+    webhooks: Option<String>,
+    enable_watchlist_events: Option<bool>,
+    activity_timeout: Option<u32>,
+    max_channels_per_connection: Option<u32>,
+    secondary_secret: Option<String>,
+    enable_cache_channels: Option<bool>,
+    enable_encrypted_channels: Option<bool>,
+    max_api_requests_per_second: Option<u32>,
+    max_connections_per_second: Option<u32>,
+}
+
+impl AppRow {
+    /// Convert database row to App struct
+    fn into_app(self) -> App {
+        let webhooks = self.webhooks.and_then(|raw| {
+            serde_json::from_str::<Vec<Webhook>>(&raw)
+                .map_err(|e| {
+                    error!(
+                        "{}",
+                        format!("Invalid webhooks JSON stored for app {}: {}", self.id, e)
+                    );
+                })
+                .ok()
+        });
+
+        App {
+            id: self.id,
+            key: self.key,
+            secret: self.secret,
+            max_connections: self.max_connections,
+            enable_client_messages: self.enable_client_messages,
+            enabled: self.enabled,
+            max_backend_events_per_second: self.max_backend_events_per_second,
+            max_client_events_per_second: self.max_client_events_per_second,
+            max_read_requests_per_second: self.max_read_requests_per_second,
+            max_presence_members_per_channel: self.max_presence_members_per_channel,
+            max_presence_member_size_in_kb: self.max_presence_member_size_in_kb,
+            max_channel_name_length: self.max_channel_name_length,
+            max_event_channels_at_once: self.max_event_channels_at_once,
+            max_event_name_length: self.max_event_name_length,
+            max_event_payload_in_kb: self.max_event_payload_in_kb,
+            max_event_batch_size: self.max_event_batch_size,
+            enable_user_authentication: self.enable_user_authentication,
+            webhooks,
+            enable_watchlist_events: self.enable_watchlist_events,
+            activity_timeout: self.activity_timeout,
+            max_channels_per_connection: self.max_channels_per_connection,
+            secondary_secret: self.secondary_secret,
+            enable_cache_channels: self.enable_cache_channels,
+            enable_encrypted_channels: self.enable_encrypted_channels,
+            max_api_requests_per_second: self.max_api_requests_per_second,
+            max_connections_per_second: self.max_connections_per_second,
+        }
+    }
+}
+
+#[async_trait]
+impl AppManager for SqliteAppManager {
+    async fn init(&self) -> Result<()> {
+        // Initialization is done in the constructor
+        Ok(())
+    }
+
+    async fn create_app(&self, app: App) -> Result<()> {
+        info!("{}", format!("Registering new app: {}", app.id));
+        validate_app_webhooks(&app)?;
+
+        let query = format!(
+            r#"INSERT INTO "{}" ({}) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
+            self.config.table_name, APP_COLUMNS
+        );
+        let webhooks = Self::serialize_webhooks(&app)?;
+
+        sqlx::query(&query)
+            .bind(&app.id)
+            .bind(&app.key)
+            .bind(&app.secret)
+            .bind(app.max_connections)
+            .bind(app.enable_client_messages)
+            .bind(app.enabled)
+            .bind(app.max_backend_events_per_second)
+            .bind(app.max_client_events_per_second)
+            .bind(app.max_read_requests_per_second)
+            .bind(app.max_presence_members_per_channel)
+            .bind(app.max_presence_member_size_in_kb)
+            .bind(app.max_channel_name_length)
+            .bind(app.max_event_channels_at_once)
+            .bind(app.max_event_name_length)
+            .bind(app.max_event_payload_in_kb)
+            .bind(app.max_event_batch_size)
+            .bind(app.enable_user_authentication)
+            .bind(webhooks)
+            .bind(app.enable_watchlist_events)
+            .bind(app.activity_timeout)
+            .bind(app.max_channels_per_connection)
+            .bind(&app.secondary_secret)
+            .bind(app.enable_cache_channels)
+            .bind(app.enable_encrypted_channels)
+            .bind(app.max_api_requests_per_second)
+            .bind(app.max_connections_per_second)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                error!(
+                    "{}",
+                    format!("Database error registering app {}: {}", app.id, e)
+                );
+                Error::InternalError(format!("Failed to insert app into SQLite: {}", e))
+            })?;
+
+        self.cache_app(&app).await;
+        Ok(())
+    }
+
+    async fn update_app(&self, app: App) -> Result<()> {
+        info!("{}", format!("Updating app: {}", app.id));
+        validate_app_webhooks(&app)?;
+
+        let query = format!(
+            r#"UPDATE "{}" SET
+                key = ?, secret = ?, max_connections = ?, enable_client_messages = ?, enabled = ?,
+                max_backend_events_per_second = ?, max_client_events_per_second = ?,
+                max_read_requests_per_second = ?, max_presence_members_per_channel = ?,
+                max_presence_member_size_in_kb = ?, max_channel_name_length = ?,
+                max_event_channels_at_once = ?, max_event_name_length = ?,
+                max_event_payload_in_kb = ?, max_event_batch_size = ?, enable_user_authentication = ?,
+                webhooks = ?, enable_watchlist_events = ?, activity_timeout = ?,
+                max_channels_per_connection = ?, secondary_secret = ?,
+                enable_cache_channels = ?, enable_encrypted_channels = ?,
+                max_api_requests_per_second = ?, max_connections_per_second = ?,
+                updated_at = CURRENT_TIMESTAMP
+                WHERE id = ?"#,
+            self.config.table_name
+        );
+        let webhooks = Self::serialize_webhooks(&app)?;
+        let old_key = self.stored_key(&app.id).await?;
+
+        let result = sqlx::query(&query)
+            .bind(&app.key)
+            .bind(&app.secret)
+            .bind(app.max_connections)
+            .bind(app.enable_client_messages)
+            .bind(app.enabled)
+            .bind(app.max_backend_events_per_second)
+            .bind(app.max_client_events_per_second)
+            .bind(app.max_read_requests_per_second)
+            .bind(app.max_presence_members_per_channel)
+            .bind(app.max_presence_member_size_in_kb)
+            .bind(app.max_channel_name_length)
+            .bind(app.max_event_channels_at_once)
+            .bind(app.max_event_name_length)
+            .bind(app.max_event_payload_in_kb)
+            .bind(app.max_event_batch_size)
+            .bind(app.enable_user_authentication)
+            .bind(webhooks)
+            .bind(app.enable_watchlist_events)
+            .bind(app.activity_timeout)
+            .bind(app.max_channels_per_connection)
+            .bind(&app.secondary_secret)
+            .bind(app.enable_cache_channels)
+            .bind(app.enable_encrypted_channels)
+            .bind(app.max_api_requests_per_second)
+            .bind(app.max_connections_per_second)
+            .bind(&app.id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                error!(
+                    "{}",
+                    format!("Database error updating app {}: {}", app.id, e)
+                );
+                Error::InternalError(format!("Failed to update app in SQLite: {}", e))
+            })?;
+
+        if result.rows_affected() == 0 {
+            return Err(Error::InvalidAppKey);
+        }
+
+        // The key may have changed, so drop the old key mapping before re-caching
+        self.invalidate_app(&app.id, old_key.as_deref()).await;
+        self.cache_app(&app).await;
+        Ok(())
+    }
+
+    async fn delete_app(&self, app_id: &str) -> Result<()> {
+        info!("{}", format!("Removing app: {}", app_id));
+
+        let query = format!(r#"DELETE FROM "{}" WHERE id = ?"#, self.config.table_name);
+        let old_key = self.stored_key(app_id).await?;
+
+        let result = sqlx::query(&query)
+            .bind(app_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                error!(
+                    "{}",
+                    format!("Database error removing app {}: {}", app_id, e)
+                );
+                Error::InternalError(format!("Failed to delete app from SQLite: {}", e))
+            })?;
+
+        if result.rows_affected() == 0 {
+            return Err(Error::InvalidAppKey);
+        }
+
+        self.invalidate_app(app_id, old_key.as_deref()).await;
+        Ok(())
+    }
+
+    async fn get_apps(&self) -> Result<Vec<App>> {
+        let query = format!(
+            r#"SELECT {} FROM "{}""#,
+            APP_COLUMNS, self.config.table_name
+        );
+
+        let rows = sqlx::query_as::<_, AppRow>(&query)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| {
+                error!("{}", format!("Database error fetching all apps: {}", e));
+                Error::InternalError(format!("Failed to fetch apps from SQLite: {}", e))
+            })?;
+
+        let mut apps = Vec::with_capacity(rows.len());
+        for row in rows {
+            let app = row.into_app();
+            self.cache_app(&app).await;
+            apps.push(app);
+        }
+        Ok(apps)
+    }
+
+    async fn find_by_id(&self, app_id: &str) -> Result<Option<App>> {
+        if let Some(app) = self.app_cache.get(app_id).await {
+            return Ok(Some(app));
+        }
+        self.fetch_one("id", app_id).await
+    }
+
+    async fn find_by_key(&self, key: &str) -> Result<Option<App>> {
+        if let Some(app) = self.key_cache.get(key).await {
+            return Ok(Some(app));
+        }
+        self.fetch_one("key", key).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_manager(name: &str) -> SqliteAppManager {
+        let path = std::env::temp_dir().join(format!("sockudo_{}_{}.db", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        SqliteAppManager::new(SqliteSettings {
+            path: path.to_string_lossy().into_owned(),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+    }
+
+    fn test_app() -> App {
+        App {
+            id: "app1".to_string(),
+            key: "old_key".to_string(),
+            secret: "secret".to_string(),
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_key_rotation_drops_evicted_key_mapping() {
+        let manager = test_manager("key_rotation").await;
+        manager.create_app(test_app()).await.unwrap();
+        assert!(manager.find_by_key("old_key").await.unwrap().is_some());
+
+        // The ID entry can be evicted while the key entry is still cached
+        manager.app_cache.invalidate("app1").await;
+
+        let mut rotated = test_app();
+        rotated.key = "new_key".to_string();
+        manager.update_app(rotated).await.unwrap();
+
+        assert!(manager.find_by_key("old_key").await.unwrap().is_none());
+        assert!(manager.find_by_key("new_key").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_deleted_app_is_not_found_by_key() {
+        let manager = test_manager("delete").await;
+        manager.create_app(test_app()).await.unwrap();
+        assert!(manager.find_by_key("old_key").await.unwrap().is_some());
+        manager.app_cache.invalidate("app1").await;
+
+        manager.delete_app("app1").await.unwrap();
+
+        assert!(manager.find_by_key("old_key").await.unwrap().is_none());
+    }
+}
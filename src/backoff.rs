@@ -0,0 +1,72 @@
+// src/backoff.rs
+//! Retry advice for clients turned away while the server sheds load. Each
+//! client's delay doubles while it keeps getting shed, and lands at a point
+//! in the upper half of its window picked by hashing the client's identity,
+//! so a crowd of clients dropped at the same moment comes back spread out.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+
+use crate::options::BackoffConfig;
+
+/// Clients tracked before stale entries are pruned
+const MAX_TRACKED_CLIENTS: usize = 100_000;
+
+pub struct BackoffAdvisor {
+    config: BackoffConfig,
+    /// Times each client was shed in a row, and when it last was
+    attempts: DashMap<String, (u32, Instant)>,
+}
+
+impl BackoffAdvisor {
+    pub fn new(config: BackoffConfig) -> Self {
+        Self {
+            config,
+            attempts: DashMap::new(),
+        }
+    }
+
+    /// A client unseen for this long starts over at `base_ms`
+    fn reset_after(&self) -> Duration {
+        Duration::from_millis(self.config.max_ms.saturating_mul(2))
+    }
+
+    /// Record that `client` was shed and return how many milliseconds it
+    /// should wait before retrying
+    pub fn advise(&self, client: &str) -> u64 {
+        let now = Instant::now();
+        if self.attempts.len() >= MAX_TRACKED_CLIENTS {
+            let reset_after = self.reset_after();
+            self.attempts
+                .retain(|_, (_, last)| now.duration_since(*last) < reset_after);
+        }
+
+        let attempt = {
+            let mut entry = self.attempts.entry(client.to_string()).or_insert((0, now));
+            let (attempt, last) = entry.value_mut();
+            if now.duration_since(*last) >= self.reset_after() {
+                *attempt = 0;
+            }
+            *last = now;
+            *attempt = attempt.saturating_add(1);
+            *attempt
+        };
+
+        let max = self.config.max_ms.max(1);
+        let window = self
+            .config
+            .base_ms
+            .max(1)
+            .saturating_mul(1u64 << (attempt - 1).min(32))
+            .min(max);
+
+        let mut hasher = DefaultHasher::new();
+        client.hash(&mut hasher);
+        attempt.hash(&mut hasher);
+        let half = window / 2;
+        window - half + hasher.finish() % (half + 1)
+    }
+}
@@ -7,9 +7,12 @@ use crate::cache::redis_cache_manager::{
 use crate::cache::redis_cluster_cache_manager::{
     RedisClusterCacheConfig, RedisClusterCacheManager,
 };
+use crate::cache::sqlite_cache_manager::SqliteCacheManager;
+use crate::cache::tiered_cache_manager::TieredCacheManager;
 use crate::error::{Error, Result};
+use crate::redis_client::RedisAuth;
 
-use crate::options::{CacheConfig, CacheDriver, MemoryCacheOptions, RedisConnection};
+use crate::options::{CacheConfig, CacheDriver, RedisClusterAdapterConfig, RedisConnection};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use tracing::{error, info};
@@ -20,6 +23,7 @@ impl CacheManagerFactory {
     pub async fn create(
         config: &CacheConfig,
         global_redis_conn_details: &RedisConnection,
+        cluster_adapter: &RedisClusterAdapterConfig,
         debug_enabled: bool,
     ) -> Result<Arc<Mutex<dyn CacheManager + Send + Sync>>> {
         // Corrected return type
@@ -32,31 +36,7 @@ impl CacheManagerFactory {
             CacheDriver::Redis => {
                 if config.redis.cluster_mode {
                     info!("{}", "Cache: Using Redis Cluster driver.".to_string());
-                    if global_redis_conn_details.cluster_nodes.is_empty() {
-                        error!("{}", "Cache: Redis cluster mode enabled, but no cluster_nodes configured in database.redis section.".to_string());
-                        return Err(Error::CacheError(
-                            "Cache: Redis cluster nodes not configured.".to_string(),
-                        ));
-                    }
-                    let nodes: Vec<String> = global_redis_conn_details
-                        .cluster_nodes
-                        .iter()
-                        .map(|node| format!("redis://{}:{}", node.host, node.port))
-                        .collect();
-
-                    let prefix =
-                        config.redis.prefix.clone().unwrap_or_else(|| {
-                            global_redis_conn_details.key_prefix.clone() + "cache:"
-                        });
-
-                    let cluster_cache_config = RedisClusterCacheConfig {
-                        nodes,
-                        prefix,
-                        ..Default::default()
-                    };
-                    let manager = RedisClusterCacheManager::new(cluster_cache_config).await?;
-                    // Directly create Arc<Mutex<ConcreteType>> which coerces to Arc<Mutex<dyn Trait>>
-                    Ok(Arc::new(Mutex::new(manager)))
+                    Self::create_cluster(config, global_redis_conn_details, cluster_adapter).await
                 } else {
                     info!("{}", "Cache: Using standalone Redis driver.".to_string());
                     let redis_url = config.redis.url_override.clone().unwrap_or_else(|| {
@@ -74,10 +54,11 @@ impl CacheManagerFactory {
                     let standalone_redis_cache_config = StandaloneRedisCacheConfig {
                         url: redis_url,
                         prefix,
+                        auth: RedisAuth::from_connection(global_redis_conn_details),
                         ..Default::default()
                     };
                     let manager = RedisCacheManager::new(standalone_redis_cache_config).await?;
-                    Ok(Arc::new(Mutex::new(manager)))
+                    Ok(Self::with_l1(config, manager))
                 }
             }
             CacheDriver::RedisCluster => {
@@ -85,45 +66,22 @@ impl CacheManagerFactory {
                     "{}",
                     "Cache: Using Redis Cluster driver (explicitly selected).".to_string()
                 );
-                if global_redis_conn_details.cluster_nodes.is_empty() {
-                    error!("{}", "Cache: Redis cluster driver selected, but no cluster_nodes configured in database.redis section.".to_string());
-                    return Err(Error::CacheError(
-                        "Cache: Redis cluster nodes not configured for explicit cluster driver."
-                            .to_string(),
-                    ));
-                }
-                let nodes: Vec<String> = global_redis_conn_details
-                    .cluster_nodes
-                    .iter()
-                    .map(|node| format!("redis://{}:{}", node.host, node.port))
-                    .collect();
-
-                let prefix = config
-                    .redis
-                    .prefix
-                    .clone()
-                    .unwrap_or_else(|| global_redis_conn_details.key_prefix.clone() + "cache:");
-
-                let cluster_cache_config = RedisClusterCacheConfig {
-                    nodes,
-                    prefix,
-                    ..Default::default()
-                };
-                let manager = RedisClusterCacheManager::new(cluster_cache_config).await?;
-                Ok(Arc::new(Mutex::new(manager)))
+                Self::create_cluster(config, global_redis_conn_details, cluster_adapter).await
             }
             CacheDriver::Memory => {
                 info!("{}", "Using memory cache manager.".to_string());
-                // Assuming MemoryCacheOptions is the correct config struct for MemoryCacheManager
-                let mem_config = MemoryCacheOptions {
-                    ttl: config.memory.ttl,
-                    cleanup_interval: config.memory.cleanup_interval,
-                    max_capacity: config.memory.max_capacity,
-                };
                 let manager =
                     MemoryCacheManager::new("default_mem_cache".to_string(), config.memory.clone()); // Pass prefix and MemoryCacheOptions
                 Ok(Arc::new(Mutex::new(manager)))
             }
+            CacheDriver::Sqlite => {
+                info!(
+                    "{}",
+                    format!("Using SQLite cache manager at {}", config.sqlite.path)
+                );
+                let manager = SqliteCacheManager::new(config.sqlite.clone()).await?;
+                Ok(Arc::new(Mutex::new(manager)))
+            }
             CacheDriver::None => {
                 info!(
                     "{}",
@@ -135,4 +93,65 @@ impl CacheManagerFactory {
             }
         }
     }
+
+    /// Connect to the same cluster as the Redis Cluster adapter: its
+    /// `adapter.cluster.nodes` and credentials, falling back to
+    /// `database.redis.cluster_nodes` and `database.redis`
+    async fn create_cluster(
+        config: &CacheConfig,
+        global_redis_conn_details: &RedisConnection,
+        cluster_adapter: &RedisClusterAdapterConfig,
+    ) -> Result<Arc<Mutex<dyn CacheManager + Send + Sync>>> {
+        let nodes: Vec<String> = if !cluster_adapter.nodes.is_empty() {
+            cluster_adapter.nodes.clone()
+        } else {
+            global_redis_conn_details
+                .cluster_nodes
+                .iter()
+                .map(|node| format!("redis://{}:{}", node.host, node.port))
+                .collect()
+        };
+        if nodes.is_empty() {
+            error!("{}", "Cache: Redis cluster selected, but neither adapter.cluster.nodes nor database.redis.cluster_nodes is configured.".to_string());
+            return Err(Error::CacheError(
+                "Cache: Redis cluster nodes not configured.".to_string(),
+            ));
+        }
+
+        let prefix = config
+            .redis
+            .prefix
+            .clone()
+            .unwrap_or_else(|| global_redis_conn_details.key_prefix.clone() + "cache:");
+
+        let cluster_cache_config = RedisClusterCacheConfig {
+            nodes,
+            prefix,
+            auth: RedisAuth::from_cluster_adapter(cluster_adapter, global_redis_conn_details),
+            ..Default::default()
+        };
+        let manager = RedisClusterCacheManager::new(cluster_cache_config).await?;
+        Ok(Self::with_l1(config, manager))
+    }
+
+    /// Put the in-process tier in front of a Redis cache when `cache.l1` is enabled
+    fn with_l1<M>(config: &CacheConfig, manager: M) -> Arc<Mutex<dyn CacheManager + Send + Sync>>
+    where
+        M: CacheManager + Send + Sync + 'static,
+    {
+        if !config.l1.enabled {
+            return Arc::new(Mutex::new(manager));
+        }
+        info!(
+            "{}",
+            format!(
+                "Cache: L1 enabled with {} ms TTL and {} entries",
+                config.l1.ttl_ms, config.l1.max_capacity
+            )
+        );
+        Arc::new(Mutex::new(TieredCacheManager::new(
+            Box::new(manager),
+            &config.l1,
+        )))
+    }
 }
@@ -0,0 +1,108 @@
+// src/cache/instrumented_cache_manager.rs
+//! Reports the activity of whichever cache manager is configured to the
+//! metrics driver: hits and misses of `get` and `has`, the duration of every
+//! operation, and the entries the cache evicted to stay within its capacity.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use tokio::sync::Mutex;
+
+use crate::cache::manager::CacheManager;
+use crate::error::Result;
+use crate::metrics::MetricsInterface;
+
+pub struct InstrumentedCacheManager {
+    inner: Arc<Mutex<dyn CacheManager + Send + Sync>>,
+    metrics: Arc<Mutex<dyn MetricsInterface + Send + Sync>>,
+    /// Evictions of the inner cache already reported
+    reported_evictions: u64,
+}
+
+impl InstrumentedCacheManager {
+    pub fn new(
+        inner: Arc<Mutex<dyn CacheManager + Send + Sync>>,
+        metrics: Arc<Mutex<dyn MetricsInterface + Send + Sync>>,
+    ) -> Self {
+        Self {
+            inner,
+            metrics,
+            reported_evictions: 0,
+        }
+    }
+
+    /// Record an operation, with its hit or miss when it was a lookup, and
+    /// any evictions since the last one
+    async fn record(&mut self, operation: &str, started: Instant, hit: Option<bool>) {
+        let evictions = self.inner.lock().await.evictions();
+        let evicted = evictions.saturating_sub(self.reported_evictions);
+        self.reported_evictions = evictions;
+
+        let metrics = self.metrics.lock().await;
+        metrics.track_cache_operation_duration(operation, started.elapsed().as_secs_f64() * 1000.0);
+        if let Some(hit) = hit {
+            metrics.mark_cache_lookup(hit);
+        }
+        if evicted > 0 {
+            metrics.mark_cache_evictions(evicted);
+        }
+    }
+}
+
+#[async_trait]
+impl CacheManager for InstrumentedCacheManager {
+    async fn has(&mut self, key: &str) -> Result<bool> {
+        let started = Instant::now();
+        let result = self.inner.lock().await.has(key).await;
+        self.record("has", started, result.as_ref().ok().copied())
+            .await;
+        result
+    }
+
+    async fn get(&mut self, key: &str) -> Result<Option<String>> {
+        let started = Instant::now();
+        let result = self.inner.lock().await.get(key).await;
+        let hit = result.as_ref().ok().map(Option::is_some);
+        self.record("get", started, hit).await;
+        result
+    }
+
+    async fn set(&mut self, key: &str, value: &str, ttl_seconds: u64) -> Result<()> {
+        let started = Instant::now();
+        let result = self.inner.lock().await.set(key, value, ttl_seconds).await;
+        self.record("set", started, None).await;
+        result
+    }
+
+    async fn remove(&mut self, key: &str) -> Result<()> {
+        let started = Instant::now();
+        let result = self.inner.lock().await.remove(key).await;
+        self.record("remove", started, None).await;
+        result
+    }
+
+    async fn delete_by_prefix(&mut self, prefix: &str) -> Result<usize> {
+        self.inner.lock().await.delete_by_prefix(prefix).await
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        self.inner.lock().await.disconnect().await
+    }
+
+    async fn is_healthy(&self) -> Result<bool> {
+        self.inner.lock().await.is_healthy().await
+    }
+
+    async fn is_persistent(&self) -> bool {
+        self.inner.lock().await.is_persistent().await
+    }
+
+    async fn ttl(&mut self, key: &str) -> Result<Option<Duration>> {
+        self.inner.lock().await.ttl(key).await
+    }
+
+    fn evictions(&self) -> u64 {
+        self.reported_evictions
+    }
+}
@@ -15,11 +15,58 @@ pub trait CacheManager: Send + Sync {
     /// Set or overwrite the value in the cache
     async fn set(&mut self, key: &str, value: &str, ttl_seconds: u64) -> Result<()>;
 
+    /// Remove a key from the cache. Removing a missing key is not an error.
+    async fn remove(&mut self, key: &str) -> Result<()>;
+
+    /// Remove every key starting with `prefix`, returning how many were removed
+    async fn delete_by_prefix(&mut self, prefix: &str) -> Result<usize>;
+
+    /// Remove the cached channel entries of an app, such as cache-channel
+    /// payloads. Records kept under the app, such as user bans, are left alone.
+    async fn clear_namespace(&mut self, app_id: &str) -> Result<usize> {
+        self.delete_by_prefix(&format!("app:{}:channel:", app_id))
+            .await
+    }
+
     /// Disconnect the manager's made connections
     async fn disconnect(&mut self) -> Result<()>;
 
     async fn is_healthy(&self) -> Result<bool> {
         Ok(true)
     }
+
+    /// Whether entries outlive a restart and are shared by every node, so
+    /// records such as user bans can be kept in the cache
+    async fn is_persistent(&self) -> bool {
+        false
+    }
     async fn ttl(&mut self, key: &str) -> Result<Option<Duration>>;
+
+    /// Entries evicted so far to stay within capacity. Caches that don't
+    /// evict, or don't know, report 0.
+    fn evictions(&self) -> u64 {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::ConnectionHandler;
+    use crate::cache::memory_cache_manager::MemoryCacheManager;
+    use crate::options::MemoryCacheOptions;
+
+    #[tokio::test]
+    async fn test_clear_namespace_keeps_bans() {
+        let mut cache = MemoryCacheManager::new("test".to_string(), MemoryCacheOptions::default());
+        let ban = ConnectionHandler::user_ban_key("app-1", "user-1");
+        cache.set(&ban, "{}", 60).await.unwrap();
+        cache
+            .set("app:app-1:channel:cache-prices:cache_miss", "{}", 60)
+            .await
+            .unwrap();
+
+        assert_eq!(cache.clear_namespace("app-1").await.unwrap(), 1);
+        assert!(cache.has(&ban).await.unwrap());
+    }
 }
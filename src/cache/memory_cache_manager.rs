@@ -2,10 +2,15 @@
 
 use crate::cache::manager::CacheManager;
 use crate::error::Result; // Assuming your project's Error/Result types
-use crate::options::MemoryCacheOptions; // Using the type-safe options
+use crate::options::{MemoryCacheOptions, MemoryEvictionPolicy}; // Using the type-safe options
 use async_trait::async_trait;
 use moka::future::Cache;
+use moka::notification::RemovalCause;
+use moka::policy::EvictionPolicy;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
+use tokio::task::AbortHandle;
 // std::sync::Arc and Mutex are not directly needed in this struct if CacheManager trait doesn't require them for self
 
 /// A Memory-based implementation of the CacheManager trait using Moka.
@@ -17,16 +22,52 @@ pub struct MemoryCacheManager {
     options: MemoryCacheOptions,
     /// Prefix for all keys in this cache instance.
     prefix: String,
+    /// Entries evicted because the cache was full
+    evictions: Arc<AtomicU64>,
+    /// Stops the expired-entry sweeper once the last clone is dropped
+    _sweeper: Option<Arc<Sweeper>>,
+}
+
+struct Sweeper(AbortHandle);
+
+impl Drop for Sweeper {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
 }
 
 impl MemoryCacheManager {
     /// Creates a new Memory cache manager with Moka configuration.
     pub fn new(prefix: String, options: MemoryCacheOptions) -> Self {
-        let cache_builder = Cache::builder()
-            .max_capacity(options.max_capacity)
-            // Moka's cleanup is internal and efficient, so options.cleanup_interval is not directly used here.
+        let evictions = Arc::new(AtomicU64::new(0));
+        let evicted = evictions.clone();
+        let mut cache_builder = Cache::builder()
+            .eviction_policy(match options.eviction_policy {
+                MemoryEvictionPolicy::Lru => EvictionPolicy::lru(),
+                MemoryEvictionPolicy::Lfu => EvictionPolicy::tiny_lfu(),
+            })
+            .eviction_listener(move |_key, _value, cause| {
+                if cause == RemovalCause::Size {
+                    evicted.fetch_add(1, Ordering::Relaxed);
+                }
+            })
             .name(format!("sockudo-memory-cache-{}", prefix).as_str()); // Optional: name the cache for monitoring
 
+        cache_builder = if options.max_bytes > 0 {
+            // Moka enforces a single capacity. Weighing each entry at least its
+            // share of max_bytes keeps the total within max_bytes and the count
+            // within max_capacity.
+            let min_weight = (options.max_bytes / options.max_capacity.max(1)).max(1);
+            cache_builder
+                .weigher(move |key: &String, value: &String| {
+                    let bytes = (key.len() + value.len()) as u64;
+                    u32::try_from(bytes.max(min_weight)).unwrap_or(u32::MAX)
+                })
+                .max_capacity(options.max_bytes)
+        } else {
+            cache_builder.max_capacity(options.max_capacity)
+        };
+
         // Set default time_to_live if options.ttl > 0
         let cache = if options.ttl > 0 {
             cache_builder
@@ -38,10 +79,29 @@ impl MemoryCacheManager {
             cache_builder.build()
         };
 
+        // Expired entries otherwise linger until the cache is next used
+        let sweeper = (options.cleanup_interval > 0)
+            .then(tokio::runtime::Handle::try_current)
+            .and_then(|handle| handle.ok())
+            .map(|handle| {
+                let cache = cache.clone();
+                let interval = Duration::from_secs(options.cleanup_interval);
+                let task = handle.spawn(async move {
+                    let mut ticker = tokio::time::interval(interval);
+                    loop {
+                        ticker.tick().await;
+                        cache.run_pending_tasks().await;
+                    }
+                });
+                Arc::new(Sweeper(task.abort_handle()))
+            });
+
         Self {
             cache,
             options,
             prefix,
+            evictions,
+            _sweeper: sweeper,
         }
     }
 
@@ -86,6 +146,25 @@ impl CacheManager for MemoryCacheManager {
         Ok(())
     }
 
+    async fn remove(&mut self, key: &str) -> Result<()> {
+        self.cache.invalidate(&self.prefixed_key(key)).await;
+        Ok(())
+    }
+
+    async fn delete_by_prefix(&mut self, prefix: &str) -> Result<usize> {
+        let prefixed = self.prefixed_key(prefix);
+        let keys: Vec<_> = self
+            .cache
+            .iter()
+            .filter(|(key, _)| key.starts_with(&prefixed))
+            .map(|(key, _)| key)
+            .collect();
+        for key in &keys {
+            self.cache.invalidate(key.as_str()).await;
+        }
+        Ok(keys.len())
+    }
+
     async fn disconnect(&mut self) -> Result<()> {
         // Moka's cache is in-memory and managed by RAII.
         // "Disconnecting" can mean clearing all entries.
@@ -114,6 +193,10 @@ impl CacheManager for MemoryCacheManager {
             Ok(None) // Key does not exist
         }
     }
+
+    fn evictions(&self) -> u64 {
+        self.evictions.load(Ordering::Relaxed)
+    }
 }
 
 impl MemoryCacheManager {
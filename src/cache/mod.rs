@@ -1,5 +1,8 @@
 pub(crate) mod factory;
+pub mod instrumented_cache_manager;
 pub mod manager;
 pub mod memory_cache_manager;
 pub mod redis_cache_manager;
 pub mod redis_cluster_cache_manager;
+pub mod sqlite_cache_manager;
+pub mod tiered_cache_manager;
@@ -1,5 +1,6 @@
 use crate::cache::manager::CacheManager;
 use crate::error::{Error, Result};
+use crate::redis_client::{RedisAuth, RedisDegradedMode};
 use async_trait::async_trait;
 use redis::{AsyncCommands, Client, aio::MultiplexedConnection};
 use std::time::Duration;
@@ -15,6 +16,8 @@ pub struct RedisCacheConfig {
     pub response_timeout: Option<Duration>,
     /// Use RESP3 protocol
     pub use_resp3: bool,
+    /// ACL credentials and TLS settings
+    pub auth: RedisAuth,
 }
 
 impl Default for RedisCacheConfig {
@@ -24,6 +27,7 @@ impl Default for RedisCacheConfig {
             prefix: "cache".to_string(),
             response_timeout: Some(Duration::from_secs(5)),
             use_resp3: false,
+            auth: RedisAuth::default(),
         }
     }
 }
@@ -36,6 +40,8 @@ pub struct RedisCacheManager {
     connection: MultiplexedConnection,
     /// Key prefix
     prefix: String,
+    /// Set while Redis refuses writes (OOM/READONLY); cache writes are skipped
+    degraded: RedisDegradedMode,
 }
 
 impl RedisCacheManager {
@@ -53,7 +59,9 @@ impl RedisCacheManager {
         };
 
         // Create Redis client
-        let client = Client::open(redis_url)
+        let client = config
+            .auth
+            .build_client(&redis_url)
             .map_err(|e| Error::CacheError(format!("Failed to create Redis client: {}", e)))?;
 
         // Get multiplexed connection for better performance
@@ -71,6 +79,7 @@ impl RedisCacheManager {
             client,
             connection,
             prefix: config.prefix,
+            degraded: RedisDegradedMode::new("the Redis cache", "uncached lookups"),
         })
     }
 
@@ -91,6 +100,18 @@ impl RedisCacheManager {
     }
 }
 
+/// Escape the glob characters of a key prefix for a `MATCH` or `KEYS` pattern
+pub(crate) fn glob_escape(prefix: &str) -> String {
+    let mut escaped = String::with_capacity(prefix.len());
+    for c in prefix.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
 #[async_trait]
 impl CacheManager for RedisCacheManager {
     /// Check if the given key exists in cache
@@ -118,23 +139,69 @@ impl CacheManager for RedisCacheManager {
     async fn set(&mut self, key: &str, value: &str, ttl_seconds: u64) -> Result<()> {
         let prefixed_key = self.prefixed_key(key);
 
-        if ttl_seconds > 0 {
+        // Callers fall back to the source of truth on a miss, so skipping
+        // writes is safe while Redis is out of memory or read-only
+        if self.degraded.is_active() {
+            return Ok(());
+        }
+
+        let result = if ttl_seconds > 0 {
             // Set with expiration
             self.connection
                 .set_ex::<_, _, ()>(prefixed_key, value, ttl_seconds)
                 .await
-                .map_err(|e| Error::CacheError(format!("Redis set error: {}", e)))?;
         } else {
             // Set without expiration
-            self.connection
-                .set::<_, _, ()>(prefixed_key, value)
-                .await
-                .map_err(|e| Error::CacheError(format!("Redis set error: {}", e)))?;
+            self.connection.set::<_, _, ()>(prefixed_key, value).await
+        };
+
+        match result {
+            Ok(()) => self.degraded.record_success(),
+            Err(e) if self.degraded.record_error(&e) => {}
+            Err(e) => return Err(Error::CacheError(format!("Redis set error: {}", e))),
         }
 
         Ok(())
     }
 
+    async fn remove(&mut self, key: &str) -> Result<()> {
+        self.connection
+            .del::<_, ()>(self.prefixed_key(key))
+            .await
+            .map_err(|e| Error::CacheError(format!("Redis del error: {}", e)))
+    }
+
+    /// SCAN for the matching keys and delete each batch, so Redis isn't
+    /// blocked as it would be by KEYS
+    async fn delete_by_prefix(&mut self, prefix: &str) -> Result<usize> {
+        let pattern = format!("{}*", glob_escape(&self.prefixed_key(prefix)));
+        let mut cursor: u64 = 0;
+        let mut deleted = 0;
+        loop {
+            let (next, keys): (u64, Vec<String>) = redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(&pattern)
+                .arg("COUNT")
+                .arg(500)
+                .query_async(&mut self.connection)
+                .await
+                .map_err(|e| Error::CacheError(format!("Redis scan error: {}", e)))?;
+            if !keys.is_empty() {
+                let removed: usize = self
+                    .connection
+                    .del(keys)
+                    .await
+                    .map_err(|e| Error::CacheError(format!("Redis del error: {}", e)))?;
+                deleted += removed;
+            }
+            if next == 0 {
+                return Ok(deleted);
+            }
+            cursor = next;
+        }
+    }
+
     /// Disconnect the manager's made connections
     async fn disconnect(&mut self) -> Result<()> {
         // delete all keys with the current prefix
@@ -160,6 +227,10 @@ impl CacheManager for RedisCacheManager {
             Err(_) => Ok(false),
         }
     }
+    async fn is_persistent(&self) -> bool {
+        true
+    }
+
     async fn ttl(&mut self, key: &str) -> Result<Option<Duration>> {
         let ttl: i64 = self
             .connection
@@ -305,6 +376,7 @@ impl CacheManagerFactory {
             prefix: prefix.unwrap_or("cache").to_string(),
             response_timeout,
             use_resp3: false,
+            ..Default::default()
         };
 
         let cache_manager = RedisCacheManager::new(config).await?;
@@ -322,6 +394,7 @@ impl CacheManagerFactory {
             prefix: prefix.unwrap_or("cache").to_string(),
             response_timeout,
             use_resp3: true,
+            ..Default::default()
         };
 
         let cache_manager = RedisCacheManager::new(config).await?;
@@ -1,8 +1,10 @@
 use crate::cache::manager::CacheManager;
+use crate::cache::redis_cache_manager::glob_escape;
 use crate::error::{Error, Result};
+use crate::redis_client::{RedisAuth, RedisDegradedMode};
 use async_trait::async_trait;
 use redis::AsyncCommands;
-use redis::cluster::{ClusterClient, ClusterClientBuilder};
+use redis::cluster::ClusterClient;
 use redis::cluster_async::ClusterConnection;
 use std::time::Duration;
 
@@ -17,6 +19,8 @@ pub struct RedisClusterCacheConfig {
     pub response_timeout: Option<Duration>,
     /// Read from replicas (if supported)
     pub read_from_replicas: bool,
+    /// ACL credentials and TLS settings
+    pub auth: RedisAuth,
 }
 
 impl Default for RedisClusterCacheConfig {
@@ -26,6 +30,7 @@ impl Default for RedisClusterCacheConfig {
             prefix: "cache".to_string(),
             response_timeout: Some(Duration::from_secs(5)),
             read_from_replicas: false,
+            auth: RedisAuth::default(),
         }
     }
 }
@@ -38,13 +43,15 @@ pub struct RedisClusterCacheManager {
     connection: ClusterConnection,
     /// Key prefix
     prefix: String,
+    /// Set while Redis refuses writes (OOM/READONLY); cache writes are skipped
+    degraded: RedisDegradedMode,
 }
 
 impl RedisClusterCacheManager {
     /// Creates a new Redis Cluster cache manager with configuration
     pub async fn new(config: RedisClusterCacheConfig) -> Result<Self> {
         // Create Redis cluster client builder
-        let mut builder = ClusterClientBuilder::new(config.nodes.clone());
+        let mut builder = config.auth.cluster_client_builder(&config.nodes)?;
         if let Some(timeout) = config.response_timeout {
             // Note: This is a no-op in current redis-rs version for cluster connections
             // but kept for future compatibility
@@ -71,6 +78,7 @@ impl RedisClusterCacheManager {
             client,
             connection,
             prefix: config.prefix,
+            degraded: RedisDegradedMode::new("the Redis cluster cache", "uncached lookups"),
         })
     }
 
@@ -118,23 +126,61 @@ impl CacheManager for RedisClusterCacheManager {
     async fn set(&mut self, key: &str, value: &str, ttl_seconds: u64) -> Result<()> {
         let prefixed_key = self.prefixed_key(key);
 
-        if ttl_seconds > 0 {
+        // Callers fall back to the source of truth on a miss, so skipping
+        // writes is safe while Redis is out of memory or read-only
+        if self.degraded.is_active() {
+            return Ok(());
+        }
+
+        let result = if ttl_seconds > 0 {
             // Set with expiration
             self.connection
                 .set_ex::<_, _, ()>(prefixed_key, value, ttl_seconds)
                 .await
-                .map_err(|e| Error::CacheError(format!("Redis Cluster set error: {}", e)))?;
         } else {
             // Set without expiration
-            self.connection
-                .set::<_, _, ()>(prefixed_key, value)
-                .await
-                .map_err(|e| Error::CacheError(format!("Redis Cluster set error: {}", e)))?;
+            self.connection.set::<_, _, ()>(prefixed_key, value).await
+        };
+
+        match result {
+            Ok(()) => self.degraded.record_success(),
+            Err(e) if self.degraded.record_error(&e) => {}
+            Err(e) => return Err(Error::CacheError(format!("Redis Cluster set error: {}", e))),
         }
 
         Ok(())
     }
 
+    async fn remove(&mut self, key: &str) -> Result<()> {
+        self.connection
+            .del::<_, ()>(self.prefixed_key(key))
+            .await
+            .map_err(|e| Error::CacheError(format!("Redis Cluster del error: {}", e)))
+    }
+
+    /// SCAN runs per node and the cluster client doesn't fan it out, so the
+    /// keys are found with KEYS, which it sends to every master
+    async fn delete_by_prefix(&mut self, prefix: &str) -> Result<usize> {
+        let pattern = format!("{}*", glob_escape(&self.prefixed_key(prefix)));
+        let keys: Vec<String> = redis::cmd("KEYS")
+            .arg(&pattern)
+            .query_async(&mut self.connection)
+            .await
+            .map_err(|e| Error::CacheError(format!("Redis Cluster keys error: {}", e)))?;
+
+        // Keys of one app hash to different slots, so delete them one by one
+        let mut deleted = 0;
+        for key in keys {
+            let removed: usize = self
+                .connection
+                .del(&key)
+                .await
+                .map_err(|e| Error::CacheError(format!("Redis Cluster delete error: {}", e)))?;
+            deleted += removed;
+        }
+        Ok(deleted)
+    }
+
     /// Disconnect the manager's made connections
     async fn disconnect(&mut self) -> Result<()> {
         // lcear all the cache
@@ -159,6 +205,10 @@ impl CacheManager for RedisClusterCacheManager {
         }
     }
 
+    async fn is_persistent(&self) -> bool {
+        true
+    }
+
     async fn ttl(&mut self, key: &str) -> Result<Option<Duration>> {
         let ttl: i64 = self
             .connection
@@ -329,6 +379,7 @@ impl ClusterCacheManagerFactory {
             prefix: prefix.unwrap_or("cache").to_string(),
             response_timeout,
             read_from_replicas,
+            ..Default::default()
         };
 
         let cache_manager = RedisClusterCacheManager::new(config).await?;
@@ -0,0 +1,220 @@
+use crate::cache::manager::CacheManager;
+use crate::error::{Error, Result};
+use crate::options::SqliteCacheOptions;
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A SQLite-based implementation of the CacheManager trait.
+/// Entries survive restarts, which keeps cache channels warm on single-node deployments.
+pub struct SqliteCacheManager {
+    pool: SqlitePool,
+    table_name: String,
+    prefix: String,
+}
+
+impl SqliteCacheManager {
+    /// Creates a new SQLite cache manager, creating the database file if needed
+    pub async fn new(options: SqliteCacheOptions) -> Result<Self> {
+        let connect_options = SqliteConnectOptions::from_str(&format!("sqlite://{}", options.path))
+            .map_err(|e| Error::CacheError(format!("Invalid SQLite path: {}", e)))?
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .busy_timeout(Duration::from_secs(5));
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(5)
+            .connect_with(connect_options)
+            .await
+            .map_err(|e| Error::CacheError(format!("Failed to open SQLite cache: {}", e)))?;
+
+        let manager = Self {
+            pool,
+            table_name: options.table_name,
+            prefix: Self::key_prefix(&options.prefix),
+        };
+        manager.ensure_table_exists().await?;
+
+        Ok(manager)
+    }
+
+    async fn ensure_table_exists(&self) -> Result<()> {
+        let query = format!(
+            r#"CREATE TABLE IF NOT EXISTS "{}" (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL,
+                expires_at INTEGER NULL
+            )"#,
+            self.table_name
+        );
+        sqlx::query(&query).execute(&self.pool).await.map_err(|e| {
+            Error::CacheError(format!("Failed to create SQLite cache table: {}", e))
+        })?;
+        Ok(())
+    }
+
+    /// The configured prefix and its ':' separator, which a prefix written
+    /// as `sockudo_cache:` already includes
+    fn key_prefix(prefix: &str) -> String {
+        format!("{}:", prefix.trim_end_matches(':'))
+    }
+
+    /// Get the prefixed key
+    fn prefixed_key(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+
+    fn now_secs() -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0)
+    }
+
+    /// Delete a key from the cache
+    pub async fn delete(&mut self, key: &str) -> Result<bool> {
+        let query = format!(r#"DELETE FROM "{}" WHERE key = ?"#, self.table_name);
+        let result = sqlx::query(&query)
+            .bind(self.prefixed_key(key))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::CacheError(format!("SQLite delete error: {}", e)))?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Remove every expired entry
+    pub async fn purge_expired(&mut self) -> Result<u64> {
+        let query = format!(
+            r#"DELETE FROM "{}" WHERE expires_at IS NOT NULL AND expires_at <= ?"#,
+            self.table_name
+        );
+        let result = sqlx::query(&query)
+            .bind(Self::now_secs())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::CacheError(format!("SQLite purge error: {}", e)))?;
+        Ok(result.rows_affected())
+    }
+}
+
+#[async_trait]
+impl CacheManager for SqliteCacheManager {
+    async fn has(&mut self, key: &str) -> Result<bool> {
+        Ok(self.get(key).await?.is_some())
+    }
+
+    async fn get(&mut self, key: &str) -> Result<Option<String>> {
+        let query = format!(
+            r#"SELECT value FROM "{}" WHERE key = ? AND (expires_at IS NULL OR expires_at > ?)"#,
+            self.table_name
+        );
+        let value: Option<String> = sqlx::query_scalar(&query)
+            .bind(self.prefixed_key(key))
+            .bind(Self::now_secs())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Error::CacheError(format!("SQLite get error: {}", e)))?;
+        Ok(value)
+    }
+
+    async fn set(&mut self, key: &str, value: &str, ttl_seconds: u64) -> Result<()> {
+        let expires_at = if ttl_seconds > 0 {
+            Some(Self::now_secs() + ttl_seconds as i64)
+        } else {
+            None
+        };
+        let query = format!(
+            r#"INSERT INTO "{}" (key, value, expires_at) VALUES (?, ?, ?)
+               ON CONFLICT(key) DO UPDATE SET value = excluded.value, expires_at = excluded.expires_at"#,
+            self.table_name
+        );
+        sqlx::query(&query)
+            .bind(self.prefixed_key(key))
+            .bind(value)
+            .bind(expires_at)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::CacheError(format!("SQLite set error: {}", e)))?;
+        Ok(())
+    }
+
+    async fn remove(&mut self, key: &str) -> Result<()> {
+        let query = format!(r#"DELETE FROM "{}" WHERE key = ?"#, self.table_name);
+        sqlx::query(&query)
+            .bind(self.prefixed_key(key))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::CacheError(format!("SQLite delete error: {}", e)))?;
+        Ok(())
+    }
+
+    async fn delete_by_prefix(&mut self, prefix: &str) -> Result<usize> {
+        // substr rather than LIKE, so `_` and `%` in keys need no escaping
+        let query = format!(
+            r#"DELETE FROM "{}" WHERE substr(key, 1, length(?1)) = ?1"#,
+            self.table_name
+        );
+        let result = sqlx::query(&query)
+            .bind(self.prefixed_key(prefix))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| Error::CacheError(format!("SQLite delete error: {}", e)))?;
+        Ok(result.rows_affected() as usize)
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        self.purge_expired().await?;
+        self.pool.close().await;
+        Ok(())
+    }
+
+    async fn is_healthy(&self) -> Result<bool> {
+        Ok(sqlx::query("SELECT 1").execute(&self.pool).await.is_ok())
+    }
+
+    /// Entries outlive restarts, though only this node reads the file
+    async fn is_persistent(&self) -> bool {
+        true
+    }
+
+    async fn ttl(&mut self, key: &str) -> Result<Option<Duration>> {
+        let query = format!(
+            r#"SELECT expires_at FROM "{}" WHERE key = ?"#,
+            self.table_name
+        );
+        let expires_at: Option<Option<i64>> = sqlx::query_scalar(&query)
+            .bind(self.prefixed_key(key))
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| Error::CacheError(format!("SQLite TTL error: {}", e)))?;
+
+        let remaining = expires_at.flatten().map(|at| at - Self::now_secs());
+        match remaining {
+            Some(secs) if secs > 0 => Ok(Some(Duration::from_secs(secs as u64))),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_prefix_has_one_separator() {
+        assert_eq!(
+            SqliteCacheManager::key_prefix("sockudo_cache"),
+            "sockudo_cache:"
+        );
+        assert_eq!(
+            SqliteCacheManager::key_prefix("sockudo_cache:"),
+            "sockudo_cache:"
+        );
+        assert_eq!(
+            SqliteCacheManager::key_prefix("sockudo_cache::"),
+            "sockudo_cache:"
+        );
+    }
+}
@@ -0,0 +1,223 @@
+// src/cache/tiered_cache_manager.rs
+//! A bounded in-process cache (L1) in front of a shared cache such as Redis
+//! (L2). Reads are served from L1 while its entry is fresh and fall through
+//! to L2 otherwise, copying what they find back into L1. Writes and removals
+//! go to both tiers.
+//!
+//! L1 is local to the node, so a key changed or removed by another node can
+//! be read stale here for up to `cache.l1.ttl_ms`. That includes keys removed
+//! with `delete_by_prefix`, which clears only this node's L1.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use moka::future::Cache;
+use moka::notification::RemovalCause;
+
+use crate::cache::manager::CacheManager;
+use crate::error::Result;
+use crate::options::L1CacheOptions;
+
+#[derive(Clone)]
+struct L1Entry {
+    value: String,
+    /// Never later than the entry's expiry in L2, when that is known
+    expires_at: Instant,
+}
+
+pub struct TieredCacheManager {
+    l1: Cache<String, L1Entry>,
+    l1_ttl: Duration,
+    l2: Box<dyn CacheManager + Send + Sync>,
+    /// Entries evicted from L1 because it was full
+    l1_evictions: Arc<AtomicU64>,
+}
+
+impl TieredCacheManager {
+    pub fn new(l2: Box<dyn CacheManager + Send + Sync>, options: &L1CacheOptions) -> Self {
+        let l1_ttl = Duration::from_millis(options.ttl_ms.max(1));
+        let l1_evictions = Arc::new(AtomicU64::new(0));
+        let evicted = l1_evictions.clone();
+        Self {
+            l1: Cache::builder()
+                .max_capacity(options.max_capacity)
+                .time_to_live(l1_ttl)
+                .eviction_listener(move |_key, _value, cause| {
+                    if cause == RemovalCause::Size {
+                        evicted.fetch_add(1, Ordering::Relaxed);
+                    }
+                })
+                .name("sockudo-l1-cache")
+                .build(),
+            l1_ttl,
+            l2,
+            l1_evictions,
+        }
+    }
+
+    async fn l1_get(&self, key: &str) -> Option<String> {
+        let entry = self.l1.get(key).await?;
+        if entry.expires_at > Instant::now() {
+            Some(entry.value)
+        } else {
+            self.l1.invalidate(key).await;
+            None
+        }
+    }
+
+    async fn l1_insert(&self, key: &str, value: &str, ttl: Duration) {
+        let entry = L1Entry {
+            value: value.to_string(),
+            expires_at: Instant::now() + ttl.min(self.l1_ttl),
+        };
+        self.l1.insert(key.to_string(), entry).await;
+    }
+}
+
+#[async_trait]
+impl CacheManager for TieredCacheManager {
+    async fn has(&mut self, key: &str) -> Result<bool> {
+        if self.l1_get(key).await.is_some() {
+            return Ok(true);
+        }
+        self.l2.has(key).await
+    }
+
+    async fn get(&mut self, key: &str) -> Result<Option<String>> {
+        if let Some(value) = self.l1_get(key).await {
+            return Ok(Some(value));
+        }
+        let value = self.l2.get(key).await?;
+        if let Some(value) = &value {
+            // Kept no longer than L2 keeps it, so an expired entry isn't
+            // served from L1. When L2 can't tell, it isn't kept at all.
+            match self.l2.ttl(key).await {
+                Ok(Some(remaining)) => self.l1_insert(key, value, remaining).await,
+                Ok(None) => self.l1_insert(key, value, self.l1_ttl).await,
+                Err(_) => {}
+            }
+        }
+        Ok(value)
+    }
+
+    async fn set(&mut self, key: &str, value: &str, ttl_seconds: u64) -> Result<()> {
+        // Drop the L1 copy first, so a failed write doesn't leave it stale
+        self.l1.invalidate(key).await;
+        self.l2.set(key, value, ttl_seconds).await?;
+        let ttl = if ttl_seconds > 0 {
+            Duration::from_secs(ttl_seconds)
+        } else {
+            self.l1_ttl
+        };
+        self.l1_insert(key, value, ttl).await;
+        Ok(())
+    }
+
+    async fn remove(&mut self, key: &str) -> Result<()> {
+        self.l1.invalidate(key).await;
+        self.l2.remove(key).await
+    }
+
+    /// Other nodes' L1 copies of the removed keys are left to expire
+    async fn delete_by_prefix(&mut self, prefix: &str) -> Result<usize> {
+        let keys: Vec<_> = self
+            .l1
+            .iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .map(|(key, _)| key)
+            .collect();
+        for key in keys {
+            self.l1.invalidate(key.as_str()).await;
+        }
+        self.l2.delete_by_prefix(prefix).await
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        self.l1.invalidate_all();
+        self.l2.disconnect().await
+    }
+
+    async fn is_healthy(&self) -> Result<bool> {
+        self.l2.is_healthy().await
+    }
+
+    async fn is_persistent(&self) -> bool {
+        self.l2.is_persistent().await
+    }
+
+    async fn ttl(&mut self, key: &str) -> Result<Option<Duration>> {
+        self.l2.ttl(key).await
+    }
+
+    fn evictions(&self) -> u64 {
+        self.l1_evictions.load(Ordering::Relaxed) + self.l2.evictions()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// L2 holding one value, which it reports to expire after `remaining`
+    struct ExpiringL2 {
+        value: Arc<Mutex<Option<String>>>,
+        remaining: Duration,
+    }
+
+    #[async_trait]
+    impl CacheManager for ExpiringL2 {
+        async fn has(&mut self, _key: &str) -> Result<bool> {
+            Ok(self.value.lock().unwrap().is_some())
+        }
+
+        async fn get(&mut self, _key: &str) -> Result<Option<String>> {
+            Ok(self.value.lock().unwrap().clone())
+        }
+
+        async fn set(&mut self, _key: &str, value: &str, _ttl_seconds: u64) -> Result<()> {
+            *self.value.lock().unwrap() = Some(value.to_string());
+            Ok(())
+        }
+
+        async fn remove(&mut self, _key: &str) -> Result<()> {
+            *self.value.lock().unwrap() = None;
+            Ok(())
+        }
+
+        async fn delete_by_prefix(&mut self, _prefix: &str) -> Result<usize> {
+            Ok(0)
+        }
+
+        async fn disconnect(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn ttl(&mut self, _key: &str) -> Result<Option<Duration>> {
+            Ok(self.value.lock().unwrap().as_ref().map(|_| self.remaining))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_l1_copy_expires_with_l2_entry() {
+        let value = Arc::new(Mutex::new(Some("payload".to_string())));
+        let l2 = ExpiringL2 {
+            value: value.clone(),
+            remaining: Duration::from_millis(20),
+        };
+        let options = L1CacheOptions {
+            enabled: true,
+            ttl_ms: 60_000,
+            max_capacity: 100,
+        };
+        let mut cache = TieredCacheManager::new(Box::new(l2), &options);
+        assert_eq!(cache.get("key").await.unwrap().as_deref(), Some("payload"));
+
+        // L2 drops the entry when it expires
+        *value.lock().unwrap() = None;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(cache.get("key").await.unwrap(), None);
+    }
+}
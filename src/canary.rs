@@ -0,0 +1,201 @@
+// src/canary.rs
+//! Synthetic canary traffic. Every round the canary connects to a node as a
+//! client of its app, subscribes to the canary channel, publishes an event
+//! carrying a fresh nonce through the HTTP API and waits for that event to
+//! come back over the WebSocket. The time from publish to receipt is the
+//! node's end-to-end delivery latency, exported with whether the round
+//! succeeded so alerts can fire on either.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde_json::{Value, json};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+use crate::app::config::App;
+use crate::app::manager::AppManager;
+use crate::metrics::MetricsInterface;
+use crate::options::CanaryConfig;
+use crate::probe::{ProbeFailure, expect_event, open_client, send_message};
+use crate::token::Token;
+
+/// Event the canary publishes to its channel
+const CANARY_EVENT: &str = "sockudo:canary";
+
+pub struct Canary {
+    app_manager: Arc<dyn AppManager + Send + Sync>,
+    metrics: Option<Arc<Mutex<dyn MetricsInterface + Send + Sync>>>,
+    config: CanaryConfig,
+    /// `host:port` the canary connects and publishes to
+    authority: String,
+    /// Metrics label for the node under test
+    target: String,
+    http_client: reqwest::Client,
+}
+
+impl Canary {
+    /// `local_authority` is how this node is reached when no `target` is set
+    pub fn new(
+        app_manager: Arc<dyn AppManager + Send + Sync>,
+        metrics: Option<Arc<Mutex<dyn MetricsInterface + Send + Sync>>>,
+        config: CanaryConfig,
+        local_authority: String,
+    ) -> Self {
+        let (authority, target) = match &config.target {
+            Some(target) => (target.clone(), target.clone()),
+            None => (local_authority, "self".to_string()),
+        };
+        Self {
+            app_manager,
+            metrics,
+            config,
+            authority,
+            target,
+            http_client: reqwest::Client::new(),
+        }
+    }
+
+    /// Run a round every `interval_seconds`, starting one interval from now
+    /// so the server is listening by the first round
+    pub fn start(self: &Arc<Self>) {
+        let canary = self.clone();
+        tokio::spawn(async move {
+            let period = Duration::from_secs(canary.config.interval_seconds.max(1));
+            let mut interval =
+                tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            loop {
+                interval.tick().await;
+                canary.run_round().await;
+            }
+        });
+    }
+
+    async fn run_round(&self) {
+        let timeout = Duration::from_millis(self.config.timeout_ms.max(1));
+        let outcome = match tokio::time::timeout(timeout, self.round()).await {
+            Ok(outcome) => outcome,
+            Err(_) => Err(ProbeFailure::new(
+                "timeout",
+                format!("Round took longer than {}ms", timeout.as_millis()),
+            )),
+        };
+
+        match &outcome {
+            Ok(latency_ms) => debug!(
+                "{}",
+                format!(
+                    "Canary event reached {} in {:.2}ms",
+                    self.target, latency_ms
+                )
+            ),
+            Err(failure) => warn!(
+                "{}",
+                format!(
+                    "Canary round against {} failed at {}: {}",
+                    self.target, failure.stage, failure.error
+                )
+            ),
+        }
+
+        if let Some(metrics) = &self.metrics {
+            let metrics = metrics.lock().await;
+            if let Ok(latency_ms) = outcome {
+                metrics.track_canary_latency(&self.target, latency_ms);
+            }
+            metrics.mark_canary_result(&self.target, outcome.is_ok());
+        }
+    }
+
+    /// One round, returning the publish-to-receipt latency in milliseconds
+    async fn round(&self) -> Result<f64, ProbeFailure> {
+        let app = self
+            .app_manager
+            .find_by_id(&self.config.app_id)
+            .await
+            .map_err(|e| ProbeFailure::new("app", e))?
+            .ok_or_else(|| {
+                ProbeFailure::new("app", format!("App {} not found", self.config.app_id))
+            })?;
+
+        let stream = TcpStream::connect(&self.authority)
+            .await
+            .map_err(|e| ProbeFailure::new("connect", e))?;
+        let mut ws = open_client(stream, &self.authority, &app.key, "sockudo-canary").await?;
+        expect_event(
+            &mut ws,
+            "pusher:connection_established",
+            "connection_established",
+        )
+        .await?;
+
+        let subscribe = json!({
+            "event": "pusher:subscribe",
+            "data": { "channel": self.config.channel },
+        });
+        send_message(&mut ws, &subscribe.to_string(), "subscribe").await?;
+        expect_event(
+            &mut ws,
+            "pusher_internal:subscription_succeeded",
+            "subscribe",
+        )
+        .await?;
+
+        let nonce = uuid::Uuid::new_v4().to_string();
+        let published_at = Instant::now();
+        self.publish(&app, &nonce).await?;
+
+        // Canaries of other nodes may share the channel, so wait for our nonce
+        loop {
+            let message = expect_event(&mut ws, CANARY_EVENT, "deliver").await?;
+            if message.get("data").and_then(Value::as_str) == Some(nonce.as_str()) {
+                break;
+            }
+        }
+        let latency_ms = published_at.elapsed().as_secs_f64() * 1000.0;
+
+        let _ = ws
+            .write_frame(fastwebsockets::Frame::close(1000, b""))
+            .await;
+        Ok(latency_ms)
+    }
+
+    /// Trigger the canary event through the signed HTTP API
+    async fn publish(&self, app: &App, nonce: &str) -> Result<(), ProbeFailure> {
+        let path = format!("/apps/{}/events", app.id);
+        let body = json!({
+            "name": CANARY_EVENT,
+            "channels": [self.config.channel],
+            "data": nonce,
+        })
+        .to_string();
+        let query = format!(
+            "auth_key={}&auth_timestamp={}&auth_version=1.0&body_md5={:x}",
+            app.key,
+            chrono::Utc::now().timestamp(),
+            md5::compute(body.as_bytes())
+        );
+        let signature = Token::new(app.key.clone(), app.secret.clone())
+            .sign(&format!("POST\n{}\n{}", path, query));
+
+        let response = self
+            .http_client
+            .post(format!(
+                "http://{}{}?{}&auth_signature={}",
+                self.authority, path, query, signature
+            ))
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| ProbeFailure::new("publish", e))?;
+        if !response.status().is_success() {
+            return Err(ProbeFailure::new(
+                "publish",
+                format!("HTTP API answered {}", response.status()),
+            ));
+        }
+        Ok(())
+    }
+}
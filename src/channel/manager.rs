@@ -9,8 +9,13 @@ use crate::websocket::SocketId;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+/// Number of locks that channel joins and leaves are striped over
+const CHANNEL_LOCK_STRIPES: usize = 256;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PresenceMember {
@@ -38,12 +43,29 @@ pub struct LeaveResponse {
 }
 
 pub struct ChannelManager {
-    connection_manager: Arc<Mutex<Box<dyn Adapter + Send + Sync>>>,
+    connection_manager: Arc<dyn Adapter + Send + Sync>,
+    channel_locks: Vec<Arc<Mutex<()>>>,
 }
 
 impl ChannelManager {
-    pub fn new(connection_manager: Arc<Mutex<Box<dyn Adapter + Send + Sync>>>) -> Self {
-        Self { connection_manager }
+    pub fn new(connection_manager: Arc<dyn Adapter + Send + Sync>) -> Self {
+        Self {
+            connection_manager,
+            channel_locks: (0..CHANNEL_LOCK_STRIPES)
+                .map(|_| Arc::new(Mutex::new(())))
+                .collect(),
+        }
+    }
+
+    /// Held while a socket joins or leaves a channel, so this node's joins
+    /// and leaves of one channel, with their presence bookkeeping, run one at
+    /// a time. Adapters only make single operations atomic. Channels share a
+    /// fixed set of locks, so never hold two at once.
+    pub async fn lock_channel(&self, app_id: &str, channel: &str) -> OwnedMutexGuard<()> {
+        let mut hasher = DefaultHasher::new();
+        (app_id, channel).hash(&mut hasher);
+        let stripe = hasher.finish() as usize % self.channel_locks.len();
+        self.channel_locks[stripe].clone().lock_owned().await
     }
 
     pub async fn subscribe(
@@ -63,13 +85,15 @@ impl ChannelManager {
         // Create SocketId without clone by using borrowed str
         let socket_id = SocketId(socket_id.to_string());
 
-        let mut connection_manager = self.connection_manager.lock().await;
-
-        if connection_manager
+        if self
+            .connection_manager
             .is_in_channel(app_id, channel_name, &socket_id)
             .await?
         {
-            let channel = connection_manager.get_channel(app_id, channel_name).await?;
+            let channel = self
+                .connection_manager
+                .get_channel(app_id, channel_name)
+                .await?;
 
             return Ok(JoinResponse {
                 success: true,
@@ -90,12 +114,13 @@ impl ChannelManager {
         };
 
         // Add socket to channel
-        connection_manager
+        self.connection_manager
             .add_to_channel(app_id, channel_name, &socket_id)
             .await
             .expect("TODO: panic message");
 
-        let total_connections = connection_manager
+        let total_connections = self
+            .connection_manager
             .get_channel(app_id, channel_name)
             .await?
             .len();
@@ -119,11 +144,11 @@ impl ChannelManager {
         user_id: Option<&str>,
     ) -> Result<LeaveResponse, Error> {
         let socket_id = SocketId(socket_id.to_string());
-        let mut connection_manager = self.connection_manager.lock().await;
 
         let member = if ChannelType::from_name(channel_name) == ChannelType::Presence {
             if let Some(user_id) = user_id {
-                let members = connection_manager
+                let members = self
+                    .connection_manager
                     .get_channel_members(app_id, channel_name)
                     .await?;
 
@@ -139,17 +164,19 @@ impl ChannelManager {
             None
         };
 
-        let socket_removed = connection_manager
+        let socket_removed = self
+            .connection_manager
             .remove_from_channel(app_id, channel_name, &socket_id)
             .await;
 
-        let remaining_connections = connection_manager
+        let remaining_connections = self
+            .connection_manager
             .get_channel(app_id, channel_name)
             .await?
             .len();
 
         if remaining_connections == 0 {
-            connection_manager
+            self.connection_manager
                 .remove_channel(app_id, channel_name)
                 .await;
         }
@@ -217,8 +244,12 @@ impl ChannelManager {
         signature: &str,
         message: PusherMessage,
     ) -> bool {
-        let expected = Self::get_expected_signature(app_config, socket_id, message);
-        secure_compare(signature, &expected)
+        let data_to_sign = Self::get_data_to_sign_for_signature(socket_id, message);
+        app_config.verification_secrets().any(|secret| {
+            let token = Token::new(app_config.key.clone(), secret.to_string());
+            let expected = format!("{}:{}", app_config.key, token.sign(&data_to_sign));
+            secure_compare(signature, &expected)
+        })
     }
 
     pub fn get_expected_signature(
@@ -259,8 +290,7 @@ impl ChannelManager {
         app_id: &str,
         channel: &str,
     ) -> Result<HashMap<String, PresenceMemberInfo>, Error> {
-        let mut connection_manager = self.connection_manager.lock().await;
-        connection_manager
+        self.connection_manager
             .get_channel_members(app_id, channel)
             .await
     }
@@ -1,4 +1,5 @@
 pub mod manager;
+pub mod reclaim;
 pub mod types;
 
 pub use manager::ChannelManager;
@@ -0,0 +1,130 @@
+// src/channel/reclaim.rs
+//! Reclaims the bookkeeping of channels nobody has used for a while. A node
+//! remembers when its last local subscriber left a channel. Once the channel
+//! has been vacant long enough, the node checks the cluster-wide subscriber
+//! count, so a channel still busy on another node is left alone, and then
+//! drops the empty channel entry and, when configured, the cached event of
+//! cache channels.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::adapter::Adapter;
+use crate::cache::manager::CacheManager;
+use crate::options::ChannelReclaimConfig;
+use crate::utils::is_cache_channel;
+
+pub struct ChannelReclaimer {
+    connection_manager: Arc<dyn Adapter + Send + Sync>,
+    cache_manager: Arc<Mutex<dyn CacheManager + Send + Sync>>,
+    config: ChannelReclaimConfig,
+    /// When each channel lost its last local subscriber, keyed by (app, channel)
+    vacated: DashMap<(String, String), Instant>,
+}
+
+impl ChannelReclaimer {
+    pub fn new(
+        connection_manager: Arc<dyn Adapter + Send + Sync>,
+        cache_manager: Arc<Mutex<dyn CacheManager + Send + Sync>>,
+        config: ChannelReclaimConfig,
+    ) -> Self {
+        Self {
+            connection_manager,
+            cache_manager,
+            config,
+            vacated: DashMap::new(),
+        }
+    }
+
+    pub fn mark_vacated(&self, app_id: &str, channel: &str) {
+        self.vacated
+            .insert((app_id.to_string(), channel.to_string()), Instant::now());
+    }
+
+    pub fn mark_occupied(&self, app_id: &str, channel: &str) {
+        self.vacated
+            .remove(&(app_id.to_string(), channel.to_string()));
+    }
+
+    /// How long `channel` must stay vacant before it is reclaimed
+    fn vacancy_limit(&self, channel: &str) -> Duration {
+        let minutes = self
+            .config
+            .policies
+            .iter()
+            .filter(|policy| channel.starts_with(&policy.prefix))
+            .max_by_key(|policy| policy.prefix.len())
+            .map_or(self.config.vacant_minutes, |policy| policy.vacant_minutes);
+        Duration::from_secs(minutes.saturating_mul(60))
+    }
+
+    /// Sweep for reclaimable channels every `sweep_interval_seconds`
+    pub fn start(self: &Arc<Self>) {
+        let reclaimer = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(
+                reclaimer.config.sweep_interval_seconds.max(1),
+            ));
+            loop {
+                interval.tick().await;
+                reclaimer.sweep().await;
+            }
+        });
+    }
+
+    async fn sweep(&self) {
+        let now = Instant::now();
+        let due: Vec<(String, String)> = self
+            .vacated
+            .iter()
+            .filter(|entry| {
+                now.duration_since(*entry.value()) >= self.vacancy_limit(&entry.key().1)
+            })
+            .map(|entry| entry.key().clone())
+            .collect();
+
+        let mut reclaimed = 0;
+        for (app_id, channel) in due {
+            // A subscriber may have joined here or on another node meanwhile
+            let subscribers = self
+                .connection_manager
+                .get_channel_socket_count(&app_id, &channel)
+                .await;
+            self.vacated.remove(&(app_id.clone(), channel.clone()));
+            if subscribers > 0 {
+                continue;
+            }
+            self.reclaim(&app_id, &channel).await;
+            reclaimed += 1;
+        }
+
+        if reclaimed > 0 {
+            info!(
+                "{}",
+                format!("Reclaimed bookkeeping of {} vacant channels", reclaimed)
+            );
+        }
+    }
+
+    async fn reclaim(&self, app_id: &str, channel: &str) {
+        if let Some(namespace) = self.connection_manager.get_namespace(app_id).await {
+            namespace
+                .channels
+                .remove_if(channel, |_, sockets| sockets.is_empty());
+        }
+
+        if self.config.reclaim_cached_events && is_cache_channel(channel) {
+            let key = format!("app:{}:channel:{}:cache_miss", app_id, channel);
+            if let Err(e) = self.cache_manager.lock().await.remove(&key).await {
+                warn!(
+                    "{}",
+                    format!("Failed to remove cached event of {}: {}", channel, e)
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,343 @@
+// src/config_migration.rs
+//! Migration of config files written for older sockudo releases or for
+//! soketi. Recognized old fields are moved to their current names before the
+//! file is deserialized, and each one is reported so the operator can update
+//! the file, or let `sockudo config migrate` rewrite it.
+//!
+//! Four shapes are handled:
+//! - soketi-style dotted keys (`"appManager.array.apps": [...]`), expanded
+//!   into nested objects
+//! - camelCase keys whose snake_case form is a current field
+//! - fields that were renamed or moved, listed in [`RENAMED_FIELDS`]
+//! - webhook `event_types` that only list exclusions, which used to match
+//!   every other event and now need the `*` wildcard
+
+use std::fmt;
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use crate::app::config::App;
+use crate::error::{Error, Result};
+use crate::options::ServerOptions;
+use crate::webhook::types::{WEBHOOK_EVENT_WILDCARD, only_excludes};
+
+/// Fields that moved beyond a camelCase to snake_case change, as dotted
+/// paths. Old paths use the snake_case spelling of their segments.
+const RENAMED_FIELDS: &[(&str, &str)] = &[
+    ("database.mysql.user", "database.mysql.username"),
+    ("database.postgres.user", "database.postgres.username"),
+    ("app_manager.mysql.table", "database.mysql.table_name"),
+    ("app_manager.postgres.table", "database.postgres.table_name"),
+    ("app_manager.dynamodb.table", "database.dynamodb.table_name"),
+    ("app_manager.dynamodb.region", "database.dynamodb.region"),
+    (
+        "app_manager.dynamodb.endpoint",
+        "database.dynamodb.endpoint_url",
+    ),
+];
+
+/// An old field found in a config file, and where its value went
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deprecation {
+    pub old: String,
+    pub new: String,
+    pub kind: DeprecationKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeprecationKind {
+    /// `old` was renamed or moved to `new`
+    Renamed,
+    /// `old` was a single dotted key rather than a nested field
+    DottedKey,
+    /// `old` lists webhook event types that only exclude events, and got the
+    /// wildcard that keeps it matching the others
+    ExclusionsOnly,
+}
+
+impl fmt::Display for Deprecation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            DeprecationKind::Renamed => {
+                write!(f, "`{}` is deprecated, use `{}`", self.old, self.new)
+            }
+            DeprecationKind::DottedKey => write!(
+                f,
+                "dotted key \"{}\" is deprecated, nest it as objects instead",
+                self.old
+            ),
+            DeprecationKind::ExclusionsOnly => write!(
+                f,
+                "`{}` only excludes events, which no longer matches the others; add \"{}\" to it",
+                self.old, WEBHOOK_EVENT_WILDCARD
+            ),
+        }
+    }
+}
+
+/// Parse a config file, migrating old fields first
+pub fn parse(contents: &str) -> serde_json::Result<(ServerOptions, Vec<Deprecation>)> {
+    let mut value: Value = serde_json::from_str(contents)?;
+    let deprecations = migrate(&mut value);
+    Ok((serde_json::from_value(value)?, deprecations))
+}
+
+/// Rewrite the config file at `path` in the current shape. The result goes to
+/// `output` when given; otherwise the original is kept as `<path>.bak` and
+/// replaced. Returns the migrated fields, and writes nothing if there are none.
+pub fn migrate_file(path: &str, output: Option<&str>) -> Result<Vec<Deprecation>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| Error::ConfigFileError(format!("Failed to read {}: {}", path, e)))?;
+    let mut value: Value = serde_json::from_str(&contents)
+        .map_err(|e| Error::ConfigFileError(format!("Failed to parse {}: {}", path, e)))?;
+    let deprecations = migrate(&mut value);
+    if deprecations.is_empty() {
+        return Ok(deprecations);
+    }
+
+    // Refuse to write a file the server would not load
+    serde_json::from_value::<ServerOptions>(value.clone()).map_err(|e| {
+        Error::ConfigFileError(format!("Migrated config for {} is invalid: {}", path, e))
+    })?;
+    let migrated = serde_json::to_string_pretty(&value)?;
+
+    let target = match output {
+        Some(output) => output.to_string(),
+        None => {
+            let backup = format!("{}.bak", path);
+            std::fs::copy(path, &backup).map_err(|e| {
+                Error::ConfigFileError(format!("Failed to back up {} to {}: {}", path, backup, e))
+            })?;
+            path.to_string()
+        }
+    };
+    std::fs::write(Path::new(&target), migrated + "\n")
+        .map_err(|e| Error::ConfigFileError(format!("Failed to write {}: {}", target, e)))?;
+    Ok(deprecations)
+}
+
+/// Move old fields in `config` to their current names
+pub fn migrate(config: &mut Value) -> Vec<Deprecation> {
+    let mut deprecations = Vec::new();
+    let Value::Object(root) = config else {
+        return deprecations;
+    };
+
+    expand_dotted_keys(root, "", &mut deprecations);
+
+    let schema = serde_json::to_value(ServerOptions::default()).unwrap_or(Value::Null);
+    snake_case_keys(root, &schema, "", &mut deprecations);
+
+    for (old, new) in RENAMED_FIELDS {
+        let Some(value) = remove_path(root, old) else {
+            continue;
+        };
+        if get_path(root, new).is_none() {
+            set_path(root, new, value);
+        }
+        deprecations.push(Deprecation {
+            old: old.to_string(),
+            new: new.to_string(),
+            kind: DeprecationKind::Renamed,
+        });
+    }
+
+    add_webhook_wildcards(config, &mut deprecations);
+    deprecations
+}
+
+/// Prepend `*` to webhook event types that only list exclusions, so they keep
+/// receiving every other event
+fn add_webhook_wildcards(config: &mut Value, out: &mut Vec<Deprecation>) {
+    let Some(apps) = config
+        .pointer_mut("/app_manager/array/apps")
+        .and_then(Value::as_array_mut)
+    else {
+        return;
+    };
+    for (app_index, app) in apps.iter_mut().enumerate() {
+        let Some(webhooks) = app.get_mut("webhooks").and_then(Value::as_array_mut) else {
+            continue;
+        };
+        for (webhook_index, webhook) in webhooks.iter_mut().enumerate() {
+            let Some(event_types) = webhook.get_mut("event_types").and_then(Value::as_array_mut)
+            else {
+                continue;
+            };
+            let entries: Vec<&str> = event_types.iter().filter_map(Value::as_str).collect();
+            if entries.len() == event_types.len() && only_excludes(&entries) {
+                event_types.insert(0, Value::from(WEBHOOK_EVENT_WILDCARD));
+                let path = format!(
+                    "app_manager.array.apps[{}].webhooks[{}].event_types",
+                    app_index, webhook_index
+                );
+                out.push(Deprecation {
+                    old: path.clone(),
+                    new: path,
+                    kind: DeprecationKind::ExclusionsOnly,
+                });
+            }
+        }
+    }
+}
+
+fn join(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+fn to_snake_case(key: &str) -> String {
+    let mut snake = String::with_capacity(key.len() + 4);
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            if !snake.is_empty() {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+/// Turn `{"a.b": 1}` into `{"a": {"b": 1}}`, merging into existing objects
+fn expand_dotted_keys(object: &mut Map<String, Value>, prefix: &str, out: &mut Vec<Deprecation>) {
+    let dotted: Vec<String> = object.keys().filter(|k| k.contains('.')).cloned().collect();
+    for key in dotted {
+        if let Some(value) = object.remove(&key) {
+            let full = join(prefix, &key);
+            out.push(Deprecation {
+                old: full.clone(),
+                new: full,
+                kind: DeprecationKind::DottedKey,
+            });
+            set_path(object, &key, value);
+        }
+    }
+    for (key, value) in object.iter_mut() {
+        if let Value::Object(child) = value {
+            expand_dotted_keys(child, &join(prefix, key), out);
+        }
+    }
+}
+
+/// Element shape for arrays whose default is empty
+fn array_element_schema(path: &str) -> Option<Value> {
+    match path {
+        "app_manager.array.apps" => serde_json::to_value(App::default()).ok(),
+        _ => None,
+    }
+}
+
+/// Rename camelCase keys whose snake_case form is a field in `schema`.
+/// Free-form maps have no fields in the schema, so their keys are left alone.
+fn snake_case_keys(
+    object: &mut Map<String, Value>,
+    schema: &Value,
+    prefix: &str,
+    out: &mut Vec<Deprecation>,
+) {
+    let Value::Object(fields) = schema else {
+        return;
+    };
+
+    let renames: Vec<(String, String)> = object
+        .keys()
+        .filter(|key| !fields.contains_key(*key))
+        .map(|key| (key.clone(), to_snake_case(key)))
+        .filter(|(key, snake)| key != snake && fields.contains_key(snake))
+        .collect();
+    for (key, snake) in renames {
+        if let Some(value) = object.remove(&key) {
+            // When both spellings are present the current one wins
+            if !object.contains_key(&snake) {
+                object.insert(snake.clone(), value);
+            }
+            out.push(Deprecation {
+                old: join(prefix, &key),
+                new: join(prefix, &snake),
+                kind: DeprecationKind::Renamed,
+            });
+        }
+    }
+
+    for (key, value) in object.iter_mut() {
+        let path = join(prefix, key);
+        match (value, fields.get(key)) {
+            (Value::Object(child), Some(child_schema)) => {
+                snake_case_keys(child, child_schema, &path, out);
+            }
+            (Value::Array(items), Some(_)) => {
+                let Some(element_schema) = array_element_schema(&path) else {
+                    continue;
+                };
+                for (index, item) in items.iter_mut().enumerate() {
+                    if let Value::Object(child) = item {
+                        let item_path = format!("{}[{}]", path, index);
+                        snake_case_keys(child, &element_schema, &item_path, out);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn get_path<'a>(object: &'a Map<String, Value>, path: &str) -> Option<&'a Value> {
+    let (head, rest) = match path.split_once('.') {
+        Some((head, rest)) => (head, Some(rest)),
+        None => (path, None),
+    };
+    let value = object.get(head)?;
+    match (rest, value) {
+        (None, value) => Some(value),
+        (Some(rest), Value::Object(child)) => get_path(child, rest),
+        _ => None,
+    }
+}
+
+/// Set `path`, creating (or merging into) the objects along the way
+fn set_path(object: &mut Map<String, Value>, path: &str, value: Value) {
+    let Some((head, rest)) = path.split_once('.') else {
+        match (object.get_mut(path), value) {
+            (Some(Value::Object(existing)), Value::Object(incoming)) => {
+                for (key, value) in incoming {
+                    existing.entry(key).or_insert(value);
+                }
+            }
+            (_, value) => {
+                object.insert(path.to_string(), value);
+            }
+        }
+        return;
+    };
+    let child = object
+        .entry(head.to_string())
+        .or_insert_with(|| Value::Object(Map::new()));
+    if !child.is_object() {
+        *child = Value::Object(Map::new());
+    }
+    if let Value::Object(child) = child {
+        set_path(child, rest, value);
+    }
+}
+
+/// Remove `path`, dropping objects the removal leaves empty
+fn remove_path(object: &mut Map<String, Value>, path: &str) -> Option<Value> {
+    let Some((head, rest)) = path.split_once('.') else {
+        return object.remove(path);
+    };
+    let Some(Value::Object(child)) = object.get_mut(head) else {
+        return None;
+    };
+    let removed = remove_path(child, rest);
+    if removed.is_some() && child.is_empty() {
+        object.remove(head);
+    }
+    removed
+}
@@ -0,0 +1,186 @@
+// src/dns.rs
+//! Shared asynchronous DNS resolution for outbound connections.
+//!
+//! Lookups go through a hickory resolver whose cache honours record TTLs (or
+//! the configured override), so targets behind round-robin DNS are re-resolved
+//! instead of pinning to whatever address the OS returned first. Results are
+//! ordered IPv6/IPv4 alternately so connectors can race both families.
+
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use futures::StreamExt;
+use futures::stream::FuturesUnordered;
+use hickory_resolver::TokioAsyncResolver;
+use hickory_resolver::config::{LookupIpStrategy, ResolverConfig, ResolverOpts};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use tokio::net::TcpStream;
+use tracing::{info, warn};
+
+use crate::options::DnsConfig;
+
+static RESOLVER: OnceLock<Arc<CachingResolver>> = OnceLock::new();
+
+/// Install the process-wide resolver. Later calls are ignored.
+pub fn init(config: &DnsConfig) {
+    if RESOLVER.set(Arc::new(CachingResolver::new(config))).is_ok() {
+        info!(
+            "{}",
+            format!(
+                "DNS resolver initialized (ttl override: {:?}, happy eyeballs delay: {}ms)",
+                config.ttl_override_secs, config.happy_eyeballs_delay_ms
+            )
+        );
+    }
+}
+
+/// The process-wide resolver, created with default options if `init` was never called
+pub fn resolver() -> Arc<CachingResolver> {
+    RESOLVER
+        .get_or_init(|| Arc::new(CachingResolver::new(&DnsConfig::default())))
+        .clone()
+}
+
+/// Caching resolver used for webhook deliveries and broker-less peer connections
+pub struct CachingResolver {
+    inner: TokioAsyncResolver,
+    happy_eyeballs_delay: Duration,
+    http_pool_idle_timeout: Duration,
+}
+
+impl CachingResolver {
+    pub fn new(config: &DnsConfig) -> Self {
+        let (resolver_config, mut opts) = match hickory_resolver::system_conf::read_system_conf() {
+            Ok(system) => system,
+            Err(e) => {
+                warn!(
+                    "{}",
+                    format!("Failed to read system DNS config: {}, using defaults", e)
+                );
+                (ResolverConfig::default(), ResolverOpts::default())
+            }
+        };
+
+        opts.ip_strategy = LookupIpStrategy::Ipv4AndIpv6;
+        opts.cache_size = config.cache_size;
+        if let Some(ttl) = config.ttl_override_secs {
+            let ttl = Duration::from_secs(ttl);
+            opts.positive_min_ttl = Some(ttl);
+            opts.positive_max_ttl = Some(ttl);
+        }
+
+        Self {
+            inner: TokioAsyncResolver::tokio(resolver_config, opts),
+            happy_eyeballs_delay: Duration::from_millis(config.happy_eyeballs_delay_ms),
+            http_pool_idle_timeout: Duration::from_secs(config.http_pool_idle_timeout_secs),
+        }
+    }
+
+    /// How long pooled HTTP connections may idle before they are closed and
+    /// the next request resolves the host again
+    pub fn http_pool_idle_timeout(&self) -> Duration {
+        self.http_pool_idle_timeout
+    }
+
+    /// Resolve a host name into addresses, alternating IPv6 and IPv4
+    pub async fn lookup(&self, host: &str) -> io::Result<Vec<IpAddr>> {
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            return Ok(vec![ip]);
+        }
+
+        let lookup = self
+            .inner
+            .lookup_ip(host)
+            .await
+            .map_err(|e| io::Error::other(format!("DNS lookup for {} failed: {}", host, e)))?;
+        let (v6, v4): (Vec<IpAddr>, Vec<IpAddr>) = lookup.iter().partition(|ip| ip.is_ipv6());
+        Ok(interleave(v6, v4))
+    }
+
+    /// Connect to a `host:port` address, racing the resolved addresses
+    /// happy-eyeballs style: each attempt gets a head start of the configured
+    /// delay before the next address is tried in parallel
+    pub async fn connect_tcp(&self, address: &str) -> io::Result<TcpStream> {
+        let (host, port) = address
+            .rsplit_once(':')
+            .and_then(|(host, port)| port.parse::<u16>().ok().map(|port| (host, port)))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Invalid address: {}", address),
+                )
+            })?;
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+
+        let mut remaining = self
+            .lookup(host)
+            .await?
+            .into_iter()
+            .map(|ip| SocketAddr::new(ip, port));
+        let mut attempts = FuturesUnordered::new();
+        let mut last_error = None;
+
+        if let Some(addr) = remaining.next() {
+            attempts.push(TcpStream::connect(addr));
+        }
+        while !attempts.is_empty() {
+            tokio::select! {
+                Some(result) = attempts.next() => match result {
+                    Ok(stream) => return Ok(stream),
+                    Err(e) => {
+                        last_error = Some(e);
+                        // A failed attempt starts the next one right away
+                        if let Some(addr) = remaining.next() {
+                            attempts.push(TcpStream::connect(addr));
+                        }
+                    }
+                },
+                _ = tokio::time::sleep(self.happy_eyeballs_delay) => {
+                    if let Some(addr) = remaining.next() {
+                        attempts.push(TcpStream::connect(addr));
+                    }
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No addresses found for {}", address),
+            )
+        }))
+    }
+}
+
+impl Resolve for CachingResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.inner.clone();
+        Box::pin(async move {
+            let lookup = resolver.lookup_ip(name.as_str()).await?;
+            let (v6, v4): (Vec<IpAddr>, Vec<IpAddr>) = lookup.iter().partition(|ip| ip.is_ipv6());
+            // The port is filled in by the HTTP connector
+            let addrs: Addrs = Box::new(
+                interleave(v6, v4)
+                    .into_iter()
+                    .map(|ip| SocketAddr::new(ip, 0)),
+            );
+            Ok(addrs)
+        })
+    }
+}
+
+/// Alternate address families, starting with IPv6 (RFC 8305 section 4)
+fn interleave(v6: Vec<IpAddr>, v4: Vec<IpAddr>) -> Vec<IpAddr> {
+    let mut ordered = Vec::with_capacity(v6.len() + v4.len());
+    let mut v6 = v6.into_iter();
+    let mut v4 = v4.into_iter();
+    loop {
+        match (v6.next(), v4.next()) {
+            (None, None) => break,
+            (a, b) => ordered.extend(a.into_iter().chain(b)),
+        }
+    }
+    ordered
+}
@@ -12,9 +12,6 @@ pub enum Error {
     #[error("Application disabled")]
     ApplicationDisabled,
 
-    #[error("Application is over adapter quota")]
-    OverConnectionQuota,
-
     #[error("Path not found")]
     PathNotFound,
 
@@ -30,10 +27,16 @@ pub enum Error {
     #[error("Connection is unauthorized")]
     Unauthorized,
 
+    #[error("Too many connection attempts")]
+    ConnectionRateLimit,
+
     // 4100-4199: Reconnect with backoff errors
     #[error("Over capacity")]
     OverCapacity,
 
+    #[error("Application is over its connection quota")]
+    OverConnectionQuota,
+
     // 4200-4299: Reconnect immediately errors
     #[error("Generic reconnect immediately")]
     ReconnectImmediately,
@@ -51,6 +54,18 @@ pub enum Error {
     #[error("Watchlist limit exceeded")]
     WatchlistLimitExceeded,
 
+    #[error("The maximum of {0} members per presence channel was reached")]
+    PresenceMembersLimit(u32),
+
+    #[error("Presence member data ({size_kb} KB) exceeds the limit of {max_kb} KB")]
+    PresenceMemberTooLarge { size_kb: usize, max_kb: u32 },
+
+    #[error("The maximum of {0} channels per connection was reached")]
+    ChannelsPerConnectionLimit(u32),
+
+    #[error("{0} are disabled for this app")]
+    ChannelFeatureDisabled(&'static str),
+
     // Channel specific errors
     #[error("Channel error: {0}")]
     ChannelError(String),
@@ -120,6 +135,9 @@ pub enum Error {
     #[error("Cache error: {0}")]
     CacheError(String),
 
+    #[error("User bans unavailable: {0}")]
+    BansUnavailable(String),
+
     #[error("Invalid JSON")]
     SerializationError(String),
 
@@ -165,15 +183,16 @@ impl Error {
             Error::SSLRequired => 4000,
             Error::ApplicationNotFound => 4001,
             Error::ApplicationDisabled => 4003,
-            Error::OverConnectionQuota => 4004,
             Error::PathNotFound => 4005,
             Error::InvalidVersionFormat => 4006,
             Error::UnsupportedProtocolVersion(_) => 4007,
             Error::NoProtocolVersion => 4008,
             Error::Unauthorized => 4009,
+            Error::ConnectionRateLimit => 4009,
 
             // 4100-4199: Reconnect with backoff
             Error::OverCapacity => 4100,
+            Error::OverConnectionQuota => 4100,
 
             // 4200-4299: Reconnect immediately
             Error::ReconnectImmediately => 4200,
@@ -183,11 +202,15 @@ impl Error {
             // 4300-4399: Other errors
             Error::ClientEventRateLimit => 4301,
             Error::WatchlistLimitExceeded => 4302,
+            Error::PresenceMembersLimit(_) => 4100,
+            Error::PresenceMemberTooLarge { .. } => 4301,
 
             Error::BroadcastError(_) => 4303,
+            Error::ChannelsPerConnectionLimit(_) => 4304,
 
             // Map other errors to appropriate ranges
             Error::ChannelError(_)
+            | Error::ChannelFeatureDisabled(_)
             | Error::InvalidChannelName(_)
             | Error::ChannelExists
             | Error::ChannelNotFound => 4300,
@@ -208,7 +231,6 @@ impl Error {
             Error::SSLRequired
                 | Error::ApplicationNotFound
                 | Error::ApplicationDisabled
-                | Error::OverConnectionQuota
                 | Error::PathNotFound
                 | Error::InvalidVersionFormat
                 | Error::UnsupportedProtocolVersion(_)
@@ -217,6 +239,12 @@ impl Error {
         )
     }
 
+    /// Errors in the 4100-4199 range, after which clients should wait before
+    /// reconnecting
+    pub fn should_back_off(&self) -> bool {
+        (4100..4200).contains(&self.close_code())
+    }
+
     pub fn should_reconnect(&self) -> bool {
         matches!(
             self,
@@ -0,0 +1,208 @@
+// src/fixtures.rs
+//! Known-good protocol vectors for `sockudo fixtures`. For each configured
+//! app it signs sample HTTP API requests, channel and user authentication
+//! strings and a webhook body with the same code the server verifies and
+//! sends them with, so SDKs in other languages can check their signing
+//! against Sockudo's.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use serde_json::{Value, json};
+
+use crate::app::auth::AuthValidator;
+use crate::app::config::App;
+use crate::app::manager::AppManager;
+use crate::channel::ChannelManager;
+use crate::error::{Error, Result};
+use crate::protocol::messages::{MessageData, PusherMessage};
+use crate::token::Token;
+use crate::webhook::types::{PusherWebhookPayload, WebhookEvent};
+use crate::websocket::SocketId;
+
+/// Fixed inputs, so vectors stay the same from run to run
+const FIXTURE_TIMESTAMP: i64 = 1_700_000_000;
+const FIXTURE_SOCKET_ID: &str = "1234.5678";
+
+/// Fixtures for every app, or only `app_id` when given
+pub async fn generate(
+    app_manager: Arc<dyn AppManager + Send + Sync>,
+    app_id: Option<&str>,
+) -> Result<Value> {
+    let apps = match app_id {
+        Some(app_id) => vec![
+            app_manager
+                .find_by_id(app_id)
+                .await?
+                .ok_or(Error::ApplicationNotFound)?,
+        ],
+        None => app_manager.get_apps().await?,
+    };
+
+    let auth_validator = AuthValidator::new(app_manager.clone());
+    let apps: Vec<Value> = apps
+        .into_iter()
+        .map(|app| app_fixtures(&auth_validator, app))
+        .collect::<Result<_>>()?;
+
+    Ok(json!({
+        "generator": format!("sockudo {}", env!("CARGO_PKG_VERSION")),
+        "socket_id": FIXTURE_SOCKET_ID,
+        "auth_timestamp": FIXTURE_TIMESTAMP.to_string(),
+        "apps": apps,
+    }))
+}
+
+fn app_fixtures(auth_validator: &AuthValidator, app: App) -> Result<Value> {
+    let trigger_body = json!({
+        "name": "my-event",
+        "channels": ["my-channel"],
+        "data": "{\"message\":\"hello\"}",
+    })
+    .to_string();
+    let api_requests = vec![
+        api_request(
+            &app,
+            "POST",
+            &format!("/apps/{}/events", app.id),
+            &[],
+            Some(&trigger_body),
+        ),
+        api_request(
+            &app,
+            "GET",
+            &format!("/apps/{}/channels", app.id),
+            &[("filter_by_prefix", "presence-"), ("info", "user_count")],
+            None,
+        ),
+    ];
+
+    let presence_data = json!({ "user_id": "user-1", "user_info": { "name": "Ada" } }).to_string();
+    let channel_auth = vec![
+        channel_auth(&app, "private-my-channel", None),
+        channel_auth(&app, "presence-my-channel", Some(&presence_data)),
+    ];
+
+    let user_data = json!({ "id": "user-1", "name": "Ada" }).to_string();
+    let user_signature =
+        auth_validator.sing_in_token_for_user_data(FIXTURE_SOCKET_ID, &user_data, app.clone());
+    let user_authentication = json!({
+        "user_data": user_data,
+        "string_to_sign": format!("{}::user::{}", FIXTURE_SOCKET_ID, user_data),
+        "signature": user_signature,
+        "auth": format!("{}:{}", app.key, user_signature),
+    });
+
+    let webhook_payload = PusherWebhookPayload {
+        time_ms: FIXTURE_TIMESTAMP * 1000,
+        events: vec![
+            WebhookEvent::ChannelOccupied {
+                channel: "my-channel".to_string(),
+            },
+            WebhookEvent::MemberAdded {
+                channel: "presence-my-channel".to_string(),
+                user_id: "user-1".to_string(),
+            },
+        ],
+        batch: None,
+    };
+    let webhook_body = serde_json::to_string(&webhook_payload).map_err(|e| {
+        Error::SerializationError(format!("Failed to serialize webhook body: {}", e))
+    })?;
+    let webhook = json!({
+        "body": webhook_body,
+        "headers": {
+            "Content-Type": "application/json",
+            "X-Pusher-Key": app.key,
+            "X-Pusher-Signature": token(&app).sign(&webhook_body),
+        },
+    });
+
+    Ok(json!({
+        "app_id": app.id,
+        "key": app.key,
+        "secret": app.secret,
+        "api_requests": api_requests,
+        "channel_auth": channel_auth,
+        "user_authentication": user_authentication,
+        "webhook": webhook,
+    }))
+}
+
+fn token(app: &App) -> Token {
+    Token::new(app.key.clone(), app.secret.clone())
+}
+
+/// A signed HTTP API request, with the string its signature covers
+fn api_request(
+    app: &App,
+    method: &str,
+    path: &str,
+    params: &[(&str, &str)],
+    body: Option<&str>,
+) -> Value {
+    let mut query: BTreeMap<String, String> = params
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    query.insert("auth_key".to_string(), app.key.clone());
+    query.insert("auth_timestamp".to_string(), FIXTURE_TIMESTAMP.to_string());
+    query.insert("auth_version".to_string(), "1.0".to_string());
+    if let Some(body) = body {
+        query.insert(
+            "body_md5".to_string(),
+            format!("{:x}", md5::compute(body.as_bytes())),
+        );
+    }
+
+    let string_to_sign = AuthValidator::api_string_to_sign(method, path, &query);
+    let auth_signature = token(app).sign(&string_to_sign);
+    let query_string = query
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, urlencoding::encode(value)))
+        .chain(std::iter::once(format!(
+            "auth_signature={}",
+            auth_signature
+        )))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    json!({
+        "method": method,
+        "path": path,
+        "body": body,
+        "string_to_sign": string_to_sign,
+        "auth_signature": auth_signature,
+        "url": format!("{}?{}", path, query_string),
+    })
+}
+
+/// The `auth` a subscription to `channel` must carry
+fn channel_auth(app: &App, channel: &str, channel_data: Option<&str>) -> Value {
+    let subscribe = PusherMessage {
+        channel: None,
+        name: None,
+        event: Some("pusher:subscribe".to_string()),
+        data: Some(MessageData::Structured {
+            channel_data: channel_data.map(str::to_string),
+            channel: Some(channel.to_string()),
+            user_data: None,
+            extra: Default::default(),
+        }),
+    };
+    let string_to_sign = match channel_data {
+        Some(channel_data) => format!("{}:{}:{}", FIXTURE_SOCKET_ID, channel, channel_data),
+        None => format!("{}:{}", FIXTURE_SOCKET_ID, channel),
+    };
+
+    json!({
+        "channel": channel,
+        "channel_data": channel_data,
+        "string_to_sign": string_to_sign,
+        "auth": ChannelManager::get_expected_signature(
+            app.clone(),
+            &SocketId(FIXTURE_SOCKET_ID.to_string()),
+            subscribe,
+        ),
+    })
+}
@@ -1,18 +1,22 @@
 use crate::adapter::ConnectionHandler;
 use crate::app::auth::AuthValidator; // Added for API auth
 use crate::app::config::App; // To access app limits
+use crate::middleware::get_params_for_signature;
 use crate::protocol::constants::EVENT_NAME_MAX_LENGTH as DEFAULT_EVENT_NAME_MAX_LENGTH;
+use crate::protocol::constants::SERVER_NOTICE_CHANNEL;
 use crate::protocol::messages::{
     ApiMessageData, BatchPusherApiMessage, InfoQueryParser, PusherApiMessage, PusherMessage,
 };
 use crate::utils::{self, validate_channel_name};
 use crate::websocket::SocketId;
+use axum::body::Bytes;
 use axum::{
     Json,
     extract::{Path, Query, RawQuery, State}, // Added RawQuery
     http::{HeaderMap, HeaderValue, Response, StatusCode, Uri, header}, // Added Uri
     response::{IntoResponse, Response as AxumResponse},
 };
+use futures_util::StreamExt;
 use futures_util::future::join_all;
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
@@ -38,6 +42,10 @@ pub enum AppError {
     MissingChannelInfo,
     #[error("User connection termination failed: {0}")]
     TerminationFailed(String),
+    #[error("User ban failed: {0}")]
+    BanFailed(String),
+    #[error("User bans unavailable: {0}")]
+    BansUnavailable(String),
     #[error("Internal Server Error: {0}")]
     InternalError(String),
     #[error("Serialization Error: {0}")]
@@ -46,6 +54,8 @@ pub enum AppError {
     HeaderBuildError(#[from] axum::http::Error),
     #[error("Limit exceeded: {0}")]
     LimitExceeded(String),
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
     #[error("Invalid input: {0}")]
     InvalidInput(String),
 }
@@ -65,6 +75,12 @@ impl IntoResponse for AppError {
             AppError::TerminationFailed(msg) => {
                 (StatusCode::INTERNAL_SERVER_ERROR, json!({ "error": msg }))
             }
+            AppError::BanFailed(msg) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, json!({ "error": msg }))
+            }
+            AppError::BansUnavailable(msg) => {
+                (StatusCode::NOT_IMPLEMENTED, json!({ "error": msg }))
+            }
             AppError::SerializationError(e) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 json!({ "error": format!("Internal error during serialization: {}", e) }),
@@ -77,6 +93,9 @@ impl IntoResponse for AppError {
                 (StatusCode::INTERNAL_SERVER_ERROR, json!({ "error": msg }))
             }
             AppError::LimitExceeded(msg) => (StatusCode::BAD_REQUEST, json!({ "error": msg })),
+            AppError::PayloadTooLarge(msg) => {
+                (StatusCode::PAYLOAD_TOO_LARGE, json!({ "error": msg }))
+            }
             AppError::InvalidInput(msg) => (StatusCode::BAD_REQUEST, json!({ "error": msg })),
         };
         error!(error.message = %self, status_code = %status, "HTTP request failed");
@@ -92,10 +111,14 @@ impl From<crate::error::Error> for AppError {
                 AppError::AppNotFound(format!("Application key not found or invalid: {}", err))
             }
             crate::error::Error::ApplicationNotFound => AppError::AppNotFound(err.to_string()),
+            crate::error::Error::ApplicationDisabled => AppError::ApiAuthFailed(err.to_string()),
             crate::error::Error::InvalidChannelName(s) => {
                 AppError::InvalidInput(format!("Invalid channel name: {}", s))
             }
             crate::error::Error::ChannelError(s) => AppError::InvalidInput(s),
+            crate::error::Error::ChannelFeatureDisabled(_) => {
+                AppError::ApiAuthFailed(err.to_string())
+            }
             crate::error::Error::AuthError(s) => AppError::ApiAuthFailed(s),
             _ => AppError::InternalError(err.to_string()),
         }
@@ -158,8 +181,15 @@ struct UsageResponse {
 // --- Helper Functions ---
 
 /// Helper to build cache payload string
-fn build_cache_payload(event_name: &str, event_data: &Value) -> Result<String, serde_json::Error> {
+/// The event replayed to new subscribers of a cache channel. It carries the
+/// channel so clients route it like a live event.
+fn build_cache_payload(
+    channel: &str,
+    event_name: &str,
+    event_data: &Value,
+) -> Result<String, serde_json::Error> {
     serde_json::to_string(&json!({
+        "channel": channel,
         "event": event_name,
         "data": event_data,
     }))
@@ -271,7 +301,7 @@ async fn process_single_event_parallel(
         };
         let payload_size_bytes = utils::data_to_bytes_flexible(vec![value_for_size_calc]);
         if payload_size_bytes > (max_payload_kb as usize * 1024) {
-            return Err(AppError::LimitExceeded(format!(
+            return Err(AppError::PayloadTooLarge(format!(
                 "Event payload size ({} bytes) for event '{}' exceeds limit ({}KB)",
                 payload_size_bytes, event_name_str, max_payload_kb
             )));
@@ -308,6 +338,13 @@ async fn process_single_event_parallel(
         }
     };
 
+    // Validate every channel name before publishing to any of them, so an
+    // invalid name rejects the whole event
+    for target_channel_str in &target_channels {
+        validate_channel_name(app, target_channel_str).await?;
+        utils::check_channel_features(app, target_channel_str)?;
+    }
+
     // Create a collection of futures, one for each channel to process.
     // These futures will be executed concurrently by `join_all`.
     let channel_processing_futures = target_channels.into_iter().map(|target_channel_str| {
@@ -326,10 +363,6 @@ async fn process_single_event_parallel(
             // This block processes a single channel.
             info!(channel = %target_channel_str, "Processing channel for event (parallel task)");
 
-            // Validate the channel name.
-            // `app` is captured by reference from the outer scope.
-            validate_channel_name(app, &target_channel_str).await?;
-
             // Construct the message to be sent to this specific channel.
             let message_to_send = PusherApiMessage {
                 name: name_for_task, // Use cloned name
@@ -385,8 +418,6 @@ async fn process_single_event_parallel(
                 {
                     let count = handler_clone
                         .connection_manager
-                        .lock()
-                        .await
                         .get_channel_socket_count(&app.id, &target_channel_str)
                         .await;
                     current_channel_info_map.insert("subscription_count".to_string(), json!(count));
@@ -410,7 +441,11 @@ async fn process_single_event_parallel(
                 };
 
                 // Attempt to build the cache payload string.
-                match build_cache_payload(&event_name_for_task, &payload_value_for_cache) {
+                match build_cache_payload(
+                    &target_channel_str,
+                    &event_name_for_task,
+                    &payload_value_for_cache,
+                ) {
                     Ok(cache_payload_str) => {
                         let mut cache_manager_locked = handler_clone.cache_manager.lock().await;
                         let cache_key_str =
@@ -418,7 +453,11 @@ async fn process_single_event_parallel(
 
                         // Attempt to set the cache entry.
                         match cache_manager_locked
-                            .set(&cache_key_str, &cache_payload_str, 3600) // TTL is 1 hour
+                            .set(
+                                &cache_key_str,
+                                &cache_payload_str,
+                                handler_clone.cache_channel_ttl,
+                            )
                             .await
                         {
                             Ok(_) => {
@@ -623,6 +662,149 @@ pub async fn batch_events(
     Ok((StatusCode::OK, Json(final_response_payload)))
 }
 
+/// Longest NDJSON line accepted on an event stream
+const EVENT_STREAM_MAX_LINE_BYTES: usize = 1024 * 1024;
+
+/// Acknowledge one line of an event stream
+fn event_stream_ack(line: usize, result: Result<(), AppError>) -> Bytes {
+    let ack = match result {
+        Ok(()) => json!({ "line": line, "ok": true }),
+        Err(e) => json!({ "line": line, "ok": false, "error": e.to_string() }),
+    };
+    let mut bytes = serde_json::to_vec(&ack).unwrap_or_default();
+    bytes.push(b'\n');
+    Bytes::from(bytes)
+}
+
+/// GET /apps/{app_id}/events/stream
+///
+/// Persistent publish stream for high-volume backends. The request is signed
+/// once, like any other GET to the HTTP API, and its body is then read as
+/// NDJSON: one `PusherApiMessage` per line, blank lines ignored. Every line is
+/// acknowledged on the chunked response body as `{"line", "ok", "error"}`.
+#[instrument(skip_all, fields(app_id = %app_id))]
+pub async fn events_stream(
+    Path(app_id): Path<String>,
+    State(handler): State<Arc<ConnectionHandler>>,
+    request: axum::extract::Request,
+) -> Result<impl IntoResponse, AppError> {
+    let uri = request.uri().clone();
+    let auth_params: EventQuery = serde_urlencoded::from_str(uri.query().unwrap_or_default())
+        .map_err(|e| {
+            AppError::InvalidInput(format!("Invalid authentication query parameters: {}", e))
+        })?;
+    let params_for_signature = get_params_for_signature(uri.query())?;
+
+    // Authenticate once when the stream opens; the body is not covered by
+    // the signature since it has no end to hash
+    let authenticated = AuthValidator::new(handler.app_manager.clone())
+        .validate_pusher_api_request(&auth_params, "GET", uri.path(), &params_for_signature, None)
+        .await?;
+    if !authenticated {
+        return Err(AppError::ApiAuthFailed("Invalid API signature".to_string()));
+    }
+
+    let app = handler
+        .app_manager
+        .find_by_id(app_id.as_str())
+        .await?
+        .ok_or_else(|| AppError::AppNotFound(app_id.clone()))?;
+    if app.key != auth_params.auth_key {
+        return Err(AppError::ApiAuthFailed(
+            "auth_key does not belong to this app".to_string(),
+        ));
+    }
+
+    info!("{}", format!("Opened event stream for app {}", app_id));
+
+    let (ack_tx, ack_rx) = tokio::sync::mpsc::channel::<Bytes>(256);
+    let mut body = request.into_body().into_data_stream();
+
+    tokio::spawn(async move {
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut line_number = 0;
+        let mut published = 0;
+
+        'stream: loop {
+            let chunk = match body.next().await {
+                Some(Ok(chunk)) => chunk,
+                Some(Err(e)) => {
+                    warn!(
+                        "{}",
+                        format!("Event stream for app {} failed: {}", app_id, e)
+                    );
+                    break;
+                }
+                None => break,
+            };
+            buffer.extend_from_slice(&chunk);
+
+            while let Some(newline) = buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=newline).collect();
+                let line = line.trim_ascii();
+                if line.is_empty() {
+                    continue;
+                }
+                line_number += 1;
+
+                let result = match serde_json::from_slice::<PusherApiMessage>(line) {
+                    Ok(message) => process_single_event_parallel(&handler, &app, message, false)
+                        .await
+                        .map(|_| ()),
+                    Err(e) => Err(AppError::InvalidInput(format!("Invalid event JSON: {}", e))),
+                };
+                if result.is_ok() {
+                    published += 1;
+                    record_api_metrics(&handler, &app_id, line.len(), 0).await;
+                }
+                if ack_tx
+                    .send(event_stream_ack(line_number, result))
+                    .await
+                    .is_err()
+                {
+                    // The publisher stopped reading acknowledgements
+                    break 'stream;
+                }
+            }
+
+            if buffer.len() > EVENT_STREAM_MAX_LINE_BYTES {
+                line_number += 1;
+                let _ = ack_tx
+                    .send(event_stream_ack(
+                        line_number,
+                        Err(AppError::LimitExceeded(format!(
+                            "Line exceeds {} bytes",
+                            EVENT_STREAM_MAX_LINE_BYTES
+                        ))),
+                    ))
+                    .await;
+                break;
+            }
+        }
+
+        info!(
+            "{}",
+            format!(
+                "Closed event stream for app {} after {} events ({} published)",
+                app_id, line_number, published
+            )
+        );
+    });
+
+    let acks = futures_util::stream::unfold(ack_rx, |mut ack_rx| async move {
+        ack_rx
+            .recv()
+            .await
+            .map(|ack| (Ok::<_, std::convert::Infallible>(ack), ack_rx))
+    });
+
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        axum::body::Body::from_stream(acks),
+    ))
+}
+
 /// GET /apps/{app_id}/channels/{channel_name}
 #[instrument(skip(handler), fields(app_id = %app_id, channel = %channel_name))]
 pub async fn channel(
@@ -646,13 +828,10 @@ pub async fn channel(
     let wants_user_count = info_query_str.wants_user_count();
     let wants_cache_data = info_query_str.wants_cache();
 
-    let socket_count_val;
-    {
-        let mut connection_manager_locked = handler.connection_manager.lock().await;
-        socket_count_val = connection_manager_locked
-            .get_channel_socket_count(&app_id, &channel_name)
-            .await;
-    }
+    let socket_count_val = handler
+        .connection_manager
+        .get_channel_socket_count(&app_id, &channel_name)
+        .await;
 
     let user_count_val = if wants_user_count {
         if channel_name.starts_with("presence-") {
@@ -729,13 +908,10 @@ pub async fn channels(
         .await?
         .ok_or_else(|| AppError::AppNotFound(app_id.clone()))?;
 
-    let channels_map;
-    {
-        let mut connection_manager_locked = handler.connection_manager.lock().await;
-        channels_map = connection_manager_locked
-            .get_channels_with_socket_count(&app_id)
-            .await?;
-    }
+    let channels_map = handler
+        .connection_manager
+        .get_channels_with_socket_count(&app_id)
+        .await?;
 
     let mut channels_info_response_map = HashMap::new();
     for entry in channels_map.iter() {
@@ -777,6 +953,134 @@ pub async fn channels(
     Ok((StatusCode::OK, Json(response_payload)))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct StatsQuery {
+    /// First day (`YYYY-MM-DD`), defaults to `to`
+    pub from: Option<String>,
+    /// Last day (`YYYY-MM-DD`), defaults to today (UTC)
+    pub to: Option<String>,
+}
+
+fn parse_stats_date(value: &str) -> Result<chrono::NaiveDate, AppError> {
+    chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+        AppError::InvalidInput(format!("Invalid date '{}', expected YYYY-MM-DD", value))
+    })
+}
+
+/// GET /apps/{app_id}/stats
+#[instrument(skip(handler), fields(app_id = %app_id))]
+pub async fn app_stats(
+    Path(app_id): Path<String>,
+    Query(query): Query<StatsQuery>,
+    State(handler): State<Arc<ConnectionHandler>>,
+) -> Result<impl IntoResponse, AppError> {
+    let usage_stats = handler
+        .usage_stats
+        .as_ref()
+        .ok_or_else(|| AppError::InvalidInput("Usage stats are not enabled".to_string()))?;
+    handler
+        .app_manager
+        .find_by_id(&app_id)
+        .await?
+        .ok_or_else(|| AppError::AppNotFound(app_id.clone()))?;
+
+    let to = match query.to.as_deref() {
+        Some(to) => parse_stats_date(to)?,
+        None => chrono::Utc::now().date_naive(),
+    };
+    let from = match query.from.as_deref() {
+        Some(from) => parse_stats_date(from)?,
+        None => to,
+    };
+    if from > to {
+        return Err(AppError::InvalidInput(
+            "'from' must not be after 'to'".to_string(),
+        ));
+    }
+    if (to - from).num_days() >= crate::usage_stats::MAX_QUERY_DAYS {
+        return Err(AppError::LimitExceeded(format!(
+            "Stats queries are limited to {} days",
+            crate::usage_stats::MAX_QUERY_DAYS
+        )));
+    }
+
+    let days = usage_stats.query(&app_id, from, to).await?;
+    let total_messages: u64 = days.iter().map(|day| day.total_messages).sum();
+    let peak_connections = days
+        .iter()
+        .map(|day| day.peak_connections)
+        .max()
+        .unwrap_or(0);
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "app_id": app_id,
+            "from": from,
+            "to": to,
+            "total_messages": total_messages,
+            "peak_connections": peak_connections,
+            "days": days,
+        })),
+    ))
+}
+
+/// GET /apps/{app_id}/meta
+/// Server time, protocol versions, features and limits of the app, so SDKs
+/// can discover capabilities instead of hardcoding them
+#[instrument(skip(handler), fields(app_id = %app_id))]
+pub async fn app_meta(
+    Path(app_id): Path<String>,
+    State(handler): State<Arc<ConnectionHandler>>,
+) -> Result<impl IntoResponse, AppError> {
+    let app = handler
+        .app_manager
+        .find_by_id(&app_id)
+        .await?
+        .ok_or_else(|| AppError::AppNotFound(app_id.clone()))?;
+    let now = chrono::Utc::now();
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "app_id": app.id,
+            "server": {
+                "version": env!("CARGO_PKG_VERSION"),
+                "time": now.to_rfc3339(),
+                "time_ms": now.timestamp_millis(),
+                "healthy": handler.adapter_health.is_healthy(),
+            },
+            "protocol_versions": [crate::protocol::constants::PROTOCOL_VERSION],
+            "features": {
+                "client_events": app.enable_client_messages,
+                "user_authentication": app.enable_user_authentication.unwrap_or(false),
+                "encrypted_channels": app.encrypted_channels_enabled(),
+                "cache_channels": app.cache_channels_enabled(),
+                "watchlist_events": app.enable_watchlist_events.unwrap_or(false),
+            },
+            "limits": {
+                "activity_timeout": handler.activity_timeout.for_app(&app),
+                "max_connections": app.max_connections,
+                "max_client_events_per_second": app.max_client_events_per_second,
+                "max_backend_events_per_second": app.max_backend_events_per_second,
+                "max_read_requests_per_second": app.max_read_requests_per_second,
+                "max_presence_members_per_channel": app.max_presence_members_per_channel,
+                "max_presence_member_size_in_kb": app.max_presence_member_size_in_kb,
+                "max_channel_name_length": app
+                    .max_channel_name_length
+                    .unwrap_or(crate::protocol::constants::CHANNEL_NAME_MAX_LENGTH as u32),
+                "max_event_name_length": app
+                    .max_event_name_length
+                    .unwrap_or(DEFAULT_EVENT_NAME_MAX_LENGTH as u32),
+                "max_event_channels_at_once": app.max_event_channels_at_once,
+                "max_event_payload_in_kb": app.max_event_payload_in_kb,
+                "max_event_batch_size": app.max_event_batch_size,
+                "max_channels_per_connection": app.max_channels_per_connection,
+            },
+        })),
+    ))
+}
+
 /// GET /apps/{app_id}/channels/{channel_name}/users
 #[instrument(skip(handler), fields(app_id = %app_id, channel = %channel_name))]
 pub async fn channel_users(
@@ -818,6 +1122,50 @@ pub async fn channel_users(
     Ok((StatusCode::OK, Json(response_payload_val)))
 }
 
+/// Default and maximum number of sockets returned by the sampling endpoint
+const DEFAULT_SOCKET_SAMPLE_SIZE: usize = 50;
+const MAX_SOCKET_SAMPLE_SIZE: usize = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct SocketSampleQuery {
+    #[serde(default)]
+    pub sample: Option<usize>,
+}
+
+/// GET /admin/apps/{app_id}/channels/{channel_name}/sockets
+#[instrument(skip(handler), fields(app_id = %app_id, channel = %channel_name))]
+pub async fn channel_sockets_sample(
+    Path((app_id, channel_name)): Path<(String, String)>,
+    Query(query): Query<SocketSampleQuery>,
+    State(handler): State<Arc<ConnectionHandler>>,
+) -> Result<impl IntoResponse, AppError> {
+    let app = handler
+        .app_manager
+        .find_by_id(&app_id)
+        .await?
+        .ok_or_else(|| AppError::AppNotFound(app_id.clone()))?;
+    validate_channel_name(&app, &channel_name).await?;
+
+    let sample = query
+        .sample
+        .unwrap_or(DEFAULT_SOCKET_SAMPLE_SIZE)
+        .clamp(1, MAX_SOCKET_SAMPLE_SIZE);
+
+    let sockets = handler
+        .connection_manager
+        .get_channel_socket_details(&app_id, &channel_name, sample)
+        .await?;
+    info!(
+        socket_count = sockets.len(),
+        "Sampled sockets for channel '{}'", channel_name
+    );
+
+    Ok((
+        StatusCode::OK,
+        Json(json!({ "sample": sample, "sockets": sockets })),
+    ))
+}
+
 /// POST /apps/{app_id}/users/{user_id}/terminate_connections
 #[instrument(skip(handler), fields(app_id = %app_id, user_id = %user_id))]
 pub async fn terminate_user_connections(
@@ -834,10 +1182,8 @@ pub async fn terminate_user_connections(
         user_id
     );
 
-    let connection_manager_arc = handler.connection_manager.clone();
-    connection_manager_arc
-        .lock()
-        .await
+    handler
+        .connection_manager
         .terminate_connection(&app_id, &user_id)
         .await?;
     info!(
@@ -847,6 +1193,56 @@ pub async fn terminate_user_connections(
     Ok((StatusCode::OK, Json(json!({ "ok": true }))))
 }
 
+/// Default ban duration when the request does not specify one
+const DEFAULT_BAN_TTL_SECONDS: u64 = 3600;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct BanUserRequest {
+    /// Ban duration in seconds
+    #[serde(default)]
+    pub ttl: Option<u64>,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// POST /apps/{app_id}/users/{user_id}/ban
+#[instrument(skip(handler, body), fields(app_id = %app_id, user_id = %user_id))]
+pub async fn ban_user(
+    Path((app_id, user_id)): Path<(String, String)>,
+    State(handler): State<Arc<ConnectionHandler>>,
+    body: axum::body::Bytes,
+) -> Result<impl IntoResponse, AppError> {
+    let app = handler
+        .app_manager
+        .find_by_id(&app_id)
+        .await?
+        .ok_or_else(|| AppError::AppNotFound(app_id.clone()))?;
+
+    let ban_request: BanUserRequest = if body.is_empty() {
+        BanUserRequest::default()
+    } else {
+        serde_json::from_slice(&body)
+            .map_err(|e| AppError::InvalidInput(format!("Invalid ban request body: {}", e)))?
+    };
+    let ttl = ban_request.ttl.unwrap_or(DEFAULT_BAN_TTL_SECONDS);
+    if ttl == 0 {
+        return Err(AppError::InvalidInput(
+            "Ban ttl must be greater than zero".to_string(),
+        ));
+    }
+
+    info!("Banning user_id {} for {} seconds", user_id, ttl);
+    handler
+        .ban_user(&app, &user_id, ttl, ban_request.reason.as_deref())
+        .await
+        .map_err(|e| match e {
+            crate::error::Error::BansUnavailable(msg) => AppError::BansUnavailable(msg),
+            e => AppError::BanFailed(e.to_string()),
+        })?;
+
+    Ok((StatusCode::OK, Json(json!({ "ok": true, "ttl": ttl }))))
+}
+
 /// GET /up/{app_id}
 #[instrument(skip(handler), fields(app_id = %app_id))]
 pub async fn up(
@@ -865,6 +1261,21 @@ pub async fn up(
             app_id
         );
     }
+    if !handler.adapter_health.is_healthy() {
+        let reason = handler
+            .adapter_health
+            .last_error()
+            .unwrap_or_else(|| "unknown".to_string());
+        warn!(
+            "Health check for app_id {} failed, adapter is unhealthy: {}",
+            app_id, reason
+        );
+        let response_val = Response::builder()
+            .status(StatusCode::SERVICE_UNAVAILABLE)
+            .header("X-Health-Check", "ADAPTER_UNHEALTHY")
+            .body(format!("Adapter unhealthy: {}", reason))?;
+        return Ok(response_val);
+    }
     let response_val = Response::builder()
         .status(StatusCode::OK)
         .header("X-Health-Check", "OK")
@@ -872,12 +1283,643 @@ pub async fn up(
     Ok(response_val)
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ProbeQuery {
+    /// Key of the app the probe connects as
+    pub app_key: String,
+}
+
+/// GET /probe/ws
+#[instrument(skip(handler), fields(service = "probe"))]
+pub async fn probe_ws(
+    Query(query): Query<ProbeQuery>,
+    State(handler): State<Arc<ConnectionHandler>>,
+) -> Result<impl IntoResponse, AppError> {
+    let probe = handler.ws_probe.as_ref().ok_or_else(|| {
+        AppError::InvalidInput(
+            "The WebSocket probe is unavailable while SSL is enabled".to_string(),
+        )
+    })?;
+    let report = probe.run(&query.app_key).await;
+    if !report.ok {
+        warn!(
+            "{}",
+            format!(
+                "WebSocket probe failed at {}: {}",
+                report.stage.unwrap_or("unknown"),
+                report.error.as_deref().unwrap_or("unknown error")
+            )
+        );
+    }
+    let status = if report.ok {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    Ok((status, Json(report)))
+}
+
+/// GET /cluster/nodes
+#[instrument(skip(handler), fields(service = "cluster"))]
+pub async fn cluster_nodes(
+    State(handler): State<Arc<ConnectionHandler>>,
+) -> Result<impl IntoResponse, AppError> {
+    let registry = handler
+        .node_registry
+        .as_ref()
+        .ok_or_else(|| AppError::InternalError("Node registry is not initialized".to_string()))?;
+    let nodes = registry.live_nodes().await?;
+    Ok((
+        StatusCode::OK,
+        Json(json!({ "count": nodes.len(), "nodes": nodes })),
+    ))
+}
+
+/// POST /admin/drain
+#[instrument(skip(handler), fields(service = "admin"))]
+pub async fn drain(
+    State(handler): State<Arc<ConnectionHandler>>,
+) -> Result<impl IntoResponse, AppError> {
+    let drain = handler
+        .node_drain
+        .as_ref()
+        .ok_or_else(|| AppError::InternalError("Node drain is not initialized".to_string()))?;
+    let started = drain.start();
+    if started {
+        info!("{}", "Node drain requested through the admin API");
+    }
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(json!({ "draining": true, "started": started })),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BroadcastNoticeRequest {
+    pub message: String,
+    /// Only notify this app's connections; every app when unset
+    #[serde(default)]
+    pub app_id: Option<String>,
+    /// Extra details for clients, e.g. the minimum supported client version
+    #[serde(default)]
+    pub data: Option<Value>,
+}
+
+/// POST /admin/broadcast
+#[instrument(skip(handler, request), fields(service = "admin"))]
+pub async fn broadcast_notice(
+    State(handler): State<Arc<ConnectionHandler>>,
+    Json(request): Json<BroadcastNoticeRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let app_ids = match &request.app_id {
+        Some(app_id) => {
+            handler
+                .app_manager
+                .find_by_id(app_id)
+                .await?
+                .ok_or_else(|| AppError::AppNotFound(app_id.clone()))?;
+            vec![app_id.clone()]
+        }
+        None => handler
+            .app_manager
+            .get_apps()
+            .await?
+            .into_iter()
+            .map(|app| app.id)
+            .collect(),
+    };
+
+    let mut payload = json!({ "message": request.message });
+    if let Some(data) = request.data {
+        payload["data"] = data;
+    }
+    let notice = PusherMessage::server_notice(payload);
+
+    for app_id in &app_ids {
+        handler
+            .connection_manager
+            .send(SERVER_NOTICE_CHANNEL, notice.clone(), None, app_id)
+            .await?;
+    }
+    info!(
+        "{}",
+        format!(
+            "Broadcast server notice to {} apps through the admin API",
+            app_ids.len()
+        )
+    );
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(json!({ "ok": true, "apps": app_ids.len() })),
+    ))
+}
+
+/// Random hex credential for apps created without one
+fn generate_credential() -> String {
+    format!("{:032x}", rand::random::<u128>())
+}
+
+/// Reject a key already used by another app
+async fn ensure_key_unused(
+    handler: &ConnectionHandler,
+    key: &str,
+    app_id: &str,
+) -> Result<(), AppError> {
+    match handler.app_manager.find_by_key(key).await? {
+        Some(other) if other.id != app_id => Err(AppError::InvalidInput(format!(
+            "Key {} is already used by app {}",
+            key, other.id
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// GET /admin/apps
+#[instrument(skip(handler), fields(service = "admin"))]
+pub async fn list_apps(
+    State(handler): State<Arc<ConnectionHandler>>,
+) -> Result<impl IntoResponse, AppError> {
+    let apps = handler.app_manager.get_apps().await?;
+    Ok(Json(json!({ "apps": apps })))
+}
+
+/// POST /admin/apps
+///
+/// Takes the app's JSON config. `id`, `key` and `secret` are generated when
+/// left out.
+#[instrument(skip(handler, body), fields(service = "admin"))]
+pub async fn create_app(
+    State(handler): State<Arc<ConnectionHandler>>,
+    Json(mut body): Json<Value>,
+) -> Result<impl IntoResponse, AppError> {
+    let fields = body
+        .as_object_mut()
+        .ok_or_else(|| AppError::InvalidInput("App must be a JSON object".to_string()))?;
+    for field in ["id", "key", "secret"] {
+        fields
+            .entry(field)
+            .or_insert_with(|| Value::String(generate_credential()));
+    }
+    let app: App = serde_json::from_value(body)
+        .map_err(|e| AppError::InvalidInput(format!("Invalid app: {}", e)))?;
+
+    if handler.app_manager.find_by_id(&app.id).await?.is_some() {
+        return Err(AppError::InvalidInput(format!(
+            "App {} already exists",
+            app.id
+        )));
+    }
+    ensure_key_unused(&handler, &app.key, &app.id).await?;
+    handler
+        .app_manager
+        .create_app(app.clone())
+        .await
+        .map_err(|e| AppError::InvalidInput(e.to_string()))?;
+
+    info!(
+        "{}",
+        format!("Created app {} through the admin API", app.id)
+    );
+    Ok((StatusCode::CREATED, Json(app)))
+}
+
+/// GET /admin/apps/{app_id}
+#[instrument(skip(handler), fields(service = "admin"))]
+pub async fn get_app(
+    Path(app_id): Path<String>,
+    State(handler): State<Arc<ConnectionHandler>>,
+) -> Result<impl IntoResponse, AppError> {
+    let app = handler
+        .app_manager
+        .find_by_id(&app_id)
+        .await?
+        .ok_or_else(|| AppError::AppNotFound(app_id.clone()))?;
+    Ok(Json(app))
+}
+
+/// PUT /admin/apps/{app_id}
+///
+/// Fields in the body replace the app's; fields left out keep their value,
+/// so credentials can be rotated by sending only a new `key` or `secret`.
+#[instrument(skip(handler, body), fields(service = "admin"))]
+pub async fn update_app(
+    Path(app_id): Path<String>,
+    State(handler): State<Arc<ConnectionHandler>>,
+    Json(body): Json<Value>,
+) -> Result<impl IntoResponse, AppError> {
+    let Value::Object(changes) = body else {
+        return Err(AppError::InvalidInput(
+            "App must be a JSON object".to_string(),
+        ));
+    };
+    let current = handler
+        .app_manager
+        .find_by_id(&app_id)
+        .await?
+        .ok_or_else(|| AppError::AppNotFound(app_id.clone()))?;
+
+    let mut merged = serde_json::to_value(&current)?;
+    if let Value::Object(fields) = &mut merged {
+        fields.extend(changes);
+        fields.insert("id".to_string(), Value::String(app_id.clone()));
+    }
+    let app: App = serde_json::from_value(merged)
+        .map_err(|e| AppError::InvalidInput(format!("Invalid app: {}", e)))?;
+
+    if app.key != current.key {
+        ensure_key_unused(&handler, &app.key, &app.id).await?;
+    }
+    handler
+        .app_manager
+        .update_app(app.clone())
+        .await
+        .map_err(|e| AppError::InvalidInput(e.to_string()))?;
+    if current.enabled && !app.enabled {
+        warn!(
+            "{}",
+            format!("Disabled app {}, disconnecting its clients", app.id)
+        );
+        handler.connection_manager.disconnect_app(&app.id).await?;
+    }
+
+    info!(
+        "{}",
+        format!("Updated app {} through the admin API", app.id)
+    );
+    Ok(Json(app))
+}
+
+/// POST /admin/apps/{app_id}/secret/promote
+///
+/// Makes the app's `secondary_secret` its `secret` and clears the secondary,
+/// ending a secret rotation. Signatures made with the old secret stop
+/// validating from then on.
+#[instrument(skip(handler), fields(service = "admin"))]
+pub async fn promote_app_secret(
+    Path(app_id): Path<String>,
+    State(handler): State<Arc<ConnectionHandler>>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut app = handler
+        .app_manager
+        .find_by_id(&app_id)
+        .await?
+        .ok_or_else(|| AppError::AppNotFound(app_id.clone()))?;
+
+    let secondary = app
+        .secondary_secret
+        .take()
+        .filter(|secret| !secret.is_empty())
+        .ok_or_else(|| {
+            AppError::InvalidInput(format!("App {} has no secondary secret to promote", app_id))
+        })?;
+    app.secret = secondary;
+    handler
+        .app_manager
+        .update_app(app.clone())
+        .await
+        .map_err(|e| AppError::InvalidInput(e.to_string()))?;
+
+    warn!(
+        "{}",
+        format!("Promoted the secondary secret of app {} to primary", app.id)
+    );
+    Ok(Json(app))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CacheInvalidationQuery {
+    /// Limit the invalidation to the entries of one channel
+    pub channel: Option<String>,
+}
+
+/// DELETE /admin/apps/{appId}/cache
+///
+/// Removes the app's cached channel entries, or only one channel's with
+/// `?channel=`. User bans are kept.
+/// The app doesn't have to exist, so a deleted app's leftovers can be purged.
+#[instrument(skip(handler), fields(service = "admin"))]
+pub async fn invalidate_app_cache(
+    Path(app_id): Path<String>,
+    Query(query): Query<CacheInvalidationQuery>,
+    State(handler): State<Arc<ConnectionHandler>>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut cache_manager = handler.cache_manager.lock().await;
+    let deleted = match &query.channel {
+        Some(channel) => {
+            cache_manager
+                .delete_by_prefix(&format!("app:{}:channel:{}:", app_id, channel))
+                .await?
+        }
+        None => cache_manager.clear_namespace(&app_id).await?,
+    };
+    drop(cache_manager);
+
+    info!(
+        "{}",
+        format!("Invalidated {} cache entries of app {}", deleted, app_id)
+    );
+    Ok(Json(json!({ "deleted": deleted })))
+}
+
+/// POST /admin/reload
+///
+/// Re-reads the apps of the config file and applies what changed, as SIGHUP
+/// does
+#[instrument(skip(handler), fields(service = "admin"))]
+pub async fn reload_apps(
+    State(handler): State<Arc<ConnectionHandler>>,
+) -> Result<impl IntoResponse, AppError> {
+    let app_reloader = handler.app_reloader.as_ref().ok_or_else(|| {
+        AppError::InvalidInput("Reloading apps requires the memory app manager".to_string())
+    })?;
+    let summary = app_reloader
+        .reload()
+        .await
+        .map_err(|e| AppError::InvalidInput(e.to_string()))?;
+    Ok(Json(summary))
+}
+
+/// DELETE /admin/apps/{app_id}
+#[instrument(skip(handler), fields(service = "admin"))]
+pub async fn delete_app(
+    Path(app_id): Path<String>,
+    State(handler): State<Arc<ConnectionHandler>>,
+) -> Result<impl IntoResponse, AppError> {
+    if handler.app_manager.find_by_id(&app_id).await?.is_none() {
+        return Err(AppError::AppNotFound(app_id));
+    }
+    handler.app_manager.delete_app(&app_id).await?;
+
+    warn!(
+        "{}",
+        format!("Deleted app {} through the admin API", app_id)
+    );
+    Ok(Json(json!({ "ok": true, "id": app_id })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LogFilterUpdate {
+    pub subsystem: String,
+    /// New level (`trace` to `off`); `null` restores the startup filter
+    pub level: Option<String>,
+}
+
+fn log_filters(
+    handler: &ConnectionHandler,
+) -> Result<&Arc<crate::log_filters::LogFilters>, AppError> {
+    handler
+        .log_filters
+        .as_ref()
+        .ok_or_else(|| AppError::InternalError("Log filters are not initialized".to_string()))
+}
+
+/// GET /admin/log-filters
+#[instrument(skip(handler), fields(service = "admin"))]
+pub async fn get_log_filters(
+    State(handler): State<Arc<ConnectionHandler>>,
+) -> Result<impl IntoResponse, AppError> {
+    Ok(Json(log_filters(&handler)?.state()))
+}
+
+/// PUT /admin/log-filters
+#[instrument(skip(handler), fields(service = "admin"))]
+pub async fn update_log_filter(
+    State(handler): State<Arc<ConnectionHandler>>,
+    Json(update): Json<LogFilterUpdate>,
+) -> Result<impl IntoResponse, AppError> {
+    let state = log_filters(&handler)?
+        .set(&update.subsystem, update.level.as_deref())
+        .map_err(|e| AppError::InvalidInput(e.to_string()))?;
+    Ok(Json(state))
+}
+
+/// Default and maximum number of jobs handled per queue admin request
+const DEFAULT_QUEUE_JOBS_LIMIT: usize = 100;
+const MAX_QUEUE_JOBS_LIMIT: usize = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct QueueJobsQuery {
+    pub limit: Option<usize>,
+}
+
+impl QueueJobsQuery {
+    fn limit(&self) -> usize {
+        self.limit
+            .unwrap_or(DEFAULT_QUEUE_JOBS_LIMIT)
+            .min(MAX_QUEUE_JOBS_LIMIT)
+    }
+}
+
+fn webhook_queue_manager(
+    handler: &ConnectionHandler,
+) -> Result<Arc<tokio::sync::Mutex<crate::queue::manager::QueueManager>>, AppError> {
+    handler
+        .webhook_integration
+        .as_ref()
+        .and_then(|integration| integration.queue_manager())
+        .ok_or_else(|| AppError::InvalidInput("Queueing is not enabled".to_string()))
+}
+
+/// GET /admin/queues
+#[instrument(skip(handler), fields(service = "admin"))]
+pub async fn list_queues(
+    State(handler): State<Arc<ConnectionHandler>>,
+) -> Result<impl IntoResponse, AppError> {
+    let queue_manager = webhook_queue_manager(&handler)?;
+    let queues = queue_manager.lock().await.queue_stats().await?;
+    Ok(Json(json!({ "queues": queues })))
+}
+
+/// GET /admin/queues/{queue_name}/jobs
+#[instrument(skip(handler), fields(service = "admin"))]
+pub async fn queue_jobs(
+    Path(queue_name): Path<String>,
+    Query(query): Query<QueueJobsQuery>,
+    State(handler): State<Arc<ConnectionHandler>>,
+) -> Result<impl IntoResponse, AppError> {
+    let queue_manager = webhook_queue_manager(&handler)?;
+    let jobs = queue_manager
+        .lock()
+        .await
+        .peek_jobs(&queue_name, query.limit())
+        .await?
+        .ok_or_else(|| {
+            AppError::InvalidInput("The queue driver can't list pending jobs".to_string())
+        })?;
+    Ok(Json(json!({ "queue": queue_name, "jobs": jobs })))
+}
+
+/// DELETE /admin/queues/{queue_name}/jobs
+#[instrument(skip(handler), fields(service = "admin"))]
+pub async fn purge_queue_jobs(
+    Path(queue_name): Path<String>,
+    State(handler): State<Arc<ConnectionHandler>>,
+) -> Result<impl IntoResponse, AppError> {
+    let queue_manager = webhook_queue_manager(&handler)?;
+    let purged = queue_manager
+        .lock()
+        .await
+        .purge_jobs(&queue_name)
+        .await?
+        .ok_or_else(|| AppError::InvalidInput("The queue driver can't purge jobs".to_string()))?;
+    warn!(
+        "{}",
+        format!(
+            "Purged {} jobs from queue {} through the admin API",
+            purged, queue_name
+        )
+    );
+    Ok(Json(json!({ "queue": queue_name, "purged": purged })))
+}
+
+/// POST /admin/queues/{queue_name}/retry
+#[instrument(skip(handler), fields(service = "admin"))]
+pub async fn retry_queue_jobs(
+    Path(queue_name): Path<String>,
+    Query(query): Query<QueueJobsQuery>,
+    State(handler): State<Arc<ConnectionHandler>>,
+) -> Result<impl IntoResponse, AppError> {
+    let queue_manager = webhook_queue_manager(&handler)?;
+    let retried = queue_manager
+        .lock()
+        .await
+        .retry_delayed_jobs(&queue_name, query.limit())
+        .await?
+        .ok_or_else(|| {
+            AppError::InvalidInput("The queue driver can't retry delayed jobs early".to_string())
+        })?;
+    info!(
+        "{}",
+        format!(
+            "Retried {} delayed jobs of queue {} through the admin API",
+            retried, queue_name
+        )
+    );
+    Ok(Json(json!({ "queue": queue_name, "retried": retried })))
+}
+
+/// GET /admin/queues/{queue_name}/dead-letter
+#[instrument(skip(handler), fields(service = "admin"))]
+pub async fn dead_letter_jobs(
+    Path(queue_name): Path<String>,
+    Query(query): Query<QueueJobsQuery>,
+    State(handler): State<Arc<ConnectionHandler>>,
+) -> Result<impl IntoResponse, AppError> {
+    let queue_manager = webhook_queue_manager(&handler)?;
+    let jobs = queue_manager
+        .lock()
+        .await
+        .dead_letter_jobs(&queue_name, query.limit())
+        .await?;
+    Ok(Json(json!({ "queue": queue_name, "jobs": jobs })))
+}
+
+/// POST /admin/queues/{queue_name}/dead-letter/requeue
+#[instrument(skip(handler), fields(service = "admin"))]
+pub async fn requeue_dead_letters(
+    Path(queue_name): Path<String>,
+    Query(query): Query<QueueJobsQuery>,
+    State(handler): State<Arc<ConnectionHandler>>,
+) -> Result<impl IntoResponse, AppError> {
+    let queue_manager = webhook_queue_manager(&handler)?;
+    let requeued = queue_manager
+        .lock()
+        .await
+        .requeue_dead_letters(&queue_name, query.limit())
+        .await?;
+    info!(
+        "{}",
+        format!(
+            "Requeued {} dead-lettered jobs onto {} through the admin API",
+            requeued, queue_name
+        )
+    );
+    Ok(Json(json!({ "queue": queue_name, "requeued": requeued })))
+}
+
+/// Default and maximum number of connections listed by `GET /admin/top`
+const DEFAULT_TOP_LIMIT: usize = 20;
+const MAX_TOP_LIMIT: usize = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct TopQuery {
+    /// `memory` (default), `channels` or `events`
+    pub by: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// GET /admin/top
+///
+/// The connections on this node using the most memory, subscribed to the
+/// most channels, or exchanging the most messages.
+#[instrument(skip(handler), fields(service = "admin"))]
+pub async fn top_connections(
+    Query(query): Query<TopQuery>,
+    State(handler): State<Arc<ConnectionHandler>>,
+) -> Result<impl IntoResponse, AppError> {
+    let by = query.by.as_deref().unwrap_or("memory");
+    let key: fn(&crate::websocket::ConnectionUsage) -> u64 = match by {
+        "memory" => |usage| usage.memory_bytes as u64,
+        "channels" => |usage| usage.channels as u64,
+        "events" => |usage| usage.messages_sent + usage.messages_received,
+        other => {
+            return Err(AppError::InvalidInput(format!(
+                "Unknown sort '{}', expected memory, channels or events",
+                other
+            )));
+        }
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_TOP_LIMIT).min(MAX_TOP_LIMIT);
+
+    let namespaces = handler.connection_manager.get_namespaces().await?;
+    let namespaces: Vec<_> = namespaces
+        .iter()
+        .map(|entry| entry.value().clone())
+        .collect();
+    let mut connections = Vec::new();
+    for namespace in namespaces {
+        connections.extend(namespace.get_connection_usage().await);
+    }
+    let total = connections.len();
+    connections.sort_unstable_by_key(|usage| std::cmp::Reverse(key(usage)));
+    connections.truncate(limit);
+
+    Ok(Json(json!({
+        "by": by,
+        "total_connections": total,
+        "connections": connections,
+    })))
+}
+
 /// GET /metrics (Prometheus format)
 #[instrument(skip(handler), fields(service = "metrics_exporter"))]
 pub async fn metrics(
     State(handler): State<Arc<ConnectionHandler>>,
+    request_headers: HeaderMap,
 ) -> Result<impl IntoResponse, AppError> {
     info!("{}", "Metrics endpoint called");
+    // Scrapers that want exemplars ask for OpenMetrics explicitly
+    let wants_openmetrics = request_headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|accept| accept.contains("application/openmetrics-text"));
+
+    if wants_openmetrics {
+        if let Some(metrics_arc) = handler.metrics.clone() {
+            let metrics_data_guard = metrics_arc.lock().await;
+            if let Some(openmetrics_str) = metrics_data_guard.get_metrics_as_openmetrics().await {
+                let mut response_headers = HeaderMap::new();
+                response_headers.insert(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static(
+                        "application/openmetrics-text; version=1.0.0; charset=utf-8",
+                    ),
+                );
+                return Ok((StatusCode::OK, response_headers, openmetrics_str));
+            }
+        }
+    }
+
     let plaintext_metrics_str = match handler.metrics.clone() {
         Some(metrics_arc) => {
             let metrics_data_guard = metrics_arc.lock().await;
@@ -0,0 +1,58 @@
+// src/listener.rs
+//! TCP listeners for the HTTP and metrics servers. Depending on
+//! `listen.stack` a server binds its configured host, every IPv4 or IPv6
+//! interface, or separate IPv4 and IPv6 sockets.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use socket2::{Domain, Protocol, Socket, Type};
+use tracing::warn;
+
+use crate::error::Result;
+use crate::options::{ListenConfig, ListenStack};
+
+const BACKLOG: i32 = 1024;
+
+/// Addresses to bind for `host:port`, each with whether it is IPv6-only
+pub fn listen_addrs(host: &str, port: u16, config: &ListenConfig) -> Vec<(SocketAddr, bool)> {
+    let v4_any = SocketAddr::from((Ipv4Addr::UNSPECIFIED, port));
+    let v6_any = SocketAddr::from((Ipv6Addr::UNSPECIFIED, port));
+    match config.stack {
+        ListenStack::Host => {
+            let ip = host
+                .trim_matches(|c| c == '[' || c == ']')
+                .parse::<IpAddr>()
+                .unwrap_or_else(|_| {
+                    warn!(
+                        "{}",
+                        format!("Invalid listen host '{}', using 127.0.0.1", host)
+                    );
+                    IpAddr::V4(Ipv4Addr::LOCALHOST)
+                });
+            vec![(SocketAddr::from((ip, port)), ip.is_ipv6() && config.v6_only)]
+        }
+        ListenStack::V4 => vec![(v4_any, false)],
+        ListenStack::V6 => vec![(v6_any, config.v6_only)],
+        ListenStack::Dual => vec![(v4_any, false), (v6_any, true)],
+    }
+}
+
+/// Bind every address for `host:port`
+pub fn bind(host: &str, port: u16, config: &ListenConfig) -> Result<Vec<tokio::net::TcpListener>> {
+    listen_addrs(host, port, config)
+        .into_iter()
+        .map(|(addr, v6_only)| bind_addr(addr, v6_only))
+        .collect()
+}
+
+fn bind_addr(addr: SocketAddr, v6_only: bool) -> Result<tokio::net::TcpListener> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    if addr.is_ipv6() {
+        socket.set_only_v6(v6_only)?;
+    }
+    socket.set_reuse_address(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(BACKLOG)?;
+    Ok(tokio::net::TcpListener::from_std(socket.into())?)
+}
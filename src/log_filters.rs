@@ -0,0 +1,115 @@
+// src/log_filters.rs
+//! Runtime log level overrides per subsystem. The global filter is installed
+//! with a reload handle, so an operator can turn on verbose adapter logging
+//! during an incident without restarting or enabling debug everywhere.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tracing::info;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::{EnvFilter, fmt, reload};
+
+use crate::error::{Error, Result};
+
+pub type FilterHandle = reload::Handle<EnvFilter, fmt::Formatter>;
+
+/// Subsystems that can be tuned at runtime and the module targets they cover
+const SUBSYSTEMS: &[(&str, &[&str])] = &[
+    ("adapter", &["sockudo::adapter"]),
+    (
+        "ws_handler",
+        &["sockudo::ws_handler", "sockudo::adapter::handler"],
+    ),
+    ("webhook", &["sockudo::webhook"]),
+    ("queue", &["sockudo::queue"]),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogFilterState {
+    /// Filter the process started with (`RUST_LOG` or the debug/prod default)
+    pub base: String,
+    /// Level overrides keyed by subsystem
+    pub overrides: BTreeMap<String, String>,
+    /// Directive string currently applied
+    pub effective: String,
+}
+
+pub struct LogFilters {
+    handle: FilterHandle,
+    base: String,
+    overrides: Mutex<BTreeMap<String, String>>,
+}
+
+impl LogFilters {
+    pub fn new(handle: FilterHandle, base: String) -> Self {
+        Self {
+            handle,
+            base,
+            overrides: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn subsystems() -> impl Iterator<Item = &'static str> {
+        SUBSYSTEMS.iter().map(|(name, _)| *name)
+    }
+
+    pub fn state(&self) -> LogFilterState {
+        let overrides = self.overrides.lock().unwrap().clone();
+        LogFilterState {
+            base: self.base.clone(),
+            effective: self.directives(&overrides),
+            overrides,
+        }
+    }
+
+    /// Set `subsystem` to `level`, or drop its override when `level` is None,
+    /// and reload the global filter
+    pub fn set(&self, subsystem: &str, level: Option<&str>) -> Result<LogFilterState> {
+        if !SUBSYSTEMS.iter().any(|(name, _)| *name == subsystem) {
+            return Err(Error::Config(format!(
+                "Unknown subsystem '{}', expected one of: {}",
+                subsystem,
+                Self::subsystems().collect::<Vec<_>>().join(", ")
+            )));
+        }
+
+        let mut overrides = self.overrides.lock().unwrap();
+        let mut updated = overrides.clone();
+        match level {
+            Some(level) => {
+                let level = level
+                    .parse::<LevelFilter>()
+                    .map_err(|_| Error::Config(format!("Invalid log level '{}'", level)))?;
+                updated.insert(subsystem.to_string(), level.to_string().to_lowercase());
+            }
+            None => {
+                updated.remove(subsystem);
+            }
+        }
+
+        let directives = self.directives(&updated);
+        let filter = EnvFilter::try_new(&directives)
+            .map_err(|e| Error::Config(format!("Invalid log filter '{}': {}", directives, e)))?;
+        self.handle
+            .reload(filter)
+            .map_err(|e| Error::InternalError(format!("Failed to reload log filter: {}", e)))?;
+        *overrides = updated;
+        drop(overrides);
+
+        info!("{}", format!("Log filter changed to '{}'", directives));
+        Ok(self.state())
+    }
+
+    fn directives(&self, overrides: &BTreeMap<String, String>) -> String {
+        let mut directives = vec![self.base.clone()];
+        for (subsystem, level) in overrides {
+            if let Some((_, targets)) = SUBSYSTEMS.iter().find(|(name, _)| name == subsystem) {
+                directives.extend(targets.iter().map(|target| format!("{}={}", target, level)));
+            }
+        }
+        directives.retain(|directive| !directive.is_empty());
+        directives.join(",")
+    }
+}
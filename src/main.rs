@@ -1,17 +1,32 @@
 mod adapter;
 mod app;
+mod backoff;
 mod cache;
+mod canary;
 mod channel;
+mod config_migration;
+mod dns;
 mod error;
+mod fixtures;
 mod http_handler;
+mod listener;
+mod log_filters;
 mod metrics;
 mod middleware;
+mod migrate;
 mod namespace;
 mod options;
+mod probe;
 mod protocol;
 mod queue;
 mod rate_limiter;
+mod redis_client;
+mod screening;
+#[cfg(test)]
+mod testing;
+mod tls;
 mod token;
+mod usage_stats;
 pub mod utils;
 mod watchlist;
 mod webhook;
@@ -19,6 +34,7 @@ mod websocket;
 mod ws_handler;
 
 use std::fs::File;
+use std::future::IntoFuture;
 use std::io::Read;
 use std::net::SocketAddr;
 use std::path::Path;
@@ -32,12 +48,12 @@ use axum::http::header::HeaderName;
 use axum::http::uri::Authority;
 use axum::http::{HeaderValue, StatusCode, Uri};
 use axum::response::Redirect;
-use axum::routing::{get, post};
+use axum::routing::{delete, get, post};
 use axum::{BoxError, Router, ServiceExt, middleware as axum_middleware};
 
 use axum_extra::extract::Host;
 use axum_server::tls_rustls::RustlsConfig;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use error::Error;
 use futures_util::future::join_all;
 use serde_json::{from_str, json}; // Added json import
@@ -47,22 +63,38 @@ use tokio::signal;
 use tokio::sync::{Mutex, RwLock};
 
 // Updated factory imports
+use crate::adapter::ack::MessageAcks;
+use crate::adapter::connection_quota::ConnectionQuota;
+use crate::adapter::deadline::DeliveryDeadline;
+use crate::adapter::drain::NodeDrain;
 use crate::adapter::factory::AdapterFactory;
+use crate::adapter::health::AdapterSupervisor;
+use crate::adapter::qos::DispatchQos;
+use crate::adapter::registry::NodeRegistry;
 use crate::app::factory::AppManagerFactory;
+use crate::backoff::BackoffAdvisor;
 use crate::cache::factory::CacheManagerFactory;
+use crate::canary::Canary;
 use crate::channel::ChannelManager;
+use crate::channel::reclaim::ChannelReclaimer;
 use crate::error::Result;
 use crate::http_handler::{
-    batch_events, channel, channel_users, channels, events, metrics, terminate_user_connections,
-    up, usage,
+    app_meta, app_stats, ban_user, batch_events, broadcast_notice, channel, channel_sockets_sample,
+    channel_users, channels, cluster_nodes, create_app, dead_letter_jobs, delete_app, drain,
+    events, events_stream, get_app, get_log_filters, invalidate_app_cache, list_apps, list_queues,
+    metrics, probe_ws, promote_app_secret, purge_queue_jobs, queue_jobs, reload_apps,
+    requeue_dead_letters, retry_queue_jobs, terminate_user_connections, top_connections, up,
+    update_app, update_log_filter, usage,
 };
 
 use crate::metrics::MetricsFactory;
-use crate::options::{AdapterDriver, QueueDriver, ServerOptions}; // Added QueueDriver
+use crate::options::{AppManagerDriver, CacheDriver, QueueDriver, ServerOptions}; // Added QueueDriver
+use crate::probe::{WsProbe, loopback_addr};
 use crate::queue::manager::{QueueManager, QueueManagerFactory};
-use crate::rate_limiter::RateLimiter;
 use crate::rate_limiter::factory::RateLimiterFactory;
-use crate::rate_limiter::middleware::IpKeyExtractor;
+use crate::rate_limiter::middleware::{CompositeKeyExtractor, IpKeyExtractor, RoutePolicy};
+use crate::rate_limiter::{ConnectRateLimit, RateLimiter};
+use crate::redis_client::RedisAuth;
 use crate::webhook::integration::{BatchingConfig, WebhookConfig, WebhookIntegration};
 use crate::ws_handler::handle_ws_upgrade;
 use tower_http::cors::{AllowOrigin, CorsLayer};
@@ -70,23 +102,24 @@ use tower_http::cors::{AllowOrigin, CorsLayer};
 use tracing::{error, info, level_filters::LevelFilter, warn}; // Added LevelFilter
 use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 
-// Import concrete adapter types for downcasting if set_metrics is specific
 use crate::adapter::Adapter;
 use crate::adapter::ConnectionHandler;
-use crate::adapter::local_adapter::LocalAdapter;
-use crate::adapter::nats_adapter::NatsAdapter;
-use crate::adapter::redis_adapter::RedisAdapter;
-use crate::adapter::redis_cluster_adapter::RedisClusterAdapter;
 use crate::app::auth::AuthValidator;
 use crate::app::config::App;
 // AppManager trait and concrete types
 use crate::app::manager::AppManager;
+use crate::app::reload::AppReloader;
 // CacheManager trait and concrete types
+use crate::cache::instrumented_cache_manager::InstrumentedCacheManager;
 use crate::cache::manager::CacheManager;
 use crate::cache::memory_cache_manager::MemoryCacheManager; // Import for fallback
 // MetricsInterface trait
+use crate::log_filters::LogFilters;
 use crate::metrics::MetricsInterface;
-use crate::middleware::pusher_api_auth_middleware;
+use crate::middleware::{admin_auth_middleware, pusher_api_auth_middleware};
+use crate::screening::ConnectionScreening;
+use crate::tls::HostAppBindings;
+use crate::usage_stats::UsageStats;
 use crate::webhook::types::Webhook;
 use crate::websocket::WebSocketRef;
 
@@ -95,7 +128,7 @@ use crate::websocket::WebSocketRef;
 struct ServerState {
     app_manager: Arc<dyn AppManager + Send + Sync>,
     channel_manager: Arc<RwLock<ChannelManager>>,
-    connection_manager: Arc<Mutex<Box<dyn Adapter + Send + Sync>>>,
+    connection_manager: Arc<dyn Adapter + Send + Sync>,
     auth_validator: Arc<AuthValidator>,
     cache_manager: Arc<Mutex<dyn CacheManager + Send + Sync>>,
     queue_manager: Option<Arc<QueueManager>>,
@@ -103,6 +136,8 @@ struct ServerState {
     metrics: Option<Arc<Mutex<dyn MetricsInterface + Send + Sync>>>,
     running: Arc<AtomicBool>,
     http_api_rate_limiter: Option<Arc<dyn RateLimiter + Send + Sync>>,
+    /// Limiters of the routes with limits of their own
+    route_rate_limiters: Vec<RoutePolicy>,
     debug_enabled: bool,
 }
 
@@ -116,58 +151,211 @@ struct SockudoServer {
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
-    #[arg(short, long)]
+    #[arg(short, long, global = true, default_value = "")]
     config: String,
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-impl SockudoServer {
-    fn get_http_addr(&self) -> SocketAddr {
-        format!("{}:{}", self.config.host, self.config.port)
-            .parse()
-            .unwrap_or_else(|_| "127.0.0.1:6001".parse().unwrap())
-    }
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Config file tools
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Write signed request, channel auth and webhook examples for the
+    /// configured apps, for testing SDKs in other languages
+    Fixtures {
+        /// Only generate fixtures for this app ID
+        #[arg(long)]
+        app: Option<String>,
+        /// File to write the fixtures to
+        #[arg(short, long, default_value = "fixtures.json")]
+        output: String,
+    },
+    /// Move apps over from Pusher or soketi
+    #[command(subcommand)]
+    Migrate(MigrateCommand),
+}
 
-    fn get_metrics_addr(&self) -> SocketAddr {
-        format!("{}:{}", self.config.metrics.host, self.config.metrics.port)
-            .parse()
-            .unwrap_or_else(|_| "127.0.0.1:9601".parse().unwrap())
+#[derive(Subcommand, Debug)]
+enum MigrateCommand {
+    /// Convert the apps of a soketi config file or a Pusher export, with
+    /// their webhooks, into Sockudo apps
+    Import {
+        #[arg(long, value_enum)]
+        from: migrate::MigrationSource,
+        /// soketi config file or Pusher export to read
+        #[arg(long)]
+        source: String,
+        /// Write the apps as an `app_manager` config section to this file
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Create or update the apps through the configured app manager
+        #[arg(long)]
+        apply: bool,
+    },
+    /// Publish test events to the old platform and to Sockudo, and compare
+    /// what subscribers on each side receive
+    Verify {
+        #[arg(long, value_enum)]
+        from: migrate::MigrationSource,
+        #[arg(long)]
+        source: String,
+        /// `host:port` of the Sockudo node to check
+        #[arg(long)]
+        target: String,
+        /// `host:port` of the soketi server; Pusher apps use their cluster
+        #[arg(long)]
+        legacy: Option<String>,
+        /// Only verify this app ID
+        #[arg(long)]
+        app: Option<String>,
+        #[arg(long, default_value = "sockudo-migration-check")]
+        channel: String,
+        /// Number of events to publish per app
+        #[arg(long, default_value_t = 20)]
+        events: u32,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// Rewrite deprecated fields in the config file under their current
+    /// names, keeping the original as `<file>.bak`
+    Migrate {
+        /// Write the migrated config here instead of replacing the file
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+}
+
+impl SockudoServer {
+    /// Bind the metrics listeners and serve them in the background
+    fn spawn_metrics_server(&self, metrics_router: Router) {
+        let listeners = match listener::bind(
+            &self.config.metrics.host,
+            self.config.metrics.port,
+            &self.config.listen,
+        ) {
+            Ok(listeners) => listeners,
+            Err(e) => {
+                warn!(
+                    "Failed to bind metrics server on {}:{}: {}. Metrics will not be available.",
+                    self.config.metrics.host, self.config.metrics.port, e
+                );
+                return;
+            }
+        };
+        for metrics_listener in listeners {
+            if let Ok(addr) = metrics_listener.local_addr() {
+                info!("Metrics server listening on http://{}", addr);
+            }
+            let metrics_router_clone = metrics_router.clone(); // Clone for the new task
+            tokio::spawn(async move {
+                if let Err(e) =
+                    axum::serve(metrics_listener, metrics_router_clone.into_make_service()).await
+                {
+                    error!("Metrics server error: {}", e);
+                }
+            });
+        }
     }
 
-    async fn new(config: ServerOptions) -> Result<Self> {
+    async fn new(
+        config: ServerOptions,
+        log_filters: Option<Arc<LogFilters>>,
+        config_path: String,
+    ) -> Result<Self> {
         let debug_enabled = config.debug;
         info!(
             "Initializing Sockudo server with new configuration... Debug mode: {}",
             debug_enabled
         );
 
+        dns::init(&config.dns);
+
         let app_manager = AppManagerFactory::create(&config.app_manager, &config.database).await?;
         info!(
             "AppManager initialized with driver: {:?}",
             config.app_manager.driver
         );
 
-        let connection_manager_box =
-            AdapterFactory::create(&config.adapter, &config.database, debug_enabled).await?;
-        let connection_manager_arc = Arc::new(Mutex::new(connection_manager_box));
+        let dispatch_qos = if config.adapter.dispatch_qos.enabled {
+            info!("{}", "Dispatch QoS enabled");
+            Some(Arc::new(DispatchQos::new(
+                config.adapter.dispatch_qos.clone(),
+            )))
+        } else {
+            None
+        };
+        let delivery_deadline =
+            if config.metrics.enabled && config.metrics.delivery_deadline.enabled {
+                info!(
+                    "{}",
+                    format!(
+                        "Delivery deadline SLO enabled: {}ms",
+                        config.metrics.delivery_deadline.deadline_ms
+                    )
+                );
+                Some(Arc::new(DeliveryDeadline::new(
+                    &config.metrics.delivery_deadline,
+                )))
+            } else {
+                None
+            };
+        let message_acks = if config.adapter.message_acks.enabled {
+            info!(
+                "{}",
+                format!(
+                    "Message acknowledgement required on: {}",
+                    config.adapter.message_acks.channels.join(", ")
+                )
+            );
+            Some(Arc::new(MessageAcks::new(
+                config.adapter.message_acks.clone(),
+            )))
+        } else {
+            None
+        };
+        let connection_manager_arc = AdapterFactory::create(
+            &config.adapter,
+            &config.database,
+            debug_enabled,
+            dispatch_qos.clone(),
+            delivery_deadline.clone(),
+            message_acks.clone(),
+        )
+        .await?;
         info!(
             "Adapter initialized with driver: {:?}",
             config.adapter.driver
         );
+        // Apps disabled on any node are dropped from this node's app cache
+        connection_manager_arc.set_app_manager(app_manager.clone());
 
-        let cache_manager =
-            CacheManagerFactory::create(&config.cache, &config.database.redis, debug_enabled)
-                .await
-                .unwrap_or_else(|e| {
-                    warn!(
-                        "CacheManagerFactory creation failed: {}. Using a NoOp (Memory) Cache.",
-                        e
-                    );
-                    let fallback_cache_options = config.cache.memory.clone();
-                    Arc::new(Mutex::new(MemoryCacheManager::new(
-                        "fallback_cache".to_string(),
-                        fallback_cache_options,
-                    )))
-                });
+        let cache_manager = CacheManagerFactory::create(
+            &config.cache,
+            &config.database.redis,
+            &config.adapter.cluster,
+            debug_enabled,
+        )
+        .await
+        .unwrap_or_else(|e| {
+            if config.cache.driver == CacheDriver::None {
+                info!("{}. Using a NoOp (Memory) Cache.", e);
+            } else {
+                // Entries the configured driver would have kept are lost on restart
+                error!(
+                    "Failed to create the {:?} cache manager: {}. Falling back to an in-memory cache.",
+                    config.cache.driver, e
+                );
+            }
+            let fallback_cache_options = config.cache.memory.clone();
+            Arc::new(Mutex::new(MemoryCacheManager::new(
+                "fallback_cache".to_string(),
+                fallback_cache_options,
+            )))
+        });
         info!(
             "CacheManager initialized with driver: {:?}",
             config.cache.driver
@@ -186,7 +374,7 @@ impl SockudoServer {
             match MetricsFactory::create(
                 config.metrics.driver.as_ref(),
                 config.metrics.port,
-                Some(&config.metrics.prometheus.prefix),
+                &config.metrics.prometheus,
             )
             .await
             {
@@ -204,6 +392,14 @@ impl SockudoServer {
             None
         };
 
+        let cache_manager: Arc<Mutex<dyn CacheManager + Send + Sync>> = match &metrics {
+            Some(metrics) => Arc::new(Mutex::new(InstrumentedCacheManager::new(
+                cache_manager,
+                metrics.clone(),
+            ))),
+            None => cache_manager,
+        };
+
         let http_api_rate_limiter_instance = if config.rate_limiter.enabled {
             RateLimiterFactory::create(
                 &config.rate_limiter,
@@ -226,6 +422,30 @@ impl SockudoServer {
             config.rate_limiter.enabled, config.rate_limiter.driver
         );
 
+        let mut route_rate_limiters = Vec::new();
+        if config.rate_limiter.enabled {
+            for (pattern, limit) in &config.rate_limiter.routes {
+                let limiter = RateLimiterFactory::create_for(
+                    &config.rate_limiter,
+                    limit,
+                    &config.database.redis,
+                    debug_enabled,
+                )
+                .await?;
+                info!(
+                    "{}",
+                    format!(
+                        "Rate limiting {} to {} requests per {}s",
+                        pattern, limit.max_requests, limit.window_seconds
+                    )
+                );
+                route_rate_limiters.push(RoutePolicy {
+                    pattern: pattern.clone(),
+                    limiter,
+                });
+            }
+        }
+
         let owned_default_queue_redis_url: String;
         let queue_redis_url_arg: Option<&str>;
 
@@ -302,8 +522,10 @@ impl SockudoServer {
             match QueueManagerFactory::create(
                 config.queue.driver.as_ref(),
                 queue_redis_url_or_nodes.as_deref(),
+                &RedisAuth::from_connection(&config.database.redis),
                 Some(queue_prefix),
                 Some(queue_concurrency),
+                &config.queue,
             )
             .await
             {
@@ -312,7 +534,13 @@ impl SockudoServer {
                         "Queue manager initialized with driver: {:?}",
                         config.queue.driver
                     );
-                    Some(Arc::new(QueueManager::new(queue_driver_impl)))
+                    Some(Arc::new(
+                        QueueManager::new(queue_driver_impl)
+                            .with_shutdown_grace_period(Duration::from_secs(
+                                config.shutdown_grace_period,
+                            ))
+                            .with_autoscaling(&config.queue.autoscaling),
+                    ))
                 }
                 Err(e) => {
                     warn!(
@@ -337,13 +565,21 @@ impl SockudoServer {
             batching: BatchingConfig {
                 enabled: config.webhooks.batching.enabled,
                 duration: config.webhooks.batching.duration,
+                max_batch_size: config.webhooks.batching.max_batch_size,
+                max_payload_bytes: config.webhooks.batching.max_payload_bytes,
             },
             queue_driver: config.queue.driver.as_ref().to_string(),
             redis_url: Some(webhook_redis_url),
+            redis_auth: RedisAuth::from_connection(&config.database.redis),
             redis_prefix: Some(config.database.redis.key_prefix.clone() + "webhooks:"), // Ensure key_prefix exists
             redis_concurrency: Some(config.queue.redis.concurrency as usize),
+            queue: config.queue.clone(),
             process_id: config.instance.process_id.clone(),
             debug: config.debug,
+            shutdown_grace_period: Duration::from_secs(config.shutdown_grace_period),
+            retry: config.webhooks.retry.clone(),
+            circuit_breaker: config.webhooks.circuit_breaker.clone(),
+            tls: config.webhooks.tls.clone(),
         };
 
         let webhook_integration = match WebhookIntegration::new(
@@ -381,11 +617,16 @@ impl SockudoServer {
             webhooks_integration: webhook_integration.clone(),
             metrics: metrics.clone(),
             running: Arc::new(AtomicBool::new(true)),
-            http_api_rate_limiter: Some(http_api_rate_limiter_instance.clone()),
+            // Left unset when rate limiting is off, so per-app limits are off too
+            http_api_rate_limiter: config
+                .rate_limiter
+                .enabled
+                .then(|| http_api_rate_limiter_instance.clone()),
+            route_rate_limiters,
             debug_enabled,
         };
 
-        let handler = Arc::new(ConnectionHandler::new(
+        let mut handler = ConnectionHandler::new(
             state.app_manager.clone(),
             state.channel_manager.clone(),
             state.connection_manager.clone(),
@@ -393,58 +634,165 @@ impl SockudoServer {
             state.metrics.clone(),
             Some(webhook_integration), // Pass the (potentially disabled) webhook_integration
             state.http_api_rate_limiter.clone(),
+        );
+        handler.activity_timeout = config.activity_timeout.clone();
+        handler.node_registry = Some(Arc::new(NodeRegistry::new(
+            state.connection_manager.clone(),
+            config.instance.process_id.clone(),
+            config.adapter.node_registry.clone(),
+        )));
+        handler.backoff = Arc::new(BackoffAdvisor::new(config.backoff.clone()));
+        handler.connection_quota = Arc::new(ConnectionQuota::new(
+            state.connection_manager.clone(),
+            &config.adapter.connection_quota,
         ));
+        handler.cache_channel_ttl = config.channel_limits.cache_ttl;
+        handler.client_event_rate_limit = config.rate_limiter.client_event_rate_limit.clone();
+        handler.node_drain = Some(Arc::new(NodeDrain::new(
+            state.connection_manager.clone(),
+            config.drain.clone(),
+            handler.backoff.clone(),
+        )));
+        handler.admin_token = config.admin.token.clone();
+        handler.log_filters = log_filters;
+        if config.usage_stats.enabled {
+            let usage_stats = Arc::new(UsageStats::new(
+                state.cache_manager.clone(),
+                state.connection_manager.clone(),
+                config.usage_stats.clone(),
+            ));
+            usage_stats.start();
+            handler.usage_stats = Some(usage_stats);
+        }
+        handler.ip_extractor = IpKeyExtractor::new(
+            config
+                .rate_limiter
+                .websocket_rate_limit
+                .trust_hops
+                .unwrap_or(0) as usize,
+        );
+        if config.rate_limiter.enabled {
+            match RateLimiterFactory::create_for(
+                &config.rate_limiter,
+                &config.rate_limiter.websocket_rate_limit,
+                &config.database.redis,
+                debug_enabled,
+            )
+            .await
+            {
+                Ok(limiter) => {
+                    handler.connect_rate_limit = Some(ConnectRateLimit::new(
+                        limiter,
+                        &config.rate_limiter.websocket_rate_limit,
+                    ));
+                }
+                Err(e) => error!(
+                    "{}",
+                    format!(
+                        "Failed to initialize WebSocket connection rate limiter: {}. Connections will not be rate limited.",
+                        e
+                    )
+                ),
+            }
+        }
+        if config.screening.enabled {
+            handler.screening = Some(ConnectionScreening::from_config(&config.screening)?);
+            info!("{}", "Connection screening enabled");
+        }
+        if config.channel_reclaim.enabled {
+            let reclaimer = Arc::new(ChannelReclaimer::new(
+                state.connection_manager.clone(),
+                state.cache_manager.clone(),
+                config.channel_reclaim.clone(),
+            ));
+            reclaimer.start();
+            handler.channel_reclaimer = Some(reclaimer);
+        }
+        if let Some(acks) = &message_acks {
+            acks.clone().start();
+            handler.message_acks = Some(acks.clone());
+        }
+        if config.canary.enabled {
+            if config.ssl.enabled && config.canary.target.is_none() {
+                warn!(
+                    "{}",
+                    "Canary disabled: it cannot reach this node over SSL, set canary.target to a plain HTTP listener"
+                );
+            } else {
+                let local_authority =
+                    loopback_addr(&config.host, config.port, &config.listen).to_string();
+                let canary = Arc::new(Canary::new(
+                    state.app_manager.clone(),
+                    metrics.clone(),
+                    config.canary.clone(),
+                    local_authority,
+                ));
+                canary.start();
+                info!("{}", "Synthetic canary enabled");
+            }
+        }
+        if config.ssl.enabled {
+            handler.host_apps = HostAppBindings::from_config(&config.ssl).map(Arc::new);
+        } else {
+            handler.ws_probe = Some(Arc::new(WsProbe::new(
+                &config.host,
+                config.port,
+                &config.listen,
+            )));
+        }
+        // Apps of other drivers live in their store, where deleting one
+        // because it left the config file would lose it for good
+        if config.app_manager.driver == AppManagerDriver::Memory {
+            handler.app_reloader = Some(Arc::new(AppReloader::new(
+                state.app_manager.clone(),
+                state.connection_manager.clone(),
+                config_path,
+                &config.app_manager.array.apps,
+            )));
+        }
+        let handler = Arc::new(handler);
 
         // Set metrics for adapters
         if let Some(metrics_instance_arc) = &metrics {
-            let mut connection_manager_guard = state.connection_manager.lock().await;
-            // Get a mutable reference to the trait object inside the MutexGuard
-            let adapter_as_any: &mut dyn std::any::Any = connection_manager_guard.as_any_mut();
-
-            match config.adapter.driver {
-                AdapterDriver::Redis => {
-                    if let Some(adapter_mut) = adapter_as_any.downcast_mut::<RedisAdapter>() {
-                        adapter_mut
-                            .set_metrics(metrics_instance_arc.clone())
-                            .await
-                            .ok(); // .ok() converts Result to Option, ignoring error
-                        info!("Set metrics for RedisAdapter");
-                    } else {
-                        warn!("Failed to downcast to RedisAdapter for metrics setup");
-                    }
-                }
-                AdapterDriver::Nats => {
-                    if let Some(adapter_mut) = adapter_as_any.downcast_mut::<NatsAdapter>() {
-                        adapter_mut
-                            .set_metrics(metrics_instance_arc.clone())
-                            .await
-                            .ok();
-                        info!("Set metrics for NatsAdapter");
-                    } else {
-                        warn!("Failed to downcast to NatsAdapter for metrics setup");
-                    }
-                }
-                AdapterDriver::RedisCluster => {
-                    // Assuming RedisClusterAdapter also has a set_metrics method
-                    if let Some(adapter_mut) = adapter_as_any.downcast_mut::<RedisClusterAdapter>()
-                    {
-                        // adapter_mut.set_metrics(metrics_instance_arc.clone()).await.ok(); // Uncomment if method exists
-                        info!(
-                            "Metrics setup for RedisClusterAdapter (call set_metrics if available)"
-                        );
-                    } else {
-                        warn!("Failed to downcast to RedisClusterAdapter for metrics setup");
-                    }
-                }
-                AdapterDriver::Local => {
-                    // Assuming LocalAdapter might have a set_metrics method
-                    if let Some(adapter_mut) = adapter_as_any.downcast_mut::<LocalAdapter>() {
-                        // adapter_mut.set_metrics(metrics_instance_arc.clone()).await.ok(); // Uncomment if method exists
-                        info!("Metrics setup for LocalAdapter (call set_metrics if applicable)");
-                    } else {
-                        warn!("Failed to downcast to LocalAdapter for metrics setup");
-                    }
-                }
+            if let Err(e) = state
+                .connection_manager
+                .set_metrics(metrics_instance_arc.clone())
+                .await
+            {
+                warn!(
+                    "Failed to set metrics for {:?} adapter: {}",
+                    config.adapter.driver, e
+                );
+            } else {
+                info!("Set metrics for {:?} adapter", config.adapter.driver);
+            }
+            if let Some(qos) = &dispatch_qos {
+                qos.set_metrics(metrics_instance_arc.clone()).await;
+            }
+            if let Some(deadline) = &delivery_deadline {
+                deadline.set_metrics(metrics_instance_arc.clone()).await;
+            }
+            if let Some(acks) = &message_acks {
+                acks.set_metrics(metrics_instance_arc.clone()).await;
+            }
+            if let Some(queue_manager) = &state.queue_manager {
+                queue_manager
+                    .set_metrics(metrics_instance_arc.clone())
+                    .await;
+            }
+            if let Some(integration) = &handler.webhook_integration {
+                integration.set_metrics(metrics_instance_arc.clone()).await;
+            }
+            if let Some(queue_manager) = handler
+                .webhook_integration
+                .as_ref()
+                .and_then(|integration| integration.queue_manager())
+            {
+                queue_manager
+                    .lock()
+                    .await
+                    .set_metrics(metrics_instance_arc.clone())
+                    .await;
             }
         }
         Ok(Self {
@@ -460,10 +808,54 @@ impl SockudoServer {
         self.state.app_manager.init().await?; // Assuming AppManager has an init method
 
         // Initialize ConnectionManager (Adapter)
-        {
-            // Scope for MutexGuard
-            let mut connection_manager = self.state.connection_manager.lock().await;
-            connection_manager.init().await; // Assuming Adapter has an init method
+        self.state.connection_manager.init().await;
+
+        // Watch the adapter connection and reconnect it when it drops
+        AdapterSupervisor::new(
+            self.state.connection_manager.clone(),
+            self.handler.adapter_health.clone(),
+            self.state.metrics.clone(),
+            self.config.adapter.health_check.clone(),
+        )
+        .start();
+
+        // Announce this node to the rest of the cluster
+        if let Some(registry) = &self.handler.node_registry {
+            registry.clone().start();
+        }
+
+        // SIGUSR1 drains the node ahead of a shutdown
+        #[cfg(unix)]
+        if let Some(node_drain) = self.handler.node_drain.clone() {
+            match signal::unix::signal(signal::unix::SignalKind::user_defined1()) {
+                Ok(mut drain_signal) => {
+                    tokio::spawn(async move {
+                        while drain_signal.recv().await.is_some() {
+                            info!("SIGUSR1 received, draining node...");
+                            node_drain.start();
+                        }
+                    });
+                }
+                Err(e) => warn!("Failed to install SIGUSR1 handler: {}", e),
+            }
+        }
+
+        // SIGHUP reloads the apps of the config file
+        #[cfg(unix)]
+        if let Some(app_reloader) = self.handler.app_reloader.clone() {
+            match signal::unix::signal(signal::unix::SignalKind::hangup()) {
+                Ok(mut reload_signal) => {
+                    tokio::spawn(async move {
+                        while reload_signal.recv().await.is_some() {
+                            info!("SIGHUP received, reloading apps...");
+                            if let Err(e) = app_reloader.reload().await {
+                                error!("{}", format!("Failed to reload apps: {}", e));
+                            }
+                        }
+                    });
+                }
+                Err(e) => warn!("Failed to install SIGHUP handler: {}", e),
+            }
         }
 
         // Register apps from configuration
@@ -583,6 +975,14 @@ impl SockudoServer {
                         .parse()
                         .unwrap_or(false),
                 ),
+                enable_cache_channels: std::env::var("SOCKUDO_DEFAULT_APP_ENABLE_CACHE_CHANNELS")
+                    .ok()
+                    .and_then(|v| v.parse().ok()),
+                enable_encrypted_channels: std::env::var(
+                    "SOCKUDO_DEFAULT_APP_ENABLE_ENCRYPTED_CHANNELS",
+                )
+                .ok()
+                .and_then(|v| v.parse().ok()),
                 webhooks: None,
                 max_backend_events_per_second: Some(
                     std::env::var("SOCKUDO_DEFAULT_APP_MAX_BACKEND_EVENTS_PER_SECOND")
@@ -596,6 +996,25 @@ impl SockudoServer {
                         .parse()
                         .unwrap_or(false),
                 ),
+                activity_timeout: std::env::var("SOCKUDO_DEFAULT_APP_ACTIVITY_TIMEOUT")
+                    .ok()
+                    .and_then(|v| v.parse().ok()),
+                secondary_secret: std::env::var("SOCKUDO_DEFAULT_APP_SECONDARY_SECRET").ok(),
+                max_channels_per_connection: std::env::var(
+                    "SOCKUDO_DEFAULT_APP_MAX_CHANNELS_PER_CONNECTION",
+                )
+                .ok()
+                .and_then(|v| v.parse().ok()),
+                max_api_requests_per_second: std::env::var(
+                    "SOCKUDO_DEFAULT_APP_MAX_API_REQUESTS_PER_SECOND",
+                )
+                .ok()
+                .and_then(|v| v.parse().ok()),
+                max_connections_per_second: std::env::var(
+                    "SOCKUDO_DEFAULT_APP_MAX_CONNECTIONS_PER_SECOND",
+                )
+                .ok()
+                .and_then(|v| v.parse().ok()),
             };
             match self.state.app_manager.create_app(default_app).await {
                 Ok(_) => info!("Successfully registered demo app"),
@@ -710,18 +1129,22 @@ impl SockudoServer {
                     .api_rate_limit
                     .trust_hops
                     .unwrap_or(0) as usize;
-                let ip_key_extractor = IpKeyExtractor::new(trust_hops);
+                let ip_key_extractor = IpKeyExtractor::new(trust_hops)
+                    .with_ipv6_prefix(self.config.rate_limiter.api_rate_limit.ipv6_prefix_length);
+                let key_by = self.config.rate_limiter.api_rate_limit.key_by.clone();
 
                 info!(
-                    "Applying custom rate limiting middleware with trust_hops: {}",
-                    trust_hops
+                    "Applying custom rate limiting middleware with trust_hops: {}, keyed by: {:?}",
+                    trust_hops, key_by
                 );
                 Some(
                     crate::rate_limiter::middleware::RateLimitLayer::with_options(
                         rate_limiter_instance.clone(),
-                        ip_key_extractor,
+                        CompositeKeyExtractor::new(ip_key_extractor, key_by),
                         options,
-                    ),
+                    )
+                    .with_app_overrides(self.state.app_manager.clone())
+                    .with_route_policies(self.state.route_rate_limiters.clone()),
                 )
             } else {
                 warn!(
@@ -736,58 +1159,156 @@ impl SockudoServer {
 
         let mut router = Router::new()
             .route("/app/{appKey}", get(handle_ws_upgrade)) // Corrected Axum path param syntax
+            // Authenticates itself: the auth middleware would buffer the endless body
+            .route("/apps/{appId}/events/stream", get(events_stream))
+            .route("/usage", get(usage))
+            .route("/cluster/nodes", get(cluster_nodes))
             .route(
-                "/apps/{appId}/events",
-                post(events).route_layer(axum_middleware::from_fn_with_state(
+                "/admin/log-filters",
+                get(get_log_filters).put(update_log_filter).route_layer(
+                    axum_middleware::from_fn_with_state(
+                        self.handler.clone(),
+                        admin_auth_middleware,
+                    ),
+                ),
+            )
+            .route(
+                "/admin/queues",
+                get(list_queues).route_layer(axum_middleware::from_fn_with_state(
                     self.handler.clone(),
-                    pusher_api_auth_middleware,
+                    admin_auth_middleware,
                 )),
             )
             .route(
-                "/apps/{appId}/batch_events",
-                post(batch_events).route_layer(axum_middleware::from_fn_with_state(
+                "/admin/queues/{queueName}/jobs",
+                get(queue_jobs).delete(purge_queue_jobs).route_layer(
+                    axum_middleware::from_fn_with_state(
+                        self.handler.clone(),
+                        admin_auth_middleware,
+                    ),
+                ),
+            )
+            .route(
+                "/admin/queues/{queueName}/retry",
+                post(retry_queue_jobs).route_layer(axum_middleware::from_fn_with_state(
                     self.handler.clone(),
-                    pusher_api_auth_middleware,
+                    admin_auth_middleware,
                 )),
             )
             .route(
-                "/apps/{appId}/channels",
-                get(channels).route_layer(axum_middleware::from_fn_with_state(
+                "/admin/queues/{queueName}/dead-letter",
+                get(dead_letter_jobs).route_layer(axum_middleware::from_fn_with_state(
                     self.handler.clone(),
-                    pusher_api_auth_middleware,
+                    admin_auth_middleware,
                 )),
             )
             .route(
-                "/apps/{appId}/channels/{channelName}",
-                get(channel).route_layer(axum_middleware::from_fn_with_state(
+                "/admin/queues/{queueName}/dead-letter/requeue",
+                post(requeue_dead_letters).route_layer(axum_middleware::from_fn_with_state(
                     self.handler.clone(),
-                    pusher_api_auth_middleware,
+                    admin_auth_middleware,
                 )),
             )
             .route(
-                "/apps/{appId}/channels/{channelName}/users",
-                get(channel_users).route_layer(axum_middleware::from_fn_with_state(
+                "/admin/top",
+                get(top_connections).route_layer(axum_middleware::from_fn_with_state(
                     self.handler.clone(),
-                    pusher_api_auth_middleware,
+                    admin_auth_middleware,
                 )),
             )
             .route(
-                "/apps/{appId}/users/{userId}/terminate_connections",
-                post(terminate_user_connections).route_layer(axum_middleware::from_fn_with_state(
+                "/admin/apps",
+                get(list_apps)
+                    .post(create_app)
+                    .route_layer(axum_middleware::from_fn_with_state(
+                        self.handler.clone(),
+                        admin_auth_middleware,
+                    )),
+            )
+            .route(
+                "/admin/apps/{appId}",
+                get(get_app).put(update_app).delete(delete_app).route_layer(
+                    axum_middleware::from_fn_with_state(
+                        self.handler.clone(),
+                        admin_auth_middleware,
+                    ),
+                ),
+            )
+            .route(
+                "/admin/apps/{appId}/channels/{channelName}/sockets",
+                get(channel_sockets_sample).route_layer(axum_middleware::from_fn_with_state(
                     self.handler.clone(),
-                    pusher_api_auth_middleware,
+                    admin_auth_middleware,
+                )),
+            )
+            .route(
+                "/admin/apps/{appId}/cache",
+                delete(invalidate_app_cache).route_layer(axum_middleware::from_fn_with_state(
+                    self.handler.clone(),
+                    admin_auth_middleware,
+                )),
+            )
+            .route(
+                "/admin/reload",
+                post(reload_apps).route_layer(axum_middleware::from_fn_with_state(
+                    self.handler.clone(),
+                    admin_auth_middleware,
+                )),
+            )
+            .route(
+                "/admin/apps/{appId}/secret/promote",
+                post(promote_app_secret).route_layer(axum_middleware::from_fn_with_state(
+                    self.handler.clone(),
+                    admin_auth_middleware,
+                )),
+            )
+            .route(
+                "/admin/broadcast",
+                post(broadcast_notice).route_layer(axum_middleware::from_fn_with_state(
+                    self.handler.clone(),
+                    admin_auth_middleware,
+                )),
+            )
+            .route(
+                "/admin/drain",
+                post(drain).route_layer(axum_middleware::from_fn_with_state(
+                    self.handler.clone(),
+                    admin_auth_middleware,
                 )),
             )
-            .route("/usage", get(usage))
             .route("/up/{appId}", get(up)) // Corrected Axum path param syntax
-            .layer(cors); // Apply CORS layer
+            .route("/probe/ws", get(probe_ws));
+
+        // Signed API requests are limited once verified, so that only a
+        // verified app and key are counted
+        let mut signed_router = Router::new()
+            .route("/apps/{appId}/events", post(events))
+            .route("/apps/{appId}/batch_events", post(batch_events))
+            .route("/apps/{appId}/stats", get(app_stats))
+            .route("/apps/{appId}/meta", get(app_meta))
+            .route("/apps/{appId}/channels", get(channels))
+            .route("/apps/{appId}/channels/{channelName}", get(channel))
+            .route("/apps/{appId}/channels/{channelName}/users", get(channel_users))
+            .route(
+                "/apps/{appId}/users/{userId}/terminate_connections",
+                post(terminate_user_connections),
+            )
+            .route("/apps/{appId}/users/{userId}/ban", post(ban_user));
 
         // Apply rate limiter middleware if it was created
         if let Some(middleware) = rate_limiter_middleware_layer {
-            router = router.layer(middleware);
+            router = router.layer(middleware.clone());
+            signed_router = signed_router.route_layer(middleware);
         }
+        let signed_router = signed_router.route_layer(axum_middleware::from_fn_with_state(
+            self.handler.clone(),
+            pusher_api_auth_middleware,
+        ));
 
-        router.with_state(self.handler.clone()) // Pass the handler state to all routes
+        router
+            .merge(signed_router)
+            .layer(cors) // Apply CORS layer
+            .with_state(self.handler.clone()) // Pass the handler state to all routes
     }
 
     fn configure_metrics_routes(&self) -> Router {
@@ -802,12 +1323,12 @@ impl SockudoServer {
         let http_router = self.configure_http_routes();
         let metrics_router = self.configure_metrics_routes();
 
-        let http_addr = self.get_http_addr();
-        let metrics_addr = self.get_metrics_addr();
+        let http_listeners =
+            listener::bind(&self.config.host, self.config.port, &self.config.listen)?;
 
         if self.config.ssl.enabled
-            && !self.config.ssl.cert_path.is_empty()
-            && !self.config.ssl.key_path.is_empty()
+            && ((!self.config.ssl.cert_path.is_empty() && !self.config.ssl.key_path.is_empty())
+                || !self.config.ssl.sni.is_empty())
         {
             info!("SSL is enabled, starting HTTPS server");
             let tls_config = self.load_tls_config().await?;
@@ -859,36 +1380,32 @@ impl SockudoServer {
 
             // Metrics server (always HTTP for Prometheus, typically)
             if self.config.metrics.enabled {
-                if let Ok(metrics_listener) = TcpListener::bind(metrics_addr).await {
-                    info!(
-                        "Metrics server listening on http://{}",
-                        metrics_addr // Clarify HTTP
-                    );
-                    let metrics_router_clone = metrics_router.clone(); // Clone for the new task
-                    tokio::spawn(async move {
-                        if let Err(e) =
-                            axum::serve(metrics_listener, metrics_router_clone.into_make_service())
-                                .await
-                        {
-                            error!("Metrics server error: {}", e);
-                        }
-                    });
-                } else {
-                    warn!(
-                        "Failed to start metrics server on {}: {}. Metrics will not be available.",
-                        metrics_addr,
-                        metrics_addr // Corrected variable
-                    );
-                }
+                self.spawn_metrics_server(metrics_router.clone());
             }
 
-            // Main HTTPS server
-            info!("HTTPS server listening on https://{}", http_addr); // Clarify HTTPS
+            // Main HTTPS server, one per bound address family
             let running = self.state.running.clone();
-            let server = axum_server::bind_rustls(http_addr, tls_config);
+            let mut servers = Vec::with_capacity(http_listeners.len());
+            for http_listener in http_listeners {
+                info!(
+                    "HTTPS server listening on https://{}",
+                    http_listener.local_addr()?
+                );
+                let server =
+                    axum_server::from_tcp_rustls(http_listener.into_std()?, tls_config.clone());
+                servers.push(
+                    server.serve(
+                        http_router
+                            .clone()
+                            .into_make_service_with_connect_info::<SocketAddr>(),
+                    ),
+                );
+            }
             tokio::select! {
-                result = server.serve(http_router.into_make_service_with_connect_info::<SocketAddr>()) => {
-                    if let Err(err) = result { error!("HTTPS server error: {}", err); }
+                results = futures::future::join_all(servers) => {
+                    for result in results {
+                        if let Err(err) = result { error!("HTTPS server error: {}", err); }
+                    }
                 }
                 _ = self.shutdown_signal() => {
                     info!("Shutdown signal received, stopping HTTPS server...");
@@ -899,51 +1416,37 @@ impl SockudoServer {
         } else {
             // HTTP only mode
             info!("SSL is not enabled, starting HTTP server");
-            let http_listener = TcpListener::bind(http_addr).await?;
 
             // Metrics server (HTTP)
-            let metrics_listener_opt = if self.config.metrics.enabled {
-                match TcpListener::bind(metrics_addr).await {
-                    Ok(listener) => {
-                        info!("Metrics server listening on http://{}", metrics_addr);
-                        Some(listener)
-                    }
-                    Err(e) => {
-                        warn!(
-                            "Failed to bind metrics server on {}: {}. Metrics will not be available.",
-                            metrics_addr, e
-                        );
-                        None
-                    }
-                }
-            } else {
-                None
-            };
+            if self.config.metrics.enabled {
+                self.spawn_metrics_server(metrics_router.clone());
+            }
 
-            info!("HTTP server listening on http://{}", http_addr);
             let running = self.state.running.clone();
 
-            if let Some(metrics_listener) = metrics_listener_opt {
-                let metrics_router_clone = metrics_router.clone(); // Clone for the new task
-                tokio::spawn(async move {
-                    if let Err(e) =
-                        axum::serve(metrics_listener, metrics_router_clone.into_make_service())
-                            .await
-                    {
-                        error!("Metrics server error: {}", e);
-                    }
-                });
+            // Main HTTP server, one per bound address family
+            let mut servers = Vec::with_capacity(http_listeners.len());
+            for http_listener in http_listeners {
+                info!(
+                    "HTTP server listening on http://{}",
+                    http_listener.local_addr()?
+                );
+                servers.push(
+                    axum::serve(
+                        http_listener,
+                        http_router
+                            .clone()
+                            .into_make_service_with_connect_info::<SocketAddr>(),
+                    )
+                    .into_future(),
+                ); // .with_graceful_shutdown(self.shutdown_signal()); // Add graceful shutdown
             }
 
-            // Main HTTP server
-            let http_server = axum::serve(
-                http_listener,
-                http_router.into_make_service_with_connect_info::<SocketAddr>(),
-            ); // .with_graceful_shutdown(self.shutdown_signal()); // Add graceful shutdown
-
             tokio::select! {
-                res = http_server => {
-                    if let Err(err) = res { error!("HTTP server error: {}", err); }
+                results = futures::future::join_all(servers) => {
+                    for res in results {
+                        if let Err(err) = res { error!("HTTP server error: {}", err); }
+                    }
                 }
                 _ = self.shutdown_signal() => {
                     info!("Shutdown signal received, stopping HTTP server...");
@@ -956,6 +1459,9 @@ impl SockudoServer {
     }
 
     async fn load_tls_config(&self) -> Result<RustlsConfig> {
+        if !self.config.ssl.sni.is_empty() {
+            return tls::server_config(&self.config.ssl);
+        }
         let cert_path = std::path::PathBuf::from(&self.config.ssl.cert_path);
         let key_path = std::path::PathBuf::from(&self.config.ssl.key_path);
         if !cert_path.exists() {
@@ -993,9 +1499,18 @@ impl SockudoServer {
         #[cfg(not(unix))]
         let terminate = std::future::pending::<()>(); // On non-Unix, this future never completes
 
+        // A completed drain shuts the server down like a signal would
+        let drained = async {
+            match &self.handler.node_drain {
+                Some(node_drain) => node_drain.finished().await,
+                None => std::future::pending::<()>().await,
+            }
+        };
+
         tokio::select! {
             _ = ctrl_c => info!("Ctrl+C received, initiating shutdown..."),
             _ = terminate => info!("Terminate signal received, initiating shutdown..."),
+            _ = drained => info!("Node drained, initiating shutdown..."),
         }
         // The actual .stop() is called after server.start() returns in main
     }
@@ -1007,10 +1522,8 @@ impl SockudoServer {
         let mut connections_to_cleanup: Vec<(String, WebSocketRef)> = Vec::new();
 
         // --- Step 1: Collect all connection identifiers ---
-        // Scope for the initial lock to quickly gather connection details.
         {
-            let mut connection_manager_guard = self.state.connection_manager.lock().await;
-            match connection_manager_guard.get_namespaces().await {
+            match self.state.connection_manager.get_namespaces().await {
                 Ok(namespaces_vec) => {
                     // Assuming get_namespaces returns an iterable collection
                     for (app_id, namespace_obj) in namespaces_vec {
@@ -1044,7 +1557,7 @@ impl SockudoServer {
                     // Consider if this error should be propagated.
                 }
             }
-        } // connection_manager_guard is dropped here, releasing the main lock.
+        }
 
         info!(
             "Collected {} connections to cleanup.",
@@ -1052,19 +1565,17 @@ impl SockudoServer {
         );
 
         // --- Step 2: Parallelize Cleanup ---
-        // Each cleanup task will briefly re-acquire the lock on ConnectionManager.
         if !connections_to_cleanup.is_empty() {
             let cleanup_futures = connections_to_cleanup
                 .into_iter()
                 .map(|(app_id, ws_raw_obj)| {
                     let cm_arc = Arc::clone(&self.state.connection_manager); // Clone Arc for the task
                     async move {
-                        let mut guard = cm_arc.lock().await;
                         // Construct WebSocketRef as in your original code
-                        guard.cleanup_connection(app_id.as_str(), ws_raw_obj).await;
+                        cm_arc.cleanup_connection(app_id.as_str(), ws_raw_obj).await;
                         // TODO: Consider if cleanup_connection should return a Result to log/handle individual cleanup errors.
                         // For example:
-                        // if let Err(e) = guard.cleanup_connection(...).await {
+                        // if let Err(e) = cm_arc.cleanup_connection(...).await {
                         //     warn!(%app_id, "Error cleaning up connection: {}", e);
                         // }
                     }
@@ -1083,11 +1594,22 @@ impl SockudoServer {
                 warn!("Error disconnecting cache manager: {}", e);
             }
         }
-        if let Some(queue_manager_arc) = &self.state.queue_manager {
-            if let Err(e) = queue_manager_arc.disconnect().await {
-                warn!("Error disconnecting queue manager: {}", e);
+        // Both queue managers drain their in-flight jobs at the same time
+        let queue_disconnect = async {
+            if let Some(queue_manager_arc) = &self.state.queue_manager {
+                if let Err(e) = queue_manager_arc.disconnect().await {
+                    warn!("Error disconnecting queue manager: {}", e);
+                }
             }
-        }
+        };
+        let webhook_queue_disconnect = async {
+            if let Some(queue_manager) = self.state.webhooks_integration.queue_manager() {
+                if let Err(e) = queue_manager.lock().await.disconnect().await {
+                    warn!("Error disconnecting webhook queue manager: {}", e);
+                }
+            }
+        };
+        tokio::join!(queue_disconnect, webhook_queue_disconnect);
         // Add disconnect for app_manager if it has such a method
         // self.state.app_manager.disconnect().await?;
 
@@ -1182,9 +1704,82 @@ async fn main() -> Result<()> {
     if let Ok(driver_str) = std::env::var("ADAPTER_DRIVER") {
         config.adapter.driver = parse_driver_enum(driver_str, config.adapter.driver, "Adapter");
     }
+    if let Ok(path) = std::env::var("NATS_CREDENTIALS_FILE") {
+        config.adapter.nats.credentials_file = Some(path);
+    }
+    if let Ok(seed) = std::env::var("NATS_NKEY_SEED") {
+        config.adapter.nats.nkey_seed = Some(seed);
+    }
+    if let Ok(val) = std::env::var("NATS_TLS") {
+        config.adapter.nats.tls.enabled = val == "1" || val.to_lowercase() == "true";
+    }
+    if let Ok(url) = std::env::var("RABBITMQ_URL") {
+        config.adapter.rabbitmq.url = url;
+    }
+    if let Ok(peers_str) = std::env::var("GOSSIP_PEERS") {
+        config.adapter.gossip.peers = peers_str
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+    }
+    if let Ok(srv) = std::env::var("GOSSIP_DNS_SRV") {
+        config.adapter.gossip.dns_srv = Some(srv);
+    }
+    if let Ok(address) = std::env::var("GOSSIP_ADVERTISE_ADDRESS") {
+        config.adapter.gossip.advertise_address = Some(address);
+    }
+    if let Ok(address) = std::env::var("GOSSIP_BIND_ADDRESS") {
+        config.adapter.gossip.bind_address = address;
+    }
+    if let Ok(secret) = std::env::var("GOSSIP_SECRET") {
+        config.adapter.gossip.secret = Some(secret);
+    }
+    if let Ok(host) = std::env::var("MQTT_HOST") {
+        config.adapter.mqtt.host = host;
+    }
+    if let Ok(val_str) = std::env::var("MQTT_PORT") {
+        if let Ok(port) = val_str.parse() {
+            config.adapter.mqtt.port = port;
+        } else {
+            eprintln!(
+                "[CONFIG-WARN] Failed to parse MQTT_PORT env var: '{}'",
+                val_str
+            );
+        }
+    }
+    if let Ok(username) = std::env::var("MQTT_USERNAME") {
+        config.adapter.mqtt.username = Some(username);
+    }
+    if let Ok(password) = std::env::var("MQTT_PASSWORD") {
+        config.adapter.mqtt.password = Some(password);
+    }
+    if let Ok(algorithm_str) = std::env::var("ADAPTER_COMPRESSION") {
+        config.adapter.compression.algorithm = parse_driver_enum(
+            algorithm_str,
+            config.adapter.compression.algorithm,
+            "Adapter compression",
+        );
+    }
+    if let Ok(val) = std::env::var("ADAPTER_CHANNEL_INTEREST") {
+        config.adapter.channel_interest.enabled = val == "1" || val.to_lowercase() == "true";
+    }
     if let Ok(driver_str) = std::env::var("CACHE_DRIVER") {
         config.cache.driver = parse_driver_enum(driver_str, config.cache.driver, "Cache");
     }
+    if let Ok(val) = std::env::var("CACHE_L1_ENABLED") {
+        config.cache.l1.enabled = val == "1" || val.to_lowercase() == "true";
+    }
+    if let Ok(val) = std::env::var("CACHE_L1_TTL_MS") {
+        if let Ok(ttl_ms) = val.parse() {
+            config.cache.l1.ttl_ms = ttl_ms;
+        }
+    }
+    if let Ok(val) = std::env::var("CACHE_L1_MAX_CAPACITY") {
+        if let Ok(max_capacity) = val.parse() {
+            config.cache.l1.max_capacity = max_capacity;
+        }
+    }
     // Add after the existing queue driver env var parsing:
     if let Ok(driver_str) = std::env::var("QUEUE_DRIVER") {
         config.queue.driver = parse_driver_enum(driver_str, config.queue.driver, "Queue");
@@ -1215,6 +1810,12 @@ async fn main() -> Result<()> {
         config.app_manager.driver =
             parse_driver_enum(driver_str, config.app_manager.driver, "AppManager");
     }
+    if let Ok(url) = std::env::var("APP_MANAGER_HTTP_URL") {
+        config.app_manager.http.url = Some(url);
+    }
+    if let Ok(auth) = std::env::var("APP_MANAGER_HTTP_AUTH_HEADER") {
+        config.app_manager.http.auth_header = Some(auth);
+    }
     if let Ok(driver_str) = std::env::var("RATE_LIMITER_DRIVER") {
         config.rate_limiter.driver = parse_driver_enum(
             driver_str,
@@ -1258,9 +1859,18 @@ async fn main() -> Result<()> {
             );
         }
     }
+    if let Ok(val) = std::env::var("DATABASE_REDIS_USERNAME") {
+        config.database.redis.username = Some(val);
+    }
     if let Ok(val) = std::env::var("DATABASE_REDIS_PASSWORD") {
         config.database.redis.password = Some(val);
     }
+    if let Ok(val) = std::env::var("DATABASE_REDIS_TLS") {
+        config.database.redis.tls.enabled = val == "1" || val.to_lowercase() == "true";
+    }
+    if let Ok(val) = std::env::var("DATABASE_REDIS_TLS_CA_CERT") {
+        config.database.redis.tls.ca_cert_path = Some(val);
+    }
     if let Ok(val_str) = std::env::var("DATABASE_REDIS_DB") {
         if let Ok(db) = val_str.parse() {
             config.database.redis.db = db;
@@ -1275,6 +1885,12 @@ async fn main() -> Result<()> {
         config.database.redis.key_prefix = val;
     }
 
+    // Database - SQLite specific
+    if let Ok(val) = std::env::var("DATABASE_SQLITE_PATH") {
+        config.cache.sqlite.path = val.clone();
+        config.database.sqlite.path = val;
+    }
+
     // Metrics specific
     if let Ok(val) = std::env::var("METRICS_ENABLED") {
         config.metrics.enabled = val == "1" || val.to_lowercase() == "true";
@@ -1295,6 +1911,19 @@ async fn main() -> Result<()> {
     if let Ok(val) = std::env::var("METRICS_PROMETHEUS_PREFIX") {
         config.metrics.prometheus.prefix = val;
     }
+    if let Ok(val_str) = std::env::var("DNS_TTL_OVERRIDE_SECS") {
+        if let Ok(ttl) = val_str.parse() {
+            config.dns.ttl_override_secs = Some(ttl);
+        } else {
+            eprintln!(
+                "[CONFIG-WARN] Failed to parse DNS_TTL_OVERRIDE_SECS env var: '{}'",
+                val_str
+            );
+        }
+    }
+    if let Ok(val) = std::env::var("METRICS_PROMETHEUS_EXEMPLARS") {
+        config.metrics.prometheus.exemplars = val == "1" || val.to_lowercase() == "true";
+    }
 
     // Instance specific
     if let Ok(val) = std::env::var("INSTANCE_PROCESS_ID") {
@@ -1310,6 +1939,29 @@ async fn main() -> Result<()> {
             );
         }
     }
+    if let Ok(val) = std::env::var("ADMIN_TOKEN") {
+        config.admin.token = Some(val);
+    }
+    if let Ok(val_str) = std::env::var("DRAIN_DURATION_MS") {
+        if let Ok(duration) = val_str.parse() {
+            config.drain.duration_ms = duration;
+        } else {
+            eprintln!(
+                "[CONFIG-WARN] Failed to parse DRAIN_DURATION_MS env var: '{}'",
+                val_str
+            );
+        }
+    }
+    if let Ok(val_str) = std::env::var("ACTIVITY_TIMEOUT") {
+        if let Ok(seconds) = val_str.parse() {
+            config.activity_timeout.default_seconds = seconds;
+        } else {
+            eprintln!(
+                "[CONFIG-WARN] Failed to parse ACTIVITY_TIMEOUT env var: '{}'",
+                val_str
+            );
+        }
+    }
 
     // --- Load configuration from file ---
     // File settings will override ENV vars set above, except for `config.debug` if ENV DEBUG was explicitly set.
@@ -1322,6 +1974,23 @@ async fn main() -> Result<()> {
             std::env::var("CONFIG_FILE").unwrap_or_else(|_| "src/config.json".to_string());
     }
 
+    if let Some(Command::Config(ConfigCommand::Migrate { output })) = &args.command {
+        let deprecations = config_migration::migrate_file(&config_path, output.as_deref())?;
+        if deprecations.is_empty() {
+            println!("{} has no deprecated fields", config_path);
+        }
+        for deprecation in &deprecations {
+            println!("Migrated {}", deprecation);
+        }
+        if !deprecations.is_empty() {
+            println!(
+                "Wrote migrated config to {}",
+                output.as_deref().unwrap_or(&config_path)
+            );
+        }
+        return Ok(());
+    }
+
     if Path::new(&config_path).exists() {
         println!("[PRE-LOG] Loading configuration from file: {}", config_path); // Basic print before logging init
         let mut file = File::open(&config_path).map_err(|e| {
@@ -1332,9 +2001,15 @@ async fn main() -> Result<()> {
             Error::ConfigFileError(format!("Failed to read {}: {}", config_path, e))
         })?;
 
-        match from_str::<ServerOptions>(&contents) {
-            Ok(file_config) => {
+        match config_migration::parse(&contents) {
+            Ok((file_config, deprecations)) => {
                 config = file_config; // File config overrides previous defaults and ENV vars
+                for deprecation in &deprecations {
+                    eprintln!(
+                        "[CONFIG-WARN] {} (run `sockudo config migrate` to update {})",
+                        deprecation, config_path
+                    );
+                }
                 println!(
                     "[PRE-LOG] Successfully loaded and applied configuration from {}",
                     config_path
@@ -1387,6 +2062,96 @@ async fn main() -> Result<()> {
         }
     }
 
+    if let Some(Command::Fixtures { app, output }) = &args.command {
+        let app_manager = AppManagerFactory::create(&config.app_manager, &config.database).await?;
+        app_manager.init().await?;
+        let fixtures = fixtures::generate(app_manager, app.as_deref()).await?;
+        let contents = serde_json::to_string_pretty(&fixtures).map_err(|e| {
+            Error::SerializationError(format!("Failed to serialize fixtures: {}", e))
+        })?;
+        std::fs::write(output, contents)
+            .map_err(|e| Error::ConfigFileError(format!("Failed to write {}: {}", output, e)))?;
+        println!(
+            "Wrote fixtures for {} apps to {}",
+            fixtures["apps"].as_array().map_or(0, Vec::len),
+            output
+        );
+        return Ok(());
+    }
+
+    if let Some(Command::Migrate(command)) = &args.command {
+        let http_client = reqwest::Client::new();
+        match command {
+            MigrateCommand::Import {
+                from,
+                source,
+                output,
+                apply,
+            } => {
+                let apps = migrate::load(*from, source)?;
+                for app in &apps {
+                    let webhooks = app.app.webhooks.as_ref().map_or(0, Vec::len);
+                    if *from == migrate::MigrationSource::Pusher {
+                        let occupied = migrate::check_pusher_app(&http_client, app).await?;
+                        println!(
+                            "Imported app {} ({} webhooks, {} channels occupied on Pusher)",
+                            app.app.id, webhooks, occupied
+                        );
+                    } else {
+                        println!("Imported app {} ({} webhooks)", app.app.id, webhooks);
+                    }
+                }
+                if let Some(output) = output {
+                    let contents = serde_json::to_string_pretty(&migrate::apps_config(&apps)?)?;
+                    std::fs::write(output, contents + "\n").map_err(|e| {
+                        Error::ConfigFileError(format!("Failed to write {}: {}", output, e))
+                    })?;
+                    println!("Wrote {} apps to {}", apps.len(), output);
+                }
+                if *apply {
+                    let app_manager =
+                        AppManagerFactory::create(&config.app_manager, &config.database).await?;
+                    app_manager.init().await?;
+                    migrate::apply(&app_manager, &apps).await?;
+                }
+            }
+            MigrateCommand::Verify {
+                from,
+                source,
+                target,
+                legacy,
+                app,
+                channel,
+                events,
+            } => {
+                let options = migrate::VerifyOptions {
+                    target: target.clone(),
+                    legacy: legacy.clone(),
+                    channel: channel.clone(),
+                    events: *events,
+                };
+                let mut failed = 0;
+                for source in migrate::load(*from, source)? {
+                    if app.as_ref().is_some_and(|app| *app != source.app.id) {
+                        continue;
+                    }
+                    let report = migrate::verify(&http_client, &source, &options).await?;
+                    if report["passed"] != true {
+                        failed += 1;
+                    }
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                }
+                if failed > 0 {
+                    return Err(Error::Other(format!(
+                        "Deliveries differ for {} apps",
+                        failed
+                    )));
+                }
+            }
+        }
+        return Ok(());
+    }
+
     // --- Part 2: Initialize logging using final config.debug ---
     let final_debug_is_enabled = config.debug;
 
@@ -1400,7 +2165,15 @@ async fn main() -> Result<()> {
     let env_filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new(default_log_directive_str));
 
-    let subscriber_builder = fmt::Subscriber::builder().with_env_filter(env_filter);
+    // Keep a reload handle so per-subsystem levels can change at runtime
+    let base_log_filter = env_filter.to_string();
+    let subscriber_builder = fmt::Subscriber::builder()
+        .with_env_filter(env_filter)
+        .with_filter_reloading();
+    let log_filters = Arc::new(LogFilters::new(
+        subscriber_builder.reload_handle(),
+        base_log_filter,
+    ));
 
     if final_debug_is_enabled {
         subscriber_builder
@@ -1427,7 +2200,7 @@ async fn main() -> Result<()> {
     // --- Part 3: Rest of the application logic ---
     info!("Starting Sockudo server initialization process with resolved configuration...");
 
-    let server = match SockudoServer::new(config).await {
+    let server = match SockudoServer::new(config, Some(log_filters), config_path).await {
         // Pass the fully resolved config
         Ok(s) => s,
         Err(e) => {
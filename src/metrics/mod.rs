@@ -5,6 +5,7 @@ pub mod prometheus;
 pub use prometheus::PrometheusMetricsDriver;
 use tokio::sync::Mutex;
 
+use crate::options::PrometheusConfig;
 use crate::websocket::SocketId;
 use async_trait::async_trait;
 use serde_json::Value;
@@ -39,6 +40,19 @@ pub trait MetricsInterface: Send + Sync {
     /// Track the time in which horizontal adapter resolves requests from other nodes
     fn track_horizontal_adapter_resolve_time(&self, app_id: &str, time_ms: f64);
 
+    /// Track the resolve time along with the ID of the request it belongs to,
+    /// as an exemplar. Drivers without exemplar support record the plain
+    /// observation.
+    fn track_horizontal_adapter_resolve_time_with_exemplar(
+        &self,
+        app_id: &str,
+        time_ms: f64,
+        request_id: &str,
+    ) {
+        let _ = request_id;
+        self.track_horizontal_adapter_resolve_time(app_id, time_ms);
+    }
+
     /// Track the fulfillings in which horizontal adapter resolves requests from other nodes
     fn track_horizontal_adapter_resolved_promises(&self, app_id: &str, resolved: bool);
 
@@ -51,9 +65,76 @@ pub trait MetricsInterface: Send + Sync {
     /// Handle a new horizontal adapter response from other node
     fn mark_horizontal_adapter_response_received(&self, app_id: &str);
 
+    /// Track how long publishing a broadcast to the other nodes took
+    fn track_horizontal_adapter_publish_latency(&self, app_id: &str, time_ms: f64);
+
+    /// Handle a broadcast received from another node
+    fn mark_horizontal_adapter_broadcast_received(&self, app_id: &str);
+
+    /// Track the result of the latest adapter health check
+    fn mark_adapter_health(&self, healthy: bool);
+
+    /// Handle an adapter reconnect attempt made by the health supervisor
+    fn mark_adapter_reconnect_attempt(&self, success: bool);
+
+    /// Track whether the adapter has failed over to its fallback driver
+    fn mark_adapter_failover(&self, active: bool);
+
+    /// Handle a broadcast shed by dispatch QoS, labelled with its class
+    fn mark_dispatch_shed(&self, class: &str);
+
+    /// Track how many jobs are waiting in a queue
+    fn mark_queue_pending_jobs(&self, queue: &str, pending: usize);
+
+    /// Track how many jobs from a queue this node is processing right now
+    fn mark_queue_in_flight_jobs(&self, queue: &str, in_flight: usize);
+
+    /// Track how long processing a queue job took
+    fn track_queue_job_duration(&self, queue: &str, time_ms: f64);
+
+    /// Handle a queue job whose processing failed
+    fn mark_queue_job_failed(&self, queue: &str);
+
+    /// Track the circuit breaker state of a webhook endpoint
+    fn mark_webhook_circuit_state(&self, endpoint: &str, state: &str);
+
+    /// Track the end-to-end delivery latency measured by the canary
+    fn track_canary_latency(&self, target: &str, time_ms: f64);
+
+    /// Track whether the latest canary round against `target` succeeded
+    fn mark_canary_result(&self, target: &str, success: bool);
+
+    /// Count a broadcast delivered to local sockets, and whether it made the
+    /// delivery deadline, labelled with its channel class
+    fn mark_delivery_deadline(&self, app_id: &str, channel_class: &str, met: bool);
+
+    /// Track how many messages on acknowledged channels await a client ack
+    fn mark_unacked_messages(&self, app_id: &str, unacked: usize);
+
+    /// Count a message resent because the client did not acknowledge it
+    fn mark_ack_redelivery(&self, app_id: &str);
+
+    /// Count a message that was never acknowledged within the ack timeout
+    fn mark_ack_expired(&self, app_id: &str);
+
+    /// Count a cache lookup as a hit or a miss
+    fn mark_cache_lookup(&self, hit: bool);
+
+    /// Track how long a cache operation (get, has, set, remove) took
+    fn track_cache_operation_duration(&self, operation: &str, time_ms: f64);
+
+    /// Count entries the cache evicted to stay within its capacity
+    fn mark_cache_evictions(&self, evicted: u64);
+
     /// Get the stored metrics as plain text, if possible
     async fn get_metrics_as_plaintext(&self) -> String;
 
+    /// Get the stored metrics in the OpenMetrics text format, which carries
+    /// exemplars. `None` when the driver does not support it.
+    async fn get_metrics_as_openmetrics(&self) -> Option<String> {
+        None
+    }
+
     /// Get the stored metrics as JSON, if possible
     async fn get_metrics_as_json(&self) -> Value;
 
@@ -69,11 +150,11 @@ impl MetricsFactory {
     pub async fn create(
         driver_type: &str,
         port: u16,
-        prefix: Option<&str>,
+        prometheus_options: &PrometheusConfig,
     ) -> Option<Arc<Mutex<dyn MetricsInterface + Send + Sync>>> {
         match driver_type.to_lowercase().as_str() {
             "prometheus" => {
-                let driver = PrometheusMetricsDriver::new(port, prefix).await;
+                let driver = PrometheusMetricsDriver::new(port, prometheus_options).await;
                 Some(Arc::new(Mutex::new(driver)))
             }
             // Add more drivers here
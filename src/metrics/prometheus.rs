@@ -3,14 +3,95 @@
 use crate::error::Result;
 
 use super::MetricsInterface;
+use crate::options::PrometheusConfig;
 use crate::websocket::SocketId;
 use async_trait::async_trait;
 use prometheus::{
-    CounterVec, GaugeVec, HistogramVec, Opts, TextEncoder, register_counter_vec,
-    register_gauge_vec, register_histogram_vec,
+    CounterVec, GaugeVec, HistogramOpts, HistogramVec, Opts, TextEncoder, exponential_buckets,
+    register_counter_vec, register_gauge_vec, register_histogram_vec,
 };
+use prometheus_client::collector::Collector;
+use prometheus_client::encoding::{DescriptorEncoder, EncodeLabelSet};
+use prometheus_client::metrics::MetricType;
+use prometheus_client::metrics::exemplar::HistogramWithExemplars;
+use prometheus_client::metrics::family::Family;
+use prometheus_client::registry::Registry;
 use serde_json::{Value, json};
-use tracing::{error, info};
+use tracing::{error, info, warn};
+
+/// Labels of the resolve time histogram
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct ResolveTimeLabels {
+    app_id: String,
+    port: String,
+}
+
+/// Exemplar label linking an observation to the horizontal request every
+/// node logs
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct RequestIdLabel {
+    request_id: String,
+}
+
+type ResolveTimeFamily = Family<ResolveTimeLabels, HistogramWithExemplars<RequestIdLabel>>;
+
+/// Feeds the default `prometheus` registry to the OpenMetrics encoder,
+/// except the family registered there natively with its exemplars
+#[derive(Debug)]
+struct DefaultRegistryCollector {
+    skip: String,
+}
+
+impl Collector for DefaultRegistryCollector {
+    fn encode(&self, mut encoder: DescriptorEncoder) -> std::result::Result<(), std::fmt::Error> {
+        use prometheus::proto::MetricType as ClassicType;
+
+        for family in prometheus::gather() {
+            let field_type = family.get_field_type();
+            let (name, metric_type) = match field_type {
+                // The encoder appends `_total` to counter samples itself
+                ClassicType::COUNTER => (
+                    family.name().trim_end_matches("_total"),
+                    MetricType::Counter,
+                ),
+                ClassicType::GAUGE => (family.name(), MetricType::Gauge),
+                ClassicType::HISTOGRAM => (family.name(), MetricType::Histogram),
+                _ => continue,
+            };
+            if family.name() == self.skip {
+                continue;
+            }
+
+            let mut family_encoder =
+                encoder.encode_descriptor(name, family.help(), None, metric_type)?;
+            for metric in family.get_metric() {
+                let labels: Vec<(String, String)> = metric
+                    .get_label()
+                    .iter()
+                    .map(|label| (label.name().to_string(), label.value().to_string()))
+                    .collect();
+                let mut metric_encoder = family_encoder.encode_family(&labels)?;
+                match field_type {
+                    ClassicType::COUNTER => metric_encoder
+                        .encode_counter::<(), _, f64>(&metric.get_counter().get_value(), None)?,
+                    ClassicType::GAUGE => {
+                        metric_encoder.encode_gauge(&metric.get_gauge().get_value())?
+                    }
+                    _ => {
+                        let histogram = metric.get_histogram();
+                        metric_encoder.encode_histogram::<()>(
+                            histogram.get_sample_sum(),
+                            histogram.get_sample_count(),
+                            &bucket_counts(histogram),
+                            None,
+                        )?
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
 
 /// A Prometheus implementation of the metrics interface
 pub struct PrometheusMetricsDriver {
@@ -34,12 +115,40 @@ pub struct PrometheusMetricsDriver {
     horizontal_adapter_sent_requests: CounterVec,
     horizontal_adapter_received_requests: CounterVec,
     horizontal_adapter_received_responses: CounterVec,
+    horizontal_adapter_publish_latency: HistogramVec,
+    horizontal_adapter_received_broadcasts: CounterVec,
+    adapter_healthy: GaugeVec,
+    adapter_reconnect_attempts: CounterVec,
+    adapter_failover_active: GaugeVec,
+    dispatch_shed: CounterVec,
+    queue_pending_jobs: GaugeVec,
+    queue_in_flight_jobs: GaugeVec,
+    queue_job_duration: HistogramVec,
+    queue_jobs_failed: CounterVec,
+    webhook_circuit_state: GaugeVec,
+    canary_delivery_latency: HistogramVec,
+    canary_last_latency: GaugeVec,
+    canary_up: GaugeVec,
+    delivery_deadline_checks: CounterVec,
+    delivery_deadline_violations: CounterVec,
+    unacked_messages: GaugeVec,
+    ack_redeliveries: CounterVec,
+    acks_expired: CounterVec,
+    cache_hits: CounterVec,
+    cache_misses: CounterVec,
+    cache_operation_duration: HistogramVec,
+    cache_evictions: CounterVec,
+
+    /// Resolve times with the request each observation came from, exposed
+    /// through `openmetrics_registry`; unset unless exemplars are enabled
+    resolve_time_exemplars: Option<ResolveTimeFamily>,
+    openmetrics_registry: Registry,
 }
 
 impl PrometheusMetricsDriver {
     /// Creates a new Prometheus metrics driver
-    pub async fn new(port: u16, prefix_opt: Option<&str>) -> Self {
-        let prefix = prefix_opt.unwrap_or("sockudo_").to_string();
+    pub async fn new(port: u16, options: &PrometheusConfig) -> Self {
+        let prefix = options.prefix.clone();
 
         // Initialize all metrics
         let connected_sockets = register_gauge_vec!(
@@ -133,8 +242,11 @@ impl PrometheusMetricsDriver {
         .unwrap();
 
         let horizontal_adapter_resolve_time = register_histogram_vec!(
-            format!("{}horizontal_adapter_resolve_time", prefix),
-            "The average resolve time for requests to other nodes",
+            HistogramOpts::new(
+                format!("{}horizontal_adapter_resolve_time", prefix),
+                "The average resolve time for requests to other nodes"
+            )
+            .buckets(prometheus::DEFAULT_BUCKETS.to_vec()),
             &["app_id", "port"]
         )
         .unwrap();
@@ -184,6 +296,240 @@ impl PrometheusMetricsDriver {
         )
         .unwrap();
 
+        let horizontal_adapter_publish_latency = register_histogram_vec!(
+            HistogramOpts::new(
+                format!("{}horizontal_adapter_publish_latency", prefix),
+                "The time it takes to publish a broadcast to other nodes"
+            )
+            .buckets(prometheus::DEFAULT_BUCKETS.to_vec()),
+            &["app_id", "port"]
+        )
+        .unwrap();
+
+        let horizontal_adapter_received_broadcasts = register_counter_vec!(
+            Opts::new(
+                format!("{}horizontal_adapter_received_broadcasts", prefix),
+                "The total amount of broadcasts received from other nodes"
+            ),
+            &["app_id", "port"]
+        )
+        .unwrap();
+
+        let adapter_healthy = register_gauge_vec!(
+            Opts::new(
+                format!("{}adapter_healthy", prefix),
+                "Whether the adapter passed its latest health check (1) or not (0)"
+            ),
+            &["port"]
+        )
+        .unwrap();
+
+        let adapter_reconnect_attempts = register_counter_vec!(
+            Opts::new(
+                format!("{}adapter_reconnect_attempts_total", prefix),
+                "The total amount of adapter reconnect attempts made by the health supervisor"
+            ),
+            &["port", "result"]
+        )
+        .unwrap();
+
+        let adapter_failover_active = register_gauge_vec!(
+            Opts::new(
+                format!("{}adapter_failover_active", prefix),
+                "Whether the adapter is delivering through its fallback driver (1) or not (0)"
+            ),
+            &["port"]
+        )
+        .unwrap();
+
+        let dispatch_shed = register_counter_vec!(
+            Opts::new(
+                format!("{}dispatch_shed_total", prefix),
+                "The total amount of broadcasts shed by dispatch QoS, by class"
+            ),
+            &["port", "class"]
+        )
+        .unwrap();
+
+        let queue_pending_jobs = register_gauge_vec!(
+            Opts::new(
+                format!("{}queue_pending_jobs", prefix),
+                "The number of jobs waiting in a queue"
+            ),
+            &["port", "queue"]
+        )
+        .unwrap();
+
+        let queue_in_flight_jobs = register_gauge_vec!(
+            Opts::new(
+                format!("{}queue_in_flight_jobs", prefix),
+                "The number of queue jobs this node is processing"
+            ),
+            &["port", "queue"]
+        )
+        .unwrap();
+
+        // Jobs such as webhook deliveries take milliseconds to seconds
+        let queue_job_duration = register_histogram_vec!(
+            HistogramOpts::new(
+                format!("{}queue_job_duration", prefix),
+                "The time it takes to process a queue job, in milliseconds"
+            )
+            .buckets(exponential_buckets(1.0, 2.0, 16).unwrap_or_default()),
+            &["port", "queue"]
+        )
+        .unwrap();
+
+        let queue_jobs_failed = register_counter_vec!(
+            Opts::new(
+                format!("{}queue_jobs_failed_total", prefix),
+                "The total amount of queue jobs whose processing failed"
+            ),
+            &["port", "queue"]
+        )
+        .unwrap();
+
+        let webhook_circuit_state = register_gauge_vec!(
+            Opts::new(
+                format!("{}webhook_circuit_state", prefix),
+                "Whether a webhook endpoint's circuit is in this state (1) or not (0)"
+            ),
+            &["port", "endpoint", "state"]
+        )
+        .unwrap();
+
+        let canary_delivery_latency = register_histogram_vec!(
+            HistogramOpts::new(
+                format!("{}canary_delivery_latency", prefix),
+                "The time from publishing a canary event to receiving it, in milliseconds"
+            )
+            .buckets(prometheus::DEFAULT_BUCKETS.to_vec()),
+            &["port", "target"]
+        )
+        .unwrap();
+
+        let canary_last_latency = register_gauge_vec!(
+            Opts::new(
+                format!("{}canary_last_latency_ms", prefix),
+                "The delivery latency of the latest successful canary round, in milliseconds"
+            ),
+            &["port", "target"]
+        )
+        .unwrap();
+
+        let canary_up = register_gauge_vec!(
+            Opts::new(
+                format!("{}canary_up", prefix),
+                "Whether the latest canary round delivered its event (1) or not (0)"
+            ),
+            &["port", "target"]
+        )
+        .unwrap();
+
+        let delivery_deadline_checks = register_counter_vec!(
+            Opts::new(
+                format!("{}delivery_deadline_checks_total", prefix),
+                "The total amount of broadcasts checked against the delivery deadline"
+            ),
+            &["app_id", "port", "channel_class"]
+        )
+        .unwrap();
+
+        let delivery_deadline_violations = register_counter_vec!(
+            Opts::new(
+                format!("{}delivery_deadline_violations_total", prefix),
+                "The total amount of broadcasts delivered after the delivery deadline"
+            ),
+            &["app_id", "port", "channel_class"]
+        )
+        .unwrap();
+
+        let unacked_messages = register_gauge_vec!(
+            Opts::new(
+                format!("{}unacked_messages", prefix),
+                "The number of messages on acknowledged channels awaiting a client ack"
+            ),
+            &["app_id", "port"]
+        )
+        .unwrap();
+
+        let ack_redeliveries = register_counter_vec!(
+            Opts::new(
+                format!("{}ack_redeliveries_total", prefix),
+                "The total amount of messages resent because the client did not acknowledge them"
+            ),
+            &["app_id", "port"]
+        )
+        .unwrap();
+
+        let acks_expired = register_counter_vec!(
+            Opts::new(
+                format!("{}acks_expired_total", prefix),
+                "The total amount of messages never acknowledged within the ack timeout"
+            ),
+            &["app_id", "port"]
+        )
+        .unwrap();
+
+        let cache_hits = register_counter_vec!(
+            Opts::new(
+                format!("{}cache_hits_total", prefix),
+                "The total amount of cache lookups that found their key"
+            ),
+            &["port"]
+        )
+        .unwrap();
+
+        let cache_misses = register_counter_vec!(
+            Opts::new(
+                format!("{}cache_misses_total", prefix),
+                "The total amount of cache lookups that did not find their key"
+            ),
+            &["port"]
+        )
+        .unwrap();
+
+        // Memory lookups take microseconds and Redis ones a few milliseconds
+        let cache_operation_duration = register_histogram_vec!(
+            HistogramOpts::new(
+                format!("{}cache_operation_duration", prefix),
+                "The time a cache operation takes, in milliseconds"
+            )
+            .buckets(exponential_buckets(0.01, 2.0, 16).unwrap_or_default()),
+            &["port", "operation"]
+        )
+        .unwrap();
+
+        let cache_evictions = register_counter_vec!(
+            Opts::new(
+                format!("{}cache_evictions_total", prefix),
+                "The total amount of entries the cache evicted to stay within its capacity"
+            ),
+            &["port"]
+        )
+        .unwrap();
+
+        // Exemplars are only served as OpenMetrics, which the `prometheus`
+        // crate cannot encode, so that output goes through `prometheus_client`
+        let mut openmetrics_registry = Registry::default();
+        let resolve_time_exemplars = if options.exemplars {
+            let resolve_time_name = format!("{}horizontal_adapter_resolve_time", prefix);
+            let family = ResolveTimeFamily::new_with_constructor(|| {
+                HistogramWithExemplars::new(prometheus::DEFAULT_BUCKETS.iter().copied())
+            });
+            openmetrics_registry.register(
+                resolve_time_name.clone(),
+                "The average resolve time for requests to other nodes",
+                family.clone(),
+            );
+            openmetrics_registry.register_collector(Box::new(DefaultRegistryCollector {
+                skip: resolve_time_name,
+            }));
+            Some(family)
+        } else {
+            None
+        };
+
         Self {
             prefix,
             port,
@@ -203,6 +549,31 @@ impl PrometheusMetricsDriver {
             horizontal_adapter_sent_requests,
             horizontal_adapter_received_requests,
             horizontal_adapter_received_responses,
+            horizontal_adapter_publish_latency,
+            horizontal_adapter_received_broadcasts,
+            adapter_healthy,
+            adapter_reconnect_attempts,
+            adapter_failover_active,
+            dispatch_shed,
+            queue_pending_jobs,
+            queue_in_flight_jobs,
+            queue_job_duration,
+            queue_jobs_failed,
+            webhook_circuit_state,
+            canary_delivery_latency,
+            canary_last_latency,
+            canary_up,
+            delivery_deadline_checks,
+            delivery_deadline_violations,
+            unacked_messages,
+            ack_redeliveries,
+            acks_expired,
+            cache_hits,
+            cache_misses,
+            cache_operation_duration,
+            cache_evictions,
+            resolve_time_exemplars,
+            openmetrics_registry,
         }
     }
 
@@ -286,6 +657,27 @@ impl MetricsInterface for PrometheusMetricsDriver {
             .observe(time_ms);
     }
 
+    fn track_horizontal_adapter_resolve_time_with_exemplar(
+        &self,
+        app_id: &str,
+        time_ms: f64,
+        request_id: &str,
+    ) {
+        self.track_horizontal_adapter_resolve_time(app_id, time_ms);
+        if let Some(family) = &self.resolve_time_exemplars {
+            let labels = ResolveTimeLabels {
+                app_id: app_id.to_string(),
+                port: self.port.to_string(),
+            };
+            family.get_or_create(&labels).observe(
+                time_ms,
+                Some(RequestIdLabel {
+                    request_id: request_id.to_string(),
+                }),
+            );
+        }
+    }
+
     fn track_horizontal_adapter_resolved_promises(&self, app_id: &str, resolved: bool) {
         let tags = self.get_tags(app_id);
 
@@ -321,6 +713,161 @@ impl MetricsInterface for PrometheusMetricsDriver {
             .inc();
     }
 
+    fn track_horizontal_adapter_publish_latency(&self, app_id: &str, time_ms: f64) {
+        let tags = self.get_tags(app_id);
+        self.horizontal_adapter_publish_latency
+            .with_label_values(&tags)
+            .observe(time_ms);
+    }
+
+    fn mark_horizontal_adapter_broadcast_received(&self, app_id: &str) {
+        let tags = self.get_tags(app_id);
+        self.horizontal_adapter_received_broadcasts
+            .with_label_values(&tags)
+            .inc();
+    }
+
+    fn mark_adapter_health(&self, healthy: bool) {
+        let port = self.port.to_string();
+        self.adapter_healthy
+            .with_label_values(&[port.as_str()])
+            .set(if healthy { 1.0 } else { 0.0 });
+    }
+
+    fn mark_adapter_reconnect_attempt(&self, success: bool) {
+        let port = self.port.to_string();
+        let result = if success { "success" } else { "failure" };
+        self.adapter_reconnect_attempts
+            .with_label_values(&[port.as_str(), result])
+            .inc();
+    }
+
+    fn mark_adapter_failover(&self, active: bool) {
+        let port = self.port.to_string();
+        self.adapter_failover_active
+            .with_label_values(&[port.as_str()])
+            .set(if active { 1.0 } else { 0.0 });
+    }
+
+    fn mark_dispatch_shed(&self, class: &str) {
+        let port = self.port.to_string();
+        self.dispatch_shed
+            .with_label_values(&[port.as_str(), class])
+            .inc();
+    }
+
+    fn mark_queue_pending_jobs(&self, queue: &str, pending: usize) {
+        let port = self.port.to_string();
+        self.queue_pending_jobs
+            .with_label_values(&[port.as_str(), queue])
+            .set(pending as f64);
+    }
+
+    fn mark_queue_in_flight_jobs(&self, queue: &str, in_flight: usize) {
+        let port = self.port.to_string();
+        self.queue_in_flight_jobs
+            .with_label_values(&[port.as_str(), queue])
+            .set(in_flight as f64);
+    }
+
+    fn track_queue_job_duration(&self, queue: &str, time_ms: f64) {
+        let port = self.port.to_string();
+        self.queue_job_duration
+            .with_label_values(&[port.as_str(), queue])
+            .observe(time_ms);
+    }
+
+    fn mark_queue_job_failed(&self, queue: &str) {
+        let port = self.port.to_string();
+        self.queue_jobs_failed
+            .with_label_values(&[port.as_str(), queue])
+            .inc();
+    }
+
+    fn mark_webhook_circuit_state(&self, endpoint: &str, state: &str) {
+        let port = self.port.to_string();
+        for candidate in ["closed", "open", "half_open"] {
+            self.webhook_circuit_state
+                .with_label_values(&[port.as_str(), endpoint, candidate])
+                .set(if candidate == state { 1.0 } else { 0.0 });
+        }
+    }
+
+    fn track_canary_latency(&self, target: &str, time_ms: f64) {
+        let port = self.port.to_string();
+        self.canary_delivery_latency
+            .with_label_values(&[port.as_str(), target])
+            .observe(time_ms);
+        self.canary_last_latency
+            .with_label_values(&[port.as_str(), target])
+            .set(time_ms);
+    }
+
+    fn mark_canary_result(&self, target: &str, success: bool) {
+        let port = self.port.to_string();
+        self.canary_up
+            .with_label_values(&[port.as_str(), target])
+            .set(if success { 1.0 } else { 0.0 });
+    }
+
+    fn mark_delivery_deadline(&self, app_id: &str, channel_class: &str, met: bool) {
+        let port = self.port.to_string();
+        let labels = [app_id, port.as_str(), channel_class];
+        self.delivery_deadline_checks
+            .with_label_values(&labels)
+            .inc();
+        if !met {
+            self.delivery_deadline_violations
+                .with_label_values(&labels)
+                .inc();
+        }
+    }
+
+    fn mark_unacked_messages(&self, app_id: &str, unacked: usize) {
+        let port = self.port.to_string();
+        self.unacked_messages
+            .with_label_values(&[app_id, port.as_str()])
+            .set(unacked as f64);
+    }
+
+    fn mark_ack_redelivery(&self, app_id: &str) {
+        let port = self.port.to_string();
+        self.ack_redeliveries
+            .with_label_values(&[app_id, port.as_str()])
+            .inc();
+    }
+
+    fn mark_ack_expired(&self, app_id: &str) {
+        let port = self.port.to_string();
+        self.acks_expired
+            .with_label_values(&[app_id, port.as_str()])
+            .inc();
+    }
+
+    fn mark_cache_lookup(&self, hit: bool) {
+        let port = self.port.to_string();
+        let counter = if hit {
+            &self.cache_hits
+        } else {
+            &self.cache_misses
+        };
+        counter.with_label_values(&[port.as_str()]).inc();
+    }
+
+    fn track_cache_operation_duration(&self, operation: &str, time_ms: f64) {
+        let port = self.port.to_string();
+        self.cache_operation_duration
+            .with_label_values(&[port.as_str(), operation])
+            .observe(time_ms);
+    }
+
+    fn mark_cache_evictions(&self, evicted: u64) {
+        let port = self.port.to_string();
+        self.cache_evictions
+            .with_label_values(&[port.as_str()])
+            .inc_by(evicted as f64);
+    }
+
     async fn get_metrics_as_plaintext(&self) -> String {
         let encoder = TextEncoder::new();
         let metric_families = prometheus::gather(); // Gather from the default registry
@@ -332,6 +879,18 @@ impl MetricsInterface for PrometheusMetricsDriver {
             })
     }
 
+    async fn get_metrics_as_openmetrics(&self) -> Option<String> {
+        self.resolve_time_exemplars.as_ref()?;
+        let mut output = String::new();
+        match prometheus_client::encoding::text::encode(&mut output, &self.openmetrics_registry) {
+            Ok(()) => Some(output),
+            Err(e) => {
+                warn!("{}", format!("Failed to encode OpenMetrics output: {}", e));
+                None
+            }
+        }
+    }
+
     /// Get metrics data as a JSON object
     async fn get_metrics_as_json(&self) -> Value {
         // Create a base JSON structure
@@ -460,3 +1019,21 @@ impl MetricsInterface for PrometheusMetricsDriver {
         );
     }
 }
+
+/// Per-bucket counts of a classic histogram, ending with the `+Inf` bucket
+/// (`f64::MAX` to the encoder), which is how the OpenMetrics encoder takes them
+fn bucket_counts(histogram: &prometheus::proto::Histogram) -> Vec<(f64, u64)> {
+    let mut seen = 0;
+    let mut buckets: Vec<(f64, u64)> = histogram
+        .get_bucket()
+        .iter()
+        .filter(|bucket| bucket.upper_bound().is_finite())
+        .map(|bucket| {
+            let count = bucket.cumulative_count().saturating_sub(seen);
+            seen = bucket.cumulative_count();
+            (bucket.upper_bound(), count)
+        })
+        .collect();
+    buckets.push((f64::MAX, histogram.get_sample_count().saturating_sub(seen)));
+    buckets
+}
@@ -14,7 +14,7 @@ use serde::de::DeserializeOwned; // For generic JSON payload in original handler
 use std::{collections::BTreeMap, sync::Arc};
 
 // Helper to extract query parameters for the signature
-fn get_params_for_signature(
+pub(crate) fn get_params_for_signature(
     query_str_option: Option<&str>,
 ) -> Result<BTreeMap<String, String>, AppError> {
     let mut params_map = BTreeMap::new();
@@ -35,11 +35,20 @@ fn get_params_for_signature(
     Ok(params_map)
 }
 
+/// The app and key a signed API request was verified against, added to the
+/// request's extensions by `pusher_api_auth_middleware`
+#[derive(Clone, Debug)]
+pub struct VerifiedApiRequest {
+    pub app_id: String,
+    pub auth_key: String,
+}
+
 /// Axum middleware for Pusher API authentication.
 ///
 /// This middleware authenticates incoming requests based on the Pusher protocol,
 /// checking the auth_signature, timestamp, and optionally body_md5.
 /// It requires the `ConnectionHandler` state to access the `AppManager` for app details.
+/// Verified requests carry a `VerifiedApiRequest` extension for the layers after it.
 pub async fn pusher_api_auth_middleware(
     State(handler_state): State<Arc<ConnectionHandler>>, // Access to AppManager via ConnectionHandler
     request: HttpRequest<Body>,                          // The incoming HTTP request
@@ -106,6 +115,14 @@ pub async fn pusher_api_auth_middleware(
     {
         Ok(true) => {
             tracing::debug!("Pusher API authentication successful for path: {}", path);
+            // The validator found the app by this key, so the lookup is cached
+            let auth_key = auth_q_params_struct.auth_key;
+            if let Ok(Some(app)) = handler_state.app_manager.find_by_key(&auth_key).await {
+                parts.extensions.insert(VerifiedApiRequest {
+                    app_id: app.id,
+                    auth_key,
+                });
+            }
             // Auth passed. Reconstruct the request with the buffered body.
             let request = HttpRequest::from_parts(parts, Body::from(body_bytes.clone())); // Use cloned bytes for safety
             Ok(next.run(request).await) // Proceed to the next handler
@@ -129,3 +146,43 @@ pub async fn pusher_api_auth_middleware(
         }
     }
 }
+
+/// Axum middleware for the operator endpoints under `/admin`.
+///
+/// Requests must carry `Authorization: Bearer <token>` matching the configured
+/// admin token. Without a configured token every admin request is rejected.
+pub async fn admin_auth_middleware(
+    State(handler_state): State<Arc<ConnectionHandler>>,
+    request: HttpRequest<Body>,
+    next: Next,
+) -> Result<Response, AppError> {
+    let Some(expected) = handler_state.admin_token.as_deref() else {
+        return Err(AppError::ApiAuthFailed(
+            "Admin API is disabled: no admin token configured".to_string(),
+        ));
+    };
+
+    let provided = request
+        .headers()
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+
+    // Compare without short-circuiting so response time does not leak the token
+    let matches = provided.len() == expected.len()
+        && provided
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0;
+    if !matches {
+        tracing::warn!(
+            "Admin API authentication failed for path: {}",
+            request.uri().path()
+        );
+        return Err(AppError::ApiAuthFailed("Invalid admin token".to_string()));
+    }
+
+    Ok(next.run(request).await)
+}
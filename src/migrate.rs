@@ -0,0 +1,580 @@
+// src/migrate.rs
+//! `sockudo migrate`, for moving tenants over from Pusher or soketi.
+//! `import` turns the apps of a soketi config file, or an export of Pusher
+//! apps, into Sockudo apps with their webhooks. `verify` publishes the same
+//! test events to the old platform and to Sockudo and compares what a
+//! subscriber on each side receives, so traffic can be switched over once
+//! both agree.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use tokio::net::TcpStream;
+
+use crate::app::auth::AuthValidator;
+use crate::app::config::App;
+use crate::app::manager::AppManager;
+use crate::config_migration;
+use crate::error::{Error, Result};
+use crate::probe::{ClientSocket, ProbeFailure, expect_event, open_client, send_message};
+use crate::token::Token;
+use crate::webhook::types::validate_app_webhooks;
+
+/// Platform apps are migrated from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MigrationSource {
+    Pusher,
+    Soketi,
+}
+
+/// Sockudo event types behind each Pusher webhook setting
+const PUSHER_WEBHOOK_EVENTS: &[(&str, &[&str])] = &[
+    (
+        "channel_existence",
+        &["channel_occupied", "channel_vacated"],
+    ),
+    ("presence", &["member_added", "member_removed"]),
+    ("client_events", &["client_event"]),
+    ("cache_channels", &["cache_miss"]),
+    ("subscription_count", &["subscription_count"]),
+];
+
+/// Client events each connection may send per second on Pusher
+const PUSHER_CLIENT_EVENTS_PER_SECOND: u32 = 10;
+
+/// Event published by `verify`
+const VERIFY_EVENT: &str = "sockudo-migration-check";
+
+/// How long `verify` waits for deliveries after its last publish
+const VERIFY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// App fields that take their number as a string
+const STRING_NUMBER_FIELDS: &[&str] = &[
+    "max_connections",
+    "max_client_events_per_second",
+    "max_backend_events_per_second",
+    "max_read_requests_per_second",
+    "max_presence_members_per_channel",
+    "max_presence_member_size_in_kb",
+    "max_event_channels_at_once",
+    "max_event_payload_in_kb",
+    "max_event_batch_size",
+    "activity_timeout",
+];
+
+/// App fields Sockudo requires, where 0 means unlimited
+const REQUIRED_LIMITS: &[&str] = &["max_connections", "max_client_events_per_second"];
+
+#[derive(Debug, Deserialize)]
+struct PusherExport {
+    apps: Vec<PusherApp>,
+}
+
+/// An app as listed in a Pusher export, with its dashboard settings
+#[derive(Debug, Deserialize)]
+struct PusherApp {
+    app_id: String,
+    key: String,
+    secret: String,
+    cluster: String,
+    #[serde(default)]
+    enable_client_events: bool,
+    #[serde(default)]
+    enable_user_authentication: bool,
+    #[serde(default)]
+    webhooks: Vec<PusherWebhook>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PusherWebhook {
+    url: String,
+    /// Pusher webhook settings, e.g. `channel_existence` or `presence`
+    #[serde(default)]
+    events: Vec<String>,
+}
+
+/// An app read from the old platform
+pub struct SourceApp {
+    pub app: App,
+    /// Pusher cluster serving the app
+    pub cluster: Option<String>,
+}
+
+/// Read the apps in `path`, a soketi config file or a Pusher export
+pub fn load(from: MigrationSource, path: &str) -> Result<Vec<SourceApp>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| Error::ConfigFileError(format!("Failed to read {}: {}", path, e)))?;
+    let apps = match from {
+        MigrationSource::Pusher => {
+            let export: PusherExport = serde_json::from_str(&contents).map_err(|e| {
+                Error::ConfigFileError(format!("Failed to parse Pusher export {}: {}", path, e))
+            })?;
+            export
+                .apps
+                .into_iter()
+                .map(from_pusher)
+                .collect::<Result<Vec<_>>>()?
+        }
+        MigrationSource::Soketi => {
+            let mut config: Value = serde_json::from_str(&contents).map_err(|e| {
+                Error::ConfigFileError(format!("Failed to parse soketi config {}: {}", path, e))
+            })?;
+            // Expands soketi's dotted keys and camelCase app fields
+            config_migration::migrate(&mut config);
+            let apps = config
+                .pointer_mut("/app_manager/array/apps")
+                .and_then(Value::as_array_mut)
+                .map(std::mem::take)
+                .ok_or_else(|| {
+                    Error::ConfigFileError(format!(
+                        "{} has no apps under appManager.array.apps",
+                        path
+                    ))
+                })?;
+            apps.into_iter()
+                .map(from_soketi)
+                .collect::<Result<Vec<_>>>()?
+        }
+    };
+
+    for source in &apps {
+        validate_app_webhooks(&source.app)?;
+    }
+    Ok(apps)
+}
+
+fn from_pusher(pusher: PusherApp) -> Result<SourceApp> {
+    let webhooks = pusher
+        .webhooks
+        .into_iter()
+        .map(|webhook| {
+            let mut event_types = Vec::new();
+            for setting in &webhook.events {
+                let (_, events) = PUSHER_WEBHOOK_EVENTS
+                    .iter()
+                    .find(|(name, _)| *name == setting.as_str())
+                    .ok_or_else(|| {
+                        Error::ConfigurationError(format!(
+                            "Unknown Pusher webhook setting '{}' for app {}",
+                            setting, pusher.app_id
+                        ))
+                    })?;
+                event_types.extend(events.iter().map(|event| event.to_string()));
+            }
+            Ok(json!({ "url": webhook.url, "event_types": event_types }))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let app = json!({
+        "id": pusher.app_id,
+        "key": pusher.key,
+        "secret": pusher.secret,
+        "max_connections": "0",
+        "enable_client_messages": pusher.enable_client_events,
+        "enabled": true,
+        "max_client_events_per_second": PUSHER_CLIENT_EVENTS_PER_SECOND.to_string(),
+        "enable_user_authentication": pusher.enable_user_authentication,
+        "webhooks": webhooks,
+    });
+    Ok(SourceApp {
+        app: parse_app(app)?,
+        cluster: Some(pusher.cluster),
+    })
+}
+
+fn from_soketi(app: Value) -> Result<SourceApp> {
+    let Value::Object(mut app) = app else {
+        return Err(Error::ConfigFileError(
+            "soketi apps must be objects".to_string(),
+        ));
+    };
+
+    // soketi takes plain numbers, with -1 for no limit
+    for field in STRING_NUMBER_FIELDS {
+        match app.get(*field).and_then(Value::as_f64) {
+            Some(limit) if limit >= 0.0 => {
+                app.insert(field.to_string(), json!((limit.round() as u64).to_string()));
+            }
+            Some(_) if REQUIRED_LIMITS.contains(field) => {
+                app.insert(field.to_string(), json!("0"));
+            }
+            Some(_) => {
+                app.remove(*field);
+            }
+            None => {}
+        }
+    }
+    for field in REQUIRED_LIMITS {
+        app.entry(field.to_string()).or_insert_with(|| json!("0"));
+    }
+    for field in ["max_channel_name_length", "max_event_name_length"] {
+        if app
+            .get(field)
+            .and_then(Value::as_i64)
+            .is_some_and(|limit| limit < 0)
+        {
+            app.remove(field);
+        }
+    }
+    app.entry("enabled").or_insert(json!(true));
+    app.entry("enable_client_messages").or_insert(json!(false));
+
+    if let Some(Value::Array(webhooks)) = app.remove("webhooks") {
+        let webhooks: Vec<Value> = webhooks.into_iter().map(soketi_webhook).collect();
+        app.insert("webhooks".to_string(), Value::Array(webhooks));
+    }
+
+    Ok(SourceApp {
+        app: parse_app(Value::Object(app))?,
+        cluster: None,
+    })
+}
+
+/// Map a soketi webhook onto Sockudo's fields
+fn soketi_webhook(webhook: Value) -> Value {
+    let Value::Object(webhook) = webhook else {
+        return webhook;
+    };
+    let mut mapped = Map::new();
+    for key in ["url", "event_types", "headers", "lambda_function"] {
+        if let Some(value) = webhook.get(key) {
+            mapped.insert(key.to_string(), value.clone());
+        }
+    }
+    if let Some(filter) = webhook.get("filter") {
+        mapped.insert(
+            "filter".to_string(),
+            json!({
+                "channel_prefix": filter.get("channel_name_starts_with"),
+                "channel_suffix": filter.get("channel_name_ends_with"),
+            }),
+        );
+    }
+    if let Some(lambda) = webhook.get("lambda") {
+        let asynchronous = lambda
+            .get("async")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        mapped.insert(
+            "lambda".to_string(),
+            json!({
+                "function_name": webhook.get("lambda_function"),
+                "region": lambda.get("region").cloned().unwrap_or(json!("us-east-1")),
+                "invocation_type": if asynchronous { "event" } else { "request_response" },
+            }),
+        );
+        mapped.remove("lambda_function");
+    }
+    Value::Object(mapped)
+}
+
+fn parse_app(app: Value) -> Result<App> {
+    let id = app["id"].as_str().unwrap_or_default().to_string();
+    serde_json::from_value(app)
+        .map_err(|e| Error::ConfigFileError(format!("Failed to convert app {}: {}", id, e)))
+}
+
+/// Confirm a Pusher app's credentials against its HTTP API, returning how
+/// many channels are occupied on Pusher right now
+pub async fn check_pusher_app(client: &reqwest::Client, source: &SourceApp) -> Result<usize> {
+    let Some(cluster) = &source.cluster else {
+        return Ok(0);
+    };
+    let url = signed_url(
+        &source.app,
+        &format!("https://api-{}.pusher.com", cluster),
+        "GET",
+        &format!("/apps/{}/channels", source.app.id),
+        None,
+    );
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| Error::Other(format!("Failed to reach Pusher: {}", e)))?;
+    if !response.status().is_success() {
+        return Err(Error::ConfigurationError(format!(
+            "Pusher rejected the credentials of app {}: {}",
+            source.app.id,
+            response.status()
+        )));
+    }
+    let channels: Value = response
+        .json()
+        .await
+        .map_err(|e| Error::Other(format!("Invalid Pusher response: {}", e)))?;
+    Ok(channels["channels"].as_object().map_or(0, Map::len))
+}
+
+/// Create the apps, or update them when they already exist
+pub async fn apply(
+    app_manager: &Arc<dyn AppManager + Send + Sync>,
+    apps: &[SourceApp],
+) -> Result<()> {
+    for source in apps {
+        if app_manager.find_by_id(&source.app.id).await?.is_some() {
+            app_manager.update_app(source.app.clone()).await?;
+            println!("Updated app {}", source.app.id);
+        } else {
+            app_manager.create_app(source.app.clone()).await?;
+            println!("Created app {}", source.app.id);
+        }
+    }
+    Ok(())
+}
+
+/// The apps as an `app_manager` config section for the memory driver
+pub fn apps_config(apps: &[SourceApp]) -> Result<Value> {
+    let apps: Vec<&App> = apps.iter().map(|source| &source.app).collect();
+    Ok(json!({
+        "app_manager": {
+            "driver": "memory",
+            "array": { "apps": serde_json::to_value(apps)? },
+        }
+    }))
+}
+
+/// A platform `verify` subscribes and publishes through
+struct Endpoint {
+    /// `host:port` of the WebSocket endpoint
+    ws: String,
+    /// Base URL of the HTTP API
+    api: String,
+}
+
+/// Dual-publish settings
+pub struct VerifyOptions {
+    /// `host:port` of the Sockudo node
+    pub target: String,
+    /// `host:port` of the soketi server; Pusher apps use their cluster
+    pub legacy: Option<String>,
+    pub channel: String,
+    pub events: u32,
+}
+
+/// Dual-publish `options.events` events for the app and compare the
+/// deliveries on the old platform and on Sockudo
+pub async fn verify(
+    client: &reqwest::Client,
+    source: &SourceApp,
+    options: &VerifyOptions,
+) -> Result<Value> {
+    let legacy = match (&source.cluster, &options.legacy) {
+        (_, Some(legacy)) => Endpoint {
+            ws: legacy.clone(),
+            api: format!("http://{}", legacy),
+        },
+        (Some(cluster), None) => Endpoint {
+            ws: format!("ws-{}.pusher.com:80", cluster),
+            api: format!("https://api-{}.pusher.com", cluster),
+        },
+        (None, None) => {
+            return Err(Error::ConfigurationError(
+                "--legacy is required to verify soketi apps".to_string(),
+            ));
+        }
+    };
+    let sockudo = Endpoint {
+        ws: options.target.clone(),
+        api: format!("http://{}", options.target),
+    };
+
+    let app = &source.app;
+    let mut legacy_ws = subscribe(&legacy, app, &options.channel)
+        .await
+        .map_err(|e| probe_error("old platform", e))?;
+    let mut sockudo_ws = subscribe(&sockudo, app, &options.channel)
+        .await
+        .map_err(|e| probe_error("Sockudo", e))?;
+
+    let run = uuid::Uuid::new_v4().to_string();
+    for seq in 0..options.events {
+        let body = json!({
+            "name": VERIFY_EVENT,
+            "channels": [options.channel],
+            "data": json!({ "run": run, "seq": seq }).to_string(),
+        })
+        .to_string();
+        for endpoint in [&legacy, &sockudo] {
+            publish(client, endpoint, app, &body).await?;
+        }
+    }
+
+    let legacy_received = collect(&mut legacy_ws, &run, options.events).await;
+    let sockudo_received = collect(&mut sockudo_ws, &run, options.events).await;
+
+    let expected: BTreeSet<u64> = (0..u64::from(options.events)).collect();
+    let legacy_report = delivery_report(&legacy_received, &expected);
+    let sockudo_report = delivery_report(&sockudo_received, &expected);
+    let legacy_payloads: BTreeMap<u64, &String> = legacy_received
+        .iter()
+        .map(|(seq, data)| (*seq, data))
+        .collect();
+    let mismatched: Vec<u64> = sockudo_received
+        .iter()
+        .filter(|(seq, data)| {
+            legacy_payloads
+                .get(seq)
+                .is_some_and(|legacy| *legacy != data)
+        })
+        .map(|(seq, _)| *seq)
+        .collect();
+    // The old platform's deliveries are reported for comparison; Sockudo
+    // passes when it delivered every event once, in order, unchanged
+    let passed = sockudo_report["missing"]
+        .as_array()
+        .is_some_and(Vec::is_empty)
+        && sockudo_report["duplicates"] == 0
+        && sockudo_report["in_order"] == true
+        && mismatched.is_empty();
+
+    Ok(json!({
+        "app_id": app.id,
+        "channel": options.channel,
+        "published": options.events,
+        "legacy": legacy_report,
+        "sockudo": sockudo_report,
+        "mismatched_payloads": mismatched,
+        "passed": passed,
+    }))
+}
+
+fn probe_error(platform: &str, failure: ProbeFailure) -> Error {
+    Error::Other(format!(
+        "Failed to subscribe on {} ({}): {}",
+        platform, failure.stage, failure.error
+    ))
+}
+
+async fn subscribe(
+    endpoint: &Endpoint,
+    app: &App,
+    channel: &str,
+) -> std::result::Result<ClientSocket, ProbeFailure> {
+    let stream = TcpStream::connect(&endpoint.ws)
+        .await
+        .map_err(|e| ProbeFailure::new("connect", e))?;
+    let mut ws = open_client(stream, &endpoint.ws, &app.key, "sockudo-migrate").await?;
+    expect_event(
+        &mut ws,
+        "pusher:connection_established",
+        "connection_established",
+    )
+    .await?;
+    let subscribe = json!({
+        "event": "pusher:subscribe",
+        "data": { "channel": channel },
+    });
+    send_message(&mut ws, &subscribe.to_string(), "subscribe").await?;
+    expect_event(
+        &mut ws,
+        "pusher_internal:subscription_succeeded",
+        "subscribe",
+    )
+    .await?;
+    Ok(ws)
+}
+
+async fn publish(
+    client: &reqwest::Client,
+    endpoint: &Endpoint,
+    app: &App,
+    body: &str,
+) -> Result<()> {
+    let url = signed_url(
+        app,
+        &endpoint.api,
+        "POST",
+        &format!("/apps/{}/events", app.id),
+        Some(body),
+    );
+    let response = client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body.to_string())
+        .send()
+        .await
+        .map_err(|e| Error::Other(format!("Failed to publish to {}: {}", endpoint.api, e)))?;
+    if !response.status().is_success() {
+        return Err(Error::Other(format!(
+            "{} answered {} to a publish",
+            endpoint.api,
+            response.status()
+        )));
+    }
+    Ok(())
+}
+
+/// Sequence numbers and payloads of this run's events, in arrival order
+async fn collect(ws: &mut ClientSocket, run: &str, events: u32) -> Vec<(u64, String)> {
+    let deadline = tokio::time::Instant::now() + VERIFY_TIMEOUT;
+    let mut received = Vec::new();
+    while received.len() < events as usize {
+        let message = match tokio::time::timeout_at(
+            deadline,
+            expect_event(ws, VERIFY_EVENT, "deliver"),
+        )
+        .await
+        {
+            Ok(Ok(message)) => message,
+            Ok(Err(_)) | Err(_) => break,
+        };
+        let Some(data) = message.get("data").and_then(Value::as_str) else {
+            continue;
+        };
+        let Ok(payload) = serde_json::from_str::<Value>(data) else {
+            continue;
+        };
+        if payload["run"] != run {
+            continue;
+        }
+        if let Some(seq) = payload["seq"].as_u64() {
+            received.push((seq, data.to_string()));
+        }
+    }
+    let _ = ws
+        .write_frame(fastwebsockets::Frame::close(1000, b""))
+        .await;
+    received
+}
+
+fn delivery_report(received: &[(u64, String)], expected: &BTreeSet<u64>) -> Value {
+    let seen: BTreeSet<u64> = received.iter().map(|(seq, _)| *seq).collect();
+    let missing: Vec<u64> = expected.difference(&seen).copied().collect();
+    let in_order = received.windows(2).all(|pair| pair[0].0 < pair[1].0);
+    json!({
+        "received": received.len(),
+        "missing": missing,
+        "duplicates": received.len() - seen.len(),
+        "in_order": in_order,
+    })
+}
+
+/// `url` signed for the Pusher HTTP API
+fn signed_url(app: &App, base: &str, method: &str, path: &str, body: Option<&str>) -> String {
+    let mut params = BTreeMap::new();
+    params.insert("auth_key".to_string(), app.key.clone());
+    params.insert(
+        "auth_timestamp".to_string(),
+        chrono::Utc::now().timestamp().to_string(),
+    );
+    params.insert("auth_version".to_string(), "1.0".to_string());
+    if let Some(body) = body {
+        params.insert(
+            "body_md5".to_string(),
+            format!("{:x}", md5::compute(body.as_bytes())),
+        );
+    }
+    let signature = Token::new(app.key.clone(), app.secret.clone())
+        .sign(&AuthValidator::api_string_to_sign(method, path, &params));
+    let query = params
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join("&");
+    format!("{}{}?{}&auth_signature={}", base, path, query, signature)
+}
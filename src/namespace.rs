@@ -3,13 +3,18 @@ use crate::app::manager::AppManager;
 use crate::channel::PresenceMemberInfo;
 use crate::error::{Error, Result}; // Error should be in scope
 
+use crate::protocol::encoding::{encode_json, text_frame};
 use crate::protocol::messages::PusherMessage;
-use crate::websocket::{ConnectionState, SocketId, WebSocket, WebSocketRef};
+use crate::websocket::{
+    ConnectionState, ConnectionStats, ConnectionUsage, MessageSender, SocketDetails, SocketId,
+    WebSocket, WebSocketRef,
+};
 use dashmap::{DashMap, DashSet};
 use fastwebsockets::{Frame, OpCode, Payload, WebSocketWrite};
 use futures::future::join_all;
 use hyper::upgrade::Upgraded;
 use hyper_util::rt::TokioIo;
+use smallvec::SmallVec;
 use std::collections::HashMap;
 // use std::collections::HashSet; // HashSet seems unused
 use std::sync::Arc;
@@ -19,6 +24,9 @@ use tokio::sync::{Mutex, mpsc};
 use tracing::{debug, error, info, warn};
 // use tokio::sync::Semaphore; // Semaphore seems unused
 
+/// Connections a message fans out to; small channels stay off the heap
+pub type Connections = SmallVec<[Arc<Mutex<WebSocket>>; 16]>;
+
 // Represents a namespace, typically tied to a specific application ID.
 // Manages WebSocket connections, channel subscriptions, and user presence within that app.
 pub struct Namespace {
@@ -82,10 +90,12 @@ impl Namespace {
         connection_state.socket_id = socket_id.clone();
         connection_state.app = Some(app_config); // Store the fetched app config
 
+        let stats = Arc::new(ConnectionStats::default());
         let connection = WebSocket {
             state: connection_state,
             socket: Some(socket_writer), // The actual write half of the WebSocket.
-            message_sender: tx,          // Sender part of the message channel.
+            message_sender: MessageSender::new(tx, stats.clone()), // Sender part of the message channel.
+            stats: stats.clone(),
         };
 
         let connection_arc = Arc::new(Mutex::new(connection));
@@ -116,10 +126,13 @@ impl Namespace {
                     debug!(socket_id = %task_socket_id, "Frame text payload snippet: '{}'", payload_str_snippet);
                 }
 
+                let frame_len = frame.payload.len();
                 let mut connection_guard = task_connection_arc.lock().await;
                 if let Some(socket) = &mut connection_guard.socket {
                     // Attempt to send the frame. The `frame` is consumed here.
-                    if let Err(e) = socket.write_frame(frame).await {
+                    let result = socket.write_frame(frame).await;
+                    stats.queued_bytes.fetch_sub(frame_len, Ordering::Relaxed);
+                    if let Err(e) = result {
                         // Log the error, which will include the "Broken pipe" details if that's the cause
                         error!(
                             socket_id = %task_socket_id,
@@ -129,6 +142,7 @@ impl Namespace {
                         connection_guard.socket.take();
                         break;
                     }
+                    stats.messages_sent.fetch_add(1, Ordering::Relaxed);
                 } else {
                     info!(
                         socket_id = %task_socket_id,
@@ -172,9 +186,7 @@ impl Namespace {
     pub async fn send_message(&self, socket_id: &SocketId, message: PusherMessage) -> Result<()> {
         if let Some(connection) = self.get_connection(socket_id) {
             // Serialize the message to JSON. Propagate serialization errors.
-            let message_payload = serde_json::to_string(&message)?;
-            // Create a WebSocket text frame.
-            let frame = Frame::text(Payload::from(message_payload.into_bytes()));
+            let frame = text_frame(&encode_json(&message)?);
 
             let conn_guard = connection.lock().await;
             conn_guard.message_sender.send(frame).map_err(|e| {
@@ -200,31 +212,21 @@ impl Namespace {
         message: PusherMessage,
         except: Option<&SocketId>,
     ) -> Result<()> {
-        let payload = Arc::new(serde_json::to_string(&message)?);
-
-        if let Some(socket_ids_ref) = self.channels.get(channel) {
-            let socket_ids_snapshot = socket_ids_ref.clone(); // Clone the DashSet for iteration
-            drop(socket_ids_ref); // Drop the DashMap RefGuard
-
-            for socket_id_entry in socket_ids_snapshot.iter() {
-                // iter() on DashSet gives &SocketId
-                let current_socket_id = socket_id_entry.key(); // Get the SocketId itself
-                if except.is_none_or(|excluded_id| excluded_id != current_socket_id) {
-                    if let Some(connection) = self.get_connection(current_socket_id) {
-                        let current_payload_clone = payload.clone();
-                        let frame =
-                            Frame::text(Payload::from(current_payload_clone.as_bytes().to_vec()));
-
-                        let conn_guard = connection.lock().await;
-                        if let Err(e) = conn_guard.message_sender.send(frame) {
-                            warn!(
-                                "Failed to queue broadcast message for socket {:?}: {:?}",
-                                current_socket_id.0,
-                                e // Accessing the String inside SocketId for logging
-                            );
-                        }
-                        drop(conn_guard);
-                    }
+        // Encoded once; each recipient gets a copy of the bytes
+        let payload = encode_json(&message)?;
+
+        if self.channels.contains_key(channel) {
+            for connection in self.channel_connections(channel) {
+                let conn_guard = connection.lock().await;
+                if except == Some(&conn_guard.state.socket_id) {
+                    continue;
+                }
+                if let Err(e) = conn_guard.message_sender.send(text_frame(&payload)) {
+                    warn!(
+                        "Failed to queue broadcast message for socket {:?}: {:?}",
+                        conn_guard.state.socket_id.0,
+                        e // Accessing the String inside SocketId for logging
+                    );
                 }
             }
         } else {
@@ -247,21 +249,24 @@ impl Namespace {
             let socket_ids_snapshot = socket_ids_ref.clone();
             drop(socket_ids_ref);
 
-            for socket_id_entry in socket_ids_snapshot.iter() {
-                let socket_id = socket_id_entry.key();
-                if let Some(connection) = self.get_connection(socket_id) {
-                    let presence_data = {
-                        let conn_guard = connection.lock().await;
-                        conn_guard
-                            .state
-                            .presence
-                            .as_ref()
-                            .and_then(|p_map| p_map.get(channel).cloned())
-                    };
-                    if let Some(presence_info) = presence_data {
-                        presence_members.insert(presence_info.user_id.clone(), presence_info);
-                    }
-                }
+            // Lock the sockets concurrently so one busy socket doesn't hold
+            // up reading the rest of a large channel
+            let connections: Vec<_> = socket_ids_snapshot
+                .iter()
+                .filter_map(|socket_id_entry| self.get_connection(socket_id_entry.key()))
+                .collect();
+            let presence_data = join_all(connections.iter().map(|connection| async move {
+                connection
+                    .lock()
+                    .await
+                    .state
+                    .presence
+                    .as_ref()
+                    .and_then(|p_map| p_map.get(channel).cloned())
+            }))
+            .await;
+            for presence_info in presence_data.into_iter().flatten() {
+                presence_members.insert(presence_info.user_id.clone(), presence_info);
             }
         } else {
             info!(
@@ -273,6 +278,48 @@ impl Namespace {
     }
 
     // Retrieves all connection Arcs for sockets subscribed to a specific channel.
+    pub async fn get_channel_socket_details(&self, channel: &str) -> Vec<SocketDetails> {
+        let mut details = Vec::new();
+        for entry in self.get_channel_sockets(channel).iter() {
+            let ws = entry.value().lock().await;
+            details.push(SocketDetails {
+                socket_id: entry.key().0.clone(),
+                node_id: None,
+                ip: ws.state.remote_ip.clone(),
+                user_id: ws.state.user_id.clone(),
+                connected_at: ws.state.connected_at,
+                tags: ws.state.tags.clone(),
+            });
+        }
+        details
+    }
+
+    // Resource use of every connection in this namespace.
+    pub async fn get_connection_usage(&self) -> Vec<ConnectionUsage> {
+        let connections: Vec<Arc<Mutex<WebSocket>>> = self
+            .sockets
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+        let mut usage = Vec::with_capacity(connections.len());
+        for connection in connections {
+            usage.push(connection.lock().await.usage(&self.app_id));
+        }
+        usage
+    }
+
+    /// Connections subscribed to `channel`, collected so no map guard is
+    /// held while they are written to
+    pub fn channel_connections(&self, channel: &str) -> Connections {
+        match self.channels.get(channel) {
+            Some(socket_ids) => socket_ids
+                .iter()
+                .filter_map(|socket_id| self.get_connection(socket_id.key()))
+                .collect(),
+            None => Connections::new(),
+        }
+    }
+
     pub fn get_channel_sockets(&self, channel: &str) -> DashMap<SocketId, Arc<Mutex<WebSocket>>> {
         let sockets_in_channel = DashMap::new();
         if let Some(channel_sockets_ref) = self.channels.get(channel) {
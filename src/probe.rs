@@ -0,0 +1,249 @@
+// src/probe.rs
+//! Loopback probe of the WebSocket path. The server dials its own listener,
+//! completes a WebSocket handshake through the same route clients use, waits
+//! for `pusher:connection_established`, and round-trips a `pusher:ping`, so
+//! a monitor sees whether connections actually work rather than whether the
+//! HTTP server answers.
+
+use std::future::Future;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use fastwebsockets::{FragmentCollector, Frame, OpCode, Payload, handshake};
+use http_body_util::Empty;
+use hyper::Request;
+use hyper::body::Bytes;
+use hyper::header::{CONNECTION, HOST, UPGRADE};
+use hyper::upgrade::Upgraded;
+use hyper_util::rt::TokioIo;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::net::TcpStream;
+
+use crate::listener::listen_addrs;
+use crate::options::ListenConfig;
+
+/// Longest a whole probe may take before it counts as failed
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Milliseconds spent reaching each stage of the probe
+#[derive(Debug, Default, Serialize)]
+pub struct ProbeTimings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connect_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub handshake_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connection_established_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ping_pong_ms: Option<f64>,
+    pub total_ms: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProbeReport {
+    pub ok: bool,
+    /// Stage the probe failed at
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stage: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub timings: ProbeTimings,
+}
+
+/// Client end of a loopback WebSocket
+pub(crate) type ClientSocket = FragmentCollector<TokioIo<Upgraded>>;
+
+pub(crate) struct ProbeFailure {
+    pub stage: &'static str,
+    pub error: String,
+}
+
+impl ProbeFailure {
+    pub(crate) fn new(stage: &'static str, error: impl ToString) -> Self {
+        Self {
+            stage,
+            error: error.to_string(),
+        }
+    }
+}
+
+/// Runs handshake background tasks on the tokio runtime
+struct SpawnExecutor;
+
+impl<Fut> hyper::rt::Executor<Fut> for SpawnExecutor
+where
+    Fut: Future + Send + 'static,
+    Fut::Output: Send + 'static,
+{
+    fn execute(&self, fut: Fut) {
+        tokio::spawn(fut);
+    }
+}
+
+pub struct WsProbe {
+    addr: SocketAddr,
+}
+
+impl WsProbe {
+    pub fn new(host: &str, port: u16, listen: &ListenConfig) -> Self {
+        Self {
+            addr: loopback_addr(host, port, listen),
+        }
+    }
+
+    /// Connect as a client of `app_key` and report how long each stage took
+    pub async fn run(&self, app_key: &str) -> ProbeReport {
+        let started = Instant::now();
+        let mut timings = ProbeTimings::default();
+        let outcome =
+            match tokio::time::timeout(PROBE_TIMEOUT, self.probe(app_key, started, &mut timings))
+                .await
+            {
+                Ok(outcome) => outcome,
+                Err(_) => Err(ProbeFailure::new(
+                    "timeout",
+                    format!("Probe took longer than {}s", PROBE_TIMEOUT.as_secs()),
+                )),
+            };
+        timings.total_ms = elapsed_ms(started);
+
+        match outcome {
+            Ok(()) => ProbeReport {
+                ok: true,
+                stage: None,
+                error: None,
+                timings,
+            },
+            Err(failure) => ProbeReport {
+                ok: false,
+                stage: Some(failure.stage),
+                error: Some(failure.error),
+                timings,
+            },
+        }
+    }
+
+    async fn probe(
+        &self,
+        app_key: &str,
+        started: Instant,
+        timings: &mut ProbeTimings,
+    ) -> Result<(), ProbeFailure> {
+        let authority = self.addr.to_string();
+        let stream = TcpStream::connect(self.addr)
+            .await
+            .map_err(|e| ProbeFailure::new("connect", e))?;
+        timings.connect_ms = Some(elapsed_ms(started));
+
+        let mut ws = open_client(stream, &authority, app_key, "sockudo-probe").await?;
+        timings.handshake_ms = Some(elapsed_ms(started));
+
+        expect_event(
+            &mut ws,
+            "pusher:connection_established",
+            "connection_established",
+        )
+        .await?;
+        timings.connection_established_ms = Some(elapsed_ms(started));
+
+        send_message(&mut ws, r#"{"event":"pusher:ping","data":{}}"#, "ping_pong").await?;
+        expect_event(&mut ws, "pusher:pong", "ping_pong").await?;
+        timings.ping_pong_ms = Some(elapsed_ms(started));
+
+        // Leave the server to tidy up the connection as it would for a client
+        let _ = ws.write_frame(Frame::close(1000, b"")).await;
+        Ok(())
+    }
+}
+
+/// Address that reaches the listener bound for `host:port` from this node,
+/// dialing loopback when it binds every interface
+pub(crate) fn loopback_addr(host: &str, port: u16, listen: &ListenConfig) -> SocketAddr {
+    let addr = listen_addrs(host, port, listen)
+        .first()
+        .map(|(addr, _)| *addr)
+        .unwrap_or_else(|| SocketAddr::from((Ipv4Addr::LOCALHOST, port)));
+    let ip = match addr.ip() {
+        IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+        ip => ip,
+    };
+    SocketAddr::new(ip, port)
+}
+
+/// Complete a WebSocket handshake with the server at `authority` over
+/// `stream`, as a client of `app_key`
+pub(crate) async fn open_client(
+    stream: TcpStream,
+    authority: &str,
+    app_key: &str,
+    client: &str,
+) -> Result<ClientSocket, ProbeFailure> {
+    let request = Request::builder()
+        .method("GET")
+        .uri(format!(
+            "http://{}/app/{}?protocol=7&client={}",
+            authority, app_key, client
+        ))
+        .header(HOST, authority)
+        .header(UPGRADE, "websocket")
+        .header(CONNECTION, "upgrade")
+        .header("Sec-WebSocket-Key", handshake::generate_key())
+        .header("Sec-WebSocket-Version", "13")
+        .body(Empty::<Bytes>::new())
+        .map_err(|e| ProbeFailure::new("handshake", e))?;
+    let (ws, _) = handshake::client(&SpawnExecutor, request, stream)
+        .await
+        .map_err(|e| ProbeFailure::new("handshake", e))?;
+    Ok(FragmentCollector::new(ws))
+}
+
+pub(crate) async fn send_message(
+    ws: &mut ClientSocket,
+    message: &str,
+    stage: &'static str,
+) -> Result<(), ProbeFailure> {
+    ws.write_frame(Frame::text(Payload::Borrowed(message.as_bytes())))
+        .await
+        .map_err(|e| ProbeFailure::new(stage, e))
+}
+
+/// Read frames until one carries `event` and return that message, failing
+/// on a `pusher:error` or a closed connection
+pub(crate) async fn expect_event(
+    ws: &mut ClientSocket,
+    event: &str,
+    stage: &'static str,
+) -> Result<Value, ProbeFailure> {
+    loop {
+        let frame = ws
+            .read_frame()
+            .await
+            .map_err(|e| ProbeFailure::new(stage, e))?;
+        match frame.opcode {
+            OpCode::Text => {
+                let message: Value = serde_json::from_slice(&frame.payload)
+                    .map_err(|e| ProbeFailure::new(stage, e))?;
+                match message.get("event").and_then(Value::as_str) {
+                    Some(received) if received == event => return Ok(message),
+                    Some("pusher:error") => {
+                        return Err(ProbeFailure::new(stage, message["data"].to_string()));
+                    }
+                    _ => {}
+                }
+            }
+            OpCode::Close => {
+                return Err(ProbeFailure::new(
+                    stage,
+                    format!("Connection closed before {}", event),
+                ));
+            }
+            _ => {}
+        }
+    }
+}
+
+fn elapsed_ms(started: Instant) -> f64 {
+    started.elapsed().as_secs_f64() * 1000.0
+}
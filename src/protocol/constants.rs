@@ -7,3 +7,11 @@ pub const CHANNEL_NAME_REGEX: &str = r"^[a-zA-Z0-9_\-=@,.;]+$";
 
 pub const EVENT_NAME_MAX_LENGTH: usize = 200;
 pub const CLIENT_EVENT_PREFIX: &str = "client-";
+
+/// Reserved channel that delivers to every connection of an app. Its `#`
+/// prefix is outside the channel name charset, so clients can't subscribe.
+pub const SERVER_NOTICE_CHANNEL: &str = "#server-notice";
+pub const SERVER_NOTICE_EVENT: &str = "sockudo:notice";
+/// Reserved channel whose message, a `pusher:error`, is sent to every
+/// connection of an app before they are closed, once the app is disabled
+pub const APP_DISABLED_CHANNEL: &str = "#app-disabled";
@@ -0,0 +1,40 @@
+// src/protocol/encoding.rs
+//! Serialization of outgoing messages on the broadcast path. Each thread
+//! keeps one buffer that messages are serialized into, so encoding a
+//! message costs a single exact-size allocation for the frame instead of
+//! a string that grows as it is written. A broadcast encodes its message
+//! once and copies the bytes into each recipient's frame.
+
+use std::cell::RefCell;
+
+use fastwebsockets::{Frame, Payload};
+use serde::Serialize;
+
+/// Capacity each thread's buffer starts with, enough for most events
+const INITIAL_CAPACITY: usize = 4 * 1024;
+/// A buffer grown past this by an unusually large message is shrunk back,
+/// so one payload doesn't pin its size on every worker thread
+const MAX_RETAINED_CAPACITY: usize = 64 * 1024;
+
+thread_local! {
+    static ENCODE_BUFFER: RefCell<Vec<u8>> = RefCell::new(Vec::with_capacity(INITIAL_CAPACITY));
+}
+
+/// Serialize `message` as JSON through the thread's reusable buffer
+pub fn encode_json<T: Serialize + ?Sized>(message: &T) -> serde_json::Result<Vec<u8>> {
+    ENCODE_BUFFER.with(|buffer| {
+        let mut buffer = buffer.borrow_mut();
+        buffer.clear();
+        let result = serde_json::to_writer(&mut *buffer, message).map(|()| buffer.to_vec());
+        if buffer.capacity() > MAX_RETAINED_CAPACITY {
+            buffer.clear();
+            buffer.shrink_to(INITIAL_CAPACITY);
+        }
+        result
+    })
+}
+
+/// A text frame carrying a copy of an encoded message
+pub fn text_frame(encoded: &[u8]) -> Frame<'static> {
+    Frame::text(Payload::Owned(encoded.to_vec()))
+}
@@ -1,3 +1,4 @@
+use crate::protocol::constants::SERVER_NOTICE_EVENT;
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use std::collections::HashMap;
@@ -113,13 +114,13 @@ impl From<Value> for MessageData {
 }
 
 impl PusherMessage {
-    pub fn connection_established(socket_id: String) -> Self {
+    pub fn connection_established(socket_id: String, activity_timeout: u64) -> Self {
         Self {
             event: Some("pusher:connection_established".to_string()),
             data: Some(MessageData::from(
                 json!({
                     "socket_id": socket_id,
-                    "activity_timeout": 120
+                    "activity_timeout": activity_timeout
                 })
                 .to_string(),
             )),
@@ -151,6 +152,70 @@ impl PusherMessage {
         }
     }
 
+    /// A `pusher:subscription_error`, telling the client why it couldn't
+    /// subscribe to `channel`
+    pub fn subscription_error(
+        channel: String,
+        error_type: &str,
+        error: String,
+        status: u16,
+    ) -> Self {
+        Self {
+            event: Some("pusher:subscription_error".to_string()),
+            data: Some(MessageData::Json(json!({
+                "type": error_type,
+                "error": error,
+                "status": status
+            }))),
+            channel: Some(channel),
+            name: None,
+        }
+    }
+
+    /// A `pusher:error` sent while shedding load, advising the client how long
+    /// to wait before reconnecting
+    pub fn error_with_backoff(
+        code: u16,
+        message: String,
+        channel: Option<String>,
+        retry_in_ms: u64,
+    ) -> Self {
+        Self {
+            event: Some("pusher:error".to_string()),
+            data: Some(MessageData::Json(json!({
+                "code": code,
+                "message": message,
+                "retry_in_ms": retry_in_ms
+            }))),
+            channel,
+            name: None,
+        }
+    }
+
+    /// A `pusher:error` for a rejected rate/quota check, carrying the limiter state
+    /// so clients know how long to back off before retrying.
+    pub fn rate_limit_error(
+        code: u16,
+        message: String,
+        channel: Option<String>,
+        limit: u32,
+        remaining: u32,
+        reset_after: u64,
+    ) -> Self {
+        Self {
+            event: Some("pusher:error".to_string()),
+            data: Some(MessageData::Json(json!({
+                "code": code,
+                "message": message,
+                "limit": limit,
+                "remaining": remaining,
+                "reset_after": reset_after
+            }))),
+            channel,
+            name: None,
+        }
+    }
+
     pub fn channel_event<S: Into<String>>(event: S, channel: S, data: Value) -> Self {
         Self {
             event: Some(event.into()),
@@ -184,6 +249,27 @@ impl PusherMessage {
     }
 
     // New helper method for pong response
+    /// A `sockudo:notice` sent by operators to every connection
+    pub fn server_notice(data: Value) -> Self {
+        Self {
+            event: Some(SERVER_NOTICE_EVENT.to_string()),
+            data: Some(MessageData::Json(data)),
+            channel: None,
+            name: None,
+        }
+    }
+
+    /// Sent by the server to a connection that has been silent for its
+    /// activity timeout
+    pub fn ping() -> Self {
+        Self {
+            event: Some("pusher:ping".to_string()),
+            data: None,
+            channel: None,
+            name: None,
+        }
+    }
+
     pub fn pong() -> Self {
         Self {
             event: Some("pusher:pong".to_string()),
@@ -1,2 +1,4 @@
 pub mod constants;
+pub mod encoding;
 pub mod messages;
+pub mod subprotocols;
@@ -0,0 +1,101 @@
+// src/protocol/subprotocols.rs
+//! `Sec-WebSocket-Protocol` negotiation. Clients that offer subprotocols get
+//! the first one (in their order of preference) that has a registered
+//! handler; clients that offer none are served the Pusher JSON protocol.
+
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use fastwebsockets::upgrade::UpgradeFut;
+
+use crate::adapter::ConnectionHandler;
+use crate::error::Result;
+
+/// The Pusher Channels protocol with JSON frames
+pub const PUSHER_JSON: &str = "pusher-json";
+/// Reserved for the Pusher protocol with MessagePack frames. Not served
+/// unless a handler is registered for it.
+#[allow(dead_code)]
+pub const PUSHER_MSGPACK: &str = "pusher-msgpack";
+
+/// Serves connections that negotiated a given subprotocol
+#[async_trait]
+pub trait SubprotocolHandler: Send + Sync {
+    async fn serve(
+        &self,
+        handler: Arc<ConnectionHandler>,
+        fut: UpgradeFut,
+        app_key: String,
+        remote_ip: Option<String>,
+        tags: Vec<String>,
+    ) -> Result<()>;
+}
+
+/// The built-in Pusher JSON protocol
+pub struct PusherJsonProtocol;
+
+#[async_trait]
+impl SubprotocolHandler for PusherJsonProtocol {
+    async fn serve(
+        &self,
+        handler: Arc<ConnectionHandler>,
+        fut: UpgradeFut,
+        app_key: String,
+        remote_ip: Option<String>,
+        tags: Vec<String>,
+    ) -> Result<()> {
+        handler.handle_socket(fut, app_key, remote_ip, tags).await
+    }
+}
+
+type RegisteredHandler = (String, Arc<dyn SubprotocolHandler>);
+
+pub struct SubprotocolRegistry {
+    handlers: RwLock<Vec<RegisteredHandler>>,
+}
+
+impl Default for SubprotocolRegistry {
+    fn default() -> Self {
+        let registry = Self {
+            handlers: RwLock::new(Vec::new()),
+        };
+        registry.register(PUSHER_JSON, Arc::new(PusherJsonProtocol));
+        registry
+    }
+}
+
+impl SubprotocolRegistry {
+    /// Register `handler` for `name`, replacing any earlier handler for it
+    pub fn register(&self, name: &str, handler: Arc<dyn SubprotocolHandler>) {
+        let mut handlers = self.handlers.write().unwrap();
+        match handlers.iter_mut().find(|(existing, _)| existing == name) {
+            Some(entry) => entry.1 = handler,
+            None => handlers.push((name.to_string(), handler)),
+        }
+    }
+
+    /// Names of the subprotocols this server accepts
+    pub fn supported(&self) -> Vec<String> {
+        self.handlers
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Pick a subprotocol from a `Sec-WebSocket-Protocol` header value
+    pub fn negotiate(&self, offered: &str) -> Option<RegisteredHandler> {
+        let handlers = self.handlers.read().unwrap();
+        offered
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .find_map(|name| {
+                handlers
+                    .iter()
+                    .find(|(registered, _)| registered == name)
+                    .cloned()
+            })
+    }
+}
@@ -0,0 +1,88 @@
+// src/queue/codec.rs
+//! Encoding of jobs on the queue. JSON payloads are written as-is; other
+//! encodings start with a tag naming the encoding and its version, so a
+//! reader can tell each payload apart and clusters can switch encodings one
+//! node at a time once every node understands the new one.
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+
+use crate::error::{Error, Result};
+use crate::options::QueueCodec;
+use crate::queue::job::{Job, UntaggedWebhookJob};
+
+/// Tag of binary MessagePack payloads. 0xC1 is neither valid MessagePack
+/// nor UTF-8, so it can't be mistaken for the start of a JSON job.
+const MSGPACK_TAG: [u8; 2] = [0xC1, 1];
+/// Tag of MessagePack payloads on text-only transports (SQS), base64 encoded
+const MSGPACK_TEXT_TAG: &str = "msgpack1:";
+
+fn to_msgpack(job: &Job) -> Result<Vec<u8>> {
+    rmp_serde::to_vec_named(job)
+        .map_err(|e| Error::Queue(format!("Failed to encode job as MessagePack: {}", e)))
+}
+
+fn from_msgpack(payload: &[u8]) -> Result<Job> {
+    rmp_serde::from_slice(payload)
+        .map_err(|e| Error::Queue(format!("Failed to decode MessagePack job: {}", e)))
+}
+
+/// Parse a JSON job, falling back to the untagged webhook jobs queued by
+/// older versions
+fn from_json(payload: &[u8]) -> Result<Job> {
+    match serde_json::from_slice::<Job>(payload) {
+        Ok(job) => Ok(job),
+        Err(e) => serde_json::from_slice::<UntaggedWebhookJob>(payload)
+            .map(Job::from)
+            .map_err(|_| e.into()),
+    }
+}
+
+/// Encode a job for a binary-safe transport (Redis, Kafka)
+pub fn encode_job(codec: QueueCodec, job: &Job) -> Result<Vec<u8>> {
+    match codec {
+        QueueCodec::Json => Ok(serde_json::to_vec(job)?),
+        QueueCodec::Msgpack => {
+            let mut payload = MSGPACK_TAG.to_vec();
+            payload.extend(to_msgpack(job)?);
+            Ok(payload)
+        }
+    }
+}
+
+/// Decode a job written by [`encode_job`] with any codec
+pub fn decode_job(payload: &[u8]) -> Result<Job> {
+    match payload {
+        [0xC1, 1, rest @ ..] => from_msgpack(rest),
+        [0xC1, version, ..] => Err(Error::Queue(format!(
+            "Unsupported job encoding version {}",
+            version
+        ))),
+        _ => from_json(payload),
+    }
+}
+
+/// Encode a job for a transport that only carries text (SQS)
+pub fn encode_job_text(codec: QueueCodec, job: &Job) -> Result<String> {
+    match codec {
+        QueueCodec::Json => Ok(serde_json::to_string(job)?),
+        QueueCodec::Msgpack => Ok(format!(
+            "{}{}",
+            MSGPACK_TEXT_TAG,
+            BASE64.encode(to_msgpack(job)?)
+        )),
+    }
+}
+
+/// Decode a job written by [`encode_job_text`] with any codec
+pub fn decode_job_text(payload: &str) -> Result<Job> {
+    match payload.strip_prefix(MSGPACK_TEXT_TAG) {
+        Some(encoded) => {
+            let bytes = BASE64
+                .decode(encoded)
+                .map_err(|e| Error::Queue(format!("Invalid base64 job payload: {}", e)))?;
+            from_msgpack(&bytes)
+        }
+        None => from_json(payload.as_bytes()),
+    }
+}
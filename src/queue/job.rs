@@ -0,0 +1,110 @@
+// src/queue/job.rs
+//! The job envelope every queue driver carries. The envelope holds what all
+//! jobs share, the owning app and the retry bookkeeping, while `task` says
+//! what the job does, so subsystems other than webhooks can queue work
+//! through the same managers.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::webhook::types::JobPayload;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    /// App the work belongs to
+    pub app_id: String,
+    #[serde(flatten)]
+    pub task: JobTask,
+    /// Jobs sharing a key are delivered in the order they were queued, on
+    /// drivers that can partition by it (Kafka, SQS FIFO); webhook jobs are
+    /// keyed by channel
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ordering_key: Option<String>,
+    /// Failed processing attempts so far
+    #[serde(default)]
+    pub attempts: u32,
+    /// Error from the latest failed attempt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+/// What a job does, tagged by `type` on the wire
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JobTask {
+    /// Deliver a batch of webhook events
+    Webhook(WebhookJob),
+    /// Work for another subsystem, which picks its jobs out by `name`
+    Custom {
+        name: String,
+        #[serde(default)]
+        data: Value,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookJob {
+    pub payload: JobPayload,
+    pub original_signature: String, // Sockudo's internal signature for queue deduplication, etc.
+}
+
+impl Job {
+    pub fn new(app_id: impl Into<String>, task: JobTask) -> Self {
+        Self {
+            app_id: app_id.into(),
+            task,
+            ordering_key: None,
+            attempts: 0,
+            last_error: None,
+        }
+    }
+
+    pub fn with_ordering_key(mut self, ordering_key: impl Into<String>) -> Self {
+        self.ordering_key = Some(ordering_key.into());
+        self
+    }
+
+    /// Key that partitions jobs which must stay in order, falling back to
+    /// the app so a job without one still lands somewhere stable
+    pub fn partition_key(&self) -> &str {
+        self.ordering_key.as_deref().unwrap_or(&self.app_id)
+    }
+}
+
+impl JobTask {
+    /// Short name of the task, for logs and errors
+    pub fn kind(&self) -> &str {
+        match self {
+            JobTask::Webhook(_) => "webhook",
+            JobTask::Custom { name, .. } => name,
+        }
+    }
+}
+
+/// Webhook jobs queued before jobs were typed carry no `type` tag. They are
+/// read through this shape so an upgrade doesn't strand queued deliveries.
+#[derive(Deserialize)]
+pub(crate) struct UntaggedWebhookJob {
+    app_id: String,
+    payload: JobPayload,
+    original_signature: String,
+    #[serde(default)]
+    attempts: u32,
+    #[serde(default)]
+    last_error: Option<String>,
+}
+
+impl From<UntaggedWebhookJob> for Job {
+    fn from(job: UntaggedWebhookJob) -> Self {
+        Self {
+            app_id: job.app_id,
+            task: JobTask::Webhook(WebhookJob {
+                payload: job.payload,
+                original_signature: job.original_signature,
+            }),
+            ordering_key: None,
+            attempts: job.attempts,
+            last_error: job.last_error,
+        }
+    }
+}
@@ -0,0 +1,396 @@
+// src/queue/kafka_queue_manager.rs
+//! Kafka-backed queue. Jobs are produced to `<topic_prefix>.<queue>` keyed by
+//! their ordering key (the channel, for webhooks) or else their app ID, so
+//! jobs sharing a key stay ordered within a partition, and consumed by a
+//! consumer group that downstream Kafka tooling can also read.
+
+use crate::error::{Error, Result};
+use crate::options::{KafkaQueueConfig, QueueCodec, QueueRetryConfig};
+use crate::queue::codec::{decode_job, encode_job};
+use crate::queue::job::Job;
+use crate::queue::{ArcJobProcessorFn, FailedJob, JobProcessorFnAsync, QueueInterface};
+use async_trait::async_trait;
+use rdkafka::ClientConfig;
+use rdkafka::consumer::{CommitMode, Consumer, StreamConsumer};
+use rdkafka::message::Message;
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::util::Timeout;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+/// How long a worker waits for a message before checking for shutdown
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// How long a fresh dead-letter consumer may take to join its group and
+/// receive its first message
+const JOIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct KafkaQueueManager {
+    producer: FutureProducer,
+    config: KafkaQueueConfig,
+    retry: QueueRetryConfig,
+    codec: QueueCodec,
+    worker_handles: Mutex<Vec<tokio::task::JoinHandle<()>>>,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl KafkaQueueManager {
+    pub fn new(
+        config: KafkaQueueConfig,
+        retry: QueueRetryConfig,
+        codec: QueueCodec,
+    ) -> Result<Self> {
+        let producer: FutureProducer = Self::client_config(&config)
+            .set("message.timeout.ms", config.produce_timeout_ms.to_string())
+            .create()
+            .map_err(|e| Error::Config(format!("Failed to create Kafka producer: {}", e)))?;
+
+        info!(
+            "{}",
+            format!(
+                "Created Kafka queue manager (brokers: {}, topic prefix: {}, group: {})",
+                config.brokers.join(","),
+                config.topic_prefix,
+                config.group_id
+            )
+        );
+
+        Ok(Self {
+            producer,
+            config,
+            retry,
+            codec,
+            worker_handles: Mutex::new(Vec::new()),
+            shutdown: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Connection and security settings shared by producers and consumers
+    fn client_config(config: &KafkaQueueConfig) -> ClientConfig {
+        let mut client_config = ClientConfig::new();
+        client_config.set("bootstrap.servers", config.brokers.join(","));
+        if let Some(protocol) = &config.security_protocol {
+            client_config.set("security.protocol", protocol);
+        }
+        if let Some(mechanism) = &config.sasl_mechanism {
+            client_config.set("sasl.mechanism", mechanism);
+        }
+        if let Some(username) = &config.sasl_username {
+            client_config.set("sasl.username", username);
+        }
+        if let Some(password) = &config.sasl_password {
+            client_config.set("sasl.password", password);
+        }
+        client_config
+    }
+
+    fn consumer(&self, group_id: &str, topic: &str) -> Result<StreamConsumer> {
+        let consumer: StreamConsumer = Self::client_config(&self.config)
+            .set("group.id", group_id)
+            .set("enable.auto.commit", "false")
+            .set("auto.offset.reset", "earliest")
+            .set(
+                "session.timeout.ms",
+                self.config.session_timeout_ms.to_string(),
+            )
+            .create()
+            .map_err(|e| Error::Config(format!("Failed to create Kafka consumer: {}", e)))?;
+        consumer
+            .subscribe(&[topic])
+            .map_err(|e| Error::Queue(format!("Failed to subscribe to {}: {}", topic, e)))?;
+        Ok(consumer)
+    }
+
+    fn topic(&self, queue_name: &str) -> String {
+        format!("{}.{}", self.config.topic_prefix, queue_name)
+    }
+
+    fn dead_letter_topic(&self, queue_name: &str) -> String {
+        self.topic(&self.retry.dead_letter_queue(queue_name))
+    }
+
+    async fn produce(
+        producer: &FutureProducer,
+        codec: QueueCodec,
+        topic: &str,
+        job: &Job,
+    ) -> Result<()> {
+        let payload = encode_job(codec, job)?;
+        producer
+            .send(
+                FutureRecord::to(topic)
+                    .key(job.partition_key())
+                    .payload(&payload),
+                Timeout::Never,
+            )
+            .await
+            .map_err(|(e, _)| Error::Queue(format!("Failed to produce to {}: {}", topic, e)))?;
+        Ok(())
+    }
+
+    /// Produce `job` once `delay` has passed. Kafka has no per-message delay,
+    /// so the wait happens in this process and is lost if it exits first.
+    fn produce_after(
+        producer: &FutureProducer,
+        codec: QueueCodec,
+        topic: &str,
+        job: Job,
+        delay: Duration,
+    ) {
+        let producer = producer.clone();
+        let topic = topic.to_string();
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            if let Err(e) = Self::produce(&producer, codec, &topic, &job).await {
+                error!("{}", format!("Failed to produce delayed job: {}", e));
+            }
+        });
+    }
+
+    /// Retry a failed job after its backoff by producing it again, or move it
+    /// to the dead-letter topic once it is out of attempts
+    async fn handle_failure(
+        producer: &FutureProducer,
+        codec: QueueCodec,
+        retry: &QueueRetryConfig,
+        topic: &str,
+        dead_letter_topic: &str,
+        mut job: Job,
+        error: &Error,
+    ) {
+        match retry.record_failure(&mut job, error) {
+            FailedJob::Retry(delay) => Self::produce_after(producer, codec, topic, job, delay),
+            FailedJob::DeadLetter => {
+                warn!(
+                    "{}",
+                    format!(
+                        "Job for app {} failed {} times, moving it to {}",
+                        job.app_id, job.attempts, dead_letter_topic
+                    )
+                );
+                if let Err(e) = Self::produce(producer, codec, dead_letter_topic, &job).await {
+                    error!("{}", format!("Failed to dead-letter job: {}", e));
+                }
+            }
+        }
+    }
+
+    fn start_worker(
+        &self,
+        queue_name: &str,
+        consumer: StreamConsumer,
+        processor: ArcJobProcessorFn,
+        worker_id: usize,
+    ) -> tokio::task::JoinHandle<()> {
+        let producer = self.producer.clone();
+        let retry = self.retry.clone();
+        let codec = self.codec;
+        let shutdown = self.shutdown.clone();
+        let topic = self.topic(queue_name);
+        let dead_letter_topic = self.dead_letter_topic(queue_name);
+        let queue_name = queue_name.to_string();
+
+        tokio::spawn(async move {
+            info!(
+                "{}",
+                format!(
+                    "Starting Kafka worker #{} for queue: {}",
+                    worker_id, queue_name
+                )
+            );
+
+            while !shutdown.load(Ordering::SeqCst) {
+                let message = match tokio::time::timeout(POLL_INTERVAL, consumer.recv()).await {
+                    Ok(Ok(message)) => message,
+                    Ok(Err(e)) => {
+                        error!(
+                            "{}",
+                            format!("Kafka receive error on queue {}: {}", queue_name, e)
+                        );
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                        continue;
+                    }
+                    Err(_) => continue,
+                };
+
+                match message.payload().map(decode_job) {
+                    Some(Ok(job)) => {
+                        if let Err(e) = processor(job.clone()).await {
+                            error!(
+                                "{}",
+                                format!("Kafka queue {} job failed: {}", queue_name, e)
+                            );
+                            Self::handle_failure(
+                                &producer,
+                                codec,
+                                &retry,
+                                &topic,
+                                &dead_letter_topic,
+                                job,
+                                &e,
+                            )
+                            .await;
+                        }
+                    }
+                    Some(Err(e)) => {
+                        error!(
+                            "{}",
+                            format!(
+                                "Failed to deserialize message from Kafka queue {}: {}",
+                                queue_name, e
+                            )
+                        );
+                    }
+                    None => {}
+                }
+
+                // Failed jobs were re-produced above, so the offset always moves on
+                if let Err(e) = consumer.commit_message(&message, CommitMode::Async) {
+                    warn!(
+                        "{}",
+                        format!("Failed to commit Kafka offset on {}: {}", queue_name, e)
+                    );
+                }
+            }
+
+            info!(
+                "{}",
+                format!(
+                    "Kafka worker #{} for queue {} shutting down",
+                    worker_id, queue_name
+                )
+            );
+        })
+    }
+
+    /// Read up to `limit` jobs from a dead-letter topic, stopping once no
+    /// message arrives within the poll interval
+    async fn read_dead_letters(
+        consumer: &StreamConsumer,
+        limit: usize,
+        mut handle: impl FnMut(Job),
+    ) -> usize {
+        let mut read = 0;
+        let mut wait = JOIN_TIMEOUT;
+        while read < limit {
+            let Ok(Ok(message)) = tokio::time::timeout(wait, consumer.recv()).await else {
+                break;
+            };
+            wait = POLL_INTERVAL;
+            if let Some(Ok(job)) = message.payload().map(decode_job) {
+                handle(job);
+                read += 1;
+            }
+        }
+        read
+    }
+}
+
+#[async_trait]
+impl QueueInterface for KafkaQueueManager {
+    async fn add_to_queue(&self, queue_name: &str, data: Job) -> Result<()> {
+        Self::produce(&self.producer, self.codec, &self.topic(queue_name), &data).await
+    }
+
+    async fn add_to_queue_delayed(
+        &self,
+        queue_name: &str,
+        data: Job,
+        delay: Duration,
+    ) -> Result<()> {
+        Self::produce_after(
+            &self.producer,
+            self.codec,
+            &self.topic(queue_name),
+            data,
+            delay,
+        );
+        Ok(())
+    }
+
+    async fn process_queue(&self, queue_name: &str, callback: JobProcessorFnAsync) -> Result<()> {
+        let processor: ArcJobProcessorFn = Arc::from(callback);
+        let topic = self.topic(queue_name);
+
+        // Consumers in one group split the topic's partitions between them
+        let mut handles = self.worker_handles.lock().await;
+        for worker_id in 0..self.config.concurrency.max(1) as usize {
+            let consumer = self.consumer(&self.config.group_id, &topic)?;
+            handles.push(self.start_worker(queue_name, consumer, processor.clone(), worker_id));
+        }
+
+        info!(
+            "{}",
+            format!(
+                "Started {} Kafka consumers for topic {} in group {}",
+                self.config.concurrency.max(1),
+                topic,
+                self.config.group_id
+            )
+        );
+        Ok(())
+    }
+
+    async fn dead_letter_jobs(&self, queue_name: &str, limit: usize) -> Result<Vec<Job>> {
+        // A throwaway group reads from the start without committing, so
+        // inspecting leaves the dead-letter topic untouched
+        let consumer = self.consumer(
+            &format!("{}-inspect-{}", self.config.group_id, uuid::Uuid::new_v4()),
+            &self.dead_letter_topic(queue_name),
+        )?;
+        let mut jobs = Vec::new();
+        Self::read_dead_letters(&consumer, limit, |job| jobs.push(job)).await;
+        Ok(jobs)
+    }
+
+    async fn requeue_dead_letters(&self, queue_name: &str, limit: usize) -> Result<usize> {
+        // A dedicated group remembers which dead letters were already requeued
+        let consumer = self.consumer(
+            &format!("{}-requeue", self.config.group_id),
+            &self.dead_letter_topic(queue_name),
+        )?;
+        let mut jobs = Vec::new();
+        Self::read_dead_letters(&consumer, limit, |job| jobs.push(job)).await;
+
+        let topic = self.topic(queue_name);
+        for job in &mut jobs {
+            job.attempts = 0;
+            job.last_error = None;
+            Self::produce(&self.producer, self.codec, &topic, job).await?;
+        }
+        consumer
+            .commit_consumer_state(CommitMode::Sync)
+            .map_err(|e| Error::Queue(format!("Failed to commit requeued dead letters: {}", e)))?;
+
+        info!(
+            "{}",
+            format!(
+                "Requeued {} dead-lettered jobs onto Kafka topic {}",
+                jobs.len(),
+                topic
+            )
+        );
+        Ok(jobs.len())
+    }
+
+    async fn stop_consuming(&self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+    }
+
+    /// A job whose offset was never committed is read again by the group
+    fn redelivers_unfinished_jobs(&self) -> bool {
+        true
+    }
+
+    async fn disconnect(&self) -> Result<()> {
+        self.shutdown.store(true, Ordering::SeqCst);
+        for handle in self.worker_handles.lock().await.drain(..) {
+            handle.abort();
+        }
+        self.producer
+            .flush(Timeout::After(Duration::from_secs(5)))
+            .map_err(|e| Error::Queue(format!("Failed to flush Kafka producer: {}", e)))
+    }
+}
@@ -2,14 +2,73 @@
 // Seems fine, just delegates calls.
 
 use crate::error::Result;
+use crate::metrics::MetricsInterface;
+use crate::options::{QueueAutoscalingConfig, QueueConfig};
 
-use crate::queue::QueueInterface;
+use crate::queue::job::Job;
+use crate::queue::kafka_queue_manager::KafkaQueueManager;
 use crate::queue::memory_queue_manager::MemoryQueueManager;
 use crate::queue::redis_cluster_queue_manager::RedisClusterQueueManager; // Add this import
 use crate::queue::redis_queue_manager::RedisQueueManager;
-use crate::webhook::sender::JobProcessorFnAsync;
-use crate::webhook::types::JobData;
-use tracing::info;
+use crate::queue::sqs_queue_manager::SqsQueueManager;
+use crate::queue::{JobProcessorFnAsync, QueueInterface};
+use crate::redis_client::RedisAuth;
+use dashmap::DashMap;
+use serde::Serialize;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock};
+use tracing::{info, warn};
+
+/// How often queue depth gauges are refreshed
+const DEPTH_SAMPLE_INTERVAL: Duration = Duration::from_secs(10);
+/// How often shutdown checks whether in-flight jobs have finished
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+type SharedMetrics = Arc<Mutex<dyn MetricsInterface + Send + Sync>>;
+
+/// State of one queue, as reported by the queue admin API
+#[derive(Debug, Serialize)]
+pub struct QueueStats {
+    pub name: String,
+    /// `None` when the driver can't tell
+    pub pending: Option<usize>,
+    /// Jobs this node is processing
+    pub in_flight: usize,
+}
+
+/// Workers to start per queue. With autoscaling, enough for `max_workers`,
+/// of which the scaler keeps only as many active as the load needs.
+fn worker_count(concurrency: usize, queue_config: &QueueConfig) -> usize {
+    if queue_config.autoscaling.enabled {
+        concurrency.max(queue_config.autoscaling.max_workers)
+    } else {
+        concurrency
+    }
+}
+
+/// Job times of one queue since the autoscaler last looked
+#[derive(Default)]
+struct JobLatency {
+    total_micros: AtomicU64,
+    jobs: AtomicU64,
+}
+
+impl JobLatency {
+    fn record(&self, elapsed: Duration) {
+        self.total_micros
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+        self.jobs.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Average job time since the last call, `None` when no job finished
+    fn take_average(&self) -> Option<Duration> {
+        let jobs = self.jobs.swap(0, Ordering::Relaxed);
+        let total = self.total_micros.swap(0, Ordering::Relaxed);
+        (jobs > 0).then(|| Duration::from_micros(total / jobs))
+    }
+}
 
 /// General Queue Manager interface wrapper
 pub struct QueueManagerFactory;
@@ -19,8 +78,10 @@ impl QueueManagerFactory {
     pub async fn create(
         driver: &str,
         redis_url: Option<&str>,
+        redis_auth: &RedisAuth,
         prefix: Option<&str>,
         concurrency: Option<usize>,
+        queue_config: &QueueConfig,
     ) -> Result<Box<dyn QueueInterface>> {
         // Return Result to propagate errors
         match driver {
@@ -28,6 +89,7 @@ impl QueueManagerFactory {
                 let url = redis_url.unwrap_or("redis://127.0.0.1:6379/");
                 let prefix_str = prefix.unwrap_or("sockudo"); // Consider a more generic default or make it mandatory?
                 let concurrency_val = concurrency.unwrap_or(5); // Default concurrency
+                let concurrency_val = worker_count(concurrency_val, queue_config);
                 info!(
                     "{}",
                     format!(
@@ -36,7 +98,16 @@ impl QueueManagerFactory {
                     )
                 );
                 // Use `?` to propagate potential errors from RedisQueueManager::new
-                let manager = RedisQueueManager::new(url, prefix_str, concurrency_val).await?;
+                let manager = RedisQueueManager::new(
+                    url,
+                    redis_auth,
+                    prefix_str,
+                    concurrency_val,
+                    queue_config.retry.clone(),
+                    queue_config.codec,
+                    Duration::from_secs(queue_config.redis.visibility_timeout_seconds.max(1)),
+                )
+                .await?;
                 // Note: Redis workers are started via process_queue, not here.
                 Ok(Box::new(manager))
             }
@@ -49,6 +120,7 @@ impl QueueManagerFactory {
                     nodes_str.split(',').map(|s| s.trim().to_string()).collect();
                 let prefix_str = prefix.unwrap_or("sockudo");
                 let concurrency_val = concurrency.unwrap_or(5);
+                let concurrency_val = worker_count(concurrency_val, queue_config);
 
                 info!(
                     "{}",
@@ -58,15 +130,48 @@ impl QueueManagerFactory {
                     )
                 );
 
-                let manager =
-                    RedisClusterQueueManager::new(cluster_nodes, prefix_str, concurrency_val)
-                        .await?;
+                let manager = RedisClusterQueueManager::new(
+                    cluster_nodes,
+                    redis_auth,
+                    prefix_str,
+                    concurrency_val,
+                    queue_config.retry.clone(),
+                    queue_config.codec,
+                    Duration::from_secs(
+                        queue_config.redis_cluster.visibility_timeout_seconds.max(1),
+                    ),
+                )
+                .await?;
+                Ok(Box::new(manager))
+            }
+            "sqs" => {
+                info!(
+                    "{}",
+                    format!(
+                        "Creating SQS queue manager (Region: {})",
+                        queue_config.sqs.region
+                    )
+                );
+                let manager = SqsQueueManager::new(
+                    queue_config.sqs.clone(),
+                    queue_config.retry.clone(),
+                    queue_config.codec,
+                )
+                .await?;
+                Ok(Box::new(manager))
+            }
+            "kafka" => {
+                let manager = KafkaQueueManager::new(
+                    queue_config.kafka.clone(),
+                    queue_config.retry.clone(),
+                    queue_config.codec,
+                )?;
                 Ok(Box::new(manager))
             }
             "memory" | _ => {
                 // Default to memory queue manager
                 info!("{}", "Creating Memory queue manager".to_string());
-                let manager = MemoryQueueManager::new();
+                let manager = MemoryQueueManager::new(queue_config.retry.clone());
                 // Start the single processing loop for the memory manager *after* creation.
                 // The user needs to call process_queue afterwards to register processors.
                 manager.start_processing(); // Start its background task here
@@ -77,31 +182,334 @@ impl QueueManagerFactory {
 }
 
 pub struct QueueManager {
-    driver: Box<dyn QueueInterface>,
+    driver: Arc<dyn QueueInterface>,
+    metrics: Arc<RwLock<Option<SharedMetrics>>>,
+    /// Jobs this node is processing, per queue with a registered processor
+    in_flight: Arc<DashMap<String, Arc<AtomicUsize>>>,
+    /// The jobs themselves, so shutdown can requeue those that don't finish
+    running_jobs: Arc<DashMap<u64, (String, Job)>>,
+    next_job_id: Arc<AtomicU64>,
+    /// How long `disconnect` waits for in-flight jobs
+    shutdown_grace_period: Duration,
+    autoscaling: Option<QueueAutoscalingConfig>,
 }
 
 impl QueueManager {
     /// Creates a new QueueManager wrapping a specific driver implementation.
     pub fn new(driver: Box<dyn QueueInterface>) -> Self {
-        Self { driver }
+        Self {
+            driver: Arc::from(driver),
+            metrics: Arc::new(RwLock::new(None)),
+            in_flight: Arc::new(DashMap::new()),
+            running_jobs: Arc::new(DashMap::new()),
+            next_job_id: Arc::new(AtomicU64::new(0)),
+            shutdown_grace_period: Duration::from_secs(10),
+            autoscaling: None,
+        }
+    }
+
+    /// Scale each processed queue's active workers with its load
+    pub fn with_autoscaling(mut self, config: &QueueAutoscalingConfig) -> Self {
+        self.autoscaling = config.enabled.then(|| config.clone());
+        self
+    }
+
+    /// How long `disconnect` waits for in-flight jobs before requeueing them
+    pub fn with_shutdown_grace_period(mut self, grace_period: Duration) -> Self {
+        self.shutdown_grace_period = grace_period;
+        self
+    }
+
+    /// Report queue depth, in-flight jobs, job latency and failures to
+    /// `metrics`. Depth is sampled every few seconds for each queue that has
+    /// a registered processor.
+    pub async fn set_metrics(&self, metrics: SharedMetrics) {
+        *self.metrics.write().await = Some(metrics.clone());
+
+        let driver = self.driver.clone();
+        let in_flight = self.in_flight.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(DEPTH_SAMPLE_INTERVAL);
+            loop {
+                interval.tick().await;
+                let queues: Vec<(String, usize)> = in_flight
+                    .iter()
+                    .map(|entry| (entry.key().clone(), entry.value().load(Ordering::Relaxed)))
+                    .collect();
+                for (queue_name, running) in queues {
+                    let pending = match driver.pending_jobs(&queue_name).await {
+                        Ok(pending) => pending,
+                        Err(e) => {
+                            warn!(
+                                "{}",
+                                format!("Failed to sample depth of queue {}: {}", queue_name, e)
+                            );
+                            None
+                        }
+                    };
+                    let metrics = metrics.lock().await;
+                    if let Some(pending) = pending {
+                        metrics.mark_queue_pending_jobs(&queue_name, pending);
+                    }
+                    metrics.mark_queue_in_flight_jobs(&queue_name, running);
+                }
+            }
+        });
     }
 
     /// Adds data to the specified queue via the underlying driver.
-    pub async fn add_to_queue(&self, queue_name: &str, data: JobData) -> Result<()> {
+    pub async fn add_to_queue(&self, queue_name: &str, data: Job) -> Result<()> {
         self.driver.add_to_queue(queue_name, data).await
     }
 
+    /// Adds data to the specified queue, to be processed once `delay` has passed.
+    #[allow(dead_code)]
+    pub async fn add_to_queue_delayed(
+        &self,
+        queue_name: &str,
+        data: Job,
+        delay: Duration,
+    ) -> Result<()> {
+        self.driver
+            .add_to_queue_delayed(queue_name, data, delay)
+            .await
+    }
+
     /// Registers a processor for the specified queue and starts processing (if applicable for the driver).
     pub async fn process_queue(
         &self,
         queue_name: &str,
         callback: JobProcessorFnAsync,
     ) -> Result<()> {
-        self.driver.process_queue(queue_name, callback).await
+        let in_flight = self
+            .in_flight
+            .entry(queue_name.to_string())
+            .or_default()
+            .clone();
+        let metrics = self.metrics.clone();
+        let running_jobs = self.running_jobs.clone();
+        let next_job_id = self.next_job_id.clone();
+        let latency = Arc::new(JobLatency::default());
+        let job_latency = latency.clone();
+        let queue = queue_name.to_string();
+        let callback = Arc::new(callback);
+
+        // Time every job and count failures without each driver having to
+        let instrumented: JobProcessorFnAsync = Box::new(move |job| {
+            let callback = callback.clone();
+            let in_flight = in_flight.clone();
+            let metrics = metrics.clone();
+            let running_jobs = running_jobs.clone();
+            let job_id = next_job_id.fetch_add(1, Ordering::Relaxed);
+            let queue = queue.clone();
+            let job_latency = job_latency.clone();
+            Box::pin(async move {
+                in_flight.fetch_add(1, Ordering::Relaxed);
+                running_jobs.insert(job_id, (queue.clone(), job.clone()));
+                let started = Instant::now();
+                let result = callback(job).await;
+                running_jobs.remove(&job_id);
+                in_flight.fetch_sub(1, Ordering::Relaxed);
+                job_latency.record(started.elapsed());
+
+                if let Some(metrics) = metrics.read().await.as_ref() {
+                    let metrics = metrics.lock().await;
+                    metrics
+                        .track_queue_job_duration(&queue, started.elapsed().as_secs_f64() * 1000.0);
+                    if result.is_err() {
+                        metrics.mark_queue_job_failed(&queue);
+                    }
+                }
+                result
+            })
+        });
+        self.driver.process_queue(queue_name, instrumented).await?;
+
+        if let Some(config) = &self.autoscaling {
+            self.start_autoscaler(queue_name, config.clone(), latency);
+        }
+        Ok(())
+    }
+
+    /// Start the queue on `min_workers` active workers and re-evaluate every
+    /// `check_interval_ms`. A backlog beyond `backlog_per_worker` per worker,
+    /// or waiting jobs while the average job takes longer than
+    /// `target_latency_ms`, adds half again as many workers. Once the queue
+    /// has been drained for `scale_down_cooldown_ms`, one worker is parked
+    /// per check.
+    fn start_autoscaler(
+        &self,
+        queue_name: &str,
+        config: QueueAutoscalingConfig,
+        latency: Arc<JobLatency>,
+    ) {
+        let min_workers = config.min_workers.max(1);
+        let Some(mut workers) = self.driver.set_active_workers(queue_name, min_workers) else {
+            warn!(
+                "{}",
+                format!(
+                    "Queue autoscaling is not supported by this driver, queue {} keeps its fixed workers",
+                    queue_name
+                )
+            );
+            return;
+        };
+        let max_workers = config.max_workers.max(min_workers);
+        info!(
+            "{}",
+            format!(
+                "Autoscaling workers of queue {} between {} and {}",
+                queue_name, min_workers, max_workers
+            )
+        );
+
+        let driver = self.driver.clone();
+        let queue = queue_name.to_string();
+        let target_latency = Duration::from_millis(config.target_latency_ms);
+        let cooldown = Duration::from_millis(config.scale_down_cooldown_ms);
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(Duration::from_millis(config.check_interval_ms.max(100)));
+            let mut last_busy = Instant::now();
+            loop {
+                interval.tick().await;
+                let pending = match driver.pending_jobs(&queue).await {
+                    Ok(Some(pending)) => pending,
+                    Ok(None) => continue,
+                    Err(e) => {
+                        warn!(
+                            "{}",
+                            format!("Failed to sample depth of queue {}: {}", queue, e)
+                        );
+                        continue;
+                    }
+                };
+                let average = latency.take_average();
+
+                let backlogged = pending > workers * config.backlog_per_worker;
+                let slow = pending > 0 && average.is_some_and(|average| average > target_latency);
+                let wanted = if backlogged || slow {
+                    last_busy = Instant::now();
+                    (workers + workers.div_ceil(2)).min(max_workers)
+                } else if pending > 0 {
+                    last_busy = Instant::now();
+                    workers
+                } else if last_busy.elapsed() >= cooldown {
+                    workers.saturating_sub(1).max(min_workers)
+                } else {
+                    workers
+                };
+                if wanted == workers {
+                    continue;
+                }
+
+                if let Some(applied) = driver.set_active_workers(&queue, wanted) {
+                    info!(
+                        "{}",
+                        format!(
+                            "Scaled queue {} from {} to {} workers ({} pending, average job {:?})",
+                            queue, workers, applied, pending, average
+                        )
+                    );
+                    workers = applied;
+                }
+            }
+        });
     }
 
-    /// Disconnects the underlying driver (if necessary).
+    /// Queues with a registered processor on this node, by name
+    pub async fn queue_stats(&self) -> Result<Vec<QueueStats>> {
+        let queues: Vec<(String, usize)> = self
+            .in_flight
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().load(Ordering::Relaxed)))
+            .collect();
+        let mut stats = Vec::with_capacity(queues.len());
+        for (name, in_flight) in queues {
+            let pending = self.driver.pending_jobs(&name).await?;
+            stats.push(QueueStats {
+                name,
+                pending,
+                in_flight,
+            });
+        }
+        stats.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(stats)
+    }
+
+    /// Jobs waiting in the queue, or `None` when the driver can't list them.
+    pub async fn peek_jobs(&self, queue_name: &str, limit: usize) -> Result<Option<Vec<Job>>> {
+        self.driver.peek_jobs(queue_name, limit).await
+    }
+
+    /// Makes jobs waiting out a retry backoff available right away.
+    pub async fn retry_delayed_jobs(
+        &self,
+        queue_name: &str,
+        limit: usize,
+    ) -> Result<Option<usize>> {
+        self.driver.retry_delayed_jobs(queue_name, limit).await
+    }
+
+    /// Drops every job waiting in the queue.
+    pub async fn purge_jobs(&self, queue_name: &str) -> Result<Option<usize>> {
+        self.driver.purge_jobs(queue_name).await
+    }
+
+    /// Jobs waiting in the queue's dead-letter queue.
+    pub async fn dead_letter_jobs(&self, queue_name: &str, limit: usize) -> Result<Vec<Job>> {
+        self.driver.dead_letter_jobs(queue_name, limit).await
+    }
+
+    /// Moves dead-lettered jobs back onto the queue for another round of attempts.
+    pub async fn requeue_dead_letters(&self, queue_name: &str, limit: usize) -> Result<usize> {
+        self.driver.requeue_dead_letters(queue_name, limit).await
+    }
+
+    /// Stops fetching jobs, waits up to the shutdown grace period for
+    /// in-flight jobs to finish, requeues any that didn't, then disconnects
+    /// the underlying driver.
     pub async fn disconnect(&self) -> Result<()> {
+        self.driver.stop_consuming().await;
+
+        let deadline = Instant::now() + self.shutdown_grace_period;
+        while !self.running_jobs.is_empty() && Instant::now() < deadline {
+            tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+        }
+
+        let unfinished: Vec<(String, Job)> = self
+            .running_jobs
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+        if !unfinished.is_empty() {
+            if self.driver.redelivers_unfinished_jobs() {
+                warn!(
+                    "{}",
+                    format!(
+                        "{} queue jobs still running at shutdown, leaving them to be redelivered",
+                        unfinished.len()
+                    )
+                );
+            } else {
+                warn!(
+                    "{}",
+                    format!(
+                        "Requeueing {} queue jobs still running at shutdown",
+                        unfinished.len()
+                    )
+                );
+                for (queue_name, job) in unfinished {
+                    if let Err(e) = self.driver.add_to_queue(&queue_name, job).await {
+                        warn!(
+                            "{}",
+                            format!("Failed to requeue job on queue {}: {}", queue_name, e)
+                        );
+                    }
+                }
+            }
+        }
+
         self.driver.disconnect().await
     }
 }
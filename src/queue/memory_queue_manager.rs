@@ -1,30 +1,53 @@
 // --- MemoryQueueManager ---
 // No major logical changes, but added comments and ensured consistency.
 
-use crate::queue::{ArcJobProcessorFn, QueueInterface};
-use crate::webhook::sender::JobProcessorFnAsync;
-use crate::webhook::types::JobData;
+use crate::options::QueueRetryConfig;
+use crate::queue::job::Job;
+use crate::queue::{
+    ArcJobProcessorFn, DELAYED_POLL_INTERVAL, FailedJob, JobProcessorFnAsync, QueueInterface,
+};
 use async_trait::async_trait;
 use dashmap::DashMap;
 use std::sync::Arc;
-use std::time::Duration;
-use tracing::info;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+type JobQueues = Arc<DashMap<String, Vec<Job>, ahash::RandomState>>;
+type DelayedJobs = Arc<DashMap<String, Vec<(Instant, Job)>, ahash::RandomState>>;
 
 /// Memory-based queue manager for simple deployments
 pub struct MemoryQueueManager {
     // Use channels to simulate a queue in memory
-    // DashMap<String, Vec<JobData>> is implicitly Send + Sync if JobData is Send
-    queues: DashMap<String, Vec<JobData>, ahash::RandomState>,
+    // DashMap<String, Vec<Job>> is implicitly Send + Sync if Job is Send
+    // Shared with the processing loop, so the maps are behind Arcs
+    queues: JobQueues,
     // Store Arc'd callbacks to be consistent with Redis manager and avoid potential issues if Box wasn't 'static
-    processors: DashMap<String, ArcJobProcessorFn, ahash::RandomState>,
+    processors: Arc<DashMap<String, ArcJobProcessorFn, ahash::RandomState>>,
+    // Jobs that ran out of attempts, keyed by dead-letter queue name
+    dead_letters: JobQueues,
+    // Jobs waiting for their delay, with the instant they become due
+    delayed: DelayedJobs,
+    retry: QueueRetryConfig,
+    // Set once the processing loop should stop taking jobs
+    stopped: Arc<AtomicBool>,
 }
 
 impl MemoryQueueManager {
-    pub fn new() -> Self {
-        let queues = DashMap::with_hasher(ahash::RandomState::new());
-        let processors = DashMap::with_hasher(ahash::RandomState::new());
+    pub fn new(retry: QueueRetryConfig) -> Self {
+        let queues = Arc::new(DashMap::with_hasher(ahash::RandomState::new()));
+        let processors = Arc::new(DashMap::with_hasher(ahash::RandomState::new()));
+        let dead_letters = Arc::new(DashMap::with_hasher(ahash::RandomState::new()));
+        let delayed = Arc::new(DashMap::with_hasher(ahash::RandomState::new()));
 
-        Self { queues, processors }
+        Self {
+            queues,
+            processors,
+            dead_letters,
+            delayed,
+            retry,
+            stopped: Arc::new(AtomicBool::new(false)),
+        }
     }
 
     /// Starts the background processing loop. Should be called once after setup.
@@ -32,44 +55,84 @@ impl MemoryQueueManager {
         // Clone Arcs for the background task
         let queues = self.queues.clone();
         let processors = self.processors.clone();
+        let dead_letters = self.dead_letters.clone();
+        let delayed = self.delayed.clone();
+        let retry = self.retry.clone();
+        let stopped = self.stopped.clone();
 
         info!("{}", "Starting memory queue processing loop...".to_string());
 
         tokio::spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_millis(500));
+            let mut interval = tokio::time::interval(DELAYED_POLL_INTERVAL);
 
-            loop {
+            while !stopped.load(Ordering::SeqCst) {
                 interval.tick().await;
+                Self::promote_due_jobs(&queues, &delayed);
 
-                // Iterate through queues. DashMap allows concurrent access.
-                for queue_entry in queues.iter() {
-                    // Use iter() for read access
-                    let queue_name = queue_entry.key().clone();
+                // Collect names first; holding an iterator while taking a
+                // mutable entry on the same map can deadlock
+                let queue_names: Vec<String> =
+                    queues.iter().map(|entry| entry.key().clone()).collect();
 
+                for queue_name in queue_names {
                     // Get the processor for this queue
-                    if let Some(processor) = processors.get(&queue_name) {
-                        // Get a mutable reference to the queue's Vec
-                        if let Some(mut jobs_vec) = queues.get_mut(&queue_name) {
-                            // Take all jobs from the queue for this tick
-                            // Note: If a job processor is slow, it blocks others in the same queue during this tick.
-                            // Consider spawning tasks per job for better isolation if needed.
-                            let jobs_to_process: Vec<JobData> = jobs_vec.drain(..).collect();
-
-                            if !jobs_to_process.is_empty() {
-                                info!(
+                    let Some(processor) = processors.get(&queue_name).map(|p| p.clone()) else {
+                        continue;
+                    };
+                    // Take all jobs from the queue for this tick
+                    // Note: If a job processor is slow, it blocks others in the same queue during this tick.
+                    // Consider spawning tasks per job for better isolation if needed.
+                    let jobs_to_process: Vec<Job> = match queues.get_mut(&queue_name) {
+                        Some(mut jobs_vec) => jobs_vec.drain(..).collect(),
+                        None => continue,
+                    };
+
+                    if jobs_to_process.is_empty() {
+                        continue;
+                    }
+                    info!(
+                        "{}",
+                        format!(
+                            "Processing {} jobs from memory queue {}",
+                            jobs_to_process.len(),
+                            queue_name
+                        )
+                    );
+                    // Process each job sequentially within this tick
+                    let mut jobs_to_process = jobs_to_process.into_iter();
+                    while let Some(job) = jobs_to_process.next() {
+                        // Once stopped, untouched jobs go back on the queue
+                        if stopped.load(Ordering::SeqCst) {
+                            let mut queue = queues.entry(queue_name.clone()).or_default();
+                            queue.push(job);
+                            queue.extend(jobs_to_process);
+                            break;
+                        }
+                        let Err(e) = processor(job.clone()).await else {
+                            continue;
+                        };
+                        error!(
+                            "{}",
+                            format!("Memory queue {} job failed: {}", queue_name, e)
+                        );
+                        let mut job = job;
+                        match retry.record_failure(&mut job, &e) {
+                            FailedJob::Retry(delay) => {
+                                delayed
+                                    .entry(queue_name.clone())
+                                    .or_default()
+                                    .push((Instant::now() + delay, job));
+                            }
+                            FailedJob::DeadLetter => {
+                                let dead_letter_queue = retry.dead_letter_queue(&queue_name);
+                                warn!(
                                     "{}",
                                     format!(
-                                        "Processing {} jobs from memory queue {}",
-                                        jobs_to_process.len(),
-                                        queue_name
+                                        "Job for app {} failed {} times, moving it to {}",
+                                        job.app_id, job.attempts, dead_letter_queue
                                     )
                                 );
-                                // Process each job sequentially within this tick
-                                for job in jobs_to_process {
-                                    // Clone the Arc'd processor for the call
-                                    let processor_clone = processor.clone();
-                                    processor_clone(job).await.unwrap();
-                                }
+                                dead_letters.entry(dead_letter_queue).or_default().push(job);
                             }
                         }
                     }
@@ -77,11 +140,31 @@ impl MemoryQueueManager {
             }
         });
     }
+
+    /// Move delayed jobs whose time has come onto their queues
+    fn promote_due_jobs(queues: &JobQueues, delayed: &DelayedJobs) {
+        let now = Instant::now();
+        let queue_names: Vec<String> = delayed.iter().map(|entry| entry.key().clone()).collect();
+        for queue_name in queue_names {
+            let due: Vec<Job> = match delayed.get_mut(&queue_name) {
+                Some(mut pending) => {
+                    let (due, waiting): (Vec<_>, Vec<_>) =
+                        pending.drain(..).partition(|(at, _)| *at <= now);
+                    *pending = waiting;
+                    due.into_iter().map(|(_, job)| job).collect()
+                }
+                None => continue,
+            };
+            if !due.is_empty() {
+                queues.entry(queue_name).or_default().extend(due);
+            }
+        }
+    }
 }
 
 #[async_trait]
 impl QueueInterface for MemoryQueueManager {
-    async fn add_to_queue(&self, queue_name: &str, data: JobData) -> crate::error::Result<()> {
+    async fn add_to_queue(&self, queue_name: &str, data: Job) -> crate::error::Result<()> {
         // Ensure queue Vec exists using entry API for atomicity
         self.queues
             .entry(queue_name.to_string())
@@ -90,6 +173,19 @@ impl QueueInterface for MemoryQueueManager {
         Ok(())
     }
 
+    async fn add_to_queue_delayed(
+        &self,
+        queue_name: &str,
+        data: Job,
+        delay: Duration,
+    ) -> crate::error::Result<()> {
+        self.delayed
+            .entry(queue_name.to_string())
+            .or_default()
+            .push((Instant::now() + delay, data));
+        Ok(())
+    }
+
     async fn process_queue(
         &self,
         queue_name: &str,
@@ -109,8 +205,99 @@ impl QueueInterface for MemoryQueueManager {
         Ok(())
     }
 
+    async fn dead_letter_jobs(
+        &self,
+        queue_name: &str,
+        limit: usize,
+    ) -> crate::error::Result<Vec<Job>> {
+        Ok(self
+            .dead_letters
+            .get(&self.retry.dead_letter_queue(queue_name))
+            .map(|jobs| jobs.iter().take(limit).cloned().collect())
+            .unwrap_or_default())
+    }
+
+    async fn requeue_dead_letters(
+        &self,
+        queue_name: &str,
+        limit: usize,
+    ) -> crate::error::Result<usize> {
+        let jobs: Vec<Job> = match self
+            .dead_letters
+            .get_mut(&self.retry.dead_letter_queue(queue_name))
+        {
+            Some(mut dead) => {
+                let count = limit.min(dead.len());
+                dead.drain(..count).collect()
+            }
+            None => Vec::new(),
+        };
+        let requeued = jobs.len();
+        let mut queue = self.queues.entry(queue_name.to_string()).or_default();
+        for mut job in jobs {
+            job.attempts = 0;
+            job.last_error = None;
+            queue.push(job);
+        }
+        Ok(requeued)
+    }
+
+    async fn pending_jobs(&self, queue_name: &str) -> crate::error::Result<Option<usize>> {
+        Ok(Some(
+            self.queues.get(queue_name).map_or(0, |jobs| jobs.len()),
+        ))
+    }
+
+    async fn peek_jobs(
+        &self,
+        queue_name: &str,
+        limit: usize,
+    ) -> crate::error::Result<Option<Vec<Job>>> {
+        Ok(Some(
+            self.queues
+                .get(queue_name)
+                .map(|jobs| jobs.iter().take(limit).cloned().collect())
+                .unwrap_or_default(),
+        ))
+    }
+
+    async fn retry_delayed_jobs(
+        &self,
+        queue_name: &str,
+        limit: usize,
+    ) -> crate::error::Result<Option<usize>> {
+        let jobs: Vec<Job> = match self.delayed.get_mut(queue_name) {
+            Some(mut delayed) => {
+                delayed.sort_by_key(|(due, _)| *due);
+                let count = limit.min(delayed.len());
+                delayed.drain(..count).map(|(_, job)| job).collect()
+            }
+            None => Vec::new(),
+        };
+        let retried = jobs.len();
+        self.queues
+            .entry(queue_name.to_string())
+            .or_default()
+            .extend(jobs);
+        Ok(Some(retried))
+    }
+
+    async fn purge_jobs(&self, queue_name: &str) -> crate::error::Result<Option<usize>> {
+        Ok(Some(
+            self.queues
+                .get_mut(queue_name)
+                .map_or(0, |mut jobs| jobs.drain(..).count()),
+        ))
+    }
+
+    async fn stop_consuming(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+
     async fn disconnect(&self) -> crate::error::Result<()> {
+        self.stopped.store(true, Ordering::SeqCst);
         self.queues.clear();
+        self.delayed.clear();
         Ok(())
     }
 }
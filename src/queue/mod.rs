@@ -1,34 +1,48 @@
-use crate::error::Result;
-use crate::webhook::sender::JobProcessorFnAsync;
-use crate::webhook::types::JobData;
+use crate::error::{Error, Result};
+use crate::options::{QueueCodec, QueueRetryConfig};
+use crate::queue::codec::{decode_job, encode_job};
+use crate::queue::job::Job;
+use crate::redis_client::RedisDegradedMode;
 use async_trait::async_trait;
-use serde::Serialize;
-use serde::de::DeserializeOwned;
+use redis::{AsyncCommands, Direction, RedisResult};
+use std::collections::VecDeque;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
 
+pub mod codec;
+pub mod job;
+pub mod kafka_queue_manager;
 pub mod manager;
 pub mod memory_queue_manager;
 pub mod redis_cluster_queue_manager; // Add this line
 pub mod redis_queue_manager;
 pub mod sqs_queue_manager;
 
-impl JobData where JobData: Serialize + DeserializeOwned {}
-
 // Define a type alias for the callback for clarity and easier management
 #[allow(dead_code)]
-type JobProcessorFn = Box<dyn Fn(JobData) -> Result<()> + Send + Sync + 'static>;
+type JobProcessorFn = Box<dyn Fn(Job) -> Result<()> + Send + Sync + 'static>;
+pub type JobProcessorFnAsync =
+    Box<dyn Fn(Job) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> + Send + Sync + 'static>;
 // Define a type alias for the Arc'd callback used in Redis manager
 type ArcJobProcessorFn = Arc<
-    Box<
-        dyn Fn(JobData) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> + Send + Sync + 'static,
-    >,
+    Box<dyn Fn(Job) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> + Send + Sync + 'static>,
 >;
 
 #[async_trait]
 pub trait QueueInterface: Send + Sync {
-    async fn add_to_queue(&self, queue_name: &str, data: JobData) -> crate::error::Result<()>;
+    async fn add_to_queue(&self, queue_name: &str, data: Job) -> crate::error::Result<()>;
+    /// Add a job that becomes available to `queue_name`'s workers once
+    /// `delay` has passed
+    async fn add_to_queue_delayed(
+        &self,
+        queue_name: &str,
+        data: Job,
+        delay: Duration,
+    ) -> crate::error::Result<()>;
     // Changed callback type to accept 'static lifetime needed by Redis workers
     async fn process_queue(
         &self,
@@ -36,4 +50,544 @@ pub trait QueueInterface: Send + Sync {
         callback: JobProcessorFnAsync,
     ) -> crate::error::Result<()>;
     async fn disconnect(&self) -> crate::error::Result<()>;
+    /// Stop fetching new jobs. Jobs already handed to a processor keep running.
+    async fn stop_consuming(&self) {}
+    /// Whether the backend itself delivers a job again when its processor
+    /// never finished (an undeleted SQS message, an uncommitted Kafka offset),
+    /// so shutdown must not requeue it
+    fn redelivers_unfinished_jobs(&self) -> bool {
+        false
+    }
+    /// Let only the first `workers` of `queue_name`'s in-process workers take
+    /// jobs, the others park after their current job. Returns the number
+    /// applied, capped at the workers started, or `None` when the driver
+    /// doesn't run workers it can scale.
+    fn set_active_workers(&self, queue_name: &str, workers: usize) -> Option<usize> {
+        let _ = (queue_name, workers);
+        None
+    }
+    /// Jobs waiting in `queue_name`, or `None` when the driver can't tell
+    async fn pending_jobs(&self, queue_name: &str) -> Result<Option<usize>> {
+        let _ = queue_name;
+        Ok(None)
+    }
+    /// Up to `limit` jobs waiting in `queue_name`, next in line first, or
+    /// `None` when the driver can't list jobs without consuming them
+    async fn peek_jobs(&self, queue_name: &str, limit: usize) -> Result<Option<Vec<Job>>> {
+        let _ = (queue_name, limit);
+        Ok(None)
+    }
+    /// Make up to `limit` jobs waiting out a retry backoff in `queue_name`
+    /// available right away. Returns how many were moved, or `None` when the
+    /// driver doesn't hold delayed jobs where they can be reached.
+    async fn retry_delayed_jobs(&self, queue_name: &str, limit: usize) -> Result<Option<usize>> {
+        let _ = (queue_name, limit);
+        Ok(None)
+    }
+    /// Drop every job waiting in `queue_name`. Returns how many were dropped,
+    /// or `None` when the driver can't purge.
+    async fn purge_jobs(&self, queue_name: &str) -> Result<Option<usize>> {
+        let _ = queue_name;
+        Ok(None)
+    }
+    /// Up to `limit` jobs from `queue_name`'s dead-letter queue, oldest first
+    async fn dead_letter_jobs(&self, queue_name: &str, limit: usize) -> Result<Vec<Job>>;
+    /// Move up to `limit` dead-lettered jobs back onto `queue_name` with their
+    /// attempt count reset. Returns how many were requeued.
+    async fn requeue_dead_letters(&self, queue_name: &str, limit: usize) -> Result<usize>;
+}
+
+/// What to do with a job whose processing failed
+pub(crate) enum FailedJob {
+    /// Queue it again after the delay
+    Retry(Duration),
+    /// Out of attempts, move it to the dead-letter queue
+    DeadLetter,
+}
+
+impl QueueRetryConfig {
+    /// Delay before retrying a job that has failed `attempts` times:
+    /// `backoff_base_ms * 2^(attempts - 1)`, capped at `backoff_max_ms`
+    pub fn backoff(&self, attempts: u32) -> Duration {
+        let exponent = attempts.saturating_sub(1).min(32);
+        let delay = self.backoff_base_ms.saturating_mul(1u64 << exponent);
+        Duration::from_millis(delay.min(self.backoff_max_ms))
+    }
+
+    /// Name of the dead-letter queue for `queue_name`
+    pub fn dead_letter_queue(&self, queue_name: &str) -> String {
+        self.dead_letter_queue
+            .clone()
+            .unwrap_or_else(|| format!("{}-dead-letter", queue_name))
+    }
+
+    /// Record a failed attempt on `job` and decide whether to retry it
+    pub(crate) fn record_failure(&self, job: &mut Job, error: &Error) -> FailedJob {
+        job.attempts += 1;
+        job.last_error = Some(error.to_string());
+        if job.attempts >= self.max_attempts.max(1) {
+            FailedJob::DeadLetter
+        } else {
+            FailedJob::Retry(self.backoff(job.attempts))
+        }
+    }
+}
+
+/// How often Redis and memory queues move due delayed jobs onto their queue
+pub(crate) const DELAYED_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// How often a parked Redis queue worker checks whether it may take jobs again
+pub(crate) const PARKED_WORKER_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Most delayed jobs moved onto a Redis queue per poll
+const DELAYED_PROMOTE_BATCH: isize = 100;
+
+/// Add `payload` to a Redis sorted set of delayed jobs, scored by the Unix
+/// time in milliseconds at which it becomes due. Members carry a UUID prefix
+/// so identical payloads scheduled twice are both kept.
+pub(crate) async fn schedule_redis_job<C>(
+    conn: &mut C,
+    delayed_key: &str,
+    payload: &[u8],
+    delay: Duration,
+) -> RedisResult<()>
+where
+    C: redis::aio::ConnectionLike + Send,
+{
+    let due_ms = chrono::Utc::now().timestamp_millis() + delay.as_millis() as i64;
+    let mut member = format!("{}:", uuid::Uuid::new_v4()).into_bytes();
+    member.extend_from_slice(payload);
+    conn.zadd(delayed_key, member, due_ms).await
+}
+
+/// Move delayed jobs that are due from `delayed_key` onto the `queue_key`
+/// list. A job is only pushed by the caller whose ZREM removed it, so several
+/// nodes can poll the same set without duplicating jobs.
+pub(crate) async fn promote_due_redis_jobs<C>(
+    conn: &mut C,
+    delayed_key: &str,
+    queue_key: &str,
+) -> RedisResult<usize>
+where
+    C: redis::aio::ConnectionLike + Send,
+{
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let due: Vec<Vec<u8>> = conn
+        .zrangebyscore_limit(delayed_key, "-inf", now_ms, 0, DELAYED_PROMOTE_BATCH)
+        .await?;
+    move_delayed_redis_jobs(conn, delayed_key, queue_key, due).await
+}
+
+/// Move up to `limit` delayed jobs onto the `queue_key` list right away,
+/// soonest due first
+pub(crate) async fn promote_redis_jobs_now<C>(
+    conn: &mut C,
+    delayed_key: &str,
+    queue_key: &str,
+    limit: usize,
+) -> RedisResult<usize>
+where
+    C: redis::aio::ConnectionLike + Send,
+{
+    if limit == 0 {
+        return Ok(0);
+    }
+    let members: Vec<Vec<u8>> = conn.zrange(delayed_key, 0, limit as isize - 1).await?;
+    move_delayed_redis_jobs(conn, delayed_key, queue_key, members).await
+}
+
+async fn move_delayed_redis_jobs<C>(
+    conn: &mut C,
+    delayed_key: &str,
+    queue_key: &str,
+    members: Vec<Vec<u8>>,
+) -> RedisResult<usize>
+where
+    C: redis::aio::ConnectionLike + Send,
+{
+    let mut promoted = 0;
+    for member in members {
+        let removed: usize = conn.zrem(delayed_key, &member).await?;
+        if removed == 0 {
+            continue;
+        }
+        let payload = match member.iter().position(|&byte| byte == b':') {
+            Some(separator) => &member[separator + 1..],
+            None => &member[..],
+        };
+        conn.rpush::<_, _, ()>(queue_key, payload).await?;
+        promoted += 1;
+    }
+    Ok(promoted)
+}
+
+/// Poll `delayed_key` for due jobs until the process exits
+pub(crate) fn spawn_redis_delayed_promoter<C>(
+    conn: Arc<Mutex<C>>,
+    delayed_key: String,
+    queue_key: String,
+) where
+    C: redis::aio::ConnectionLike + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(DELAYED_POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            let mut conn = conn.lock().await;
+            if let Err(e) = promote_due_redis_jobs(&mut *conn, &delayed_key, &queue_key).await {
+                error!(
+                    "{}",
+                    format!("Failed to promote delayed jobs from {}: {}", delayed_key, e)
+                );
+            }
+        }
+    });
+}
+
+/// A Redis worker's claim on its processing list. Workers move each job they
+/// take from the queue onto their own processing list and delete it once
+/// handled. The lease, the worker's score in the queue's lease set, is the
+/// time until which the list belongs to the worker; once it passes, a reaper
+/// on any node returns the list's jobs to the queue, so jobs of a crashed
+/// worker are delivered again instead of lost.
+///
+/// Processing lists and the lease set hash-tag the queue key, so they share
+/// its Redis Cluster slot and jobs can move between them atomically.
+pub(crate) struct RedisWorkerLease {
+    pub processing_key: String,
+    leases_key: String,
+    visibility_timeout: Duration,
+    renewed_at: Option<Instant>,
+}
+
+impl RedisWorkerLease {
+    pub fn new(queue_key: &str, worker_id: &str, visibility_timeout: Duration) -> Self {
+        Self {
+            processing_key: format!("{{{}}}:processing:{}", queue_key, worker_id),
+            leases_key: redis_leases_key(queue_key),
+            visibility_timeout,
+            renewed_at: None,
+        }
+    }
+
+    /// Extend the lease by the visibility timeout. Renewals happen at most
+    /// once per third of the timeout, so an idle worker polling the queue
+    /// doesn't write on every poll and a job taken right after always has
+    /// at least two thirds of the timeout to finish.
+    pub async fn renew<C>(&mut self, conn: &mut C) -> RedisResult<()>
+    where
+        C: redis::aio::ConnectionLike + Send,
+    {
+        if self
+            .renewed_at
+            .is_some_and(|renewed_at| renewed_at.elapsed() < self.visibility_timeout / 3)
+        {
+            return Ok(());
+        }
+        let deadline_ms =
+            chrono::Utc::now().timestamp_millis() + self.visibility_timeout.as_millis() as i64;
+        conn.zadd::<_, _, _, ()>(&self.leases_key, &self.processing_key, deadline_ms)
+            .await?;
+        self.renewed_at = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Move the next job from `queue_key` onto the processing list
+    pub async fn take<C>(&self, conn: &mut C, queue_key: &str) -> RedisResult<Option<Vec<u8>>>
+    where
+        C: redis::aio::ConnectionLike + Send,
+    {
+        conn.blmove(
+            queue_key,
+            &self.processing_key,
+            Direction::Left,
+            Direction::Right,
+            0.01,
+        )
+        .await
+    }
+
+    /// Drop the handled job from the processing list
+    pub async fn finish<C>(&self, conn: &mut C) -> RedisResult<()>
+    where
+        C: redis::aio::ConnectionLike + Send,
+    {
+        conn.del(&self.processing_key).await
+    }
+
+    /// Give up the lease of an empty processing list on shutdown
+    pub async fn release<C>(&self, conn: &mut C) -> RedisResult<()>
+    where
+        C: redis::aio::ConnectionLike + Send,
+    {
+        conn.zrem(&self.leases_key, &self.processing_key).await
+    }
+}
+
+fn redis_leases_key(queue_key: &str) -> String {
+    format!("{{{}}}:leases", queue_key)
+}
+
+/// Drops an expired lease, unless its worker renewed it meanwhile
+const RELEASE_EXPIRED_LEASE: &str = r#"
+local deadline = redis.call('ZSCORE', KEYS[1], ARGV[1])
+if deadline and tonumber(deadline) <= tonumber(ARGV[2]) then
+    return redis.call('ZREM', KEYS[1], ARGV[1])
+end
+return 0
+"#;
+
+/// Return the jobs of processing lists whose lease expired to the head of
+/// `queue_key`, where they were taken from. Jobs move one at a time with
+/// LMOVE, so reapers on several nodes never deliver a job twice.
+pub(crate) async fn reap_stalled_redis_jobs<C>(conn: &mut C, queue_key: &str) -> RedisResult<usize>
+where
+    C: redis::aio::ConnectionLike + Send,
+{
+    let leases_key = redis_leases_key(queue_key);
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let expired: Vec<String> = conn.zrangebyscore(&leases_key, "-inf", now_ms).await?;
+    let mut reaped = 0;
+    for processing_key in expired {
+        while conn
+            .lmove::<_, _, Option<Vec<u8>>>(
+                &processing_key,
+                queue_key,
+                Direction::Right,
+                Direction::Left,
+            )
+            .await?
+            .is_some()
+        {
+            reaped += 1;
+        }
+        redis::Script::new(RELEASE_EXPIRED_LEASE)
+            .key(&leases_key)
+            .arg(&processing_key)
+            .arg(now_ms)
+            .invoke_async::<()>(conn)
+            .await?;
+    }
+    Ok(reaped)
+}
+
+/// Reap stalled jobs of `queue_key` until the process exits
+pub(crate) fn spawn_redis_reaper<C>(
+    conn: Arc<Mutex<C>>,
+    queue_key: String,
+    visibility_timeout: Duration,
+) where
+    C: redis::aio::ConnectionLike + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval((visibility_timeout / 2).max(Duration::from_secs(1)));
+        loop {
+            interval.tick().await;
+            let mut conn = conn.lock().await;
+            match reap_stalled_redis_jobs(&mut *conn, &queue_key).await {
+                Ok(0) => {}
+                Ok(reaped) => info!(
+                    "{}",
+                    format!(
+                        "Returned {} stalled jobs to {} after their worker's lease expired",
+                        reaped, queue_key
+                    )
+                ),
+                Err(e) => error!(
+                    "{}",
+                    format!("Failed to reap stalled jobs of {}: {}", queue_key, e)
+                ),
+            }
+        }
+    });
+}
+
+/// Schedule a failed job from a Redis-backed queue for retry after its
+/// backoff, or push it onto the dead-letter list once it is out of attempts
+pub(crate) async fn retry_redis_job<C>(
+    conn: &mut C,
+    retry: &QueueRetryConfig,
+    codec: QueueCodec,
+    delayed_key: &str,
+    dead_letter_key: &str,
+    mut job: Job,
+    error: &Error,
+) where
+    C: redis::aio::ConnectionLike + Send,
+{
+    let failed = retry.record_failure(&mut job, error);
+    let payload = match encode_job(codec, &job) {
+        Ok(payload) => payload,
+        Err(e) => {
+            error!("{}", format!("Failed to serialize failed job: {}", e));
+            return;
+        }
+    };
+
+    let result = match failed {
+        FailedJob::Retry(delay) => schedule_redis_job(conn, delayed_key, &payload, delay).await,
+        FailedJob::DeadLetter => {
+            warn!(
+                "{}",
+                format!(
+                    "Job for app {} failed {} times, moving it to {}",
+                    job.app_id, job.attempts, dead_letter_key
+                )
+            );
+            conn.rpush(dead_letter_key, &payload).await
+        }
+    };
+    if let Err(e) = result {
+        error!("{}", format!("Failed to requeue failed job: {}", e));
+    }
+}
+
+/// Jobs stored in a Redis list (a queue or a dead-letter queue), oldest
+/// first. Entries that can't be decoded are skipped.
+pub(crate) async fn redis_list_jobs<C>(
+    conn: &mut C,
+    list_key: &str,
+    limit: usize,
+) -> Result<Vec<Job>>
+where
+    C: redis::aio::ConnectionLike + Send,
+{
+    if limit == 0 {
+        return Ok(Vec::new());
+    }
+    let entries: Vec<Vec<u8>> = conn
+        .lrange(list_key, 0, limit as isize - 1)
+        .await
+        .map_err(|e| Error::Queue(format!("Failed to read {}: {}", list_key, e)))?;
+    Ok(entries
+        .iter()
+        .filter_map(|entry| decode_job(entry).ok())
+        .collect())
+}
+
+/// Delete a Redis queue list, returning how many jobs it held
+pub(crate) async fn purge_redis_queue<C>(conn: &mut C, queue_key: &str) -> RedisResult<usize>
+where
+    C: redis::aio::ConnectionLike + Send,
+{
+    let (purged,): (usize,) = redis::pipe()
+        .atomic()
+        .llen(queue_key)
+        .del(queue_key)
+        .ignore()
+        .query_async(conn)
+        .await?;
+    Ok(purged)
+}
+
+/// Move up to `limit` jobs from a Redis dead-letter list back onto the queue
+pub(crate) async fn requeue_redis_dead_letters<C>(
+    conn: &mut C,
+    dead_letter_key: &str,
+    queue_key: &str,
+    limit: usize,
+    codec: QueueCodec,
+) -> Result<usize>
+where
+    C: redis::aio::ConnectionLike + Send,
+{
+    let mut requeued = 0;
+    while requeued < limit {
+        let entry: Option<Vec<u8>> = conn
+            .lpop(dead_letter_key, None)
+            .await
+            .map_err(|e| Error::Queue(format!("Failed to pop {}: {}", dead_letter_key, e)))?;
+        let Some(entry) = entry else {
+            break;
+        };
+        let payload = match decode_job(&entry) {
+            Ok(mut job) => {
+                job.attempts = 0;
+                job.last_error = None;
+                encode_job(codec, &job)?
+            }
+            // Unreadable entries go back unchanged; the worker logs and drops them
+            Err(_) => entry,
+        };
+        conn.rpush::<_, _, ()>(queue_key, &payload)
+            .await
+            .map_err(|e| Error::Queue(format!("Failed to requeue onto {}: {}", queue_key, e)))?;
+        requeued += 1;
+    }
+    Ok(requeued)
+}
+
+/// Upper bound on jobs held in memory while Redis refuses writes
+const MAX_BUFFERED_JOBS: usize = 10_000;
+
+/// Jobs held in memory while Redis refuses writes (OOM/READONLY), pushed to
+/// their queues in order once Redis accepts writes again. The buffer lives in
+/// process memory, so it only bridges short outages.
+pub(crate) struct RedisJobBuffer {
+    jobs: std::sync::Mutex<VecDeque<(String, Vec<u8>)>>,
+    degraded: RedisDegradedMode,
+}
+
+impl RedisJobBuffer {
+    pub fn new(component: &'static str) -> Self {
+        Self {
+            jobs: std::sync::Mutex::new(VecDeque::new()),
+            degraded: RedisDegradedMode::new(component, "in-memory job buffering"),
+        }
+    }
+
+    /// RPUSH `payload` onto `queue_key`, buffering it instead when Redis
+    /// refuses writes. Callers must serialize pushes (the managers hold their
+    /// connection lock) so buffered jobs keep their order.
+    pub async fn push<C>(&self, conn: &mut C, queue_key: &str, payload: Vec<u8>) -> RedisResult<()>
+    where
+        C: redis::aio::ConnectionLike + Send,
+    {
+        if self.degraded.is_active() {
+            self.buffer(queue_key, payload);
+            return Ok(());
+        }
+
+        // Flush earlier jobs first so they keep their place in the queue
+        loop {
+            let next = self.jobs.lock().unwrap().pop_front();
+            let Some((key, job)) = next else {
+                break;
+            };
+            if let Err(e) = conn.rpush::<_, _, ()>(&key, &job).await {
+                self.jobs.lock().unwrap().push_front((key, job));
+                if self.degraded.record_error(&e) {
+                    self.buffer(queue_key, payload);
+                    return Ok(());
+                }
+                return Err(e);
+            }
+        }
+
+        match conn.rpush::<_, _, ()>(queue_key, &payload).await {
+            Ok(()) => {
+                self.degraded.record_success();
+                Ok(())
+            }
+            Err(e) if self.degraded.record_error(&e) => {
+                self.buffer(queue_key, payload);
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn buffer(&self, queue_key: &str, payload: Vec<u8>) {
+        let mut jobs = self.jobs.lock().unwrap();
+        if jobs.len() >= MAX_BUFFERED_JOBS {
+            jobs.pop_front();
+            warn!(
+                "{}",
+                format!(
+                    "Job buffer full ({} jobs), dropping the oldest job",
+                    MAX_BUFFERED_JOBS
+                )
+            );
+        }
+        jobs.push_back((queue_key.to_string(), payload));
+    }
 }
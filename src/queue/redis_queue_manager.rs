@@ -1,15 +1,23 @@
-use crate::queue::{ArcJobProcessorFn, QueueInterface};
-use crate::webhook::sender::JobProcessorFnAsync;
-use crate::webhook::types::JobData;
+use crate::options::{QueueCodec, QueueRetryConfig};
+use crate::queue::codec::{decode_job, encode_job};
+use crate::queue::job::Job;
+use crate::queue::{
+    ArcJobProcessorFn, JobProcessorFnAsync, PARKED_WORKER_POLL_INTERVAL, QueueInterface,
+    RedisJobBuffer, RedisWorkerLease, promote_redis_jobs_now, purge_redis_queue, redis_list_jobs,
+    requeue_redis_dead_letters, retry_redis_job, schedule_redis_job, spawn_redis_delayed_promoter,
+    spawn_redis_reaper,
+};
+use crate::redis_client::RedisAuth;
 use async_trait::async_trait;
 use redis::aio::MultiplexedConnection;
 use redis::{AsyncCommands, RedisResult};
 use serde::Serialize;
 use serde::de::DeserializeOwned;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::time::Duration;
 use tokio::sync::Mutex;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 pub struct RedisQueueManager {
     redis_connection: Arc<Mutex<MultiplexedConnection>>,
@@ -17,6 +25,19 @@ pub struct RedisQueueManager {
     job_processors: dashmap::DashMap<String, ArcJobProcessorFn, ahash::RandomState>,
     prefix: String,
     concurrency: usize,
+    // Holds jobs while Redis refuses writes (OOM/READONLY)
+    job_buffer: RedisJobBuffer,
+    retry: QueueRetryConfig,
+    codec: QueueCodec,
+    // How long a worker's processing list may go unrenewed before its jobs
+    // are returned to the queue
+    visibility_timeout: Duration,
+    // Distinguishes this process's workers from those of other nodes
+    worker_prefix: String,
+    // Tells workers to stop popping jobs
+    shutdown: Arc<AtomicBool>,
+    // Per queue, how many of its workers may take jobs; the rest stay parked
+    active_workers: dashmap::DashMap<String, Arc<AtomicUsize>, ahash::RandomState>,
 }
 
 impl RedisQueueManager {
@@ -24,10 +45,14 @@ impl RedisQueueManager {
     /// Connects to Redis and returns a Result.
     pub async fn new(
         redis_url: &str,
+        auth: &RedisAuth,
         prefix: &str,
         concurrency: usize,
+        retry: QueueRetryConfig,
+        codec: QueueCodec,
+        visibility_timeout: Duration,
     ) -> crate::error::Result<Self> {
-        let client = redis::Client::open(redis_url).map_err(|e| {
+        let client = auth.build_client(redis_url).map_err(|e| {
             crate::error::Error::Config(format!("Failed to open Redis client: {}", e))
         })?; // Use custom error type
 
@@ -43,6 +68,13 @@ impl RedisQueueManager {
             job_processors: dashmap::DashMap::with_hasher(ahash::RandomState::new()),
             prefix: prefix.to_string(),
             concurrency,
+            retry,
+            codec,
+            visibility_timeout,
+            worker_prefix: uuid::Uuid::new_v4().to_string(),
+            shutdown: Arc::new(AtomicBool::new(false)),
+            active_workers: dashmap::DashMap::with_hasher(ahash::RandomState::new()),
+            job_buffer: RedisJobBuffer::new("the Redis queue"),
         })
     }
 
@@ -56,23 +88,39 @@ impl RedisQueueManager {
     async fn format_key(&self, queue_name: &str) -> String {
         format!("{}:queue:{}", self.prefix, queue_name)
     }
+
+    // Sorted set of jobs waiting for their delay, scored by due time
+    fn delayed_key(&self, queue_name: &str) -> String {
+        format!("{}:delayed:{}", self.prefix, queue_name)
+    }
+
+    // Kept outside `:queue:*` so dead-lettered jobs never mix with live queues
+    fn dead_letter_key(&self, queue_name: &str) -> String {
+        format!(
+            "{}:dead_letter:{}",
+            self.prefix,
+            self.retry.dead_letter_queue(queue_name)
+        )
+    }
 }
 
 #[async_trait]
 impl QueueInterface for RedisQueueManager {
     /// Adds a job to the specified Redis queue (list).
-    /// Serializes the job data to JSON.
-    async fn add_to_queue(&self, queue_name: &str, data: JobData) -> crate::error::Result<()>
+    /// Serializes the job data with the configured codec.
+    async fn add_to_queue(&self, queue_name: &str, data: Job) -> crate::error::Result<()>
     where
-        JobData: Serialize, // Ensure JobData can be serialized
+        Job: Serialize, // Ensure Job can be serialized
     {
         let queue_key = self.format_key(queue_name).await;
-        let data_json = serde_json::to_string(&data)?; // Propagate serialization error
+        let payload = encode_job(self.codec, &data)?; // Propagate serialization error
 
         let mut conn = self.redis_connection.lock().await;
 
-        // Perform RPUSH and handle potential Redis errors
-        conn.rpush::<_, _, ()>(&queue_key, data_json)
+        // Perform RPUSH and handle potential Redis errors; while Redis refuses
+        // writes (OOM/READONLY) the job is buffered in memory instead
+        self.job_buffer
+            .push(&mut *conn, &queue_key, payload)
             .await
             .map_err(|e| {
                 crate::error::Error::Queue(format!(
@@ -86,6 +134,24 @@ impl QueueInterface for RedisQueueManager {
         Ok(())
     }
 
+    async fn add_to_queue_delayed(
+        &self,
+        queue_name: &str,
+        data: Job,
+        delay: Duration,
+    ) -> crate::error::Result<()> {
+        let payload = encode_job(self.codec, &data)?;
+        let mut conn = self.redis_connection.lock().await;
+        schedule_redis_job(&mut *conn, &self.delayed_key(queue_name), &payload, delay)
+            .await
+            .map_err(|e| {
+                crate::error::Error::Queue(format!(
+                    "Redis ZADD failed for delayed queue {}: {}",
+                    queue_name, e
+                ))
+            })
+    }
+
     /// Registers a callback for a queue and starts worker tasks to process jobs.
     async fn process_queue(
         &self,
@@ -93,9 +159,24 @@ impl QueueInterface for RedisQueueManager {
         callback: JobProcessorFnAsync,
     ) -> crate::error::Result<()>
     where
-        JobData: DeserializeOwned + Send + 'static, // Ensure JobData can be deserialized and sent across threads
+        Job: DeserializeOwned + Send + 'static, // Ensure Job can be deserialized and sent across threads
     {
         let queue_key = self.format_key(queue_name).await;
+        let dead_letter_key = self.dead_letter_key(queue_name);
+        let delayed_key = self.delayed_key(queue_name);
+
+        // Moves delayed jobs and retries onto the queue once they are due
+        spawn_redis_delayed_promoter(
+            self.redis_connection.clone(),
+            delayed_key.clone(),
+            queue_key.clone(),
+        );
+        // Returns the jobs of crashed workers to the queue
+        spawn_redis_reaper(
+            self.redis_connection.clone(),
+            queue_key.clone(),
+            self.visibility_timeout,
+        );
 
         // Wrap the callback in an Arc to share it safely with multiple worker tasks
         let processor_arc: ArcJobProcessorFn = Arc::from(callback);
@@ -111,12 +192,27 @@ impl QueueInterface for RedisQueueManager {
             )
         );
 
+        let active_workers = Arc::new(AtomicUsize::new(self.concurrency));
+        self.active_workers
+            .insert(queue_name.to_string(), active_workers.clone());
+
         // Start worker tasks
         for i in 0..self.concurrency {
             let worker_queue_key = queue_key.clone();
             let worker_redis_conn = self.redis_connection.clone();
             let worker_processor = processor_arc.clone(); // Clone the Arc for this worker
             let worker_queue_name = queue_name.to_string(); // Clone queue name for logging
+            let worker_dead_letter_key = dead_letter_key.clone();
+            let worker_delayed_key = delayed_key.clone();
+            let worker_retry = self.retry.clone();
+            let worker_codec = self.codec;
+            let worker_shutdown = self.shutdown.clone();
+            let worker_active = active_workers.clone();
+            let mut worker_lease = RedisWorkerLease::new(
+                &queue_key,
+                &format!("{}:{}", self.worker_prefix, i),
+                self.visibility_timeout,
+            );
 
             tokio::spawn(async move {
                 info!(
@@ -127,26 +223,43 @@ impl QueueInterface for RedisQueueManager {
                     )
                 );
 
-                loop {
-                    let blpop_result: RedisResult<Option<(String, String)>> = {
-                        // Type hint for clarity
+                while !worker_shutdown.load(Ordering::SeqCst) {
+                    if i >= worker_active.load(Ordering::Relaxed) {
+                        tokio::time::sleep(PARKED_WORKER_POLL_INTERVAL).await;
+                        continue;
+                    }
+                    let take_result: RedisResult<Option<Vec<u8>>> = {
                         let mut conn = worker_redis_conn.lock().await;
-                        // Use BLPOP with a timeout (e.g., 1 second)
-                        conn.blpop(&worker_queue_key, 0.01).await
+                        // The lease must cover the processing list before a job lands on it
+                        match worker_lease.renew(&mut *conn).await {
+                            Ok(()) => worker_lease.take(&mut *conn, &worker_queue_key).await,
+                            Err(e) => Err(e),
+                        }
                     };
 
-                    match blpop_result {
+                    match take_result {
                         // Successfully received a job
-                        Ok(Some((_key, job_data_str))) => {
-                            match serde_json::from_str::<JobData>(&job_data_str) {
+                        Ok(Some(job_payload)) => {
+                            match decode_job(&job_payload) {
                                 Ok(job_data) => {
                                     // Execute the job processing callback
-                                    match worker_processor(job_data).await {
+                                    match worker_processor(job_data.clone()).await {
                                         Ok(_) => {
                                             info!("{}", "Worker finished".to_string());
                                         }
                                         Err(e) => {
                                             error!("{}", format!("Worker error: {}", e));
+                                            let mut conn = worker_redis_conn.lock().await;
+                                            retry_redis_job(
+                                                &mut *conn,
+                                                &worker_retry,
+                                                worker_codec,
+                                                &worker_delayed_key,
+                                                &worker_dead_letter_key,
+                                                job_data,
+                                                &e,
+                                            )
+                                            .await;
                                         }
                                     }
                                 }
@@ -156,14 +269,28 @@ impl QueueInterface for RedisQueueManager {
                                         "{}",
                                         format!(
                                             "[Worker {}] Error deserializing job data from Redis queue {}: {}. Data: '{}'",
-                                            i, worker_queue_name, e, job_data_str
+                                            i,
+                                            worker_queue_name,
+                                            e,
+                                            String::from_utf8_lossy(&job_payload)
                                         )
                                     );
                                     // Potential: Move corrupted data to a specific place?
                                 }
                             }
+                            // Handled, retried or unreadable: either way it leaves the processing list
+                            let mut conn = worker_redis_conn.lock().await;
+                            if let Err(e) = worker_lease.finish(&mut *conn).await {
+                                error!(
+                                    "{}",
+                                    format!(
+                                        "[Worker {}] Failed to clear processing list of queue {}: {}",
+                                        i, worker_queue_name, e
+                                    )
+                                );
+                            }
                         }
-                        // BLPOP timed out, no job available
+                        // BLMOVE timed out, no job available
                         Ok(None) => {
                             // Continue loop to wait again
                             continue;
@@ -173,7 +300,7 @@ impl QueueInterface for RedisQueueManager {
                             error!(
                                 "{}",
                                 format!(
-                                    "[Worker {}] Redis BLPOP error on queue {}: {}",
+                                    "[Worker {}] Redis BLMOVE error on queue {}: {}",
                                     i, worker_queue_name, e
                                 )
                             );
@@ -182,21 +309,132 @@ impl QueueInterface for RedisQueueManager {
                         }
                     }
                 }
+
+                // Stopped between jobs, so nothing is left to reap
+                let mut conn = worker_redis_conn.lock().await;
+                if let Err(e) = worker_lease.release(&mut *conn).await {
+                    warn!(
+                        "{}",
+                        format!(
+                            "[Worker {}] Failed to release lease on queue {}: {}",
+                            i, worker_queue_name, e
+                        )
+                    );
+                }
             });
         }
 
         Ok(())
     }
 
-    async fn disconnect(&self) -> crate::error::Result<()> {
+    async fn dead_letter_jobs(
+        &self,
+        queue_name: &str,
+        limit: usize,
+    ) -> crate::error::Result<Vec<Job>> {
+        let mut conn = self.redis_connection.lock().await;
+        redis_list_jobs(&mut *conn, &self.dead_letter_key(queue_name), limit).await
+    }
+
+    async fn requeue_dead_letters(
+        &self,
+        queue_name: &str,
+        limit: usize,
+    ) -> crate::error::Result<usize> {
+        let queue_key = self.format_key(queue_name).await;
+        let mut conn = self.redis_connection.lock().await;
+        let requeued = requeue_redis_dead_letters(
+            &mut *conn,
+            &self.dead_letter_key(queue_name),
+            &queue_key,
+            limit,
+            self.codec,
+        )
+        .await?;
+        info!(
+            "{}",
+            format!(
+                "Requeued {} dead-lettered jobs onto Redis queue {}",
+                requeued, queue_name
+            )
+        );
+        Ok(requeued)
+    }
+
+    async fn pending_jobs(&self, queue_name: &str) -> crate::error::Result<Option<usize>> {
+        let queue_key = self.format_key(queue_name).await;
+        let mut conn = self.redis_connection.lock().await;
+        let pending: usize = conn.llen(&queue_key).await.map_err(|e| {
+            crate::error::Error::Queue(format!("Redis LLEN failed for queue {}: {}", queue_name, e))
+        })?;
+        Ok(Some(pending))
+    }
+
+    async fn peek_jobs(
+        &self,
+        queue_name: &str,
+        limit: usize,
+    ) -> crate::error::Result<Option<Vec<Job>>> {
+        let queue_key = self.format_key(queue_name).await;
         let mut conn = self.redis_connection.lock().await;
-        let keys: Vec<String> = conn
-            .keys(format!("{}:queue:*", self.prefix))
+        redis_list_jobs(&mut *conn, &queue_key, limit)
             .await
-            .expect("Error fetching keys");
-        for key in keys {
-            conn.del::<_, ()>(&key).await.expect("Error deleting key");
-        }
+            .map(Some)
+    }
+
+    async fn retry_delayed_jobs(
+        &self,
+        queue_name: &str,
+        limit: usize,
+    ) -> crate::error::Result<Option<usize>> {
+        let queue_key = self.format_key(queue_name).await;
+        let mut conn = self.redis_connection.lock().await;
+        let retried =
+            promote_redis_jobs_now(&mut *conn, &self.delayed_key(queue_name), &queue_key, limit)
+                .await
+                .map_err(|e| {
+                    crate::error::Error::Queue(format!(
+                        "Redis failed to retry delayed jobs of queue {}: {}",
+                        queue_name, e
+                    ))
+                })?;
+        Ok(Some(retried))
+    }
+
+    async fn purge_jobs(&self, queue_name: &str) -> crate::error::Result<Option<usize>> {
+        let queue_key = self.format_key(queue_name).await;
+        let mut conn = self.redis_connection.lock().await;
+        let purged = purge_redis_queue(&mut *conn, &queue_key)
+            .await
+            .map_err(|e| {
+                crate::error::Error::Queue(format!(
+                    "Redis failed to purge queue {}: {}",
+                    queue_name, e
+                ))
+            })?;
+        Ok(Some(purged))
+    }
+
+    /// Jobs of a worker that stops mid-job stay on its processing list and
+    /// are reaped once its lease expires
+    fn redelivers_unfinished_jobs(&self) -> bool {
+        true
+    }
+
+    fn set_active_workers(&self, queue_name: &str, workers: usize) -> Option<usize> {
+        let active = self.active_workers.get(queue_name)?;
+        let workers = workers.min(self.concurrency);
+        active.store(workers, Ordering::Relaxed);
+        Some(workers)
+    }
+
+    async fn stop_consuming(&self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+    }
+
+    /// Stops the workers. Queued jobs stay in Redis for the next node to pick up.
+    async fn disconnect(&self) -> crate::error::Result<()> {
+        self.shutdown.store(true, Ordering::SeqCst);
         Ok(())
     }
 }
@@ -1,16 +1,178 @@
 use crate::error::{Error, Result};
 
-use crate::options::SqsQueueConfig; // Use the struct from options.rs
-use crate::queue::{ArcJobProcessorFn, QueueInterface};
-use crate::webhook::sender::JobProcessorFnAsync;
+use crate::options::{QueueCodec, QueueRetryConfig, SqsQueueConfig}; // Use the struct from options.rs
+use crate::queue::codec::{decode_job_text, encode_job_text};
+use crate::queue::job::Job;
+use crate::queue::{ArcJobProcessorFn, FailedJob, JobProcessorFnAsync, QueueInterface};
 use async_trait::async_trait;
 use aws_sdk_sqs as sqs;
+use aws_sdk_sqs::types::{MessageSystemAttributeName, SendMessageBatchRequestEntry};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::Mutex;
-use tokio::time::interval;
-use tracing::{error, info};
+use tokio::sync::{Mutex, mpsc, oneshot};
+use tracing::{error, info, warn};
+
+/// SendMessageBatch accepts at most 10 entries
+const MAX_SEND_BATCH_SIZE: usize = 10;
+
+/// Longest visibility timeout SQS accepts, in seconds
+const MAX_VISIBILITY_TIMEOUT: u64 = 43_200;
+
+/// Longest per-message DelaySeconds SQS accepts
+const MAX_DELAY_SECONDS: u64 = 900;
+
+/// Longest FIFO MessageGroupId SQS accepts
+const MAX_MESSAGE_GROUP_ID_LENGTH: usize = 128;
+
+/// A job waiting to be sent, with the channel its result is reported on
+struct PendingSend {
+    queue_url: String,
+    body: String,
+    /// FIFO message group, when the queue is FIFO
+    message_group_id: Option<String>,
+    result: oneshot::Sender<Result<()>>,
+}
+
+/// Coalesces jobs added in quick succession into SendMessageBatch calls, so
+/// webhook bursts cost one round trip per ten messages instead of one each.
+struct SendBatcher {
+    sender: mpsc::UnboundedSender<PendingSend>,
+}
+
+impl SendBatcher {
+    fn start(client: sqs::Client, config: &SqsQueueConfig) -> Self {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<PendingSend>();
+        let window = Duration::from_millis(config.send_batch_window_ms.max(1));
+        let max_batch_size = config.send_batch_size.clamp(1, MAX_SEND_BATCH_SIZE);
+
+        tokio::spawn(async move {
+            while let Some(first) = receiver.recv().await {
+                let mut pending = vec![first];
+                let deadline = tokio::time::Instant::now() + window;
+
+                // Collect everything else queued before the window closes
+                while pending.len() < max_batch_size {
+                    match tokio::time::timeout_at(deadline, receiver.recv()).await {
+                        Ok(Some(item)) => pending.push(item),
+                        Ok(None) | Err(_) => break,
+                    }
+                }
+
+                // A batch can only target one queue
+                let mut by_queue: HashMap<String, Vec<PendingSend>> = HashMap::new();
+                for item in pending {
+                    by_queue
+                        .entry(item.queue_url.clone())
+                        .or_default()
+                        .push(item);
+                }
+                for (queue_url, items) in by_queue {
+                    Self::send_batch(&client, &queue_url, items).await;
+                }
+            }
+        });
+
+        Self { sender }
+    }
+
+    async fn send_batch(client: &sqs::Client, queue_url: &str, items: Vec<PendingSend>) {
+        let mut entries = Vec::with_capacity(items.len());
+        let mut results = HashMap::with_capacity(items.len());
+        for (index, item) in items.into_iter().enumerate() {
+            let id = index.to_string();
+            let entry = SendMessageBatchRequestEntry::builder()
+                .id(&id)
+                .message_body(item.body)
+                .set_message_group_id(item.message_group_id)
+                .build();
+            match entry {
+                Ok(entry) => {
+                    entries.push(entry);
+                    results.insert(id, item.result);
+                }
+                Err(e) => {
+                    let _ = item.result.send(Err(Error::Queue(format!(
+                        "Failed to build SQS batch entry: {}",
+                        e
+                    ))));
+                }
+            }
+        }
+        if entries.is_empty() {
+            return;
+        }
+
+        match client
+            .send_message_batch()
+            .queue_url(queue_url)
+            .set_entries(Some(entries))
+            .send()
+            .await
+        {
+            Ok(output) => {
+                for failed in output.failed() {
+                    if let Some(result) = results.remove(failed.id()) {
+                        let _ = result.send(Err(Error::Queue(format!(
+                            "SQS rejected batched message ({}): {}",
+                            failed.code(),
+                            failed.message().unwrap_or("no message")
+                        ))));
+                    }
+                }
+                for (_, result) in results {
+                    let _ = result.send(Ok(()));
+                }
+            }
+            Err(e) => {
+                let message = format!("Failed to send message batch to {}: {}", queue_url, e);
+                for (_, result) in results {
+                    let _ = result.send(Err(Error::Queue(message.clone())));
+                }
+            }
+        }
+    }
+
+    async fn send(
+        &self,
+        queue_url: String,
+        body: String,
+        message_group_id: Option<String>,
+    ) -> Result<()> {
+        let (result, receiver) = oneshot::channel();
+        self.sender
+            .send(PendingSend {
+                queue_url,
+                body,
+                message_group_id,
+                result,
+            })
+            .map_err(|_| Error::Queue("SQS send batcher stopped".to_string()))?;
+        receiver
+            .await
+            .map_err(|_| Error::Queue("SQS send batcher dropped the message".to_string()))?
+    }
+}
+
+/// FIFO message group of `job`. With `group_by_ordering_key`, jobs sharing an
+/// ordering key (a channel, for webhooks) form their own group, so they stay
+/// in order while other groups are processed in parallel.
+fn message_group_id(config: &SqsQueueConfig, job: &Job) -> Option<String> {
+    if !config.fifo {
+        return None;
+    }
+    match &job.ordering_key {
+        Some(key) if config.group_by_ordering_key => {
+            // Group IDs are limited to 128 characters
+            if key.len() <= MAX_MESSAGE_GROUP_ID_LENGTH {
+                Some(key.clone())
+            } else {
+                Some(format!("{:x}", md5::compute(key)))
+            }
+        }
+        _ => config.message_group_id.clone(),
+    }
+}
 
 /// SQS-based implementation of the QueueInterface
 pub struct SqsQueueManager {
@@ -26,11 +188,21 @@ pub struct SqsQueueManager {
     worker_handles: Arc<Mutex<HashMap<String, Vec<tokio::task::JoinHandle<()>>>>>,
     /// Flag to control worker shutdown
     shutdown: Arc<Mutex<bool>>,
+    /// Batches outgoing jobs when `send_batch_size` is above 1
+    send_batcher: Option<SendBatcher>,
+    /// Attempts before a job is moved to the dead-letter queue
+    retry: QueueRetryConfig,
+    /// How job bodies are encoded
+    codec: QueueCodec,
 }
 
 impl SqsQueueManager {
     /// Create a new SQS queue manager
-    pub async fn new(config: SqsQueueConfig) -> Result<Self> {
+    pub async fn new(
+        config: SqsQueueConfig,
+        retry: QueueRetryConfig,
+        codec: QueueCodec,
+    ) -> Result<Self> {
         // Build AWS config
         let mut aws_config_builder = aws_config::from_env();
 
@@ -46,8 +218,29 @@ impl SqsQueueManager {
         // Build the AWS config
         let aws_config = aws_config_builder.load().await;
 
-        // Create SQS client
-        let client = sqs::Client::new(&aws_config);
+        // Create SQS client, assuming the configured role when one is set
+        let client = match &config.assume_role_arn {
+            Some(role_arn) => {
+                info!("{}", format!("Assuming role {} for SQS", role_arn));
+                let mut provider = aws_config::sts::AssumeRoleProvider::builder(role_arn)
+                    .session_name(&config.assume_role_session_name)
+                    .configure(&aws_config);
+                if let Some(external_id) = &config.assume_role_external_id {
+                    provider = provider.external_id(external_id);
+                }
+                let sqs_config = sqs::config::Builder::from(&aws_config)
+                    .credentials_provider(provider.build().await)
+                    .build();
+                sqs::Client::from_conf(sqs_config)
+            }
+            None => sqs::Client::new(&aws_config),
+        };
+
+        let send_batcher = if config.send_batch_size > 1 {
+            Some(SendBatcher::start(client.clone(), &config))
+        } else {
+            None
+        };
 
         Ok(Self {
             client,
@@ -55,6 +248,9 @@ impl SqsQueueManager {
             queue_urls: Arc::new(Mutex::new(HashMap::new())),
             worker_handles: Arc::new(Mutex::new(HashMap::new())),
             shutdown: Arc::new(Mutex::new(false)),
+            send_batcher,
+            retry,
+            codec,
         })
     }
 
@@ -182,17 +378,46 @@ impl SqsQueueManager {
         }
     }
 
+    /// Send a single job to `queue_url`, bypassing the batcher
+    async fn send_job(
+        client: &sqs::Client,
+        config: &SqsQueueConfig,
+        codec: QueueCodec,
+        queue_url: &str,
+        job: &Job,
+    ) -> Result<()> {
+        let body = encode_job_text(codec, job)
+            .map_err(|e| Error::Queue(format!("Failed to serialize job data: {}", e)))?;
+        client
+            .send_message()
+            .queue_url(queue_url)
+            .message_body(body)
+            .set_message_group_id(message_group_id(config, job))
+            .send()
+            .await
+            .map_err(|e| {
+                Error::Queue(format!(
+                    "Failed to send message to SQS queue {}: {}",
+                    queue_url, e
+                ))
+            })?;
+        Ok(())
+    }
+
     /// Start a worker for processing messages from the queue
     async fn start_worker(
         &self,
         queue_name: &str,
         queue_url: String,
+        dead_letter_url: String,
         processor: ArcJobProcessorFn,
         worker_id: usize,
     ) -> tokio::task::JoinHandle<()> {
         // Clone values needed for the worker
         let client = self.client.clone();
         let config = self.config.clone();
+        let retry = self.retry.clone();
+        let codec = self.codec;
         let shutdown = self.shutdown.clone();
         let queue_name = queue_name.to_string();
 
@@ -206,10 +431,15 @@ impl SqsQueueManager {
                 )
             );
 
-            let mut interval = interval(Duration::from_secs(1));
+            // SQS caps long polling at 20 seconds
+            let wait_time_seconds = config.wait_time_seconds.clamp(0, 20);
 
             loop {
-                interval.tick().await;
+                // Long polling already waits on the server; only short polling
+                // needs a pause between empty receives
+                if wait_time_seconds == 0 {
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
 
                 // Check if we should shutdown
                 if *shutdown.lock().await {
@@ -229,7 +459,10 @@ impl SqsQueueManager {
                     .queue_url(&queue_url)
                     .max_number_of_messages(config.max_messages)
                     .visibility_timeout(config.visibility_timeout)
-                    .wait_time_seconds(config.wait_time_seconds)
+                    .wait_time_seconds(wait_time_seconds)
+                    .message_system_attribute_names(
+                        MessageSystemAttributeName::ApproximateReceiveCount,
+                    )
                     .send()
                     .await;
 
@@ -252,14 +485,17 @@ impl SqsQueueManager {
 
                             // Iterate through the messages in the slice
                             for message in messages {
+                                // Once stopped, the rest of the batch becomes
+                                // visible again after the visibility timeout
+                                if *shutdown.lock().await {
+                                    break;
+                                }
                                 if let Some(body) = message.body() {
                                     // Process the message
-                                    match serde_json::from_str::<crate::webhook::types::JobData>(
-                                        body,
-                                    ) {
+                                    match decode_job_text(body) {
                                         Ok(job_data) => {
                                             // Call the processor
-                                            match processor(job_data).await {
+                                            match processor(job_data.clone()).await {
                                                 Ok(_) => {
                                                     // Processing succeeded, delete the message
                                                     if let Some(receipt_handle) =
@@ -291,6 +527,95 @@ impl SqsQueueManager {
                                                             queue_name, e
                                                         )
                                                     );
+                                                    // SQS counts receives, which
+                                                    // stands in for the attempt count
+                                                    let mut job_data = job_data;
+                                                    job_data.attempts = message
+                                                        .attributes()
+                                                        .and_then(|attributes| {
+                                                            attributes.get(
+                                                                &MessageSystemAttributeName::ApproximateReceiveCount,
+                                                            )
+                                                        })
+                                                        .and_then(|count| count.parse::<u32>().ok())
+                                                        .unwrap_or(1)
+                                                        .saturating_sub(1);
+                                                    let Some(receipt_handle) =
+                                                        message.receipt_handle()
+                                                    else {
+                                                        continue;
+                                                    };
+                                                    match retry.record_failure(&mut job_data, &e) {
+                                                        FailedJob::Retry(delay) => {
+                                                            // Make it visible again after the backoff
+                                                            // (or the fixed retry delay, when set)
+                                                            // instead of the full visibility timeout
+                                                            let timeout = config
+                                                                .retry_visibility_timeout
+                                                                .unwrap_or_else(|| {
+                                                                    delay
+                                                                        .as_secs()
+                                                                        .min(MAX_VISIBILITY_TIMEOUT)
+                                                                        as i32
+                                                                });
+                                                            if let Err(e) = client
+                                                                .change_message_visibility()
+                                                                .queue_url(&queue_url)
+                                                                .receipt_handle(receipt_handle)
+                                                                .visibility_timeout(timeout)
+                                                                .send()
+                                                                .await
+                                                            {
+                                                                warn!(
+                                                                    "{}",
+                                                                    format!(
+                                                                        "Failed to change message visibility in SQS queue {}: {}",
+                                                                        queue_name, e
+                                                                    )
+                                                                );
+                                                            }
+                                                        }
+                                                        FailedJob::DeadLetter => {
+                                                            warn!(
+                                                                "{}",
+                                                                format!(
+                                                                    "Job for app {} failed {} times, moving it to the dead-letter queue of {}",
+                                                                    job_data.app_id,
+                                                                    job_data.attempts,
+                                                                    queue_name
+                                                                )
+                                                            );
+                                                            if let Err(e) = Self::send_job(
+                                                                &client,
+                                                                &config,
+                                                                codec,
+                                                                &dead_letter_url,
+                                                                &job_data,
+                                                            )
+                                                            .await
+                                                            {
+                                                                // Leave the message in place to be
+                                                                // dead-lettered on its next failure
+                                                                error!("{}", e);
+                                                                continue;
+                                                            }
+                                                            if let Err(e) = client
+                                                                .delete_message()
+                                                                .queue_url(&queue_url)
+                                                                .receipt_handle(receipt_handle)
+                                                                .send()
+                                                                .await
+                                                            {
+                                                                error!(
+                                                                    "{}",
+                                                                    format!(
+                                                                        "Failed to delete dead-lettered message from SQS queue {}: {}",
+                                                                        queue_name, e
+                                                                    )
+                                                                );
+                                                            }
+                                                        }
+                                                    }
                                                 }
                                             }
                                         }
@@ -348,18 +673,25 @@ impl SqsQueueManager {
 #[async_trait]
 impl QueueInterface for SqsQueueManager {
     /// Add a job to a queue
-    async fn add_to_queue(
-        &self,
-        queue_name: &str,
-        data: crate::webhook::types::JobData,
-    ) -> Result<()> {
+    async fn add_to_queue(&self, queue_name: &str, data: Job) -> Result<()> {
         // Get the queue URL
         let queue_url = self.get_queue_url(queue_name).await?;
 
         // Serialize the job data
-        let data_json = serde_json::to_string(&data)
+        let data_json = encode_job_text(self.codec, &data)
             .map_err(|e| Error::Queue(format!("Failed to serialize job data: {}", e)))?;
 
+        if let Some(batcher) = &self.send_batcher {
+            batcher
+                .send(queue_url, data_json, message_group_id(&self.config, &data))
+                .await?;
+            info!(
+                "{}",
+                format!("Added job to SQS queue {} (batched)", queue_name)
+            );
+            return Ok(());
+        }
+
         // Build send message request
         let mut send_message_request = self
             .client
@@ -370,9 +702,8 @@ impl QueueInterface for SqsQueueManager {
         // Add FIFO-specific attributes if needed
         if self.config.fifo {
             // For FIFO queues, we need a message group ID and deduplication ID
-            if let Some(group_id) = &self.config.message_group_id {
-                send_message_request = send_message_request.message_group_id(group_id);
-            }
+            send_message_request =
+                send_message_request.set_message_group_id(message_group_id(&self.config, &data));
 
             // Use a unique ID based on the request ID for deduplication
             // Only needed if ContentBasedDeduplication is not enabled
@@ -399,10 +730,58 @@ impl QueueInterface for SqsQueueManager {
         Ok(())
     }
 
+    /// Add a job that SQS keeps invisible for `delay`. SQS caps per-message
+    /// delays at 15 minutes and FIFO queues only support a queue-wide delay.
+    async fn add_to_queue_delayed(
+        &self,
+        queue_name: &str,
+        data: Job,
+        delay: Duration,
+    ) -> Result<()> {
+        if self.config.fifo {
+            return Err(Error::Queue(format!(
+                "SQS FIFO queue {} does not support per-message delays",
+                queue_name
+            )));
+        }
+        if delay.as_secs() > MAX_DELAY_SECONDS {
+            warn!(
+                "{}",
+                format!(
+                    "Delay of {}s for SQS queue {} exceeds the {}s maximum, capping it",
+                    delay.as_secs(),
+                    queue_name,
+                    MAX_DELAY_SECONDS
+                )
+            );
+        }
+
+        let queue_url = self.get_queue_url(queue_name).await?;
+        let data_json = encode_job_text(self.codec, &data)
+            .map_err(|e| Error::Queue(format!("Failed to serialize job data: {}", e)))?;
+        self.client
+            .send_message()
+            .queue_url(queue_url)
+            .message_body(data_json)
+            .delay_seconds(delay.as_secs().min(MAX_DELAY_SECONDS) as i32)
+            .send()
+            .await
+            .map_err(|e| {
+                Error::Queue(format!(
+                    "Failed to send delayed message to SQS queue {}: {}",
+                    queue_name, e
+                ))
+            })?;
+        Ok(())
+    }
+
     /// Process jobs from a queue
     async fn process_queue(&self, queue_name: &str, callback: JobProcessorFnAsync) -> Result<()> {
         // Get the queue URL
         let queue_url = self.get_queue_url(queue_name).await?;
+        let dead_letter_url = self
+            .get_queue_url(&self.retry.dead_letter_queue(queue_name))
+            .await?;
 
         // Wrap the callback in an Arc for thread-safe sharing
         let processor: ArcJobProcessorFn = Arc::from(callback);
@@ -412,7 +791,13 @@ impl QueueInterface for SqsQueueManager {
         let concurrency = self.config.concurrency as usize;
         for worker_id in 0..concurrency {
             let handle = self
-                .start_worker(queue_name, queue_url.clone(), processor.clone(), worker_id)
+                .start_worker(
+                    queue_name,
+                    queue_url.clone(),
+                    dead_letter_url.clone(),
+                    processor.clone(),
+                    worker_id,
+                )
                 .await;
 
             worker_handles.push(handle);
@@ -433,7 +818,160 @@ impl QueueInterface for SqsQueueManager {
         Ok(())
     }
 
+    /// Peek at dead-lettered jobs. Messages are received with a zero
+    /// visibility timeout, so they stay in the dead-letter queue.
+    async fn dead_letter_jobs(&self, queue_name: &str, limit: usize) -> Result<Vec<Job>> {
+        let dead_letter_url = self
+            .get_queue_url(&self.retry.dead_letter_queue(queue_name))
+            .await?;
+        let mut seen = std::collections::HashSet::new();
+        let mut jobs = Vec::new();
+
+        while jobs.len() < limit {
+            let response = self
+                .client
+                .receive_message()
+                .queue_url(&dead_letter_url)
+                .max_number_of_messages((limit - jobs.len()).min(MAX_SEND_BATCH_SIZE) as i32)
+                .visibility_timeout(0)
+                .send()
+                .await
+                .map_err(|e| {
+                    Error::Queue(format!("Failed to read SQS dead-letter queue: {}", e))
+                })?;
+
+            let mut received_new = false;
+            for message in response.messages() {
+                if !seen.insert(message.message_id().unwrap_or_default().to_string()) {
+                    continue;
+                }
+                received_new = true;
+                if let Some(job) = message.body().and_then(|body| decode_job_text(body).ok()) {
+                    jobs.push(job);
+                }
+            }
+            if !received_new {
+                break;
+            }
+        }
+
+        jobs.truncate(limit);
+        Ok(jobs)
+    }
+
+    async fn requeue_dead_letters(&self, queue_name: &str, limit: usize) -> Result<usize> {
+        let queue_url = self.get_queue_url(queue_name).await?;
+        let dead_letter_url = self
+            .get_queue_url(&self.retry.dead_letter_queue(queue_name))
+            .await?;
+        let mut requeued = 0;
+
+        while requeued < limit {
+            let response = self
+                .client
+                .receive_message()
+                .queue_url(&dead_letter_url)
+                .max_number_of_messages((limit - requeued).min(MAX_SEND_BATCH_SIZE) as i32)
+                .visibility_timeout(self.config.visibility_timeout)
+                .send()
+                .await
+                .map_err(|e| {
+                    Error::Queue(format!("Failed to read SQS dead-letter queue: {}", e))
+                })?;
+            let messages = response.messages();
+            if messages.is_empty() {
+                break;
+            }
+
+            for message in messages {
+                let (Some(body), Some(receipt_handle)) = (message.body(), message.receipt_handle())
+                else {
+                    continue;
+                };
+                let Ok(mut job) = decode_job_text(body) else {
+                    warn!(
+                        "{}",
+                        format!("Skipping unreadable dead-lettered job in {}", queue_name)
+                    );
+                    continue;
+                };
+                job.attempts = 0;
+                job.last_error = None;
+                Self::send_job(&self.client, &self.config, self.codec, &queue_url, &job).await?;
+                self.client
+                    .delete_message()
+                    .queue_url(&dead_letter_url)
+                    .receipt_handle(receipt_handle)
+                    .send()
+                    .await
+                    .map_err(|e| {
+                        Error::Queue(format!(
+                            "Failed to delete requeued job from SQS dead-letter queue: {}",
+                            e
+                        ))
+                    })?;
+                requeued += 1;
+            }
+        }
+
+        info!(
+            "{}",
+            format!(
+                "Requeued {} dead-lettered jobs onto SQS queue {}",
+                requeued, queue_name
+            )
+        );
+        Ok(requeued)
+    }
+
     /// Disconnect and clean up
+    async fn pending_jobs(&self, queue_name: &str) -> Result<Option<usize>> {
+        use aws_sdk_sqs::types::QueueAttributeName;
+
+        let queue_url = self.get_queue_url(queue_name).await?;
+        let output = self
+            .client
+            .get_queue_attributes()
+            .queue_url(queue_url)
+            .attribute_names(QueueAttributeName::ApproximateNumberOfMessages)
+            .send()
+            .await
+            .map_err(|e| {
+                Error::Queue(format!(
+                    "Failed to get attributes of SQS queue {}: {}",
+                    queue_name, e
+                ))
+            })?;
+        Ok(output
+            .attributes()
+            .and_then(|attributes| attributes.get(&QueueAttributeName::ApproximateNumberOfMessages))
+            .and_then(|count| count.parse().ok()))
+    }
+
+    /// Purges through the SQS API. SQS doesn't report how many messages a
+    /// purge removed, so the count is the queue's approximate size beforehand.
+    async fn purge_jobs(&self, queue_name: &str) -> Result<Option<usize>> {
+        let pending = self.pending_jobs(queue_name).await?;
+        let queue_url = self.get_queue_url(queue_name).await?;
+        self.client
+            .purge_queue()
+            .queue_url(queue_url)
+            .send()
+            .await
+            .map_err(|e| {
+                Error::Queue(format!("Failed to purge SQS queue {}: {}", queue_name, e))
+            })?;
+        Ok(Some(pending.unwrap_or(0)))
+    }
+
+    async fn stop_consuming(&self) {
+        *self.shutdown.lock().await = true;
+    }
+
+    fn redelivers_unfinished_jobs(&self) -> bool {
+        true
+    }
+
     async fn disconnect(&self) -> Result<()> {
         // Signal workers to shutdown
         {
@@ -0,0 +1,155 @@
+// src/rate_limiter/client_event_limiter.rs
+//! Limits the client events of one connection to its app's
+//! `max_client_events_per_second` with a token bucket, so a burst of up to a
+//! second's worth goes through and anything faster is rejected. Connections
+//! that keep going over the limit can be closed.
+
+use std::time::{Duration, Instant};
+
+use super::RateLimitResult;
+use super::token_bucket::TokenBucket;
+use crate::options::ClientEventRateLimit;
+
+/// What to do with a client event
+pub enum ClientEventVerdict {
+    Allowed,
+    /// Reject the event, keeping the connection
+    Rejected(RateLimitResult),
+    /// Reject the event and close the connection, which went over the limit
+    /// `disconnect_after` times within the offence window
+    Disconnect(RateLimitResult),
+}
+
+pub struct ClientEventLimiter {
+    bucket: TokenBucket,
+    disconnect_after: u32,
+    offence_window: Duration,
+    /// Rejected events since `offences_since`
+    offences: u32,
+    offences_since: Instant,
+}
+
+impl ClientEventLimiter {
+    pub fn new(events_per_second: u32, config: &ClientEventRateLimit) -> Self {
+        Self {
+            bucket: TokenBucket::new(events_per_second, events_per_second),
+            disconnect_after: config.disconnect_after,
+            offence_window: Duration::from_secs(config.offence_window_seconds.max(1)),
+            offences: 0,
+            offences_since: Instant::now(),
+        }
+    }
+
+    pub fn check(&mut self) -> ClientEventVerdict {
+        if self.bucket.try_take() {
+            return ClientEventVerdict::Allowed;
+        }
+
+        let now = Instant::now();
+        if now.duration_since(self.offences_since) >= self.offence_window {
+            self.offences = 0;
+            self.offences_since = now;
+        }
+        self.offences += 1;
+
+        let result = RateLimitResult {
+            allowed: false,
+            remaining: self.bucket.remaining(),
+            reset_after: self.bucket.time_to_next().as_secs_f64().ceil() as u64,
+            limit: self.bucket.capacity(),
+        };
+        if self.disconnect_after > 0 && self.offences >= self.disconnect_after {
+            ClientEventVerdict::Disconnect(result)
+        } else {
+            ClientEventVerdict::Rejected(result)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(events_per_second: u32, disconnect_after: u32) -> ClientEventLimiter {
+        ClientEventLimiter::new(
+            events_per_second,
+            &ClientEventRateLimit {
+                disconnect_after,
+                offence_window_seconds: 10,
+            },
+        )
+    }
+
+    fn is_allowed(verdict: &ClientEventVerdict) -> bool {
+        matches!(verdict, ClientEventVerdict::Allowed)
+    }
+
+    fn is_rejected(verdict: &ClientEventVerdict) -> bool {
+        matches!(verdict, ClientEventVerdict::Rejected(_))
+    }
+
+    fn is_disconnect(verdict: &ClientEventVerdict) -> bool {
+        matches!(verdict, ClientEventVerdict::Disconnect(_))
+    }
+
+    #[test]
+    fn test_burst_of_one_second_is_allowed() {
+        let mut limiter = limiter(3, 0);
+        for _ in 0..3 {
+            assert!(is_allowed(&limiter.check()));
+        }
+        match limiter.check() {
+            ClientEventVerdict::Rejected(result) => {
+                assert!(!result.allowed);
+                assert_eq!(result.remaining, 0);
+                assert_eq!(result.limit, 3);
+                assert_eq!(result.reset_after, 1);
+            }
+            _ => panic!("expected the fourth event to be rejected"),
+        }
+    }
+
+    #[test]
+    fn test_disconnects_after_repeated_offences() {
+        let mut limiter = limiter(1, 3);
+        assert!(is_allowed(&limiter.check()));
+        assert!(is_rejected(&limiter.check()));
+        assert!(is_rejected(&limiter.check()));
+        assert!(is_disconnect(&limiter.check()));
+        assert!(is_disconnect(&limiter.check()));
+    }
+
+    #[test]
+    fn test_zero_disconnect_after_keeps_connection() {
+        let mut limiter = limiter(1, 0);
+        assert!(is_allowed(&limiter.check()));
+        for _ in 0..100 {
+            assert!(is_rejected(&limiter.check()));
+        }
+    }
+
+    #[test]
+    fn test_offences_reset_after_window() {
+        let mut limiter = limiter(1, 2);
+        assert!(is_allowed(&limiter.check()));
+        assert!(is_rejected(&limiter.check()));
+
+        // An empty window has always passed, so the count starts over
+        limiter.offence_window = Duration::ZERO;
+        assert!(is_rejected(&limiter.check()));
+        assert_eq!(limiter.offences, 1);
+
+        limiter.offence_window = Duration::from_secs(10);
+        assert!(is_disconnect(&limiter.check()));
+    }
+
+    #[test]
+    fn test_allowed_events_are_not_offences() {
+        let mut limiter = limiter(2, 2);
+        assert!(is_allowed(&limiter.check()));
+        assert!(is_allowed(&limiter.check()));
+        assert_eq!(limiter.offences, 0);
+        assert!(is_rejected(&limiter.check()));
+        assert_eq!(limiter.offences, 1);
+    }
+}
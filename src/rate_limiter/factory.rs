@@ -1,13 +1,14 @@
 // src/rate_limiter/factory.rs
-use crate::rate_limiter::RateLimiter;
+use crate::rate_limiter::{RateLimitConfig, RateLimiter};
 use std::sync::Arc;
 use tracing::{error, info, warn};
 // Use the type-safe RedisConfig and CacheDriver from options.rs
 use crate::error::Result;
 
-use crate::options::{CacheDriver, RateLimiterConfig, RedisConnection};
+use crate::options::{CacheDriver, RateLimit, RateLimiterConfig, RedisConnection};
 use crate::rate_limiter::memory_limiter::MemoryRateLimiter;
 use crate::rate_limiter::redis_limiter::RedisRateLimiter;
+use crate::redis_client::RedisAuth;
 
 pub struct RateLimiterFactory;
 
@@ -16,6 +17,23 @@ impl RateLimiterFactory {
         config: &RateLimiterConfig,
         global_redis_conn_details: &RedisConnection, // For Redis URL/nodes if not in RateLimiterConfig.redis.url_override
         debug_enabled: bool,
+    ) -> Result<Arc<dyn RateLimiter + Send + Sync>> {
+        Self::create_for(
+            config,
+            &config.api_rate_limit,
+            global_redis_conn_details,
+            debug_enabled,
+        )
+        .await
+    }
+
+    /// A limiter on the configured backend enforcing `limit`, such as
+    /// `websocket_rate_limit` rather than the API limit
+    pub async fn create_for(
+        config: &RateLimiterConfig,
+        limit: &RateLimit,
+        global_redis_conn_details: &RedisConnection,
+        debug_enabled: bool,
     ) -> Result<Arc<dyn RateLimiter + Send + Sync>> {
         if !config.enabled {
             info!(
@@ -29,7 +47,8 @@ impl RateLimiterFactory {
         info!(
             "{}",
             format!(
-                "Initializing HTTP API RateLimiter with driver: {:?}",
+                "Initializing {} RateLimiter with driver: {:?}",
+                limit.identifier.as_deref().unwrap_or("HTTP API"),
                 config.driver
             )
         );
@@ -62,13 +81,11 @@ impl RateLimiterFactory {
                     // Here you would instantiate your RedisClusterRateLimiter
                     // For now, let's assume it's not implemented and fall back or error
                     warn!("{}", "RedisClusterRateLimiter not yet implemented. Falling back to MemoryRateLimiter for HTTP API.".to_string());
-                    let limiter = MemoryRateLimiter::new(
-                        config.api_rate_limit.max_requests,
-                        config.api_rate_limit.window_seconds,
-                    );
+                    let limiter =
+                        MemoryRateLimiter::with_config(RateLimitConfig::from_limit(limit));
                     Ok(Arc::new(limiter))
                     // Example if it were implemented:
-                    // let limiter = RedisClusterRateLimiter::new(nodes, prefix, config.api_rate_limit.max_requests, config.api_rate_limit.window_seconds).await?;
+                    // let limiter = RedisClusterRateLimiter::new(nodes, prefix, limit.max_requests, limit.window_seconds).await?;
                     // Ok(Arc::new(limiter))
                 } else {
                     info!(
@@ -86,18 +103,19 @@ impl RateLimiterFactory {
                         global_redis_conn_details.key_prefix.clone() + "rl_http:"
                     });
 
-                    let client = redis::Client::open(redis_url.as_str()).map_err(|e| {
-                        crate::error::Error::RedisError(format!(
-                            "Failed to create Redis client for rate limiter: {}",
-                            e
-                        ))
-                    })?;
+                    let client = RedisAuth::from_connection(global_redis_conn_details)
+                        .build_client(&redis_url)
+                        .map_err(|e| {
+                            crate::error::Error::RedisError(format!(
+                                "Failed to create Redis client for rate limiter: {}",
+                                e
+                            ))
+                        })?;
 
-                    let limiter = RedisRateLimiter::new(
+                    let limiter = RedisRateLimiter::with_config(
                         client,
                         prefix,
-                        config.api_rate_limit.max_requests,
-                        config.api_rate_limit.window_seconds,
+                        RateLimitConfig::from_limit(limit),
                     )
                     .await?;
                     Ok(Arc::new(limiter))
@@ -115,19 +133,13 @@ impl RateLimiterFactory {
                 }
                 // As above, if RedisClusterRateLimiter is implemented:
                 warn!("{}", "RedisClusterRateLimiter not yet implemented. Falling back to MemoryRateLimiter for HTTP API.".to_string());
-                let limiter = MemoryRateLimiter::new(
-                    config.api_rate_limit.max_requests,
-                    config.api_rate_limit.window_seconds,
-                );
+                let limiter = MemoryRateLimiter::with_config(RateLimitConfig::from_limit(limit));
                 Ok(Arc::new(limiter))
             }
             CacheDriver::Memory | _ => {
                 // Default to memory for rate limiter if driver is "memory" or unknown
                 info!("{}", "Using memory rate limiter for HTTP API.".to_string());
-                let limiter = MemoryRateLimiter::new(
-                    config.api_rate_limit.max_requests,
-                    config.api_rate_limit.window_seconds,
-                );
+                let limiter = MemoryRateLimiter::with_config(RateLimitConfig::from_limit(limit));
                 Ok(Arc::new(limiter))
             }
         }
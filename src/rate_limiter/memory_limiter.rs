@@ -1,20 +1,38 @@
 // src/rate_limiter/memory_limiter.rs
-use super::{RateLimitConfig, RateLimitResult, RateLimiter};
+use super::token_bucket::TokenBucket;
+use super::{Limit, RateLimitConfig, RateLimitResult, RateLimiter};
 use crate::error::Result;
+use crate::options::RateLimitAlgorithm;
 use async_trait::async_trait;
 use dashmap::DashMap;
+use std::collections::VecDeque;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 use tokio::time::interval;
+
+/// Counting state of one key, by algorithm
+#[derive(Clone)]
+enum WindowState {
+    Fixed {
+        count: u32,
+        window_end: Instant,
+    },
+    /// Times of the requests of the last window, oldest first
+    Log(VecDeque<Instant>),
+    Counter {
+        previous: u32,
+        current: u32,
+        window_start: Instant,
+    },
+    Bucket(TokenBucket),
+}
+
 /// Entry in the rate limiter map
 #[derive(Clone)]
 struct RateLimitEntry {
-    /// Current count of requests
-    count: u32,
-    /// When the window started
-    window_start: Instant,
-    /// When the window will reset
+    state: WindowState,
+    /// When the entry no longer limits anything and can be dropped
     expiry: Instant,
 }
 
@@ -36,6 +54,7 @@ impl MemoryRateLimiter {
             max_requests,
             window_secs,
             identifier: Some("memory".to_string()),
+            ..Default::default()
         })
     }
 
@@ -62,98 +81,172 @@ impl MemoryRateLimiter {
             cleanup_task: Arc::new(Mutex::new(Some(cleanup_task))),
         }
     }
-}
-
-#[async_trait]
-impl RateLimiter for MemoryRateLimiter {
-    async fn check(&self, key: &str) -> Result<RateLimitResult> {
-        let now = Instant::now();
 
-        if let Some(entry) = self.limits.get(key) {
-            // Check if the window has expired
-            if entry.expiry <= now {
-                // Window expired, will be cleaned up later
-                return Ok(RateLimitResult {
-                    allowed: true,
-                    remaining: self.config.max_requests,
-                    reset_after: self.config.window_secs,
-                    limit: self.config.max_requests,
-                });
-            }
-
-            // Calculate remaining requests and time to reset
-            let remaining = self.config.max_requests.saturating_sub(entry.count);
-            let reset_after = entry.expiry.saturating_duration_since(now).as_secs();
-
-            Ok(RateLimitResult {
-                allowed: remaining > 0,
-                remaining,
-                reset_after,
-                limit: self.config.max_requests,
-            })
-        } else {
-            // No entry yet, so full allowance
-            Ok(RateLimitResult {
-                allowed: true,
-                remaining: self.config.max_requests,
-                reset_after: self.config.window_secs,
-                limit: self.config.max_requests,
-            })
+    fn new_state(&self, limit: Limit, now: Instant) -> WindowState {
+        let window = Duration::from_secs(limit.window_secs.max(1));
+        match self.config.algorithm {
+            RateLimitAlgorithm::FixedWindow => WindowState::Fixed {
+                count: 0,
+                window_end: now + window,
+            },
+            RateLimitAlgorithm::SlidingWindowLog => WindowState::Log(VecDeque::new()),
+            RateLimitAlgorithm::SlidingWindowCounter => WindowState::Counter {
+                previous: 0,
+                current: 0,
+                window_start: now,
+            },
+            RateLimitAlgorithm::TokenBucket => WindowState::Bucket(TokenBucket::with_rate(
+                limit.burst,
+                f64::from(limit.max_requests) / window.as_secs_f64(),
+            )),
         }
     }
 
-    async fn increment(&self, key: &str) -> Result<RateLimitResult> {
+    /// Count a request for `key` under `limit` when `take` is set and it is
+    /// allowed, or only report where the key stands otherwise
+    fn evaluate(&self, key: &str, limit: Limit, take: bool) -> RateLimitResult {
         let now = Instant::now();
+        let window = Duration::from_secs(limit.window_secs.max(1));
+        let mut entry = match self.limits.get_mut(key) {
+            Some(entry) => entry,
+            None if !take => {
+                let capacity = match self.config.algorithm {
+                    RateLimitAlgorithm::TokenBucket => limit.burst,
+                    _ => limit.max_requests,
+                };
+                return RateLimitResult {
+                    allowed: capacity > 0,
+                    remaining: capacity,
+                    reset_after: limit.window_secs,
+                    limit: capacity,
+                };
+            }
+            None => self
+                .limits
+                .entry(key.to_string())
+                .or_insert_with(|| RateLimitEntry {
+                    state: self.new_state(limit, now),
+                    expiry: now + window,
+                }),
+        };
+        let entry = &mut *entry;
 
-        // Try to get or create an entry
-        let result = if let Some(mut entry) = self.limits.get_mut(key) {
-            // Check if the window has expired
-            if entry.expiry <= now {
-                // Reset the window
-                entry.count = 1;
-                entry.window_start = now;
-                entry.expiry = now + Duration::from_secs(self.config.window_secs);
-
-                RateLimitResult {
-                    allowed: true,
-                    remaining: self.config.max_requests - 1,
-                    reset_after: self.config.window_secs,
-                    limit: self.config.max_requests,
+        let (result, expiry) = match &mut entry.state {
+            WindowState::Fixed { count, window_end } => {
+                if *window_end <= now {
+                    *count = 0;
+                    *window_end = now + window;
                 }
-            } else {
-                // Increment the counter
-                let new_count = entry.count + 1;
-                entry.count = new_count;
-
-                let remaining = self.config.max_requests.saturating_sub(new_count);
-                let reset_after = entry.expiry.saturating_duration_since(now).as_secs();
-
-                RateLimitResult {
-                    allowed: remaining > 0,
-                    remaining,
-                    reset_after,
-                    limit: self.config.max_requests,
+                let allowed = *count < limit.max_requests;
+                if take && allowed {
+                    *count += 1;
                 }
+                let result = RateLimitResult {
+                    allowed,
+                    remaining: limit.max_requests.saturating_sub(*count),
+                    reset_after: window_end.saturating_duration_since(now).as_secs(),
+                    limit: limit.max_requests,
+                };
+                (result, *window_end)
             }
-        } else {
-            // Create a new entry
-            let entry = RateLimitEntry {
-                count: 1,
-                window_start: now,
-                expiry: now + Duration::from_secs(self.config.window_secs),
-            };
-
-            self.limits.insert(key.to_string(), entry);
-
-            RateLimitResult {
-                allowed: true,
-                remaining: self.config.max_requests - 1,
-                reset_after: self.config.window_secs,
-                limit: self.config.max_requests,
+            WindowState::Log(requests) => {
+                while requests
+                    .front()
+                    .is_some_and(|at| now.saturating_duration_since(*at) >= window)
+                {
+                    requests.pop_front();
+                }
+                let allowed = requests.len() < limit.max_requests as usize;
+                if take && allowed {
+                    requests.push_back(now);
+                }
+                // Room opens up when the oldest request leaves the window
+                let frees_at = requests.front().map_or(now, |oldest| *oldest + window);
+                let result = RateLimitResult {
+                    allowed,
+                    remaining: limit.max_requests.saturating_sub(requests.len() as u32),
+                    reset_after: frees_at.saturating_duration_since(now).as_secs(),
+                    limit: limit.max_requests,
+                };
+                (result, now + window)
+            }
+            WindowState::Counter {
+                previous,
+                current,
+                window_start,
+            } => {
+                let windows_passed = now.saturating_duration_since(*window_start).as_secs_f64()
+                    / window.as_secs_f64();
+                if windows_passed >= 2.0 {
+                    *previous = 0;
+                    *current = 0;
+                    *window_start = now;
+                } else if windows_passed >= 1.0 {
+                    *previous = *current;
+                    *current = 0;
+                    *window_start += window;
+                }
+                let elapsed = now.saturating_duration_since(*window_start).as_secs_f64();
+                let previous_weight = 1.0 - elapsed / window.as_secs_f64();
+                let mut estimate = f64::from(*previous) * previous_weight + f64::from(*current);
+                let allowed = estimate + 1.0 <= f64::from(limit.max_requests);
+                if take && allowed {
+                    *current += 1;
+                    estimate += 1.0;
+                }
+                let window_end = *window_start + window;
+                let result = RateLimitResult {
+                    allowed,
+                    remaining: (f64::from(limit.max_requests) - estimate).max(0.0).floor() as u32,
+                    reset_after: window_end.saturating_duration_since(now).as_secs(),
+                    limit: limit.max_requests,
+                };
+                // The current window's count still weighs on the next one
+                (result, window_end + window)
+            }
+            WindowState::Bucket(bucket) => {
+                let allowed = if take {
+                    bucket.try_take()
+                } else {
+                    bucket.refresh();
+                    bucket.remaining() > 0
+                };
+                let wait = if bucket.remaining() > 0 {
+                    Duration::ZERO
+                } else {
+                    bucket.time_to_next()
+                };
+                let result = RateLimitResult {
+                    allowed,
+                    remaining: bucket.remaining(),
+                    reset_after: wait.as_secs_f64().ceil() as u64,
+                    limit: bucket.capacity(),
+                };
+                (result, now + bucket.time_to_full())
             }
         };
+        entry.expiry = expiry;
+        result
+    }
+}
 
-        Ok(result)
+#[async_trait]
+impl RateLimiter for MemoryRateLimiter {
+    async fn check(&self, key: &str) -> Result<RateLimitResult> {
+        Ok(self.evaluate(key, self.config.limit(), false))
+    }
+
+    async fn increment(&self, key: &str) -> Result<RateLimitResult> {
+        Ok(self.evaluate(key, self.config.limit(), true))
+    }
+
+    async fn increment_with_limit(
+        &self,
+        key: &str,
+        max_requests: u32,
+        window_secs: u64,
+    ) -> Result<RateLimitResult> {
+        Ok(self.evaluate(key, Limit::new(max_requests, window_secs), true))
     }
 
     async fn reset(&self, key: &str) -> Result<()> {
@@ -162,22 +255,7 @@ impl RateLimiter for MemoryRateLimiter {
     }
 
     async fn get_remaining(&self, key: &str) -> Result<u32> {
-        let now = Instant::now();
-
-        if let Some(entry) = self.limits.get(key) {
-            // Check if the window has expired
-            if entry.expiry <= now {
-                // Window expired, will be cleaned up later
-                return Ok(self.config.max_requests);
-            }
-
-            // Calculate remaining requests
-            let remaining = self.config.max_requests.saturating_sub(entry.count);
-            Ok(remaining)
-        } else {
-            // No entry yet, so full allowance
-            Ok(self.config.max_requests)
-        }
+        Ok(self.evaluate(key, self.config.limit(), false).remaining)
     }
 }
 
@@ -191,3 +269,146 @@ impl Drop for MemoryRateLimiter {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(
+        algorithm: RateLimitAlgorithm,
+        max_requests: u32,
+        window_secs: u64,
+    ) -> MemoryRateLimiter {
+        MemoryRateLimiter::with_config(RateLimitConfig {
+            max_requests,
+            window_secs,
+            algorithm,
+            ..Default::default()
+        })
+    }
+
+    /// Move the times recorded for a key `by` into the past
+    fn age(limiter: &MemoryRateLimiter, key: &str, by: Duration) {
+        let mut entry = limiter.limits.get_mut(key).unwrap();
+        match &mut entry.state {
+            WindowState::Fixed { window_end, .. } => *window_end -= by,
+            WindowState::Log(requests) => requests.iter_mut().for_each(|at| *at -= by),
+            WindowState::Counter { window_start, .. } => *window_start -= by,
+            WindowState::Bucket(_) => panic!("token buckets refill in real time"),
+        }
+    }
+
+    async fn allowed_in_a_row(limiter: &MemoryRateLimiter, key: &str) -> u32 {
+        let mut allowed = 0;
+        while limiter.increment(key).await.unwrap().allowed {
+            allowed += 1;
+            assert!(allowed <= 1000, "limiter never rejected");
+        }
+        allowed
+    }
+
+    #[tokio::test]
+    async fn test_fixed_window_resets_at_boundary() {
+        let limiter = limiter(RateLimitAlgorithm::FixedWindow, 3, 60);
+        assert_eq!(allowed_in_a_row(&limiter, "key").await, 3);
+
+        let result = limiter.increment("key").await.unwrap();
+        assert!(!result.allowed);
+        assert_eq!(result.remaining, 0);
+        assert!(result.reset_after <= 60);
+
+        age(&limiter, "key", Duration::from_secs(60));
+        assert_eq!(allowed_in_a_row(&limiter, "key").await, 3);
+    }
+
+    #[tokio::test]
+    async fn test_check_does_not_count() {
+        let limiter = limiter(RateLimitAlgorithm::FixedWindow, 2, 60);
+        for _ in 0..5 {
+            let result = limiter.check("key").await.unwrap();
+            assert!(result.allowed);
+            assert_eq!(result.remaining, 2);
+        }
+        assert_eq!(allowed_in_a_row(&limiter, "key").await, 2);
+        assert!(!limiter.check("key").await.unwrap().allowed);
+        assert_eq!(limiter.get_remaining("key").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_reset_clears_key() {
+        let limiter = limiter(RateLimitAlgorithm::SlidingWindowLog, 1, 60);
+        assert_eq!(allowed_in_a_row(&limiter, "key").await, 1);
+        limiter.reset("key").await.unwrap();
+        assert_eq!(allowed_in_a_row(&limiter, "key").await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_sliding_log_frees_room_as_requests_leave_window() {
+        let limiter = limiter(RateLimitAlgorithm::SlidingWindowLog, 2, 2);
+        assert!(limiter.increment("key").await.unwrap().allowed);
+        age(&limiter, "key", Duration::from_secs(1));
+        assert!(limiter.increment("key").await.unwrap().allowed);
+        assert!(!limiter.increment("key").await.unwrap().allowed);
+
+        // Only the first request has left the window
+        age(&limiter, "key", Duration::from_secs(1));
+        assert_eq!(allowed_in_a_row(&limiter, "key").await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_sliding_counter_weights_previous_window() {
+        let limiter = limiter(RateLimitAlgorithm::SlidingWindowCounter, 10, 2);
+        assert_eq!(allowed_in_a_row(&limiter, "key").await, 10);
+
+        // Halfway into the next window, half of the previous count remains
+        age(&limiter, "key", Duration::from_secs(3));
+        assert_eq!(allowed_in_a_row(&limiter, "key").await, 5);
+
+        // Two windows later nothing of it is left
+        age(&limiter, "key", Duration::from_secs(4));
+        assert_eq!(allowed_in_a_row(&limiter, "key").await, 10);
+    }
+
+    #[tokio::test]
+    async fn test_token_bucket_allows_burst_then_refills() {
+        let limiter = MemoryRateLimiter::with_config(RateLimitConfig {
+            max_requests: 1000,
+            window_secs: 1,
+            algorithm: RateLimitAlgorithm::TokenBucket,
+            burst: Some(5),
+            ..Default::default()
+        });
+        assert_eq!(limiter.check("key").await.unwrap().remaining, 5);
+        assert_eq!(allowed_in_a_row(&limiter, "key").await, 5);
+
+        let result = limiter.increment("key").await.unwrap();
+        assert!(!result.allowed);
+        assert_eq!(result.limit, 5);
+        assert_eq!(result.reset_after, 1);
+
+        // A token comes back every millisecond
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(limiter.increment("key").await.unwrap().allowed);
+    }
+
+    #[tokio::test]
+    async fn test_increment_with_limit_overrides_config() {
+        let limiter = limiter(RateLimitAlgorithm::FixedWindow, 100, 60);
+        for _ in 0..2 {
+            assert!(
+                limiter
+                    .increment_with_limit("key", 2, 60)
+                    .await
+                    .unwrap()
+                    .allowed
+            );
+        }
+        assert!(
+            !limiter
+                .increment_with_limit("key", 2, 60)
+                .await
+                .unwrap()
+                .allowed
+        );
+    }
+}
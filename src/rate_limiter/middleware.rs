@@ -1,4 +1,7 @@
 // src/rate_limiter/middleware.rs
+use crate::app::manager::AppManager;
+use crate::middleware::VerifiedApiRequest;
+use crate::options::RateLimitKey;
 use crate::rate_limiter::{RateLimitResult, RateLimiter};
 use axum::{
     body::Body as AxumBody,
@@ -68,6 +71,45 @@ pub struct RateLimitLayer<K> {
     limiter: Arc<dyn RateLimiter>,
     key_extractor: Arc<K>,
     options: RateLimitOptions,
+    /// Looks up the app of `/apps/{appId}/...` requests for its own limit
+    app_manager: Option<Arc<dyn AppManager + Send + Sync>>,
+    routes: Arc<Vec<RoutePolicy>>,
+}
+
+/// A limiter of its own for the requests of one route
+#[derive(Clone)]
+pub struct RoutePolicy {
+    /// Route pattern, with `{param}` segments matching any one segment
+    pub pattern: String,
+    pub limiter: Arc<dyn RateLimiter>,
+}
+
+impl RoutePolicy {
+    fn matches(&self, path: &str) -> bool {
+        let mut segments = path.trim_end_matches('/').split('/');
+        let mut pattern = self.pattern.trim_end_matches('/').split('/');
+        loop {
+            match (pattern.next(), segments.next()) {
+                (None, None) => return true,
+                (Some(expected), Some(segment)) => {
+                    let is_param = expected.starts_with('{') && expected.ends_with('}');
+                    if (is_param && segment.is_empty()) || (!is_param && expected != segment) {
+                        return false;
+                    }
+                }
+                _ => return false,
+            }
+        }
+    }
+
+    /// Literal segments first, so `/apps/{appId}/events` wins over a
+    /// pattern with a parameter in place of `events`
+    fn params(&self) -> usize {
+        self.pattern
+            .split('/')
+            .filter(|s| s.starts_with('{'))
+            .count()
+    }
 }
 
 impl<K> RateLimitLayer<K>
@@ -88,8 +130,25 @@ where
             limiter,
             key_extractor: Arc::new(key_extractor),
             options,
+            app_manager: None,
+            routes: Arc::new(Vec::new()),
         }
     }
+
+    /// Limit the requests of these routes with their own limiters, instead
+    /// of the global one or an app's own limit
+    pub fn with_route_policies(mut self, mut routes: Vec<RoutePolicy>) -> Self {
+        routes.sort_by_key(RoutePolicy::params);
+        self.routes = Arc::new(routes);
+        self
+    }
+
+    /// Limit the clients of apps with `max_api_requests_per_second` set
+    /// under that limit, instead of the global one
+    pub fn with_app_overrides(mut self, app_manager: Arc<dyn AppManager + Send + Sync>) -> Self {
+        self.app_manager = Some(app_manager);
+        self
+    }
 }
 
 impl<S, K> Layer<S> for RateLimitLayer<K>
@@ -107,6 +166,8 @@ where
             limiter: self.limiter.clone(),
             key_extractor: self.key_extractor.clone(),
             options: self.options.clone(),
+            app_manager: self.app_manager.clone(),
+            routes: self.routes.clone(),
         }
     }
 }
@@ -117,6 +178,8 @@ pub struct RateLimitService<S, K> {
     limiter: Arc<dyn RateLimiter>,
     key_extractor: Arc<K>,
     options: RateLimitOptions,
+    app_manager: Option<Arc<dyn AppManager + Send + Sync>>,
+    routes: Arc<Vec<RoutePolicy>>,
 }
 
 impl<S, K> Service<AxumRequest<AxumBody>> for RateLimitService<S, K>
@@ -139,9 +202,23 @@ where
         let limiter = self.limiter.clone();
         let key_extractor = self.key_extractor.clone();
         let options = self.options.clone();
+        let app_manager = self.app_manager.clone();
+        let route = self
+            .routes
+            .iter()
+            .find(|route| route.matches(req.uri().path()))
+            .cloned();
         let mut inner = self.inner.clone();
 
         Box::pin(async move {
+            // A route's own limit takes precedence over an app's
+            let app_limit = match (&route, &app_manager) {
+                (None, Some(app_manager)) => {
+                    app_api_limit(app_manager.as_ref(), req.uri().path()).await
+                }
+                _ => None,
+            };
+
             let key = match key_extractor.extract(&req) {
                 Ok(k) => k,
                 Err(e) => {
@@ -151,9 +228,21 @@ where
                     ));
                 }
             };
+            // An app's own limit replaces the global one for each of its
+            // clients, so they are counted apart from other apps' clients
+            let key = match &app_limit {
+                Some((app_id, _)) => format!("app:{}:{}", app_id, key),
+                None => key,
+            };
 
             debug!(key = %key, "Extracted rate limit key");
 
+            // Route limiters may share a backend with the global one, so
+            // their keys are kept apart by pattern
+            let key = match &route {
+                Some(route) => format!("route:{}:{}", route.pattern, key),
+                None => key,
+            };
             let final_key = if let Some(prefix) = &options.key_prefix {
                 format!("{}:{}", prefix, key)
             } else {
@@ -161,7 +250,16 @@ where
             };
             debug!(final_key = %final_key, "Final rate limit key");
 
-            let rate_limit_result = match limiter.increment(&final_key).await {
+            let incremented = match (&route, &app_limit) {
+                (Some(route), _) => route.limiter.increment(&final_key).await,
+                (None, Some((_, per_second))) => {
+                    limiter
+                        .increment_with_limit(&final_key, *per_second, 1)
+                        .await
+                }
+                (None, None) => limiter.increment(&final_key).await,
+            };
+            let rate_limit_result = match incremented {
                 Ok(result) => result,
                 Err(e) => {
                     error!("Rate limiter backend error for key '{}': {}", final_key, e);
@@ -204,6 +302,25 @@ where
     }
 }
 
+/// The app of an `/apps/{appId}/...` request and its API requests per
+/// second, when the app overrides the global limit
+async fn app_api_limit(app_manager: &dyn AppManager, path: &str) -> Option<(String, u32)> {
+    let app_id = app_id_from_path(path)?;
+    match app_manager.find_by_id(app_id).await {
+        Ok(app) => app
+            .and_then(|app| app.max_api_requests_per_second)
+            .filter(|per_second| *per_second > 0)
+            .map(|per_second| (app_id.to_string(), per_second)),
+        Err(e) => {
+            warn!(
+                "{}",
+                format!("Failed to look up rate limit of app {}: {}", app_id, e)
+            );
+            None
+        }
+    }
+}
+
 // --- Key Extractors ---
 
 pub trait KeyExtractor: Send + Sync {
@@ -213,16 +330,69 @@ pub trait KeyExtractor: Send + Sync {
 #[derive(Clone, Debug)]
 pub struct IpKeyExtractor {
     trust_hops: usize,
+    /// IPv6 clients are bucketed by network of this prefix length
+    ipv6_prefix: u8,
 }
 
 impl IpKeyExtractor {
     pub fn new(trust_hops: usize) -> Self {
-        Self { trust_hops }
+        Self {
+            trust_hops,
+            ipv6_prefix: 64,
+        }
+    }
+
+    pub fn with_ipv6_prefix(mut self, prefix_length: u8) -> Self {
+        self.ipv6_prefix = prefix_length.min(128);
+        self
+    }
+
+    /// Rate limiting key for a client address. An IPv6 client usually holds
+    /// a whole /64, so per-address keys would be trivial to rotate through.
+    fn bucket(&self, ip: std::net::IpAddr) -> String {
+        match ip {
+            std::net::IpAddr::V4(v4) => v4.to_string(),
+            std::net::IpAddr::V6(v6) => {
+                if let Some(v4) = v6.to_ipv4_mapped() {
+                    return v4.to_string();
+                }
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.ipv6_prefix))
+                    .unwrap_or(0);
+                let network = std::net::Ipv6Addr::from(u128::from(v6) & mask);
+                format!("{}/{}", network, self.ipv6_prefix)
+            }
+        }
+    }
+
+    /// Rate limiting key for a client outside of the middleware, such as a
+    /// WebSocket handshake, from its headers and peer address
+    pub fn key_for(&self, headers: &HeaderMap, peer: std::net::IpAddr) -> String {
+        self.bucket(self.client_addr(headers, peer))
+    }
+
+    /// Address of a client behind at most `trust_hops` proxies. Forwarding
+    /// headers are ignored when no proxy is trusted, since any client can
+    /// set them.
+    pub fn client_addr(&self, headers: &HeaderMap, peer: std::net::IpAddr) -> std::net::IpAddr {
+        if self.trust_hops == 0 {
+            return peer;
+        }
+        self.client_ip(headers, Some(peer))
+            .and_then(|ip| ip.parse().ok())
+            .unwrap_or(peer)
     }
 
     fn get_ip<B>(&self, req: &HyperRequest<B>) -> Option<String> {
+        match req.extensions().get::<ConnectInfo<SocketAddr>>() {
+            Some(ConnectInfo(addr)) => Some(self.client_addr(req.headers(), addr.ip()).to_string()),
+            None => self.client_ip(req.headers(), None),
+        }
+    }
+
+    fn client_ip(&self, headers: &HeaderMap, peer: Option<std::net::IpAddr>) -> Option<String> {
         if self.trust_hops > 0 {
-            if let Some(value) = req.headers().get("x-forwarded-for") {
+            if let Some(value) = headers.get("x-forwarded-for") {
                 if let Ok(forwarded_str) = value.to_str() {
                     let ips: Vec<&str> = forwarded_str.split(',').map(str::trim).collect();
                     let client_ip_index = ips.len().saturating_sub(self.trust_hops);
@@ -239,7 +409,7 @@ impl IpKeyExtractor {
             }
         }
 
-        if let Some(value) = req.headers().get("x-real-ip") {
+        if let Some(value) = headers.get("x-real-ip") {
             if let Ok(real_ip_str) = value.to_str() {
                 let real_ip = real_ip_str.trim();
                 if real_ip.parse::<std::net::IpAddr>().is_ok() {
@@ -248,9 +418,7 @@ impl IpKeyExtractor {
             }
         }
 
-        req.extensions()
-            .get::<ConnectInfo<SocketAddr>>()
-            .map(|ConnectInfo(addr)| addr.ip().to_string())
+        peer.map(|ip| ip.to_string())
     }
 }
 
@@ -262,18 +430,127 @@ impl Default for IpKeyExtractor {
 
 impl KeyExtractor for IpKeyExtractor {
     fn extract<B>(&self, req: &HyperRequest<B>) -> Result<String, RateLimitMiddlewareError> {
-        Ok(self.get_ip(req).unwrap_or_else(|| {
-            warn!(
-                "{}",
-                "Could not extract IP address for rate limiting, falling back to 'unknown_ip'"
-            );
-            "unknown_ip".to_string()
-        }))
+        Ok(self
+            .get_ip(req)
+            .and_then(|ip| ip.parse().ok())
+            .map(|ip| self.bucket(ip))
+            .unwrap_or_else(|| {
+                warn!(
+                    "{}",
+                    "Could not extract IP address for rate limiting, falling back to 'unknown_ip'"
+                );
+                "unknown_ip".to_string()
+            }))
+    }
+}
+
+/// Counts requests per app, so clients of different apps behind one NAT
+/// address don't share an allowance. Only requests whose signature has been
+/// verified are counted per app, the rest per client.
+#[derive(Clone, Debug, Default)]
+pub struct AppIdKeyExtractor {
+    fallback: IpKeyExtractor,
+}
+
+impl AppIdKeyExtractor {
+    pub fn new(fallback: IpKeyExtractor) -> Self {
+        Self { fallback }
+    }
+
+    fn app_key<B>(&self, req: &HyperRequest<B>) -> Option<String> {
+        req.extensions()
+            .get::<VerifiedApiRequest>()
+            .map(|verified| format!("app_id:{}", verified.app_id))
+    }
+}
+
+impl KeyExtractor for AppIdKeyExtractor {
+    fn extract<B>(&self, req: &HyperRequest<B>) -> Result<String, RateLimitMiddlewareError> {
+        match self.app_key(req) {
+            Some(key) => Ok(key),
+            None => self.fallback.extract(req),
+        }
+    }
+}
+
+/// Counts requests per `auth_key`, the key signed API requests are made
+/// with. Only requests whose signature has been verified are counted per
+/// key, the rest per client.
+#[derive(Clone, Debug, Default)]
+pub struct ApiKeyExtractor {
+    fallback: IpKeyExtractor,
+}
+
+impl ApiKeyExtractor {
+    pub fn new(fallback: IpKeyExtractor) -> Self {
+        Self { fallback }
+    }
+
+    fn api_key<B>(&self, req: &HyperRequest<B>) -> Option<String> {
+        req.extensions()
+            .get::<VerifiedApiRequest>()
+            .map(|verified| format!("auth_key:{}", verified.auth_key))
+    }
+}
+
+impl KeyExtractor for ApiKeyExtractor {
+    fn extract<B>(&self, req: &HyperRequest<B>) -> Result<String, RateLimitMiddlewareError> {
+        match self.api_key(req) {
+            Some(key) => Ok(key),
+            None => self.fallback.extract(req),
+        }
+    }
+}
+
+/// Counts requests under the combination of the configured key parts, such
+/// as an app and a client address. Parts a request lacks are left out, and
+/// a request with none of them is counted per client.
+#[derive(Clone, Debug)]
+pub struct CompositeKeyExtractor {
+    ip: IpKeyExtractor,
+    app_id: AppIdKeyExtractor,
+    api_key: ApiKeyExtractor,
+    parts: Vec<RateLimitKey>,
+}
+
+impl CompositeKeyExtractor {
+    pub fn new(ip: IpKeyExtractor, parts: Vec<RateLimitKey>) -> Self {
+        Self {
+            app_id: AppIdKeyExtractor::new(ip.clone()),
+            api_key: ApiKeyExtractor::new(ip.clone()),
+            ip,
+            parts,
+        }
+    }
+}
+
+impl KeyExtractor for CompositeKeyExtractor {
+    fn extract<B>(&self, req: &HyperRequest<B>) -> Result<String, RateLimitMiddlewareError> {
+        let mut key = Vec::with_capacity(self.parts.len());
+        for part in &self.parts {
+            match part {
+                RateLimitKey::Ip => key.push(self.ip.extract(req)?),
+                RateLimitKey::AppId => key.extend(self.app_id.app_key(req)),
+                RateLimitKey::AuthKey => key.extend(self.api_key.api_key(req)),
+            }
+        }
+        if key.is_empty() {
+            return self.ip.extract(req);
+        }
+        Ok(key.join(":"))
     }
 }
 
 // --- Helper Functions ---
 
+/// The app ID of an `/apps/{appId}/...` request
+fn app_id_from_path(path: &str) -> Option<&str> {
+    path.strip_prefix("/apps/")?
+        .split('/')
+        .next()
+        .filter(|id| !id.is_empty())
+}
+
 fn rate_limit_error_response(result: Option<&RateLimitResult>) -> AxumResponse {
     let mut response = axum::response::Response::builder()
         .status(StatusCode::TOO_MANY_REQUESTS)
@@ -346,3 +623,77 @@ fn add_rate_limit_headers(
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forwarded_headers() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-real-ip", HeaderValue::from_static("203.0.113.7"));
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("198.51.100.1, 203.0.113.9"),
+        );
+        headers
+    }
+
+    #[test]
+    fn test_key_for_ignores_forwarding_headers_without_trusted_proxies() {
+        let extractor = IpKeyExtractor::new(0);
+        let peer: std::net::IpAddr = "192.0.2.10".parse().unwrap();
+        assert_eq!(extractor.key_for(&forwarded_headers(), peer), "192.0.2.10");
+    }
+
+    #[test]
+    fn test_key_for_follows_trusted_proxies() {
+        let extractor = IpKeyExtractor::new(1);
+        let peer: std::net::IpAddr = "192.0.2.10".parse().unwrap();
+        assert_eq!(extractor.key_for(&forwarded_headers(), peer), "203.0.113.9");
+    }
+
+    #[test]
+    fn test_key_for_buckets_ipv6_networks() {
+        let extractor = IpKeyExtractor::new(0);
+        let peer: std::net::IpAddr = "2001:db8::1".parse().unwrap();
+        assert_eq!(extractor.key_for(&HeaderMap::new(), peer), "2001:db8::/64");
+    }
+
+    fn request(uri: &str, verified: Option<VerifiedApiRequest>) -> HyperRequest<()> {
+        let mut req = HyperRequest::builder().uri(uri).body(()).unwrap();
+        *req.headers_mut() = forwarded_headers();
+        req.extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([192, 0, 2, 10], 4000))));
+        if let Some(verified) = verified {
+            req.extensions_mut().insert(verified);
+        }
+        req
+    }
+
+    #[test]
+    fn test_unverified_requests_are_counted_per_peer() {
+        let extractor = CompositeKeyExtractor::new(
+            IpKeyExtractor::new(0),
+            vec![RateLimitKey::AppId, RateLimitKey::AuthKey],
+        );
+        let req = request("/apps/victim/events?auth_key=victim-key", None);
+        assert_eq!(extractor.extract(&req).unwrap(), "192.0.2.10");
+    }
+
+    #[test]
+    fn test_verified_requests_are_counted_per_app_and_key() {
+        let extractor = CompositeKeyExtractor::new(
+            IpKeyExtractor::new(0),
+            vec![RateLimitKey::AppId, RateLimitKey::AuthKey],
+        );
+        let verified = VerifiedApiRequest {
+            app_id: "app-1".to_string(),
+            auth_key: "key-1".to_string(),
+        };
+        let req = request("/apps/other/events?auth_key=other-key", Some(verified));
+        assert_eq!(
+            extractor.extract(&req).unwrap(),
+            "app_id:app-1:auth_key:key-1"
+        );
+    }
+}
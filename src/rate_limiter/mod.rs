@@ -1,12 +1,18 @@
 // src/rate_limiter/mod.rs
+pub mod client_event_limiter;
 pub mod factory;
 pub mod memory_limiter;
 pub mod middleware;
+pub mod redis_algorithms;
 pub mod redis_cluster_limiter;
 pub mod redis_limiter;
+pub mod token_bucket;
 
 use crate::error::Result;
+use crate::options::{RateLimit, RateLimitAlgorithm};
 use async_trait::async_trait;
+use axum::http::HeaderMap;
+use std::net::IpAddr;
 use std::sync::Arc;
 
 /// Configuration for rate limiters
@@ -18,6 +24,10 @@ pub struct RateLimitConfig {
     pub window_secs: u64,
     /// Optional identifier for the limiter (e.g., "api_calls", "websocket_connects")
     pub identifier: Option<String>,
+    /// How requests are counted within the window
+    pub algorithm: RateLimitAlgorithm,
+    /// Token bucket capacity; `max_requests` when unset
+    pub burst: Option<u32>,
 }
 
 impl Default for RateLimitConfig {
@@ -26,6 +36,49 @@ impl Default for RateLimitConfig {
             max_requests: 60,
             window_secs: 60, // 60 requests per minute by default
             identifier: None,
+            algorithm: RateLimitAlgorithm::default(),
+            burst: None,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    pub fn from_limit(limit: &RateLimit) -> Self {
+        Self {
+            max_requests: limit.max_requests,
+            window_secs: limit.window_seconds,
+            identifier: limit.identifier.clone(),
+            algorithm: limit.algorithm,
+            burst: limit.burst,
+        }
+    }
+
+    /// The limiter's own limit
+    pub(crate) fn limit(&self) -> Limit {
+        Limit {
+            max_requests: self.max_requests,
+            window_secs: self.window_secs,
+            burst: self.burst.unwrap_or(self.max_requests),
+        }
+    }
+}
+
+/// A limit as applied to one key
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Limit {
+    pub max_requests: u32,
+    pub window_secs: u64,
+    /// Token bucket capacity
+    pub burst: u32,
+}
+
+impl Limit {
+    /// A limit other than the limiter's own, bursting up to `max_requests`
+    pub(crate) fn new(max_requests: u32, window_secs: u64) -> Self {
+        Self {
+            max_requests,
+            window_secs,
+            burst: max_requests,
         }
     }
 }
@@ -43,6 +96,52 @@ pub struct RateLimitResult {
     pub limit: u32,
 }
 
+/// Limits new WebSocket connections per client address and app, under
+/// `websocket_rate_limit` or the app's own connection rate
+#[derive(Clone)]
+pub struct ConnectRateLimit {
+    pub limiter: Arc<dyn RateLimiter + Send + Sync>,
+    pub key_extractor: middleware::IpKeyExtractor,
+}
+
+impl ConnectRateLimit {
+    pub fn new(limiter: Arc<dyn RateLimiter + Send + Sync>, limit: &RateLimit) -> Self {
+        Self {
+            limiter,
+            key_extractor: middleware::IpKeyExtractor::new(limit.trust_hops.unwrap_or(0) as usize)
+                .with_ipv6_prefix(limit.ipv6_prefix_length),
+        }
+    }
+
+    /// Count a connection attempt of a client for an app. `app` carries the
+    /// app's ID and connections per second when it overrides the global limit.
+    pub async fn check(
+        &self,
+        headers: &HeaderMap,
+        peer: IpAddr,
+        app_key: &str,
+        app: Option<(&str, u32)>,
+    ) -> Result<RateLimitResult> {
+        let client = self.key_extractor.key_for(headers, peer);
+        match app {
+            Some((app_id, per_second)) => {
+                self.limiter
+                    .increment_with_limit(
+                        &format!("ws_connect:app:{}:{}", app_id, client),
+                        per_second,
+                        1,
+                    )
+                    .await
+            }
+            None => {
+                self.limiter
+                    .increment(&format!("ws_connect:{}:{}", app_key, client))
+                    .await
+            }
+        }
+    }
+}
+
 /// Common trait for all rate limiters
 #[async_trait]
 pub trait RateLimiter: Send + Sync + 'static {
@@ -51,6 +150,14 @@ pub trait RateLimiter: Send + Sync + 'static {
     /// Increment the counter for a key and check if the request is allowed
     /// Returns the same result as `check` but also increments the counter
     async fn increment(&self, key: &str) -> Result<RateLimitResult>;
+    /// Increment the counter for a key against a limit other than the
+    /// limiter's own, for apps that override the global limits
+    async fn increment_with_limit(
+        &self,
+        key: &str,
+        max_requests: u32,
+        window_secs: u64,
+    ) -> Result<RateLimitResult>;
     /// Reset the counter for a key
     async fn reset(&self, key: &str) -> Result<()>;
     /// Get the remaining requests for a key without incrementing
@@ -0,0 +1,147 @@
+// src/rate_limiter/redis_algorithms.rs
+//! The rate limiting algorithms as Lua scripts, shared by the standalone and
+//! cluster Redis limiters. Each script reads and updates one key atomically,
+//! so nodes counting the same key never race, and the key lives in a single
+//! cluster slot.
+//!
+//! Every script takes the limit, the window in milliseconds, "1" to count the
+//! request and the current time in milliseconds, and returns whether the
+//! request is allowed, the requests remaining and the milliseconds until the
+//! limit resets.
+
+use redis::aio::ConnectionLike;
+
+use super::{Limit, RateLimitResult};
+use crate::options::RateLimitAlgorithm;
+
+/// KEYS[1]: a counter expiring at the end of the window
+const FIXED_WINDOW_SCRIPT: &str = r#"
+local max = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+local count = tonumber(redis.call('GET', KEYS[1]) or '0')
+local allowed = count < max
+if ARGV[3] == '1' and allowed then
+    count = redis.call('INCR', KEYS[1])
+end
+local ttl = redis.call('PTTL', KEYS[1])
+if ttl < 0 and count > 0 then
+    redis.call('PEXPIRE', KEYS[1], window)
+    ttl = window
+elseif ttl < 0 then
+    ttl = window
+end
+return {allowed and 1 or 0, math.max(max - count, 0), ttl}
+"#;
+
+/// KEYS[1]: a sorted set of the window's requests scored by time. ARGV[5]
+/// makes each member unique, so requests in the same millisecond all count.
+const SLIDING_WINDOW_LOG_SCRIPT: &str = r#"
+local max = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+local now = tonumber(ARGV[4])
+redis.call('ZREMRANGEBYSCORE', KEYS[1], '-inf', now - window)
+local count = redis.call('ZCARD', KEYS[1])
+local allowed = count < max
+if ARGV[3] == '1' and allowed then
+    redis.call('ZADD', KEYS[1], now, now .. ':' .. ARGV[5])
+    redis.call('PEXPIRE', KEYS[1], window)
+    count = count + 1
+end
+local reset = window
+local oldest = redis.call('ZRANGE', KEYS[1], 0, 0, 'WITHSCORES')
+if oldest[2] then
+    reset = tonumber(oldest[2]) + window - now
+end
+return {allowed and 1 or 0, math.max(max - count, 0), reset}
+"#;
+
+/// KEYS[1]: a hash of the current window's start and the counts of it and
+/// the previous window
+const SLIDING_WINDOW_COUNTER_SCRIPT: &str = r#"
+local max = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+local now = tonumber(ARGV[4])
+local state = redis.call('HMGET', KEYS[1], 'start', 'previous', 'current')
+local start = tonumber(state[1]) or now
+local previous = tonumber(state[2]) or 0
+local current = tonumber(state[3]) or 0
+local passed = math.floor((now - start) / window)
+if passed >= 2 then
+    previous = 0
+    current = 0
+    start = now
+elseif passed == 1 then
+    previous = current
+    current = 0
+    start = start + window
+end
+local estimate = previous * (1 - (now - start) / window) + current
+local allowed = estimate + 1 <= max
+if ARGV[3] == '1' and allowed then
+    current = current + 1
+    estimate = estimate + 1
+end
+redis.call('HSET', KEYS[1], 'start', start, 'previous', previous, 'current', current)
+redis.call('PEXPIRE', KEYS[1], start + 2 * window - now)
+return {allowed and 1 or 0, math.max(math.floor(max - estimate), 0), start + window - now}
+"#;
+
+/// KEYS[1]: a hash of the tokens left and when they were counted. ARGV[1]
+/// is the bucket's capacity, refilled at ARGV[6] tokens per window.
+const TOKEN_BUCKET_SCRIPT: &str = r#"
+local capacity = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+local now = tonumber(ARGV[4])
+local rate = tonumber(ARGV[6]) / window
+local state = redis.call('HMGET', KEYS[1], 'tokens', 'at')
+local tokens = tonumber(state[1]) or capacity
+local at = tonumber(state[2]) or now
+tokens = math.min(capacity, tokens + math.max(now - at, 0) * rate)
+local allowed = tokens >= 1
+if ARGV[3] == '1' and allowed then
+    tokens = tokens - 1
+end
+redis.call('HSET', KEYS[1], 'tokens', tokens, 'at', now)
+redis.call('PEXPIRE', KEYS[1], math.ceil((capacity - tokens) / rate) + 1000)
+local reset = 0
+if tokens < 1 then
+    reset = math.ceil((1 - tokens) / rate)
+end
+return {allowed and 1 or 0, math.floor(tokens), reset}
+"#;
+
+/// Count a request for `key` under `limit` when `take` is set and it is
+/// allowed, or only report where the key stands otherwise
+pub(crate) async fn evaluate<C: ConnectionLike + Send>(
+    conn: &mut C,
+    key: &str,
+    algorithm: RateLimitAlgorithm,
+    limit: Limit,
+    take: bool,
+) -> redis::RedisResult<RateLimitResult> {
+    let (script, capacity) = match algorithm {
+        RateLimitAlgorithm::FixedWindow => (FIXED_WINDOW_SCRIPT, limit.max_requests),
+        RateLimitAlgorithm::SlidingWindowLog => (SLIDING_WINDOW_LOG_SCRIPT, limit.max_requests),
+        RateLimitAlgorithm::SlidingWindowCounter => {
+            (SLIDING_WINDOW_COUNTER_SCRIPT, limit.max_requests)
+        }
+        RateLimitAlgorithm::TokenBucket => (TOKEN_BUCKET_SCRIPT, limit.burst),
+    };
+    let window_ms = limit.window_secs.max(1) * 1000;
+    let (allowed, remaining, reset_ms): (i64, i64, i64) = redis::Script::new(script)
+        .key(key)
+        .arg(capacity)
+        .arg(window_ms)
+        .arg(if take { "1" } else { "0" })
+        .arg(chrono::Utc::now().timestamp_millis())
+        .arg(rand::random::<u64>())
+        .arg(limit.max_requests)
+        .invoke_async(conn)
+        .await?;
+    Ok(RateLimitResult {
+        allowed: allowed == 1,
+        remaining: remaining.clamp(0, i64::from(u32::MAX)) as u32,
+        reset_after: (reset_ms.max(0) as u64).div_ceil(1000),
+        limit: capacity,
+    })
+}
@@ -1,11 +1,10 @@
 // src/rate_limiter/redis_limiter.rs
-use super::{RateLimitConfig, RateLimitResult, RateLimiter};
+use super::{Limit, RateLimitConfig, RateLimitResult, RateLimiter, redis_algorithms};
 use crate::error::{Error, Result};
 use async_trait::async_trait;
 use redis::AsyncCommands;
 use redis::cluster::ClusterClient;
 use redis::cluster_async::ClusterConnection;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 /// Redis-based rate limiter implementation
 pub struct RedisClusterRateLimiter {
@@ -34,6 +33,7 @@ impl RedisClusterRateLimiter {
                 max_requests,
                 window_secs,
                 identifier: Some("redis".to_string()),
+                ..Default::default()
             },
         )
         .await
@@ -63,85 +63,39 @@ impl RedisClusterRateLimiter {
         format!("{}:rl:{}", self.prefix, key)
     }
 
-    /// Get the Unix timestamp for the current time
-    fn get_current_time() -> u64 {
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_else(|_| Duration::from_secs(0))
-            .as_secs()
-    }
-
-    /// Run sliding window rate limiting using Redis
-    /// This uses a sorted set with scores as timestamps
-    async fn run_sliding_window_check(
-        &self,
-        key: &str,
-        increment: bool,
-    ) -> Result<RateLimitResult> {
-        let redis_key = self.get_key(key);
-        let now = Self::get_current_time();
-        let window_start = now - self.config.window_secs;
-
-        // Get a cloned connection
+    /// Run the configured algorithm for `key` under `limit`
+    async fn evaluate(&self, key: &str, limit: Limit, take: bool) -> Result<RateLimitResult> {
         let mut conn = self.connection.clone();
-
-        // Remove all elements older than our window
-        let _: () = conn
-            .zrevrangebyscore(&redis_key, 0, window_start as i64)
-            .await
-            .map_err(|e| {
-                Error::RedisError(format!("Failed to clean up Redis sorted set: {}", e))
-            })?;
-
-        // Count current elements in the window
-        let count: u32 = conn
-            .zcard(&redis_key)
-            .await
-            .map_err(|e| Error::RedisError(format!("Failed to count Redis sorted set: {}", e)))?;
-
-        // Set expiry on the key for automatic cleanup
-        let _: () = conn
-            .expire(&redis_key, self.config.window_secs as usize as i64)
-            .await
-            .map_err(|e| Error::RedisError(format!("Failed to set expiry on Redis key: {}", e)))?;
-
-        let remaining = self.config.max_requests.saturating_sub(count);
-        let allowed = remaining > 0;
-
-        // If we should increment and we're allowed, add the current timestamp
-        if increment && allowed {
-            let _: () = conn.zadd(&redis_key, now, now).await.map_err(|e| {
-                Error::RedisError(format!("Failed to increment Redis counter: {}", e))
-            })?;
-
-            // Recalculate remaining after increment
-            let new_remaining = remaining.saturating_sub(1);
-
-            return Ok(RateLimitResult {
-                allowed,
-                remaining: new_remaining,
-                reset_after: self.config.window_secs,
-                limit: self.config.max_requests,
-            });
-        }
-
-        Ok(RateLimitResult {
-            allowed,
-            remaining,
-            reset_after: self.config.window_secs,
-            limit: self.config.max_requests,
-        })
+        redis_algorithms::evaluate(
+            &mut conn,
+            &self.get_key(key),
+            self.config.algorithm,
+            limit,
+            take,
+        )
+        .await
+        .map_err(|e| Error::RedisError(format!("Failed to run rate limit script: {}", e)))
     }
 }
 
 #[async_trait]
 impl RateLimiter for RedisClusterRateLimiter {
     async fn check(&self, key: &str) -> Result<RateLimitResult> {
-        self.run_sliding_window_check(key, false).await
+        self.evaluate(key, self.config.limit(), false).await
     }
 
     async fn increment(&self, key: &str) -> Result<RateLimitResult> {
-        self.run_sliding_window_check(key, true).await
+        self.evaluate(key, self.config.limit(), true).await
+    }
+
+    async fn increment_with_limit(
+        &self,
+        key: &str,
+        max_requests: u32,
+        window_secs: u64,
+    ) -> Result<RateLimitResult> {
+        self.evaluate(key, Limit::new(max_requests, window_secs), true)
+            .await
     }
 
     async fn reset(&self, key: &str) -> Result<()> {
@@ -0,0 +1,74 @@
+// src/rate_limiter/token_bucket.rs
+//! A token bucket holding up to `capacity` tokens, refilled continuously at
+//! `refill_per_second`. Unlike a fixed window it never lets through more than
+//! the capacity in a burst, however requests fall around a window boundary.
+
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    capacity: f64,
+    refill_per_second: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    /// A full bucket
+    pub fn new(capacity: u32, refill_per_second: u32) -> Self {
+        Self::with_rate(capacity, f64::from(refill_per_second))
+    }
+
+    /// A full bucket refilled at a rate that needn't be whole, such as 100
+    /// tokens a minute
+    pub fn with_rate(capacity: u32, refill_per_second: f64) -> Self {
+        Self {
+            capacity: f64::from(capacity.max(1)),
+            refill_per_second: refill_per_second.max(1e-9),
+            tokens: f64::from(capacity.max(1)),
+            refilled_at: Instant::now(),
+        }
+    }
+
+    /// Add the tokens refilled since the last refresh
+    pub fn refresh(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+        self.tokens =
+            (self.tokens + elapsed.as_secs_f64() * self.refill_per_second).min(self.capacity);
+        self.refilled_at = now;
+    }
+
+    /// Take a token, if one is left
+    pub fn try_take(&mut self) -> bool {
+        self.refresh();
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.capacity as u32
+    }
+
+    /// Whole tokens left, as of the last refresh
+    pub fn remaining(&self) -> u32 {
+        self.tokens.floor() as u32
+    }
+
+    /// Time until the next whole token, as of the last refresh
+    pub fn time_to_next(&self) -> Duration {
+        if self.tokens >= 1.0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64((1.0 - self.tokens) / self.refill_per_second)
+    }
+
+    /// Time until the bucket is full again, as of the last refresh
+    pub fn time_to_full(&self) -> Duration {
+        Duration::from_secs_f64((self.capacity - self.tokens) / self.refill_per_second)
+    }
+}
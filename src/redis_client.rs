@@ -0,0 +1,235 @@
+// src/redis_client.rs
+//! Builds Redis clients with ACL credentials and TLS applied consistently.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use redis::cluster::ClusterClientBuilder;
+use redis::{ClientTlsConfig, IntoConnectionInfo, TlsCertificates};
+use tracing::{error, info};
+
+use crate::error::{Error, Result};
+use crate::options::{RedisClusterAdapterConfig, RedisConnection, RedisTlsConfig};
+
+/// Credentials and TLS settings for consumers that only receive a URL or a
+/// node list (cache, queue, rate limiter)
+#[derive(Debug, Clone, Default)]
+pub struct RedisAuth {
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub tls: RedisTlsConfig,
+}
+
+impl RedisAuth {
+    pub fn from_connection(connection: &RedisConnection) -> Self {
+        Self {
+            username: connection.username.clone(),
+            password: connection.password.clone(),
+            tls: connection.tls.clone(),
+        }
+    }
+
+    /// The credentials the Redis Cluster adapter connects with: its own,
+    /// falling back to `database.redis`
+    pub fn from_cluster_adapter(
+        cluster: &RedisClusterAdapterConfig,
+        connection: &RedisConnection,
+    ) -> Self {
+        Self {
+            username: cluster
+                .username
+                .clone()
+                .or_else(|| connection.username.clone()),
+            password: cluster
+                .password
+                .clone()
+                .or_else(|| connection.password.clone()),
+            tls: if cluster.tls.enabled {
+                cluster.tls.clone()
+            } else {
+                connection.tls.clone()
+            },
+        }
+    }
+
+    pub fn build_client(&self, url: &str) -> Result<redis::Client> {
+        build_client(
+            url,
+            self.username.as_deref(),
+            self.password.as_deref(),
+            &self.tls,
+        )
+    }
+
+    pub fn cluster_client_builder(&self, nodes: &[String]) -> Result<ClusterClientBuilder> {
+        cluster_client_builder(
+            nodes,
+            self.username.as_deref(),
+            self.password.as_deref(),
+            &self.tls,
+        )
+    }
+}
+
+/// Create a standalone client for `url`, overriding the URL's credentials when
+/// `username`/`password` are given
+pub fn build_client(
+    url: &str,
+    username: Option<&str>,
+    password: Option<&str>,
+    tls: &RedisTlsConfig,
+) -> Result<redis::Client> {
+    let url = apply_tls_scheme(url, tls);
+    let mut connection_info = url
+        .as_str()
+        .into_connection_info()
+        .map_err(|e| Error::RedisError(format!("Invalid Redis URL: {}", e)))?;
+    if let Some(username) = username {
+        connection_info.redis.username = Some(username.to_string());
+    }
+    if let Some(password) = password {
+        connection_info.redis.password = Some(password.to_string());
+    }
+
+    let client = match load_certificates(tls)? {
+        Some(certificates) => redis::Client::build_with_tls(connection_info, certificates),
+        None => redis::Client::open(connection_info),
+    };
+    client.map_err(|e| Error::RedisError(format!("Failed to create Redis client: {}", e)))
+}
+
+/// Create a cluster client builder with credentials and TLS applied, so callers
+/// can still set protocol options before building
+pub fn cluster_client_builder(
+    nodes: &[String],
+    username: Option<&str>,
+    password: Option<&str>,
+    tls: &RedisTlsConfig,
+) -> Result<ClusterClientBuilder> {
+    let nodes: Vec<String> = nodes
+        .iter()
+        .map(|node| apply_tls_scheme(node, tls))
+        .collect();
+    let mut builder = ClusterClientBuilder::new(nodes);
+    if let Some(username) = username {
+        builder = builder.username(username.to_string());
+    }
+    if let Some(password) = password {
+        builder = builder.password(password.to_string());
+    }
+    if let Some(certificates) = load_certificates(tls)? {
+        builder = builder.certs(certificates);
+    }
+    Ok(builder)
+}
+
+/// Switch `redis://` URLs to `rediss://` when TLS is enabled
+fn apply_tls_scheme(url: &str, tls: &RedisTlsConfig) -> String {
+    let mut url = match url.strip_prefix("redis://") {
+        Some(rest) if tls.enabled => format!("rediss://{}", rest),
+        _ => url.to_string(),
+    };
+    if tls.insecure && url.starts_with("rediss://") && !url.ends_with("#insecure") {
+        url.push_str("#insecure");
+    }
+    url
+}
+
+/// Read the configured CA and client certificate files, if any
+fn load_certificates(tls: &RedisTlsConfig) -> Result<Option<TlsCertificates>> {
+    if !tls.enabled {
+        return Ok(None);
+    }
+
+    let root_cert = tls.ca_cert_path.as_deref().map(read_file).transpose()?;
+    let client_tls = match (&tls.client_cert_path, &tls.client_key_path) {
+        (Some(cert_path), Some(key_path)) => Some(ClientTlsConfig {
+            client_cert: read_file(cert_path)?,
+            client_key: read_file(key_path)?,
+        }),
+        (None, None) => None,
+        _ => {
+            return Err(Error::ConfigurationError(
+                "Redis TLS client_cert_path and client_key_path must be set together".to_string(),
+            ));
+        }
+    };
+
+    if root_cert.is_none() && client_tls.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(TlsCertificates {
+        client_tls,
+        root_cert,
+    }))
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>> {
+    std::fs::read(path).map_err(|e| {
+        Error::ConfigurationError(format!("Failed to read Redis TLS file {}: {}", path, e))
+    })
+}
+
+/// How long a component stays degraded before trying Redis writes again
+const DEGRADED_RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Whether Redis refused a write because it hit `maxmemory` (OOM) or the
+/// node is a read-only replica, e.g. during a failover
+pub fn is_write_refused(error: &redis::RedisError) -> bool {
+    error.kind() == redis::ErrorKind::ReadOnly || error.code() == Some("OOM")
+}
+
+/// Tracks whether a Redis-backed component has fallen back to its degraded
+/// behaviour after Redis started refusing writes. One alert is logged when
+/// the component degrades and one when it recovers, instead of an error per
+/// message; writes are retried every `DEGRADED_RETRY_INTERVAL`.
+pub struct RedisDegradedMode {
+    component: &'static str,
+    fallback: &'static str,
+    retry_at: Mutex<Option<Instant>>,
+}
+
+impl RedisDegradedMode {
+    pub fn new(component: &'static str, fallback: &'static str) -> Self {
+        Self {
+            component,
+            fallback,
+            retry_at: Mutex::new(None),
+        }
+    }
+
+    /// True while writes should be skipped in favour of the fallback
+    pub fn is_active(&self) -> bool {
+        matches!(*self.retry_at.lock().unwrap(), Some(retry_at) if Instant::now() < retry_at)
+    }
+
+    /// Enter (or extend) degraded mode if `error` is an OOM/READONLY refusal.
+    /// Returns false for any other error, which the caller should surface.
+    pub fn record_error(&self, error: &redis::RedisError) -> bool {
+        if !is_write_refused(error) {
+            return false;
+        }
+        let mut retry_at = self.retry_at.lock().unwrap();
+        if retry_at.is_none() {
+            error!(
+                "{}",
+                format!(
+                    "Redis refused writes for {} ({}); degrading to {} until it recovers",
+                    self.component, error, self.fallback
+                )
+            );
+        }
+        *retry_at = Some(Instant::now() + DEGRADED_RETRY_INTERVAL);
+        true
+    }
+
+    /// Leave degraded mode after a successful write
+    pub fn record_success(&self) {
+        if self.retry_at.lock().unwrap().take().is_some() {
+            info!(
+                "{}",
+                format!("Redis accepts writes again; {} recovered", self.component)
+            );
+        }
+    }
+}
@@ -0,0 +1,171 @@
+// src/screening.rs
+//! Connection screening before the WebSocket upgrade. A screener sees the
+//! handshake (headers, IP, TLS fingerprint when a proxy forwards one) and
+//! can reject the connection before it consumes a socket, or tag it so the
+//! tags show up next to the connection in admin views and logs.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use axum::http::HeaderMap;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::error::{Error, Result};
+use crate::options::ScreeningConfig;
+
+/// Headers never forwarded to a screening service
+const REDACTED_HEADERS: &[&str] = &["authorization", "cookie", "sec-websocket-key"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HandshakeMetadata {
+    pub app_key: String,
+    pub remote_ip: String,
+    pub headers: BTreeMap<String, String>,
+    pub tls_fingerprint: Option<String>,
+    pub protocol: Option<u8>,
+    pub client: Option<String>,
+    pub version: Option<String>,
+}
+
+impl HandshakeMetadata {
+    pub fn new(
+        app_key: &str,
+        remote_ip: &str,
+        headers: &HeaderMap,
+        tls_fingerprint_header: Option<&str>,
+    ) -> Self {
+        let tls_fingerprint = tls_fingerprint_header
+            .and_then(|name| headers.get(name))
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let headers = headers
+            .iter()
+            .filter(|(name, _)| !REDACTED_HEADERS.contains(&name.as_str()))
+            .filter_map(|(name, value)| {
+                Some((name.as_str().to_string(), value.to_str().ok()?.to_string()))
+            })
+            .collect();
+        Self {
+            app_key: app_key.to_string(),
+            remote_ip: remote_ip.to_string(),
+            headers,
+            tls_fingerprint,
+            protocol: None,
+            client: None,
+            version: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum ScreeningDecision {
+    Allow,
+    /// Accept the connection and attach these tags to it
+    Tag {
+        tags: Vec<String>,
+    },
+    Reject {
+        reason: Option<String>,
+    },
+}
+
+#[async_trait]
+pub trait ConnectionScreener: Send + Sync {
+    async fn screen(&self, metadata: &HandshakeMetadata) -> ScreeningDecision;
+}
+
+/// The configured screener and where to find the TLS fingerprint
+#[derive(Clone)]
+pub struct ConnectionScreening {
+    pub screener: Arc<dyn ConnectionScreener>,
+    pub tls_fingerprint_header: Option<String>,
+}
+
+impl ConnectionScreening {
+    pub fn from_config(config: &ScreeningConfig) -> Result<Self> {
+        Ok(Self {
+            screener: Arc::new(HttpScreener::new(config)?),
+            tls_fingerprint_header: config.tls_fingerprint_header.clone(),
+        })
+    }
+}
+
+/// Screens connections by asking an external anti-abuse service
+pub struct HttpScreener {
+    client: reqwest::Client,
+    url: String,
+    fail_open: bool,
+}
+
+impl HttpScreener {
+    pub fn new(config: &ScreeningConfig) -> Result<Self> {
+        let url = config
+            .url
+            .clone()
+            .ok_or_else(|| Error::Config("screening.url is required".to_string()))?;
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms.max(1)))
+            .build()
+            .map_err(|e| Error::Config(format!("Failed to build screening client: {}", e)))?;
+        Ok(Self {
+            client,
+            url,
+            fail_open: config.fail_open,
+        })
+    }
+
+    async fn request(&self, metadata: &HandshakeMetadata) -> Result<ScreeningDecision> {
+        let response = self
+            .client
+            .post(&self.url)
+            .json(metadata)
+            .send()
+            .await
+            .map_err(|e| Error::InternalError(format!("Screening request failed: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(Error::InternalError(format!(
+                "Screening service returned {}",
+                response.status()
+            )));
+        }
+        response
+            .json()
+            .await
+            .map_err(|e| Error::InternalError(format!("Invalid screening response: {}", e)))
+    }
+}
+
+#[async_trait]
+impl ConnectionScreener for HttpScreener {
+    async fn screen(&self, metadata: &HandshakeMetadata) -> ScreeningDecision {
+        match self.request(metadata).await {
+            Ok(decision) => decision,
+            Err(e) => {
+                warn!(
+                    "{}",
+                    format!(
+                        "Screening failed for {} ({}), {}",
+                        metadata.remote_ip,
+                        e,
+                        if self.fail_open {
+                            "allowing"
+                        } else {
+                            "rejecting"
+                        }
+                    )
+                );
+                if self.fail_open {
+                    ScreeningDecision::Allow
+                } else {
+                    ScreeningDecision::Reject {
+                        reason: Some("Screening unavailable".to_string()),
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,394 @@
+// src/testing.rs
+//! Integration test harness. `TestCluster` starts several in-process nodes
+//! joined by a `MemoryBus` and connects real WebSocket clients to them, so
+//! cross-node behaviour (broadcasts, presence, terminations) is asserted on
+//! what clients actually receive, without Docker or a broker.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+
+use fastwebsockets::{FragmentCollector, OpCode, Role, WebSocket as FastWebSocket};
+use http_body_util::Empty;
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{CONNECTION, HOST, UPGRADE};
+use hyper::service::service_fn;
+use hyper::upgrade::Upgraded;
+use hyper::{Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use serde_json::{Value, json};
+use tokio::sync::oneshot;
+
+use crate::adapter::Adapter;
+use crate::adapter::memory_adapter::{MemoryAdapter, MemoryBus};
+use crate::app::config::App;
+use crate::app::manager::AppManager;
+use crate::app::memory_app_manager::MemoryAppManager;
+use crate::channel::PresenceMemberInfo;
+use crate::protocol::messages::PusherMessage;
+use crate::websocket::SocketId;
+
+pub const TEST_APP_ID: &str = "test-app";
+
+/// How long a client waits for a frame before reporting nothing arrived
+const RECEIVE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Cross-node requests give up quickly so a missing node fails a test fast
+const REQUEST_TIMEOUT_MS: u64 = 1000;
+
+/// What a test client observed on its connection
+#[derive(Debug)]
+pub enum ClientEvent {
+    Message(PusherMessage),
+    Closed(u16),
+}
+
+pub struct TestClient {
+    pub socket_id: SocketId,
+    reader: FragmentCollector<TokioIo<Upgraded>>,
+}
+
+impl TestClient {
+    /// Next message or close frame, or `None` if nothing arrives in time
+    pub async fn recv(&mut self) -> Option<ClientEvent> {
+        let frame = tokio::time::timeout(RECEIVE_TIMEOUT, self.reader.read_frame())
+            .await
+            .ok()?
+            .ok()?;
+        match frame.opcode {
+            OpCode::Text => serde_json::from_slice(&frame.payload)
+                .ok()
+                .map(ClientEvent::Message),
+            OpCode::Close => {
+                let code = match frame.payload.get(..2) {
+                    Some(&[high, low]) => u16::from_be_bytes([high, low]),
+                    _ => 1005,
+                };
+                Some(ClientEvent::Closed(code))
+            }
+            _ => None,
+        }
+    }
+
+    pub async fn recv_message(&mut self) -> Option<PusherMessage> {
+        match self.recv().await? {
+            ClientEvent::Message(message) => Some(message),
+            ClientEvent::Closed(_) => None,
+        }
+    }
+}
+
+pub struct TestCluster {
+    pub nodes: Vec<Arc<MemoryAdapter>>,
+    pub app_manager: Arc<dyn AppManager + Send + Sync>,
+}
+
+impl TestCluster {
+    /// Start `node_count` nodes on a fresh bus, all serving `TEST_APP_ID`
+    pub async fn start(node_count: usize) -> Self {
+        let app_manager: Arc<dyn AppManager + Send + Sync> = Arc::new(MemoryAppManager::new());
+        app_manager
+            .create_app(App {
+                id: TEST_APP_ID.to_string(),
+                key: format!("{}-key", TEST_APP_ID),
+                secret: format!("{}-secret", TEST_APP_ID),
+                max_connections: 1000,
+                enable_client_messages: true,
+                enabled: true,
+                max_client_events_per_second: 100,
+                ..Default::default()
+            })
+            .await
+            .expect("failed to register the test app");
+
+        let bus = MemoryBus::default();
+        let mut nodes = Vec::with_capacity(node_count);
+        for _ in 0..node_count {
+            let node = Arc::new(MemoryAdapter::new(bus.clone(), REQUEST_TIMEOUT_MS));
+            node.init().await;
+            nodes.push(node);
+        }
+
+        Self { nodes, app_manager }
+    }
+
+    pub fn node(&self, index: usize) -> &Arc<MemoryAdapter> {
+        &self.nodes[index]
+    }
+
+    /// Open a WebSocket connection to a node
+    pub async fn connect(&self, node: usize) -> TestClient {
+        let (client_io, server_io) = upgraded_pair().await;
+
+        let server = FastWebSocket::after_handshake(TokioIo::new(server_io), Role::Server);
+        let (_, writer) = server.split(tokio::io::split);
+        let socket_id = SocketId::new();
+        self.node(node)
+            .add_socket(socket_id.clone(), writer, TEST_APP_ID, &self.app_manager)
+            .await
+            .expect("failed to add socket");
+
+        let client = FastWebSocket::after_handshake(TokioIo::new(client_io), Role::Client);
+        TestClient {
+            socket_id,
+            reader: FragmentCollector::new(client),
+        }
+    }
+
+    /// Open a connection already signed in as `user_id`
+    pub async fn connect_as(&self, node: usize, user_id: &str) -> TestClient {
+        let client = self.connect(node).await;
+        let connection = self
+            .node(node)
+            .get_connection(&client.socket_id, TEST_APP_ID)
+            .await
+            .expect("connection not registered");
+        {
+            let mut ws = connection.lock().await;
+            ws.state.user_id = Some(user_id.to_string());
+            ws.state.user = Some(json!({ "id": user_id }));
+        }
+        self.node(node)
+            .add_user(connection)
+            .await
+            .expect("failed to add user");
+        client
+    }
+
+    pub async fn subscribe(&self, node: usize, client: &TestClient, channel: &str) {
+        self.node(node)
+            .add_to_channel(TEST_APP_ID, channel, &client.socket_id)
+            .await
+            .expect("failed to subscribe");
+        if let Some(connection) = self
+            .node(node)
+            .get_connection(&client.socket_id, TEST_APP_ID)
+            .await
+        {
+            connection
+                .lock()
+                .await
+                .state
+                .subscribed_channels
+                .insert(channel.to_string());
+        }
+    }
+
+    /// Subscribe to a presence channel as a member, like an authorized
+    /// `pusher:subscribe` with `channel_data` would
+    pub async fn join_presence(
+        &self,
+        node: usize,
+        client: &TestClient,
+        channel: &str,
+        user_id: &str,
+        user_info: Value,
+    ) {
+        let connection = self
+            .node(node)
+            .get_connection(&client.socket_id, TEST_APP_ID)
+            .await
+            .expect("connection not registered");
+        connection
+            .lock()
+            .await
+            .state
+            .presence
+            .get_or_insert_with(HashMap::new)
+            .insert(
+                channel.to_string(),
+                PresenceMemberInfo {
+                    user_id: user_id.to_string(),
+                    user_info: Some(user_info),
+                },
+            );
+        self.subscribe(node, client, channel).await;
+    }
+}
+
+/// Both ends of an upgraded HTTP/1 connection over an in-memory pipe
+async fn upgraded_pair() -> (Upgraded, Upgraded) {
+    let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+    let (upgrade_tx, upgrade_rx) = oneshot::channel();
+    let upgrade_tx = Arc::new(std::sync::Mutex::new(Some(upgrade_tx)));
+
+    tokio::spawn(async move {
+        let service = service_fn(move |mut request: Request<Incoming>| {
+            if let Some(tx) = upgrade_tx.lock().unwrap().take() {
+                let _ = tx.send(hyper::upgrade::on(&mut request));
+            }
+            async {
+                let response = Response::builder()
+                    .status(StatusCode::SWITCHING_PROTOCOLS)
+                    .header(UPGRADE, "websocket")
+                    .header(CONNECTION, "upgrade")
+                    .body(Empty::<Bytes>::new())
+                    .unwrap();
+                Ok::<_, Infallible>(response)
+            }
+        });
+        let _ = hyper::server::conn::http1::Builder::new()
+            .serve_connection(TokioIo::new(server_io), service)
+            .with_upgrades()
+            .await;
+    });
+
+    let (mut sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(client_io))
+        .await
+        .expect("client handshake failed");
+    tokio::spawn(connection.with_upgrades());
+
+    let request = Request::builder()
+        .uri("/")
+        .header(HOST, "localhost")
+        .header(UPGRADE, "websocket")
+        .header(CONNECTION, "upgrade")
+        .body(Empty::<Bytes>::new())
+        .unwrap();
+    let response = sender
+        .send_request(request)
+        .await
+        .expect("upgrade request failed");
+    let client = hyper::upgrade::on(response)
+        .await
+        .expect("client upgrade failed");
+    let server = upgrade_rx
+        .await
+        .expect("server never saw the upgrade")
+        .await
+        .expect("server upgrade failed");
+    (client, server)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn broadcasts_reach_subscribers_on_other_nodes() {
+        let cluster = TestCluster::start(3).await;
+        let mut subscribers = Vec::new();
+        for node in 0..3 {
+            let client = cluster.connect(node).await;
+            cluster.subscribe(node, &client, "news").await;
+            subscribers.push(client);
+        }
+
+        cluster
+            .node(0)
+            .send(
+                "news",
+                PusherMessage::channel_event("update", "news", json!({ "n": 1 })),
+                None,
+                TEST_APP_ID,
+            )
+            .await
+            .unwrap();
+
+        for client in &mut subscribers {
+            let message = client.recv_message().await.expect("broadcast not received");
+            assert_eq!(message.event.as_deref(), Some("update"));
+            assert_eq!(message.channel.as_deref(), Some("news"));
+        }
+    }
+
+    #[tokio::test]
+    async fn excluded_socket_does_not_receive_its_own_broadcast() {
+        let cluster = TestCluster::start(2).await;
+        let mut sender = cluster.connect(0).await;
+        let mut other = cluster.connect(1).await;
+        cluster.subscribe(0, &sender, "chat").await;
+        cluster.subscribe(1, &other, "chat").await;
+
+        cluster
+            .node(1)
+            .send(
+                "chat",
+                PusherMessage::channel_event("client-typing", "chat", json!({})),
+                Some(&other.socket_id),
+                TEST_APP_ID,
+            )
+            .await
+            .unwrap();
+
+        assert!(sender.recv_message().await.is_some());
+        assert!(other.recv_message().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn presence_members_are_aggregated_across_nodes() {
+        let cluster = TestCluster::start(2).await;
+        let alice = cluster.connect(0).await;
+        let bob = cluster.connect(1).await;
+        cluster
+            .join_presence(
+                0,
+                &alice,
+                "presence-room",
+                "alice",
+                json!({ "name": "Alice" }),
+            )
+            .await;
+        cluster
+            .join_presence(1, &bob, "presence-room", "bob", json!({ "name": "Bob" }))
+            .await;
+
+        for node in 0..2 {
+            let members = cluster
+                .node(node)
+                .get_channel_members(TEST_APP_ID, "presence-room")
+                .await
+                .unwrap();
+            assert_eq!(members.len(), 2);
+            assert!(members.contains_key("alice") && members.contains_key("bob"));
+        }
+        assert_eq!(
+            cluster
+                .node(0)
+                .get_channel_socket_count(TEST_APP_ID, "presence-room")
+                .await,
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn terminating_a_user_closes_connections_on_every_node() {
+        let cluster = TestCluster::start(2).await;
+        let mut local = cluster.connect_as(0, "user-1").await;
+        let mut remote = cluster.connect_as(1, "user-1").await;
+        let mut bystander = cluster.connect_as(1, "user-2").await;
+
+        cluster
+            .node(0)
+            .terminate_user_connections(TEST_APP_ID, "user-1")
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            local.recv().await,
+            Some(ClientEvent::Closed(4009))
+        ));
+        assert!(matches!(
+            remote.recv().await,
+            Some(ClientEvent::Closed(4009))
+        ));
+        assert!(bystander.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn stopped_nodes_are_not_waited_for() {
+        let cluster = TestCluster::start(2).await;
+        let client = cluster.connect(1).await;
+        cluster.subscribe(1, &client, "news").await;
+        cluster.node(1).stop();
+
+        let started = tokio::time::Instant::now();
+        let count = cluster
+            .node(0)
+            .get_channel_socket_count(TEST_APP_ID, "news")
+            .await;
+        assert_eq!(count, 0);
+        assert!(started.elapsed() < Duration::from_millis(REQUEST_TIMEOUT_MS));
+    }
+}
@@ -0,0 +1,181 @@
+// src/tls.rs
+//! TLS for the main listener when certificates are served per SNI hostname.
+//! Each configured hostname gets its own certificate and clients sending no
+//! or an unknown server name get the default `cert_path` one. Hostnames
+//! bound to an app also restrict which app key WebSocket clients connecting
+//! through them may use.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+
+use axum_server::tls_rustls::RustlsConfig;
+use rustls::ServerConfig;
+use rustls::crypto::CryptoProvider;
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
+use tracing::info;
+
+use crate::error::{Error, Result};
+use crate::options::SslConfig;
+
+/// Values keyed by hostname, where a `*.example.com` entry matches exactly
+/// one label under `example.com`
+#[derive(Debug)]
+struct HostnameMap<T> {
+    exact: HashMap<String, T>,
+    wildcard: HashMap<String, T>,
+}
+
+impl<T> HostnameMap<T> {
+    fn new() -> Self {
+        Self {
+            exact: HashMap::new(),
+            wildcard: HashMap::new(),
+        }
+    }
+
+    fn insert(&mut self, hostname: &str, value: T) {
+        let hostname = hostname.trim_end_matches('.').to_ascii_lowercase();
+        match hostname.strip_prefix("*.") {
+            Some(parent) => self.wildcard.insert(parent.to_string(), value),
+            None => self.exact.insert(hostname, value),
+        };
+    }
+
+    fn get(&self, hostname: &str) -> Option<&T> {
+        let hostname = hostname.trim_end_matches('.').to_ascii_lowercase();
+        self.exact.get(&hostname).or_else(|| {
+            let (_, parent) = hostname.split_once('.')?;
+            self.wildcard.get(parent)
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.exact.is_empty() && self.wildcard.is_empty()
+    }
+}
+
+/// Picks the certificate for the server name a client asks for
+#[derive(Debug)]
+struct SniCertResolver {
+    certificates: HostnameMap<Arc<CertifiedKey>>,
+    default: Option<Arc<CertifiedKey>>,
+}
+
+impl ResolvesServerCert for SniCertResolver {
+    fn resolve(&self, client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        client_hello
+            .server_name()
+            .and_then(|name| self.certificates.get(name).cloned())
+            .or_else(|| self.default.clone())
+    }
+}
+
+/// Build the listener's TLS configuration from the default certificate and
+/// the `sni` certificates
+pub fn server_config(ssl: &SslConfig) -> Result<RustlsConfig> {
+    let provider = CryptoProvider::get_default()
+        .cloned()
+        .unwrap_or_else(|| Arc::new(rustls::crypto::aws_lc_rs::default_provider()));
+
+    let default = if ssl.cert_path.is_empty() || ssl.key_path.is_empty() {
+        None
+    } else {
+        Some(load_certified_key(
+            &provider,
+            &ssl.cert_path,
+            &ssl.key_path,
+        )?)
+    };
+
+    let mut certificates = HostnameMap::new();
+    for entry in &ssl.sni {
+        let key = load_certified_key(&provider, &entry.cert_path, &entry.key_path)?;
+        for hostname in &entry.hostnames {
+            certificates.insert(hostname, key.clone());
+        }
+        info!(
+            "{}",
+            format!(
+                "Serving {} for {}",
+                entry.cert_path,
+                entry.hostnames.join(", ")
+            )
+        );
+    }
+
+    let mut config = ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| Error::InternalError(format!("Failed to load TLS configuration: {}", e)))?
+        .with_no_client_auth()
+        .with_cert_resolver(Arc::new(SniCertResolver {
+            certificates,
+            default,
+        }));
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(RustlsConfig::from_config(Arc::new(config)))
+}
+
+fn load_certified_key(
+    provider: &CryptoProvider,
+    cert_path: &str,
+    key_path: &str,
+) -> Result<Arc<CertifiedKey>> {
+    let open = |path: &str| {
+        File::open(path)
+            .map(BufReader::new)
+            .map_err(|e| Error::ConfigFileError(format!("Failed to open TLS file {}: {}", path, e)))
+    };
+
+    let certs = rustls_pemfile::certs(&mut open(cert_path)?)
+        .collect::<std::io::Result<Vec<_>>>()
+        .map_err(|e| {
+            Error::ConfigFileError(format!("Invalid certificate in {}: {}", cert_path, e))
+        })?;
+    if certs.is_empty() {
+        return Err(Error::ConfigFileError(format!(
+            "No certificate found in {}",
+            cert_path
+        )));
+    }
+    let key = rustls_pemfile::private_key(&mut open(key_path)?)
+        .map_err(|e| Error::ConfigFileError(format!("Invalid key in {}: {}", key_path, e)))?
+        .ok_or_else(|| Error::ConfigFileError(format!("No private key found in {}", key_path)))?;
+    let signing_key = provider
+        .key_provider
+        .load_private_key(key)
+        .map_err(|e| Error::ConfigFileError(format!("Unusable key in {}: {}", key_path, e)))?;
+    Ok(Arc::new(CertifiedKey::new(certs, signing_key)))
+}
+
+/// Apps bound to SNI hostnames
+#[derive(Debug)]
+pub struct HostAppBindings {
+    apps: HostnameMap<String>,
+}
+
+impl HostAppBindings {
+    /// None when no hostname is bound to an app
+    pub fn from_config(ssl: &SslConfig) -> Option<Self> {
+        let mut apps = HostnameMap::new();
+        for entry in &ssl.sni {
+            if let Some(app_id) = &entry.app_id {
+                for hostname in &entry.hostnames {
+                    apps.insert(hostname, app_id.clone());
+                }
+            }
+        }
+        (!apps.is_empty()).then_some(Self { apps })
+    }
+
+    /// App bound to the hostname of a `Host` header, which may carry a port
+    pub fn app_for_host(&self, host: &str) -> Option<&str> {
+        let hostname = match host.rsplit_once(':') {
+            Some((hostname, port)) if port.chars().all(|c| c.is_ascii_digit()) => hostname,
+            _ => host,
+        };
+        self.apps.get(hostname).map(String::as_str)
+    }
+}
@@ -0,0 +1,243 @@
+// src/usage_stats.rs
+//! Historical usage counters for billing. Messages per channel prefix and
+//! the peak connection count are accumulated in memory, then merged into one
+//! rollup per app and day in the cache backend.
+//!
+//! Rollups are read-modify-write: nodes sharing a cache add their message
+//! counts into the same rollup, and the peak is the highest count any single
+//! node saw.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{NaiveDate, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::adapter::Adapter;
+use crate::cache::manager::CacheManager;
+use crate::error::Result;
+use crate::options::UsageStatsConfig;
+
+/// Longest range a single stats query may cover
+pub const MAX_QUERY_DAYS: i64 = 366;
+
+/// Channel prefixes reported on their own; other channels are grouped by
+/// the text up to their first `-`
+const KNOWN_PREFIXES: &[&str] = &["private-encrypted-", "presence-", "private-", "cache-"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DailyUsage {
+    pub date: NaiveDate,
+    /// Messages sent, keyed by channel prefix
+    pub messages: BTreeMap<String, u64>,
+    pub total_messages: u64,
+    pub peak_connections: usize,
+}
+
+impl DailyUsage {
+    fn merge(&mut self, other: &DailyUsage) {
+        for (prefix, count) in &other.messages {
+            *self.messages.entry(prefix.clone()).or_default() += count;
+        }
+        self.total_messages += other.total_messages;
+        self.peak_connections = self.peak_connections.max(other.peak_connections);
+    }
+}
+
+pub struct UsageStats {
+    cache_manager: Arc<Mutex<dyn CacheManager + Send + Sync>>,
+    connection_manager: Arc<dyn Adapter + Send + Sync>,
+    config: UsageStatsConfig,
+    /// Counters not yet flushed, keyed by (app ID, day)
+    pending: DashMap<(String, NaiveDate), DailyUsage>,
+}
+
+impl UsageStats {
+    pub fn new(
+        cache_manager: Arc<Mutex<dyn CacheManager + Send + Sync>>,
+        connection_manager: Arc<dyn Adapter + Send + Sync>,
+        config: UsageStatsConfig,
+    ) -> Self {
+        Self {
+            cache_manager,
+            connection_manager,
+            config,
+            pending: DashMap::new(),
+        }
+    }
+
+    /// Spawn the sampling and flush loops
+    pub fn start(self: &Arc<Self>) {
+        info!(
+            "{}",
+            format!(
+                "Usage stats enabled (flush interval: {}ms, retention: {} days)",
+                self.config.flush_interval_ms, self.config.retention_days
+            )
+        );
+
+        let stats = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(
+                stats.config.sample_interval_ms.max(1000),
+            ));
+            loop {
+                interval.tick().await;
+                stats.sample_connections().await;
+            }
+        });
+
+        let stats = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(
+                stats.config.flush_interval_ms.max(1000),
+            ));
+            loop {
+                interval.tick().await;
+                stats.flush().await;
+            }
+        });
+    }
+
+    /// Count a message published to `channel`
+    pub fn record_message(&self, app_id: &str, channel: &str) {
+        let mut usage = self.entry(app_id);
+        *usage
+            .messages
+            .entry(channel_prefix(channel).to_string())
+            .or_default() += 1;
+        usage.total_messages += 1;
+    }
+
+    fn entry(
+        &self,
+        app_id: &str,
+    ) -> dashmap::mapref::one::RefMut<'_, (String, NaiveDate), DailyUsage> {
+        let date = Utc::now().date_naive();
+        self.pending
+            .entry((app_id.to_string(), date))
+            .or_insert_with(|| DailyUsage {
+                date,
+                ..Default::default()
+            })
+    }
+
+    async fn sample_connections(&self) {
+        let namespaces = match self.connection_manager.get_namespaces().await {
+            Ok(namespaces) => namespaces,
+            Err(e) => {
+                warn!(
+                    "{}",
+                    format!("Failed to sample connections for usage stats: {}", e)
+                );
+                return;
+            }
+        };
+        for namespace in namespaces.iter() {
+            let connections = namespace.sockets.len();
+            if connections == 0 {
+                continue;
+            }
+            let mut usage = self.entry(namespace.key());
+            usage.peak_connections = usage.peak_connections.max(connections);
+        }
+    }
+
+    /// Merge pending counters into the stored daily rollups
+    pub async fn flush(&self) {
+        let keys: Vec<(String, NaiveDate)> = self
+            .pending
+            .iter()
+            .map(|entry| entry.key().clone())
+            .collect();
+        let ttl = u64::from(self.config.retention_days) * 86_400;
+
+        for key in keys {
+            let Some(((app_id, date), pending)) = self.pending.remove(&key) else {
+                continue;
+            };
+            let cache_key = rollup_key(&app_id, date);
+
+            let mut cache = self.cache_manager.lock().await;
+            let mut rollup = match cache.get(&cache_key).await {
+                Ok(Some(stored)) => serde_json::from_str(&stored).unwrap_or_else(|_| DailyUsage {
+                    date,
+                    ..Default::default()
+                }),
+                _ => DailyUsage {
+                    date,
+                    ..Default::default()
+                },
+            };
+            rollup.merge(&pending);
+
+            let stored = match serde_json::to_string(&rollup) {
+                Ok(stored) => stored,
+                Err(e) => {
+                    warn!("{}", format!("Failed to serialize usage rollup: {}", e));
+                    continue;
+                }
+            };
+            if let Err(e) = cache.set(&cache_key, &stored, ttl).await {
+                warn!(
+                    "{}",
+                    format!("Failed to store usage rollup {}: {}", cache_key, e)
+                );
+                // Keep the counts for the next flush
+                drop(cache);
+                self.pending
+                    .entry((app_id, date))
+                    .or_insert_with(|| DailyUsage {
+                        date,
+                        ..Default::default()
+                    })
+                    .merge(&pending);
+            }
+        }
+    }
+
+    /// Daily rollups for `app_id` between `from` and `to` inclusive,
+    /// including counters not flushed yet
+    pub async fn query(
+        &self,
+        app_id: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<DailyUsage>> {
+        let mut days = Vec::new();
+        let mut cache = self.cache_manager.lock().await;
+        for date in from.iter_days().take_while(|date| *date <= to) {
+            let mut usage = match cache.get(&rollup_key(app_id, date)).await? {
+                Some(stored) => serde_json::from_str(&stored)?,
+                None => DailyUsage {
+                    date,
+                    ..Default::default()
+                },
+            };
+            if let Some(pending) = self.pending.get(&(app_id.to_string(), date)) {
+                usage.merge(&pending);
+            }
+            days.push(usage);
+        }
+        Ok(days)
+    }
+}
+
+fn rollup_key(app_id: &str, date: NaiveDate) -> String {
+    format!("usage:{}:{}", app_id, date.format("%Y-%m-%d"))
+}
+
+/// Prefix a channel is reported under, e.g. `presence-` or `chat-`
+fn channel_prefix(channel: &str) -> &str {
+    if let Some(prefix) = KNOWN_PREFIXES.iter().find(|p| channel.starts_with(*p)) {
+        return prefix;
+    }
+    match channel.find('-') {
+        Some(index) => &channel[..=index],
+        None => "other",
+    }
+}
@@ -1,31 +1,27 @@
-use std::sync::LazyLock;
-
 use crate::app::config::App;
 use crate::error::Error;
-use regex::Regex;
+use crate::protocol::constants::{
+    APP_DISABLED_CHANNEL, CHANNEL_NAME_MAX_LENGTH, SERVER_NOTICE_CHANNEL,
+};
+
+/// Channels whose last event is kept for new subscribers
+const CACHE_CHANNEL_PREFIXES: [&str; 4] = [
+    "cache-",
+    "private-cache-",
+    "private-encrypted-cache-",
+    "presence-cache-",
+];
 
-// Compile regexes once using lazy_static
-static CACHING_CHANNEL_REGEXES: LazyLock<Vec<Regex>> = LazyLock::new(|| {
-    let patterns = vec![
-        "cache-*",
-        "private-cache-*",
-        "private-encrypted-cache-*",
-        "presence-cache-*",
-    ];
-    patterns
-        .into_iter()
-        .map(|pattern| Regex::new(&pattern.replace("*", ".*")).unwrap())
-        .collect()
-});
+/// Reserved channels that reach every connection of an app on every node,
+/// rather than the subscribers of a channel
+pub fn is_app_wide_channel(channel: &str) -> bool {
+    channel == SERVER_NOTICE_CHANNEL || channel == APP_DISABLED_CHANNEL
+}
 
 pub fn is_cache_channel(channel: &str) -> bool {
-    // Use the pre-compiled regexes
-    for regex in CACHING_CHANNEL_REGEXES.iter() {
-        if regex.is_match(channel) {
-            return true;
-        }
-    }
-    false
+    CACHE_CHANNEL_PREFIXES
+        .iter()
+        .any(|prefix| channel.starts_with(prefix))
 }
 
 pub fn data_to_bytes<T: AsRef<str> + serde::Serialize>(data: &[T]) -> usize {
@@ -63,10 +59,13 @@ pub fn data_to_bytes_flexible(data: Vec<serde_json::Value>) -> usize {
 }
 
 pub async fn validate_channel_name(app: &App, channel: &str) -> crate::error::Result<()> {
-    if channel.len() > app.max_channel_name_length.unwrap_or(200) as usize {
+    let max_length = app
+        .max_channel_name_length
+        .unwrap_or(CHANNEL_NAME_MAX_LENGTH as u32);
+    if channel.len() > max_length as usize {
         return Err(Error::ChannelError(format!(
             "Channel name too long. Max length is {}",
-            app.max_channel_name_length.unwrap_or(200)
+            max_length
         )));
     }
     if !channel.chars().all(|c| {
@@ -79,3 +78,14 @@ pub async fn validate_channel_name(app: &App, channel: &str) -> crate::error::Re
 
     Ok(())
 }
+
+/// Refuse a channel whose kind the app has turned off
+pub fn check_channel_features(app: &App, channel: &str) -> crate::error::Result<()> {
+    if channel.starts_with("private-encrypted-") && !app.encrypted_channels_enabled() {
+        return Err(Error::ChannelFeatureDisabled("Encrypted channels"));
+    }
+    if is_cache_channel(channel) && !app.cache_channels_enabled() {
+        return Err(Error::ChannelFeatureDisabled("Cache channels"));
+    }
+    Ok(())
+}
@@ -0,0 +1,27 @@
+// src/webhook/aws.rs
+//! Shared AWS SDK configuration for the Lambda, SNS and EventBridge webhook
+//! targets.
+
+use aws_config::SdkConfig;
+use aws_config::meta::region::RegionProviderChain;
+use aws_sdk_lambda::config::Region;
+use aws_sdk_lambda::config::timeout::TimeoutConfig;
+use std::time::Duration;
+
+/// SDK configuration for `region`, with credentials from the standard AWS
+/// environment and a 10s operation timeout
+pub async fn sdk_config(region: &str) -> SdkConfig {
+    let region_provider = RegionProviderChain::first_try(Region::new(region.to_string()))
+        .or_default_provider()
+        .or_else(Region::new("us-east-1"));
+
+    aws_config::from_env()
+        .region(region_provider)
+        .timeout_config(
+            TimeoutConfig::builder()
+                .operation_timeout(Duration::from_secs(10))
+                .build(),
+        )
+        .load()
+        .await
+}
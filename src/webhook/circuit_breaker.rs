@@ -0,0 +1,182 @@
+// src/webhook/circuit_breaker.rs
+//! Per-endpoint circuit breaker for webhook deliveries. A URL whose
+//! deliveries fail `failure_threshold` times in a row is left alone for
+//! `cooldown_seconds` rather than hammered by every job. Once the cooldown
+//! is over, a single trial delivery goes through: success closes the circuit
+//! and failure opens it for another cooldown.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use tokio::sync::{Mutex, RwLock};
+use tracing::{info, warn};
+
+use crate::metrics::MetricsInterface;
+use crate::options::WebhookCircuitBreakerConfig;
+
+type SharedMetrics = Arc<Mutex<dyn MetricsInterface + Send + Sync>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Deliveries go through
+    Closed,
+    /// Deliveries are paused until the cooldown ends
+    Open,
+    /// A trial delivery decides whether the circuit closes
+    HalfOpen,
+}
+
+impl CircuitState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CircuitState::Closed => "closed",
+            CircuitState::Open => "open",
+            CircuitState::HalfOpen => "half_open",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Circuit {
+    consecutive_failures: u32,
+    /// When the circuit last opened; unset while closed
+    opened_at: Option<Instant>,
+    /// A half-open circuit lets a single trial delivery through
+    trial_in_flight: bool,
+}
+
+impl Circuit {
+    fn state(&self) -> CircuitState {
+        match self.opened_at {
+            None => CircuitState::Closed,
+            Some(_) if self.trial_in_flight => CircuitState::HalfOpen,
+            Some(_) => CircuitState::Open,
+        }
+    }
+}
+
+pub struct CircuitBreaker {
+    config: WebhookCircuitBreakerConfig,
+    /// Circuits keyed by webhook URL
+    circuits: DashMap<String, Circuit>,
+    metrics: RwLock<Option<SharedMetrics>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: WebhookCircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            circuits: DashMap::new(),
+            metrics: RwLock::new(None),
+        }
+    }
+
+    pub async fn set_metrics(&self, metrics: SharedMetrics) {
+        *self.metrics.write().await = Some(metrics);
+    }
+
+    fn cooldown(&self) -> Duration {
+        Duration::from_secs(self.config.cooldown_seconds)
+    }
+
+    /// Whether a delivery to `endpoint` may go ahead now. An open circuit
+    /// whose cooldown is over turns half-open and admits the caller as its
+    /// trial delivery.
+    pub async fn try_acquire(&self, endpoint: &str) -> bool {
+        if !self.config.enabled {
+            return true;
+        }
+        let transition = {
+            let Some(mut circuit) = self.circuits.get_mut(endpoint) else {
+                return true;
+            };
+            match circuit.opened_at {
+                None => return true,
+                Some(opened_at)
+                    if !circuit.trial_in_flight && opened_at.elapsed() >= self.cooldown() =>
+                {
+                    circuit.trial_in_flight = true;
+                    circuit.state()
+                }
+                Some(_) => return false,
+            }
+        };
+        self.report(endpoint, transition).await;
+        true
+    }
+
+    /// Record a delivery the endpoint answered, closing its circuit
+    pub async fn record_success(&self, endpoint: &str) {
+        if !self.config.enabled {
+            return;
+        }
+        let Some((_, circuit)) = self.circuits.remove(endpoint) else {
+            return;
+        };
+        if circuit.opened_at.is_some() {
+            info!(
+                "{}",
+                format!("Webhook circuit for {} closed", endpoint_label(endpoint))
+            );
+            self.report(endpoint, CircuitState::Closed).await;
+        }
+    }
+
+    /// Record a failed delivery, opening the circuit once the endpoint has
+    /// failed `failure_threshold` times in a row or failed its trial
+    pub async fn record_failure(&self, endpoint: &str) {
+        if !self.config.enabled {
+            return;
+        }
+        let opened = {
+            let mut circuit = self.circuits.entry(endpoint.to_string()).or_default();
+            circuit.consecutive_failures = circuit.consecutive_failures.saturating_add(1);
+            let trial_failed = circuit.trial_in_flight;
+            if trial_failed
+                || (circuit.opened_at.is_none()
+                    && circuit.consecutive_failures >= self.config.failure_threshold.max(1))
+            {
+                circuit.opened_at = Some(Instant::now());
+                circuit.trial_in_flight = false;
+                true
+            } else {
+                false
+            }
+        };
+        if opened {
+            warn!(
+                "{}",
+                format!(
+                    "Webhook circuit for {} opened, pausing deliveries for {}s",
+                    endpoint_label(endpoint),
+                    self.config.cooldown_seconds
+                )
+            );
+            self.report(endpoint, CircuitState::Open).await;
+        }
+    }
+
+    async fn report(&self, endpoint: &str, state: CircuitState) {
+        if let Some(metrics) = self.metrics.read().await.as_ref() {
+            metrics
+                .lock()
+                .await
+                .mark_webhook_circuit_state(&endpoint_label(endpoint), state.as_str());
+        }
+    }
+}
+
+/// The URL without its query string, which may carry credentials, for logs
+/// and metric labels
+fn endpoint_label(endpoint: &str) -> String {
+    match url::Url::parse(endpoint) {
+        Ok(mut url) => {
+            url.set_query(None);
+            let _ = url.set_password(None);
+            let _ = url.set_username("");
+            url.to_string()
+        }
+        Err(_) => endpoint.split('?').next().unwrap_or(endpoint).to_string(),
+    }
+}
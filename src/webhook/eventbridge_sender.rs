@@ -0,0 +1,104 @@
+// src/webhook/eventbridge_sender.rs
+
+use crate::error::{Error, Result};
+use crate::webhook::aws::sdk_config;
+use crate::webhook::types::EventBridgeConfig;
+use aws_sdk_eventbridge::Client as EventBridgeClient;
+use aws_sdk_eventbridge::types::PutEventsRequestEntry;
+use tracing::{error, info};
+
+const DEFAULT_SOURCE: &str = "sockudo";
+const DEFAULT_DETAIL_TYPE: &str = "pusher.webhook";
+
+/// Puts webhooks on AWS EventBridge buses
+#[derive(Clone)]
+pub struct EventBridgeWebhookSender {
+    // Cache EventBridge clients by region to avoid recreating them
+    clients: dashmap::DashMap<String, EventBridgeClient>,
+}
+
+impl EventBridgeWebhookSender {
+    pub fn new() -> Self {
+        Self {
+            clients: dashmap::DashMap::new(),
+        }
+    }
+
+    async fn get_client(&self, region: &str) -> EventBridgeClient {
+        if let Some(client_ref) = self.clients.get(region) {
+            return client_ref.clone();
+        }
+        let client = EventBridgeClient::new(&sdk_config(region).await);
+        self.clients.insert(region.to_string(), client.clone());
+        client
+    }
+
+    /// Put one event whose `detail` is the webhook body. PutEvents answers
+    /// 200 even when the entry is rejected, so the entry's own result is
+    /// checked too.
+    pub async fn put_event(
+        &self,
+        config: &EventBridgeConfig,
+        app_id: &str,
+        body: &str,
+    ) -> Result<()> {
+        let client = self.get_client(&config.region).await;
+
+        let entry = PutEventsRequestEntry::builder()
+            .event_bus_name(&config.event_bus_name)
+            .source(config.source.as_deref().unwrap_or(DEFAULT_SOURCE))
+            .detail_type(config.detail_type.as_deref().unwrap_or(DEFAULT_DETAIL_TYPE))
+            .detail(body)
+            .build();
+
+        let output = client
+            .put_events()
+            .entries(entry)
+            .send()
+            .await
+            .map_err(|e| {
+                error!(
+                    "{}",
+                    format!(
+                        "Failed to put webhook on EventBridge bus {}: {}",
+                        config.event_bus_name, e
+                    )
+                );
+                Error::Other(format!("Failed to put event on EventBridge: {}", e))
+            })?;
+
+        if output.failed_entry_count() > 0 {
+            let reason = output
+                .entries()
+                .iter()
+                .find_map(|entry| entry.error_message().or(entry.error_code()))
+                .unwrap_or("unknown error");
+            error!(
+                "{}",
+                format!(
+                    "EventBridge bus {} rejected webhook for app '{}': {}",
+                    config.event_bus_name, app_id, reason
+                )
+            );
+            return Err(Error::Other(format!(
+                "EventBridge rejected the event: {}",
+                reason
+            )));
+        }
+
+        info!(
+            "{}",
+            format!(
+                "Put webhook for app '{}' on EventBridge bus {}",
+                app_id, config.event_bus_name
+            )
+        );
+        Ok(())
+    }
+}
+
+impl Default for EventBridgeWebhookSender {
+    fn default() -> Self {
+        Self::new()
+    }
+}
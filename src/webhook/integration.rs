@@ -2,19 +2,24 @@
 use crate::app::config::App;
 use crate::app::manager::AppManager;
 use crate::error::{Error, Result};
+use crate::metrics::MetricsInterface;
+use crate::options::{
+    QueueConfig, WebhookCircuitBreakerConfig, WebhookRetryConfig, WebhookTlsConfig,
+};
 
+use crate::queue::JobProcessorFnAsync;
+use crate::queue::job::{Job, JobTask, WebhookJob};
 use crate::queue::manager::{QueueManager, QueueManagerFactory};
+use crate::redis_client::RedisAuth;
 use crate::webhook::sender::WebhookSender;
-use crate::webhook::types::{JobData, JobPayload};
-use serde_json::{Value, json};
+use crate::webhook::types::{BatchInfo, JobPayload, WebhookEvent};
+use serde_json::Value;
 use std::collections::HashMap;
-use std::future::Future;
-use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::Mutex;
 use tokio::time::interval;
-use tracing::{error, info};
+use tracing::{debug, error, info};
 
 /// Configuration for the webhook integration
 #[derive(Debug, Clone)]
@@ -23,10 +28,17 @@ pub struct WebhookConfig {
     pub batching: BatchingConfig,
     pub queue_driver: String,
     pub redis_url: Option<String>,
+    pub redis_auth: RedisAuth,
     pub redis_prefix: Option<String>,
     pub redis_concurrency: Option<usize>,
+    pub queue: QueueConfig,
     pub process_id: String,
     pub debug: bool,
+    /// How long shutdown waits for in-flight deliveries
+    pub shutdown_grace_period: Duration,
+    pub retry: WebhookRetryConfig,
+    pub circuit_breaker: WebhookCircuitBreakerConfig,
+    pub tls: WebhookTlsConfig,
 }
 
 impl Default for WebhookConfig {
@@ -36,10 +48,16 @@ impl Default for WebhookConfig {
             batching: BatchingConfig::default(),
             queue_driver: "redis".to_string(),
             redis_url: None,
+            redis_auth: RedisAuth::default(),
             redis_prefix: None,
             redis_concurrency: Some(5),
+            queue: QueueConfig::default(),
             process_id: uuid::Uuid::new_v4().to_string(),
             debug: false,
+            shutdown_grace_period: Duration::from_secs(10),
+            retry: WebhookRetryConfig::default(),
+            circuit_breaker: WebhookCircuitBreakerConfig::default(),
+            tls: WebhookTlsConfig::default(),
         }
     }
 }
@@ -49,6 +67,10 @@ impl Default for WebhookConfig {
 pub struct BatchingConfig {
     pub enabled: bool,
     pub duration: u64, // in milliseconds
+    /// Events that flush a batch before `duration` is up; 0 for no limit
+    pub max_batch_size: usize,
+    /// Serialized event bytes that flush a batch before `duration` is up; 0 for no limit
+    pub max_payload_bytes: usize,
 }
 
 impl Default for BatchingConfig {
@@ -56,19 +78,90 @@ impl Default for BatchingConfig {
         Self {
             enabled: false,
             duration: 50,
+            max_batch_size: 100,
+            max_payload_bytes: 256 * 1024,
         }
     }
 }
 
-pub type JobProcessorFnAsync = Box<
-    dyn Fn(JobData) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> + Send + Sync + 'static,
->;
+impl BatchingConfig {
+    fn is_full(&self, batch: &PendingBatch) -> bool {
+        (self.max_batch_size > 0 && batch.events.len() >= self.max_batch_size)
+            || (self.max_payload_bytes > 0 && batch.bytes >= self.max_payload_bytes)
+    }
+}
+
+/// Webhook events of one app collected for a single delivery
+struct PendingBatch {
+    events: Vec<WebhookEvent>,
+    /// Approximate serialized size of `events`
+    bytes: usize,
+    first_time_ms: i64,
+    last_time_ms: i64,
+    /// Shared by every job in the batch, or `None` once they differ
+    ordering_key: Option<String>,
+    signature: String,
+}
+
+impl PendingBatch {
+    fn new(payload: &JobPayload, ordering_key: &Option<String>, signature: &str) -> Self {
+        Self {
+            events: Vec::new(),
+            bytes: 0,
+            first_time_ms: payload.time_ms,
+            last_time_ms: payload.time_ms,
+            ordering_key: ordering_key.clone(),
+            signature: signature.to_string(),
+        }
+    }
+
+    fn add(&mut self, payload: JobPayload, ordering_key: &Option<String>) {
+        self.bytes += payload
+            .events
+            .iter()
+            .map(|event| serde_json::to_vec(event).map_or(0, |bytes| bytes.len()))
+            .sum::<usize>();
+        self.last_time_ms = payload.time_ms;
+        if self.ordering_key != *ordering_key {
+            self.ordering_key = None;
+        }
+        self.events.extend(payload.events);
+    }
+
+    /// One webhook job carrying every event of the batch
+    fn into_job(self, app_id: String) -> Job {
+        let count = self.events.len();
+        let job = Job::new(
+            app_id,
+            JobTask::Webhook(WebhookJob {
+                payload: JobPayload {
+                    time_ms: chrono::Utc::now().timestamp_millis(),
+                    events: self.events,
+                    batch: Some(BatchInfo {
+                        count,
+                        first_time_ms: self.first_time_ms,
+                        last_time_ms: self.last_time_ms,
+                    }),
+                },
+                original_signature: self.signature,
+            }),
+        );
+        match self.ordering_key {
+            Some(ordering_key) => job.with_ordering_key(ordering_key),
+            None => job,
+        }
+    }
+}
+
+/// Pending batches, keyed by queue name and app ID
+type Batches = HashMap<(String, String), PendingBatch>;
 
 /// Webhook integration for processing events
 pub struct WebhookIntegration {
     config: WebhookConfig,
-    batched_webhooks: Arc<Mutex<HashMap<String, Vec<JobData>>>>,
+    batched_webhooks: Arc<Mutex<Batches>>,
     queue_manager: Option<Arc<Mutex<QueueManager>>>,
+    webhook_sender: Option<Arc<WebhookSender>>,
     app_manager: Arc<dyn AppManager + Send + Sync>,
 }
 
@@ -81,6 +174,7 @@ impl WebhookIntegration {
             config,
             batched_webhooks: Arc::new(Mutex::new(HashMap::new())),
             queue_manager: None,
+            webhook_sender: None,
             app_manager,
         };
 
@@ -95,23 +189,45 @@ impl WebhookIntegration {
             let driver = QueueManagerFactory::create(
                 &self.config.queue_driver,
                 self.config.redis_url.as_deref(),
+                &self.config.redis_auth,
                 self.config.redis_prefix.as_deref(),
                 self.config.redis_concurrency,
+                &self.config.queue,
             )
             .await?;
-            let queue_manager = Arc::new(Mutex::new(QueueManager::new(driver)));
-            let webhook_sender = Arc::new(WebhookSender::new(self.app_manager.clone()));
+            let queue_manager = Arc::new(Mutex::new(
+                QueueManager::new(driver)
+                    .with_shutdown_grace_period(self.config.shutdown_grace_period)
+                    .with_autoscaling(&self.config.queue.autoscaling),
+            ));
+            let webhook_sender = Arc::new(WebhookSender::new(
+                self.app_manager.clone(),
+                self.config.retry.clone(),
+                self.config.circuit_breaker.clone(),
+                &self.config.tls,
+                self.config.rate_limit.clone(),
+            ));
             let queue_name = "webhooks".to_string();
             let sender_clone = webhook_sender.clone();
 
-            let processor: JobProcessorFnAsync = Box::new(move |job_data| {
+            let processor: JobProcessorFnAsync = Box::new(move |job: Job| {
                 let sender_for_task = sender_clone.clone();
                 Box::pin(async move {
                     info!(
                         "{}",
-                        format!("Processing webhook job from queue: {:?}", job_data.app_id)
+                        format!("Processing webhook job from queue: {:?}", job.app_id)
                     );
-                    sender_for_task.process_webhook_job(job_data).await
+                    match job.task {
+                        JobTask::Webhook(webhook_job) => {
+                            sender_for_task
+                                .process_webhook_job(&job.app_id, webhook_job)
+                                .await
+                        }
+                        other => Err(Error::Queue(format!(
+                            "Unexpected {} job on the webhooks queue",
+                            other.kind()
+                        ))),
+                    }
                 })
             });
 
@@ -120,6 +236,7 @@ impl WebhookIntegration {
                 manager.process_queue(&queue_name, processor).await?;
             }
             self.queue_manager = Some(queue_manager);
+            self.webhook_sender = Some(webhook_sender);
 
             if self.config.batching.enabled {
                 self.start_batching_task();
@@ -140,7 +257,7 @@ impl WebhookIntegration {
             let mut interval = interval(Duration::from_millis(batch_duration));
             loop {
                 interval.tick().await;
-                let webhooks_to_process: HashMap<String, Vec<JobData>> = {
+                let webhooks_to_process: Batches = {
                     let mut batched = batched_webhooks_clone.lock().await;
                     std::mem::take(&mut *batched)
                 };
@@ -151,24 +268,20 @@ impl WebhookIntegration {
                 info!(
                     "{}",
                     format!(
-                        "Processing {} batched webhook queues (Sockudo internal batching)",
+                        "Processing {} webhook batches (Sockudo internal batching)",
                         webhooks_to_process.len()
                     )
                 );
 
                 if let Some(manager_arc) = &queue_manager_clone {
-                    for (queue_name, jobs) in webhooks_to_process {
-                        let manager_locked = manager_arc.lock().await;
-                        for job in jobs {
-                            if let Err(e) = manager_locked.add_to_queue(&queue_name, job).await {
-                                error!(
-                                    "{}",
-                                    format!(
-                                        "Failed to add batched job to queue {}: {}",
-                                        queue_name, e
-                                    )
-                                );
-                            }
+                    let manager_locked = manager_arc.lock().await;
+                    for ((queue_name, app_id), batch) in webhooks_to_process {
+                        let job = batch.into_job(app_id);
+                        if let Err(e) = manager_locked.add_to_queue(&queue_name, job).await {
+                            error!(
+                                "{}",
+                                format!("Failed to add batched job to queue {}: {}", queue_name, e)
+                            );
                         }
                     }
                 }
@@ -180,17 +293,26 @@ impl WebhookIntegration {
         self.config.enabled
     }
 
-    async fn add_webhook(&self, queue_name: &str, job_data: JobData) -> Result<()> {
+    /// Queue manager webhook jobs go through, if queueing is enabled
+    pub fn queue_manager(&self) -> Option<Arc<Mutex<QueueManager>>> {
+        self.queue_manager.clone()
+    }
+
+    /// Report webhook delivery metrics, such as circuit breaker state, to `metrics`
+    pub async fn set_metrics(&self, metrics: Arc<Mutex<dyn MetricsInterface + Send + Sync>>) {
+        if let Some(webhook_sender) = &self.webhook_sender {
+            webhook_sender.set_metrics(metrics).await;
+        }
+    }
+
+    async fn add_webhook(&self, queue_name: &str, job_data: Job) -> Result<()> {
         if !self.config.enabled {
             return Ok(());
         }
-        if self.config.batching.enabled {
-            let mut batched = self.batched_webhooks.lock().await;
-            batched
-                .entry(queue_name.to_string())
-                .or_default()
-                .push(job_data);
-        } else if let Some(qm_arc) = &self.queue_manager {
+        if self.config.batching.enabled && matches!(job_data.task, JobTask::Webhook(_)) {
+            return self.add_to_batch(queue_name, job_data).await;
+        }
+        if let Some(qm_arc) = &self.queue_manager {
             let manager = qm_arc.lock().await;
             manager.add_to_queue(queue_name, job_data).await?;
         } else {
@@ -201,22 +323,79 @@ impl WebhookIntegration {
         Ok(())
     }
 
+    /// Add a job's events to its app's pending batch. A batch that reaches
+    /// `max_batch_size` events or `max_payload_bytes` is queued right away
+    /// instead of waiting for the batching interval.
+    async fn add_to_batch(&self, queue_name: &str, job: Job) -> Result<()> {
+        let JobTask::Webhook(webhook_job) = job.task else {
+            return Ok(());
+        };
+        let key = (queue_name.to_string(), job.app_id);
+        let full = {
+            let mut batched = self.batched_webhooks.lock().await;
+            let batch = batched.entry(key.clone()).or_insert_with(|| {
+                PendingBatch::new(
+                    &webhook_job.payload,
+                    &job.ordering_key,
+                    &webhook_job.original_signature,
+                )
+            });
+            batch.add(webhook_job.payload, &job.ordering_key);
+            if self.config.batching.is_full(batch) {
+                batched.remove(&key)
+            } else {
+                None
+            }
+        };
+
+        if let Some(batch) = full {
+            debug!(
+                "{}",
+                format!(
+                    "Flushing full webhook batch of {} events for app {}",
+                    batch.events.len(),
+                    key.1
+                )
+            );
+            let qm_arc = self.queue_manager.as_ref().ok_or_else(|| {
+                Error::InternalError("Queue manager not initialized for webhooks".to_string())
+            })?;
+            let manager = qm_arc.lock().await;
+            manager
+                .add_to_queue(queue_name, batch.into_job(key.1))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Only the event fields and the app id are queued; serialization and
+    /// signing happen on the consumer side, off the WebSocket/HTTP path.
+    /// Jobs are keyed by channel so drivers that partition by ordering key
+    /// deliver one channel's webhooks in the order its events happened.
     fn create_job_data(
         &self,
         app: &App,
-        events_payload: Vec<Value>,
-        original_signature_for_queue: &str,
-    ) -> JobData {
-        let job_payload = JobPayload {
-            time_ms: chrono::Utc::now().timestamp_millis(),
-            events: events_payload,
-        };
-        JobData {
-            app_key: app.key.clone(),
-            app_id: app.id.clone(),
-            app_secret: app.secret.clone(),
-            payload: job_payload,
-            original_signature: original_signature_for_queue.to_string(),
+        events: Vec<WebhookEvent>,
+        original_signature_for_queue: String,
+    ) -> Job {
+        let channel = events
+            .first()
+            .and_then(WebhookEvent::channel)
+            .map(|channel| format!("{}:{}", app.id, channel));
+        let job = Job::new(
+            app.id.clone(),
+            JobTask::Webhook(WebhookJob {
+                payload: JobPayload {
+                    time_ms: chrono::Utc::now().timestamp_millis(),
+                    events,
+                    batch: None,
+                },
+                original_signature: original_signature_for_queue,
+            }),
+        );
+        match channel {
+            Some(ordering_key) => job.with_ordering_key(ordering_key),
+            None => job,
         }
     }
 
@@ -227,20 +406,26 @@ impl WebhookIntegration {
         app.webhooks.as_ref().map_or(false, |webhooks| {
             webhooks
                 .iter()
-                .any(|wh_config| wh_config.event_types.contains(&event_type_name.to_string()))
+                .any(|wh_config| wh_config.subscribes_to(event_type_name))
         })
     }
 
+    /// Whether `app` has a webhook for `channel_occupied` or `channel_vacated`,
+    /// and so needs the adapter to track which node occupies its channels
+    pub async fn tracks_channel_existence(&self, app: &App) -> bool {
+        self.should_send_webhook(app, "channel_occupied").await
+            || self.should_send_webhook(app, "channel_vacated").await
+    }
+
     pub async fn send_channel_occupied(&self, app: &App, channel: &str) -> Result<()> {
         if !self.should_send_webhook(app, "channel_occupied").await {
             return Ok(());
         }
-        let event_obj = json!({
-            "name": "channel_occupied",
-            "channel": channel
-        });
+        let event = WebhookEvent::ChannelOccupied {
+            channel: channel.to_string(),
+        };
         let signature = format!("{}:{}:channel_occupied", app.id, channel);
-        let job_data = self.create_job_data(app, vec![event_obj], &signature);
+        let job_data = self.create_job_data(app, vec![event], signature);
         self.add_webhook("webhooks", job_data).await
     }
 
@@ -248,12 +433,11 @@ impl WebhookIntegration {
         if !self.should_send_webhook(app, "channel_vacated").await {
             return Ok(());
         }
-        let event_obj = json!({
-            "name": "channel_vacated",
-            "channel": channel
-        });
+        let event = WebhookEvent::ChannelVacated {
+            channel: channel.to_string(),
+        };
         let signature = format!("{}:{}:channel_vacated", app.id, channel);
-        let job_data = self.create_job_data(app, vec![event_obj], &signature);
+        let job_data = self.create_job_data(app, vec![event], signature);
         self.add_webhook("webhooks", job_data).await
     }
 
@@ -261,13 +445,12 @@ impl WebhookIntegration {
         if !self.should_send_webhook(app, "member_added").await {
             return Ok(());
         }
-        let event_obj = json!({
-            "name": "member_added",
-            "channel": channel,
-            "user_id": user_id
-        });
+        let event = WebhookEvent::MemberAdded {
+            channel: channel.to_string(),
+            user_id: user_id.to_string(),
+        };
         let signature = format!("{}:{}:{}:member_added", app.id, channel, user_id);
-        let job_data = self.create_job_data(app, vec![event_obj], &signature);
+        let job_data = self.create_job_data(app, vec![event], signature);
         self.add_webhook("webhooks", job_data).await
     }
 
@@ -275,13 +458,12 @@ impl WebhookIntegration {
         if !self.should_send_webhook(app, "member_removed").await {
             return Ok(());
         }
-        let event_obj = json!({
-            "name": "member_removed",
-            "channel": channel,
-            "user_id": user_id
-        });
+        let event = WebhookEvent::MemberRemoved {
+            channel: channel.to_string(),
+            user_id: user_id.to_string(),
+        };
         let signature = format!("{}:{}:{}:member_removed", app.id, channel, user_id);
-        let job_data = self.create_job_data(app, vec![event_obj], &signature);
+        let job_data = self.create_job_data(app, vec![event], signature);
         self.add_webhook("webhooks", job_data).await
     }
 
@@ -298,19 +480,17 @@ impl WebhookIntegration {
             return Ok(());
         }
 
-        let mut client_event_pusher_payload = json!({
-            "name": "client_event",
-            "channel": channel,
-            "event": event_name,
-            "data": event_data,
-            "socket_id": socket_id,
-        });
-
-        if channel.starts_with("presence-") {
-            if let Some(uid) = user_id {
-                client_event_pusher_payload["user_id"] = json!(uid);
-            }
-        }
+        let event = WebhookEvent::ClientEvent {
+            channel: channel.to_string(),
+            event: event_name.to_string(),
+            data: event_data,
+            socket_id: socket_id.map(str::to_string),
+            user_id: if channel.starts_with("presence-") {
+                user_id.map(str::to_string)
+            } else {
+                None
+            },
+        };
 
         let signature = format!(
             "{}:{}:{}:client_event",
@@ -318,7 +498,7 @@ impl WebhookIntegration {
             channel,
             socket_id.unwrap_or("unknown")
         );
-        let job_data = self.create_job_data(app, vec![client_event_pusher_payload], &signature);
+        let job_data = self.create_job_data(app, vec![event], signature);
         self.add_webhook("webhooks", job_data).await
     }
 
@@ -326,12 +506,32 @@ impl WebhookIntegration {
         if !self.should_send_webhook(app, "cache_miss").await {
             return Ok(());
         }
-        let event_obj = json!({
-            "name": "cache_miss",
-            "channel": channel
-        });
+        let event = WebhookEvent::CacheMiss {
+            channel: channel.to_string(),
+        };
         let signature = format!("{}:{}:cache_miss", app.id, channel);
-        let job_data = self.create_job_data(app, vec![event_obj], &signature);
+        let job_data = self.create_job_data(app, vec![event], signature);
+        self.add_webhook("webhooks", job_data).await
+    }
+
+    /// Sends a webhook when a user is banned through the moderation API.
+    pub async fn send_user_banned(
+        &self,
+        app: &App,
+        user_id: &str,
+        ttl_seconds: u64,
+        reason: Option<&str>,
+    ) -> Result<()> {
+        if !self.should_send_webhook(app, "user_banned").await {
+            return Ok(());
+        }
+        let event = WebhookEvent::UserBanned {
+            user_id: user_id.to_string(),
+            ttl: ttl_seconds,
+            reason: reason.map(str::to_string),
+        };
+        let signature = format!("{}:{}:user_banned", app.id, user_id);
+        let job_data = self.create_job_data(app, vec![event], signature);
         self.add_webhook("webhooks", job_data).await
     }
 
@@ -347,12 +547,10 @@ impl WebhookIntegration {
             return Ok(());
         }
 
-        // Construct the event payload according to Pusher documentation
-        let event_obj = json!({
-            "name": "subscription_count",
-            "channel": channel,
-            "subscription_count": subscription_count // Pusher docs show this as a string, but number is more common
-        });
+        let event = WebhookEvent::SubscriptionCount {
+            channel: channel.to_string(),
+            subscription_count,
+        };
 
         // Create a unique signature for queue deduplication or logging if needed
         let signature = format!(
@@ -360,7 +558,7 @@ impl WebhookIntegration {
             app.id, channel, subscription_count
         );
 
-        let job_data = self.create_job_data(app, vec![event_obj], &signature);
+        let job_data = self.create_job_data(app, vec![event], signature);
         self.add_webhook("webhooks", job_data).await
     }
 }
@@ -0,0 +1,121 @@
+// src/webhook/kafka_sender.rs
+
+use crate::error::{Error, Result};
+use crate::webhook::types::KafkaWebhookConfig;
+use rdkafka::ClientConfig;
+use rdkafka::message::{Header, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::util::Timeout;
+use std::time::Duration;
+use tracing::{error, info};
+
+const PRODUCE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Produces webhooks to Kafka topics
+#[derive(Clone)]
+pub struct KafkaWebhookSender {
+    // Cache producers by cluster and credentials to avoid recreating them
+    producers: dashmap::DashMap<String, FutureProducer>,
+}
+
+impl KafkaWebhookSender {
+    pub fn new() -> Self {
+        Self {
+            producers: dashmap::DashMap::new(),
+        }
+    }
+
+    fn get_producer(&self, config: &KafkaWebhookConfig) -> Result<FutureProducer> {
+        let cache_key = format!(
+            "{}|{}|{}",
+            config.brokers.join(","),
+            config.security_protocol.as_deref().unwrap_or_default(),
+            config.sasl_username.as_deref().unwrap_or_default()
+        );
+        if let Some(producer) = self.producers.get(&cache_key) {
+            return Ok(producer.clone());
+        }
+
+        let mut client_config = ClientConfig::new();
+        client_config
+            .set("bootstrap.servers", config.brokers.join(","))
+            .set(
+                "message.timeout.ms",
+                PRODUCE_TIMEOUT.as_millis().to_string(),
+            );
+        if let Some(protocol) = &config.security_protocol {
+            client_config.set("security.protocol", protocol);
+        }
+        if let Some(mechanism) = &config.sasl_mechanism {
+            client_config.set("sasl.mechanism", mechanism);
+        }
+        if let Some(username) = &config.sasl_username {
+            client_config.set("sasl.username", username);
+        }
+        if let Some(password) = &config.sasl_password {
+            client_config.set("sasl.password", password);
+        }
+        let producer: FutureProducer = client_config
+            .create()
+            .map_err(|e| Error::Other(format!("Failed to create Kafka producer: {}", e)))?;
+        self.producers.insert(cache_key, producer.clone());
+        Ok(producer)
+    }
+
+    /// Produce the webhook body to the webhook's topic for `app_id` and,
+    /// when the topic is split per event type, `event_name`. Records are
+    /// keyed by app ID, so each app's events stay ordered within a partition,
+    /// and carry `app_id` and `event_names` headers.
+    pub async fn produce(
+        &self,
+        config: &KafkaWebhookConfig,
+        app_id: &str,
+        event_names: &[&str],
+        body: &str,
+    ) -> Result<()> {
+        let producer = self.get_producer(config)?;
+        let topic = config.topic_for(app_id, event_names.first().copied().unwrap_or_default());
+        let event_names_json = serde_json::to_string(event_names)?;
+        let headers = OwnedHeaders::new()
+            .insert(Header {
+                key: "app_id",
+                value: Some(app_id),
+            })
+            .insert(Header {
+                key: "event_names",
+                value: Some(&event_names_json),
+            });
+
+        let (partition, offset) = producer
+            .send(
+                FutureRecord::to(&topic)
+                    .key(app_id)
+                    .payload(body)
+                    .headers(headers),
+                Timeout::After(PRODUCE_TIMEOUT),
+            )
+            .await
+            .map_err(|(e, _)| {
+                error!(
+                    "{}",
+                    format!("Failed to produce webhook to Kafka topic {}: {}", topic, e)
+                );
+                Error::Other(format!("Failed to produce to Kafka topic: {}", e))
+            })?;
+
+        info!(
+            "{}",
+            format!(
+                "Produced webhook for app '{}' to Kafka topic {} (partition {}, offset {})",
+                app_id, topic, partition, offset
+            )
+        );
+        Ok(())
+    }
+}
+
+impl Default for KafkaWebhookSender {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -2,16 +2,13 @@
 
 use crate::error::{Error, Result};
 
-use crate::webhook::types::{LambdaConfig, Webhook}; // Added PusherWebhookPayload for clarity
-use aws_config::meta::region::RegionProviderChain;
+use crate::webhook::aws::sdk_config;
+use crate::webhook::types::{LambdaConfig, LambdaInvocationType, Webhook};
 use aws_sdk_lambda::Client as LambdaClient;
-use aws_sdk_lambda::config::Region; // Credentials not directly used here for client creation
-use aws_sdk_lambda::error::SdkError; // Keep for sync if needed
-use aws_sdk_lambda::operation::invoke::{InvokeError, InvokeOutput}; // Keep for sync if needed
 use aws_sdk_lambda::primitives::Blob;
 use aws_sdk_lambda::types::InvocationType;
-use serde_json::{Value, json};
-use std::time::Duration;
+use serde_json::Value;
+use std::borrow::Cow;
 use tracing::{error, info, warn};
 
 /// Handles invoking AWS Lambda functions for webhooks
@@ -35,28 +32,55 @@ impl LambdaWebhookSender {
             return Ok(client_ref.clone());
         }
 
-        let region_provider = RegionProviderChain::first_try(Region::new(region.to_string()))
-            .or_default_provider()
-            .or_else(Region::new("us-east-1"));
-
-        let shared_config = aws_config::from_env()
-            .region(region_provider)
-            .timeout_config(
-                aws_sdk_lambda::config::timeout::TimeoutConfig::builder()
-                    .operation_timeout(Duration::from_secs(10))
-                    .build(),
-            )
-            .load()
-            .await;
-
+        let shared_config = sdk_config(region).await;
         let client = LambdaClient::new(&shared_config);
         self.clients.insert(region.to_string(), client.clone());
         Ok(client)
     }
 
+    /// The webhook's Lambda configuration, building one for the legacy
+    /// `lambda_function` field
+    fn lambda_config<'a>(webhook: &'a Webhook, app_id: &str) -> Result<Cow<'a, LambdaConfig>> {
+        if let Some(config) = &webhook.lambda {
+            return Ok(Cow::Borrowed(config));
+        }
+        match &webhook.lambda_function {
+            Some(function_name) => {
+                warn!(
+                    "{}",
+                    format!(
+                        "Webhook for app {} uses legacy 'lambda_function' field. Defaulting region to 'us-east-1'. Consider updating to structured 'lambda' config.",
+                        app_id
+                    )
+                );
+                Ok(Cow::Owned(LambdaConfig {
+                    function_name: function_name.clone(),
+                    region: "us-east-1".to_string(),
+                    qualifier: None,
+                    invocation_type: LambdaInvocationType::default(),
+                }))
+            }
+            None => {
+                error!(
+                    "{}",
+                    format!(
+                        "Missing Lambda configuration in webhook for app_id: {}",
+                        app_id
+                    )
+                );
+                Err(Error::InternalError(
+                    "Missing Lambda configuration: Neither 'lambda' struct nor 'lambda_function' string provided.".to_string(),
+                ))
+            }
+        }
+    }
+
     /// Invoke a Lambda function with the provided webhook and payload.
     /// The `payload` argument is expected to be the complete PusherWebhookPayload.
     /// The `triggering_event_name` is for logging/context, could be "batch_events" or a specific event.
+    ///
+    /// `event` invocations succeed once Lambda has queued the payload;
+    /// `request_response` invocations also fail when the function throws.
     pub async fn invoke_lambda(
         &self,
         webhook: &Webhook,
@@ -64,51 +88,8 @@ impl LambdaWebhookSender {
         app_id: &str,
         pusher_webhook_payload: Value,
     ) -> Result<()> {
-        // This `temp_owned_config` allows us to create an owned LambdaConfig if needed
-        // for the legacy `lambda_function` case, and then take a reference to it.
-        // It must be declared outside the match so its lifetime extends for `lambda_config_ref`.
-        let temp_owned_config: LambdaConfig;
-
-        let lambda_config_ref: &LambdaConfig = match &webhook.lambda {
-            Some(config_struct) => {
-                // If the structured 'lambda' field exists, use it directly.
-                config_struct
-            }
-            None => {
-                // If 'lambda' is None, try the legacy 'lambda_function' string.
-                if let Some(function_name_str) = &webhook.lambda_function {
-                    warn!(
-                        "{}",
-                        format!(
-                            "Webhook for app {} uses legacy 'lambda_function' field. Defaulting region to 'us-east-1'. Consider updating to structured 'lambda' config.",
-                            app_id
-                        )
-                    );
-                    // Assign to the outer `temp_owned_config`
-                    temp_owned_config = LambdaConfig {
-                        function_name: function_name_str.clone(),
-                        region: "us-east-1".to_string(),
-                    };
-                    // Now `lambda_config_ref` can borrow from `temp_owned_config`.
-                    &temp_owned_config
-                } else {
-                    // If both 'lambda' and 'lambda_function' are None, it's an error.
-                    error!(
-                        "{}",
-                        format!(
-                            "Missing Lambda configuration in webhook for app_id: {}",
-                            app_id
-                        )
-                    );
-                    return Err(Error::InternalError(
-                        "Missing Lambda configuration: Neither 'lambda' struct nor 'lambda_function' string provided.".to_string(),
-                    ));
-                }
-            }
-        };
-
-        // Now `lambda_config_ref` is guaranteed to be a valid reference to a LambdaConfig.
-        let client = self.get_client(&lambda_config_ref.region).await?;
+        let lambda_config = Self::lambda_config(webhook, app_id)?;
+        let client = self.get_client(&lambda_config.region).await?;
 
         let payload_bytes = serde_json::to_vec(&pusher_webhook_payload).map_err(|e| {
             Error::Other(format!(
@@ -120,153 +101,69 @@ impl LambdaWebhookSender {
         info!(
             "{}",
             format!(
-                "Invoking Lambda function '{}' in region '{}' for app '{}', triggered by '{}'. Payload size: {} bytes.",
-                lambda_config_ref.function_name,
-                lambda_config_ref.region,
+                "Invoking Lambda function '{}' ({:?}) in region '{}' for app '{}', triggered by '{}'. Payload size: {} bytes.",
+                lambda_config.function_name,
+                lambda_config.invocation_type,
+                lambda_config.region,
                 app_id,
                 triggering_event_name,
                 payload_bytes.len()
             )
         );
 
-        match client
+        let invocation_type = match lambda_config.invocation_type {
+            LambdaInvocationType::Event => InvocationType::Event,
+            LambdaInvocationType::RequestResponse => InvocationType::RequestResponse,
+        };
+        let output = client
             .invoke()
-            .function_name(&lambda_config_ref.function_name)
+            .function_name(&lambda_config.function_name)
+            .set_qualifier(lambda_config.qualifier.clone())
             .payload(Blob::new(payload_bytes))
-            .invocation_type(InvocationType::Event)
+            .invocation_type(invocation_type)
             .send()
             .await
-        {
-            Ok(_) => {
-                info!(
-                    "{}",
-                    format!(
-                        "Successfully invoked Lambda function {} for app '{}', triggered by '{}'",
-                        lambda_config_ref.function_name, app_id, triggering_event_name
-                    )
-                );
-                Ok(())
-            }
-            Err(e) => {
+            .map_err(|e| {
                 error!(
                     "{}",
                     format!(
                         "Failed to invoke Lambda function {}: {}",
-                        lambda_config_ref.function_name, e
+                        lambda_config.function_name, e
                     )
                 );
-                Err(Error::Other(format!(
-                    "Failed to invoke Lambda function: {}",
-                    e
-                )))
-            }
+                Error::Other(format!("Failed to invoke Lambda function: {}", e))
+            })?;
+
+        // The invoke call succeeds even when the function itself throws
+        if let Some(function_error) = output.function_error() {
+            let details = output
+                .payload()
+                .map(|payload| String::from_utf8_lossy(payload.as_ref()).to_string())
+                .unwrap_or_default();
+            error!(
+                "{}",
+                format!(
+                    "Lambda function {} failed ({}): {}",
+                    lambda_config.function_name, function_error, details
+                )
+            );
+            return Err(Error::Other(format!(
+                "Lambda function {} failed: {}",
+                lambda_config.function_name, function_error
+            )));
         }
-    }
-
-    /// Invoke a Lambda function synchronously and parse the response
-    #[allow(dead_code)]
-    pub async fn invoke_lambda_sync(
-        &self,
-        webhook: &Webhook,
-        triggering_event_name: &str,
-        app_id: &str,
-        pusher_webhook_payload: Value,
-    ) -> Result<Option<Value>> {
-        let temp_owned_config: LambdaConfig;
-        let lambda_config_ref: &LambdaConfig = match &webhook.lambda {
-            Some(config_struct) => config_struct,
-            None => {
-                if let Some(function_name_str) = &webhook.lambda_function {
-                    temp_owned_config = LambdaConfig {
-                        function_name: function_name_str.clone(),
-                        region: "us-east-1".to_string(),
-                    };
-                    &temp_owned_config
-                } else {
-                    return Err(Error::InternalError(
-                        "Missing Lambda configuration".to_string(),
-                    ));
-                }
-            }
-        };
-
-        let client = self.get_client(&lambda_config_ref.region).await?;
-
-        let payload_bytes = serde_json::to_vec(&pusher_webhook_payload).map_err(|e| {
-            Error::Other(format!(
-                "Failed to serialize Pusher Webhook payload for Lambda sync: {}",
-                e
-            ))
-        })?;
 
         info!(
             "{}",
             format!(
-                "Invoking Lambda function {} synchronously for app '{}', triggered by '{}'",
-                lambda_config_ref.function_name, app_id, triggering_event_name
+                "Successfully invoked Lambda function {} for app '{}', triggered by '{}' (status {})",
+                lambda_config.function_name,
+                app_id,
+                triggering_event_name,
+                output.status_code()
             )
         );
-
-        let result: core::result::Result<InvokeOutput, SdkError<InvokeError>> = client
-            .invoke()
-            .function_name(&lambda_config_ref.function_name)
-            .payload(Blob::new(payload_bytes))
-            .invocation_type(InvocationType::RequestResponse)
-            .send()
-            .await;
-
-        match result {
-            Ok(output) => {
-                if let Some(response_payload_blob) = output.payload() {
-                    match serde_json::from_slice::<Value>(response_payload_blob.as_ref()) {
-                        Ok(json_response) => {
-                            info!(
-                                "{}",
-                                format!(
-                                    "Received response from Lambda function {}: {:?}",
-                                    lambda_config_ref.function_name, json_response
-                                )
-                            );
-                            Ok(Some(json_response))
-                        }
-                        Err(e) => {
-                            warn!(
-                                "{}",
-                                format!(
-                                    "Failed to parse Lambda response as JSON: {}. Raw response: {:?}",
-                                    e, response_payload_blob
-                                )
-                            );
-                            let response_str =
-                                String::from_utf8_lossy(response_payload_blob.as_ref());
-                            Ok(Some(json!({"raw_response": response_str.to_string() })))
-                        }
-                    }
-                } else {
-                    info!(
-                        "{}",
-                        format!(
-                            "Lambda function {} returned no payload",
-                            lambda_config_ref.function_name
-                        )
-                    );
-                    Ok(None)
-                }
-            }
-            Err(e) => {
-                error!(
-                    "{}",
-                    format!(
-                        "Failed to invoke Lambda function {} synchronously: {}",
-                        lambda_config_ref.function_name, e
-                    )
-                );
-                Err(Error::Other(format!(
-                    "Failed to invoke Lambda function synchronously: {}",
-                    e
-                )))
-            }
-        }
+        Ok(())
     }
 }
 
@@ -1,4 +1,11 @@
+pub mod aws;
+pub mod circuit_breaker;
+pub mod eventbridge_sender;
 pub mod integration;
+pub mod kafka_sender;
 pub mod lambda_sender;
+pub mod rate_limiter;
 pub mod sender;
+pub mod sns_sender;
+pub mod transform;
 pub mod types;
@@ -0,0 +1,70 @@
+// src/webhook/rate_limiter.rs
+//! Per-endpoint pacing of webhook deliveries. Each URL may take `burst`
+//! deliveries at once and then `requests_per_second`; deliveries over the
+//! limit wait for their turn instead of being sent or dropped, so a spike in
+//! channel activity reaches the endpoint smoothed out. A delivery that would
+//! wait longer than `max_delay_ms` fails its job instead, and the queue
+//! retries it later. Limits apply per node.
+
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use tracing::debug;
+
+use crate::error::{Error, Result};
+use crate::options::WebhookRateLimitConfig;
+
+pub struct DestinationRateLimiter {
+    config: WebhookRateLimitConfig,
+    /// When the next delivery to each URL is due if it had no burst allowance
+    schedules: DashMap<String, Instant>,
+}
+
+impl DestinationRateLimiter {
+    pub fn new(config: WebhookRateLimitConfig) -> Self {
+        Self {
+            config,
+            schedules: DashMap::new(),
+        }
+    }
+
+    /// Wait until a delivery to `url` fits its limit, which is the webhook's
+    /// own when it sets one and the global one otherwise
+    pub async fn acquire(&self, url: &str, limit: Option<&WebhookRateLimitConfig>) -> Result<()> {
+        let limit = limit.unwrap_or(&self.config);
+        if limit.requests_per_second == 0 {
+            return Ok(());
+        }
+        let interval = Duration::from_secs_f64(1.0 / limit.requests_per_second as f64);
+        let burst_allowance = interval * limit.burst.max(1).saturating_sub(1);
+
+        let wait = {
+            let now = Instant::now();
+            let mut due = self.schedules.entry(url.to_string()).or_insert(now);
+            let next = (*due).max(now);
+            let wait = next
+                .saturating_duration_since(now)
+                .saturating_sub(burst_allowance);
+            if wait > Duration::from_millis(limit.max_delay_ms) {
+                return Err(Error::Other(format!(
+                    "Webhook {} is over its rate limit of {}/s, delivery postponed",
+                    url, limit.requests_per_second
+                )));
+            }
+            *due = next + interval;
+            wait
+        };
+        if !wait.is_zero() {
+            debug!(
+                "{}",
+                format!(
+                    "Delaying webhook to {} by {}ms for its rate limit",
+                    url,
+                    wait.as_millis()
+                )
+            );
+            tokio::time::sleep(wait).await;
+        }
+        Ok(())
+    }
+}
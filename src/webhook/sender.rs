@@ -3,59 +3,130 @@
 use crate::app::manager::AppManager; // Keep for AppManager trait
 use crate::error::{Error, Result};
 
+use crate::metrics::MetricsInterface;
+use crate::options::{
+    WebhookCircuitBreakerConfig, WebhookRateLimitConfig, WebhookRetryConfig, WebhookTlsConfig,
+};
+use crate::webhook::circuit_breaker::CircuitBreaker;
+use crate::webhook::eventbridge_sender::EventBridgeWebhookSender;
+use crate::webhook::kafka_sender::KafkaWebhookSender;
 use crate::webhook::lambda_sender::LambdaWebhookSender;
-// JobData now contains app_secret and its payload.events is Vec<Value>
+use crate::webhook::rate_limiter::DestinationRateLimiter;
+use crate::webhook::sns_sender::SnsWebhookSender;
+// WebhookJob carries minimal event data; the payload is built and signed here
 // PusherWebhookPayload is the structure for the final POST body
+use crate::queue::job::WebhookJob;
 use crate::token::Token; // For HMAC SHA256 signing
-use crate::webhook::types::{JobData, PusherWebhookPayload, Webhook};
-use reqwest::{Client, header};
+use crate::webhook::types::{PusherWebhookPayload, Webhook};
+use dashmap::DashMap;
+use reqwest::{Certificate, Client, Identity, header};
 use serde_json::{Value, json}; // Keep json! and Value
 use std::collections::HashMap;
-use std::future::Future;
-use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::Semaphore;
+use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore};
 use tracing::{error, info, warn};
 
-pub type JobProcessorFnAsync = Box<
-    dyn Fn(JobData) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> + Send + Sync + 'static,
->;
-
 const MAX_CONCURRENT_WEBHOOKS: usize = 20;
 
+/// Headers Pusher webhook verifiers read, which custom headers may not replace
+const SIGNING_HEADERS: [&str; 3] = ["content-type", "x-pusher-key", "x-pusher-signature"];
+
+#[derive(Clone)]
 pub struct WebhookSender {
     client: Client,
-    app_manager: Arc<dyn AppManager + Send + Sync>, // Still needed to fetch App if JobData doesn't have full App
+    /// Clients of webhooks with their own client certificate
+    tls_clients: DashMap<WebhookTlsConfig, Client>,
+    app_manager: Arc<dyn AppManager + Send + Sync>, // Jobs only carry the app ID
     lambda_sender: LambdaWebhookSender,
+    sns_sender: SnsWebhookSender,
+    eventbridge_sender: EventBridgeWebhookSender,
+    kafka_sender: KafkaWebhookSender,
     webhook_semaphore: Arc<Semaphore>,
+    retry: WebhookRetryConfig,
+    circuit_breaker: Arc<CircuitBreaker>,
+    rate_limiter: Arc<DestinationRateLimiter>,
+}
+
+impl WebhookRetryConfig {
+    /// Delay before retry number `attempt`: `backoff_base_ms * 2^(attempt - 1)`,
+    /// capped at `backoff_max_ms`, then jittered over its upper half so
+    /// deliveries that failed together don't retry together
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(32);
+        let delay = self
+            .backoff_base_ms
+            .saturating_mul(1u64 << exponent)
+            .min(self.backoff_max_ms);
+        Duration::from_millis(rand::random_range(delay / 2..=delay))
+    }
+}
+
+/// Why a delivery attempt failed
+enum DeliveryFailure {
+    /// 5xx, 429, timeouts and connection errors, which may pass
+    Transient(Error),
+    /// Any other non-2xx answer, which a retry would only repeat
+    Rejected(Error),
 }
 
 impl WebhookSender {
-    pub fn new(app_manager: Arc<dyn AppManager + Send + Sync>) -> Self {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(10)) // Timeout for HTTP requests
-            .build()
-            .unwrap_or_default();
+    pub fn new(
+        app_manager: Arc<dyn AppManager + Send + Sync>,
+        retry: WebhookRetryConfig,
+        circuit_breaker: WebhookCircuitBreakerConfig,
+        tls: &WebhookTlsConfig,
+        rate_limit: WebhookRateLimitConfig,
+    ) -> Self {
+        let client = build_client(tls).unwrap_or_else(|e| {
+            error!(
+                "{}",
+                format!("{}, sending webhooks without a client certificate", e)
+            );
+            build_client(&WebhookTlsConfig::default()).unwrap_or_default()
+        });
         Self {
             client,
+            tls_clients: DashMap::new(),
             app_manager,
             lambda_sender: LambdaWebhookSender::new(),
+            sns_sender: SnsWebhookSender::new(),
+            eventbridge_sender: EventBridgeWebhookSender::new(),
+            kafka_sender: KafkaWebhookSender::new(),
             webhook_semaphore: Arc::new(Semaphore::new(MAX_CONCURRENT_WEBHOOKS)),
+            retry,
+            circuit_breaker: Arc::new(CircuitBreaker::new(circuit_breaker)),
+            rate_limiter: Arc::new(DestinationRateLimiter::new(rate_limit)),
         }
     }
 
-    pub async fn process_webhook_job(&self, job: JobData) -> Result<()> {
-        let app_key = job.app_key.clone();
-        let app_id = job.app_id.clone();
+    /// Client for a webhook, built once per distinct client certificate
+    fn client_for(&self, webhook: &Webhook) -> Result<Client> {
+        let Some(tls) = &webhook.tls else {
+            return Ok(self.client.clone());
+        };
+        if let Some(client) = self.tls_clients.get(tls) {
+            return Ok(client.clone());
+        }
+        let client = build_client(tls)?;
+        self.tls_clients.insert(tls.clone(), client.clone());
+        Ok(client)
+    }
+
+    /// Report circuit breaker state changes to `metrics`
+    pub async fn set_metrics(&self, metrics: Arc<Mutex<dyn MetricsInterface + Send + Sync>>) {
+        self.circuit_breaker.set_metrics(metrics).await;
+    }
+
+    pub async fn process_webhook_job(&self, app_id: &str, job: WebhookJob) -> Result<()> {
+        let app_id = app_id.to_string();
         info!(
             "{}",
             format!("Processing webhook job for app_id: {}", app_id.clone())
         );
 
-        // The App struct (or at least its webhooks configuration) is needed.
-        // If JobData doesn't contain the full App.webhooks, we fetch it.
-        // For simplicity, let's assume JobData might not have the Webhook configs, so we fetch the app.
+        // Jobs only carry the app ID; the key, secret and webhook configuration
+        // come from the app itself.
         let app_config = match self.app_manager.find_by_id(&app_id.clone()).await? {
             Some(app) => app,
             None => {
@@ -86,46 +157,38 @@ impl WebhookSender {
             return Ok(());
         }
 
-        // Construct the Pusher-compatible payload body
-        // The `job.payload.events` is already a Vec<Value> where each Value is a Pusher event object.
-        let pusher_payload_body = PusherWebhookPayload {
-            time_ms: job.payload.time_ms,
-            events: job.payload.events.clone(), // Clone the events for the payload
-        };
-
-        // Serialize the payload body to JSON string for signing and sending
-        let body_json_string = serde_json::to_string(&pusher_payload_body).map_err(|e| {
-            Error::SerializationError(format!("Failed to serialize webhook body: {}", e))
-        })?;
-
-        // Create the HMAC SHA256 signature
-        // The app_secret is now in JobData
-        let signature =
-            Token::new(job.app_key.clone(), job.app_secret.clone()).sign(&body_json_string);
-
-        let mut tasks = Vec::new();
-
-        // Iterate through events in the job to find matching webhook configurations
-        // Pusher webhooks are configured per event type. A single job might trigger webhooks
-        // if any of its contained events match a webhook configuration.
+        // Collect the webhook configurations interested in any event of this job
+        // before the events move into the payload body
         let mut relevant_webhook_configs: HashMap<String, &Webhook> = HashMap::new();
 
-        for event_value in &job.payload.events {
-            if let Some(event_name) = event_value.get("name").and_then(Value::as_str) {
-                for wh_config in webhook_configurations {
-                    if wh_config.event_types.contains(&event_name.to_string()) {
-                        // Use webhook URL or function name as key to avoid duplicate tasks for the same endpoint
-                        let key = wh_config
-                            .url
-                            .as_ref()
-                            .map(|u| u.to_string())
-                            .or_else(|| wh_config.lambda_function.clone())
-                            .or_else(|| wh_config.lambda.as_ref().map(|l| l.function_name.clone()))
-                            .unwrap_or_else(String::new); // Should have one
-
-                        if !key.is_empty() {
-                            relevant_webhook_configs.entry(key).or_insert(wh_config);
-                        }
+        for event in &job.payload.events {
+            let event_name = event.name();
+            for wh_config in webhook_configurations {
+                if wh_config.subscribes_to(event_name) {
+                    // Use the webhook's target as key to avoid duplicate tasks for the same endpoint
+                    let key = wh_config
+                        .url
+                        .as_ref()
+                        .map(|u| u.to_string())
+                        .or_else(|| wh_config.lambda_function.clone())
+                        .or_else(|| wh_config.lambda.as_ref().map(|l| l.function_name.clone()))
+                        .or_else(|| wh_config.sns.as_ref().map(|sns| sns.topic_arn.clone()))
+                        .or_else(|| {
+                            wh_config
+                                .eventbridge
+                                .as_ref()
+                                .map(|eb| format!("{}/{}", eb.region, eb.event_bus_name))
+                        })
+                        .or_else(|| {
+                            wh_config
+                                .kafka
+                                .as_ref()
+                                .map(|kafka| format!("{}/{}", kafka.brokers.join(","), kafka.topic))
+                        })
+                        .unwrap_or_else(String::new); // Should have one
+
+                    if !key.is_empty() {
+                        relevant_webhook_configs.entry(key).or_insert(wh_config);
                     }
                 }
             }
@@ -142,124 +205,394 @@ impl WebhookSender {
             return Ok(());
         }
 
+        let mut event_names: Vec<&'static str> =
+            job.payload.events.iter().map(|e| e.name()).collect();
+        event_names.sort_unstable();
+        event_names.dedup();
+
+        // Construct the Pusher-compatible payload body; events serialize to Pusher event objects
+        let pusher_payload_body = PusherWebhookPayload {
+            time_ms: job.payload.time_ms,
+            events: job.payload.events,
+            batch: job.payload.batch,
+        };
+
+        // Serialize the payload body to JSON string for signing and sending
+        let body_json_string = serde_json::to_string(&pusher_payload_body).map_err(|e| {
+            Error::SerializationError(format!("Failed to serialize webhook body: {}", e))
+        })?;
+
+        // Bodies are signed with HMAC SHA256 using the app's current credentials
+        let app_key = app_config.key.clone();
+        let token = Token::new(app_key.clone(), app_config.secret.clone());
+
+        let mut tasks = Vec::new();
+
         log_webhook_processing_pusher_format(&app_id, &pusher_payload_body);
 
         for (_endpoint_key, webhook_config) in relevant_webhook_configs {
             let permit_semaphore = self.webhook_semaphore.clone();
-            let permit = permit_semaphore.acquire_owned().await.map_err(|e| {
-                Error::Other(format!("Failed to acquire webhook semaphore permit: {}", e))
-            })?;
             let app_id = app_id.clone(); // Clone app_id for the async task
             let current_app_key = app_key.clone();
-            let current_signature = signature.clone();
-            let body_to_send = body_json_string.clone(); // Clone the already serialized body
+            // The already serialized body, unless the webhook has a template
+            let bodies = match webhook_config.transform {
+                Some(_) => render_bodies(webhook_config, &app_id, &pusher_payload_body)?,
+                None => vec![body_json_string.clone()],
+            };
 
             if let Some(url) = &webhook_config.url {
-                let client = self.client.clone();
+                let client = self.client_for(webhook_config);
+                let retry = self.retry.clone();
+                let circuit_breaker = self.circuit_breaker.clone();
+                let rate_limiter = self.rate_limiter.clone();
+                let rate_limit = webhook_config.rate_limit.clone();
                 let url_str = url.to_string();
-                let custom_headers_config = webhook_config
+                let signed_bodies: Vec<(String, String)> = bodies
+                    .into_iter()
+                    .map(|body| (token.sign(&body), body))
+                    .collect();
+                let mut custom_headers_config = webhook_config
                     .headers
                     .as_ref()
                     .map(|h| h.headers.clone())
                     .unwrap_or_default();
+                let authorization = webhook_config
+                    .auth
+                    .as_ref()
+                    .map(|auth| auth.header_value())
+                    .transpose();
 
                 let task = tokio::spawn(async move {
-                    let _permit = permit; // Permit dropped when task finishes
-                    if let Err(e) = send_pusher_webhook(
-                        &client,
-                        &url_str,
-                        &current_app_key,
-                        &current_signature,
-                        body_to_send, // Send the pre-serialized JSON string
-                        custom_headers_config,
-                    )
-                    .await
-                    {
-                        error!(
-                            "{}",
-                            format!("Webhook send error to URL {}: {}", url_str, e)
-                        );
-                    } else {
-                        info!(
+                    let result = match (client, authorization) {
+                        (Ok(client), Ok(authorization)) => {
+                            // Configured credentials win over a custom header
+                            if let Some(authorization) = authorization {
+                                custom_headers_config
+                                    .retain(|key, _| !key.eq_ignore_ascii_case("authorization"));
+                                custom_headers_config
+                                    .insert("Authorization".to_string(), authorization);
+                            }
+                            let mut delivered = Ok(());
+                            for (body_signature, body) in signed_bodies {
+                                // Waiting for the endpoint's turn doesn't hold a permit
+                                let turn =
+                                    rate_limiter.acquire(&url_str, rate_limit.as_ref()).await;
+                                let permit = match turn {
+                                    Ok(()) => acquire_permit(permit_semaphore.clone()).await,
+                                    Err(e) => Err(e),
+                                };
+                                let _permit = match permit {
+                                    Ok(permit) => permit,
+                                    Err(e) => {
+                                        delivered = Err(e);
+                                        break;
+                                    }
+                                };
+                                delivered = deliver_with_retries(
+                                    &client,
+                                    &retry,
+                                    &circuit_breaker,
+                                    &url_str,
+                                    &current_app_key,
+                                    &body_signature,
+                                    body,
+                                    custom_headers_config.clone(),
+                                )
+                                .await;
+                                if delivered.is_err() {
+                                    break;
+                                }
+                            }
+                            delivered
+                        }
+                        (Err(e), _) | (_, Err(e)) => Err(e),
+                    };
+                    match &result {
+                        Ok(()) => info!(
                             "{}",
                             format!("Successfully sent Pusher webhook to URL: {}", url_str)
-                        );
+                        ),
+                        Err(e) => error!(
+                            "{}",
+                            format!("Webhook send error to URL {}: {}", url_str, e)
+                        ),
                     }
+                    result
                 });
                 tasks.push(task);
             } else if webhook_config.lambda.is_some() || webhook_config.lambda_function.is_some() {
-                // Lambda sending needs to be adapted if it's to receive the Pusher format.
-                // For now, we assume Lambda might expect a different format or handle the Pusher format.
-                // The body_to_send is the Pusher JSON string.
+                let permit = acquire_permit(permit_semaphore).await?;
+                // Lambda functions receive the same Pusher payload body an HTTP
+                // endpoint would; IAM authorizes the call, so it carries no signature.
                 let lambda_sender = self.lambda_sender.clone();
                 let webhook_clone = webhook_config.clone();
                 // For Lambda, we might need to pass the raw Pusher payload or a structured version of it.
                 // The current lambda_sender.invoke_lambda expects a Value.
                 // Let's parse the body_to_send back to Value for the lambda_sender.
-                let payload_for_lambda: Value =
-                    serde_json::from_str(&body_to_send).unwrap_or(json!({}));
+                let payloads_for_lambda: Vec<Value> = bodies
+                    .iter()
+                    .map(|body| serde_json::from_str(body).unwrap_or(json!({})))
+                    .collect();
 
                 let task = tokio::spawn(async move {
                     let _permit = permit;
-                    // The lambda_sender.invoke_lambda might need adjustment if it's also to send
-                    // X-Pusher-Key and X-Pusher-Signature as part of its invocation,
-                    // or if the Lambda function itself expects to verify these from an API Gateway.
-                    // This example passes the Pusher payload body to Lambda.
-                    if let Err(e) = lambda_sender
-                        .invoke_lambda(
-                            &webhook_clone,
-                            "batch_events",  // Generic event type for batched lambda
-                            &app_id.clone(), // Pass app_id for context
-                            payload_for_lambda, // Pass the parsed Value
-                        )
-                        .await
-                    {
-                        error!(
-                            "{}",
-                            format!("Lambda webhook error for app {}: {}", app_id.clone(), e)
-                        );
-                    } else {
-                        info!(
+                    let mut result = Ok(());
+                    for payload_for_lambda in payloads_for_lambda {
+                        result = lambda_sender
+                            .invoke_lambda(
+                                &webhook_clone,
+                                "batch_events", // Generic event type for batched lambda
+                                &app_id.clone(), // Pass app_id for context
+                                payload_for_lambda, // Pass the parsed Value
+                            )
+                            .await;
+                        if result.is_err() {
+                            break;
+                        }
+                    }
+                    match &result {
+                        Ok(()) => info!(
                             "{}",
                             format!("Successfully invoked Lambda for app: {}", app_id.clone())
-                        );
+                        ),
+                        Err(e) => error!(
+                            "{}",
+                            format!("Lambda webhook error for app {}: {}", app_id.clone(), e)
+                        ),
                     }
+                    result
+                });
+                tasks.push(task);
+            } else if let Some(sns_config) = webhook_config.sns.clone() {
+                let permit = acquire_permit(permit_semaphore).await?;
+                // Like Lambda, SNS and EventBridge authorize the publish with
+                // IAM and carry the unsigned body
+                let sns_sender = self.sns_sender.clone();
+                let event_names = event_names.clone();
+                let task = tokio::spawn(async move {
+                    let _permit = permit;
+                    for body in bodies {
+                        sns_sender
+                            .publish(&sns_config, &app_id, &event_names, &body)
+                            .await?;
+                    }
+                    Ok(())
+                });
+                tasks.push(task);
+            } else if let Some(eventbridge_config) = webhook_config.eventbridge.clone() {
+                let permit = acquire_permit(permit_semaphore).await?;
+                let eventbridge_sender = self.eventbridge_sender.clone();
+                let task = tokio::spawn(async move {
+                    let _permit = permit;
+                    for body in bodies {
+                        eventbridge_sender
+                            .put_event(&eventbridge_config, &app_id, &body)
+                            .await?;
+                    }
+                    Ok(())
+                });
+                tasks.push(task);
+            } else if let Some(kafka_config) = webhook_config.kafka.clone() {
+                let permit = acquire_permit(permit_semaphore).await?;
+                let kafka_sender = self.kafka_sender.clone();
+                // Each record with the event names it carries
+                let records: Vec<(Vec<&'static str>, String)> = if kafka_config.splits_by_event() {
+                    let mut records = Vec::new();
+                    for event_name in &event_names {
+                        let events_of_type = PusherWebhookPayload {
+                            time_ms: pusher_payload_body.time_ms,
+                            events: pusher_payload_body
+                                .events
+                                .iter()
+                                .filter(|event| event.name() == *event_name)
+                                .cloned()
+                                .collect(),
+                            batch: None,
+                        };
+                        for body in render_bodies(webhook_config, &app_id, &events_of_type)? {
+                            records.push((vec![*event_name], body));
+                        }
+                    }
+                    records
+                } else {
+                    bodies
+                        .into_iter()
+                        .map(|body| (event_names.clone(), body))
+                        .collect()
+                };
+                let task = tokio::spawn(async move {
+                    let _permit = permit;
+                    for (record_event_names, body) in records {
+                        kafka_sender
+                            .produce(&kafka_config, &app_id, &record_event_names, &body)
+                            .await?;
+                    }
+                    Ok(())
                 });
                 tasks.push(task);
             } else {
                 warn!(
                     "{}",
                     format!(
-                        "Webhook for app {} has neither URL nor Lambda config.",
+                        "Webhook for app {} has no URL, Lambda, SNS, EventBridge or Kafka target.",
                         app_id.clone()
                     )
                 );
-                drop(permit);
             }
         }
 
+        let delivery_count = tasks.len();
+        let mut failed_deliveries = 0;
         for task_handle in tasks {
-            // Renamed variable
-            if let Err(e) = task_handle.await {
-                error!("{}", format!("Webhook task execution failed: {}", e));
+            match task_handle.await {
+                Ok(Ok(())) => {}
+                Ok(Err(_)) => failed_deliveries += 1,
+                Err(e) => {
+                    error!("{}", format!("Webhook task execution failed: {}", e));
+                    failed_deliveries += 1;
+                }
             }
         }
 
+        // Fail the job so the queue retries it later, after its own backoff
+        if failed_deliveries > 0 {
+            return Err(Error::Other(format!(
+                "{} of {} webhook deliveries for app {} failed",
+                failed_deliveries, delivery_count, app_id
+            )));
+        }
         Ok(())
     }
 }
 
-impl Clone for WebhookSender {
-    fn clone(&self) -> Self {
-        Self {
-            client: self.client.clone(),
-            app_manager: self.app_manager.clone(),
-            lambda_sender: self.lambda_sender.clone(),
-            webhook_semaphore: self.webhook_semaphore.clone(),
+/// Bodies `webhook` receives for `payload`, rendered from its template when
+/// it has one
+fn render_bodies(
+    webhook: &Webhook,
+    app_id: &str,
+    payload: &PusherWebhookPayload,
+) -> Result<Vec<String>> {
+    match &webhook.transform {
+        Some(transform) => transform.render_bodies(app_id, payload),
+        None => Ok(vec![serde_json::to_string(payload)?]),
+    }
+}
+
+/// One of the permits bounding concurrent deliveries
+async fn acquire_permit(semaphore: Arc<Semaphore>) -> Result<OwnedSemaphorePermit> {
+    semaphore
+        .acquire_owned()
+        .await
+        .map_err(|e| Error::Other(format!("Failed to acquire webhook semaphore permit: {}", e)))
+}
+
+/// Deliver a webhook to `url`, retrying transient failures with backoff.
+/// Deliveries are skipped while the URL's circuit is open.
+#[allow(clippy::too_many_arguments)]
+async fn deliver_with_retries(
+    client: &Client,
+    retry: &WebhookRetryConfig,
+    circuit_breaker: &CircuitBreaker,
+    url: &str,
+    app_key: &str,
+    signature: &str,
+    json_body: String,
+    custom_headers_config: HashMap<String, String>,
+) -> Result<()> {
+    let max_attempts = retry.max_attempts.max(1);
+    let mut attempt = 1;
+    loop {
+        if !circuit_breaker.try_acquire(url).await {
+            return Err(Error::Other(format!(
+                "Circuit for webhook {} is open, delivery paused",
+                url
+            )));
+        }
+        match send_pusher_webhook(
+            client,
+            url,
+            app_key,
+            signature,
+            json_body.clone(),
+            custom_headers_config.clone(),
+        )
+        .await
+        {
+            Ok(()) => {
+                circuit_breaker.record_success(url).await;
+                return Ok(());
+            }
+            // The endpoint is up, it just won't take this payload
+            Err(DeliveryFailure::Rejected(e)) => {
+                circuit_breaker.record_success(url).await;
+                return Err(e);
+            }
+            Err(DeliveryFailure::Transient(e)) => {
+                circuit_breaker.record_failure(url).await;
+                if attempt >= max_attempts {
+                    return Err(e);
+                }
+                let delay = retry.backoff(attempt);
+                warn!(
+                    "{}",
+                    format!(
+                        "Webhook to {} failed (attempt {}/{}), retrying in {}ms: {}",
+                        url,
+                        attempt,
+                        max_attempts,
+                        delay.as_millis(),
+                        e
+                    )
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
         }
     }
 }
 
+/// HTTP client for webhook deliveries, presenting the client certificate
+/// in `tls` when one is configured
+fn build_client(tls: &WebhookTlsConfig) -> Result<Client> {
+    let read = |path: &str| {
+        std::fs::read(path)
+            .map_err(|e| Error::ConfigFileError(format!("Failed to read {}: {}", path, e)))
+    };
+    let resolver = crate::dns::resolver();
+    let mut builder = Client::builder()
+        .timeout(Duration::from_secs(10)) // Timeout for HTTP requests
+        .pool_idle_timeout(resolver.http_pool_idle_timeout())
+        .dns_resolver(resolver);
+
+    match (&tls.cert_path, &tls.key_path) {
+        (Some(cert_path), Some(key_path)) => {
+            let identity =
+                Identity::from_pkcs8_pem(&read(cert_path)?, &read(key_path)?).map_err(|e| {
+                    Error::ConfigFileError(format!(
+                        "Invalid webhook client certificate {}: {}",
+                        cert_path, e
+                    ))
+                })?;
+            builder = builder.identity(identity);
+        }
+        (None, None) => {}
+        _ => {
+            return Err(Error::ConfigurationError(
+                "Webhook TLS needs both cert_path and key_path".to_string(),
+            ));
+        }
+    }
+    if let Some(ca_cert_path) = &tls.ca_cert_path {
+        let ca = Certificate::from_pem(&read(ca_cert_path)?).map_err(|e| {
+            Error::ConfigFileError(format!("Invalid webhook CA {}: {}", ca_cert_path, e))
+        })?;
+        builder = builder.add_root_certificate(ca);
+    }
+    builder
+        .build()
+        .map_err(|e| Error::Other(format!("Failed to build webhook HTTP client: {}", e)))
+}
+
 /// Helper function to send a Pusher-formatted webhook
 async fn send_pusher_webhook(
     client: &Client,
@@ -268,9 +601,11 @@ async fn send_pusher_webhook(
     signature: &str,
     json_body: String, // Expects already serialized JSON string
     custom_headers_config: HashMap<String, String>,
-) -> Result<()> {
+) -> std::result::Result<(), DeliveryFailure> {
     info!("{}", format!("Sending Pusher webhook to URL: {}", url));
 
+    // Same headers as Pusher: the app key, and the hex HMAC-SHA256 of the
+    // body keyed with the app secret
     let mut request_builder = client
         .post(url)
         .header(header::CONTENT_TYPE, "application/json")
@@ -278,6 +613,15 @@ async fn send_pusher_webhook(
         .header("X-Pusher-Signature", signature);
 
     for (key, value) in custom_headers_config {
+        // reqwest appends rather than replaces, and a second signature
+        // header would fail verification
+        if SIGNING_HEADERS.contains(&key.to_ascii_lowercase().as_str()) {
+            warn!(
+                "{}",
+                format!("Ignoring custom header {} on webhook to {}", key, url)
+            );
+            continue;
+        }
         request_builder = request_builder.header(key, value);
     }
 
@@ -303,10 +647,12 @@ async fn send_pusher_webhook(
                         url, status, error_text
                     )
                 );
-                Err(Error::Other(format!(
-                    "Webhook to {} failed with status {}",
-                    url, status
-                )))
+                let e = Error::Other(format!("Webhook to {} failed with status {}", url, status));
+                if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                    Err(DeliveryFailure::Transient(e))
+                } else {
+                    Err(DeliveryFailure::Rejected(e))
+                }
             }
         }
         Err(e) => {
@@ -314,10 +660,10 @@ async fn send_pusher_webhook(
                 "{}",
                 format!("Failed to send Pusher webhook to {}: {}", url, e)
             );
-            Err(Error::Other(format!(
+            Err(DeliveryFailure::Transient(Error::Other(format!(
                 "HTTP request failed for webhook to {}: {}",
                 url, e
-            )))
+            ))))
         }
     }
 }
@@ -0,0 +1,95 @@
+// src/webhook/sns_sender.rs
+
+use crate::error::{Error, Result};
+use crate::webhook::aws::sdk_config;
+use crate::webhook::types::SnsConfig;
+use aws_sdk_sns::Client as SnsClient;
+use aws_sdk_sns::types::MessageAttributeValue;
+use tracing::{error, info};
+
+/// Publishes webhooks to AWS SNS topics
+#[derive(Clone)]
+pub struct SnsWebhookSender {
+    // Cache SNS clients by region to avoid recreating them
+    clients: dashmap::DashMap<String, SnsClient>,
+}
+
+impl SnsWebhookSender {
+    pub fn new() -> Self {
+        Self {
+            clients: dashmap::DashMap::new(),
+        }
+    }
+
+    async fn get_client(&self, region: &str) -> SnsClient {
+        if let Some(client_ref) = self.clients.get(region) {
+            return client_ref.clone();
+        }
+        let client = SnsClient::new(&sdk_config(region).await);
+        self.clients.insert(region.to_string(), client.clone());
+        client
+    }
+
+    /// Publish the webhook body to the topic. The message carries `app_id`
+    /// and `event_names` attributes, so subscriptions can filter on them.
+    /// FIFO topics get a message group and a deduplication ID derived from
+    /// the body, so a retried job isn't delivered twice.
+    pub async fn publish(
+        &self,
+        config: &SnsConfig,
+        app_id: &str,
+        event_names: &[&str],
+        body: &str,
+    ) -> Result<()> {
+        let client = self.get_client(&config.region).await;
+
+        let attribute = |data_type: &str, value: String| {
+            MessageAttributeValue::builder()
+                .data_type(data_type)
+                .string_value(value)
+                .build()
+                .map_err(|e| Error::Other(format!("Invalid SNS message attribute: {}", e)))
+        };
+        let event_names_json = serde_json::to_string(event_names)?;
+
+        let mut request = client
+            .publish()
+            .topic_arn(&config.topic_arn)
+            .message(body)
+            .message_attributes("app_id", attribute("String", app_id.to_string())?)
+            .message_attributes("event_names", attribute("String.Array", event_names_json)?);
+        if config.topic_arn.ends_with(".fifo") {
+            request = request
+                .message_group_id(config.message_group_id.as_deref().unwrap_or(app_id))
+                .message_deduplication_id(format!("{:x}", md5::compute(body.as_bytes())));
+        }
+
+        let output = request.send().await.map_err(|e| {
+            error!(
+                "{}",
+                format!(
+                    "Failed to publish webhook to SNS topic {}: {}",
+                    config.topic_arn, e
+                )
+            );
+            Error::Other(format!("Failed to publish to SNS topic: {}", e))
+        })?;
+
+        info!(
+            "{}",
+            format!(
+                "Published webhook for app '{}' to SNS topic {} (message {})",
+                app_id,
+                config.topic_arn,
+                output.message_id().unwrap_or("unknown")
+            )
+        );
+        Ok(())
+    }
+}
+
+impl Default for SnsWebhookSender {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,143 @@
+// src/webhook/transform.rs
+//! Webhook payload templates. A template is the JSON body to send, in which
+//! strings may hold `{{path}}` placeholders. A string that is a single
+//! placeholder is replaced by the value at that path, keeping its JSON type;
+//! placeholders inside longer strings are replaced by the value as text.
+//! Paths are dot-separated keys and array indexes into the render context:
+//! `app_id`, `time_ms`, `events`, `event` and, for batched jobs, `batch`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+
+use crate::error::{Error, Result};
+use crate::webhook::types::PusherWebhookPayload;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookTransform {
+    /// Body to send in place of the Pusher payload
+    pub template: Value,
+    /// Send one rendered body per event, with `event` set to it, instead of
+    /// one body per job with `event` set to the first event
+    #[serde(default)]
+    pub per_event: bool,
+}
+
+impl WebhookTransform {
+    /// Reject placeholders that are never closed
+    pub fn validate(&self) -> Result<()> {
+        fn check(value: &Value) -> Result<()> {
+            match value {
+                Value::String(text) => {
+                    let mut rest = text.as_str();
+                    while let Some(start) = rest.find("{{") {
+                        let after = &rest[start + 2..];
+                        let end = after.find("}}").ok_or_else(|| {
+                            Error::ConfigurationError(format!(
+                                "Unclosed placeholder in webhook template: {}",
+                                text
+                            ))
+                        })?;
+                        rest = &after[end + 2..];
+                    }
+                    Ok(())
+                }
+                Value::Array(items) => items.iter().try_for_each(check),
+                Value::Object(fields) => fields.values().try_for_each(check),
+                _ => Ok(()),
+            }
+        }
+        check(&self.template)
+    }
+
+    /// Serialized bodies to deliver for `payload`
+    pub fn render_bodies(
+        &self,
+        app_id: &str,
+        payload: &PusherWebhookPayload,
+    ) -> Result<Vec<String>> {
+        let payload = serde_json::to_value(payload).map_err(|e| {
+            Error::SerializationError(format!("Failed to serialize webhook body: {}", e))
+        })?;
+        let events = payload.get("events").cloned().unwrap_or(json!([]));
+        let mut context = Map::new();
+        context.insert("app_id".to_string(), json!(app_id));
+        context.insert("time_ms".to_string(), payload["time_ms"].clone());
+        if let Some(batch) = payload.get("batch") {
+            context.insert("batch".to_string(), batch.clone());
+        }
+
+        let current_events: Vec<Value> = if self.per_event {
+            events.as_array().cloned().unwrap_or_default()
+        } else {
+            vec![events.get(0).cloned().unwrap_or(Value::Null)]
+        };
+        context.insert("events".to_string(), events);
+
+        let mut context = Value::Object(context);
+        current_events
+            .into_iter()
+            .map(|event| {
+                context["event"] = event;
+                serde_json::to_string(&render(&self.template, &context)).map_err(|e| {
+                    Error::SerializationError(format!("Failed to serialize webhook body: {}", e))
+                })
+            })
+            .collect()
+    }
+}
+
+fn render(template: &Value, context: &Value) -> Value {
+    match template {
+        Value::String(text) => render_string(text, context),
+        Value::Array(items) => {
+            Value::Array(items.iter().map(|item| render(item, context)).collect())
+        }
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), render(value, context)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+fn render_string(text: &str, context: &Value) -> Value {
+    if let Some(path) = text
+        .strip_prefix("{{")
+        .and_then(|rest| rest.strip_suffix("}}"))
+        .filter(|path| !path.contains("{{") && !path.contains("}}"))
+    {
+        return lookup(context, path.trim()).cloned().unwrap_or(Value::Null);
+    }
+
+    let mut rendered = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        rendered.push_str(&rest[..start]);
+        match lookup(context, rest[start + 2..start + 2 + end].trim()) {
+            Some(Value::String(value)) => rendered.push_str(value),
+            Some(Value::Null) | None => {}
+            Some(value) => rendered.push_str(&value.to_string()),
+        }
+        rest = &rest[start + 2 + end + 2..];
+    }
+    rendered.push_str(rest);
+    Value::String(rendered)
+}
+
+/// Value at a dot-separated path of keys and array indexes
+fn lookup<'a>(context: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .try_fold(context, |value, segment| match value {
+            Value::Object(fields) => fields.get(segment),
+            Value::Array(items) => segment
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| items.get(index)),
+            _ => None,
+        })
+}
@@ -1,8 +1,34 @@
 // src/webhook/types.rs
 // No SdkConfig needed here, it's for AWS SDK interaction in lambda_sender.
+use crate::app::config::App;
+use crate::error::{Error, Result};
+use crate::options::{WebhookRateLimitConfig, WebhookTlsConfig};
+use crate::webhook::transform::WebhookTransform;
 use serde::{Deserialize, Serialize};
 use serde_json::Value; // Keep this for Value type
 
+/// Event types Sockudo can emit webhooks for
+pub const SUPPORTED_WEBHOOK_EVENTS: &[&str] = &[
+    "channel_occupied",
+    "channel_vacated",
+    "member_added",
+    "member_removed",
+    "client_event",
+    "cache_miss",
+    "subscription_count",
+    "user_banned",
+];
+
+/// Matches every supported event type
+pub const WEBHOOK_EVENT_WILDCARD: &str = "*";
+
+/// Older spelling of [`WEBHOOK_EVENT_WILDCARD`], still accepted
+const WEBHOOK_EVENT_WILDCARD_ALIAS: &str = "all";
+
+fn is_wildcard(entry: &str) -> bool {
+    entry == WEBHOOK_EVENT_WILDCARD || entry == WEBHOOK_EVENT_WILDCARD_ALIAS
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Webhook {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -11,11 +37,119 @@ pub struct Webhook {
     pub lambda_function: Option<String>, // Kept for potential legacy or direct Lambda use
     #[serde(skip_serializing_if = "Option::is_none")]
     pub lambda: Option<LambdaConfig>, // For structured Lambda config
-    pub event_types: Vec<String>, // Names of events this webhook is interested in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sns: Option<SnsConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eventbridge: Option<EventBridgeConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kafka: Option<KafkaWebhookConfig>,
+    /// Names of events this webhook is interested in. `*` matches every event and
+    /// `!name` excludes one; only listed events are sent, so an empty list
+    /// means none.
+    #[serde(default)]
+    pub event_types: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub filter: Option<WebhookFilter>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub headers: Option<WebhookHeaders>, // Custom headers user might want to add to outgoing webhook
+    /// Credentials sent as the `Authorization` header of URL webhooks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<WebhookAuth>,
+    /// Client certificate for endpoints requiring mutual TLS, replacing
+    /// the global `webhooks.tls` one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<WebhookTlsConfig>,
+    /// Template for the delivered body, replacing the Pusher payload
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transform: Option<WebhookTransform>,
+    /// Pacing of deliveries to this webhook's URL, replacing the global
+    /// `webhooks.rate_limit`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<WebhookRateLimitConfig>,
+}
+
+impl Webhook {
+    /// Whether this webhook should receive the given event type
+    pub fn subscribes_to(&self, event_type: &str) -> bool {
+        let mut included = false;
+        for entry in &self.event_types {
+            match entry.strip_prefix('!') {
+                Some(excluded) => {
+                    if excluded == event_type {
+                        return false;
+                    }
+                }
+                None => {
+                    if is_wildcard(entry) || entry == event_type {
+                        included = true;
+                    }
+                }
+            }
+        }
+        included
+    }
+
+    /// Reject event types Sockudo never emits, e.g. typos like `member_add`,
+    /// and lists that only exclude events and so match none
+    pub fn validate_event_types(&self) -> Result<()> {
+        if only_excludes(&self.event_types) {
+            return Err(Error::ConfigurationError(format!(
+                "Webhook event types {:?} only exclude events and match none; add '{}' to receive the others",
+                self.event_types, WEBHOOK_EVENT_WILDCARD
+            )));
+        }
+        for entry in &self.event_types {
+            let name = entry.strip_prefix('!').unwrap_or(entry);
+            if !(is_wildcard(entry) || SUPPORTED_WEBHOOK_EVENTS.contains(&name)) {
+                return Err(Error::ConfigurationError(format!(
+                    "Unsupported webhook event type '{}'. Supported types: {}, or '{}'",
+                    entry,
+                    SUPPORTED_WEBHOOK_EVENTS.join(", "),
+                    WEBHOOK_EVENT_WILDCARD
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether `event_types` lists exclusions but nothing to exclude them from.
+/// Such lists used to mean every other event.
+pub fn only_excludes<S: AsRef<str>>(event_types: &[S]) -> bool {
+    !event_types.is_empty()
+        && event_types
+            .iter()
+            .all(|entry| entry.as_ref().starts_with('!'))
+}
+
+/// Validate every webhook configured on an app before it is registered
+pub fn validate_app_webhooks(app: &App) -> Result<()> {
+    if let Some(webhooks) = &app.webhooks {
+        for webhook in webhooks {
+            webhook.validate_event_types().map_err(|e| {
+                Error::ConfigurationError(format!("Invalid webhook for app {}: {}", app.id, e))
+            })?;
+            if let Some(auth) = &webhook.auth {
+                auth.header_value().map_err(|e| {
+                    Error::ConfigurationError(format!("Invalid webhook for app {}: {}", app.id, e))
+                })?;
+            }
+            if let Some(kafka) = &webhook.kafka {
+                if kafka.brokers.is_empty() || kafka.topic.is_empty() {
+                    return Err(Error::ConfigurationError(format!(
+                        "Invalid webhook for app {}: Kafka webhooks need brokers and a topic",
+                        app.id
+                    )));
+                }
+            }
+            if let Some(transform) = &webhook.transform {
+                transform.validate().map_err(|e| {
+                    Error::ConfigurationError(format!("Invalid webhook for app {}: {}", app.id, e))
+                })?;
+            }
+        }
+    }
+    Ok(())
 }
 
 // This struct is not directly used in the Pusher payload format,
@@ -38,30 +172,232 @@ pub struct WebhookHeaders {
     pub headers: std::collections::HashMap<String, String>,
 }
 
+/// `Authorization` credentials for webhook endpoints behind an auth gateway.
+/// Secrets can be read from environment variables instead of the app config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WebhookAuth {
+    Bearer {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        token: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        token_env: Option<String>,
+    },
+    Basic {
+        username: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        password: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        password_env: Option<String>,
+    },
+}
+
+impl WebhookAuth {
+    /// Value of the `Authorization` header, reading secrets from the
+    /// environment when configured that way
+    pub fn header_value(&self) -> Result<String> {
+        match self {
+            WebhookAuth::Bearer { token, token_env } => {
+                let token = resolve_secret("token", token, token_env)?;
+                Ok(format!("Bearer {}", token))
+            }
+            WebhookAuth::Basic {
+                username,
+                password,
+                password_env,
+            } => {
+                use base64::Engine;
+                let password = resolve_secret("password", password, password_env)?;
+                let credentials = base64::engine::general_purpose::STANDARD
+                    .encode(format!("{}:{}", username, password));
+                Ok(format!("Basic {}", credentials))
+            }
+        }
+    }
+}
+
+/// A secret given inline or through the environment variable `env`
+fn resolve_secret(name: &str, value: &Option<String>, env: &Option<String>) -> Result<String> {
+    match (value, env) {
+        (_, Some(var)) => std::env::var(var).map_err(|_| {
+            Error::ConfigurationError(format!("Webhook auth {} variable {} is not set", name, var))
+        }),
+        (Some(value), None) => Ok(value.clone()),
+        (None, None) => Err(Error::ConfigurationError(format!(
+            "Webhook auth needs a {} or {}_env",
+            name, name
+        ))),
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LambdaConfig {
     pub function_name: String,
     pub region: String,
+    /// Version or alias to invoke; `$LATEST` when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub qualifier: Option<String>,
+    #[serde(default)]
+    pub invocation_type: LambdaInvocationType,
+}
+
+/// How Lambda webhooks are invoked
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LambdaInvocationType {
+    /// Queued by Lambda and run asynchronously; delivery succeeds once queued
+    #[default]
+    Event,
+    /// Run synchronously; delivery fails if the function throws
+    RequestResponse,
+}
+
+/// Publishes the webhook body to an SNS topic
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnsConfig {
+    pub topic_arn: String,
+    pub region: String,
+    /// Message group of FIFO topics; the app ID when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_group_id: Option<String>,
+}
+
+/// Produces the webhook body to a Kafka topic. `{app_id}` and `{event}` in
+/// `topic` are replaced by the app ID and the event name; a topic naming
+/// `{event}` gets one record per event type of the job.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KafkaWebhookConfig {
+    pub brokers: Vec<String>,
+    pub topic: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub security_protocol: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sasl_mechanism: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sasl_username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sasl_password: Option<String>,
+}
+
+impl KafkaWebhookConfig {
+    /// Whether each event type goes to its own topic
+    pub fn splits_by_event(&self) -> bool {
+        self.topic.contains("{event}")
+    }
+
+    pub fn topic_for(&self, app_id: &str, event_name: &str) -> String {
+        self.topic
+            .replace("{app_id}", app_id)
+            .replace("{event}", event_name)
+    }
 }
 
-// This is the JobData structure that Sockudo uses internally for its queue.
-// The `payload` field will be structured to produce the Pusher-compatible format when sent.
+/// Puts the webhook body on an EventBridge bus as the event detail
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct JobData {
-    pub app_key: String,    // Needed for X-Pusher-Key and signing
-    pub app_id: String,     // For logging and potentially for the webhook receiver
-    pub app_secret: String, // Needed for signing the X-Pusher-Signature
-    pub payload: JobPayload,
-    pub original_signature: String, // Sockudo's internal signature for queue deduplication, etc.
+pub struct EventBridgeConfig {
+    pub event_bus_name: String,
+    pub region: String,
+    /// Event `source`; `sockudo` when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Event `detail-type`; `pusher.webhook` when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail_type: Option<String>,
+}
+
+/// A webhook event as recorded on the WebSocket/HTTP path. Only these fields
+/// travel through the queue; it serializes to the Pusher event object
+/// (e.g. `{ "name": "channel_occupied", "channel": "my-channel" }`) when the
+/// consumer builds the request body.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "name", rename_all = "snake_case")]
+pub enum WebhookEvent {
+    ChannelOccupied {
+        channel: String,
+    },
+    ChannelVacated {
+        channel: String,
+    },
+    MemberAdded {
+        channel: String,
+        user_id: String,
+    },
+    MemberRemoved {
+        channel: String,
+        user_id: String,
+    },
+    ClientEvent {
+        channel: String,
+        event: String,
+        data: Value,
+        socket_id: Option<String>,
+        /// Only set for presence channels
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        user_id: Option<String>,
+    },
+    CacheMiss {
+        channel: String,
+    },
+    SubscriptionCount {
+        channel: String,
+        subscription_count: usize,
+    },
+    UserBanned {
+        user_id: String,
+        ttl: u64,
+        reason: Option<String>,
+    },
+}
+
+impl WebhookEvent {
+    /// Event type name, as used in webhook `event_types`
+    pub fn name(&self) -> &'static str {
+        match self {
+            WebhookEvent::ChannelOccupied { .. } => "channel_occupied",
+            WebhookEvent::ChannelVacated { .. } => "channel_vacated",
+            WebhookEvent::MemberAdded { .. } => "member_added",
+            WebhookEvent::MemberRemoved { .. } => "member_removed",
+            WebhookEvent::ClientEvent { .. } => "client_event",
+            WebhookEvent::CacheMiss { .. } => "cache_miss",
+            WebhookEvent::SubscriptionCount { .. } => "subscription_count",
+            WebhookEvent::UserBanned { .. } => "user_banned",
+        }
+    }
+
+    /// Channel the event happened on, if any
+    pub fn channel(&self) -> Option<&str> {
+        match self {
+            WebhookEvent::ChannelOccupied { channel }
+            | WebhookEvent::ChannelVacated { channel }
+            | WebhookEvent::MemberAdded { channel, .. }
+            | WebhookEvent::MemberRemoved { channel, .. }
+            | WebhookEvent::ClientEvent { channel, .. }
+            | WebhookEvent::CacheMiss { channel }
+            | WebhookEvent::SubscriptionCount { channel, .. } => Some(channel),
+            WebhookEvent::UserBanned { .. } => None,
+        }
+    }
 }
 
-// This is the JobPayload structure.
-// The `events` field will now hold a vector of fully formed Pusher event objects.
+// Events of a queued webhook job (`crate::queue::job::WebhookJob`). It is kept
+// minimal: the consumer looks the app up, builds the Pusher payload and signs it.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JobPayload {
     pub time_ms: i64, // Unix timestamp in milliseconds
-    pub events: Vec<Value>, // Each Value is a JSON object representing a Pusher event,
-                      // e.g., { "name": "channel_occupied", "channel": "my-channel" }
+    pub events: Vec<WebhookEvent>,
+    /// Set on jobs built by internal batching
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch: Option<BatchInfo>,
+}
+
+/// Describes the events a batched webhook carries, so receivers can tell
+/// how long the batch was collected over
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchInfo {
+    pub count: usize,
+    /// When the first and last events of the batch were raised, in Unix milliseconds
+    pub first_time_ms: i64,
+    pub last_time_ms: i64,
 }
 
 // This struct represents the final payload sent to the webhook receiver,
@@ -69,5 +405,74 @@ pub struct JobPayload {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PusherWebhookPayload {
     pub time_ms: i64,
-    pub events: Vec<Value>, // Array of event objects
+    pub events: Vec<WebhookEvent>, // Serialized as Pusher event objects
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch: Option<BatchInfo>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn webhook(event_types: &[&str]) -> Webhook {
+        serde_json::from_value(json!({
+            "url": "https://example.com/hooks",
+            "event_types": event_types,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_listed_events_match() {
+        let webhook = webhook(&["member_added", "member_removed"]);
+        assert!(webhook.subscribes_to("member_added"));
+        assert!(webhook.subscribes_to("member_removed"));
+        assert!(!webhook.subscribes_to("channel_occupied"));
+    }
+
+    #[test]
+    fn test_empty_list_matches_nothing() {
+        let webhook = webhook(&[]);
+        for event in SUPPORTED_WEBHOOK_EVENTS {
+            assert!(!webhook.subscribes_to(event));
+        }
+    }
+
+    #[test]
+    fn test_wildcard_matches_everything_but_exclusions() {
+        for wildcard in ["*", "all"] {
+            let webhook = webhook(&[wildcard, "!client_event"]);
+            assert!(webhook.subscribes_to("channel_occupied"));
+            assert!(webhook.subscribes_to("user_banned"));
+            assert!(!webhook.subscribes_to("client_event"));
+        }
+    }
+
+    #[test]
+    fn test_exclusions_win_over_inclusions() {
+        let webhook = webhook(&["!member_added", "member_added", "member_removed"]);
+        assert!(!webhook.subscribes_to("member_added"));
+        assert!(webhook.subscribes_to("member_removed"));
+    }
+
+    #[test]
+    fn test_only_exclusions_match_nothing_and_are_rejected() {
+        let webhook = webhook(&["!client_event"]);
+        assert!(!webhook.subscribes_to("channel_occupied"));
+        assert!(webhook.validate_event_types().is_err());
+    }
+
+    #[test]
+    fn test_validate_event_types() {
+        assert!(
+            webhook(&["*", "!cache_miss"])
+                .validate_event_types()
+                .is_ok()
+        );
+        assert!(webhook(&["all"]).validate_event_types().is_ok());
+        assert!(webhook(&[]).validate_event_types().is_ok());
+        assert!(webhook(&["member_add"]).validate_event_types().is_err());
+        assert!(webhook(&["!*"]).validate_event_types().is_err());
+    }
 }
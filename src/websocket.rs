@@ -7,16 +7,104 @@ use tokio::sync::{Mutex, mpsc};
 pub struct WebSocket {
     pub state: ConnectionState,
     pub socket: Option<WebSocketWrite<WriteHalf<TokioIo<Upgraded>>>>,
-    pub message_sender: mpsc::UnboundedSender<Frame<'static>>,
+    pub message_sender: MessageSender,
+    pub stats: Arc<ConnectionStats>,
+}
+
+/// Traffic counters of one connection, shared with its send loop
+#[derive(Debug, Default)]
+pub struct ConnectionStats {
+    /// Bytes queued for the client but not yet written to the socket
+    pub queued_bytes: AtomicUsize,
+    pub messages_sent: AtomicU64,
+    pub messages_received: AtomicU64,
+}
+
+/// A connection's outgoing frame queue. Frames are counted into
+/// `ConnectionStats::queued_bytes` until the send loop writes them.
+#[derive(Clone)]
+pub struct MessageSender {
+    sender: mpsc::UnboundedSender<Frame<'static>>,
+    stats: Arc<ConnectionStats>,
+}
+
+impl MessageSender {
+    pub fn new(sender: mpsc::UnboundedSender<Frame<'static>>, stats: Arc<ConnectionStats>) -> Self {
+        Self { sender, stats }
+    }
+
+    pub fn send(
+        &self,
+        frame: Frame<'static>,
+    ) -> Result<(), mpsc::error::SendError<Frame<'static>>> {
+        let len = frame.payload.len();
+        self.sender.send(frame)?;
+        self.stats.queued_bytes.fetch_add(len, Ordering::Relaxed);
+        Ok(())
+    }
 }
 
 impl WebSocket {
     pub fn new(socket: WebSocketWrite<WriteHalf<TokioIo<Upgraded>>>) -> Self {
-        let (message_sender, _) = mpsc::unbounded_channel();
+        let (sender, _) = mpsc::unbounded_channel();
+        let stats = Arc::new(ConnectionStats::default());
         Self {
             state: ConnectionState::new(),
             socket: Some(socket),
-            message_sender,
+            message_sender: MessageSender::new(sender, stats.clone()),
+            stats,
+        }
+    }
+
+    /// Approximate memory held for this connection: the connection itself,
+    /// frames waiting to be written, subscriptions and presence payloads
+    pub fn memory_usage(&self) -> usize {
+        let channels: usize = self
+            .state
+            .subscribed_channels
+            .iter()
+            .map(|channel| channel.len() + std::mem::size_of::<String>())
+            .sum();
+        let presence: usize = self
+            .state
+            .presence
+            .as_ref()
+            .map(|members| {
+                members
+                    .iter()
+                    .map(|(channel, member)| {
+                        channel.len() + serde_json::to_vec(member).map_or(0, |json| json.len())
+                    })
+                    .sum()
+            })
+            .unwrap_or(0);
+        let user = self
+            .state
+            .user
+            .as_ref()
+            .map_or(0, |user| user.to_string().len());
+
+        std::mem::size_of::<WebSocket>()
+            + self.stats.queued_bytes.load(Ordering::Relaxed)
+            + channels
+            + presence
+            + user
+    }
+
+    /// This connection's entry in the admin top view
+    pub fn usage(&self, app_id: &str) -> ConnectionUsage {
+        ConnectionUsage {
+            socket_id: self.state.socket_id.0.clone(),
+            app_id: app_id.to_string(),
+            ip: self.state.remote_ip.clone(),
+            user_id: self.state.user_id.clone(),
+            connected_at: self.state.connected_at,
+            tags: self.state.tags.clone(),
+            memory_bytes: self.memory_usage(),
+            queued_bytes: self.stats.queued_bytes.load(Ordering::Relaxed),
+            channels: self.state.subscribed_channels.len(),
+            messages_sent: self.stats.messages_sent.load(Ordering::Relaxed),
+            messages_received: self.stats.messages_received.load(Ordering::Relaxed),
         }
     }
 
@@ -67,11 +155,13 @@ use crate::app::config::App;
 use crate::channel::PresenceMemberInfo;
 use dashmap::DashMap;
 use rand::Rng;
+use rand::seq::SliceRandom;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::time::Instant;
 use tokio::task::JoinHandle;
 
@@ -118,6 +208,46 @@ impl SocketId {
     }
 }
 
+/// Resource use of one connection, as returned by `GET /admin/top`
+#[derive(Debug, Serialize, Clone)]
+pub struct ConnectionUsage {
+    pub socket_id: String,
+    pub app_id: String,
+    pub ip: Option<String>,
+    pub user_id: Option<String>,
+    pub connected_at: i64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    pub memory_bytes: usize,
+    pub queued_bytes: usize,
+    pub channels: usize,
+    pub messages_sent: u64,
+    pub messages_received: u64,
+}
+
+/// Debug view of a subscribed socket, as returned by the channel sampling API
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SocketDetails {
+    pub socket_id: String,
+    pub node_id: Option<String>,
+    pub ip: Option<String>,
+    pub user_id: Option<String>,
+    pub connected_at: i64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl SocketDetails {
+    /// Pick up to `size` entries at random
+    pub fn sample(mut details: Vec<SocketDetails>, size: usize) -> Vec<SocketDetails> {
+        if details.len() > size {
+            details.shuffle(&mut rand::rng());
+            details.truncate(size);
+        }
+        details
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct UserInfo {
     pub id: String,
@@ -138,6 +268,13 @@ pub struct ConnectionState {
     pub activity_timeout_handle: Option<JoinHandle<()>>, // Add this
     #[serde(skip)]
     pub auth_timeout_handle: Option<JoinHandle<()>>, // Add this
+    #[serde(default)]
+    pub remote_ip: Option<String>,
+    #[serde(default)]
+    pub connected_at: i64, // Unix timestamp in milliseconds
+    /// Tags attached by connection screening
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 impl ConnectionState {
@@ -153,6 +290,9 @@ impl ConnectionState {
             user_info: None, // Initialize with None
             activity_timeout_handle: None,
             auth_timeout_handle: None,
+            remote_ip: None,
+            connected_at: chrono::Utc::now().timestamp_millis(),
+            tags: Vec::new(),
         }
     }
 
@@ -1,11 +1,17 @@
 use crate::adapter::ConnectionHandler;
+use crate::error::Error;
+use crate::screening::{HandshakeMetadata, ScreeningDecision};
 
-use axum::extract::{Path, Query, State};
+use axum::Json;
+use axum::extract::{ConnectInfo, Path, Query, State};
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
 use axum::response::IntoResponse;
 use fastwebsockets::upgrade;
 use serde::Deserialize;
+use serde_json::json;
+use std::net::SocketAddr;
 use std::sync::Arc;
-use tracing::log::error;
+use tracing::log::{error, warn};
 
 #[derive(Debug, Deserialize)]
 pub struct ConnectionQuery {
@@ -18,14 +24,201 @@ pub struct ConnectionQuery {
 pub async fn handle_ws_upgrade(
     Path(app_key): Path<String>,
     Query(params): Query<ConnectionQuery>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     ws: upgrade::IncomingUpgrade,
     State(handler): State<Arc<ConnectionHandler>>,
 ) -> impl IntoResponse {
-    let (response, fut) = ws.upgrade().unwrap();
-    tokio::task::spawn(async move {
-        if let Err(e) = handler.handle_socket(fut, app_key).await {
-            error!("{}", format!("Error handling socket: {}", e));
-        }
+    // Prefer the client address reported by a trusted fronting proxy
+    let remote_ip = handler
+        .ip_extractor
+        .client_addr(&headers, addr.ip())
+        .to_string();
+
+    // A draining node sends new clients elsewhere, staggered so they don't
+    // all retry at once
+    if handler
+        .node_drain
+        .as_ref()
+        .is_some_and(|drain| drain.is_draining())
+    {
+        let retry_in_ms = handler.backoff.advise(&remote_ip);
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, retry_in_ms.div_ceil(1000).to_string())],
+            Json(json!({ "error": "Server is draining", "retry_in_ms": retry_in_ms })),
+        )
+            .into_response();
+    }
+
+    // A hostname bound to an app only serves that app's clients
+    let bound_app = handler.host_apps.as_ref().and_then(|bindings| {
+        headers
+            .get(header::HOST)
+            .and_then(|host| host.to_str().ok())
+            .and_then(|host| bindings.app_for_host(host))
     });
-    response
+    if let Some(bound_app) = bound_app {
+        let app = handler
+            .app_manager
+            .find_by_key(&app_key)
+            .await
+            .ok()
+            .flatten();
+        if app.is_none_or(|app| app.id != bound_app) {
+            warn!(
+                "{}",
+                format!(
+                    "Rejected connection from {} for app key {}: hostname is bound to app {}",
+                    remote_ip, app_key, bound_app
+                )
+            );
+            return (StatusCode::FORBIDDEN, "App not served on this hostname").into_response();
+        }
+    }
+
+    // Refuse connection floods before a socket is allocated. Apps with their
+    // own connection rate have each client limited to it.
+    if let Some(connect_limit) = &handler.connect_rate_limit {
+        let app = handler
+            .app_manager
+            .find_by_key(&app_key)
+            .await
+            .ok()
+            .flatten();
+        // Unknown keys are refused before counting, so made-up keys can't
+        // create a bucket each
+        let Some(app) = app else {
+            warn!(
+                "{}",
+                format!(
+                    "Rejected connection from {} for unknown app key {}",
+                    remote_ip, app_key
+                )
+            );
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "error": Error::ApplicationNotFound.to_string(),
+                    "code": Error::ApplicationNotFound.close_code(),
+                })),
+            )
+                .into_response();
+        };
+        let app_limit = app
+            .max_connections_per_second
+            .filter(|per_second| *per_second > 0)
+            .map(|per_second| (app.id.as_str(), per_second));
+        match connect_limit
+            .check(&headers, addr.ip(), &app_key, app_limit)
+            .await
+        {
+            Ok(result) if !result.allowed => {
+                warn!(
+                    "{}",
+                    format!(
+                        "Rejected connection from {} for app key {}: connection rate limit exceeded",
+                        remote_ip, app_key
+                    )
+                );
+                return (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    [(header::RETRY_AFTER, result.reset_after.max(1).to_string())],
+                    Json(json!({
+                        "error": Error::ConnectionRateLimit.to_string(),
+                        "code": Error::ConnectionRateLimit.close_code(),
+                        "retry_in_ms": result.reset_after.max(1) * 1000,
+                    })),
+                )
+                    .into_response();
+            }
+            Ok(_) => {}
+            Err(e) => error!(
+                "{}",
+                format!(
+                    "Connection rate limiter failed for app key {}: {}",
+                    app_key, e
+                )
+            ),
+        }
+    }
+
+    // Screen the handshake before the upgrade consumes a socket
+    let mut tags = Vec::new();
+    if let Some(screening) = &handler.screening {
+        let mut metadata = HandshakeMetadata::new(
+            &app_key,
+            &remote_ip,
+            &headers,
+            screening.tls_fingerprint_header.as_deref(),
+        );
+        metadata.protocol = params.protocol;
+        metadata.client = params.client.clone();
+        metadata.version = params.version.clone();
+
+        match screening.screener.screen(&metadata).await {
+            ScreeningDecision::Allow => {}
+            ScreeningDecision::Tag { tags: screened } => tags = screened,
+            ScreeningDecision::Reject { reason } => {
+                let reason = reason.unwrap_or_else(|| "Connection rejected".to_string());
+                warn!(
+                    "{}",
+                    format!(
+                        "Rejected connection from {} for app {}: {}",
+                        remote_ip, app_key, reason
+                    )
+                );
+                return (StatusCode::FORBIDDEN, reason).into_response();
+            }
+        }
+    }
+
+    // Clients that offer no subprotocol get the Pusher JSON protocol
+    let offered = headers
+        .get(header::SEC_WEBSOCKET_PROTOCOL)
+        .and_then(|value| value.to_str().ok());
+    let negotiated = offered.and_then(|offered| handler.subprotocols.negotiate(offered));
+    if let (Some(offered), None) = (offered, &negotiated) {
+        warn!(
+            "{}",
+            format!(
+                "No supported subprotocol in '{}' (supported: {}), using the Pusher protocol",
+                offered,
+                handler.subprotocols.supported().join(", ")
+            )
+        );
+    }
+
+    let (mut response, fut) = ws.upgrade().unwrap();
+    match negotiated {
+        Some((name, protocol)) => {
+            if let Ok(value) = HeaderValue::from_str(&name) {
+                response
+                    .headers_mut()
+                    .insert(header::SEC_WEBSOCKET_PROTOCOL, value);
+            }
+            tokio::task::spawn(async move {
+                if let Err(e) = protocol
+                    .serve(handler, fut, app_key, Some(remote_ip), tags)
+                    .await
+                {
+                    error!(
+                        "{}",
+                        format!("Error handling socket with subprotocol {}: {}", name, e)
+                    );
+                }
+            });
+        }
+        None => {
+            tokio::task::spawn(async move {
+                if let Err(e) = handler
+                    .handle_socket(fut, app_key, Some(remote_ip), tags)
+                    .await
+                {
+                    error!("{}", format!("Error handling socket: {}", e));
+                }
+            });
+        }
+    }
+    response.into_response()
 }